@@ -1,3 +1,6 @@
+pub use crate::error::Span;
+use crate::error::{CompileError, Diagnostic};
+
 /// Root of a GAME program.
 #[derive(Debug, Clone)]
 pub struct Program {
@@ -14,6 +17,7 @@ pub struct Import {
     pub alias: String,
     /// Names exposed by `import "path" expose a, b` (empty for `as` style imports).
     pub exposed: Vec<String>,
+    pub span: Span,
 }
 
 /// `cinematic "name" { layers, arcs, resonates, listen, voice, score, gravity, lenses, react, defines }`
@@ -30,6 +34,7 @@ pub struct Cinematic {
     pub lenses: Vec<Lens>,
     pub react: Option<ReactBlock>,
     pub defines: Vec<DefineBlock>,
+    pub span: Span,
 }
 
 /// `layer ident [(opts)] [memory: f] [cast kind] { body }`
@@ -39,7 +44,11 @@ pub struct Layer {
     pub opts: Vec<Param>,
     pub memory: Option<f64>,
     pub cast: Option<String>,
+    /// The `blend:` (or `blend_mode:`) option, kept out of `opts` so it
+    /// never becomes a uniform. `None` composites with screen.
+    pub blend: Option<Param>,
     pub body: LayerBody,
+    pub span: Span,
 }
 
 /// A layer body is either a list of named params or a stage pipeline.
@@ -56,6 +65,7 @@ pub struct Param {
     pub value: Expr,
     pub modulation: Option<Expr>,
     pub temporal_ops: Vec<TemporalOp>,
+    pub span: Span,
 }
 
 /// Temporal operator applied to a parameter value.
@@ -76,6 +86,82 @@ pub enum TemporalOp {
 pub struct Stage {
     pub name: String,
     pub args: Vec<Arg>,
+    pub span: Span,
+    /// Define calls this stage was expanded from, innermost first.
+    pub expanded_from: Vec<Expansion>,
+}
+
+impl Stage {
+    /// Where to report a problem with this stage: its own span, or the
+    /// outermost call site when the stage came from an imported define.
+    pub fn report_span(&self) -> Span {
+        if self.span.is_empty() {
+            self.expanded_from.last().map_or(self.span, |e| e.call_site)
+        } else {
+            self.span
+        }
+    }
+
+    /// Span of just the stage name, or empty when the stage has no span.
+    pub fn name_span(&self) -> Span {
+        if self.span.is_empty() {
+            self.span
+        } else {
+            Span::new(self.span.start, self.span.start + self.name.len())
+        }
+    }
+
+    /// "expanded from define 'x'" notes, innermost first.
+    pub fn expansion_notes(&self) -> impl Iterator<Item = Diagnostic> + '_ {
+        self.expanded_from.iter().map(|e| {
+            let note = Diagnostic::note(format!("expanded from define '{}'", e.define));
+            if e.call_site.is_empty() {
+                note
+            } else {
+                note.with_span(e.call_site)
+            }
+        })
+    }
+
+    /// Attach this stage's location and expansion notes to an error.
+    pub fn locate(&self, err: CompileError) -> CompileError {
+        self.expansion_notes()
+            .fold(err.with_span(self.report_span()), CompileError::with_note)
+    }
+
+    /// Nested pipelines among the arguments (combinator stages only).
+    pub fn sub_pipelines(&self) -> impl Iterator<Item = &[Stage]> {
+        self.args.iter().filter_map(|arg| match &arg.value {
+            Expr::Pipeline(stages, _) => Some(stages.as_slice()),
+            _ => None,
+        })
+    }
+
+    /// Attach this stage's location and expansion notes to a diagnostic.
+    pub fn locate_diagnostic(&self, diag: Diagnostic) -> Diagnostic {
+        let span = self.report_span();
+        let diag = if span.is_empty() { diag } else { diag.with_span(span) };
+        self.expansion_notes().fold(diag, Diagnostic::with_note)
+    }
+}
+
+/// Visit every stage of a pipeline, including those nested in combinator
+/// arguments, each combinator before its sub-pipelines.
+pub fn walk_stages<'a>(stages: &'a [Stage], f: &mut impl FnMut(&'a Stage)) {
+    for stage in stages {
+        f(stage);
+        for sub in stage.sub_pipelines() {
+            walk_stages(sub, f);
+        }
+    }
+}
+
+/// One step of define expansion: the stage came from `define`'s body,
+/// spliced in at `call_site`.
+#[derive(Debug, Clone)]
+pub struct Expansion {
+    pub define: String,
+    pub call_site: Span,
 }
 
 /// An argument — optionally named.
@@ -83,12 +169,14 @@ pub struct Stage {
 pub struct Arg {
     pub name: Option<String>,
     pub value: Expr,
+    pub span: Span,
 }
 
 /// `arc { entries }`
 #[derive(Debug, Clone)]
 pub struct ArcBlock {
     pub entries: Vec<ArcEntry>,
+    pub span: Span,
 }
 
 /// `target: from -> to over duration [easing]`
@@ -99,12 +187,14 @@ pub struct ArcEntry {
     pub to: Expr,
     pub duration: Duration,
     pub easing: Option<String>,
+    pub span: Span,
 }
 
 /// `resonate { entries }`
 #[derive(Debug, Clone)]
 pub struct ResonateBlock {
     pub entries: Vec<ResonateEntry>,
+    pub span: Span,
 }
 
 /// `source -> target.field * weight`
@@ -114,6 +204,7 @@ pub struct ResonateEntry {
     pub target: String,
     pub field: String,
     pub weight: Expr,
+    pub span: Span,
 }
 
 /// Time durations supported by the language.
@@ -131,26 +222,92 @@ pub enum BinOp {
     Sub,
     Mul,
     Div,
+    /// `%`: truncated remainder, like WGSL and JavaScript `%`
+    Mod,
     Pow,
     Gt,
     Lt,
+    Ge,
+    Le,
+    Eq,
+    Ne,
+    And,
+    Or,
+}
+
+impl BinOp {
+    /// Comparisons and logic produce booleans.
+    pub fn is_boolean(&self) -> bool {
+        !matches!(
+            self,
+            BinOp::Add | BinOp::Sub | BinOp::Mul | BinOp::Div | BinOp::Mod | BinOp::Pow
+        )
+    }
 }
 
 /// Expression tree.
+///
+/// Every node carries the span of its source text. Nodes built outside the
+/// parser, such as a gradient's default stops, have an empty span.
 #[derive(Debug, Clone)]
 pub enum Expr {
-    Number(f64),
-    String(String),
-    Ident(String),
-    DottedIdent { object: String, field: String },
-    Array(Vec<Expr>),
-    Paren(Box<Expr>),
-    Neg(Box<Expr>),
-    BinOp { op: BinOp, left: Box<Expr>, right: Box<Expr> },
-    Call { name: String, args: Vec<Arg> },
-    Duration(Duration),
+    Number(f64, Span),
+    String(String, Span),
+    Ident(String, Span),
+    DottedIdent { object: String, field: String, span: Span },
+    Array(Vec<Expr>, Span),
+    Paren(Box<Expr>, Span),
+    Neg(Box<Expr>, Span),
+    /// Logical not: `!x`
+    Not(Box<Expr>, Span),
+    BinOp { op: BinOp, left: Box<Expr>, right: Box<Expr>, span: Span },
+    Call { name: String, args: Vec<Arg>, span: Span },
+    Duration(Duration, Span),
     /// Ternary: `cond ? a : b`
-    Ternary { condition: Box<Expr>, if_true: Box<Expr>, if_false: Box<Expr> },
+    Ternary { condition: Box<Expr>, if_true: Box<Expr>, if_false: Box<Expr>, span: Span },
+    /// A nested stage chain passed to a combinator stage, e.g. the second
+    /// argument of `union(circle(0.2), translate(0.3, 0.0) | box(0.1, 0.1))`.
+    Pipeline(Vec<Stage>, Span),
+}
+
+impl Expr {
+    /// Where this expression is in the source; empty when synthesized.
+    pub fn span(&self) -> Span {
+        match self {
+            Expr::Number(_, span)
+            | Expr::String(_, span)
+            | Expr::Ident(_, span)
+            | Expr::DottedIdent { span, .. }
+            | Expr::Array(_, span)
+            | Expr::Paren(_, span)
+            | Expr::Neg(_, span)
+            | Expr::Not(_, span)
+            | Expr::BinOp { span, .. }
+            | Expr::Call { span, .. }
+            | Expr::Duration(_, span)
+            | Expr::Ternary { span, .. }
+            | Expr::Pipeline(_, span) => *span,
+        }
+    }
+
+    /// Mutable access to [`Expr::span`].
+    pub fn span_mut(&mut self) -> &mut Span {
+        match self {
+            Expr::Number(_, span)
+            | Expr::String(_, span)
+            | Expr::Ident(_, span)
+            | Expr::DottedIdent { span, .. }
+            | Expr::Array(_, span)
+            | Expr::Paren(_, span)
+            | Expr::Neg(_, span)
+            | Expr::Not(_, span)
+            | Expr::BinOp { span, .. }
+            | Expr::Call { span, .. }
+            | Expr::Duration(_, span)
+            | Expr::Ternary { span, .. }
+            | Expr::Pipeline(_, span) => span,
+        }
+    }
 }
 
 // ── Phase 3: Audio blocks ────────────────────────────────
@@ -159,6 +316,7 @@ pub enum Expr {
 #[derive(Debug, Clone)]
 pub struct ListenBlock {
     pub signals: Vec<ListenSignal>,
+    pub span: Span,
 }
 
 /// A named audio signal with its DSP algorithm.
@@ -167,12 +325,14 @@ pub struct ListenSignal {
     pub name: String,
     pub algorithm: String,
     pub params: Vec<Param>,
+    pub span: Span,
 }
 
 /// `voice { oscillators, filters, output chain }`
 #[derive(Debug, Clone)]
 pub struct VoiceBlock {
     pub nodes: Vec<VoiceNode>,
+    pub span: Span,
 }
 
 /// A node in the voice synthesis graph.
@@ -181,6 +341,7 @@ pub struct VoiceNode {
     pub name: String,
     pub kind: String,
     pub params: Vec<Param>,
+    pub span: Span,
 }
 
 // ── Phase 4: Composition blocks ──────────────────────────
@@ -193,6 +354,7 @@ pub struct ScoreBlock {
     pub phrases: Vec<Phrase>,
     pub sections: Vec<Section>,
     pub arrange: Vec<String>,
+    pub span: Span,
 }
 
 /// `motif name { target: from -> to over duration }`
@@ -225,6 +387,7 @@ pub struct BreedBlock {
     pub parents: Vec<String>,
     pub inherit_rules: Vec<InheritRule>,
     pub mutations: Vec<Mutation>,
+    pub span: Span,
 }
 
 /// `inherit layers|params: mix(weight)`
@@ -250,6 +413,7 @@ pub struct GravityBlock {
     pub force_law: Expr,
     pub damping: f64,
     pub bounds: BoundsMode,
+    pub span: Span,
 }
 
 /// How particles interact with boundaries.
@@ -266,6 +430,7 @@ pub struct ProjectBlock {
     pub mode: ProjectMode,
     pub source: String,
     pub params: Vec<Param>,
+    pub span: Span,
 }
 
 /// Projection target surface type.
@@ -285,6 +450,7 @@ pub struct Lens {
     pub name: Option<String>,
     pub properties: Vec<Param>,
     pub post: Vec<Stage>,
+    pub span: Span,
 }
 
 // ── React (interactive events) ──────────────────────────
@@ -293,6 +459,7 @@ pub struct Lens {
 #[derive(Debug, Clone)]
 pub struct ReactBlock {
     pub reactions: Vec<Reaction>,
+    pub span: Span,
 }
 
 /// A single reaction: signal expression → action expression.
@@ -300,6 +467,7 @@ pub struct ReactBlock {
 pub struct Reaction {
     pub signal: Expr,
     pub action: Expr,
+    pub span: Span,
 }
 
 // ── Define (reusable macros) ────────────────────────────
@@ -310,4 +478,5 @@ pub struct DefineBlock {
     pub name: String,
     pub params: Vec<String>,
     pub body: Vec<Stage>,
+    pub span: Span,
 }
@@ -36,6 +36,49 @@ pub struct BuiltinFn {
     pub params: &'static [BuiltinParam],
     pub input: ShaderState,
     pub output: ShaderState,
+    /// Reads `time` itself, so the stage animates whatever its arguments.
+    pub animated: bool,
+}
+
+impl BuiltinFn {
+    /// Call signature with defaults, e.g. `ring(radius = 0.3, width = 0.02)`.
+    pub fn signature(&self) -> String {
+        let params: Vec<String> = self
+            .params
+            .iter()
+            .map(|p| match p.default {
+                Some(d) => format!("{} = {d}", p.name),
+                None => p.name.to_string(),
+            })
+            .collect();
+        format!("{}({})", self.name, params.join(", "))
+    }
+
+    /// What role the stage plays in a pipe chain, from its state transition.
+    pub fn kind(&self) -> &'static str {
+        match (self.input, self.output) {
+            (ShaderState::Position, ShaderState::Position) => "domain transform",
+            (ShaderState::Position, ShaderState::Sdf) if self.is_combinator() => "SDF combinator",
+            (ShaderState::Position, ShaderState::Sdf) => "SDF generator",
+            (ShaderState::Position, ShaderState::Color) => "full-screen color generator",
+            (ShaderState::Sdf, ShaderState::Sdf) => "SDF modifier",
+            (ShaderState::Sdf, ShaderState::Color) => "SDF-to-color bridge",
+            (ShaderState::Color, ShaderState::Color) => "color processor",
+            _ => "stage",
+        }
+    }
+
+    /// Whether the stage combines nested sub-pipelines (`union`, `morph`, ...)
+    /// rather than transforming the state flowing through its own chain.
+    pub fn is_combinator(&self) -> bool {
+        is_combinator(self.name)
+    }
+
+    /// Whether the stage may follow a pipeline in `state`. Position-input
+    /// stages may appear anywhere (they restart from the pixel position).
+    pub fn accepts(&self, state: ShaderState) -> bool {
+        self.input == state || self.input == ShaderState::Position
+    }
 }
 
 // ── Param lists ──────────────────────────────────────────
@@ -116,6 +159,7 @@ static GRADIENT_PARAMS: &[BuiltinParam] = &[
     BuiltinParam { name: "color_a", default: None },
     BuiltinParam { name: "color_b", default: None },
     BuiltinParam { name: "mode", default: None },
+    BuiltinParam { name: "angle", default: Some(0.0) },
 ];
 
 static THRESHOLD_PARAMS: &[BuiltinParam] = &[
@@ -216,82 +260,115 @@ static PALETTE_PARAMS: &[BuiltinParam] = &[
     BuiltinParam { name: "name", default: None },
 ];
 
+// Combinators: `a` and `b` are nested Position -> Sdf pipelines
+static COMBINE_PARAMS: &[BuiltinParam] = &[
+    BuiltinParam { name: "a", default: None },
+    BuiltinParam { name: "b", default: None },
+];
+
+static SMOOTH_UNION_PARAMS: &[BuiltinParam] = &[
+    BuiltinParam { name: "a", default: None },
+    BuiltinParam { name: "b", default: None },
+    BuiltinParam { name: "k", default: Some(0.1) },
+];
+
+static MORPH_PARAMS: &[BuiltinParam] = &[
+    BuiltinParam { name: "a", default: None },
+    BuiltinParam { name: "b", default: None },
+    BuiltinParam { name: "t", default: Some(0.5) },
+];
+
 // ── Registry ─────────────────────────────────────────────
 
 pub static BUILTINS: &[BuiltinFn] = &[
     // SDF generators: Position -> Sdf
-    BuiltinFn { name: "circle",  params: CIRCLE_PARAMS,  input: ShaderState::Position, output: ShaderState::Sdf },
-    BuiltinFn { name: "ring",    params: RING_PARAMS,    input: ShaderState::Position, output: ShaderState::Sdf },
-    BuiltinFn { name: "star",    params: STAR_PARAMS,    input: ShaderState::Position, output: ShaderState::Sdf },
-    BuiltinFn { name: "fbm",     params: FBM_PARAMS,     input: ShaderState::Position, output: ShaderState::Sdf },
-    BuiltinFn { name: "simplex", params: SIMPLEX_PARAMS,  input: ShaderState::Position, output: ShaderState::Sdf },
+    BuiltinFn { name: "circle",  params: CIRCLE_PARAMS,  input: ShaderState::Position, output: ShaderState::Sdf, animated: false },
+    BuiltinFn { name: "ring",    params: RING_PARAMS,    input: ShaderState::Position, output: ShaderState::Sdf, animated: false },
+    BuiltinFn { name: "star",    params: STAR_PARAMS,    input: ShaderState::Position, output: ShaderState::Sdf, animated: false },
+    BuiltinFn { name: "fbm",     params: FBM_PARAMS,     input: ShaderState::Position, output: ShaderState::Sdf, animated: false },
+    BuiltinFn { name: "simplex", params: SIMPLEX_PARAMS,  input: ShaderState::Position, output: ShaderState::Sdf, animated: false },
 
     // Bridges: Sdf -> Color
-    BuiltinFn { name: "glow",     params: GLOW_PARAMS,     input: ShaderState::Sdf, output: ShaderState::Color },
-    BuiltinFn { name: "shade",    params: SHADE_PARAMS,    input: ShaderState::Sdf, output: ShaderState::Color },
-    BuiltinFn { name: "emissive", params: EMISSIVE_PARAMS, input: ShaderState::Sdf, output: ShaderState::Color },
+    BuiltinFn { name: "glow",     params: GLOW_PARAMS,     input: ShaderState::Sdf, output: ShaderState::Color, animated: false },
+    BuiltinFn { name: "shade",    params: SHADE_PARAMS,    input: ShaderState::Sdf, output: ShaderState::Color, animated: false },
+    BuiltinFn { name: "emissive", params: EMISSIVE_PARAMS, input: ShaderState::Sdf, output: ShaderState::Color, animated: false },
 
     // Color processors: Color -> Color
-    BuiltinFn { name: "tint",  params: TINT_PARAMS,  input: ShaderState::Color, output: ShaderState::Color },
-    BuiltinFn { name: "bloom", params: BLOOM_PARAMS, input: ShaderState::Color, output: ShaderState::Color },
-    BuiltinFn { name: "grain", params: GRAIN_PARAMS, input: ShaderState::Color, output: ShaderState::Color },
+    BuiltinFn { name: "tint",  params: TINT_PARAMS,  input: ShaderState::Color, output: ShaderState::Color, animated: false },
+    BuiltinFn { name: "bloom", params: BLOOM_PARAMS, input: ShaderState::Color, output: ShaderState::Color, animated: false },
+    BuiltinFn { name: "grain", params: GRAIN_PARAMS, input: ShaderState::Color, output: ShaderState::Color, animated: true },
 
     // Transforms: Position -> Position
-    BuiltinFn { name: "translate", params: TRANSLATE_PARAMS, input: ShaderState::Position, output: ShaderState::Position },
-    BuiltinFn { name: "rotate",    params: ROTATE_PARAMS,    input: ShaderState::Position, output: ShaderState::Position },
-    BuiltinFn { name: "scale",     params: SCALE_PARAMS,     input: ShaderState::Position, output: ShaderState::Position },
+    BuiltinFn { name: "translate", params: TRANSLATE_PARAMS, input: ShaderState::Position, output: ShaderState::Position, animated: false },
+    BuiltinFn { name: "rotate",    params: ROTATE_PARAMS,    input: ShaderState::Position, output: ShaderState::Position, animated: false },
+    BuiltinFn { name: "scale",     params: SCALE_PARAMS,     input: ShaderState::Position, output: ShaderState::Position, animated: false },
 
     // Sdf modifiers: Sdf -> Sdf
-    BuiltinFn { name: "mask_arc", params: MASK_ARC_PARAMS, input: ShaderState::Sdf, output: ShaderState::Sdf },
-    BuiltinFn { name: "threshold", params: THRESHOLD_PARAMS, input: ShaderState::Sdf, output: ShaderState::Sdf },
+    BuiltinFn { name: "mask_arc", params: MASK_ARC_PARAMS, input: ShaderState::Sdf, output: ShaderState::Sdf, animated: false },
+    BuiltinFn { name: "threshold", params: THRESHOLD_PARAMS, input: ShaderState::Sdf, output: ShaderState::Sdf, animated: false },
 
     // Position modifiers: Position -> Position
-    BuiltinFn { name: "twist",  params: TWIST_PARAMS,  input: ShaderState::Position, output: ShaderState::Position },
-    BuiltinFn { name: "mirror", params: MIRROR_PARAMS, input: ShaderState::Position, output: ShaderState::Position },
-    BuiltinFn { name: "repeat", params: REPEAT_PARAMS, input: ShaderState::Position, output: ShaderState::Position },
+    BuiltinFn { name: "twist",  params: TWIST_PARAMS,  input: ShaderState::Position, output: ShaderState::Position, animated: false },
+    BuiltinFn { name: "mirror", params: MIRROR_PARAMS, input: ShaderState::Position, output: ShaderState::Position, animated: false },
+    BuiltinFn { name: "repeat", params: REPEAT_PARAMS, input: ShaderState::Position, output: ShaderState::Position, animated: false },
 
     // Full-screen generators: Position -> Color
-    BuiltinFn { name: "gradient", params: GRADIENT_PARAMS, input: ShaderState::Position, output: ShaderState::Color },
+    BuiltinFn { name: "gradient", params: GRADIENT_PARAMS, input: ShaderState::Position, output: ShaderState::Color, animated: false },
 
     // Color mixers: Color -> Color
-    BuiltinFn { name: "blend", params: BLEND_PARAMS, input: ShaderState::Color, output: ShaderState::Color },
-    BuiltinFn { name: "vignette", params: VIGNETTE_PARAMS, input: ShaderState::Color, output: ShaderState::Color },
+    BuiltinFn { name: "blend", params: BLEND_PARAMS, input: ShaderState::Color, output: ShaderState::Color, animated: false },
+    BuiltinFn { name: "vignette", params: VIGNETTE_PARAMS, input: ShaderState::Color, output: ShaderState::Color, animated: false },
 
     // Additional SDF generators: Position -> Sdf
-    BuiltinFn { name: "voronoi", params: VORONOI_PARAMS, input: ShaderState::Position, output: ShaderState::Sdf },
+    BuiltinFn { name: "voronoi", params: VORONOI_PARAMS, input: ShaderState::Position, output: ShaderState::Sdf, animated: false },
 
     // SDF modifiers: Sdf -> Sdf
-    BuiltinFn { name: "onion", params: ONION_PARAMS, input: ShaderState::Sdf, output: ShaderState::Sdf },
+    BuiltinFn { name: "onion", params: ONION_PARAMS, input: ShaderState::Sdf, output: ShaderState::Sdf, animated: false },
 
     // Position modifiers: Position -> Position
-    BuiltinFn { name: "domain_warp", params: DOMAIN_WARP_PARAMS, input: ShaderState::Position, output: ShaderState::Position },
-    BuiltinFn { name: "curl_noise", params: CURL_NOISE_PARAMS, input: ShaderState::Position, output: ShaderState::Position },
+    BuiltinFn { name: "domain_warp", params: DOMAIN_WARP_PARAMS, input: ShaderState::Position, output: ShaderState::Position, animated: false },
+    BuiltinFn { name: "curl_noise", params: CURL_NOISE_PARAMS, input: ShaderState::Position, output: ShaderState::Position, animated: false },
 
     // Additional SDF generators: Position -> Sdf
-    BuiltinFn { name: "box",     params: BOX_PARAMS,     input: ShaderState::Position, output: ShaderState::Sdf },
-    BuiltinFn { name: "polygon", params: POLYGON_PARAMS, input: ShaderState::Position, output: ShaderState::Sdf },
-    BuiltinFn { name: "concentric_waves", params: CONCENTRIC_WAVES_PARAMS, input: ShaderState::Position, output: ShaderState::Sdf },
+    BuiltinFn { name: "box",     params: BOX_PARAMS,     input: ShaderState::Position, output: ShaderState::Sdf, animated: false },
+    BuiltinFn { name: "polygon", params: POLYGON_PARAMS, input: ShaderState::Position, output: ShaderState::Sdf, animated: false },
+    BuiltinFn { name: "concentric_waves", params: CONCENTRIC_WAVES_PARAMS, input: ShaderState::Position, output: ShaderState::Sdf, animated: true },
 
     // SDF modifiers: Sdf -> Sdf
-    BuiltinFn { name: "round", params: ROUND_PARAMS, input: ShaderState::Sdf, output: ShaderState::Sdf },
+    BuiltinFn { name: "round", params: ROUND_PARAMS, input: ShaderState::Sdf, output: ShaderState::Sdf, animated: false },
 
     // Post-processing: Color -> Color
-    BuiltinFn { name: "tonemap",        params: TONEMAP_PARAMS,        input: ShaderState::Color, output: ShaderState::Color },
-    BuiltinFn { name: "scanlines",      params: SCANLINES_PARAMS,      input: ShaderState::Color, output: ShaderState::Color },
-    BuiltinFn { name: "chromatic",      params: CHROMATIC_PARAMS,      input: ShaderState::Color, output: ShaderState::Color },
-    BuiltinFn { name: "saturate_color", params: SATURATE_COLOR_PARAMS, input: ShaderState::Color, output: ShaderState::Color },
-    BuiltinFn { name: "glitch",         params: GLITCH_PARAMS,         input: ShaderState::Color, output: ShaderState::Color },
+    BuiltinFn { name: "tonemap",        params: TONEMAP_PARAMS,        input: ShaderState::Color, output: ShaderState::Color, animated: false },
+    BuiltinFn { name: "scanlines",      params: SCANLINES_PARAMS,      input: ShaderState::Color, output: ShaderState::Color, animated: false },
+    BuiltinFn { name: "chromatic",      params: CHROMATIC_PARAMS,      input: ShaderState::Color, output: ShaderState::Color, animated: false },
+    BuiltinFn { name: "saturate_color", params: SATURATE_COLOR_PARAMS, input: ShaderState::Color, output: ShaderState::Color, animated: false },
+    BuiltinFn { name: "glitch",         params: GLITCH_PARAMS,         input: ShaderState::Color, output: ShaderState::Color, animated: true },
 
     // Domain warp: Position -> Position (noise displacement before SDF evaluation)
-    BuiltinFn { name: "displace", params: DISPLACE_PARAMS, input: ShaderState::Position, output: ShaderState::Position },
+    BuiltinFn { name: "displace", params: DISPLACE_PARAMS, input: ShaderState::Position, output: ShaderState::Position, animated: false },
 
     // Spectrum generator: Position -> Color
-    BuiltinFn { name: "spectrum", params: SPECTRUM_PARAMS, input: ShaderState::Position, output: ShaderState::Color },
+    BuiltinFn { name: "spectrum", params: SPECTRUM_PARAMS, input: ShaderState::Position, output: ShaderState::Color, animated: false },
 
     // Palette bridge: Sdf -> Color (IQ cosine palettes)
-    BuiltinFn { name: "palette", params: PALETTE_PARAMS, input: ShaderState::Sdf, output: ShaderState::Color },
+    BuiltinFn { name: "palette", params: PALETTE_PARAMS, input: ShaderState::Sdf, output: ShaderState::Color, animated: false },
+
+    // SDF combinators: Position -> Sdf over two nested Position -> Sdf pipelines
+    BuiltinFn { name: "union",        params: COMBINE_PARAMS,      input: ShaderState::Position, output: ShaderState::Sdf, animated: false },
+    BuiltinFn { name: "subtract",     params: COMBINE_PARAMS,      input: ShaderState::Position, output: ShaderState::Sdf, animated: false },
+    BuiltinFn { name: "intersect",    params: COMBINE_PARAMS,      input: ShaderState::Position, output: ShaderState::Sdf, animated: false },
+    BuiltinFn { name: "smooth_union", params: SMOOTH_UNION_PARAMS, input: ShaderState::Position, output: ShaderState::Sdf, animated: false },
+    BuiltinFn { name: "morph",        params: MORPH_PARAMS,        input: ShaderState::Position, output: ShaderState::Sdf, animated: false },
 ];
 
+/// Builtins whose first two arguments are nested pipelines.
+pub static COMBINATORS: &[&str] = &["union", "subtract", "intersect", "smooth_union", "morph"];
+
+/// Whether `name` is a combinator stage.
+pub fn is_combinator(name: &str) -> bool {
+    COMBINATORS.contains(&name)
+}
+
 /// Look up a built-in function by name.
 pub fn lookup(name: &str) -> Option<&'static BuiltinFn> {
     BUILTINS.iter().find(|b| b.name == name)
@@ -321,7 +398,8 @@ mod tests {
                       "vignette", "voronoi", "onion", "domain_warp",
                       "box", "round", "polygon", "curl_noise", "tonemap",
                       "scanlines", "chromatic", "saturate_color", "glitch",
-                      "concentric_waves", "displace", "spectrum", "palette"] {
+                      "concentric_waves", "displace", "spectrum", "palette",
+                      "union", "subtract", "intersect", "smooth_union", "morph"] {
             assert!(lookup(name).is_some(), "missing builtin: {name}");
         }
     }
@@ -368,4 +446,25 @@ mod tests {
         assert_eq!(suggest("xyzxyzxyz"), None);
         assert_eq!(suggest("aaaaaaa"), None);
     }
+
+    #[test]
+    fn signature_lists_defaults() {
+        assert_eq!(lookup("ring").unwrap().signature(), "ring(radius = 0.3, width = 0.02)");
+        assert_eq!(lookup("palette").unwrap().signature(), "palette(name)");
+        assert_eq!(lookup("glow").unwrap().kind(), "SDF-to-color bridge");
+        assert_eq!(lookup("morph").unwrap().signature(), "morph(a, b, t = 0.5)");
+    }
+
+    #[test]
+    fn combinators_are_position_to_sdf() {
+        for name in COMBINATORS {
+            let b = lookup(name).unwrap();
+            assert!(b.is_combinator());
+            assert_eq!(b.kind(), "SDF combinator");
+            assert_eq!((b.input, b.output), (ShaderState::Position, ShaderState::Sdf));
+            assert_eq!(&b.params[0].name, &"a");
+            assert_eq!(&b.params[1].name, &"b");
+        }
+        assert!(!lookup("circle").unwrap().is_combinator());
+    }
 }
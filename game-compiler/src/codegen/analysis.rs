@@ -2,14 +2,19 @@
 //!
 //! This module provides:
 //! - `expand_defines`: macro expansion for `define` blocks in layer pipelines
-//! - `cinematic_uses_audio` / `cinematic_uses_mouse`: signal detection across
-//!   an entire cinematic (params, layer bodies, lenses, react blocks)
+//! - `cinematic_uses_audio` / `cinematic_uses_mouse` / `cinematic_uses_time`:
+//!   signal detection across an entire cinematic (params, layer bodies,
+//!   lenses, react blocks)
+//! - `cinematic_is_animated`: whether frames change without input, which
+//!   lets static cinematics render only on change
 
 use std::collections::HashMap;
 
 use crate::ast::*;
-use crate::codegen::expr;
+use crate::builtins;
+use crate::codegen::{expr, memory};
 use crate::error::CompileError;
+use crate::parser;
 
 /// Maximum depth for nested define expansion (prevents infinite recursion).
 const MAX_DEFINE_DEPTH: usize = 16;
@@ -19,6 +24,8 @@ const MAX_DEFINE_DEPTH: usize = 16;
 /// Scans each layer's `Pipeline` body for stage names that match a `define` block.
 /// When found, replaces the call with the define's body stages, substituting
 /// formal parameters with the actual arguments provided at the call site.
+/// Spliced stages keep their span inside the define body and record the call
+/// site in `Stage::expanded_from`.
 ///
 /// Repeats up to `MAX_DEFINE_DEPTH` passes to handle nested defines.
 pub fn expand_defines(cinematic: &mut Cinematic) -> Result<(), CompileError> {
@@ -36,23 +43,7 @@ pub fn expand_defines(cinematic: &mut Cinematic) -> Result<(), CompileError> {
     for layer in &mut cinematic.layers {
         if let LayerBody::Pipeline(ref mut stages) = layer.body {
             for depth in 0..MAX_DEFINE_DEPTH {
-                let mut expanded = false;
-                let mut new_stages = Vec::with_capacity(stages.len());
-
-                for stage in stages.iter() {
-                    if let Some(def) = defines.get(&stage.name) {
-                        // Substitute formal params with actual args
-                        let substituted = substitute_define(def, &stage.args);
-                        new_stages.extend(substituted);
-                        expanded = true;
-                    } else {
-                        new_stages.push(stage.clone());
-                    }
-                }
-
-                *stages = new_stages;
-
-                if !expanded {
+                if !expand_pass(stages, &defines) {
                     break;
                 }
 
@@ -61,7 +52,8 @@ pub fn expand_defines(cinematic: &mut Cinematic) -> Result<(), CompileError> {
                         "define expansion exceeded max depth ({MAX_DEFINE_DEPTH}) \
                          in layer '{}' — possible recursive define",
                         layer.name
-                    )));
+                    ))
+                    .with_span(layer.span));
                 }
             }
         }
@@ -70,11 +62,38 @@ pub fn expand_defines(cinematic: &mut Cinematic) -> Result<(), CompileError> {
     Ok(())
 }
 
-/// Substitute formal parameters in a define body with actual arguments.
+/// One expansion pass over a pipeline and, recursively, the sub-pipelines
+/// of its combinator stages. Returns whether any define call was expanded.
+fn expand_pass(stages: &mut Vec<Stage>, defines: &HashMap<String, DefineBlock>) -> bool {
+    let mut expanded = false;
+    let mut new_stages = Vec::with_capacity(stages.len());
+
+    for stage in stages.drain(..) {
+        if let Some(def) = defines.get(&stage.name) {
+            // Substitute formal params with actual args
+            new_stages.extend(substitute_define(def, &stage));
+            expanded = true;
+        } else {
+            new_stages.push(stage);
+        }
+    }
+
+    for arg in new_stages.iter_mut().flat_map(|s| &mut s.args) {
+        if let Expr::Pipeline(sub, _) = &mut arg.value {
+            expanded |= expand_pass(sub, defines);
+        }
+    }
+
+    *stages = new_stages;
+    expanded
+}
+
+/// Substitute formal parameters in a define body with the arguments of `call`.
 ///
 /// For each stage in the define body, replaces `Expr::Ident` values that match
 /// a formal parameter name with the corresponding argument expression.
-fn substitute_define(def: &DefineBlock, actual_args: &[Arg]) -> Vec<Stage> {
+fn substitute_define(def: &DefineBlock, call: &Stage) -> Vec<Stage> {
+    let actual_args = &call.args;
     // Map formal param name -> actual expression
     let mut param_map: HashMap<&str, &Expr> = HashMap::new();
     for (i, formal) in def.params.iter().enumerate() {
@@ -88,65 +107,117 @@ fn substitute_define(def: &DefineBlock, actual_args: &[Arg]) -> Vec<Stage> {
         }
     }
 
+    // Innermost expansion first, then whatever the call site was expanded from
+    let mut expanded_from = vec![Expansion {
+        define: def.name.clone(),
+        call_site: call.span,
+    }];
+    expanded_from.extend(call.expanded_from.iter().cloned());
+
     def.body
         .iter()
-        .map(|stage| Stage {
-            name: stage.name.clone(),
-            args: stage
-                .args
-                .iter()
-                .map(|arg| Arg {
-                    name: arg.name.clone(),
-                    value: substitute_expr(&arg.value, &param_map),
-                })
-                .collect(),
+        .map(|stage| {
+            let mut stage = Stage {
+                name: stage.name.clone(),
+                args: substitute_args(stage, &param_map),
+                span: stage.span,
+                expanded_from: Vec::new(),
+            };
+            set_expanded_from(&mut stage, &expanded_from);
+            stage
         })
         .collect()
 }
 
+/// Record the expansion chain on a spliced stage and any stages nested in
+/// its combinator arguments that came from the define body.
+fn set_expanded_from(stage: &mut Stage, expanded_from: &[Expansion]) {
+    if stage.expanded_from.is_empty() {
+        stage.expanded_from = expanded_from.to_vec();
+    }
+    for arg in &mut stage.args {
+        if let Expr::Pipeline(sub, _) = &mut arg.value {
+            for nested in sub {
+                set_expanded_from(nested, expanded_from);
+            }
+        }
+    }
+}
+
+/// Substitute parameters in a stage's arguments.
+fn substitute_args(stage: &Stage, param_map: &HashMap<&str, &Expr>) -> Vec<Arg> {
+    let mut args: Vec<Arg> = stage
+        .args
+        .iter()
+        .map(|arg| Arg {
+            name: arg.name.clone(),
+            value: substitute_expr(&arg.value, param_map),
+            span: arg.span,
+        })
+        .collect();
+    // A shape passed through a define param may land in a combinator slot
+    parser::lift_combinator_args(&stage.name, &mut args);
+    args
+}
+
 /// Recursively substitute identifiers in an expression using the parameter map.
 fn substitute_expr(expr: &Expr, param_map: &HashMap<&str, &Expr>) -> Expr {
     match expr {
-        Expr::Ident(name) => {
+        Expr::Ident(name, _) => {
             if let Some(replacement) = param_map.get(name.as_str()) {
                 (*replacement).clone()
             } else {
                 expr.clone()
             }
         }
-        Expr::Paren(inner) => {
-            Expr::Paren(Box::new(substitute_expr(inner, param_map)))
+        Expr::Paren(inner, span) => {
+            Expr::Paren(Box::new(substitute_expr(inner, param_map)), *span)
         }
-        Expr::Neg(inner) => {
-            Expr::Neg(Box::new(substitute_expr(inner, param_map)))
+        Expr::Neg(inner, span) => {
+            Expr::Neg(Box::new(substitute_expr(inner, param_map)), *span)
         }
-        Expr::BinOp { op, left, right } => Expr::BinOp {
+        Expr::Not(inner, span) => {
+            Expr::Not(Box::new(substitute_expr(inner, param_map)), *span)
+        }
+        Expr::BinOp { op, left, right, span } => Expr::BinOp {
             op: op.clone(),
             left: Box::new(substitute_expr(left, param_map)),
             right: Box::new(substitute_expr(right, param_map)),
+            span: *span,
         },
-        Expr::Call { name, args } => Expr::Call {
+        Expr::Call { name, args, span } => Expr::Call {
             name: name.clone(),
             args: args
                 .iter()
                 .map(|a| Arg {
                     name: a.name.clone(),
                     value: substitute_expr(&a.value, param_map),
+                    span: a.span,
                 })
                 .collect(),
+            span: *span,
         },
-        Expr::Array(elems) => {
-            Expr::Array(elems.iter().map(|e| substitute_expr(e, param_map)).collect())
+        Expr::Array(elems, span) => {
+            Expr::Array(elems.iter().map(|e| substitute_expr(e, param_map)).collect(), *span)
         }
         Expr::Ternary {
             condition,
             if_true,
             if_false,
+            span,
         } => Expr::Ternary {
             condition: Box::new(substitute_expr(condition, param_map)),
             if_true: Box::new(substitute_expr(if_true, param_map)),
             if_false: Box::new(substitute_expr(if_false, param_map)),
+            span: *span,
         },
+        Expr::Pipeline(stages, span) => Expr::Pipeline(
+            stages
+                .iter()
+                .map(|stage| Stage { args: substitute_args(stage, param_map), ..stage.clone() })
+                .collect(),
+            *span,
+        ),
         // Literals pass through unchanged
         _ => expr.clone(),
     }
@@ -157,117 +228,90 @@ fn substitute_expr(expr: &Expr, param_map: &HashMap<&str, &Expr>) -> Expr {
 /// Scans layer params, layer pipeline stage args, layer opts, lens properties,
 /// lens post-processing stages, and react signal/action expressions.
 pub fn cinematic_uses_audio(cinematic: &Cinematic) -> bool {
-    // Check layer bodies and opts
-    for layer in &cinematic.layers {
-        for opt in &layer.opts {
-            if param_uses_audio(opt) {
-                return true;
-            }
-        }
-        match &layer.body {
-            LayerBody::Params(params) => {
-                for param in params {
-                    if param_uses_audio(param) {
-                        return true;
-                    }
-                }
-            }
-            LayerBody::Pipeline(stages) => {
-                for stage in stages {
-                    for arg in &stage.args {
-                        if expr::uses_audio(&arg.value) {
-                            return true;
-                        }
-                    }
-                }
-            }
-        }
-    }
+    // A listen block means audio is used even if no expression reads it
+    cinematic.listen.is_some() || any_expr(cinematic, expr::uses_audio)
+}
 
-    // Check lenses
-    for lens in &cinematic.lenses {
-        for prop in &lens.properties {
-            if param_uses_audio(prop) {
-                return true;
-            }
-        }
-        for stage in &lens.post {
-            for arg in &stage.args {
-                if expr::uses_audio(&arg.value) {
-                    return true;
-                }
-            }
-        }
-    }
+/// Check if a cinematic uses mouse input anywhere.
+///
+/// Same scan scope as `cinematic_uses_audio` but checks for `mouse.*` references.
+pub fn cinematic_uses_mouse(cinematic: &Cinematic) -> bool {
+    any_expr(cinematic, expr::uses_mouse)
+}
 
-    // Check react block
-    if let Some(ref react) = cinematic.react {
-        for reaction in &react.reactions {
-            if expr::uses_audio(&reaction.signal) || expr::uses_audio(&reaction.action) {
-                return true;
-            }
+/// Check if a cinematic reads `time` anywhere.
+///
+/// Same scan scope as `cinematic_uses_audio`.
+pub fn cinematic_uses_time(cinematic: &Cinematic) -> bool {
+    any_expr(cinematic, expr::uses_time)
+}
+
+/// Check if a cinematic's frames can change while its inputs stay put.
+///
+/// A cinematic is static when nothing moves on its own: no `time`, audio,
+/// pointer or memory, no arcs, score, resonance, gravity or temporal
+/// operators, and no stage that animates internally. Static cinematics only
+/// need a new frame when a param, `data.*` field or the canvas size changes.
+pub fn cinematic_is_animated(cinematic: &Cinematic) -> bool {
+    let timeline = !cinematic.arcs.is_empty()
+        || cinematic.score.is_some()
+        || !cinematic.resonates.is_empty()
+        || cinematic.gravity.is_some();
+    let temporal = cinematic.layers.iter().any(|layer| {
+        layer_params(layer).any(|param| !param.temporal_ops.is_empty())
+    });
+    let mut animated_stage = false;
+    for layer in &cinematic.layers {
+        if let LayerBody::Pipeline(stages) = &layer.body {
+            walk_stages(stages, &mut |s| {
+                animated_stage |= builtins::lookup(&s.name).is_some_and(|b| b.animated)
+            });
         }
     }
-
-    // Check listen block presence (if listen exists, audio is used)
-    if cinematic.listen.is_some() {
-        return true;
+    for lens in &cinematic.lenses {
+        walk_stages(&lens.post, &mut |s| {
+            animated_stage |= builtins::lookup(&s.name).is_some_and(|b| b.animated)
+        });
     }
 
-    false
+    timeline
+        || temporal
+        || animated_stage
+        || memory::any_layer_uses_memory(&cinematic.layers)
+        || cinematic_uses_time(cinematic)
+        || cinematic_uses_audio(cinematic)
+        || cinematic_uses_mouse(cinematic)
 }
 
-/// Check if a cinematic uses mouse input anywhere.
-///
-/// Same scan scope as `cinematic_uses_audio` but checks for `mouse.*` references.
-pub fn cinematic_uses_mouse(cinematic: &Cinematic) -> bool {
-    // Check layer bodies and opts
+/// Check whether `uses` holds for any expression in a cinematic: layer
+/// params and opts, pipeline stage args, lens properties and post stages,
+/// and react signals and actions.
+fn any_expr(cinematic: &Cinematic, uses: fn(&Expr) -> bool) -> bool {
+    let param_uses = |param: &Param| {
+        uses(&param.value) || param.modulation.as_ref().is_some_and(uses)
+    };
+    let stage_uses = |stage: &Stage| stage.args.iter().any(|arg| uses(&arg.value));
+
     for layer in &cinematic.layers {
-        for opt in &layer.opts {
-            if param_uses_mouse(opt) {
-                return true;
-            }
+        if layer_params(layer).any(param_uses) {
+            return true;
         }
-        match &layer.body {
-            LayerBody::Params(params) => {
-                for param in params {
-                    if param_uses_mouse(param) {
-                        return true;
-                    }
-                }
-            }
-            LayerBody::Pipeline(stages) => {
-                for stage in stages {
-                    for arg in &stage.args {
-                        if expr::uses_mouse(&arg.value) {
-                            return true;
-                        }
-                    }
-                }
+        if let LayerBody::Pipeline(stages) = &layer.body {
+            if stages.iter().any(stage_uses) {
+                return true;
             }
         }
     }
 
-    // Check lenses
     for lens in &cinematic.lenses {
-        for prop in &lens.properties {
-            if param_uses_mouse(prop) {
-                return true;
-            }
-        }
-        for stage in &lens.post {
-            for arg in &stage.args {
-                if expr::uses_mouse(&arg.value) {
-                    return true;
-                }
-            }
+        if lens.properties.iter().any(param_uses) || lens.post.iter().any(stage_uses) {
+            return true;
         }
     }
 
-    // Check react block
     if let Some(ref react) = cinematic.react {
         for reaction in &react.reactions {
-            if expr::uses_mouse(&reaction.signal) || expr::uses_mouse(&reaction.action) {
+            if uses(&reaction.signal) || uses(&reaction.action) {
                 return true;
             }
         }
@@ -276,16 +320,13 @@ pub fn cinematic_uses_mouse(cinematic: &Cinematic) -> bool {
     false
 }
 
-/// Check if a `Param` references audio (value or modulation).
-fn param_uses_audio(param: &Param) -> bool {
-    expr::uses_audio(&param.value)
-        || param.modulation.as_ref().map_or(false, |m| expr::uses_audio(m))
-}
-
-/// Check if a `Param` references mouse (value or modulation).
-fn param_uses_mouse(param: &Param) -> bool {
-    expr::uses_mouse(&param.value)
-        || param.modulation.as_ref().map_or(false, |m| expr::uses_mouse(m))
+/// A layer's inline `fn:` params (stored in opts) followed by its body params.
+fn layer_params(layer: &Layer) -> impl Iterator<Item = &Param> {
+    let body = match &layer.body {
+        LayerBody::Params(params) => params.as_slice(),
+        LayerBody::Pipeline(_) => &[],
+    };
+    layer.opts.iter().chain(body)
 }
 
 // ── Tests ────────────────────────────────────────────────
@@ -293,6 +334,7 @@ fn param_uses_mouse(param: &Param) -> bool {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::builtins::ShaderState;
 
     /// Helper to build a minimal cinematic for testing.
     fn make_cinematic_with_defines(
@@ -306,7 +348,9 @@ mod tests {
                 opts: vec![],
                 memory: None,
                 cast: None,
+                blend: None,
                 body: LayerBody::Pipeline(pipeline),
+                span: Span::default(),
             }],
             arcs: vec![],
             resonates: vec![],
@@ -317,6 +361,7 @@ mod tests {
             lenses: vec![],
             react: None,
             defines,
+            span: Span::default(),
         }
     }
 
@@ -333,6 +378,7 @@ mod tests {
             lenses: vec![],
             react: None,
             defines: vec![],
+            span: Span::default(),
         }
     }
 
@@ -347,17 +393,24 @@ mod tests {
                 name: "circle".into(),
                 args: vec![Arg {
                     name: Some("radius".into()),
-                    value: Expr::Ident("r".into()),
+                    value: Expr::Ident("r".into(), Span::default()),
+                    span: Span::default(),
                 }],
+                span: Span::default(),
+                expanded_from: vec![],
             }],
+            span: Span::default(),
         };
 
         let pipeline = vec![Stage {
             name: "my_shape".into(),
             args: vec![Arg {
                 name: None,
-                value: Expr::Number(0.5),
+                value: Expr::Number(0.5, Span::default()),
+                span: Span::default(),
             }],
+            span: Span::default(),
+            expanded_from: vec![],
         }];
 
         let mut cin = make_cinematic_with_defines(vec![define], pipeline);
@@ -367,7 +420,7 @@ mod tests {
             assert_eq!(stages.len(), 1);
             assert_eq!(stages[0].name, "circle");
             // The "r" param should be replaced with 0.5
-            if let Expr::Number(v) = &stages[0].args[0].value {
+            if let Expr::Number(v, _) = &stages[0].args[0].value {
                 assert!((v - 0.5).abs() < f64::EPSILON);
             } else {
                 panic!("expected Number(0.5) after substitution");
@@ -386,29 +439,62 @@ mod tests {
                 Stage {
                     name: "circle".into(),
                     args: vec![],
+                    span: Span::default(),
+                    expanded_from: vec![],
                 },
                 Stage {
                     name: "glow".into(),
                     args: vec![],
+                    span: Span::default(),
+                    expanded_from: vec![],
                 },
                 Stage {
                     name: "tint".into(),
                     args: vec![
-                        Arg { name: Some("r".into()), value: Expr::Ident("color_r".into()) },
-                        Arg { name: Some("g".into()), value: Expr::Ident("color_g".into()) },
-                        Arg { name: Some("b".into()), value: Expr::Ident("color_b".into()) },
+                        Arg {
+                            name: Some("r".into()),
+                            value: Expr::Ident("color_r".into(), Span::default()),
+                            span: Span::default(),
+                        },
+                        Arg {
+                            name: Some("g".into()),
+                            value: Expr::Ident("color_g".into(), Span::default()),
+                            span: Span::default(),
+                        },
+                        Arg {
+                            name: Some("b".into()),
+                            value: Expr::Ident("color_b".into(), Span::default()),
+                            span: Span::default(),
+                        },
                     ],
+                    span: Span::default(),
+                    expanded_from: vec![],
                 },
             ],
+            span: Span::default(),
         };
 
         let pipeline = vec![Stage {
             name: "styled".into(),
             args: vec![
-                Arg { name: None, value: Expr::Number(1.0) },
-                Arg { name: None, value: Expr::Number(0.0) },
-                Arg { name: None, value: Expr::Number(0.0) },
+                Arg {
+                    name: None,
+                    value: Expr::Number(1.0, Span::default()),
+                    span: Span::default(),
+                },
+                Arg {
+                    name: None,
+                    value: Expr::Number(0.0, Span::default()),
+                    span: Span::default(),
+                },
+                Arg {
+                    name: None,
+                    value: Expr::Number(0.0, Span::default()),
+                    span: Span::default(),
+                },
             ],
+            span: Span::default(),
+            expanded_from: vec![],
         }];
 
         let mut cin = make_cinematic_with_defines(vec![define], pipeline);
@@ -420,7 +506,7 @@ mod tests {
             assert_eq!(stages[1].name, "glow");
             assert_eq!(stages[2].name, "tint");
             // Check the tint r arg was substituted to 1.0
-            if let Expr::Number(v) = &stages[2].args[0].value {
+            if let Expr::Number(v, _) = &stages[2].args[0].value {
                 assert!((v - 1.0).abs() < f64::EPSILON);
             } else {
                 panic!("expected Number after substitution");
@@ -435,6 +521,8 @@ mod tests {
         let pipeline = vec![Stage {
             name: "circle".into(),
             args: vec![],
+            span: Span::default(),
+            expanded_from: vec![],
         }];
         let mut cin = make_cinematic_with_defines(vec![], pipeline);
         expand_defines(&mut cin).unwrap();
@@ -454,24 +542,31 @@ mod tests {
                 name: "circle".into(),
                 args: vec![Arg {
                     name: Some("radius".into()),
-                    value: Expr::Ident("size".into()),
+                    value: Expr::Ident("size".into(), Span::default()),
+                    span: Span::default(),
                 }],
+                span: Span::default(),
+                expanded_from: vec![],
             }],
+            span: Span::default(),
         };
 
         let pipeline = vec![Stage {
             name: "my_circle".into(),
             args: vec![Arg {
                 name: Some("size".into()),
-                value: Expr::Number(0.3),
+                value: Expr::Number(0.3, Span::default()),
+                span: Span::default(),
             }],
+            span: Span::default(),
+            expanded_from: vec![],
         }];
 
         let mut cin = make_cinematic_with_defines(vec![define], pipeline);
         expand_defines(&mut cin).unwrap();
 
         if let LayerBody::Pipeline(stages) = &cin.layers[0].body {
-            if let Expr::Number(v) = &stages[0].args[0].value {
+            if let Expr::Number(v, _) = &stages[0].args[0].value {
                 assert!((v - 0.3).abs() < f64::EPSILON);
             } else {
                 panic!("expected Number(0.3)");
@@ -479,6 +574,39 @@ mod tests {
         }
     }
 
+    #[test]
+    fn expand_defines_inside_and_into_combinators() {
+        let src = r#"cinematic "c" {
+            define blob(size) { circle(size) }
+            define cut(shape) { subtract(shape, blob(0.1)) }
+            layer main { cut(translate(0.2, 0.0) | box(0.3, 0.3)) | glow(2.0) }
+        }"#;
+        let mut cin = crate::compile_to_ast(src).unwrap().cinematics.remove(0);
+        expand_defines(&mut cin).unwrap();
+
+        let LayerBody::Pipeline(stages) = &cin.layers[0].body else { panic!("expected pipeline") };
+        let mut names = Vec::new();
+        walk_stages(stages, &mut |s| names.push(s.name.as_str()));
+        assert_eq!(names, ["subtract", "translate", "box", "circle", "glow"]);
+
+        let b = stages[0].sub_pipelines().nth(1).unwrap();
+        assert!(matches!(b[0].args[0].value, Expr::Number(v, _) if (v - 0.1).abs() < f64::EPSILON));
+        assert_eq!(b[0].expanded_from[0].define, "blob");
+        assert_eq!(b[0].expanded_from[1].define, "cut");
+    }
+
+    #[test]
+    fn expand_defines_lifts_a_lone_call_into_a_combinator_slot() {
+        let src = r#"cinematic "c" {
+            define pair(x) { union(x, ring(0.3, 0.02)) }
+            layer main { pair(circle(0.1)) | glow(2.0) }
+        }"#;
+        let mut cin = crate::compile_to_ast(src).unwrap().cinematics.remove(0);
+        expand_defines(&mut cin).unwrap();
+        let LayerBody::Pipeline(stages) = &cin.layers[0].body else { panic!("expected pipeline") };
+        assert_eq!(crate::codegen::stages::validate_pipeline(stages).unwrap(), ShaderState::Color);
+    }
+
     // ── cinematic_uses_audio ─────────────────────────────
 
     #[test]
@@ -490,6 +618,7 @@ mod tests {
                 opts: vec![],
                 memory: None,
                 cast: None,
+                blend: None,
                 body: LayerBody::Pipeline(vec![Stage {
                     name: "circle".into(),
                     args: vec![Arg {
@@ -497,9 +626,14 @@ mod tests {
                         value: Expr::DottedIdent {
                             object: "audio".into(),
                             field: "bass".into(),
+                            span: Span::default(),
                         },
+                        span: Span::default(),
                     }],
+                    span: Span::default(),
+                    expanded_from: vec![],
                 }]),
+                span: Span::default(),
             }],
             arcs: vec![],
             resonates: vec![],
@@ -510,6 +644,7 @@ mod tests {
             lenses: vec![],
             react: None,
             defines: vec![],
+            span: Span::default(),
         };
         assert!(cinematic_uses_audio(&cin));
     }
@@ -523,15 +658,19 @@ mod tests {
                 opts: vec![],
                 memory: None,
                 cast: None,
+                blend: None,
                 body: LayerBody::Params(vec![Param {
                     name: "intensity".into(),
                     value: Expr::DottedIdent {
                         object: "audio".into(),
                         field: "energy".into(),
+                        span: Span::default(),
                     },
                     modulation: None,
                     temporal_ops: vec![],
+                    span: Span::default(),
                 }]),
+                span: Span::default(),
             }],
             arcs: vec![],
             resonates: vec![],
@@ -542,6 +681,7 @@ mod tests {
             lenses: vec![],
             react: None,
             defines: vec![],
+            span: Span::default(),
         };
         assert!(cinematic_uses_audio(&cin));
     }
@@ -549,7 +689,7 @@ mod tests {
     #[test]
     fn cinematic_uses_audio_from_listen() {
         let mut cin = empty_cinematic();
-        cin.listen = Some(ListenBlock { signals: vec![] });
+        cin.listen = Some(ListenBlock { signals: vec![], span: Span::default() });
         assert!(cinematic_uses_audio(&cin));
     }
 
@@ -562,15 +702,19 @@ mod tests {
                 opts: vec![],
                 memory: None,
                 cast: None,
+                blend: None,
                 body: LayerBody::Params(vec![Param {
                     name: "size".into(),
-                    value: Expr::Number(0.5),
+                    value: Expr::Number(0.5, Span::default()),
                     modulation: Some(Expr::DottedIdent {
                         object: "audio".into(),
                         field: "beat".into(),
+                        span: Span::default(),
                     }),
                     temporal_ops: vec![],
+                    span: Span::default(),
                 }]),
+                span: Span::default(),
             }],
             arcs: vec![],
             resonates: vec![],
@@ -581,6 +725,7 @@ mod tests {
             lenses: vec![],
             react: None,
             defines: vec![],
+            span: Span::default(),
         };
         assert!(cinematic_uses_audio(&cin));
     }
@@ -599,9 +744,12 @@ mod tests {
                 signal: Expr::DottedIdent {
                     object: "audio".into(),
                     field: "beat".into(),
+                    span: Span::default(),
                 },
-                action: Expr::Number(1.0),
+                action: Expr::Number(1.0, Span::default()),
+                span: Span::default(),
             }],
+            span: Span::default(),
         });
         assert!(cinematic_uses_audio(&cin));
     }
@@ -616,11 +764,14 @@ mod tests {
                 value: Expr::DottedIdent {
                     object: "audio".into(),
                     field: "treble".into(),
+                    span: Span::default(),
                 },
                 modulation: None,
                 temporal_ops: vec![],
+                span: Span::default(),
             }],
             post: vec![],
+            span: Span::default(),
         }];
         assert!(cinematic_uses_audio(&cin));
     }
@@ -636,6 +787,7 @@ mod tests {
                 opts: vec![],
                 memory: None,
                 cast: None,
+                blend: None,
                 body: LayerBody::Pipeline(vec![Stage {
                     name: "translate".into(),
                     args: vec![Arg {
@@ -643,9 +795,14 @@ mod tests {
                         value: Expr::DottedIdent {
                             object: "mouse".into(),
                             field: "x".into(),
+                            span: Span::default(),
                         },
+                        span: Span::default(),
                     }],
+                    span: Span::default(),
+                    expanded_from: vec![],
                 }]),
+                span: Span::default(),
             }],
             arcs: vec![],
             resonates: vec![],
@@ -656,6 +813,7 @@ mod tests {
             lenses: vec![],
             react: None,
             defines: vec![],
+            span: Span::default(),
         };
         assert!(cinematic_uses_mouse(&cin));
     }
@@ -674,10 +832,65 @@ mod tests {
                 signal: Expr::DottedIdent {
                     object: "mouse".into(),
                     field: "click".into(),
+                    span: Span::default(),
                 },
-                action: Expr::Number(1.0),
+                action: Expr::Number(1.0, Span::default()),
+                span: Span::default(),
             }],
+            span: Span::default(),
         });
         assert!(cinematic_uses_mouse(&cin));
     }
+
+    // ── cinematic_is_animated ────────────────────────────
+
+    fn animated(src: &str) -> bool {
+        let src = format!(r#"cinematic "c" {{ {src} }}"#);
+        let mut cinematic = crate::compile_to_ast(&src).unwrap().cinematics.remove(0);
+        expand_defines(&mut cinematic).unwrap();
+        cinematic_is_animated(&cinematic)
+    }
+
+    #[test]
+    fn static_cinematics_are_not_animated() {
+        assert!(!animated("layer main { circle(0.3) | glow(2.0) | tint(1.0, 0.5, 0.2) }"));
+        assert!(!animated("layer main { circle(radius) | glow(2.0) } layer p { radius: 0.3 }"));
+        assert!(!animated("layer main { fn: ring(0.3 + data.level * 0.1, 0.02) | glow(2.0) }"));
+    }
+
+    #[test]
+    fn time_audio_pointer_and_memory_animate() {
+        assert!(animated("layer main { circle(0.3 + sin(time) * 0.05) | glow(2.0) }"));
+        assert!(animated("layer main { circle(0.3) | glow(2.0) } listen { }"));
+        assert!(animated("layer main { translate(mouse.x, mouse.y) | circle(0.1) | glow(2.0) }"));
+        assert!(animated("layer main memory: 0.9 { circle(0.3) | glow(2.0) }"));
+        assert!(animated("layer main { fn: circle(r) | glow(2.0)\n r: 0.3 ~ data.v <> 200ms }"));
+    }
+
+    #[test]
+    fn stages_that_read_time_animate() {
+        assert!(animated("layer main { circle(0.3) | glow(2.0) | grain(0.1) }"));
+        let define = "define shimmer() { grain(0.1) }";
+        assert!(animated(&format!("{define} layer main {{ circle(0.3) | glow(2.0) | shimmer() }}")));
+    }
+
+    #[test]
+    fn animated_flag_matches_emitted_stages() {
+        let reads_time = |code: &str| {
+            code.split(|c: char| !c.is_ascii_alphanumeric() && c != '_').any(|w| w == "time")
+        };
+        for b in builtins::BUILTINS {
+            let stage = Stage {
+                name: b.name.into(),
+                args: vec![],
+                span: Span::default(),
+                expanded_from: vec![],
+            };
+            let (mut wgsl, mut glsl) = (String::new(), String::new());
+            crate::codegen::wgsl::emit_wgsl_stage(&mut wgsl, &stage, "");
+            crate::codegen::glsl::emit_glsl_stage(&mut glsl, &stage, "");
+            assert_eq!(reads_time(&wgsl), b.animated, "{} (WGSL): {wgsl}", b.name);
+            assert_eq!(reads_time(&glsl), b.animated, "{} (GLSL): {glsl}", b.name);
+        }
+    }
 }
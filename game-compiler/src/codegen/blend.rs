@@ -0,0 +1,291 @@
+//! Layer compositing — `blend:` modes and `opacity`.
+//!
+//! Layers composite onto `final_color` in declaration order, starting from
+//! black. Each layer's `blend:` mode (screen by default) combines its color
+//! with what is already there, and `opacity` (a per-layer uniform, so it
+//! can be modulated or animated) mixes the result back over the backdrop.
+//! Each composite is clamped to `[0, 1]`: glowing or additive layers can go
+//! past white, and blends like screen invert on a backdrop above 1.
+//!
+//! Every fragment shader ends by deriving alpha from the brightest channel,
+//! so black is transparent when the runtime's `transparent` mode hands the
+//! canvas to the page compositor and ignored otherwise.
+
+use crate::ast::{Cinematic, Expr, Layer};
+use crate::error::{suggest_similar, CompileError, Span};
+
+/// How a layer's color combines with the layers beneath it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlendMode {
+    Normal,
+    Add,
+    Multiply,
+    Screen,
+    Overlay,
+    Difference,
+    Lighten,
+    Darken,
+}
+
+/// Accepted `blend:` names, including the `alpha` and `additive` aliases.
+pub const BLEND_MODES: &[&str] = &[
+    "normal", "alpha", "add", "additive", "multiply", "screen", "overlay", "difference",
+    "lighten", "darken",
+];
+
+impl BlendMode {
+    pub fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "normal" | "alpha" => Self::Normal,
+            "add" | "additive" => Self::Add,
+            "multiply" => Self::Multiply,
+            "screen" => Self::Screen,
+            "overlay" => Self::Overlay,
+            "difference" => Self::Difference,
+            "lighten" => Self::Lighten,
+            "darken" => Self::Darken,
+            _ => return None,
+        })
+    }
+
+    /// Blend one channel of `src` over `dst`, before opacity.
+    pub fn apply(self, dst: f32, src: f32) -> f32 {
+        match self {
+            Self::Normal => src,
+            Self::Add => dst + src,
+            Self::Multiply => dst * src,
+            Self::Screen => dst + src - dst * src,
+            Self::Overlay if dst < 0.5 => 2.0 * dst * src,
+            Self::Overlay => 1.0 - 2.0 * (1.0 - dst) * (1.0 - src),
+            Self::Difference => (dst - src).abs(),
+            Self::Lighten => dst.max(src),
+            Self::Darken => dst.min(src),
+        }
+    }
+
+    /// The blended `vec3` in WGSL (`glsl = false`) or GLSL.
+    fn expr(self, dst: &str, src: &str, glsl: bool) -> String {
+        let half = if glsl { "vec3(0.5)" } else { "vec3<f32>(0.5)" };
+        match self {
+            Self::Normal => src.to_string(),
+            Self::Add => format!("{dst} + {src}"),
+            Self::Multiply => format!("{dst} * {src}"),
+            Self::Screen => format!("{dst} + {src} - {dst} * {src}"),
+            Self::Overlay => {
+                let low = format!("2.0 * {dst} * {src}");
+                let high = format!("1.0 - 2.0 * (1.0 - {dst}) * (1.0 - {src})");
+                if glsl {
+                    format!("mix({high}, {low}, vec3(lessThan({dst}, {half})))")
+                } else {
+                    format!("select({high}, {low}, {dst} < {half})")
+                }
+            }
+            Self::Difference => format!("abs({dst} - {src})"),
+            Self::Lighten => format!("max({dst}, {src})"),
+            Self::Darken => format!("min({dst}, {src})"),
+        }
+    }
+}
+
+/// A layer's blend mode; screen when unset or unknown (see [`validate_blends`]).
+pub fn layer_blend(layer: &Layer) -> BlendMode {
+    match layer.blend.as_ref().map(|p| &p.value) {
+        Some(Expr::Ident(name, _)) => BlendMode::from_name(name).unwrap_or(BlendMode::Screen),
+        _ => BlendMode::Screen,
+    }
+}
+
+/// The uniform behind a layer's `opacity` option, if it has one.
+pub fn opacity_uniform(layer: &Layer) -> Option<String> {
+    let name = format!("{}_opacity", layer.name);
+    layer.opts.iter().any(|p| p.name == name).then_some(name)
+}
+
+/// Whether the fragment shader composites through `final_color`: always for
+/// several layers, and for a single layer with its own blend, opacity or
+/// memory (whose extra outputs need a shared return at the end).
+pub fn composites(cinematic: &Cinematic) -> bool {
+    cinematic.layers.len() > 1
+        || cinematic.layers.iter().any(|l| {
+            l.blend.is_some() || opacity_uniform(l).is_some() || l.memory.is_some()
+        })
+}
+
+/// Reject unknown `blend:` modes.
+pub fn validate_blends(cinematic: &Cinematic) -> Result<(), CompileError> {
+    for layer in &cinematic.layers {
+        let Some(param) = &layer.blend else { continue };
+        let name = match &param.value {
+            Expr::Ident(name, _) => name.as_str(),
+            _ => {
+                return Err(CompileError::validation(format!(
+                    "layer '{}': blend expects a mode name",
+                    layer.name
+                ))
+                .with_span(param.span)
+                .with_help(format!("use one of: {}", BLEND_MODES.join(", "))));
+            }
+        };
+        if BlendMode::from_name(name).is_some() {
+            continue;
+        }
+        let mut err = CompileError::validation(format!(
+            "layer '{}': unknown blend mode '{name}'",
+            layer.name
+        ))
+        .with_span(param.span);
+        err = match suggest_similar(name, BLEND_MODES) {
+            Some(s) => {
+                let value_span = if param.span.is_empty() || param.modulation.is_some() {
+                    Span::default()
+                } else {
+                    Span::new(param.span.end - name.len(), param.span.end)
+                };
+                err.with_help(format!("did you mean '{s}'?")).with_fix(value_span, s)
+            }
+            None => err.with_help(format!("use one of: {}", BLEND_MODES.join(", "))),
+        };
+        return Err(err);
+    }
+    Ok(())
+}
+
+/// Composite `color_result` onto `final_color` (WGSL).
+pub(crate) fn emit_wgsl_blend(s: &mut String, layer: &Layer, indent: &str) {
+    let value = blended(layer, false);
+    s.push_str(&format!("{indent}let lc = color_result.rgb;\n"));
+    s.push_str(&format!(
+        "{indent}final_color = vec4<f32>(clamp({value}, vec3<f32>(0.0), vec3<f32>(1.0)), 1.0);\n"
+    ));
+}
+
+/// Composite `color_result` onto `final_color` (GLSL).
+pub(crate) fn emit_glsl_blend(s: &mut String, layer: &Layer, indent: &str) {
+    let value = blended(layer, true);
+    s.push_str(&format!("{indent}vec3 lc = color_result.rgb;\n"));
+    s.push_str(&format!(
+        "{indent}final_color = vec4(clamp({value}, vec3(0.0), vec3(1.0)), 1.0);\n"
+    ));
+}
+
+/// The new `final_color.rgb`: the blend, mixed back by opacity. Normal
+/// blending also weighs by the layer's own alpha.
+fn blended(layer: &Layer, glsl: bool) -> String {
+    let mode = layer_blend(layer);
+    let value = mode.expr("final_color.rgb", "lc", glsl);
+    let weight = match (mode, opacity_uniform(layer)) {
+        (BlendMode::Normal, Some(opacity)) => format!("color_result.a * {opacity}"),
+        (BlendMode::Normal, None) => "color_result.a".to_string(),
+        (_, Some(opacity)) => opacity,
+        (_, None) => return value,
+    };
+    format!("mix(final_color.rgb, {value}, {weight})")
+}
+
+/// Set `var.a` to the brightest channel (same text in WGSL and GLSL).
+pub(crate) fn emit_alpha(s: &mut String, var: &str, indent: &str) {
+    s.push_str(&format!(
+        "{indent}{var}.a = clamp(max(max({var}.r, {var}.g), {var}.b), 0.0, 1.0);\n"
+    ));
+}
+
+/// Alpha for a finished pixel, mirroring [`emit_alpha`].
+pub fn coverage(rgb: &[f32]) -> f32 {
+    rgb.iter().fold(0.0f32, |a, &c| a.max(c)).clamp(0.0, 1.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cinematic(source: &str) -> Cinematic {
+        crate::compile_to_ast(source).unwrap().cinematics.remove(0)
+    }
+
+    #[test]
+    fn blend_and_opacity_are_lifted_out_of_uniforms() {
+        let cin = cinematic(
+            r#"cinematic "c" {
+                layer bg { circle(0.3) | glow(1.0) }
+                layer fx {
+                    fn: ring(0.3, 0.02) | glow(2.0)
+                    blend: multiply
+                    opacity: 0.5 ~ audio.bass
+                }
+            }"#,
+        );
+        let fx = &cin.layers[1];
+        assert_eq!(layer_blend(fx), BlendMode::Multiply);
+        assert_eq!(opacity_uniform(fx).as_deref(), Some("fx_opacity"));
+        assert_eq!(layer_blend(&cin.layers[0]), BlendMode::Screen);
+        let names: Vec<_> =
+            crate::codegen::extract_uniforms(&cin).into_iter().map(|u| u.name).collect();
+        assert_eq!(names, ["fx_opacity"]);
+    }
+
+    #[test]
+    fn default_screen_blend_is_unchanged() {
+        let cin = cinematic(
+            r#"cinematic "c" {
+                layer a { circle(0.3) | glow(1.0) }
+                layer b { ring(0.3, 0.02) | glow(2.0) }
+            }"#,
+        );
+        let out = crate::codegen::generate(&cin).unwrap();
+        assert!(out.wgsl_fragment.contains(
+            "final_color = vec4<f32>(clamp(final_color.rgb + lc - final_color.rgb * lc, \
+             vec3<f32>(0.0), vec3<f32>(1.0)), 1.0);"
+        ));
+        assert!(out.glsl_fragment.contains(
+            "final_color = vec4(clamp(final_color.rgb + lc - final_color.rgb * lc, \
+             vec3(0.0), vec3(1.0)), 1.0);"
+        ));
+        assert!(out.wgsl_fragment.contains("final_color.a = clamp(max(max(final_color.r"));
+    }
+
+    #[test]
+    fn modes_and_opacity_emit_in_both_languages() {
+        let cin = cinematic(
+            r#"cinematic "c" {
+                layer a (blend: normal, opacity: 0.8) { circle(0.3) | glow(1.0) }
+                layer b (blend: overlay) { ring(0.3, 0.02) | glow(2.0) }
+            }"#,
+        );
+        let out = crate::codegen::generate(&cin).unwrap();
+        assert!(out
+            .wgsl_fragment
+            .contains("mix(final_color.rgb, lc, color_result.a * a_opacity)"));
+        assert!(out.glsl_fragment.contains("mix(final_color.rgb, lc, color_result.a * a_opacity)"));
+        assert!(out.wgsl_fragment.contains("select(1.0 - 2.0 * (1.0 - final_color.rgb)"));
+        assert!(out.glsl_fragment.contains("vec3(lessThan(final_color.rgb, vec3(0.5)))"));
+    }
+
+    #[test]
+    fn single_layer_with_opacity_composites() {
+        let cin =
+            cinematic(r#"cinematic "c" { layer a (opacity: 0.5) { circle(0.3) | glow(1.0) } }"#);
+        assert!(composites(&cin));
+        let out = crate::codegen::generate(&cin).unwrap();
+        assert!(out.wgsl_fragment.contains("return final_color;"));
+        assert!(out.wgsl_fragment.contains("mix(final_color.rgb, final_color.rgb + lc"));
+    }
+
+    #[test]
+    fn unknown_mode_suggests_a_fix() {
+        let cin = cinematic(
+            r#"cinematic "c" { layer a { fn: circle(0.3) | glow(1.0)
+                blend: scren } }"#,
+        );
+        let err = validate_blends(&cin).unwrap_err().to_string();
+        assert!(err.contains("unknown blend mode 'scren'"), "{err}");
+    }
+
+    #[test]
+    fn apply_matches_the_shader_formulas() {
+        assert_eq!(BlendMode::Screen.apply(0.5, 0.5), 0.75);
+        assert_eq!(BlendMode::Overlay.apply(0.25, 0.5), 0.25);
+        assert_eq!(BlendMode::Overlay.apply(0.75, 0.5), 0.75);
+        assert_eq!(BlendMode::Difference.apply(0.25, 0.75), 0.5);
+        assert_eq!(coverage(&[0.2, 0.9, 1.5]), 1.0);
+    }
+}
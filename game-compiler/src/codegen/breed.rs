@@ -172,6 +172,7 @@ mod tests {
                 weight: 0.6,
             }],
             mutations: vec![],
+            span: Span::default(),
         };
         let js = generate_breed_js(&breed);
         assert!(js.contains("class GameBreedMerger"));
@@ -190,6 +191,7 @@ mod tests {
                 target: "scale".into(),
                 range: 0.3,
             }],
+            span: Span::default(),
         };
         let js = generate_breed_js(&breed);
         assert!(js.contains("mutate scale"));
@@ -220,6 +222,7 @@ mod tests {
             parents: vec!["a".into(), "b".into()],
             inherit_rules: vec![],
             mutations: vec![],
+            span: Span::default(),
         };
         let js = generate_breed_js(&breed);
         assert!(
@@ -241,6 +244,7 @@ mod tests {
             parents: vec!["a".into(), "b".into()],
             inherit_rules: vec![],
             mutations: vec![],
+            span: Span::default(),
         };
         let js = generate_breed_js(&breed);
         assert!(
@@ -262,6 +266,7 @@ mod tests {
             parents: vec!["a".into(), "b".into()],
             inherit_rules: vec![],
             mutations: vec![],
+            span: Span::default(),
         };
         let js = generate_breed_js(&breed);
         assert!(
@@ -287,6 +292,7 @@ mod tests {
                 weight: 0.5,
             }],
             mutations: vec![],
+            span: Span::default(),
         };
         let js = generate_breed_js(&breed);
         assert!(
@@ -308,6 +314,7 @@ mod tests {
             parents: vec!["a".into(), "b".into()],
             inherit_rules: vec![],
             mutations: vec![],
+            span: Span::default(),
         };
         let js = generate_breed_js(&breed);
         assert!(
@@ -332,6 +339,7 @@ mod tests {
                 target: "scale".into(),
                 range: 0.3,
             }],
+            span: Span::default(),
         };
         let js = generate_breed_js(&breed);
         assert!(
@@ -352,6 +360,7 @@ mod tests {
                 weight: 0.5,
             }],
             mutations: vec![],
+            span: Span::default(),
         };
         let js = generate_breed_js(&breed);
         assert!(
@@ -27,7 +27,7 @@ pub fn validate_layer_cast(layer: &Layer) -> Result<(), CompileError> {
         None => return Ok(()), // No cast declaration — anything goes
     };
 
-    let expected = cast_to_state(cast_str)?;
+    let expected = cast_to_state(cast_str).map_err(|e| e.with_span(layer.span))?;
 
     let pipeline = match &layer.body {
         LayerBody::Pipeline(stages) => stages,
@@ -41,7 +41,8 @@ pub fn validate_layer_cast(layer: &Layer) -> Result<(), CompileError> {
         return Err(CompileError::validation(format!(
             "layer '{}' is cast as '{cast_str}' but has an empty pipeline",
             layer.name
-        )));
+        ))
+        .with_span(layer.span));
     }
 
     let actual = stages::validate_pipeline(pipeline)?;
@@ -50,7 +51,8 @@ pub fn validate_layer_cast(layer: &Layer) -> Result<(), CompileError> {
         return Err(CompileError::validation(format!(
             "layer '{}' is cast as '{cast_str}' ({expected:?}) but pipeline produces {actual:?}",
             layer.name
-        )));
+        ))
+        .with_span(layer.span));
     }
 
     Ok(())
@@ -75,12 +77,14 @@ mod tests {
             opts: vec![],
             memory: None,
             cast: Some(cast.into()),
+            blend: None,
             body: LayerBody::Pipeline(stages),
+            span: Span::default(),
         }
     }
 
     fn stage(name: &str) -> Stage {
-        Stage { name: name.into(), args: vec![] }
+        Stage { name: name.into(), args: vec![], span: Span::default(), expanded_from: vec![] }
     }
 
     #[test]
@@ -131,7 +135,9 @@ mod tests {
             opts: vec![],
             memory: None,
             cast: None,
+            blend: None,
             body: LayerBody::Pipeline(vec![stage("circle"), stage("glow")]),
+            span: Span::default(),
         };
         assert!(validate_layer_cast(&layer).is_ok());
     }
@@ -163,6 +169,7 @@ mod tests {
             resonates: vec![],
             listen: None, voice: None, score: None, gravity: None,
             lenses: vec![], react: None, defines: vec![],
+            span: Span::default(),
         };
         let err = validate_casts(&cin).unwrap_err();
         assert!(err.to_string().contains("layer 'b'"));
@@ -0,0 +1,471 @@
+//! Colors: the named color table, hex literals and the `rgb()`, `hsl()`
+//! and `oklch()` constructors.
+//!
+//! Every backend reads names from [`NAMED_COLORS`]. WGSL and GLSL inline a
+//! `vec3` literal, JS an `[r, g, b]` array and the CPU renderer the channels
+//! themselves. Channels are sRGB in 0..1, rounded to three places.
+//!
+//! Constructors with constant arguments fold to a literal. `rgb()` and
+//! `hsl()` also take live expressions (`hsl(time * 30.0, 0.8, 0.5)`), but
+//! `oklch()` needs constants because its conversion is too heavy to inline.
+
+use crate::ast::{walk_stages, Arg, Cinematic, Expr, LayerBody, Stage};
+use crate::codegen::gradient;
+use crate::codegen::stages::find_arg;
+use crate::error::{suggest_similar, CompileError, Span};
+use crate::optimize;
+
+/// Every color name, as `0xRRGGBB`: the GAME palette, then the CSS named
+/// colors. `gold`, `green` and `violet` keep their GAME values, which
+/// differ from CSS (`#ffd700`, `#008000` and `#ee82ee`).
+pub static NAMED_COLORS: &[(&str, u32)] = &[
+    // GAME palette
+    ("gold", 0xd4af37), ("green", 0x00ff00), ("violet", 0x8a2be2),
+    ("midnight", 0x0a0a1e), ("obsidian", 0x120f15), ("ember", 0xe55121),
+    ("frost", 0xafe0f3), ("deep_blue", 0x001964), ("ash", 0x777777),
+    ("charcoal", 0x363636), ("plasma", 0x9400d3),
+    // CSS
+    ("aliceblue", 0xf0f8ff), ("antiquewhite", 0xfaebd7), ("aqua", 0x00ffff),
+    ("aquamarine", 0x7fffd4), ("azure", 0xf0ffff), ("beige", 0xf5f5dc),
+    ("bisque", 0xffe4c4), ("black", 0x000000), ("blanchedalmond", 0xffebcd),
+    ("blue", 0x0000ff), ("blueviolet", 0x8a2be2), ("brown", 0xa52a2a),
+    ("burlywood", 0xdeb887), ("cadetblue", 0x5f9ea0), ("chartreuse", 0x7fff00),
+    ("chocolate", 0xd2691e), ("coral", 0xff7f50), ("cornflowerblue", 0x6495ed),
+    ("cornsilk", 0xfff8dc), ("crimson", 0xdc143c), ("cyan", 0x00ffff),
+    ("darkblue", 0x00008b), ("darkcyan", 0x008b8b), ("darkgoldenrod", 0xb8860b),
+    ("darkgray", 0xa9a9a9), ("darkgreen", 0x006400), ("darkgrey", 0xa9a9a9),
+    ("darkkhaki", 0xbdb76b), ("darkmagenta", 0x8b008b), ("darkolivegreen", 0x556b2f),
+    ("darkorange", 0xff8c00), ("darkorchid", 0x9932cc), ("darkred", 0x8b0000),
+    ("darksalmon", 0xe9967a), ("darkseagreen", 0x8fbc8f), ("darkslateblue", 0x483d8b),
+    ("darkslategray", 0x2f4f4f), ("darkslategrey", 0x2f4f4f), ("darkturquoise", 0x00ced1),
+    ("darkviolet", 0x9400d3), ("deeppink", 0xff1493), ("deepskyblue", 0x00bfff),
+    ("dimgray", 0x696969), ("dimgrey", 0x696969), ("dodgerblue", 0x1e90ff),
+    ("firebrick", 0xb22222), ("floralwhite", 0xfffaf0), ("forestgreen", 0x228b22),
+    ("fuchsia", 0xff00ff), ("gainsboro", 0xdcdcdc), ("ghostwhite", 0xf8f8ff),
+    ("goldenrod", 0xdaa520), ("gray", 0x808080), ("greenyellow", 0xadff2f),
+    ("grey", 0x808080), ("honeydew", 0xf0fff0), ("hotpink", 0xff69b4),
+    ("indianred", 0xcd5c5c), ("indigo", 0x4b0082), ("ivory", 0xfffff0),
+    ("khaki", 0xf0e68c), ("lavender", 0xe6e6fa), ("lavenderblush", 0xfff0f5),
+    ("lawngreen", 0x7cfc00), ("lemonchiffon", 0xfffacd), ("lightblue", 0xadd8e6),
+    ("lightcoral", 0xf08080), ("lightcyan", 0xe0ffff), ("lightgoldenrodyellow", 0xfafad2),
+    ("lightgray", 0xd3d3d3), ("lightgreen", 0x90ee90), ("lightgrey", 0xd3d3d3),
+    ("lightpink", 0xffb6c1), ("lightsalmon", 0xffa07a), ("lightseagreen", 0x20b2aa),
+    ("lightskyblue", 0x87cefa), ("lightslategray", 0x778899), ("lightslategrey", 0x778899),
+    ("lightsteelblue", 0xb0c4de), ("lightyellow", 0xffffe0), ("lime", 0x00ff00),
+    ("limegreen", 0x32cd32), ("linen", 0xfaf0e6), ("magenta", 0xff00ff),
+    ("maroon", 0x800000), ("mediumaquamarine", 0x66cdaa), ("mediumblue", 0x0000cd),
+    ("mediumorchid", 0xba55d3), ("mediumpurple", 0x9370db), ("mediumseagreen", 0x3cb371),
+    ("mediumslateblue", 0x7b68ee), ("mediumspringgreen", 0x00fa9a),
+    ("mediumturquoise", 0x48d1cc), ("mediumvioletred", 0xc71585),
+    ("midnightblue", 0x191970), ("mintcream", 0xf5fffa), ("mistyrose", 0xffe4e1),
+    ("moccasin", 0xffe4b5), ("navajowhite", 0xffdead), ("navy", 0x000080),
+    ("oldlace", 0xfdf5e6), ("olive", 0x808000), ("olivedrab", 0x6b8e23),
+    ("orange", 0xffa500), ("orangered", 0xff4500), ("orchid", 0xda70d6),
+    ("palegoldenrod", 0xeee8aa), ("palegreen", 0x98fb98), ("paleturquoise", 0xafeeee),
+    ("palevioletred", 0xdb7093), ("papayawhip", 0xffefd5), ("peachpuff", 0xffdab9),
+    ("peru", 0xcd853f), ("pink", 0xffc0cb), ("plum", 0xdda0dd),
+    ("powderblue", 0xb0e0e6), ("purple", 0x800080), ("rebeccapurple", 0x663399),
+    ("red", 0xff0000), ("rosybrown", 0xbc8f8f), ("royalblue", 0x4169e1),
+    ("saddlebrown", 0x8b4513), ("salmon", 0xfa8072), ("sandybrown", 0xf4a460),
+    ("seagreen", 0x2e8b57), ("seashell", 0xfff5ee), ("sienna", 0xa0522d),
+    ("silver", 0xc0c0c0), ("skyblue", 0x87ceeb), ("slateblue", 0x6a5acd),
+    ("slategray", 0x708090), ("slategrey", 0x708090), ("snow", 0xfffafa),
+    ("springgreen", 0x00ff7f), ("steelblue", 0x4682b4), ("tan", 0xd2b48c),
+    ("teal", 0x008080), ("thistle", 0xd8bfd8), ("tomato", 0xff6347),
+    ("turquoise", 0x40e0d0), ("wheat", 0xf5deb3), ("white", 0xffffff),
+    ("whitesmoke", 0xf5f5f5), ("yellow", 0xffff00), ("yellowgreen", 0x9acd32),
+];
+
+/// Color constructor calls.
+pub const CONSTRUCTORS: &[&str] = &["rgb", "hsl", "oklch"];
+
+pub fn is_constructor(name: &str) -> bool {
+    CONSTRUCTORS.contains(&name)
+}
+
+/// A named color's channels.
+pub fn named(name: &str) -> Option<[f64; 3]> {
+    let &(_, hex) = NAMED_COLORS.iter().find(|(n, _)| *n == name)?;
+    Some([hex >> 16, hex >> 8, hex].map(|c| channel(c & 0xff)))
+}
+
+fn channel(byte: u32) -> f64 {
+    round(f64::from(byte) / 255.0)
+}
+
+fn round(v: f64) -> f64 {
+    (v * 1000.0).round() / 1000.0
+}
+
+/// Parse `#rgb`, `#rgba`, `#rrggbb` or `#rrggbbaa`. Layers take their alpha
+/// from brightness, so alpha is premultiplied into the channels.
+pub fn parse_hex(s: &str) -> Option<[f64; 3]> {
+    let digits = s.strip_prefix('#')?;
+    if !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    let byte = |hex: &str| u32::from_str_radix(hex, 16).ok();
+    let [r, g, b, a] = match digits.len() {
+        3 | 4 => {
+            let short = |i: usize| digits.get(i..=i).map_or(Some(15), byte).map(|v| v * 17);
+            [short(0)?, short(1)?, short(2)?, short(3)?]
+        }
+        6 | 8 => {
+            let long = |i: usize| digits.get(i..i + 2).map_or(Some(255), byte);
+            [long(0)?, long(2)?, long(4)?, long(6)?]
+        }
+        _ => return None,
+    };
+    let alpha = f64::from(a) / 255.0;
+    Some([r, g, b].map(|c| round(f64::from(c) / 255.0 * alpha)))
+}
+
+/// CSS `hsl()`: hue in degrees, saturation and lightness in 0..1.
+pub fn hsl_to_rgb(h: f64, s: f64, l: f64) -> [f64; 3] {
+    [0.0, 8.0, 4.0].map(|n: f64| {
+        let k = (n + h / 30.0).rem_euclid(12.0);
+        l - s * l.min(1.0 - l) * (k - 3.0).min(9.0 - k).clamp(-1.0, 1.0)
+    })
+}
+
+/// CSS `oklch()`: lightness in 0..1, chroma (about 0..0.4) and hue in
+/// degrees, converted to sRGB and clipped to the gamut.
+pub fn oklch_to_rgb(l: f64, c: f64, h: f64) -> [f64; 3] {
+    let (sin, cos) = h.to_radians().sin_cos();
+    let (a, b) = (c * cos, c * sin);
+    let l_ = (l + 0.396_337_777_4 * a + 0.215_803_757_3 * b).powi(3);
+    let m_ = (l - 0.105_561_345_8 * a - 0.063_854_172_8 * b).powi(3);
+    let s_ = (l - 0.089_484_177_5 * a - 1.291_485_548_0 * b).powi(3);
+    let linear = [
+        4.076_741_662_1 * l_ - 3.307_711_591_3 * m_ + 0.230_969_929_2 * s_,
+        -1.268_438_004_6 * l_ + 2.609_757_401_1 * m_ - 0.341_319_396_5 * s_,
+        -0.004_196_086_3 * l_ - 0.703_418_614_7 * m_ + 1.707_614_701_0 * s_,
+    ];
+    linear.map(|x| {
+        let x = x.clamp(0.0, 1.0);
+        if x <= 0.003_130_8 { 12.92 * x } else { 1.055 * x.powf(1.0 / 2.4) - 0.055 }
+    })
+}
+
+/// Channels of a constructor call whose arguments are all constant.
+pub fn fold(name: &str, args: &[Arg]) -> Option<[f64; 3]> {
+    let [a, b, c] = match args {
+        [a, b, c] => [a, b, c].map(|arg| constant(&arg.value)),
+        _ => return None,
+    };
+    let (a, b, c) = (a?, b?, c?);
+    let rgb = match name {
+        "rgb" => [a, b, c],
+        "hsl" => hsl_to_rgb(a, b, c),
+        "oklch" => oklch_to_rgb(a, b, c),
+        _ => return None,
+    };
+    Some(rgb.map(round))
+}
+
+/// The value of a constant argument, after folding arithmetic.
+fn constant(value: &Expr) -> Option<f64> {
+    let mut value = value.clone();
+    optimize::constant_fold(&mut value);
+    optimize::as_number(&value)
+}
+
+/// A color's channels as an `[r, g, b]` array expression, which every
+/// backend already lowers to its `vec3` or array literal.
+pub fn to_expr(rgb: [f64; 3]) -> Expr {
+    Expr::Array(rgb.map(|c| Expr::Number(c, Span::default())).to_vec(), Span::default())
+}
+
+/// Output language for [`compile_call`].
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum Backend {
+    Wgsl,
+    Glsl,
+    Js,
+}
+
+/// Lower a constructor call: a literal when the arguments are constant,
+/// otherwise an inline conversion. `compile` lowers the arguments.
+pub(crate) fn compile_call(
+    name: &str,
+    args: &[Arg],
+    compile: fn(&Expr) -> String,
+    backend: Backend,
+) -> String {
+    if let Some(rgb) = fold(name, args) {
+        return compile(&to_expr(rgb));
+    }
+    let values: Vec<Expr> = args.iter().map(|a| a.value.clone()).collect();
+    match (name, values.as_slice()) {
+        ("rgb", [..]) => compile(&Expr::Array(values, Span::default())),
+        ("hsl", [h, s, l]) => {
+            let (h, s, l) = (compile(h), compile(s), compile(l));
+            match backend {
+                // CSS Color 4 `hslToRgb`, with the arguments bound once
+                Backend::Js => format!(
+                    "((h, s, l) => [0, 8, 4].map((n) => {{ const k = (((n + h / 30) % 12) + 12) % 12; \
+                     return l - s * Math.min(l, 1 - l) * Math.max(-1, Math.min(k - 3, 9 - k, 1)); \
+                     }}))({h}, {s}, {l})"
+                ),
+                Backend::Wgsl | Backend::Glsl => {
+                    let vec3 = if matches!(backend, Backend::Wgsl) { "vec3f" } else { "vec3" };
+                    let k = format!("(fract(({vec3}(0.0, 8.0, 4.0) + {h} / 30.0) / 12.0) * 12.0)");
+                    format!(
+                        "({l} - {s} * min({l}, 1.0 - {l}) \
+                         * clamp(min({k} - 3.0, 9.0 - {k}), {vec3}(-1.0), {vec3}(1.0)))"
+                    )
+                }
+            }
+        }
+        // Rejected by `validate_colors`
+        _ => compile(&to_expr([0.0; 3])),
+    }
+}
+
+/// Whether an expression is color-valued: a named color, an `[r, g, b]`
+/// array (hex literals parse to one), a constructor call, or arithmetic on
+/// one of those (`gold * 0.5`).
+pub fn is_color(value: &Expr) -> bool {
+    match value {
+        Expr::Ident(name, _) => named(name).is_some(),
+        Expr::Array(elems, _) => elems.len() == 3,
+        Expr::Call { name, .. } => is_constructor(name),
+        Expr::Paren(inner, _) | Expr::Neg(inner, _) => is_color(inner),
+        Expr::BinOp { op, left, right, .. } if !op.is_boolean() => {
+            is_color(left) || is_color(right)
+        }
+        _ => false,
+    }
+}
+
+/// Arguments that must be colors: the lone argument of `tint` and `shade`,
+/// and `gradient`'s colors and stops.
+fn color_slots(stage: &Stage) -> Vec<&Expr> {
+    match (stage.name.as_str(), stage.args.as_slice()) {
+        ("tint" | "shade", [arg]) if arg.name.as_deref().is_none_or(|n| n == "r") => {
+            vec![&arg.value]
+        }
+        ("gradient", _) => ["color_a", "color_b"]
+            .into_iter()
+            .enumerate()
+            .filter_map(|(pos, slot)| find_arg(&stage.args, slot, pos))
+            .flat_map(|arg| gradient::colors_in(&arg.value))
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Reject unknown color names where a color is expected, and constructor
+/// calls with the wrong arity or (for `oklch`) non-constant arguments.
+pub fn validate_colors(cinematic: &Cinematic) -> Result<(), CompileError> {
+    // Layer parameters are uniforms, so they may stand in for a color
+    let declared: Vec<&str> = cinematic
+        .layers
+        .iter()
+        .flat_map(|layer| {
+            let body = match &layer.body {
+                LayerBody::Params(params) => params.as_slice(),
+                LayerBody::Pipeline(_) => &[],
+            };
+            body.iter().chain(&layer.opts).map(|p| p.name.as_str())
+        })
+        .collect();
+
+    let mut stages = Vec::new();
+    for layer in &cinematic.layers {
+        if let LayerBody::Pipeline(pipeline) = &layer.body {
+            walk_stages(pipeline, &mut |stage| stages.push(stage));
+        }
+    }
+    for lens in &cinematic.lenses {
+        walk_stages(&lens.post, &mut |stage| stages.push(stage));
+    }
+
+    for stage in stages {
+        for value in color_slots(stage) {
+            if let Expr::Ident(name, span) = value {
+                if named(name).is_none() && !declared.contains(&name.as_str()) {
+                    return Err(unknown_color(stage, name, *span));
+                }
+            }
+        }
+        for arg in &stage.args {
+            validate_constructors(stage, &arg.value)?;
+        }
+    }
+    Ok(())
+}
+
+fn unknown_color(stage: &Stage, name: &str, span: Span) -> CompileError {
+    let names: Vec<&str> = NAMED_COLORS.iter().map(|(n, _)| *n).collect();
+    let err = stage.locate(CompileError::validation(format!(
+        "{}: unknown color '{name}'",
+        stage.name
+    )));
+    match suggest_similar(name, &names) {
+        Some(s) => err.with_help(format!("did you mean '{s}'?")).with_fix(span, s),
+        None => err.with_help(format!(
+            "use a named color, a hex literal like #ff8800, rgb(), hsl() or oklch(), \
+             or declare '{name}' as a layer parameter"
+        )),
+    }
+}
+
+fn validate_constructors(stage: &Stage, value: &Expr) -> Result<(), CompileError> {
+    match value {
+        Expr::Call { name, args, .. } => {
+            if is_constructor(name) {
+                let positional = args.iter().all(|a| a.name.is_none());
+                if args.len() != 3 || !positional {
+                    let params = match name.as_str() {
+                        "rgb" => "r, g, b",
+                        "hsl" => "hue, saturation, lightness",
+                        _ => "lightness, chroma, hue",
+                    };
+                    return Err(stage.locate(CompileError::validation(format!(
+                        "{name}() takes 3 positional arguments ({params}), found {}",
+                        args.len()
+                    ))));
+                }
+                if name == "oklch" && fold(name, args).is_none() {
+                    return Err(stage
+                        .locate(CompileError::validation(
+                            "oklch() needs constant arguments",
+                        ))
+                        .with_help("animate with hsl(), or mix() between two oklch() colors"));
+                }
+            }
+            args.iter().try_for_each(|a| validate_constructors(stage, &a.value))
+        }
+        Expr::Paren(inner, _) | Expr::Neg(inner, _) | Expr::Not(inner, _) => {
+            validate_constructors(stage, inner)
+        }
+        Expr::BinOp { left, right, .. } => {
+            validate_constructors(stage, left)?;
+            validate_constructors(stage, right)
+        }
+        Expr::Array(elems, _) => elems.iter().try_for_each(|e| validate_constructors(stage, e)),
+        Expr::Ternary { condition, if_true, if_false, .. } => {
+            validate_constructors(stage, condition)?;
+            validate_constructors(stage, if_true)?;
+            validate_constructors(stage, if_false)
+        }
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codegen::expr::{compile_glsl, compile_js, compile_wgsl};
+
+    fn parse_expr(src: &str) -> Expr {
+        let src = format!(r#"cinematic "c" {{ layer main {{ fn: circle({src}) }} }}"#);
+        let mut cin = crate::compile_to_ast(&src).unwrap().cinematics.remove(0);
+        match cin.layers.remove(0).body {
+            LayerBody::Pipeline(mut stages) => stages.remove(0).args.remove(0).value,
+            _ => panic!("expected pipeline"),
+        }
+    }
+
+    fn validate(src: &str) -> Result<(), CompileError> {
+        let src = format!(r#"cinematic "c" {{ {src} }}"#);
+        validate_colors(&crate::compile_to_ast(&src).unwrap().cinematics[0])
+    }
+
+    #[test]
+    fn every_name_is_unique_and_lowercase() {
+        let mut names: Vec<&str> = NAMED_COLORS.iter().map(|(n, _)| *n).collect();
+        assert!(names.iter().all(|n| n.chars().all(|c| c.is_ascii_lowercase() || c == '_')));
+        names.sort_unstable();
+        names.dedup();
+        assert_eq!(names.len(), NAMED_COLORS.len());
+        // 148 CSS names (less the three GAME overrides) plus the GAME palette
+        assert_eq!(NAMED_COLORS.len(), 148 + 8);
+    }
+
+    #[test]
+    fn css_names_and_game_overrides() {
+        assert_eq!(named("crimson"), Some([0.863, 0.078, 0.235]));
+        assert_eq!(named("rebeccapurple"), Some([0.4, 0.2, 0.6]));
+        assert_eq!(named("gold"), Some([0.831, 0.686, 0.216]));
+        assert_eq!(named("green"), Some([0.0, 1.0, 0.0]));
+        assert_eq!(named("midnight"), Some([0.039, 0.039, 0.118]));
+        assert_eq!(named("rainbow"), None);
+    }
+
+    #[test]
+    fn hex_forms_premultiply_alpha() {
+        assert_eq!(parse_hex("#ff8800"), Some([1.0, 0.533, 0.0]));
+        assert_eq!(parse_hex("#f80"), Some([1.0, 0.533, 0.0]));
+        assert_eq!(parse_hex("#ff880080"), Some([0.502, 0.268, 0.0]));
+        assert_eq!(parse_hex("#f808"), Some([0.533, 0.284, 0.0]));
+        assert_eq!(parse_hex("#ff88zz"), None);
+        assert_eq!(parse_hex("#ff88"), Some([0.533, 0.533, 0.284]));
+        assert_eq!(parse_hex("ff8800"), None);
+        assert_eq!(parse_hex("#ff880"), None);
+    }
+
+    #[test]
+    fn constructors_fold_to_literals() {
+        assert_eq!(compile_wgsl(&parse_expr("#ff8800")), "vec3f(1.0, 0.533, 0.0)");
+        assert_eq!(compile_wgsl(&parse_expr("rgb(1.0, 0.5, 0.25 * 2.0)")), "vec3f(1.0, 0.5, 0.5)");
+        assert_eq!(compile_glsl(&parse_expr("hsl(120.0, 1.0, 0.5)")), "vec3(0.0, 1.0, 0.0)");
+        assert_eq!(compile_js(&parse_expr("hsl(240deg, 1.0, 0.25)")), "[0.0, 0.0, 0.5]");
+        // oklch(0.628 0.2577 29.23) is CSS red
+        let red = fold("oklch", &[0.628, 0.2577, 29.23].map(|v| Arg {
+            name: None,
+            value: Expr::Number(v, Span::default()),
+            span: Span::default(),
+        }));
+        assert_eq!(red, Some([1.0, 0.0, 0.0]));
+    }
+
+    #[test]
+    fn dynamic_hsl_is_inlined_per_backend() {
+        let e = parse_expr("hsl(time * 30.0, 0.8, 0.5)");
+        let wgsl = compile_wgsl(&e);
+        assert!(wgsl.contains("fract((vec3f(0.0, 8.0, 4.0) + (time * 30.0) / 30.0) / 12.0)"), "{wgsl}");
+        assert!(compile_glsl(&e).contains("clamp(min("), "{}", compile_glsl(&e));
+        assert!(compile_js(&e).starts_with("((h, s, l) => [0, 8, 4].map("));
+        assert_eq!(compile_wgsl(&parse_expr("rgb(time, 0.0, 1.0)")), "vec3f(time, 0.0, 1.0)");
+    }
+
+    #[test]
+    fn is_color_covers_names_arrays_calls_and_arithmetic() {
+        for src in ["crimson", "#fff", "[0.1, 0.2, 0.3]", "hsl(0.0, 1.0, 0.5)", "gold * 0.5"] {
+            assert!(is_color(&parse_expr(src)), "{src}");
+        }
+        for src in ["0.5", "time", "[0.1, 0.2]", "sin(time)"] {
+            assert!(!is_color(&parse_expr(src)), "{src}");
+        }
+    }
+
+    #[test]
+    fn unknown_colors_suggest_the_nearest_name() {
+        let err = validate("layer main { circle(0.3) | tint(crimsn) }").unwrap_err();
+        assert!(err.to_string().contains("tint: unknown color 'crimsn'"), "{err}");
+        assert_eq!(err.help(), Some("did you mean 'crimson'?"));
+        assert_eq!(err.to_diagnostic().fix.unwrap().replacement, "crimson");
+
+        let err = validate("layer main { gradient([black, zzzzzz]) }").unwrap_err();
+        assert!(err.to_string().contains("gradient: unknown color 'zzzzzz'"), "{err}");
+        assert!(err.help().unwrap().contains("hex literal like #ff8800"));
+    }
+
+    #[test]
+    fn layer_params_may_stand_in_for_colors() {
+        assert!(validate("layer main { fn: circle(0.3) | tint(hue)\n hue: 0.5 }").is_ok());
+        assert!(validate("layer main { circle(0.3) | tint(crimson) }").is_ok());
+        assert!(validate("layer main { circle(0.3) | tint(1.0, 0.5, 0.0) }").is_ok());
+    }
+
+    #[test]
+    fn rejects_bad_constructor_calls() {
+        let err = validate("layer main { circle(0.3) | tint(rgb(1.0, 0.5)) }").unwrap_err();
+        assert!(err.to_string().contains("rgb() takes 3 positional arguments (r, g, b), found 2"));
+        let err = validate("layer main { circle(0.3) | tint(oklch(0.7, 0.1, time)) }").unwrap_err();
+        assert!(err.to_string().contains("oklch() needs constant arguments"), "{err}");
+        assert!(validate("layer main { circle(0.3) | tint(oklch(0.7, 0.1, 30.0)) }").is_ok());
+    }
+}
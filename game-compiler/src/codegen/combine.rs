@@ -0,0 +1,158 @@
+//! SDF combinator codegen — `union`, `subtract`, `intersect`,
+//! `smooth_union` and `morph`.
+//!
+//! A combinator's `a` and `b` arguments are nested Position -> Sdf
+//! pipelines. Each one runs in its own block from the position the
+//! combinator received, so transforms inside one shape never leak into the
+//! other or into the stages that follow:
+//!
+//! ```text
+//! var sdf_result: f32;
+//! { let p_c = p;
+//!   var sdf_a: f32;
+//!   { <pipeline a>  sdf_a = sdf_result; }
+//!   p = p_c;
+//!   ...same for b...
+//!   sdf_result = min(sdf_a, sdf_b); }
+//! ```
+//!
+//! Nested combinators shadow `p_c`, `sdf_a` and `sdf_b` in their own block.
+
+use crate::ast::{Expr, Stage};
+use crate::codegen::stages::{find_arg, get_arg, get_arg_glsl};
+use crate::codegen::{glsl, wgsl};
+
+/// Emit a combinator stage (WGSL).
+pub(crate) fn emit_wgsl(s: &mut String, stage: &Stage, indent: &str) {
+    emit(s, stage, indent, false);
+}
+
+/// Emit a combinator stage (GLSL).
+pub(crate) fn emit_glsl(s: &mut String, stage: &Stage, indent: &str) {
+    emit(s, stage, indent, true);
+}
+
+fn emit(s: &mut String, stage: &Stage, indent: &str, glsl: bool) {
+    let inner = format!("{indent}    ");
+    let nested = format!("{inner}    ");
+    // Declaration keywords: mutable float, immutable vec2, immutable float
+    let (var_f, let_v, let_f) =
+        if glsl { ("float ", "vec2", "float") } else { ("var ", "let", "let") };
+    let ty = if glsl { "" } else { ": f32" };
+
+    s.push_str(&format!("{indent}{var_f}sdf_result{ty};\n"));
+    s.push_str(&format!("{indent}{{ // {}\n", stage.name));
+    s.push_str(&format!("{inner}{let_v} p_c = p;\n"));
+    for (pos, slot) in ["a", "b"].into_iter().enumerate() {
+        s.push_str(&format!("{inner}{var_f}sdf_{slot}{ty};\n"));
+        s.push_str(&format!("{inner}{{\n"));
+        for sub in sub_pipeline(stage, slot, pos) {
+            if glsl {
+                glsl::emit_glsl_stage(s, sub, &nested);
+            } else {
+                wgsl::emit_wgsl_stage(s, sub, &nested);
+            }
+        }
+        s.push_str(&format!("{nested}sdf_{slot} = sdf_result;\n"));
+        s.push_str(&format!("{inner}}}\n"));
+        s.push_str(&format!("{inner}p = p_c;\n"));
+    }
+
+    let arg = |name: &str, pos: usize| {
+        if glsl {
+            get_arg_glsl(&stage.args, name, pos, &stage.name)
+        } else {
+            get_arg(&stage.args, name, pos, &stage.name)
+        }
+    };
+    let combined = match stage.name.as_str() {
+        "union" => "min(sdf_a, sdf_b)".to_string(),
+        "subtract" => "max(sdf_a, -sdf_b)".to_string(),
+        "intersect" => "max(sdf_a, sdf_b)".to_string(),
+        "smooth_union" => {
+            // Polynomial smooth minimum; `k` is the blend radius
+            let k = arg("k", 2);
+            s.push_str(&format!("{inner}{let_f} su_k = max({k}, 0.0001);\n"));
+            s.push_str(&format!(
+                "{inner}{let_f} su_h = clamp(0.5 + 0.5 * (sdf_b - sdf_a) / su_k, 0.0, 1.0);\n"
+            ));
+            "mix(sdf_b, sdf_a, su_h) - su_k * su_h * (1.0 - su_h)".to_string()
+        }
+        "morph" => format!("mix(sdf_a, sdf_b, {})", arg("t", 2)),
+        other => unreachable!("not a combinator: {other}"),
+    };
+    s.push_str(&format!("{inner}sdf_result = {combined}; }}\n"));
+}
+
+/// The stages of a combinator's `a` or `b` pipeline (empty if missing,
+/// which `stages::validate_pipeline` has already rejected).
+pub fn sub_pipeline<'a>(stage: &'a Stage, slot: &str, pos: usize) -> &'a [Stage] {
+    match find_arg(&stage.args, slot, pos).map(|a| &a.value) {
+        Some(Expr::Pipeline(stages, _)) => stages,
+        _ => &[],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ast::LayerBody;
+
+    fn first_stage(pipeline: &str) -> Stage {
+        let src = format!(r#"cinematic "c" {{ layer main {{ {pipeline} }} }}"#);
+        let mut cin = crate::compile_to_ast(&src).unwrap().cinematics.remove(0);
+        match cin.layers.remove(0).body {
+            LayerBody::Pipeline(mut stages) => stages.remove(0),
+            _ => panic!("expected pipeline"),
+        }
+    }
+
+    #[test]
+    fn union_runs_each_pipeline_from_the_same_position() {
+        let stage = first_stage("union(circle(0.2), translate(0.3, 0.0) | box(0.1, 0.1))");
+        let mut s = String::new();
+        emit_wgsl(&mut s, &stage, "    ");
+        assert!(s.starts_with("    var sdf_result: f32;\n    { // union\n        let p_c = p;\n"));
+        assert!(s.contains("            var sdf_result = sdf_circle(p, 0.200000);\n"));
+        assert!(s.contains("            sdf_b = sdf_result;\n        }\n        p = p_c;\n"));
+        assert!(s.ends_with("        sdf_result = min(sdf_a, sdf_b); }\n"));
+        // The translate only moves the box
+        let translate = s.find("p = p - vec2<f32>(0.300000, 0.000000);").unwrap();
+        assert!(translate > s.find("var sdf_b: f32;").unwrap());
+    }
+
+    #[test]
+    fn glsl_declares_typed_locals() {
+        let stage = first_stage("subtract(circle(0.2), circle(0.1))");
+        let mut s = String::new();
+        emit_glsl(&mut s, &stage, "");
+        assert!(s.contains("    vec2 p_c = p;\n    float sdf_a;\n"));
+        assert!(s.contains("        float sdf_result = sdf_circle(p, 0.100000);\n"));
+        assert!(s.ends_with("    sdf_result = max(sdf_a, -sdf_b); }\n"));
+    }
+
+    #[test]
+    fn smooth_union_and_morph_lower_their_parameter() {
+        let stage = first_stage("smooth_union(circle(), box(), k: 0.2)");
+        let mut s = String::new();
+        emit_wgsl(&mut s, &stage, "");
+        assert!(s.contains("let su_k = max(0.200000, 0.0001);"));
+        assert!(s.contains("sdf_result = mix(sdf_b, sdf_a, su_h) - su_k * su_h * (1.0 - su_h); }"));
+
+        let stage = first_stage("morph(circle(), box(), sin(time) * 0.5 + 0.5)");
+        let mut s = String::new();
+        emit_glsl(&mut s, &stage, "");
+        assert!(s.contains("sdf_result = mix(sdf_a, sdf_b, ((sin(time) * 0.5) + 0.5)); }"), "{s}");
+    }
+
+    #[test]
+    fn nested_combinators_shadow_in_their_own_block() {
+        let stage = first_stage("intersect(union(circle(), ring()), box())");
+        let mut s = String::new();
+        emit_wgsl(&mut s, &stage, "");
+        assert!(s.contains("        var sdf_result: f32;\n        { // union\n"));
+        assert!(s.contains("            let p_c = p;\n"));
+        assert!(s.contains("                var sdf_result = abs(length(p) - 0.300000) - 0.020000;"));
+        assert!(s.ends_with("    sdf_result = max(sdf_a, sdf_b); }\n"));
+    }
+}
@@ -1,39 +1,15 @@
-//! Expression compilation to WGSL and JavaScript.
+//! Expression compilation to WGSL, GLSL and JavaScript.
 //!
-//! Provides two compilation targets for GAME expressions:
+//! Provides three compilation targets for GAME expressions:
 //! - WGSL for GPU shader code (fragment/compute shaders)
+//! - GLSL ES 3.0 for the WebGL2 fallback renderer
 //! - JavaScript for runtime modulation (audio, mouse, data binding)
 //!
 //! Also includes signal detection utilities (`uses_audio`, `uses_mouse`, etc.)
 //! used by the analysis module and runtime emitters.
 
 use crate::ast::*;
-
-/// Map a color name to its WGSL `vec3f` representation.
-pub fn resolve_color(name: &str) -> Option<&'static str> {
-    match name {
-        "black" => Some("vec3f(0.0, 0.0, 0.0)"),
-        "white" => Some("vec3f(1.0, 1.0, 1.0)"),
-        "red" => Some("vec3f(1.0, 0.0, 0.0)"),
-        "green" => Some("vec3f(0.0, 1.0, 0.0)"),
-        "blue" => Some("vec3f(0.0, 0.0, 1.0)"),
-        "gold" => Some("vec3f(0.831, 0.686, 0.216)"),
-        "midnight" => Some("vec3f(0.039, 0.039, 0.118)"),
-        "obsidian" => Some("vec3f(0.071, 0.059, 0.082)"),
-        "ember" => Some("vec3f(0.898, 0.318, 0.129)"),
-        "cyan" => Some("vec3f(0.0, 1.0, 1.0)"),
-        "ivory" => Some("vec3f(1.0, 1.0, 0.941)"),
-        "frost" => Some("vec3f(0.686, 0.878, 0.953)"),
-        "orange" => Some("vec3f(1.0, 0.647, 0.0)"),
-        "deep_blue" => Some("vec3f(0.0, 0.098, 0.392)"),
-        "ash" => Some("vec3f(0.467, 0.467, 0.467)"),
-        "charcoal" => Some("vec3f(0.212, 0.212, 0.212)"),
-        "plasma" => Some("vec3f(0.580, 0.0, 0.827)"),
-        "violet" => Some("vec3f(0.541, 0.169, 0.886)"),
-        "magenta" => Some("vec3f(1.0, 0.0, 1.0)"),
-        _ => None,
-    }
-}
+use crate::codegen::color::{self, Backend};
 
 /// Map a constant name to its numeric string value.
 pub fn resolve_constant(name: &str) -> Option<&'static str> {
@@ -49,26 +25,26 @@ pub fn resolve_constant(name: &str) -> Option<&'static str> {
 /// Compile an expression to WGSL shader code.
 pub fn compile_wgsl(expr: &Expr) -> String {
     match expr {
-        Expr::Number(v) => {
+        Expr::Number(v, _) => {
             if *v == (*v as i64) as f64 {
                 format!("{v:.1}")
             } else {
                 format!("{v}")
             }
         }
-        Expr::String(s) => format!("\"{s}\""),
-        Expr::Ident(name) => {
-            if let Some(color) = resolve_color(name) {
-                color.to_string()
+        Expr::String(s, _) => format!("\"{s}\""),
+        Expr::Ident(name, _) => {
+            if let Some(rgb) = color::named(name) {
+                compile_wgsl(&color::to_expr(rgb))
             } else if let Some(constant) = resolve_constant(name) {
                 constant.to_string()
             } else {
                 name.clone()
             }
         }
-        Expr::DottedIdent { object, field } => format!("{object}.{field}"),
-        Expr::Array(elems) => {
-            let compiled: Vec<String> = elems.iter().map(compile_wgsl).collect();
+        Expr::DottedIdent { object, field, .. } => format!("{object}.{field}"),
+        Expr::Array(elems, _) => {
+            let compiled: Vec<String> = elems.iter().map(value_wgsl).collect();
             match compiled.len() {
                 2 => format!("vec2f({}, {})", compiled[0], compiled[1]),
                 3 => format!("vec3f({}, {}, {})", compiled[0], compiled[1], compiled[2]),
@@ -79,24 +55,19 @@ pub fn compile_wgsl(expr: &Expr) -> String {
                 _ => format!("vec{}f({})", compiled.len(), compiled.join(", ")),
             }
         }
-        Expr::Paren(inner) => format!("({})", compile_wgsl(inner)),
-        Expr::Neg(inner) => format!("(-{})", compile_wgsl(inner)),
-        Expr::BinOp { op, left, right } => {
-            let l = compile_wgsl(left);
-            let r = compile_wgsl(right);
-            match op {
-                BinOp::Add => format!("({l} + {r})"),
-                BinOp::Sub => format!("({l} - {r})"),
-                BinOp::Mul => format!("({l} * {r})"),
-                BinOp::Div => format!("({l} / {r})"),
-                BinOp::Pow => format!("pow({l}, {r})"),
-                BinOp::Gt => format!("({l} > {r})"),
-                BinOp::Lt => format!("({l} < {r})"),
-            }
+        Expr::Paren(inner, _) => format!("({})", compile_wgsl(inner)),
+        Expr::Neg(inner, _) => format!("(-{})", value_wgsl(inner)),
+        Expr::Not(inner, _) => format!("(!{})", condition(inner, compile_wgsl)),
+        Expr::BinOp { op, left, right, .. } => match op {
+            BinOp::Pow => format!("pow({}, {})", value_wgsl(left), value_wgsl(right)),
+            _ => shader_binop(op, left, right, compile_wgsl, value_wgsl),
+        },
+        Expr::Call { name, args, .. } if color::is_constructor(name) => {
+            color::compile_call(name, args, value_wgsl, Backend::Wgsl)
         }
-        Expr::Call { name, args } => {
+        Expr::Call { name, args, .. } => {
             let compiled_args: Vec<String> =
-                args.iter().map(|a| compile_wgsl(&a.value)).collect();
+                args.iter().map(|a| value_wgsl(&a.value)).collect();
             let args_str = compiled_args.join(", ");
             match name.as_str() {
                 "mod" => {
@@ -117,7 +88,7 @@ pub fn compile_wgsl(expr: &Expr) -> String {
                 _ => format!("{name}({args_str})"),
             }
         }
-        Expr::Duration(dur) => {
+        Expr::Duration(dur, _) => {
             let secs = match dur {
                 Duration::Seconds(s) => *s,
                 Duration::Millis(ms) => ms / 1000.0,
@@ -133,86 +104,215 @@ pub fn compile_wgsl(expr: &Expr) -> String {
             condition,
             if_true,
             if_false,
+            ..
         } => {
             // WGSL uses select(false_val, true_val, condition)
-            let cond = compile_wgsl(condition);
-            let t = compile_wgsl(if_true);
-            let f = compile_wgsl(if_false);
+            let cond = self::condition(condition, compile_wgsl);
+            let t = value_wgsl(if_true);
+            let f = value_wgsl(if_false);
             format!("select({f}, {t}, {cond})")
         }
+        // Only combinator stages take pipelines, and they never lower them
+        // as values (see `stages::validate_pipeline`)
+        Expr::Pipeline(_, _) => "0.0".to_string(),
+    }
+}
+
+/// The infix spelling of an operator; `Pow` has none and is spelled `^`.
+pub(crate) fn op_symbol(op: &BinOp) -> &'static str {
+    match op {
+        BinOp::Add => "+",
+        BinOp::Sub => "-",
+        BinOp::Mul => "*",
+        BinOp::Div => "/",
+        BinOp::Mod => "%",
+        BinOp::Pow => "^",
+        BinOp::Gt => ">",
+        BinOp::Lt => "<",
+        BinOp::Ge => ">=",
+        BinOp::Le => "<=",
+        BinOp::Eq => "==",
+        BinOp::Ne => "!=",
+        BinOp::And => "&&",
+        BinOp::Or => "||",
+    }
+}
+
+/// Lower an infix operator that WGSL and GLSL spell the same way.
+fn shader_binop(
+    op: &BinOp,
+    left: &Expr,
+    right: &Expr,
+    compile: fn(&Expr) -> String,
+    value: fn(&Expr) -> String,
+) -> String {
+    let (l, r) = match op {
+        BinOp::And | BinOp::Or => (condition(left, compile), condition(right, compile)),
+        _ => (value(left), value(right)),
+    };
+    format!("({l} {} {r})", op_symbol(op))
+}
+
+/// Compile an expression used as a number to WGSL. Comparisons and logic
+/// are `bool`s in shaders, so they become `1.0`/`0.0`, the value they
+/// have in JS and in constant folding.
+pub fn value_wgsl(expr: &Expr) -> String {
+    if is_boolean(expr) {
+        format!("f32({})", compile_wgsl(expr))
+    } else {
+        compile_wgsl(expr)
+    }
+}
+
+/// [`value_wgsl`] for GLSL.
+pub fn value_glsl(expr: &Expr) -> String {
+    if is_boolean(expr) {
+        format!("float({})", compile_glsl(expr))
+    } else {
+        compile_glsl(expr)
+    }
+}
+
+/// An operand of `&&`, `||`, `!` or a ternary condition: comparisons are
+/// already `bool` in shaders, anything else counts as true when nonzero.
+fn condition(expr: &Expr, compile: fn(&Expr) -> String) -> String {
+    if is_boolean(expr) {
+        compile(expr)
+    } else {
+        format!("({} != 0.0)", compile(expr))
+    }
+}
+
+/// Whether an expression is a shader `bool`: a comparison or logic.
+pub fn is_boolean(expr: &Expr) -> bool {
+    match expr {
+        Expr::BinOp { op, .. } => op.is_boolean(),
+        Expr::Not(_, _) => true,
+        Expr::Paren(inner, _) => is_boolean(inner),
+        _ => false,
+    }
+}
+
+/// Compile an expression to GLSL ES 3.0 shader code.
+///
+/// Mirrors [`compile_wgsl`], differing only where the two languages do:
+/// `vecN` constructors, float `mod()`, `atan(y, x)` and C-style ternaries.
+pub fn compile_glsl(expr: &Expr) -> String {
+    match expr {
+        Expr::Ident(name, _) => {
+            if let Some(rgb) = color::named(name) {
+                compile_glsl(&color::to_expr(rgb))
+            } else if let Some(constant) = resolve_constant(name) {
+                constant.to_string()
+            } else {
+                name.clone()
+            }
+        }
+        Expr::Array(elems, _) => {
+            let compiled: Vec<String> = elems.iter().map(value_glsl).collect();
+            format!("vec{}({})", compiled.len(), compiled.join(", "))
+        }
+        Expr::Paren(inner, _) => format!("({})", compile_glsl(inner)),
+        Expr::Neg(inner, _) => format!("(-{})", value_glsl(inner)),
+        Expr::Not(inner, _) => format!("(!{})", condition(inner, compile_glsl)),
+        Expr::BinOp { op, left, right, .. } => match op {
+            BinOp::Pow => format!("pow({}, {})", value_glsl(left), value_glsl(right)),
+            // GLSL `%` is integer-only
+            BinOp::Mod => {
+                let (l, r) = (value_glsl(left), value_glsl(right));
+                format!("({l} - {r} * trunc({l} / {r}))")
+            }
+            _ => shader_binop(op, left, right, compile_glsl, value_glsl),
+        },
+        Expr::Call { name, args, .. } if color::is_constructor(name) => {
+            color::compile_call(name, args, value_glsl, Backend::Glsl)
+        }
+        Expr::Call { name, args, .. } => {
+            let compiled_args: Vec<String> =
+                args.iter().map(|a| value_glsl(&a.value)).collect();
+            let args_str = compiled_args.join(", ");
+            match name.as_str() {
+                // GLSL `%` is integer-only; floats need mod()
+                "mod" => {
+                    if compiled_args.len() == 2 {
+                        format!("mod({}, {})", compiled_args[0], compiled_args[1])
+                    } else {
+                        format!("mod({args_str}, 1.0)")
+                    }
+                }
+                "atan2" => format!("atan({args_str})"),
+                _ => format!("{name}({args_str})"),
+            }
+        }
+        Expr::Ternary {
+            condition,
+            if_true,
+            if_false,
+            ..
+        } => {
+            let cond = self::condition(condition, compile_glsl);
+            let t = value_glsl(if_true);
+            let f = value_glsl(if_false);
+            format!("({cond} ? {t} : {f})")
+        }
+        // Literals, strings, dotted idents and durations are spelled identically
+        Expr::Number(_, _)
+        | Expr::String(_, _)
+        | Expr::DottedIdent { .. }
+        | Expr::Duration(_, _)
+        | Expr::Pipeline(_, _) => compile_wgsl(expr),
     }
 }
 
 /// Compile an expression to JavaScript (for runtime modulation).
 pub fn compile_js(expr: &Expr) -> String {
     match expr {
-        Expr::Number(v) => {
+        Expr::Number(v, _) => {
             if *v == (*v as i64) as f64 {
                 format!("{v:.1}")
             } else {
                 format!("{v}")
             }
         }
-        Expr::String(s) => format!("\"{s}\""),
-        Expr::Ident(name) => {
+        Expr::String(s, _) => format!("\"{s}\""),
+        Expr::Ident(name, _) => {
             if name == "time" {
                 "time".to_string()
-            } else if let Some(_color) = resolve_color(name) {
-                // Return color as JS array [r, g, b]
-                match name.as_str() {
-                    "black" => "[0, 0, 0]".to_string(),
-                    "white" => "[1, 1, 1]".to_string(),
-                    "red" => "[1, 0, 0]".to_string(),
-                    "green" => "[0, 1, 0]".to_string(),
-                    "blue" => "[0, 0, 1]".to_string(),
-                    "gold" => "[0.831, 0.686, 0.216]".to_string(),
-                    "midnight" => "[0.039, 0.039, 0.118]".to_string(),
-                    "obsidian" => "[0.071, 0.059, 0.082]".to_string(),
-                    "ember" => "[0.898, 0.318, 0.129]".to_string(),
-                    "cyan" => "[0, 1, 1]".to_string(),
-                    "ivory" => "[1, 1, 0.941]".to_string(),
-                    "frost" => "[0.686, 0.878, 0.953]".to_string(),
-                    "orange" => "[1, 0.647, 0]".to_string(),
-                    "deep_blue" => "[0, 0.098, 0.392]".to_string(),
-                    "ash" => "[0.467, 0.467, 0.467]".to_string(),
-                    "charcoal" => "[0.212, 0.212, 0.212]".to_string(),
-                    "plasma" => "[0.580, 0, 0.827]".to_string(),
-                    "violet" => "[0.541, 0.169, 0.886]".to_string(),
-                    "magenta" => "[1, 0, 1]".to_string(),
-                    _ => name.clone(),
-                }
+            } else if let Some(rgb) = color::named(name) {
+                compile_js(&color::to_expr(rgb))
             } else if let Some(constant) = resolve_constant(name) {
                 constant.to_string()
             } else {
                 name.clone()
             }
         }
-        Expr::DottedIdent { object, field } => match object.as_str() {
+        Expr::DottedIdent { object, field, .. } => match object.as_str() {
             "audio" => format!("audio{}", capitalize(field)),
             "mouse" => format!("mouse{}", capitalize(field)),
             "data" => format!("data_{field}"),
             _ => format!("{object}_{field}"),
         },
-        Expr::Array(elems) => {
+        Expr::Array(elems, _) => {
             let compiled: Vec<String> = elems.iter().map(compile_js).collect();
             format!("[{}]", compiled.join(", "))
         }
-        Expr::Paren(inner) => format!("({})", compile_js(inner)),
-        Expr::Neg(inner) => format!("(-{})", compile_js(inner)),
-        Expr::BinOp { op, left, right } => {
+        Expr::Paren(inner, _) => format!("({})", compile_js(inner)),
+        Expr::Neg(inner, _) => format!("(-{})", compile_js(inner)),
+        Expr::Not(inner, _) => format!("(!{})", compile_js(inner)),
+        Expr::BinOp { op, left, right, .. } => {
             let l = compile_js(left);
             let r = compile_js(right);
             match op {
-                BinOp::Add => format!("({l} + {r})"),
-                BinOp::Sub => format!("({l} - {r})"),
-                BinOp::Mul => format!("({l} * {r})"),
-                BinOp::Div => format!("({l} / {r})"),
                 BinOp::Pow => format!("({l} ** {r})"),
-                BinOp::Gt => format!("({l} > {r})"),
-                BinOp::Lt => format!("({l} < {r})"),
+                BinOp::Eq => format!("({l} === {r})"),
+                BinOp::Ne => format!("({l} !== {r})"),
+                _ => format!("({l} {} {r})", op_symbol(op)),
             }
         }
-        Expr::Call { name, args } => {
+        Expr::Call { name, args, .. } if color::is_constructor(name) => {
+            color::compile_call(name, args, compile_js, Backend::Js)
+        }
+        Expr::Call { name, args, .. } => {
             let compiled_args: Vec<String> =
                 args.iter().map(|a| compile_js(&a.value)).collect();
             let args_str = compiled_args.join(", ");
@@ -273,7 +373,7 @@ pub fn compile_js(expr: &Expr) -> String {
                 _ => format!("{name}({args_str})"),
             }
         }
-        Expr::Duration(dur) => {
+        Expr::Duration(dur, _) => {
             let secs = match dur {
                 Duration::Seconds(s) => *s,
                 Duration::Millis(ms) => ms / 1000.0,
@@ -289,17 +389,19 @@ pub fn compile_js(expr: &Expr) -> String {
             condition,
             if_true,
             if_false,
+            ..
         } => {
             let cond = compile_js(condition);
             let t = compile_js(if_true);
             let f = compile_js(if_false);
             format!("({cond} ? {t} : {f})")
         }
+        Expr::Pipeline(_, _) => "0".to_string(),
     }
 }
 
 /// Capitalize the first letter of a string (for camelCase JS names).
-fn capitalize(s: &str) -> String {
+pub(crate) fn capitalize(s: &str) -> String {
     let mut chars = s.chars();
     match chars.next() {
         None => String::new(),
@@ -311,15 +413,19 @@ fn capitalize(s: &str) -> String {
 pub fn uses_audio(expr: &Expr) -> bool {
     match expr {
         Expr::DottedIdent { object, .. } => object == "audio",
-        Expr::Paren(inner) | Expr::Neg(inner) => uses_audio(inner),
+        Expr::Paren(inner, _) | Expr::Neg(inner, _) | Expr::Not(inner, _) => uses_audio(inner),
         Expr::BinOp { left, right, .. } => uses_audio(left) || uses_audio(right),
         Expr::Call { args, .. } => args.iter().any(|a| uses_audio(&a.value)),
-        Expr::Array(elems) => elems.iter().any(uses_audio),
+        Expr::Array(elems, _) => elems.iter().any(uses_audio),
         Expr::Ternary {
             condition,
             if_true,
             if_false,
+            ..
         } => uses_audio(condition) || uses_audio(if_true) || uses_audio(if_false),
+        Expr::Pipeline(stages, _) => {
+            stages.iter().flat_map(|s| &s.args).any(|a| uses_audio(&a.value))
+        }
         _ => false,
     }
 }
@@ -328,15 +434,19 @@ pub fn uses_audio(expr: &Expr) -> bool {
 pub fn uses_mouse(expr: &Expr) -> bool {
     match expr {
         Expr::DottedIdent { object, .. } => object == "mouse",
-        Expr::Paren(inner) | Expr::Neg(inner) => uses_mouse(inner),
+        Expr::Paren(inner, _) | Expr::Neg(inner, _) | Expr::Not(inner, _) => uses_mouse(inner),
         Expr::BinOp { left, right, .. } => uses_mouse(left) || uses_mouse(right),
         Expr::Call { args, .. } => args.iter().any(|a| uses_mouse(&a.value)),
-        Expr::Array(elems) => elems.iter().any(uses_mouse),
+        Expr::Array(elems, _) => elems.iter().any(uses_mouse),
         Expr::Ternary {
             condition,
             if_true,
             if_false,
+            ..
         } => uses_mouse(condition) || uses_mouse(if_true) || uses_mouse(if_false),
+        Expr::Pipeline(stages, _) => {
+            stages.iter().flat_map(|s| &s.args).any(|a| uses_mouse(&a.value))
+        }
         _ => false,
     }
 }
@@ -345,50 +455,89 @@ pub fn uses_mouse(expr: &Expr) -> bool {
 pub fn uses_data(expr: &Expr) -> bool {
     match expr {
         Expr::DottedIdent { object, .. } => object == "data",
-        Expr::Paren(inner) | Expr::Neg(inner) => uses_data(inner),
+        Expr::Paren(inner, _) | Expr::Neg(inner, _) | Expr::Not(inner, _) => uses_data(inner),
         Expr::BinOp { left, right, .. } => uses_data(left) || uses_data(right),
         Expr::Call { args, .. } => args.iter().any(|a| uses_data(&a.value)),
-        Expr::Array(elems) => elems.iter().any(uses_data),
+        Expr::Array(elems, _) => elems.iter().any(uses_data),
         Expr::Ternary {
             condition,
             if_true,
             if_false,
+            ..
         } => uses_data(condition) || uses_data(if_true) || uses_data(if_false),
+        Expr::Pipeline(stages, _) => {
+            stages.iter().flat_map(|s| &s.args).any(|a| uses_data(&a.value))
+        }
+        _ => false,
+    }
+}
+
+/// Check if an expression references `time`.
+pub fn uses_time(expr: &Expr) -> bool {
+    match expr {
+        Expr::Ident(name, _) => name == "time",
+        Expr::Paren(inner, _) | Expr::Neg(inner, _) | Expr::Not(inner, _) => uses_time(inner),
+        Expr::BinOp { left, right, .. } => uses_time(left) || uses_time(right),
+        Expr::Call { args, .. } => args.iter().any(|a| uses_time(&a.value)),
+        Expr::Array(elems, _) => elems.iter().any(uses_time),
+        Expr::Ternary {
+            condition,
+            if_true,
+            if_false,
+            ..
+        } => uses_time(condition) || uses_time(if_true) || uses_time(if_false),
+        Expr::Pipeline(stages, _) => {
+            stages.iter().flat_map(|s| &s.args).any(|a| uses_time(&a.value))
+        }
         _ => false,
     }
 }
 
 /// Collect `data.*` field names from an expression into `fields`.
 pub fn collect_data_fields(expr: &Expr, fields: &mut Vec<String>) {
+    collect_signal_fields(expr, "data", fields);
+}
+
+/// Collect the fields of every `<object>.*` reference in an expression into
+/// `fields`, in first-use order.
+pub fn collect_signal_fields(expr: &Expr, object_name: &str, fields: &mut Vec<String>) {
     match expr {
-        Expr::DottedIdent { object, field } if object == "data" => {
-            if !fields.contains(field) {
-                fields.push(field.clone());
-            }
+        Expr::DottedIdent { object, field, .. }
+            if object == object_name && !fields.contains(field) =>
+        {
+            fields.push(field.clone());
+        }
+        Expr::Paren(inner, _) | Expr::Neg(inner, _) | Expr::Not(inner, _) => {
+            collect_signal_fields(inner, object_name, fields)
         }
-        Expr::Paren(inner) | Expr::Neg(inner) => collect_data_fields(inner, fields),
         Expr::BinOp { left, right, .. } => {
-            collect_data_fields(left, fields);
-            collect_data_fields(right, fields);
+            collect_signal_fields(left, object_name, fields);
+            collect_signal_fields(right, object_name, fields);
         }
         Expr::Call { args, .. } => {
             for arg in args {
-                collect_data_fields(&arg.value, fields);
+                collect_signal_fields(&arg.value, object_name, fields);
             }
         }
-        Expr::Array(elems) => {
+        Expr::Array(elems, _) => {
             for elem in elems {
-                collect_data_fields(elem, fields);
+                collect_signal_fields(elem, object_name, fields);
             }
         }
         Expr::Ternary {
             condition,
             if_true,
             if_false,
+            ..
         } => {
-            collect_data_fields(condition, fields);
-            collect_data_fields(if_true, fields);
-            collect_data_fields(if_false, fields);
+            collect_signal_fields(condition, object_name, fields);
+            collect_signal_fields(if_true, object_name, fields);
+            collect_signal_fields(if_false, object_name, fields);
+        }
+        Expr::Pipeline(stages, _) => {
+            for arg in stages.iter().flat_map(|s| &s.args) {
+                collect_signal_fields(&arg.value, object_name, fields);
+            }
         }
         _ => {}
     }
@@ -397,9 +546,9 @@ pub fn collect_data_fields(expr: &Expr, fields: &mut Vec<String>) {
 /// Extract a numeric value from an expression (literal or negated literal).
 pub fn extract_number(expr: &Expr) -> Option<f64> {
     match expr {
-        Expr::Number(v) => Some(*v),
-        Expr::Neg(inner) => extract_number(inner).map(|v| -v),
-        Expr::Paren(inner) => extract_number(inner),
+        Expr::Number(v, _) => Some(*v),
+        Expr::Neg(inner, _) => extract_number(inner).map(|v| -v),
+        Expr::Paren(inner, _) => extract_number(inner),
         _ => None,
     }
 }
@@ -414,18 +563,19 @@ mod tests {
 
     #[test]
     fn wgsl_number_integer_gets_decimal() {
-        assert_eq!(compile_wgsl(&Expr::Number(5.0)), "5.0");
+        assert_eq!(compile_wgsl(&Expr::Number(5.0, Span::default())), "5.0");
     }
 
     #[test]
+    #[allow(clippy::approx_constant)]
     fn wgsl_number_float_preserved() {
-        assert_eq!(compile_wgsl(&Expr::Number(3.14)), "3.14");
+        assert_eq!(compile_wgsl(&Expr::Number(3.14, Span::default())), "3.14");
     }
 
     #[test]
     fn wgsl_ident_color_resolved() {
         assert_eq!(
-            compile_wgsl(&Expr::Ident("gold".into())),
+            compile_wgsl(&Expr::Ident("gold".into(), Span::default())),
             "vec3f(0.831, 0.686, 0.216)"
         );
     }
@@ -433,22 +583,23 @@ mod tests {
     #[test]
     fn wgsl_ident_constant_resolved() {
         assert_eq!(
-            compile_wgsl(&Expr::Ident("pi".into())),
+            compile_wgsl(&Expr::Ident("pi".into(), Span::default())),
             "3.14159265358979"
         );
     }
 
     #[test]
     fn wgsl_ident_passthrough() {
-        assert_eq!(compile_wgsl(&Expr::Ident("intensity".into())), "intensity");
+        assert_eq!(compile_wgsl(&Expr::Ident("intensity".into(), Span::default())), "intensity");
     }
 
     #[test]
     fn wgsl_binop_add() {
         let expr = Expr::BinOp {
             op: BinOp::Add,
-            left: Box::new(Expr::Number(1.0)),
-            right: Box::new(Expr::Number(2.0)),
+            left: Box::new(Expr::Number(1.0, Span::default())),
+            right: Box::new(Expr::Number(2.0, Span::default())),
+            span: Span::default(),
         };
         assert_eq!(compile_wgsl(&expr), "(1.0 + 2.0)");
     }
@@ -457,8 +608,9 @@ mod tests {
     fn wgsl_binop_pow_uses_pow_fn() {
         let expr = Expr::BinOp {
             op: BinOp::Pow,
-            left: Box::new(Expr::Ident("x".into())),
-            right: Box::new(Expr::Number(2.0)),
+            left: Box::new(Expr::Ident("x".into(), Span::default())),
+            right: Box::new(Expr::Number(2.0, Span::default())),
+            span: Span::default(),
         };
         assert_eq!(compile_wgsl(&expr), "pow(x, 2.0)");
     }
@@ -466,11 +618,12 @@ mod tests {
     #[test]
     fn wgsl_ternary_uses_select() {
         let expr = Expr::Ternary {
-            condition: Box::new(Expr::Ident("cond".into())),
-            if_true: Box::new(Expr::Number(1.0)),
-            if_false: Box::new(Expr::Number(0.0)),
+            condition: Box::new(Expr::Ident("cond".into(), Span::default())),
+            if_true: Box::new(Expr::Number(1.0, Span::default())),
+            if_false: Box::new(Expr::Number(0.0, Span::default())),
+            span: Span::default(),
         };
-        assert_eq!(compile_wgsl(&expr), "select(0.0, 1.0, cond)");
+        assert_eq!(compile_wgsl(&expr), "select(0.0, 1.0, (cond != 0.0))");
     }
 
     #[test]
@@ -479,8 +632,10 @@ mod tests {
             name: "sin".into(),
             args: vec![Arg {
                 name: None,
-                value: Expr::Ident("time".into()),
+                value: Expr::Ident("time".into(), Span::default()),
+                span: Span::default(),
             }],
+            span: Span::default(),
         };
         assert_eq!(compile_wgsl(&expr), "sin(time)");
     }
@@ -490,9 +645,18 @@ mod tests {
         let expr = Expr::Call {
             name: "mod".into(),
             args: vec![
-                Arg { name: None, value: Expr::Ident("x".into()) },
-                Arg { name: None, value: Expr::Number(1.0) },
+                Arg {
+                    name: None,
+                    value: Expr::Ident("x".into(), Span::default()),
+                    span: Span::default(),
+                },
+                Arg {
+                    name: None,
+                    value: Expr::Number(1.0, Span::default()),
+                    span: Span::default(),
+                },
             ],
+            span: Span::default(),
         };
         assert_eq!(compile_wgsl(&expr), "(x % 1.0)");
     }
@@ -500,16 +664,16 @@ mod tests {
     #[test]
     fn wgsl_array_vec3() {
         let expr = Expr::Array(vec![
-            Expr::Number(1.0),
-            Expr::Number(0.5),
-            Expr::Number(0.0),
-        ]);
+            Expr::Number(1.0, Span::default()),
+            Expr::Number(0.5, Span::default()),
+            Expr::Number(0.0, Span::default()),
+        ], Span::default());
         assert_eq!(compile_wgsl(&expr), "vec3f(1.0, 0.5, 0.0)");
     }
 
     #[test]
     fn wgsl_neg() {
-        let expr = Expr::Neg(Box::new(Expr::Number(3.0)));
+        let expr = Expr::Neg(Box::new(Expr::Number(3.0, Span::default())), Span::default());
         assert_eq!(compile_wgsl(&expr), "(-3.0)");
     }
 
@@ -518,50 +682,161 @@ mod tests {
         let expr = Expr::DottedIdent {
             object: "audio".into(),
             field: "bass".into(),
+            span: Span::default(),
         };
         assert_eq!(compile_wgsl(&expr), "audio.bass");
     }
 
     #[test]
     fn wgsl_duration_seconds() {
-        let expr = Expr::Duration(Duration::Seconds(2.5));
+        let expr = Expr::Duration(Duration::Seconds(2.5), Span::default());
         assert_eq!(compile_wgsl(&expr), "2.5");
     }
 
     #[test]
     fn wgsl_duration_millis() {
-        let expr = Expr::Duration(Duration::Millis(500.0));
+        let expr = Expr::Duration(Duration::Millis(500.0), Span::default());
         assert_eq!(compile_wgsl(&expr), "0.5");
     }
 
     #[test]
     fn wgsl_paren() {
-        let expr = Expr::Paren(Box::new(Expr::Number(42.0)));
+        let expr = Expr::Paren(Box::new(Expr::Number(42.0, Span::default())), Span::default());
         assert_eq!(compile_wgsl(&expr), "(42.0)");
     }
 
     #[test]
     fn wgsl_string() {
-        assert_eq!(compile_wgsl(&Expr::String("hello".into())), "\"hello\"");
+        assert_eq!(compile_wgsl(&Expr::String("hello".into(), Span::default())), "\"hello\"");
     }
 
     #[test]
     fn wgsl_gt_lt() {
         let gt = Expr::BinOp {
             op: BinOp::Gt,
-            left: Box::new(Expr::Ident("x".into())),
-            right: Box::new(Expr::Number(0.0)),
+            left: Box::new(Expr::Ident("x".into(), Span::default())),
+            right: Box::new(Expr::Number(0.0, Span::default())),
+            span: Span::default(),
         };
         assert_eq!(compile_wgsl(&gt), "(x > 0.0)");
 
         let lt = Expr::BinOp {
             op: BinOp::Lt,
-            left: Box::new(Expr::Ident("y".into())),
-            right: Box::new(Expr::Number(1.0)),
+            left: Box::new(Expr::Ident("y".into(), Span::default())),
+            right: Box::new(Expr::Number(1.0, Span::default())),
+            span: Span::default(),
         };
         assert_eq!(compile_wgsl(&lt), "(y < 1.0)");
     }
 
+    fn parse(src: &str) -> Expr {
+        let tokens = crate::lexer::lex(src).unwrap();
+        crate::parser::Parser::new(tokens).parse_expr().unwrap()
+    }
+
+    #[test]
+    fn comparison_and_logic_per_backend() {
+        let e = parse("x >= 1 && !(y == 2) || flag");
+        assert_eq!(
+            compile_wgsl(&e),
+            "(((x >= 1.0) && (!((y == 2.0)))) || (flag != 0.0))"
+        );
+        assert_eq!(
+            compile_glsl(&e),
+            "(((x >= 1.0) && (!((y == 2.0)))) || (flag != 0.0))"
+        );
+        assert_eq!(compile_js(&e), "(((x >= 1.0) && (!((y === 2.0)))) || flag)");
+        assert_eq!(compile_js(&parse("a != b")), "(a !== b)");
+        assert_eq!(compile_wgsl(&parse("x <= 0.5 ? 1.0 : 0.0")), "select(0.0, 1.0, (x <= 0.5))");
+    }
+
+    #[test]
+    fn comparisons_used_as_numbers_become_floats() {
+        let e = parse("0.2 + (time >= 1.0) * 0.1");
+        assert_eq!(compile_wgsl(&e), "(0.2 + (f32(((time >= 1.0))) * 0.1))");
+        assert_eq!(compile_glsl(&e), "(0.2 + (float(((time >= 1.0))) * 0.1))");
+        assert_eq!(value_wgsl(&parse("x > 0.5")), "f32((x > 0.5))");
+        assert_eq!(value_glsl(&parse("!flag")), "float((!(flag != 0.0)))");
+        // Still `bool` where a condition is expected
+        assert_eq!(compile_wgsl(&parse("(x > 0.5) && y")), "(((x > 0.5)) && (y != 0.0))");
+    }
+
+    #[test]
+    fn mod_operator_truncates_on_every_backend() {
+        let e = parse("time % 2");
+        assert_eq!(compile_wgsl(&e), "(time % 2.0)");
+        assert_eq!(compile_glsl(&e), "(time - 2.0 * trunc(time / 2.0))");
+        assert_eq!(compile_js(&e), "(time % 2.0)");
+    }
+
+    // ── GLSL compilation ─────────────────────────────────
+
+    #[test]
+    fn glsl_ternary_uses_question_mark() {
+        let expr = Expr::Ternary {
+            condition: Box::new(Expr::Ident("cond".into(), Span::default())),
+            if_true: Box::new(Expr::Number(1.0, Span::default())),
+            if_false: Box::new(Expr::Number(0.0, Span::default())),
+            span: Span::default(),
+        };
+        assert_eq!(compile_glsl(&expr), "((cond != 0.0) ? 1.0 : 0.0)");
+    }
+
+    #[test]
+    fn glsl_call_mod_uses_mod_fn() {
+        let expr = Expr::Call {
+            name: "mod".into(),
+            args: vec![
+                Arg {
+                    name: None,
+                    value: Expr::Ident("x".into(), Span::default()),
+                    span: Span::default(),
+                },
+                Arg {
+                    name: None,
+                    value: Expr::Number(1.0, Span::default()),
+                    span: Span::default(),
+                },
+            ],
+            span: Span::default(),
+        };
+        assert_eq!(compile_glsl(&expr), "mod(x, 1.0)");
+    }
+
+    #[test]
+    fn glsl_call_atan2_becomes_atan() {
+        let expr = Expr::Call {
+            name: "atan2".into(),
+            args: vec![
+                Arg {
+                    name: None,
+                    value: Expr::Ident("y".into(), Span::default()),
+                    span: Span::default(),
+                },
+                Arg {
+                    name: None,
+                    value: Expr::Ident("x".into(), Span::default()),
+                    span: Span::default(),
+                },
+            ],
+            span: Span::default(),
+        };
+        assert_eq!(compile_glsl(&expr), "atan(y, x)");
+    }
+
+    #[test]
+    fn glsl_array_and_color_use_plain_vec() {
+        let expr = Expr::Array(
+            vec![Expr::Number(1.0, Span::default()), Expr::Number(0.5, Span::default())],
+            Span::default(),
+        );
+        assert_eq!(compile_glsl(&expr), "vec2(1.0, 0.5)");
+        assert_eq!(
+            compile_glsl(&Expr::Ident("gold".into(), Span::default())),
+            "vec3(0.831, 0.686, 0.216)"
+        );
+    }
+
     // ── JS compilation ───────────────────────────────────
 
     #[test]
@@ -569,6 +844,7 @@ mod tests {
         let expr = Expr::DottedIdent {
             object: "audio".into(),
             field: "bass".into(),
+            span: Span::default(),
         };
         assert_eq!(compile_js(&expr), "audioBass");
     }
@@ -578,6 +854,7 @@ mod tests {
         let expr = Expr::DottedIdent {
             object: "mouse".into(),
             field: "x".into(),
+            span: Span::default(),
         };
         assert_eq!(compile_js(&expr), "mouseX");
     }
@@ -587,6 +864,7 @@ mod tests {
         let expr = Expr::DottedIdent {
             object: "data".into(),
             field: "temperature".into(),
+            span: Span::default(),
         };
         assert_eq!(compile_js(&expr), "data_temperature");
     }
@@ -595,8 +873,9 @@ mod tests {
     fn js_pow_uses_double_star() {
         let expr = Expr::BinOp {
             op: BinOp::Pow,
-            left: Box::new(Expr::Ident("x".into())),
-            right: Box::new(Expr::Number(2.0)),
+            left: Box::new(Expr::Ident("x".into(), Span::default())),
+            right: Box::new(Expr::Number(2.0, Span::default())),
+            span: Span::default(),
         };
         assert_eq!(compile_js(&expr), "(x ** 2.0)");
     }
@@ -607,8 +886,10 @@ mod tests {
             name: "sin".into(),
             args: vec![Arg {
                 name: None,
-                value: Expr::Ident("time".into()),
+                value: Expr::Ident("time".into(), Span::default()),
+                span: Span::default(),
             }],
+            span: Span::default(),
         };
         assert_eq!(compile_js(&expr), "Math.sin(time)");
     }
@@ -616,32 +897,37 @@ mod tests {
     #[test]
     fn js_ternary_uses_question_mark() {
         let expr = Expr::Ternary {
-            condition: Box::new(Expr::Ident("cond".into())),
-            if_true: Box::new(Expr::Number(1.0)),
-            if_false: Box::new(Expr::Number(0.0)),
+            condition: Box::new(Expr::Ident("cond".into(), Span::default())),
+            if_true: Box::new(Expr::Number(1.0, Span::default())),
+            if_false: Box::new(Expr::Number(0.0, Span::default())),
+            span: Span::default(),
         };
         assert_eq!(compile_js(&expr), "(cond ? 1.0 : 0.0)");
     }
 
     #[test]
+    #[allow(clippy::approx_constant)]
     fn js_number_formats() {
-        assert_eq!(compile_js(&Expr::Number(5.0)), "5.0");
-        assert_eq!(compile_js(&Expr::Number(3.14)), "3.14");
+        assert_eq!(compile_js(&Expr::Number(5.0, Span::default())), "5.0");
+        assert_eq!(compile_js(&Expr::Number(3.14, Span::default())), "3.14");
     }
 
     #[test]
     fn js_ident_time() {
-        assert_eq!(compile_js(&Expr::Ident("time".into())), "time");
+        assert_eq!(compile_js(&Expr::Ident("time".into(), Span::default())), "time");
     }
 
     #[test]
     fn js_ident_color_as_array() {
-        assert_eq!(compile_js(&Expr::Ident("red".into())), "[1, 0, 0]");
+        assert_eq!(compile_js(&Expr::Ident("red".into(), Span::default())), "[1.0, 0.0, 0.0]");
     }
 
     #[test]
     fn js_array() {
-        let expr = Expr::Array(vec![Expr::Number(1.0), Expr::Number(2.0)]);
+        let expr = Expr::Array(
+            vec![Expr::Number(1.0, Span::default()), Expr::Number(2.0, Span::default())],
+            Span::default(),
+        );
         assert_eq!(compile_js(&expr), "[1.0, 2.0]");
     }
 
@@ -650,10 +936,23 @@ mod tests {
         let expr = Expr::Call {
             name: "mix".into(),
             args: vec![
-                Arg { name: None, value: Expr::Number(0.0) },
-                Arg { name: None, value: Expr::Number(1.0) },
-                Arg { name: None, value: Expr::Number(0.5) },
+                Arg {
+                    name: None,
+                    value: Expr::Number(0.0, Span::default()),
+                    span: Span::default(),
+                },
+                Arg {
+                    name: None,
+                    value: Expr::Number(1.0, Span::default()),
+                    span: Span::default(),
+                },
+                Arg {
+                    name: None,
+                    value: Expr::Number(0.5, Span::default()),
+                    span: Span::default(),
+                },
             ],
+            span: Span::default(),
         };
         assert_eq!(compile_js(&expr), "(0.0 + (1.0 - 0.0) * 0.5)");
     }
@@ -663,10 +962,23 @@ mod tests {
         let expr = Expr::Call {
             name: "clamp".into(),
             args: vec![
-                Arg { name: None, value: Expr::Ident("x".into()) },
-                Arg { name: None, value: Expr::Number(0.0) },
-                Arg { name: None, value: Expr::Number(1.0) },
+                Arg {
+                    name: None,
+                    value: Expr::Ident("x".into(), Span::default()),
+                    span: Span::default(),
+                },
+                Arg {
+                    name: None,
+                    value: Expr::Number(0.0, Span::default()),
+                    span: Span::default(),
+                },
+                Arg {
+                    name: None,
+                    value: Expr::Number(1.0, Span::default()),
+                    span: Span::default(),
+                },
             ],
+            span: Span::default(),
         };
         assert_eq!(compile_js(&expr), "Math.min(Math.max(x, 0.0), 1.0)");
     }
@@ -678,6 +990,7 @@ mod tests {
         let expr = Expr::DottedIdent {
             object: "audio".into(),
             field: "bass".into(),
+            span: Span::default(),
         };
         assert!(uses_audio(&expr));
         assert!(!uses_mouse(&expr));
@@ -691,8 +1004,10 @@ mod tests {
             left: Box::new(Expr::DottedIdent {
                 object: "audio".into(),
                 field: "energy".into(),
+                span: Span::default(),
             }),
-            right: Box::new(Expr::Number(2.0)),
+            right: Box::new(Expr::Number(2.0, Span::default())),
+            span: Span::default(),
         };
         assert!(uses_audio(&expr));
     }
@@ -702,6 +1017,7 @@ mod tests {
         let expr = Expr::DottedIdent {
             object: "mouse".into(),
             field: "x".into(),
+            span: Span::default(),
         };
         assert!(uses_mouse(&expr));
         assert!(!uses_audio(&expr));
@@ -712,13 +1028,14 @@ mod tests {
         let expr = Expr::DottedIdent {
             object: "data".into(),
             field: "temp".into(),
+            span: Span::default(),
         };
         assert!(uses_data(&expr));
     }
 
     #[test]
     fn uses_audio_false_for_plain_number() {
-        assert!(!uses_audio(&Expr::Number(42.0)));
+        assert!(!uses_audio(&Expr::Number(42.0, Span::default())));
     }
 
     #[test]
@@ -730,8 +1047,11 @@ mod tests {
                 value: Expr::DottedIdent {
                     object: "audio".into(),
                     field: "beat".into(),
+                    span: Span::default(),
                 },
+                span: Span::default(),
             }],
+            span: Span::default(),
         };
         assert!(uses_audio(&expr));
     }
@@ -742,13 +1062,22 @@ mod tests {
             condition: Box::new(Expr::DottedIdent {
                 object: "audio".into(),
                 field: "beat".into(),
+                span: Span::default(),
             }),
-            if_true: Box::new(Expr::Number(1.0)),
-            if_false: Box::new(Expr::Number(0.0)),
+            if_true: Box::new(Expr::Number(1.0, Span::default())),
+            if_false: Box::new(Expr::Number(0.0, Span::default())),
+            span: Span::default(),
         };
         assert!(uses_audio(&expr));
     }
 
+    #[test]
+    fn uses_time_finds_nested_time() {
+        assert!(uses_time(&parse("0.3 + sin(time * 2.0) * 0.1")));
+        assert!(uses_time(&parse("x > 0.5 ? time : 0.0")));
+        assert!(!uses_time(&parse("0.3 + data.level * 0.1")));
+    }
+
     // ── Data field collection ────────────────────────────
 
     #[test]
@@ -758,11 +1087,14 @@ mod tests {
             left: Box::new(Expr::DottedIdent {
                 object: "data".into(),
                 field: "temp".into(),
+                span: Span::default(),
             }),
             right: Box::new(Expr::DottedIdent {
                 object: "data".into(),
-                field: "temp".into(), // duplicate
+                field: "temp".into(), // duplicate,
+                span: Span::default(),
             }),
+            span: Span::default(),
         };
         let mut fields = Vec::new();
         collect_data_fields(&expr, &mut fields);
@@ -776,11 +1108,14 @@ mod tests {
             left: Box::new(Expr::DottedIdent {
                 object: "data".into(),
                 field: "x".into(),
+                span: Span::default(),
             }),
             right: Box::new(Expr::DottedIdent {
                 object: "data".into(),
                 field: "y".into(),
+                span: Span::default(),
             }),
+            span: Span::default(),
         };
         let mut fields = Vec::new();
         collect_data_fields(&expr, &mut fields);
@@ -793,47 +1128,27 @@ mod tests {
 
     #[test]
     fn extract_number_literal() {
-        assert_eq!(extract_number(&Expr::Number(42.0)), Some(42.0));
+        assert_eq!(extract_number(&Expr::Number(42.0, Span::default())), Some(42.0));
     }
 
     #[test]
     fn extract_number_neg() {
-        let expr = Expr::Neg(Box::new(Expr::Number(3.0)));
+        let expr = Expr::Neg(Box::new(Expr::Number(3.0, Span::default())), Span::default());
         assert_eq!(extract_number(&expr), Some(-3.0));
     }
 
     #[test]
     fn extract_number_paren() {
-        let expr = Expr::Paren(Box::new(Expr::Number(7.0)));
+        let expr = Expr::Paren(Box::new(Expr::Number(7.0, Span::default())), Span::default());
         assert_eq!(extract_number(&expr), Some(7.0));
     }
 
     #[test]
     fn extract_number_non_numeric() {
-        assert_eq!(extract_number(&Expr::Ident("foo".into())), None);
+        assert_eq!(extract_number(&Expr::Ident("foo".into(), Span::default())), None);
     }
 
-    // ── Color/constant resolution ────────────────────────
-
-    #[test]
-    fn resolve_all_colors() {
-        let names = [
-            "black", "white", "red", "green", "blue", "gold", "midnight",
-            "obsidian", "ember", "cyan", "ivory", "frost", "orange",
-            "deep_blue", "ash", "charcoal", "plasma", "violet", "magenta",
-        ];
-        for name in names {
-            assert!(
-                resolve_color(name).is_some(),
-                "missing color: {name}"
-            );
-        }
-    }
-
-    #[test]
-    fn resolve_unknown_color_is_none() {
-        assert!(resolve_color("rainbow").is_none());
-    }
+    // ── Constant resolution ──────────────────────────────
 
     #[test]
     fn resolve_all_constants() {
@@ -13,8 +13,8 @@
 
 use crate::ast::*;
 use crate::codegen::memory;
-use crate::codegen::stages::get_arg;
-use crate::codegen::UniformInfo;
+use crate::codegen::stages::{get_arg_glsl, get_rgb_glsl};
+use crate::codegen::{blend, combine, gradient, wgsl, UniformInfo};
 
 /// Generate a GLSL ES 3.0 fragment shader for a cinematic.
 pub fn generate_fragment(
@@ -25,7 +25,53 @@ pub fn generate_fragment(
 
     // Header
     s.push_str("#version 300 es\nprecision highp float;\n\n");
+    emit_uniform_decls(&mut s, uniforms);
+
+    // Memory texture uniforms (before varyings), one per memory layer
+    let memory_layers = memory::memory_layers(&cinematic.layers).count();
+    if memory_layers > 0 {
+        memory::emit_glsl_memory_bindings(&mut s, memory_layers);
+        s.push_str("\nin vec2 v_uv;\n");
+        memory::emit_glsl_outputs(&mut s, memory_layers);
+        s.push('\n');
+    } else {
+        s.push_str("\nin vec2 v_uv;\nout vec4 fragColor;\n\n");
+    }
+
+    // Built-in helper functions (C-style params!)
+    emit_glsl_builtins(&mut s, cinematic);
+    memory::emit_glsl_memory_helpers(&mut s, cinematic);
+
+    // Entry point: void main()
+    s.push_str("void main(){\n");
+    emit_prelude(&mut s, uniforms);
+    for i in 0..memory_layers {
+        s.push_str(&format!("    mem_out_{i} = vec4(0.0);\n"));
+    }
+
+    let multi_layer = blend::composites(cinematic);
+    if multi_layer {
+        s.push_str("    vec4 final_color = vec4(0.0, 0.0, 0.0, 1.0);\n\n");
+    }
+
+    let mut memory_index = 0;
+    for (i, layer) in cinematic.layers.iter().enumerate() {
+        emit_glsl_layer(&mut s, layer, i, multi_layer, memory_index);
+        memory_index += usize::from(layer.memory.is_some());
+    }
 
+    if multi_layer {
+        blend::emit_alpha(&mut s, "final_color", "    ");
+        s.push_str("    fragColor = final_color;\n");
+    } else if !wgsl::has_pipeline_layer(cinematic) {
+        s.push_str("    fragColor = vec4(0.0, 0.0, 0.0, 0.0);\n");
+    }
+    s.push_str("}\n");
+    s
+}
+
+/// Emit the uniform declarations shared by every fragment program.
+pub(crate) fn emit_uniform_decls(s: &mut String, uniforms: &[UniformInfo]) {
     // Uniforms — individual declarations (NOT a struct)
     s.push_str("uniform float u_time;\n");
     s.push_str("uniform float u_audio_bass;\n");
@@ -38,19 +84,11 @@ pub fn generate_fragment(
     for u in uniforms {
         s.push_str(&format!("uniform float u_p_{};\n", u.name));
     }
+}
 
-    // Memory texture uniform (before varyings)
-    if memory::any_layer_uses_memory(&cinematic.layers) {
-        memory::emit_glsl_memory_bindings(&mut s);
-    }
-
-    s.push_str("\nin vec2 v_uv;\nout vec4 fragColor;\n\n");
-
-    // Built-in helper functions (C-style params!)
-    emit_glsl_builtins(&mut s, cinematic);
-
-    // Entry point: void main()
-    s.push_str("void main(){\n");
+/// Emit the `main()` prelude: centered uv, aspect, wrapped time and
+/// `float` aliases for every user uniform.
+pub(crate) fn emit_prelude(s: &mut String, uniforms: &[UniformInfo]) {
     s.push_str("    vec2 uv = v_uv * 2.0 - 1.0;\n");
     s.push_str("    float aspect = u_resolution.x / u_resolution.y;\n");
     s.push_str("    float time = fract(u_time / 120.0) * 120.0;\n\n");
@@ -62,21 +100,6 @@ pub fn generate_fragment(
     if !uniforms.is_empty() {
         s.push('\n');
     }
-
-    let multi_layer = cinematic.layers.len() > 1;
-    if multi_layer {
-        s.push_str("    vec4 final_color = vec4(0.0, 0.0, 0.0, 1.0);\n\n");
-    }
-
-    for (i, layer) in cinematic.layers.iter().enumerate() {
-        emit_glsl_layer(&mut s, layer, i, multi_layer);
-    }
-
-    if multi_layer {
-        s.push_str("    fragColor = final_color;\n");
-    }
-    s.push_str("}\n");
-    s
 }
 
 fn emit_glsl_builtins(s: &mut String, cinematic: &Cinematic) {
@@ -225,7 +248,7 @@ fn emit_glsl_palette_helper(s: &mut String) {
     s.push_str("}\n\n");
 }
 
-fn emit_glsl_layer(s: &mut String, layer: &Layer, idx: usize, multi: bool) {
+fn emit_glsl_layer(s: &mut String, layer: &Layer, idx: usize, multi: bool, memory_index: usize) {
     let body = match &layer.body {
         LayerBody::Pipeline(stages) => stages,
         _ => return,
@@ -243,39 +266,39 @@ fn emit_glsl_layer(s: &mut String, layer: &Layer, idx: usize, multi: bool) {
         emit_glsl_stage(s, stage, indent);
     }
 
-    // Memory: mix with previous frame if this layer has memory
-    if let Some(decay) = layer.memory {
-        memory::emit_glsl_memory_mix(s, decay, indent);
+    // Memory: mix with this layer's previous frame
+    if layer.memory.is_some() {
+        memory::emit_glsl_memory_mix(s, layer, memory_index, indent);
     }
 
     if multi {
-        // Screen blend instead of pure additive
-        s.push_str(&format!("{indent}vec3 lc = color_result.rgb;\n"));
-        s.push_str(&format!("{indent}final_color = vec4(final_color.rgb + lc - final_color.rgb * lc, 1.0);\n"));
+        blend::emit_glsl_blend(s, layer, indent);
         s.push_str("    }\n\n");
     } else {
+        blend::emit_alpha(s, "color_result", indent);
         s.push_str(&format!("{indent}fragColor = color_result;\n"));
     }
 }
 
-fn emit_glsl_stage(s: &mut String, stage: &Stage, indent: &str) {
+pub(crate) fn emit_glsl_stage(s: &mut String, stage: &Stage, indent: &str) {
     let args = &stage.args;
     match stage.name.as_str() {
         // ── SDF Generators: Position -> Sdf ──────────────────
         "circle" => {
-            let r = get_arg(args, "radius", 0, "circle");
+            let r = get_arg_glsl(args, "radius", 0, "circle");
             s.push_str(&format!("{indent}float sdf_result = sdf_circle(p, {r});\n"));
         }
         "ring" => {
-            let r = get_arg(args, "radius", 0, "ring");
-            let w = get_arg(args, "width", 1, "ring");
+            let r = get_arg_glsl(args, "radius", 0, "ring");
+            let w = get_arg_glsl(args, "width", 1, "ring");
             s.push_str(&format!("{indent}float sdf_result = abs(length(p) - {r}) - {w};\n"));
         }
         "star" => {
-            let points = get_arg(args, "points", 0, "star");
-            let radius = get_arg(args, "radius", 1, "star");
-            let inner = get_arg(args, "inner", 2, "star");
+            let points = get_arg_glsl(args, "points", 0, "star");
+            let radius = get_arg_glsl(args, "radius", 1, "star");
+            let inner = get_arg_glsl(args, "inner", 2, "star");
             // Star SDF via angular repetition
+            s.push_str(&format!("{indent}float sdf_result;\n"));
             s.push_str(&format!("{indent}{{ float star_an = 3.14159265359 / {points};\n"));
             s.push_str(&format!("{indent}float star_a = atan(p.y, p.x);\n"));
             s.push_str(&format!("{indent}float star_sector = mod(star_a + star_an, 2.0 * star_an) - star_an;\n"));
@@ -284,89 +307,95 @@ fn emit_glsl_stage(s: &mut String, stage: &Stage, indent: &str) {
             s.push_str(&format!("{indent}vec2 star_b_pt = vec2({inner} * cos(star_an), {inner} * sin(star_an));\n"));
             s.push_str(&format!("{indent}vec2 star_ba = star_b_pt - star_a_pt;\n"));
             s.push_str(&format!("{indent}float star_t = clamp(dot(star_p - star_a_pt, star_ba) / dot(star_ba, star_ba), 0.0, 1.0);\n"));
-            s.push_str(&format!("{indent}float sdf_result = length(star_p - star_a_pt - star_ba * star_t); }}\n"));
+            s.push_str(&format!("{indent}sdf_result = length(star_p - star_a_pt - star_ba * star_t); }}\n"));
         }
         "box" => {
-            let w = get_arg(args, "w", 0, "box");
-            let h = get_arg(args, "h", 1, "box");
+            let w = get_arg_glsl(args, "w", 0, "box");
+            let h = get_arg_glsl(args, "h", 1, "box");
             s.push_str(&format!("{indent}vec2 box_d = abs(p) - vec2({w}, {h});\n"));
             s.push_str(&format!("{indent}float sdf_result = length(max(box_d, 0.0)) + min(max(box_d.x, box_d.y), 0.0);\n"));
         }
         "polygon" => {
-            let sides = get_arg(args, "sides", 0, "polygon");
-            let radius = get_arg(args, "radius", 1, "polygon");
+            let sides = get_arg_glsl(args, "sides", 0, "polygon");
+            let radius = get_arg_glsl(args, "radius", 1, "polygon");
+            s.push_str(&format!("{indent}float sdf_result;\n"));
             s.push_str(&format!("{indent}{{ float poly_n = {sides};\n"));
             s.push_str(&format!("{indent}float poly_a = atan(p.y, p.x);\n"));
             s.push_str(&format!("{indent}float poly_r = length(p);\n"));
             s.push_str(&format!("{indent}float poly_an = 6.28318530718 / poly_n;\n"));
             s.push_str(&format!("{indent}float poly_sector = cos(floor(0.5 + poly_a / poly_an) * poly_an - poly_a) * poly_r;\n"));
-            s.push_str(&format!("{indent}float sdf_result = poly_sector - {radius}; }}\n"));
+            s.push_str(&format!("{indent}sdf_result = poly_sector - {radius}; }}\n"));
         }
         "simplex" => {
-            let sc = get_arg(args, "scale", 0, "simplex");
+            let sc = get_arg_glsl(args, "scale", 0, "simplex");
             s.push_str(&format!("{indent}float sdf_result = simplex2d(p * {sc});\n"));
         }
         "voronoi" => {
-            let sc = get_arg(args, "scale", 0, "voronoi");
+            let sc = get_arg_glsl(args, "scale", 0, "voronoi");
             s.push_str(&format!("{indent}float sdf_result = voronoi2d(p, {sc});\n"));
         }
         "concentric_waves" => {
-            let amplitude = get_arg(args, "amplitude", 0, "concentric_waves");
-            let width = get_arg(args, "width", 1, "concentric_waves");
-            let freq = get_arg(args, "frequency", 2, "concentric_waves");
+            let amplitude = get_arg_glsl(args, "amplitude", 0, "concentric_waves");
+            let width = get_arg_glsl(args, "width", 1, "concentric_waves");
+            let freq = get_arg_glsl(args, "frequency", 2, "concentric_waves");
             s.push_str(&format!("{indent}float cw_dist = length(p);\n"));
             s.push_str(&format!("{indent}float sdf_result = {amplitude} * sin(cw_dist * {freq} * 6.28318530718 - time * {width});\n"));
         }
         "fbm" => {
-            let sc = get_arg(args, "scale", 0, "fbm");
-            let oct = get_arg(args, "octaves", 1, "fbm");
-            let pers = get_arg(args, "persistence", 2, "fbm");
-            let lac = get_arg(args, "lacunarity", 3, "fbm");
+            let sc = get_arg_glsl(args, "scale", 0, "fbm");
+            let oct = get_arg_glsl(args, "octaves", 1, "fbm");
+            let pers = get_arg_glsl(args, "persistence", 2, "fbm");
+            let lac = get_arg_glsl(args, "lacunarity", 3, "fbm");
             s.push_str(&format!("{indent}float sdf_result = fbm2((p * {sc}), int({oct}), {pers}, {lac});\n"));
         }
 
+        // ── SDF Combinators: nested pipelines -> Sdf ─────────
+        "union" | "subtract" | "intersect" | "smooth_union" | "morph" => {
+            combine::emit_glsl(s, stage, indent);
+        }
+
         // ── Transforms: Position -> Position ─────────────────
         "rotate" => {
-            let angle = get_arg(args, "angle", 0, "rotate");
+            let angle = get_arg_glsl(args, "angle", 0, "rotate");
             // GLSL: use `float`, NOT `let`
             s.push_str(&format!("{indent}{{ float rc = cos({angle}); float rs = sin({angle});\n"));
             s.push_str(&format!("{indent}p = vec2(p.x * rc - p.y * rs, p.x * rs + p.y * rc); }}\n"));
         }
         "translate" => {
-            let x = get_arg(args, "x", 0, "translate");
-            let y = get_arg(args, "y", 1, "translate");
+            let x = get_arg_glsl(args, "x", 0, "translate");
+            let y = get_arg_glsl(args, "y", 1, "translate");
             s.push_str(&format!("{indent}p = p - vec2({x}, {y});\n"));
         }
         "scale" => {
-            let sc = get_arg(args, "s", 0, "scale");
+            let sc = get_arg_glsl(args, "s", 0, "scale");
             s.push_str(&format!("{indent}p = p / {sc};\n"));
         }
         "twist" => {
-            let amount = get_arg(args, "amount", 0, "twist");
+            let amount = get_arg_glsl(args, "amount", 0, "twist");
             s.push_str(&format!("{indent}{{ float tw_a = p.y * {amount};\n"));
             s.push_str(&format!("{indent}float tw_c = cos(tw_a); float tw_s = sin(tw_a);\n"));
             s.push_str(&format!("{indent}p = vec2(p.x * tw_c - p.y * tw_s, p.x * tw_s + p.y * tw_c); }}\n"));
         }
         "mirror" => {
-            let axis = get_arg(args, "axis", 0, "mirror");
+            let axis = get_arg_glsl(args, "axis", 0, "mirror");
             // axis 0 = mirror X, else mirror Y
             s.push_str(&format!("{indent}p = ({axis} < 0.5) ? vec2(abs(p.x), p.y) : vec2(p.x, abs(p.y));\n"));
         }
         "repeat" => {
-            let count = get_arg(args, "count", 0, "repeat");
+            let count = get_arg_glsl(args, "count", 0, "repeat");
             s.push_str(&format!("{indent}{{ float rep_angle = 6.28318530718 / {count};\n"));
             s.push_str(&format!("{indent}float rep_a = atan(p.y, p.x);\n"));
             s.push_str(&format!("{indent}rep_a = mod(rep_a + rep_angle * 0.5, rep_angle) - rep_angle * 0.5;\n"));
             s.push_str(&format!("{indent}p = vec2(cos(rep_a), sin(rep_a)) * length(p); }}\n"));
         }
         "domain_warp" => {
-            let amount = get_arg(args, "amount", 0, "domain_warp");
-            let freq = get_arg(args, "freq", 1, "domain_warp");
+            let amount = get_arg_glsl(args, "amount", 0, "domain_warp");
+            let freq = get_arg_glsl(args, "freq", 1, "domain_warp");
             s.push_str(&format!("{indent}p = p + vec2(noise2(p * {freq}), noise2(p * {freq} + vec2(5.2, 1.3))) * {amount};\n"));
         }
         "curl_noise" => {
-            let frequency = get_arg(args, "frequency", 0, "curl_noise");
-            let amplitude = get_arg(args, "amplitude", 1, "curl_noise");
+            let frequency = get_arg_glsl(args, "frequency", 0, "curl_noise");
+            let amplitude = get_arg_glsl(args, "amplitude", 1, "curl_noise");
             s.push_str(&format!("{indent}{{ float cn_eps = 0.001;\n"));
             s.push_str(&format!("{indent}float cn_n0 = noise2(p * {frequency} + vec2(0.0, cn_eps));\n"));
             s.push_str(&format!("{indent}float cn_n1 = noise2(p * {frequency} - vec2(0.0, cn_eps));\n"));
@@ -376,51 +405,49 @@ fn emit_glsl_stage(s: &mut String, stage: &Stage, indent: &str) {
             s.push_str(&format!("{indent}p = p + normalize(cn_curl) * {amplitude}; }}\n"));
         }
         "displace" => {
-            let strength = get_arg(args, "strength", 0, "displace");
-            s.push_str(&format!("{indent}p = p + vec2(noise2(p * 3.0 + time * 0.5), noise2(p * 3.0 + vec2(5.2, 1.3) + time * 0.5)) * {strength};\n"));
+            let strength = get_arg_glsl(args, "strength", 0, "displace");
+            s.push_str(&format!("{indent}p = p + vec2(noise2(p * 3.0), noise2(p * 3.0 + vec2(5.0, 3.0))) * {strength};\n"));
         }
 
         // ── SDF Modifiers: Sdf -> Sdf ────────────────────────
         "mask_arc" => {
-            let angle = get_arg(args, "angle", 0, "mask_arc");
+            let angle = get_arg_glsl(args, "angle", 0, "mask_arc");
             s.push_str(&format!("{indent}float arc_theta = atan(p.x, p.y) + 3.14159265359;\n"));
             // GLSL: use ternary, NOT select()
             s.push_str(&format!("{indent}sdf_result = (arc_theta < {angle} ? sdf_result : 999.0);\n"));
         }
         "threshold" => {
-            let cutoff = get_arg(args, "cutoff", 0, "threshold");
+            let cutoff = get_arg_glsl(args, "cutoff", 0, "threshold");
             s.push_str(&format!("{indent}sdf_result = (sdf_result < {cutoff}) ? -1.0 : 1.0;\n"));
         }
         "onion" => {
-            let thickness = get_arg(args, "thickness", 0, "onion");
+            let thickness = get_arg_glsl(args, "thickness", 0, "onion");
             s.push_str(&format!("{indent}sdf_result = abs(sdf_result) - {thickness};\n"));
         }
         "round" => {
-            let radius = get_arg(args, "radius", 0, "round");
+            let radius = get_arg_glsl(args, "radius", 0, "round");
             s.push_str(&format!("{indent}sdf_result = sdf_result - {radius};\n"));
         }
 
         // ── Bridges: Sdf -> Color ────────────────────────────
         "glow" => {
-            let intensity = get_arg(args, "intensity", 0, "glow");
+            let intensity = get_arg_glsl(args, "intensity", 0, "glow");
             s.push_str(&format!("{indent}float glow_result = apply_glow(sdf_result, {intensity});\n\n"));
             s.push_str(&format!("{indent}vec4 color_result = vec4(vec3(glow_result), 1.0);\n"));
         }
         "shade" => {
-            let r = get_arg(args, "r", 0, "shade");
-            let g = get_arg(args, "g", 1, "shade");
-            let b = get_arg(args, "b", 2, "shade");
+            let rgb = get_rgb_glsl(args, "shade");
             // Anti-aliased shade with fwidth-based smoothstep
             s.push_str(&format!("{indent}float aa = 0.005;\n"));
-            s.push_str(&format!("{indent}vec4 color_result = vec4(vec3({r}, {g}, {b}) * smoothstep(aa, -aa, sdf_result), 1.0);\n"));
+            s.push_str(&format!("{indent}vec4 color_result = vec4({rgb} * smoothstep(aa, -aa, sdf_result), 1.0);\n"));
         }
         "emissive" => {
-            let intensity = get_arg(args, "intensity", 0, "emissive");
+            let intensity = get_arg_glsl(args, "intensity", 0, "emissive");
             s.push_str(&format!("{indent}float glow_result = apply_glow(sdf_result, {intensity});\n"));
             s.push_str(&format!("{indent}vec4 color_result = vec4(vec3(glow_result), glow_result);\n"));
         }
         "palette" => {
-            let name = get_arg(args, "name", 0, "palette");
+            let name = get_arg_glsl(args, "name", 0, "palette");
             let (a, b, c, d) = match name.as_str() {
                 "fire"    => ("vec3(0.5,0.5,0.5)", "vec3(0.5,0.5,0.5)", "vec3(1.0,1.0,1.0)", "vec3(0.00,0.10,0.20)"),
                 "ice"     => ("vec3(0.5,0.5,0.5)", "vec3(0.5,0.5,0.5)", "vec3(1.0,1.0,1.0)", "vec3(0.30,0.20,0.20)"),
@@ -438,32 +465,30 @@ fn emit_glsl_stage(s: &mut String, stage: &Stage, indent: &str) {
 
         // ── Color Processors: Color -> Color ─────────────────
         "tint" => {
-            let r = get_arg(args, "r", 0, "tint");
-            let g = get_arg(args, "g", 1, "tint");
-            let b = get_arg(args, "b", 2, "tint");
-            s.push_str(&format!("{indent}color_result = vec4(color_result.rgb * vec3({r}, {g}, {b}), 1.0);\n"));
+            let rgb = get_rgb_glsl(args, "tint");
+            s.push_str(&format!("{indent}color_result = vec4(color_result.rgb * {rgb}, 1.0);\n"));
         }
         "bloom" => {
-            let thresh = get_arg(args, "threshold", 0, "bloom");
-            let strength = get_arg(args, "strength", 1, "bloom");
+            let thresh = get_arg_glsl(args, "threshold", 0, "bloom");
+            let strength = get_arg_glsl(args, "strength", 1, "bloom");
             // GLSL: dot returns float, NOT vec3
             s.push_str(&format!("{indent}float pp_lum = dot(color_result.rgb, vec3(0.299, 0.587, 0.114));\n"));
             s.push_str(&format!("{indent}color_result = vec4(color_result.rgb + max(pp_lum - {thresh}, 0.0) * {strength}, 1.0);\n"));
         }
         "grain" => {
-            let amount = get_arg(args, "amount", 0, "grain");
+            let amount = get_arg_glsl(args, "amount", 0, "grain");
             s.push_str(&format!("{indent}float grain_noise = fract(sin(dot(p, vec2(12.9898, 78.233)) + time) * 43758.5453);\n"));
             s.push_str(&format!("{indent}color_result = vec4(color_result.rgb + (grain_noise - 0.5) * {amount}, color_result.a);\n"));
         }
         "vignette" => {
-            let strength = get_arg(args, "strength", 0, "vignette");
-            let radius = get_arg(args, "radius", 1, "vignette");
+            let strength = get_arg_glsl(args, "strength", 0, "vignette");
+            let radius = get_arg_glsl(args, "radius", 1, "vignette");
             s.push_str(&format!("{indent}float vig_dist = length(uv);\n"));
             s.push_str(&format!("{indent}float vig_factor = 1.0 - smoothstep({radius}, {radius} + {strength}, vig_dist);\n"));
             s.push_str(&format!("{indent}color_result = vec4(color_result.rgb * vig_factor, color_result.a);\n"));
         }
         "chromatic" => {
-            let offset = get_arg(args, "offset", 0, "chromatic");
+            let offset = get_arg_glsl(args, "offset", 0, "chromatic");
             s.push_str(&format!("{indent}{{ vec2 chr_dir = normalize(uv) * {offset};\n"));
             s.push_str(&format!("{indent}float chr_r = color_result.r;\n"));
             s.push_str(&format!("{indent}float chr_b = color_result.b;\n"));
@@ -471,24 +496,24 @@ fn emit_glsl_stage(s: &mut String, stage: &Stage, indent: &str) {
             s.push_str(&format!("{indent}color_result = vec4(chr_r * (1.0 + chr_dir.x), color_result.g, chr_b * (1.0 - chr_dir.x), color_result.a); }}\n"));
         }
         "tonemap" => {
-            let exposure = get_arg(args, "exposure", 0, "tonemap");
+            let exposure = get_arg_glsl(args, "exposure", 0, "tonemap");
             // Reinhard tonemap
             s.push_str(&format!("{indent}{{ vec3 tm_c = color_result.rgb * {exposure};\n"));
             s.push_str(&format!("{indent}color_result = vec4(tm_c / (tm_c + vec3(1.0)), color_result.a); }}\n"));
         }
         "scanlines" => {
-            let frequency = get_arg(args, "frequency", 0, "scanlines");
-            let intensity = get_arg(args, "intensity", 1, "scanlines");
+            let frequency = get_arg_glsl(args, "frequency", 0, "scanlines");
+            let intensity = get_arg_glsl(args, "intensity", 1, "scanlines");
             s.push_str(&format!("{indent}float scan_val = sin(uv.y * {frequency} * 3.14159265359) * {intensity};\n"));
             s.push_str(&format!("{indent}color_result = vec4(color_result.rgb * (1.0 - scan_val * 0.5), color_result.a);\n"));
         }
         "saturate_color" => {
-            let amount = get_arg(args, "amount", 0, "saturate_color");
+            let amount = get_arg_glsl(args, "amount", 0, "saturate_color");
             s.push_str(&format!("{indent}{{ float sat_lum = dot(color_result.rgb, vec3(0.299, 0.587, 0.114));\n"));
             s.push_str(&format!("{indent}color_result = vec4(mix(vec3(sat_lum), color_result.rgb, {amount}), color_result.a); }}\n"));
         }
         "glitch" => {
-            let intensity = get_arg(args, "intensity", 0, "glitch");
+            let intensity = get_arg_glsl(args, "intensity", 0, "glitch");
             s.push_str(&format!("{indent}{{ float gl_t = floor(time * 10.0);\n"));
             s.push_str(&format!("{indent}float gl_noise = fract(sin(gl_t * 43758.5453 + p.y * 100.0) * 43758.5453);\n"));
             s.push_str(&format!("{indent}float gl_shift = (gl_noise - 0.5) * {intensity} * 0.1;\n"));
@@ -500,23 +525,18 @@ fn emit_glsl_stage(s: &mut String, stage: &Stage, indent: &str) {
             s.push_str(&format!("{indent}    color_result.a); }}\n"));
         }
         "blend" => {
-            let factor = get_arg(args, "factor", 0, "blend");
+            let factor = get_arg_glsl(args, "factor", 0, "blend");
             s.push_str(&format!("{indent}color_result = vec4(color_result.rgb * {factor}, color_result.a);\n"));
         }
 
         // ── Full-screen Generators: Position -> Color ────────
         "gradient" => {
-            let _color_a = get_arg(args, "color_a", 0, "gradient");
-            let _color_b = get_arg(args, "color_b", 1, "gradient");
-            let _mode = get_arg(args, "mode", 2, "gradient");
-            // Default vertical gradient: black at bottom, white at top
-            s.push_str(&format!("{indent}float grad_t = uv.y * 0.5 + 0.5;\n"));
-            s.push_str(&format!("{indent}vec4 color_result = vec4(vec3(grad_t), 1.0);\n"));
+            gradient::emit_glsl(s, stage, indent);
         }
         "spectrum" => {
-            let bass = get_arg(args, "bass", 0, "spectrum");
-            let mid = get_arg(args, "mid", 1, "spectrum");
-            let treble = get_arg(args, "treble", 2, "spectrum");
+            let bass = get_arg_glsl(args, "bass", 0, "spectrum");
+            let mid = get_arg_glsl(args, "mid", 1, "spectrum");
+            let treble = get_arg_glsl(args, "treble", 2, "spectrum");
             s.push_str(&format!("{indent}float spec_x = uv.x * 0.5 + 0.5;\n"));
             s.push_str(&format!("{indent}float spec_r = smoothstep(0.0, 0.33, spec_x) * (u_audio_bass + {bass});\n"));
             s.push_str(&format!("{indent}float spec_g = smoothstep(0.33, 0.66, spec_x) * (u_audio_mid + {mid});\n"));
@@ -532,7 +552,12 @@ fn emit_glsl_stage(s: &mut String, stage: &Stage, indent: &str) {
 
 fn has_stage(layer: &Layer, name: &str) -> bool {
     match &layer.body {
-        LayerBody::Pipeline(stages) => stages.iter().any(|s| s.name == name),
+        LayerBody::Pipeline(stages) => {
+            // Combinator sub-pipelines need their helpers too
+            let mut found = false;
+            walk_stages(stages, &mut |s| found |= s.name == name);
+            found
+        }
         _ => false,
     }
 }
@@ -566,20 +591,23 @@ mod tests {
                 opts: vec![],
                 memory: None,
                 cast: None,
+                blend: None,
                 body: LayerBody::Pipeline(stages),
+                span: Span::default(),
             }],
             arcs: vec![],
             resonates: vec![],
             listen: None, voice: None, score: None, gravity: None,
             lenses: vec![], react: None, defines: vec![],
+            span: Span::default(),
         }
     }
 
     #[test]
     fn glsl_has_void_main() {
         let cin = make_cinematic(vec![
-            Stage { name: "circle".into(), args: vec![] },
-            Stage { name: "glow".into(), args: vec![] },
+            Stage { name: "circle".into(), args: vec![], span: Span::default(), expanded_from: vec![] },
+            Stage { name: "glow".into(), args: vec![], span: Span::default(), expanded_from: vec![] },
         ]);
         let output = generate_fragment(&cin, &[]);
         assert!(output.contains("void main()"), "must use void main(), got:\n{output}");
@@ -589,8 +617,8 @@ mod tests {
     #[test]
     fn glsl_has_c_style_params() {
         let cin = make_cinematic(vec![
-            Stage { name: "circle".into(), args: vec![] },
-            Stage { name: "glow".into(), args: vec![] },
+            Stage { name: "circle".into(), args: vec![], span: Span::default(), expanded_from: vec![] },
+            Stage { name: "glow".into(), args: vec![], span: Span::default(), expanded_from: vec![] },
         ]);
         let output = generate_fragment(&cin, &[]);
         assert!(output.contains("float sdf_circle(vec2 p, float radius)"), "C-style params");
@@ -601,8 +629,8 @@ mod tests {
     #[test]
     fn glsl_uses_fragcolor_not_return() {
         let cin = make_cinematic(vec![
-            Stage { name: "circle".into(), args: vec![] },
-            Stage { name: "glow".into(), args: vec![] },
+            Stage { name: "circle".into(), args: vec![], span: Span::default(), expanded_from: vec![] },
+            Stage { name: "glow".into(), args: vec![], span: Span::default(), expanded_from: vec![] },
         ]);
         let output = generate_fragment(&cin, &[]);
         assert!(output.contains("fragColor = "), "must assign fragColor");
@@ -611,9 +639,9 @@ mod tests {
     #[test]
     fn glsl_uses_float_not_let() {
         let cin = make_cinematic(vec![
-            Stage { name: "circle".into(), args: vec![] },
-            Stage { name: "glow".into(), args: vec![] },
-            Stage { name: "tint".into(), args: vec![] },
+            Stage { name: "circle".into(), args: vec![], span: Span::default(), expanded_from: vec![] },
+            Stage { name: "glow".into(), args: vec![], span: Span::default(), expanded_from: vec![] },
+            Stage { name: "tint".into(), args: vec![], span: Span::default(), expanded_from: vec![] },
         ]);
         let output = generate_fragment(&cin, &[]);
         // Main body should use `float` and `vec2`, not `let`
@@ -624,9 +652,9 @@ mod tests {
     #[test]
     fn glsl_bloom_uses_float_lum() {
         let cin = make_cinematic(vec![
-            Stage { name: "circle".into(), args: vec![] },
-            Stage { name: "glow".into(), args: vec![] },
-            Stage { name: "bloom".into(), args: vec![] },
+            Stage { name: "circle".into(), args: vec![], span: Span::default(), expanded_from: vec![] },
+            Stage { name: "glow".into(), args: vec![], span: Span::default(), expanded_from: vec![] },
+            Stage { name: "bloom".into(), args: vec![], span: Span::default(), expanded_from: vec![] },
         ]);
         let output = generate_fragment(&cin, &[]);
         assert!(output.contains("float pp_lum = dot("), "dot() must return float");
@@ -636,11 +664,15 @@ mod tests {
     #[test]
     fn glsl_mask_arc_uses_ternary() {
         let cin = make_cinematic(vec![
-            Stage { name: "ring".into(), args: vec![] },
+            Stage { name: "ring".into(), args: vec![], span: Span::default(), expanded_from: vec![] },
             Stage { name: "mask_arc".into(), args: vec![
-                Arg { name: None, value: Expr::Number(4.0) },
-            ] },
-            Stage { name: "glow".into(), args: vec![] },
+                Arg {
+                    name: None,
+                    value: Expr::Number(4.0, Span::default()),
+                    span: Span::default(),
+                },
+            ], span: Span::default(), expanded_from: vec![] },
+            Stage { name: "glow".into(), args: vec![], span: Span::default(), expanded_from: vec![] },
         ]);
         let output = generate_fragment(&cin, &[]);
         assert!(output.contains("? sdf_result : 999.0"), "must use ternary");
@@ -651,10 +683,14 @@ mod tests {
     fn glsl_rotate_uses_float_not_let() {
         let cin = make_cinematic(vec![
             Stage { name: "rotate".into(), args: vec![
-                Arg { name: None, value: Expr::Number(1.0) },
-            ] },
-            Stage { name: "circle".into(), args: vec![] },
-            Stage { name: "glow".into(), args: vec![] },
+                Arg {
+                    name: None,
+                    value: Expr::Number(1.0, Span::default()),
+                    span: Span::default(),
+                },
+            ], span: Span::default(), expanded_from: vec![] },
+            Stage { name: "circle".into(), args: vec![], span: Span::default(), expanded_from: vec![] },
+            Stage { name: "glow".into(), args: vec![], span: Span::default(), expanded_from: vec![] },
         ]);
         let output = generate_fragment(&cin, &[]);
         assert!(output.contains("float rc = cos("), "must use float, not let");
@@ -675,24 +711,27 @@ mod tests {
             name: "multi".into(),
             layers: vec![
                 Layer {
-                    name: "a".into(), opts: vec![], memory: None, cast: None,
+                    name: "a".into(), opts: vec![], memory: None, cast: None, blend: None,
                     body: LayerBody::Pipeline(vec![
-                        Stage { name: "circle".into(), args: vec![] },
-                        Stage { name: "glow".into(), args: vec![] },
+                        Stage { name: "circle".into(), args: vec![], span: Span::default(), expanded_from: vec![] },
+                        Stage { name: "glow".into(), args: vec![], span: Span::default(), expanded_from: vec![] },
                     ]),
+                    span: Span::default(),
                 },
                 Layer {
-                    name: "b".into(), opts: vec![], memory: None, cast: None,
+                    name: "b".into(), opts: vec![], memory: None, cast: None, blend: None,
                     body: LayerBody::Pipeline(vec![
-                        Stage { name: "ring".into(), args: vec![] },
-                        Stage { name: "glow".into(), args: vec![] },
+                        Stage { name: "ring".into(), args: vec![], span: Span::default(), expanded_from: vec![] },
+                        Stage { name: "glow".into(), args: vec![], span: Span::default(), expanded_from: vec![] },
                     ]),
+                    span: Span::default(),
                 },
             ],
             arcs: vec![],
             resonates: vec![],
             listen: None, voice: None, score: None, gravity: None,
             lenses: vec![], react: None, defines: vec![],
+            span: Span::default(),
         };
         let output = generate_fragment(&cin, &[]);
         assert!(output.contains("vec4 final_color"));
@@ -703,8 +742,26 @@ mod tests {
     #[test]
     fn glsl_palette_emits_iq_helper_and_lookup() {
         let cin = make_cinematic(vec![
-            Stage { name: "fbm".into(), args: vec![Arg { name: None, value: Expr::Number(2.0) }] },
-            Stage { name: "palette".into(), args: vec![Arg { name: None, value: Expr::Ident("fire".into()) }] },
+            Stage {
+                name: "fbm".into(),
+                args: vec![Arg {
+                    name: None,
+                    value: Expr::Number(2.0, Span::default()),
+                    span: Span::default(),
+                }],
+                span: Span::default(),
+                expanded_from: vec![],
+            },
+            Stage {
+                name: "palette".into(),
+                args: vec![Arg {
+                    name: None,
+                    value: Expr::Ident("fire".into(), Span::default()),
+                    span: Span::default(),
+                }],
+                span: Span::default(),
+                expanded_from: vec![],
+            },
         ]);
         let output = generate_fragment(&cin, &[]);
         // Helper function emitted with C-style params
@@ -718,8 +775,8 @@ mod tests {
     #[test]
     fn glsl_fbm_correct_types() {
         let cin = make_cinematic(vec![
-            Stage { name: "fbm".into(), args: vec![] },
-            Stage { name: "glow".into(), args: vec![] },
+            Stage { name: "fbm".into(), args: vec![], span: Span::default(), expanded_from: vec![] },
+            Stage { name: "glow".into(), args: vec![], span: Span::default(), expanded_from: vec![] },
         ]);
         let output = generate_fragment(&cin, &[]);
         // hash2: vec2 param, vec3 local
@@ -0,0 +1,363 @@
+//! `gradient` — linear, radial, angular and diamond color ramps.
+//!
+//! `color_a` and `color_b` are the endpoints: any color (a name, `#ff8800`,
+//! `rgb()`, `hsl()`, `oklch()` or `[r, g, b]`), a hex string (`"#ff8800"`)
+//! or a scalar (grey). Either may also be a list of colors; the stops of
+//! both are spread evenly in order, so `gradient([midnight, ember, gold])`
+//! is a three-stop ramp. The ramp runs black to white when no colors are
+//! given.
+//!
+//! `mode` picks the shape and `angle` (radians) turns it:
+//!
+//! - `linear` (alias `y`) runs bottom to top; angle turns it clockwise
+//! - `horizontal` (alias `x`) runs left to right
+//! - `radial` runs from the center outwards
+//! - `angular` sweeps clockwise, starting at the top
+//! - `diamond` grows a square outwards; angle rotates it
+//!
+//! ```text
+//! var color_result: vec4<f32>;
+//! { let gr_a = <angle>;
+//!   let gr_t = clamp(<t>, 0.0, 1.0);
+//!   var gr_c = <stop 0>;
+//!   gr_c = mix(gr_c, <stop 1>, <segment 1 of t>);
+//!   ...
+//!   color_result = vec4<f32>(gr_c, 1.0); }
+//! ```
+
+use crate::ast::{walk_stages, Arg, Cinematic, Expr, LayerBody, Stage};
+use crate::codegen::color;
+use crate::codegen::stages::{find_arg, get_arg, get_arg_glsl, resolve_arg, resolve_arg_glsl};
+use crate::error::{suggest_similar, CompileError, Span};
+
+/// The shape of a gradient ramp.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GradientMode {
+    Linear,
+    Horizontal,
+    Radial,
+    Angular,
+    Diamond,
+}
+
+/// Accepted `mode` names, including the `y` and `x` aliases.
+pub const GRADIENT_MODES: &[&str] =
+    &["linear", "y", "vertical", "horizontal", "x", "radial", "angular", "diamond"];
+
+impl GradientMode {
+    pub fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "linear" | "y" | "vertical" => Self::Linear,
+            "horizontal" | "x" => Self::Horizontal,
+            "radial" => Self::Radial,
+            "angular" => Self::Angular,
+            "diamond" => Self::Diamond,
+            _ => return None,
+        })
+    }
+
+    /// Ramp position before clamping, from `uv`, `p` and the angle `a`
+    /// (the CPU renderer's copy of [`Self::expr`], shader literals included).
+    #[allow(clippy::approx_constant)]
+    pub fn t(self, uv: [f32; 2], p: [f32; 2], a: f32) -> f32 {
+        let (sin, cos) = a.sin_cos();
+        match self {
+            Self::Linear => (uv[0] * sin + uv[1] * cos) * 0.5 + 0.5,
+            Self::Horizontal => (uv[0] * cos - uv[1] * sin) * 0.5 + 0.5,
+            Self::Radial => (p[0] * p[0] + p[1] * p[1]).sqrt(),
+            Self::Angular => {
+                let turns = (p[0].atan2(p[1]) - a) / 6.28318;
+                turns - turns.floor()
+            }
+            Self::Diamond => (p[0] * cos - p[1] * sin).abs() + (p[0] * sin + p[1] * cos).abs(),
+        }
+    }
+
+    /// [`Self::t`] in WGSL (`glsl = false`) or GLSL, angle in `gr_a`.
+    fn expr(self, glsl: bool) -> String {
+        let atan2 = if glsl { "atan" } else { "atan2" };
+        match self {
+            Self::Linear => "(uv.x * sin(gr_a) + uv.y * cos(gr_a)) * 0.5 + 0.5".into(),
+            Self::Horizontal => "(uv.x * cos(gr_a) - uv.y * sin(gr_a)) * 0.5 + 0.5".into(),
+            Self::Radial => "length(p)".into(),
+            Self::Angular => format!("fract(({atan2}(p.x, p.y) - gr_a) / 6.28318)"),
+            Self::Diamond => "abs(p.x * cos(gr_a) - p.y * sin(gr_a)) \
+                              + abs(p.x * sin(gr_a) + p.y * cos(gr_a))"
+                .into(),
+        }
+    }
+}
+
+/// A `gradient` stage's mode and color stops, hex strings already
+/// converted to `[r, g, b]` arrays.
+#[derive(Debug, Clone)]
+pub struct Gradient {
+    pub mode: GradientMode,
+    pub stops: Vec<Expr>,
+}
+
+impl Gradient {
+    /// Read a `gradient` stage. Unknown modes fall back to linear and bad
+    /// hex strings to black; [`validate_gradients`] rejects both first.
+    pub fn of(stage: &Stage) -> Self {
+        let mode = match find_arg(&stage.args, "mode", 2).map(|a| &a.value) {
+            Some(Expr::String(name, _)) | Some(Expr::Ident(name, _)) => {
+                GradientMode::from_name(name).unwrap_or(GradientMode::Linear)
+            }
+            _ => GradientMode::Linear,
+        };
+        let color_a = find_arg(&stage.args, "color_a", 0).map(|a| &a.value);
+        let color_b = find_arg(&stage.args, "color_b", 1).map(|a| &a.value);
+        let mut stops = match color_a {
+            Some(value) => stop_list(value),
+            None => vec![Expr::Ident("black".into(), Span::default())],
+        };
+        match color_b {
+            Some(value) => stops.extend(stop_list(value)),
+            None if stops.len() < 2 => stops.push(Expr::Ident("white".into(), Span::default())),
+            None => {}
+        }
+        Self { mode, stops }
+    }
+}
+
+/// Whether a list element is a color stop rather than a color channel.
+fn is_stop(value: &Expr) -> bool {
+    color::is_color(value) || matches!(value, Expr::String(_, _) | Expr::Array(_, _))
+}
+
+/// The colors in one color argument: its elements when it is a list of
+/// colors, otherwise the argument itself.
+pub(crate) fn colors_in(value: &Expr) -> &[Expr] {
+    match value {
+        Expr::Array(elems, _) if elems.iter().any(is_stop) => elems,
+        other => std::slice::from_ref(other),
+    }
+}
+
+fn stop_list(value: &Expr) -> Vec<Expr> {
+    colors_in(value).iter().map(stop).collect()
+}
+
+fn stop(value: &Expr) -> Expr {
+    match value {
+        Expr::String(s, _) => color::to_expr(color::parse_hex(s).unwrap_or([0.0; 3])),
+        other => other.clone(),
+    }
+}
+
+/// Emit a gradient stage (WGSL).
+pub(crate) fn emit_wgsl(s: &mut String, stage: &Stage, indent: &str) {
+    emit(s, stage, indent, false);
+}
+
+/// Emit a gradient stage (GLSL).
+pub(crate) fn emit_glsl(s: &mut String, stage: &Stage, indent: &str) {
+    emit(s, stage, indent, true);
+}
+
+fn emit(s: &mut String, stage: &Stage, indent: &str, glsl: bool) {
+    let inner = format!("{indent}    ");
+    let gradient = Gradient::of(stage);
+    let (let_f, var_c, vec4) =
+        if glsl { ("float", "vec3", "vec4") } else { ("let", "var", "vec4<f32>") };
+    let angle = if glsl {
+        get_arg_glsl(&stage.args, "angle", 3, "gradient")
+    } else {
+        get_arg(&stage.args, "angle", 3, "gradient")
+    };
+
+    if glsl {
+        s.push_str(&format!("{indent}vec4 color_result;\n"));
+    } else {
+        s.push_str(&format!("{indent}var color_result: vec4<f32>;\n"));
+    }
+    s.push_str(&format!("{indent}{{ // gradient\n"));
+    s.push_str(&format!("{inner}{let_f} gr_a = {angle};\n"));
+    s.push_str(&format!(
+        "{inner}{let_f} gr_t = clamp({}, 0.0, 1.0);\n",
+        gradient.mode.expr(glsl)
+    ));
+    let segments = gradient.stops.len().saturating_sub(1);
+    for (i, value) in gradient.stops.iter().enumerate() {
+        let color = lower_stop(value, glsl);
+        if i == 0 {
+            s.push_str(&format!("{inner}{var_c} gr_c = {color};\n"));
+        } else if segments == 1 {
+            s.push_str(&format!("{inner}gr_c = mix(gr_c, {color}, gr_t);\n"));
+        } else {
+            s.push_str(&format!(
+                "{inner}gr_c = mix(gr_c, {color}, clamp(gr_t * {segments}.0 - {}.0, 0.0, 1.0));\n",
+                i - 1
+            ));
+        }
+    }
+    s.push_str(&format!("{inner}color_result = {vec4}(gr_c, 1.0); }}\n"));
+}
+
+/// Lower one stop to a `vec3`, splatting scalars to grey.
+fn lower_stop(value: &Expr, glsl: bool) -> String {
+    let arg = Arg { name: None, value: value.clone(), span: Span::default() };
+    let lowered = if glsl { resolve_arg_glsl(&arg) } else { resolve_arg(&arg) };
+    match (color::is_color(value), glsl) {
+        (true, _) => lowered,
+        (false, true) => format!("vec3({lowered})"),
+        (false, false) => format!("vec3<f32>({lowered})"),
+    }
+}
+
+/// Reject unknown gradient modes, malformed hex colors and color arrays
+/// that are not `[r, g, b]`.
+pub fn validate_gradients(cinematic: &Cinematic) -> Result<(), CompileError> {
+    let mut gradients = Vec::new();
+    for layer in &cinematic.layers {
+        if let LayerBody::Pipeline(pipeline) = &layer.body {
+            walk_stages(pipeline, &mut |stage| {
+                if stage.name == "gradient" {
+                    gradients.push(stage);
+                }
+            });
+        }
+    }
+    gradients.into_iter().try_for_each(validate_gradient)
+}
+
+fn validate_gradient(stage: &Stage) -> Result<(), CompileError> {
+    if let Some(arg) = find_arg(&stage.args, "mode", 2) {
+        validate_mode(stage, arg)?;
+    }
+    for (pos, slot) in ["color_a", "color_b"].into_iter().enumerate() {
+        let Some(arg) = find_arg(&stage.args, slot, pos) else { continue };
+        for value in colors_in(&arg.value) {
+            match value {
+                Expr::String(s, _) if color::parse_hex(s).is_none() => {
+                    return Err(stage.locate(CompileError::validation(format!(
+                        "gradient: '{s}' is not a hex color"
+                    )))
+                    .with_help("write hex colors as \"#rgb\", \"#rrggbb\" or \"#rrggbbaa\""));
+                }
+                Expr::Array(channels, _) if channels.len() != 3 => {
+                    return Err(stage.locate(CompileError::validation(format!(
+                        "gradient: a color array needs 3 components, found {}",
+                        channels.len()
+                    )))
+                    .with_help("write colors as [r, g, b] with channels in 0..1"));
+                }
+                _ => {}
+            }
+        }
+    }
+    Ok(())
+}
+
+fn validate_mode(stage: &Stage, arg: &Arg) -> Result<(), CompileError> {
+    let (name, quoted) = match &arg.value {
+        Expr::String(name, _) => (name.as_str(), true),
+        Expr::Ident(name, _) => (name.as_str(), false),
+        _ => {
+            return Err(stage
+                .locate(CompileError::validation("gradient: mode expects a mode name"))
+                .with_help(format!("use one of: {}", GRADIENT_MODES.join(", "))));
+        }
+    };
+    if GradientMode::from_name(name).is_some() {
+        return Ok(());
+    }
+    let err = stage.locate(CompileError::validation(format!(
+        "gradient: unknown mode '{name}'"
+    )));
+    Err(match suggest_similar(name, GRADIENT_MODES) {
+        Some(s) => {
+            let replacement = if quoted { format!("\"{s}\"") } else { s.into() };
+            err.with_help(format!("did you mean '{s}'?")).with_fix(arg.value.span(), replacement)
+        }
+        None => err.with_help(format!("use one of: {}", GRADIENT_MODES.join(", "))),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn first_stage(pipeline: &str) -> Stage {
+        let src = format!(r#"cinematic "c" {{ layer main {{ {pipeline} }} }}"#);
+        let mut cin = crate::compile_to_ast(&src).unwrap().cinematics.remove(0);
+        match cin.layers.remove(0).body {
+            LayerBody::Pipeline(mut stages) => stages.remove(0),
+            _ => panic!("expected pipeline"),
+        }
+    }
+
+    fn validate(pipeline: &str) -> Result<(), CompileError> {
+        let src = format!(r#"cinematic "c" {{ layer main {{ {pipeline} }} }}"#);
+        validate_gradients(&crate::compile_to_ast(&src).unwrap().cinematics[0])
+    }
+
+    #[test]
+    fn default_gradient_is_the_vertical_grey_ramp() {
+        let stage = first_stage("gradient()");
+        let mut s = String::new();
+        emit_wgsl(&mut s, &stage, "");
+        assert!(s.contains("    let gr_a = 0.000000;\n"));
+        assert!(s.contains("gr_t = clamp((uv.x * sin(gr_a) + uv.y * cos(gr_a)) * 0.5 + 0.5,"));
+        assert!(s.contains("    var gr_c = vec3f(0.0, 0.0, 0.0);\n"));
+        assert!(s.contains("    gr_c = mix(gr_c, vec3f(1.0, 1.0, 1.0), gr_t);\n"));
+        assert!(s.ends_with("    color_result = vec4<f32>(gr_c, 1.0); }\n"));
+    }
+
+    #[test]
+    fn stops_spread_evenly_across_both_arguments() {
+        let stage = first_stage(r##"gradient([midnight, "#f80"], [1.0, 0.5, 0.0], "radial")"##);
+        let gradient = Gradient::of(&stage);
+        assert_eq!(gradient.mode, GradientMode::Radial);
+        assert_eq!(gradient.stops.len(), 3);
+
+        let mut s = String::new();
+        emit_glsl(&mut s, &stage, "");
+        assert!(s.contains("    float gr_t = clamp(length(p), 0.0, 1.0);\n"));
+        assert!(s.contains("    vec3 gr_c = vec3(0.039, 0.039, 0.118);\n"));
+        assert!(s.contains("gr_c = mix(gr_c, vec3(1.0, 0.533, 0.0), clamp(gr_t * 2.0 - 0.0, 0.0, 1.0));"));
+        assert!(s.contains("gr_c = mix(gr_c, vec3(1.0, 0.5, 0.0), clamp(gr_t * 2.0 - 1.0, 0.0, 1.0));"));
+        assert!(s.ends_with("    color_result = vec4(gr_c, 1.0); }\n"));
+    }
+
+    #[test]
+    fn scalar_endpoints_are_grey_and_angle_is_lowered() {
+        let stage = first_stage(r#"gradient(0.2, ember, mode: "angular", angle: time)"#);
+        let mut s = String::new();
+        emit_wgsl(&mut s, &stage, "");
+        assert!(s.contains("let gr_a = time;"));
+        assert!(s.contains("fract((atan2(p.x, p.y) - gr_a) / 6.28318)"));
+        assert!(s.contains("var gr_c = vec3<f32>(0.200000);"));
+        assert!(s.contains("gr_c = mix(gr_c, vec3f(0.898, 0.318, 0.129), gr_t);"));
+    }
+
+    #[test]
+    fn modes_and_aliases() {
+        for name in GRADIENT_MODES {
+            assert!(GradientMode::from_name(name).is_some(), "{name}");
+        }
+        assert_eq!(GradientMode::from_name("y"), Some(GradientMode::Linear));
+        assert_eq!(GradientMode::from_name("x"), Some(GradientMode::Horizontal));
+        assert_eq!(GradientMode::from_name("conic"), None);
+    }
+
+    #[test]
+    fn rejects_unknown_modes_with_a_quoted_fix() {
+        let err = validate(r#"gradient(black, white, "radail")"#).unwrap_err();
+        assert!(err.to_string().contains("unknown mode 'radail'"), "{err}");
+        assert_eq!(err.help(), Some("did you mean 'radial'?"));
+        let fix = err.to_diagnostic().fix.unwrap();
+        assert_eq!(fix.replacement, "\"radial\"");
+        assert_eq!(fix.span.end - fix.span.start, "\"radail\"".len());
+    }
+
+    #[test]
+    fn rejects_bad_hex_and_short_color_arrays() {
+        let err = validate(r##"gradient("#ff88zz", white)"##).unwrap_err();
+        assert!(err.to_string().contains("'#ff88zz' is not a hex color"), "{err}");
+        let err = validate("gradient([black, [1.0, 0.5]])").unwrap_err();
+        assert!(err.to_string().contains("needs 3 components, found 2"), "{err}");
+        assert!(validate(r##"gradient(["#000", gold, white], mode: "diamond")"##).is_ok());
+    }
+}
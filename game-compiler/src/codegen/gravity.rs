@@ -4,18 +4,19 @@
 //! velocities, and force calculation workgroups.
 
 use crate::ast::{BinOp, BoundsMode, Expr, GravityBlock};
+use crate::codegen::expr;
 
 /// Compile a force-law `Expr` into inline WGSL, treating `dist` as the
 /// inter-particle distance and `time` as the simulation clock.
 fn force_expr_to_wgsl(expr: &Expr) -> String {
     match expr {
-        Expr::Number(n) => format!("{n:.6}"),
-        Expr::Ident(name) => match name.as_str() {
+        Expr::Number(n, _) => format!("{n:.6}"),
+        Expr::Ident(name, _) => match name.as_str() {
             "dist" => "dist".to_string(),
             "time" => "params.time".to_string(),
             _ => name.clone(),
         },
-        Expr::BinOp { op, left, right } => {
+        Expr::BinOp { op, left, right, .. } => {
             let l = force_expr_to_wgsl(left);
             let r = force_expr_to_wgsl(right);
             match op {
@@ -24,17 +25,21 @@ fn force_expr_to_wgsl(expr: &Expr) -> String {
                 BinOp::Mul => format!("({l} * {r})"),
                 BinOp::Div => format!("({l} / {r})"),
                 BinOp::Pow => format!("pow({l}, {r})"),
-                BinOp::Gt => format!("select(0.0, 1.0, {l} > {r})"),
-                BinOp::Lt => format!("select(0.0, 1.0, {l} < {r})"),
+                BinOp::Mod => format!("({l} % {r})"),
+                // Comparisons and logic are 0.0 or 1.0, so they can scale a force
+                BinOp::And => format!("select(0.0, 1.0, {l} != 0.0 && {r} != 0.0)"),
+                BinOp::Or => format!("select(0.0, 1.0, {l} != 0.0 || {r} != 0.0)"),
+                _ => format!("select(0.0, 1.0, {l} {} {r})", expr::op_symbol(op)),
             }
         }
-        Expr::Neg(inner) => format!("(-{})", force_expr_to_wgsl(inner)),
-        Expr::Call { name, args } => {
+        Expr::Neg(inner, _) => format!("(-{})", force_expr_to_wgsl(inner)),
+        Expr::Not(inner, _) => format!("select(0.0, 1.0, {} == 0.0)", force_expr_to_wgsl(inner)),
+        Expr::Call { name, args, .. } => {
             let arg_strs: Vec<String> =
                 args.iter().map(|a| force_expr_to_wgsl(&a.value)).collect();
             format!("{}({})", name, arg_strs.join(", "))
         }
-        Expr::Paren(inner) => format!("({})", force_expr_to_wgsl(inner)),
+        Expr::Paren(inner, _) => format!("({})", force_expr_to_wgsl(inner)),
         // Fallback for unsupported expression types
         _ => "1.0".to_string(),
     }
@@ -174,9 +179,7 @@ pub fn generate_compute_runtime_js(particle_count: u32) -> String {
     s.push_str("    const pass = enc.beginComputePass();\n");
     s.push_str("    pass.setPipeline(this._pipeline);\n");
     s.push_str("    pass.setBindGroup(0, bg);\n");
-    s.push_str(&format!(
-        "    pass.dispatchWorkgroups(Math.ceil(this._count / 64));\n"
-    ));
+    s.push_str("    pass.dispatchWorkgroups(Math.ceil(this._count / 64));\n");
     s.push_str("    pass.end();\n");
     s.push_str("    device.queue.submit([enc.finish()]);\n\n");
 
@@ -199,18 +202,36 @@ mod tests {
         GravityBlock {
             force_law: Expr::BinOp {
                 op: BinOp::Div,
-                left: Box::new(Expr::Number(1.0)),
+                left: Box::new(Expr::Number(1.0, Span::default())),
                 right: Box::new(Expr::BinOp {
                     op: BinOp::Mul,
-                    left: Box::new(Expr::Ident("dist".into())),
-                    right: Box::new(Expr::Ident("dist".into())),
+                    left: Box::new(Expr::Ident("dist".into(), Span::default())),
+                    right: Box::new(Expr::Ident("dist".into(), Span::default())),
+                    span: Span::default(),
                 }),
+                span: Span::default(),
             },
             damping: 0.995,
             bounds: BoundsMode::Reflect,
+            span: Span::default(),
         }
     }
 
+    #[test]
+    fn force_law_comparisons_and_logic_are_numeric() {
+        let law = |src: &str| {
+            let tokens = crate::lexer::lex(src).unwrap();
+            force_expr_to_wgsl(&crate::parser::Parser::new(tokens).parse_expr().unwrap())
+        };
+        assert_eq!(law("dist >= 0.5"), "select(0.0, 1.0, dist >= 0.500000)");
+        assert_eq!(
+            law("dist < 0.5 && !dist"),
+            "select(0.0, 1.0, select(0.0, 1.0, dist < 0.500000) != 0.0 \
+             && select(0.0, 1.0, dist == 0.0) != 0.0)"
+        );
+        assert_eq!(law("time % 2.0"), "(params.time % 2.000000)");
+    }
+
     #[test]
     fn compute_shader_has_workgroup() {
         let wgsl = generate_compute_wgsl(&make_gravity(), 200);
@@ -261,11 +282,13 @@ mod tests {
         let gravity = GravityBlock {
             force_law: Expr::BinOp {
                 op: BinOp::Div,
-                left: Box::new(Expr::Number(1.0)),
-                right: Box::new(Expr::Ident("dist".into())),
+                left: Box::new(Expr::Number(1.0, Span::default())),
+                right: Box::new(Expr::Ident("dist".into(), Span::default())),
+                span: Span::default(),
             },
             damping: 0.99,
             bounds: BoundsMode::Reflect,
+            span: Span::default(),
         };
         let wgsl = generate_compute_wgsl(&gravity, 1024);
         assert!(
@@ -286,6 +309,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::approx_constant)]
     fn force_law_with_sin_compiled() {
         let gravity = GravityBlock {
             force_law: Expr::Call {
@@ -294,13 +318,17 @@ mod tests {
                     name: None,
                     value: Expr::BinOp {
                         op: BinOp::Mul,
-                        left: Box::new(Expr::Ident("dist".into())),
-                        right: Box::new(Expr::Number(3.14159)),
+                        left: Box::new(Expr::Ident("dist".into(), Span::default())),
+                        right: Box::new(Expr::Number(3.14159, Span::default())),
+                        span: Span::default(),
                     },
+                    span: Span::default(),
                 }],
+                span: Span::default(),
             },
             damping: 0.99,
             bounds: BoundsMode::Reflect,
+            span: Span::default(),
         };
         let wgsl = generate_compute_wgsl(&gravity, 256);
         assert!(
@@ -315,15 +343,18 @@ mod tests {
         let gravity = GravityBlock {
             force_law: Expr::Neg(Box::new(Expr::BinOp {
                 op: BinOp::Div,
-                left: Box::new(Expr::Number(1.0)),
+                left: Box::new(Expr::Number(1.0, Span::default())),
                 right: Box::new(Expr::BinOp {
                     op: BinOp::Mul,
-                    left: Box::new(Expr::Ident("dist".into())),
-                    right: Box::new(Expr::Ident("dist".into())),
+                    left: Box::new(Expr::Ident("dist".into(), Span::default())),
+                    right: Box::new(Expr::Ident("dist".into(), Span::default())),
+                    span: Span::default(),
                 }),
-            })),
+                span: Span::default(),
+            }), Span::default()),
             damping: 0.99,
             bounds: BoundsMode::Reflect,
+            span: Span::default(),
         };
         let wgsl = generate_compute_wgsl(&gravity, 256);
         assert!(
@@ -338,15 +369,18 @@ mod tests {
         let gravity = GravityBlock {
             force_law: Expr::BinOp {
                 op: BinOp::Mul,
-                left: Box::new(Expr::Ident("time".into())),
+                left: Box::new(Expr::Ident("time".into(), Span::default())),
                 right: Box::new(Expr::BinOp {
                     op: BinOp::Div,
-                    left: Box::new(Expr::Number(1.0)),
-                    right: Box::new(Expr::Ident("dist".into())),
+                    left: Box::new(Expr::Number(1.0, Span::default())),
+                    right: Box::new(Expr::Ident("dist".into(), Span::default())),
+                    span: Span::default(),
                 }),
+                span: Span::default(),
             },
             damping: 0.99,
             bounds: BoundsMode::Reflect,
+            span: Span::default(),
         };
         let wgsl = generate_compute_wgsl(&gravity, 256);
         assert!(
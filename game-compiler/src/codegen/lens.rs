@@ -0,0 +1,335 @@
+//! Lens codegen — compiles `lens { ... post: ... }` blocks into a second,
+//! full-screen render pass.
+//!
+//! When a cinematic declares a post pipeline (or any of the `exposure`,
+//! `zoom`, `offset` properties), the layers render into an offscreen target
+//! and a post fragment shader samples it, applies the lens transform, then
+//! runs the Color -> Color `post` stages before writing to the canvas.
+
+use crate::ast::{Cinematic, Expr, Stage};
+use crate::builtins::{self, ShaderState};
+use crate::codegen::expr::extract_number;
+use crate::codegen::{blend, glsl, wgsl, UniformInfo};
+use crate::error::{CompileError, ErrorCode};
+
+/// Lens properties exposed as uniforms: (property, uniform name, default).
+const LENS_PROPERTIES: &[(&str, &str, f64)] = &[
+    ("exposure", "lens_exposure", 1.0),
+    ("zoom", "lens_zoom", 1.0),
+];
+
+/// `offset` is a 2D property and expands to two uniforms.
+const LENS_OFFSET_UNIFORMS: [&str; 2] = ["lens_offset_x", "lens_offset_y"];
+
+/// Check whether a cinematic needs the post pass.
+pub fn has_post_pass(cinematic: &Cinematic) -> bool {
+    cinematic.lenses.iter().any(|lens| {
+        !lens.post.is_empty()
+            || lens.properties.iter().any(|p| {
+                p.name == "offset" || LENS_PROPERTIES.iter().any(|(name, _, _)| *name == p.name)
+            })
+    })
+}
+
+/// All `post` stages across the cinematic's lenses, in declaration order.
+pub fn post_stages(cinematic: &Cinematic) -> Vec<&Stage> {
+    cinematic.lenses.iter().flat_map(|lens| lens.post.iter()).collect()
+}
+
+/// Uniforms backing the lens properties. Empty when there is no post pass.
+///
+/// Later lenses override properties set by earlier ones.
+pub fn lens_uniforms(cinematic: &Cinematic) -> Vec<UniformInfo> {
+    if !has_post_pass(cinematic) {
+        return Vec::new();
+    }
+
+    let mut uniforms: Vec<UniformInfo> = LENS_PROPERTIES
+        .iter()
+        .map(|(_, name, default)| UniformInfo { name: (*name).into(), default: *default })
+        .chain(LENS_OFFSET_UNIFORMS.iter().map(|name| UniformInfo { name: (*name).into(), default: 0.0 }))
+        .collect();
+
+    for prop in cinematic.lenses.iter().flat_map(|lens| &lens.properties) {
+        if let Some(idx) = LENS_PROPERTIES.iter().position(|(name, _, _)| *name == prop.name) {
+            if let Some(v) = extract_number(&prop.value) {
+                uniforms[idx].default = v;
+            }
+        } else if prop.name == "offset" {
+            if let Some((x, y)) = offset_components(&prop.value) {
+                uniforms[LENS_PROPERTIES.len()].default = x;
+                uniforms[LENS_PROPERTIES.len() + 1].default = y;
+            }
+        }
+    }
+    uniforms
+}
+
+/// `offset: [x, y]` or a single number applied to both axes.
+fn offset_components(value: &Expr) -> Option<(f64, f64)> {
+    match value {
+        Expr::Array(elems, _) if elems.len() == 2 => {
+            Some((extract_number(&elems[0])?, extract_number(&elems[1])?))
+        }
+        other => extract_number(other).map(|v| (v, v)),
+    }
+}
+
+/// Validate lens blocks: `post` must be Color -> Color builtins and the
+/// uniform-backed properties must be numeric literals.
+pub fn validate_lenses(cinematic: &Cinematic) -> Result<(), CompileError> {
+    for lens in &cinematic.lenses {
+        let label = lens.name.as_deref().unwrap_or("(unnamed)");
+
+        for stage in &lens.post {
+            let builtin = builtins::lookup(&stage.name).ok_or_else(|| {
+                let mut err = CompileError::validation(format!(
+                    "lens '{label}': unknown post stage '{}'",
+                    stage.name
+                ))
+                .with_code(ErrorCode::E001);
+                if let Some(suggestion) = builtins::suggest(&stage.name) {
+                    err = err
+                        .with_help(format!("did you mean '{suggestion}'?"))
+                        .with_fix(stage.name_span(), suggestion);
+                }
+                stage.locate(err)
+            })?;
+            if builtin.input != ShaderState::Color || builtin.output != ShaderState::Color {
+                return Err(stage.locate(
+                    CompileError::validation(format!(
+                        "lens '{label}': post stage '{}' is {} -> {}, but post stages must be Color -> Color",
+                        stage.name, builtin.input, builtin.output
+                    ))
+                    .with_code(ErrorCode::E002)
+                    .with_help("post stages run on the finished frame; use tint, bloom, grain, vignette, chromatic, tonemap, ..."),
+                ));
+            }
+        }
+
+        for prop in &lens.properties {
+            let numeric = match prop.name.as_str() {
+                "offset" => offset_components(&prop.value).is_some(),
+                name if LENS_PROPERTIES.iter().any(|(n, _, _)| *n == name) => {
+                    extract_number(&prop.value).is_some()
+                }
+                _ => true,
+            };
+            if !numeric {
+                return Err(CompileError::validation(format!(
+                    "lens '{label}': property '{}' must be a number{}",
+                    prop.name,
+                    if prop.name == "offset" { " or [x, y]" } else { "" }
+                ))
+                .with_code(ErrorCode::E002)
+                .with_span(prop.span));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Generate the WGSL post-pass fragment shader.
+///
+/// Binds the shared uniform buffer at group 0 and the offscreen scene
+/// texture + sampler at group 1.
+pub fn generate_post_wgsl(cinematic: &Cinematic, uniforms: &[UniformInfo]) -> String {
+    let mut s = String::with_capacity(4096);
+    wgsl::emit_uniform_block(&mut s, uniforms);
+    s.push_str("@group(1) @binding(0) var scene_tex: texture_2d<f32>;\n");
+    s.push_str("@group(1) @binding(1) var scene_sampler: sampler;\n\n");
+
+    s.push_str("struct VertexOutput {\n");
+    s.push_str("    @builtin(position) pos: vec4<f32>,\n");
+    s.push_str("    @location(0) uv: vec2<f32>,\n");
+    s.push_str("};\n\n");
+
+    s.push_str("@fragment\n");
+    s.push_str("fn fs_main(input: VertexOutput) -> @location(0) vec4<f32> {\n");
+    wgsl::emit_prelude(&mut s, uniforms);
+
+    // Lens transform: offset then zoom about the center, in centered uv units
+    s.push_str("    let lens_uv = (uv - vec2<f32>(lens_offset_x, lens_offset_y)) / lens_zoom * 0.5 + 0.5;\n");
+    s.push_str("    var p = vec2<f32>(uv.x * aspect, uv.y);\n");
+    // Texture rows run top-down in WebGPU, uv runs bottom-up
+    s.push_str("    var color_result = textureSample(scene_tex, scene_sampler, vec2<f32>(lens_uv.x, 1.0 - lens_uv.y));\n");
+    s.push_str("    color_result = vec4<f32>(color_result.rgb * lens_exposure, color_result.a);\n");
+
+    for stage in post_stages(cinematic) {
+        wgsl::emit_wgsl_stage(&mut s, stage, "    ");
+    }
+
+    blend::emit_alpha(&mut s, "color_result", "    ");
+    s.push_str("    return color_result;\n");
+    s.push_str("}\n");
+    s
+}
+
+/// Generate the GLSL ES 3.0 post-pass fragment shader.
+///
+/// Reads the offscreen scene from the `u_scene` sampler (texture unit 0).
+pub fn generate_post_glsl(cinematic: &Cinematic, uniforms: &[UniformInfo]) -> String {
+    let mut s = String::with_capacity(4096);
+    s.push_str("#version 300 es\nprecision highp float;\n\n");
+    glsl::emit_uniform_decls(&mut s, uniforms);
+    s.push_str("uniform sampler2D u_scene;\n");
+    s.push_str("\nin vec2 v_uv;\nout vec4 fragColor;\n\n");
+
+    s.push_str("void main(){\n");
+    glsl::emit_prelude(&mut s, uniforms);
+
+    s.push_str("    vec2 lens_uv = (uv - vec2(lens_offset_x, lens_offset_y)) / lens_zoom * 0.5 + 0.5;\n");
+    s.push_str("    vec2 p = vec2(uv.x * aspect, uv.y);\n");
+    s.push_str("    vec4 color_result = texture(u_scene, lens_uv);\n");
+    s.push_str("    color_result = vec4(color_result.rgb * lens_exposure, color_result.a);\n");
+
+    for stage in post_stages(cinematic) {
+        glsl::emit_glsl_stage(&mut s, stage, "    ");
+    }
+
+    blend::emit_alpha(&mut s, "color_result", "    ");
+    s.push_str("    fragColor = color_result;\n");
+    s.push_str("}\n");
+    s
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ast::*;
+
+    fn num_arg(v: f64) -> Arg {
+        Arg { name: None, value: Expr::Number(v, Span::default()), span: Span::default() }
+    }
+
+    fn lens_cinematic(properties: Vec<Param>, post: Vec<Stage>) -> Cinematic {
+        Cinematic {
+            name: "test".into(),
+            layers: vec![Layer {
+                name: "main".into(),
+                opts: vec![],
+                memory: None,
+                cast: None,
+                blend: None,
+                body: LayerBody::Pipeline(vec![
+                    Stage { name: "circle".into(), args: vec![], span: Span::default(), expanded_from: vec![] },
+                    Stage { name: "glow".into(), args: vec![], span: Span::default(), expanded_from: vec![] },
+                ]),
+                span: Span::default(),
+            }],
+            arcs: vec![],
+            resonates: vec![],
+            listen: None, voice: None, score: None, gravity: None,
+            lenses: vec![Lens { name: None, properties, post, span: Span::default() }],
+            react: None, defines: vec![],
+            span: Span::default(),
+        }
+    }
+
+    fn prop(name: &str, value: Expr) -> Param {
+        Param { name: name.into(), value, modulation: None, temporal_ops: vec![], span: Span::default() }
+    }
+
+    #[test]
+    fn no_lens_no_post_pass() {
+        let mut cin = lens_cinematic(vec![], vec![]);
+        cin.lenses.clear();
+        assert!(!has_post_pass(&cin));
+        assert!(lens_uniforms(&cin).is_empty());
+    }
+
+    #[test]
+    fn mode_only_lens_has_no_post_pass() {
+        let mode = prop("mode", Expr::Ident("flat".into(), Span::default()));
+        let cin = lens_cinematic(vec![mode], vec![]);
+        assert!(!has_post_pass(&cin));
+    }
+
+    #[test]
+    fn lens_properties_become_uniform_defaults() {
+        let cin = lens_cinematic(
+            vec![
+                prop("exposure", Expr::Number(1.5, Span::default())),
+                prop(
+                    "offset",
+                    Expr::Array(
+                        vec![
+                            Expr::Number(0.1, Span::default()),
+                            Expr::Number(-0.2, Span::default()),
+                        ],
+                        Span::default(),
+                    ),
+                ),
+            ],
+            vec![],
+        );
+        let uniforms = lens_uniforms(&cin);
+        let get = |n: &str| uniforms.iter().find(|u| u.name == n).unwrap().default;
+        assert_eq!(get("lens_exposure"), 1.5);
+        assert_eq!(get("lens_zoom"), 1.0);
+        assert_eq!(get("lens_offset_x"), 0.1);
+        assert_eq!(get("lens_offset_y"), -0.2);
+    }
+
+    #[test]
+    fn post_accepts_color_stages() {
+        let cin = lens_cinematic(
+            vec![],
+            vec![
+                Stage { name: "bloom".into(), args: vec![num_arg(0.3), num_arg(2.0)], span: Span::default(), expanded_from: vec![] },
+                Stage { name: "vignette".into(), args: vec![], span: Span::default(), expanded_from: vec![] },
+            ],
+        );
+        assert!(validate_lenses(&cin).is_ok());
+    }
+
+    #[test]
+    fn post_rejects_non_color_stage() {
+        let cin = lens_cinematic(vec![], vec![Stage { name: "circle".into(), args: vec![], span: Span::default(), expanded_from: vec![] }]);
+        let err = validate_lenses(&cin).unwrap_err();
+        assert!(err.to_string().contains("must be Color -> Color"), "{err}");
+    }
+
+    #[test]
+    fn post_unknown_stage_suggests() {
+        let cin = lens_cinematic(vec![], vec![Stage { name: "blom".into(), args: vec![], span: Span::default(), expanded_from: vec![] }]);
+        let err = validate_lenses(&cin).unwrap_err();
+        assert_eq!(err.help(), Some("did you mean 'bloom'?"));
+    }
+
+    #[test]
+    fn non_numeric_exposure_rejected() {
+        let exposure = prop("exposure", Expr::Ident("bright".into(), Span::default()));
+        let cin = lens_cinematic(vec![exposure], vec![]);
+        assert!(validate_lenses(&cin).is_err());
+    }
+
+    #[test]
+    fn wgsl_post_samples_scene_and_runs_stages() {
+        let cin = lens_cinematic(
+            vec![],
+            vec![Stage { name: "bloom".into(), args: vec![num_arg(0.3), num_arg(2.0)], span: Span::default(), expanded_from: vec![] }],
+        );
+        let uniforms = lens_uniforms(&cin);
+        let wgsl = generate_post_wgsl(&cin, &uniforms);
+        assert!(wgsl.contains("@group(1) @binding(0) var scene_tex: texture_2d<f32>;"));
+        assert!(wgsl.contains("p_lens_exposure: f32,"));
+        assert!(wgsl.contains("textureSample(scene_tex, scene_sampler"));
+        assert!(wgsl.contains("color_result.rgb * lens_exposure"));
+        assert!(wgsl.contains("max(pp_lum - 0.300000, 0.0) * 2.000000"));
+        assert!(wgsl.contains("return color_result;"));
+    }
+
+    #[test]
+    fn glsl_post_samples_scene_and_runs_stages() {
+        let cin = lens_cinematic(vec![], vec![Stage { name: "grain".into(), args: vec![num_arg(0.05)], span: Span::default(), expanded_from: vec![] }]);
+        let uniforms = lens_uniforms(&cin);
+        let glsl = generate_post_glsl(&cin, &uniforms);
+        assert!(glsl.contains("uniform sampler2D u_scene;"));
+        assert!(glsl.contains("uniform float u_p_lens_zoom;"));
+        assert!(glsl.contains("vec4 color_result = texture(u_scene, lens_uv);"));
+        assert!(glsl.contains("grain_noise"));
+        assert!(glsl.contains("fragColor = color_result;"));
+        assert!(!glsl.contains("\n    let "));
+    }
+}
@@ -67,9 +67,7 @@ pub fn generate_listen_js(listen: &ListenBlock) -> String {
                 s.push_str("      this._fluxHistory.push(flux);\n");
                 s.push_str("      if (this._fluxHistory.length > 30) this._fluxHistory.shift();\n");
                 s.push_str("      const mean = this._fluxHistory.reduce((a, b) => a + b, 0) / this._fluxHistory.length;\n");
-                s.push_str(&format!(
-                    "      const onset = flux > mean * 1.5 ? 1.0 : Math.max(0, flux / (mean || 1) - 0.5);\n"
-                ));
+                s.push_str("      const onset = flux > mean * 1.5 ? 1.0 : Math.max(0, flux / (mean || 1) - 0.5);\n");
                 s.push_str(&format!(
                     "      if (onset > 0.5) {{\n        this.signals['{}'] = 1.0;\n      }} else {{\n        this.signals['{}'] = Math.max(0, this.signals['{}'] - {decay_rate});\n      }}\n",
                     sig.name, sig.name, sig.name
@@ -186,7 +184,7 @@ pub fn generate_listen_js(listen: &ListenBlock) -> String {
 fn get_param_f64(params: &[Param], name: &str, default: f64) -> f64 {
     for p in params {
         if p.name == name {
-            if let Expr::Number(v) = &p.value {
+            if let Expr::Number(v, _) = &p.value {
                 return *v;
             }
         }
@@ -197,7 +195,7 @@ fn get_param_f64(params: &[Param], name: &str, default: f64) -> f64 {
 fn get_param_str<'a>(params: &'a [Param], name: &str, default: &'a str) -> &'a str {
     for p in params {
         if p.name == name {
-            if let Expr::Ident(v) = &p.value {
+            if let Expr::Ident(v, _) = &p.value {
                 return v.as_str();
             }
         }
@@ -218,11 +216,14 @@ mod tests {
                 algorithm: "attack".into(),
                 params: vec![Param {
                     name: "decay".into(),
-                    value: Expr::Number(300.0),
+                    value: Expr::Number(300.0, Span::default()),
                     modulation: None,
                     temporal_ops: vec![],
+                    span: Span::default(),
                 }],
+                span: Span::default(),
             }],
+            span: Span::default(),
         };
         let js = generate_listen_js(&listen);
         assert!(js.contains("class GameListenPipeline"));
@@ -239,7 +240,9 @@ mod tests {
                 name: "melody".into(),
                 algorithm: "pitch".into(),
                 params: vec![],
+                span: Span::default(),
             }],
+            span: Span::default(),
         };
         let js = generate_listen_js(&listen);
         assert!(js.contains("YIN"));
@@ -257,7 +260,9 @@ mod tests {
                 name: "test".into(),
                 algorithm: "pitch".into(),
                 params: vec![],
+                span: Span::default(),
             }],
+            span: Span::default(),
         };
         let js = generate_listen_js(&listen);
         assert!(js.contains("this._window"), "should precompute Hann window");
@@ -273,11 +278,14 @@ mod tests {
                 algorithm: "phase".into(),
                 params: vec![Param {
                     name: "subdivide".into(),
-                    value: Expr::Number(16.0),
+                    value: Expr::Number(16.0, Span::default()),
                     modulation: None,
                     temporal_ops: vec![],
+                    span: Span::default(),
                 }],
+                span: Span::default(),
             }],
+            span: Span::default(),
         };
         let js = generate_listen_js(&listen);
         assert!(js.contains("rmsEnergy"));
@@ -291,7 +299,9 @@ mod tests {
                 name: "drop".into(),
                 algorithm: "delta".into(),
                 params: vec![],
+                span: Span::default(),
             }],
+            span: Span::default(),
         };
         let js = generate_listen_js(&listen);
         assert!(js.contains("delta"));
@@ -1,149 +1,274 @@
 //! Memory (ping-pong framebuffer) codegen for stateful layers.
 //!
-//! When a layer has `memory: <decay>`, the fragment shader samples the
-//! previous frame and mixes it with the current output:
-//!   `output = mix(current, prev, decay)`
+//! Each layer with `memory: <decay>` owns a pair of render targets. The
+//! fragment shader samples the layer's own previous output, mixes it with
+//! the current one:
+//!   `output = mix(current, feedback(prev), decay)`
+//! and writes the result to an extra color attachment, which the runtime
+//! reads back on the next frame.
 //!
-//! This requires additional bindings (texture + sampler) and runtime
-//! support for two render targets swapped each frame.
+//! `feedback_*` layer options transform the previous frame before the mix:
+//! zoom, rotate, translate, blur and hue-shift. Like `opacity`, each becomes
+//! a `<layer>_feedback_*` uniform, so it can be modulated and animated.
+
+use crate::ast::{Cinematic, Layer};
+use crate::error::{suggest_similar, CompileError};
+
+/// At most this many memory layers per cinematic: WebGL2 only guarantees
+/// four draw buffers, and the first is the canvas color.
+pub const MAX_MEMORY_LAYERS: usize = 3;
+
+/// `feedback_*` options, with the value each one has when unset.
+pub const FEEDBACK_OPTIONS: &[(&str, f64)] = &[
+    ("feedback_zoom", 1.0),
+    ("feedback_rotate", 0.0),
+    ("feedback_x", 0.0),
+    ("feedback_y", 0.0),
+    ("feedback_blur", 0.0),
+    ("feedback_hue", 0.0),
+];
 
 /// Check if any layer in a cinematic uses memory.
 pub fn any_layer_uses_memory(layers: &[crate::ast::Layer]) -> bool {
     layers.iter().any(|l| l.memory.is_some())
 }
 
-/// Emit WGSL bind group declarations for memory texture (Group 1).
-pub fn emit_wgsl_memory_bindings(s: &mut String) {
-    s.push_str("@group(1) @binding(0) var prev_frame: texture_2d<f32>;\n");
-    s.push_str("@group(1) @binding(1) var prev_sampler: sampler;\n\n");
+/// Layers with `memory:`, in the order of their render targets.
+pub fn memory_layers(layers: &[Layer]) -> impl Iterator<Item = &Layer> {
+    layers.iter().filter(|l| l.memory.is_some())
 }
 
-/// Emit WGSL code to sample previous frame and mix with current color.
-/// Called at the end of a layer that has `memory: <decay>`.
-pub fn emit_wgsl_memory_mix(s: &mut String, decay: f64, indent: &str) {
-    s.push_str(&format!(
-        "{indent}let prev_color = textureSample(prev_frame, prev_sampler, input.uv);\n"
-    ));
+/// The uniform behind a layer's `feedback_<name>` option, if it has one.
+pub fn feedback_uniform(layer: &Layer, name: &str) -> Option<String> {
+    let uniform = format!("{}_feedback_{name}", layer.name);
+    layer.opts.iter().any(|p| p.name == uniform).then_some(uniform)
+}
+
+/// Reject too many memory layers, unknown `feedback_*` options and
+/// feedback on layers without memory.
+pub fn validate_memory(cinematic: &Cinematic) -> Result<(), CompileError> {
+    let count = memory_layers(&cinematic.layers).count();
+    if count > MAX_MEMORY_LAYERS {
+        return Err(CompileError::validation(format!(
+            "cinematic '{}' has {count} memory layers; at most {MAX_MEMORY_LAYERS} are supported",
+            cinematic.name
+        ))
+        .with_span(cinematic.span));
+    }
+    let known: Vec<&str> = FEEDBACK_OPTIONS.iter().map(|(name, _)| *name).collect();
+    for layer in &cinematic.layers {
+        let own_prefix = format!("{}_feedback_", layer.name);
+        for param in &layer.opts {
+            if param.name.starts_with(&own_prefix) {
+                if layer.memory.is_none() {
+                    return Err(CompileError::validation(format!(
+                        "layer '{}': feedback options need `memory:`",
+                        layer.name
+                    ))
+                    .with_span(param.span)
+                    .with_help(format!("add `memory: 0.9` after `layer {}`", layer.name)));
+                }
+            } else if param.name.starts_with("feedback_") {
+                let mut err = CompileError::validation(format!(
+                    "layer '{}': unknown feedback option '{}'",
+                    layer.name, param.name
+                ))
+                .with_span(param.span);
+                err = match suggest_similar(&param.name, &known) {
+                    Some(s) => err.with_help(format!("did you mean '{s}'?")),
+                    None => err.with_help(format!("use one of: {}", known.join(", "))),
+                };
+                return Err(err);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Where a memory layer reads its previous frame and how it is filtered.
+struct Feedback {
+    /// `(zoom, rotate, x, y)` when the layer moves its previous frame.
+    transform: Option<[String; 4]>,
+    blur: Option<String>,
+    hue: Option<String>,
+}
+
+impl Feedback {
+    fn of(layer: &Layer) -> Self {
+        let value = |name: &str| {
+            feedback_uniform(layer, name).unwrap_or_else(|| {
+                let default = FEEDBACK_OPTIONS
+                    .iter()
+                    .find(|(option, _)| option.strip_prefix("feedback_") == Some(name))
+                    .map_or(0.0, |(_, default)| *default);
+                format!("{default:.1}")
+            })
+        };
+        let moves =
+            ["zoom", "rotate", "x", "y"].iter().any(|n| feedback_uniform(layer, n).is_some());
+        Self {
+            transform: moves.then(|| [value("zoom"), value("rotate"), value("x"), value("y")]),
+            blur: feedback_uniform(layer, "blur"),
+            hue: feedback_uniform(layer, "hue"),
+        }
+    }
+}
+
+/// Whether any memory layer needs the `memory_uv` / `hue_shift` helpers.
+fn needs_helpers(cinematic: &Cinematic) -> (bool, bool) {
+    memory_layers(&cinematic.layers).fold((false, false), |(uv, hue), layer| {
+        let feedback = Feedback::of(layer);
+        (uv || feedback.transform.is_some(), hue || feedback.hue.is_some())
+    })
+}
+
+/// Emit WGSL bind group declarations for memory textures (Group 1): one
+/// shared sampler, then one texture per memory layer.
+pub fn emit_wgsl_memory_bindings(s: &mut String, count: usize) {
+    s.push_str("@group(1) @binding(0) var mem_sampler: sampler;\n");
+    for i in 0..count {
+        s.push_str(&format!("@group(1) @binding({}) var mem_{i}: texture_2d<f32>;\n", i + 1));
+    }
+    s.push('\n');
+}
+
+/// Emit the fragment output struct: the canvas color, then each memory
+/// layer's new state.
+pub fn emit_wgsl_output_struct(s: &mut String, count: usize) {
+    s.push_str("struct FragmentOutput {\n");
+    s.push_str("    @location(0) color: vec4<f32>,\n");
+    for i in 0..count {
+        s.push_str(&format!("    @location({}) mem_{i}: vec4<f32>,\n", i + 1));
+    }
+    s.push_str("};\n\n");
+}
+
+/// Emit the feedback helpers the memory layers use (WGSL).
+pub fn emit_wgsl_memory_helpers(s: &mut String, cinematic: &Cinematic) {
+    let (needs_uv, needs_hue) = needs_helpers(cinematic);
+    if needs_uv {
+        s.push_str("fn memory_uv(uv: vec2<f32>, aspect: f32, zoom: f32, angle: f32, shift: vec2<f32>) -> vec2<f32> {\n");
+        s.push_str("    let q = (vec2<f32>(uv.x * aspect, uv.y) - shift) / zoom;\n");
+        s.push_str("    let c = cos(angle);\n");
+        s.push_str("    let s = sin(angle);\n");
+        s.push_str("    let r = vec2<f32>(c * q.x + s * q.y, c * q.y - s * q.x);\n");
+        s.push_str("    return vec2<f32>(r.x / aspect, r.y) * 0.5 + 0.5;\n");
+        s.push_str("}\n\n");
+    }
+    if needs_hue {
+        s.push_str("fn hue_shift(c: vec3<f32>, turns: f32) -> vec3<f32> {\n");
+        s.push_str("    let k = vec3<f32>(0.57735);\n");
+        s.push_str("    let a = turns * 6.28318;\n");
+        s.push_str("    return c * cos(a) + cross(k, c) * sin(a) + k * dot(k, c) * (1.0 - cos(a));\n");
+        s.push_str("}\n\n");
+    }
+}
+
+/// Emit WGSL code to sample this layer's previous frame, mix it with the
+/// current color and store the result in `mem_out_<index>`.
+pub fn emit_wgsl_memory_mix(s: &mut String, layer: &Layer, index: usize, indent: &str) {
+    let decay = layer.memory.unwrap_or(0.0);
+    let feedback = Feedback::of(layer);
+    match &feedback.transform {
+        Some([zoom, rotate, x, y]) => s.push_str(&format!(
+            "{indent}let mem_uv = memory_uv(uv, aspect, {zoom}, {rotate}, vec2<f32>({x}, {y}));\n"
+        )),
+        None => s.push_str(&format!("{indent}let mem_uv = uv * 0.5 + 0.5;\n")),
+    }
+    // Textures are stored top row first; uv points up
+    s.push_str(&format!("{indent}let mem_tuv = vec2<f32>(mem_uv.x, 1.0 - mem_uv.y);\n"));
+    let sample = |offset: &str| format!("textureSample(mem_{index}, mem_sampler, mem_tuv{offset})");
+    s.push_str(&format!("{indent}var prev_color = {};\n", sample("")));
+    if let Some(blur) = &feedback.blur {
+        s.push_str(&format!("{indent}let mem_px = {blur} / u.resolution;\n"));
+        s.push_str(&format!(
+            "{indent}prev_color = (prev_color + {} + {}\n{indent}    + {} + {}) * 0.2;\n",
+            sample(" + vec2<f32>(mem_px.x, 0.0)"),
+            sample(" - vec2<f32>(mem_px.x, 0.0)"),
+            sample(" + vec2<f32>(0.0, mem_px.y)"),
+            sample(" - vec2<f32>(0.0, mem_px.y)"),
+        ));
+    }
+    if let Some(hue) = &feedback.hue {
+        s.push_str(&format!(
+            "{indent}prev_color = vec4<f32>(hue_shift(prev_color.rgb, {hue}), prev_color.a);\n"
+        ));
+    }
     s.push_str(&format!(
         "{indent}color_result = mix(color_result, prev_color, {decay:.6});\n"
     ));
+    s.push_str(&format!("{indent}mem_out_{index} = color_result;\n"));
 }
 
-/// Emit GLSL uniform declarations for memory texture.
-pub fn emit_glsl_memory_bindings(s: &mut String) {
-    s.push_str("uniform sampler2D u_prev_frame;\n\n");
+/// Emit GLSL uniform declarations for memory textures.
+pub fn emit_glsl_memory_bindings(s: &mut String, count: usize) {
+    for i in 0..count {
+        s.push_str(&format!("uniform sampler2D u_mem_{i};\n"));
+    }
+    s.push('\n');
 }
 
-/// Emit GLSL code to sample previous frame and mix with current color.
-pub fn emit_glsl_memory_mix(s: &mut String, decay: f64, indent: &str) {
-    s.push_str(&format!(
-        "{indent}vec4 prev_color = texture(u_prev_frame, v_uv);\n"
-    ));
+/// Emit the fragment outputs: `fragColor`, then each memory layer's state.
+pub fn emit_glsl_outputs(s: &mut String, count: usize) {
+    s.push_str("layout(location = 0) out vec4 fragColor;\n");
+    for i in 0..count {
+        s.push_str(&format!("layout(location = {}) out vec4 mem_out_{i};\n", i + 1));
+    }
+}
+
+/// Emit the feedback helpers the memory layers use (GLSL).
+pub fn emit_glsl_memory_helpers(s: &mut String, cinematic: &Cinematic) {
+    let (needs_uv, needs_hue) = needs_helpers(cinematic);
+    if needs_uv {
+        s.push_str("vec2 memory_uv(vec2 uv, float aspect, float zoom, float angle, vec2 shift){\n");
+        s.push_str("    vec2 q = (vec2(uv.x * aspect, uv.y) - shift) / zoom;\n");
+        s.push_str("    float c = cos(angle);\n");
+        s.push_str("    float s = sin(angle);\n");
+        s.push_str("    vec2 r = vec2(c * q.x + s * q.y, c * q.y - s * q.x);\n");
+        s.push_str("    return vec2(r.x / aspect, r.y) * 0.5 + 0.5;\n");
+        s.push_str("}\n\n");
+    }
+    if needs_hue {
+        s.push_str("vec3 hue_shift(vec3 c, float turns){\n");
+        s.push_str("    vec3 k = vec3(0.57735);\n");
+        s.push_str("    float a = turns * 6.28318;\n");
+        s.push_str("    return c * cos(a) + cross(k, c) * sin(a) + k * dot(k, c) * (1.0 - cos(a));\n");
+        s.push_str("}\n\n");
+    }
+}
+
+/// Emit GLSL code to sample this layer's previous frame, mix it with the
+/// current color and write the result to `mem_out_<index>`.
+pub fn emit_glsl_memory_mix(s: &mut String, layer: &Layer, index: usize, indent: &str) {
+    let decay = layer.memory.unwrap_or(0.0);
+    let feedback = Feedback::of(layer);
+    match &feedback.transform {
+        Some([zoom, rotate, x, y]) => s.push_str(&format!(
+            "{indent}vec2 mem_uv = memory_uv(uv, aspect, {zoom}, {rotate}, vec2({x}, {y}));\n"
+        )),
+        None => s.push_str(&format!("{indent}vec2 mem_uv = uv * 0.5 + 0.5;\n")),
+    }
+    let sample = |offset: &str| format!("texture(u_mem_{index}, mem_uv{offset})");
+    s.push_str(&format!("{indent}vec4 prev_color = {};\n", sample("")));
+    if let Some(blur) = &feedback.blur {
+        s.push_str(&format!("{indent}vec2 mem_px = {blur} / u_resolution;\n"));
+        s.push_str(&format!(
+            "{indent}prev_color = (prev_color + {} + {}\n{indent}    + {} + {}) * 0.2;\n",
+            sample(" + vec2(mem_px.x, 0.0)"),
+            sample(" - vec2(mem_px.x, 0.0)"),
+            sample(" + vec2(0.0, mem_px.y)"),
+            sample(" - vec2(0.0, mem_px.y)"),
+        ));
+    }
+    if let Some(hue) = &feedback.hue {
+        s.push_str(&format!(
+            "{indent}prev_color = vec4(hue_shift(prev_color.rgb, {hue}), prev_color.a);\n"
+        ));
+    }
     s.push_str(&format!(
         "{indent}color_result = mix(color_result, prev_color, {decay:.6});\n"
     ));
-}
-
-/// Generate the JS runtime code for WebGPU ping-pong texture management.
-pub fn webgpu_memory_runtime() -> &'static str {
-    r#"  _initMemory() {
-    const w = this.canvas.width || 1;
-    const h = this.canvas.height || 1;
-    const desc = {
-      size: { width: w, height: h },
-      format: 'rgba8unorm',
-      usage: GPUTextureUsage.TEXTURE_BINDING | GPUTextureUsage.RENDER_ATTACHMENT | GPUTextureUsage.COPY_SRC | GPUTextureUsage.COPY_DST
-    };
-    this._memTex = [this.device.createTexture(desc), this.device.createTexture(desc)];
-    this._memIdx = 0;
-    this._memSampler = this.device.createSampler({ magFilter: 'linear', minFilter: 'linear' });
-    this._memBindGroupLayout = this.device.createBindGroupLayout({
-      entries: [
-        { binding: 0, visibility: GPUShaderStage.FRAGMENT, texture: { sampleType: 'float' } },
-        { binding: 1, visibility: GPUShaderStage.FRAGMENT, sampler: { type: 'filtering' } }
-      ]
-    });
-    this._updateMemBindGroup();
-  }
-
-  _updateMemBindGroup() {
-    const readTex = this._memTex[this._memIdx];
-    this._memBindGroup = this.device.createBindGroup({
-      layout: this._memBindGroupLayout,
-      entries: [
-        { binding: 0, resource: readTex.createView() },
-        { binding: 1, resource: this._memSampler }
-      ]
-    });
-  }
-
-  _swapMemory(encoder) {
-    const writeTex = this._memTex[1 - this._memIdx];
-    // Copy current frame to write texture for next frame's read
-    encoder.copyTextureToTexture(
-      { texture: this.ctx.getCurrentTexture() },
-      { texture: writeTex },
-      { width: this.canvas.width, height: this.canvas.height }
-    );
-    this._memIdx = 1 - this._memIdx;
-    this._updateMemBindGroup();
-  }
-
-  _resizeMemory() {
-    if (this._memTex) {
-      this._memTex[0].destroy();
-      this._memTex[1].destroy();
-      this._initMemory();
-    }
-  }"#
-}
-
-/// Generate the JS runtime code for WebGL2 ping-pong FBO management.
-pub fn webgl2_memory_runtime() -> &'static str {
-    r#"  _initMemoryGL() {
-    const gl = this.gl;
-    const w = this.canvas.width || 1;
-    const h = this.canvas.height || 1;
-    this._memFbo = [gl.createFramebuffer(), gl.createFramebuffer()];
-    this._memTex = [gl.createTexture(), gl.createTexture()];
-    for (let i = 0; i < 2; i++) {
-      gl.bindTexture(gl.TEXTURE_2D, this._memTex[i]);
-      gl.texImage2D(gl.TEXTURE_2D, 0, gl.RGBA, w, h, 0, gl.RGBA, gl.UNSIGNED_BYTE, null);
-      gl.texParameteri(gl.TEXTURE_2D, gl.TEXTURE_MIN_FILTER, gl.LINEAR);
-      gl.texParameteri(gl.TEXTURE_2D, gl.TEXTURE_MAG_FILTER, gl.LINEAR);
-      gl.texParameteri(gl.TEXTURE_2D, gl.TEXTURE_WRAP_S, gl.CLAMP_TO_EDGE);
-      gl.texParameteri(gl.TEXTURE_2D, gl.TEXTURE_WRAP_T, gl.CLAMP_TO_EDGE);
-      gl.bindFramebuffer(gl.FRAMEBUFFER, this._memFbo[i]);
-      gl.framebufferTexture2D(gl.FRAMEBUFFER, gl.COLOR_ATTACHMENT0, gl.TEXTURE_2D, this._memTex[i], 0);
-    }
-    gl.bindFramebuffer(gl.FRAMEBUFFER, null);
-    gl.bindTexture(gl.TEXTURE_2D, null);
-    this._memIdx = 0;
-    this._memLoc = gl.getUniformLocation(this.program, 'u_prev_frame');
-  }
-
-  _swapMemoryGL() {
-    const gl = this.gl;
-    // Bind read texture to unit 1 for next frame
-    gl.activeTexture(gl.TEXTURE1);
-    gl.bindTexture(gl.TEXTURE_2D, this._memTex[this._memIdx]);
-    gl.uniform1i(this._memLoc, 1);
-    // Render to write FBO, then copy to screen
-    this._memIdx = 1 - this._memIdx;
-  }
-
-  _resizeMemoryGL() {
-    if (this._memTex) {
-      const gl = this.gl;
-      const w = this.canvas.width || 1;
-      const h = this.canvas.height || 1;
-      for (let i = 0; i < 2; i++) {
-        gl.bindTexture(gl.TEXTURE_2D, this._memTex[i]);
-        gl.texImage2D(gl.TEXTURE_2D, 0, gl.RGBA, w, h, 0, gl.RGBA, gl.UNSIGNED_BYTE, null);
-      }
-      gl.bindTexture(gl.TEXTURE_2D, null);
-    }
-  }"#
+    s.push_str(&format!("{indent}mem_out_{index} = color_result;\n"));
 }
 
 #[cfg(test)]
@@ -151,6 +276,10 @@ mod tests {
     use super::*;
     use crate::ast::*;
 
+    fn cinematic(source: &str) -> Cinematic {
+        crate::compile_to_ast(source).unwrap().cinematics.remove(0)
+    }
+
     #[test]
     fn detects_memory_layers() {
         let layers = vec![
@@ -159,14 +288,18 @@ mod tests {
                 opts: vec![],
                 memory: None,
                 cast: None,
+                blend: None,
                 body: LayerBody::Pipeline(vec![]),
+                span: Span::default(),
             },
             Layer {
                 name: "b".into(),
                 opts: vec![],
                 memory: Some(0.95),
                 cast: None,
+                blend: None,
                 body: LayerBody::Pipeline(vec![]),
+                span: Span::default(),
             },
         ];
         assert!(any_layer_uses_memory(&layers));
@@ -179,32 +312,99 @@ mod tests {
             opts: vec![],
             memory: None,
             cast: None,
+            blend: None,
             body: LayerBody::Pipeline(vec![]),
+            span: Span::default(),
         }];
         assert!(!any_layer_uses_memory(&layers));
     }
 
     #[test]
     fn wgsl_memory_mix_emits_correct_code() {
+        let cin =
+            cinematic(r#"cinematic "c" { layer t memory: 0.97 { circle(0.1) | glow(1.0) } }"#);
         let mut s = String::new();
-        emit_wgsl_memory_mix(&mut s, 0.97, "    ");
-        assert!(s.contains("textureSample(prev_frame, prev_sampler, input.uv)"));
+        emit_wgsl_memory_mix(&mut s, &cin.layers[0], 0, "    ");
+        assert!(s.contains("textureSample(mem_0, mem_sampler, mem_tuv)"));
         assert!(s.contains("mix(color_result, prev_color, 0.970000)"));
+        assert!(s.contains("mem_out_0 = color_result;"));
     }
 
     #[test]
     fn glsl_memory_mix_emits_correct_code() {
+        let cin =
+            cinematic(r#"cinematic "c" { layer t memory: 0.95 { circle(0.1) | glow(1.0) } }"#);
         let mut s = String::new();
-        emit_glsl_memory_mix(&mut s, 0.95, "    ");
-        assert!(s.contains("texture(u_prev_frame, v_uv)"));
+        emit_glsl_memory_mix(&mut s, &cin.layers[0], 0, "    ");
+        assert!(s.contains("texture(u_mem_0, mem_uv)"));
         assert!(s.contains("mix(color_result, prev_color, 0.950000)"));
     }
 
     #[test]
     fn wgsl_bindings_has_group_1() {
         let mut s = String::new();
-        emit_wgsl_memory_bindings(&mut s);
+        emit_wgsl_memory_bindings(&mut s, 2);
         assert!(s.contains("@group(1) @binding(0)"));
         assert!(s.contains("@group(1) @binding(1)"));
+        assert!(s.contains("@group(1) @binding(2) var mem_1: texture_2d<f32>;"));
+    }
+
+    #[test]
+    fn each_memory_layer_gets_its_own_target() {
+        let cin = cinematic(
+            r#"cinematic "c" {
+                layer a memory: 0.9 { circle(0.1) | glow(1.0) }
+                layer b { ring(0.3, 0.02) | glow(1.0) }
+                layer c memory: 0.5 { circle(0.2) | glow(1.0) }
+            }"#,
+        );
+        let out = crate::codegen::generate(&cin).unwrap();
+        assert!(out.wgsl_fragment.contains("@location(2) mem_1: vec4<f32>,"));
+        assert!(out
+            .wgsl_fragment
+            .contains("return FragmentOutput(final_color, mem_out_0, mem_out_1);"));
+        assert!(out.glsl_fragment.contains("layout(location = 2) out vec4 mem_out_1;"));
+        assert!(out.glsl_fragment.contains("texture(u_mem_1, mem_uv)"));
+        assert_eq!(out.memory_layers, 2);
+    }
+
+    #[test]
+    fn feedback_options_become_layer_uniforms() {
+        let cin = cinematic(
+            r#"cinematic "c" {
+                layer tunnel memory: 0.95 {
+                    fn: ring(0.3, 0.02) | glow(2.0)
+                    feedback_zoom: 1.03
+                    feedback_rotate: 0.02 ~ audio.bass
+                    feedback_blur: 1.0
+                    feedback_hue: 0.01
+                }
+            }"#,
+        );
+        let out = crate::codegen::generate(&cin).unwrap();
+        let names: Vec<_> = out.uniforms.iter().map(|u| u.name.as_str()).collect();
+        assert!(names.contains(&"tunnel_feedback_zoom"));
+        assert!(out.wgsl_fragment.contains(
+            "memory_uv(uv, aspect, tunnel_feedback_zoom, tunnel_feedback_rotate, vec2<f32>(0.0, 0.0))",
+        ));
+        assert!(out.wgsl_fragment.contains("fn hue_shift("));
+        assert!(out.glsl_fragment.contains("vec2 mem_px = tunnel_feedback_blur / u_resolution;"));
+    }
+
+    #[test]
+    fn feedback_needs_memory_and_known_names() {
+        let cin = cinematic(
+            r#"cinematic "c" { layer a { fn: circle(0.1) | glow(1.0)
+                feedback_zoom: 1.1 } }"#,
+        );
+        let err = validate_memory(&cin).unwrap_err().to_string();
+        assert!(err.contains("feedback options need `memory:`"), "{err}");
+
+        let cin = cinematic(
+            r#"cinematic "c" { layer a memory: 0.9 { fn: circle(0.1) | glow(1.0)
+                feedback_zom: 1.1 } }"#,
+        );
+        let err = validate_memory(&cin).unwrap_err().to_string();
+        assert!(err.contains("unknown feedback option 'feedback_zom'"), "{err}");
     }
 }
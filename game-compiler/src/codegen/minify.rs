@@ -0,0 +1,122 @@
+//! Shader minification for `-O2` builds.
+//!
+//! Works on the generated WGSL/GLSL text: strips `//` comments, indentation
+//! and blank lines, and drops whitespace next to punctuation that cannot
+//! change tokenization. Preprocessor lines (`#version`, `#define`) keep their
+//! own line since GLSL requires it.
+
+use crate::codegen::ShaderOutput;
+
+/// Characters whose surrounding whitespace is never significant.
+const TIGHT: &[char] = &['(', ')', '{', '}', '[', ']', ',', ';', ':', '='];
+
+/// Minify every shader string in a `ShaderOutput` in place.
+pub fn minify_output(shader: &mut ShaderOutput) {
+    shader.wgsl_fragment = minify_shader(&shader.wgsl_fragment);
+    shader.wgsl_vertex = minify_shader(&shader.wgsl_vertex);
+    shader.glsl_fragment = minify_shader(&shader.glsl_fragment);
+    shader.glsl_vertex = minify_shader(&shader.glsl_vertex);
+    for src in [&mut shader.post_wgsl, &mut shader.post_glsl, &mut shader.compute_wgsl]
+        .into_iter()
+        .flatten()
+    {
+        *src = minify_shader(src);
+    }
+}
+
+/// Minify a WGSL or GLSL source string.
+pub fn minify_shader(src: &str) -> String {
+    let mut out = String::with_capacity(src.len());
+    for raw in src.lines() {
+        let line = match raw.find("//") {
+            Some(idx) => &raw[..idx],
+            None => raw,
+        };
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        if line.starts_with('#') {
+            if !out.is_empty() && !out.ends_with('\n') {
+                out.push('\n');
+            }
+            out.push_str(line);
+            out.push('\n');
+            continue;
+        }
+
+        let line = tighten(line);
+        // Keep a separator only where two word tokens would otherwise merge
+        if let (Some(prev), Some(next)) = (out.chars().last(), line.chars().next()) {
+            if (is_word(prev) && is_word(next)) || (is_sign(prev) && is_sign(next)) {
+                out.push(' ');
+            }
+        }
+        out.push_str(&line);
+    }
+    if !out.ends_with('\n') {
+        out.push('\n');
+    }
+    out
+}
+
+/// Collapse whitespace runs and drop spaces adjacent to `TIGHT` punctuation.
+fn tighten(line: &str) -> String {
+    let mut out = String::with_capacity(line.len());
+    let mut pending_space = false;
+    for c in line.chars() {
+        if c.is_whitespace() {
+            pending_space = true;
+            continue;
+        }
+        if pending_space {
+            let prev = out.chars().last();
+            if !TIGHT.contains(&c) && !prev.is_some_and(|p| TIGHT.contains(&p)) {
+                out.push(' ');
+            }
+            pending_space = false;
+        }
+        out.push(c);
+    }
+    out
+}
+
+fn is_word(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '.'
+}
+
+fn is_sign(c: char) -> bool {
+    c == '-' || c == '+'
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strips_comments_and_indentation() {
+        let src = "// header\nfn f() -> f32 {\n    // body\n    let x = 1.0; // trailing\n    return x;\n}\n";
+        assert_eq!(minify_shader(src), "fn f()-> f32{let x=1.0;return x;}\n");
+    }
+
+    #[test]
+    fn keeps_preprocessor_lines() {
+        let src = "#version 300 es\nprecision highp float;\n\nvoid main(){\n    fragColor = vec4(1.0);\n}\n";
+        assert_eq!(
+            minify_shader(src),
+            "#version 300 es\nprecision highp float;void main(){fragColor=vec4(1.0);}\n"
+        );
+    }
+
+    #[test]
+    fn preserves_word_boundaries_across_lines() {
+        let src = "let a = b\n    *c;\nvar\nx = 1;\n";
+        assert_eq!(minify_shader(src), "let a=b*c;var x=1;\n");
+    }
+
+    #[test]
+    fn does_not_merge_unary_minus() {
+        assert_eq!(minify_shader("x = a - -b;\n"), "x=a - -b;\n");
+    }
+}
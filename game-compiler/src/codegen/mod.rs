@@ -4,23 +4,31 @@
 //! the runtime module to wrap them in Web Components or standalone HTML.
 
 pub mod analysis;
+pub mod blend;
 pub mod breed;
 pub mod cast;
+pub mod color;
+pub mod combine;
 pub mod expr;
 pub mod glsl;
+pub mod gradient;
 pub mod gravity;
+pub mod lens;
 pub mod listen;
 pub mod memory;
+pub mod minify;
+pub mod modulation;
 pub mod project;
 pub mod react;
 pub mod resonance;
 pub mod score;
+pub mod signals;
 pub mod stages;
 pub mod temporal;
 pub mod voice;
 pub mod wgsl;
 
-use crate::ast::{Cinematic, Expr, LayerBody, Param};
+use crate::ast::{walk_stages, Cinematic, Expr, LayerBody, Param, Stage};
 use crate::builtins;
 use crate::error::CompileError;
 
@@ -40,18 +48,29 @@ pub struct ShaderOutput {
     pub glsl_fragment: String,
     pub glsl_vertex: String,
     pub uniforms: Vec<UniformInfo>,
-    pub uses_memory: bool,
-    /// Collected JS classes (listen, voice, score, breed, temporal, gravity).
+    /// Layers with `memory:`; each gets its own ping-pong render target.
+    pub memory_layers: usize,
+    /// Collected JS classes (modulation, listen, voice, score, breed, gravity).
     pub js_modules: Vec<String>,
     /// Gravity compute shader (separate pipeline).
     pub compute_wgsl: Option<String>,
+    /// `data.*` fields, exposed as component attributes/properties.
+    pub data_fields: Vec<String>,
+    /// Lens post-pass fragment shaders; the layers render offscreen when set.
+    pub post_wgsl: Option<String>,
+    pub post_glsl: Option<String>,
+    /// False for static cinematics, which only redraw when an input changes.
+    pub animated: bool,
 }
 
 /// Extract user-defined uniform parameters from a cinematic's layers.
 ///
 /// Any layer with `LayerBody::Params` contributes named uniforms.
 /// Pipeline stages with `Ident` args that are NOT builtin names are also uniforms.
-fn extract_uniforms(cinematic: &Cinematic) -> Vec<UniformInfo> {
+/// Every referenced `data.*` field becomes a `data_<field>` uniform, pointer
+/// fields beyond `mouse.x`/`mouse.y` become `mouse_<field>` uniforms, and lens
+/// properties become `lens_*` uniforms when a post pass is generated.
+pub(crate) fn extract_uniforms(cinematic: &Cinematic) -> Vec<UniformInfo> {
     let mut uniforms = Vec::new();
     let mut seen = std::collections::HashSet::new();
 
@@ -61,7 +80,7 @@ fn extract_uniforms(cinematic: &Cinematic) -> Vec<UniformInfo> {
             for param in params {
                 if seen.insert(param.name.clone()) {
                     let default = match &param.value {
-                        Expr::Number(v) => *v,
+                        Expr::Number(v, _) => *v,
                         _ => 0.0,
                     };
                     uniforms.push(UniformInfo {
@@ -76,7 +95,7 @@ fn extract_uniforms(cinematic: &Cinematic) -> Vec<UniformInfo> {
         for param in &layer.opts {
             if seen.insert(param.name.clone()) {
                 let default = match &param.value {
-                    Expr::Number(v) => *v,
+                    Expr::Number(v, _) => *v,
                     _ => 0.0,
                 };
                 uniforms.push(UniformInfo {
@@ -86,26 +105,61 @@ fn extract_uniforms(cinematic: &Cinematic) -> Vec<UniformInfo> {
             }
         }
 
-        // Pipeline stages: ident args that aren't builtins are user uniforms
         if let LayerBody::Pipeline(stages) = &layer.body {
-            for stage in stages {
-                for arg in &stage.args {
-                    if let Expr::Ident(name) = &arg.value {
-                        if builtins::lookup(name).is_none() && seen.insert(name.clone()) {
-                            uniforms.push(UniformInfo {
-                                name: name.clone(),
-                                default: 0.0,
-                            });
-                        }
-                    }
-                }
-            }
+            collect_stage_uniforms(stages, &mut seen, &mut uniforms);
+        }
+    }
+
+    for lens in &cinematic.lenses {
+        collect_stage_uniforms(&lens.post, &mut seen, &mut uniforms);
+    }
+
+    for field in signals::collect_cinematic_data_fields(cinematic) {
+        let name = signals::data_uniform_name(&field);
+        if seen.insert(name.clone()) {
+            uniforms.push(UniformInfo { name, default: 0.0 });
+        }
+    }
+
+    for name in signals::collect_cinematic_mouse_uniforms(cinematic) {
+        if seen.insert(name.clone()) {
+            uniforms.push(UniformInfo { name, default: 0.0 });
+        }
+    }
+
+    for lens_uniform in lens::lens_uniforms(cinematic) {
+        if seen.insert(lens_uniform.name.clone()) {
+            uniforms.push(lens_uniform);
         }
     }
 
     uniforms
 }
 
+/// Pipeline stages: ident args that aren't builtins, shader locals,
+/// constants or colors are user uniforms, including identifiers nested
+/// inside argument expressions.
+fn collect_stage_uniforms(
+    stages: &[Stage],
+    seen: &mut std::collections::HashSet<String>,
+    uniforms: &mut Vec<UniformInfo>,
+) {
+    walk_stages(stages, &mut |stage| {
+        for arg in &stage.args {
+            let mut names = Vec::new();
+            // Pipelines are walked as stages in their own right
+            if !matches!(arg.value, Expr::Pipeline(_, _)) {
+                stages::collect_arg_idents(&arg.value, &mut names);
+            }
+            for name in names {
+                if builtins::lookup(&name).is_none() && seen.insert(name.clone()) {
+                    uniforms.push(UniformInfo { name, default: 0.0 });
+                }
+            }
+        }
+    });
+}
+
 /// Validate all pipeline layers in a cinematic.
 pub fn validate(cinematic: &Cinematic) -> Result<(), CompileError> {
     for layer in &cinematic.layers {
@@ -113,24 +167,30 @@ pub fn validate(cinematic: &Cinematic) -> Result<(), CompileError> {
             stages::validate_pipeline(pipeline)?;
         }
     }
+    signals::validate_signals(cinematic)?;
+    lens::validate_lenses(cinematic)?;
+    blend::validate_blends(cinematic)?;
+    color::validate_colors(cinematic)?;
+    gradient::validate_gradients(cinematic)?;
+    memory::validate_memory(cinematic)?;
     // Cast type validation (checks pipeline output matches declared cast)
     cast::validate_casts(cinematic)?;
     Ok(())
 }
 
-/// Collect all `Param` references from a cinematic's `LayerBody::Params` layers.
+/// Collect all `Param` references from a cinematic's `LayerBody::Params` layers
+/// and from the inline params of `fn:` bodies.
 fn collect_all_params(cinematic: &Cinematic) -> Vec<&Param> {
     cinematic
         .layers
         .iter()
-        .filter_map(|layer| {
-            if let LayerBody::Params(params) = &layer.body {
-                Some(params.iter())
-            } else {
-                None
-            }
+        .flat_map(|layer| {
+            let body = match &layer.body {
+                LayerBody::Params(params) => params.as_slice(),
+                LayerBody::Pipeline(_) => &[],
+            };
+            layer.opts.iter().chain(body)
         })
-        .flatten()
         .collect()
 }
 
@@ -143,16 +203,25 @@ pub fn generate(cinematic: &Cinematic) -> Result<ShaderOutput, CompileError> {
     let wgsl_fragment = wgsl::generate_fragment(cinematic, &uniforms);
     let glsl_fragment = glsl::generate_fragment(cinematic, &uniforms);
 
-    let uses_memory = memory::any_layer_uses_memory(&cinematic.layers);
+    let memory_layers = memory::memory_layers(&cinematic.layers).count();
+
+    // Lens → offscreen scene + post-processing pass
+    let (post_wgsl, post_glsl) = if lens::has_post_pass(cinematic) {
+        (
+            Some(lens::generate_post_wgsl(cinematic, &uniforms)),
+            Some(lens::generate_post_glsl(cinematic, &uniforms)),
+        )
+    } else {
+        (None, None)
+    };
 
     // Collect JS feature modules
     let mut js_modules = Vec::new();
 
-    // Temporal: collect params from all layers
+    // Modulation: `~` bindings + temporal operators, evaluated per frame
     let all_params: Vec<Param> = collect_all_params(cinematic).into_iter().cloned().collect();
-    if temporal::any_param_uses_temporal(&all_params) {
-        let (init, update) = temporal::generate_temporal_js(&all_params);
-        js_modules.push(format!("{init}\n{update}"));
+    if modulation::any_param_modulated(&all_params) {
+        js_modules.push(modulation::generate_modulation_js(&all_params, &uniforms));
     }
 
     // Listen → GameListenPipeline class
@@ -167,7 +236,7 @@ pub fn generate(cinematic: &Cinematic) -> Result<ShaderOutput, CompileError> {
 
     // Score → GameScorePlayer class
     if let Some(ref sb) = cinematic.score {
-        js_modules.push(score::generate_score_js(sb));
+        js_modules.push(score::generate_score_js(sb, &uniforms));
     }
 
     // Gravity → compute WGSL + GameGravitySim JS class
@@ -210,9 +279,13 @@ pub fn generate(cinematic: &Cinematic) -> Result<ShaderOutput, CompileError> {
         glsl_fragment,
         glsl_vertex: glsl::vertex_shader().to_string(),
         uniforms,
-        uses_memory,
+        memory_layers,
         js_modules,
         compute_wgsl,
+        data_fields: signals::collect_cinematic_data_fields(cinematic),
+        post_wgsl,
+        post_glsl,
+        animated: analysis::cinematic_is_animated(cinematic),
     })
 }
 
@@ -229,20 +302,23 @@ mod tests {
                 opts: vec![],
                 memory: None,
                 cast: None,
+                blend: None,
                 body: LayerBody::Pipeline(stages),
+                span: Span::default(),
             }],
             arcs: vec![],
             resonates: vec![],
             listen: None, voice: None, score: None, gravity: None,
             lenses: vec![], react: None, defines: vec![],
+            span: Span::default(),
         }
     }
 
     #[test]
     fn generate_produces_both_shaders() {
         let cin = make_cinematic(vec![
-            Stage { name: "circle".into(), args: vec![] },
-            Stage { name: "glow".into(), args: vec![] },
+            Stage { name: "circle".into(), args: vec![], span: Span::default(), expanded_from: vec![] },
+            Stage { name: "glow".into(), args: vec![], span: Span::default(), expanded_from: vec![] },
         ]);
         let output = generate(&cin).unwrap();
         assert!(output.wgsl_fragment.contains("fn fs_main"));
@@ -258,20 +334,62 @@ mod tests {
                 name: "circle".into(),
                 args: vec![Arg {
                     name: None,
-                    value: Expr::Ident("my_radius".into()),
+                    value: Expr::Ident("my_radius".into(), Span::default()),
+                    span: Span::default(),
                 }],
+                span: Span::default(),
+                expanded_from: vec![],
             },
-            Stage { name: "glow".into(), args: vec![] },
+            Stage { name: "glow".into(), args: vec![], span: Span::default(), expanded_from: vec![] },
         ]);
         let uniforms = extract_uniforms(&cin);
         assert_eq!(uniforms.len(), 1);
         assert_eq!(uniforms[0].name, "my_radius");
     }
 
+    #[test]
+    fn extract_nested_ident_uniforms() {
+        // circle(base + sin(time * speed) * 0.05)
+        let value = Expr::BinOp {
+            op: BinOp::Add,
+            left: Box::new(Expr::Ident("base".into(), Span::default())),
+            right: Box::new(Expr::BinOp {
+                op: BinOp::Mul,
+                left: Box::new(Expr::Call {
+                    name: "sin".into(),
+                    args: vec![Arg {
+                        name: None,
+                        value: Expr::BinOp {
+                            op: BinOp::Mul,
+                            left: Box::new(Expr::Ident("time".into(), Span::default())),
+                            right: Box::new(Expr::Ident("speed".into(), Span::default())),
+                            span: Span::default(),
+                        },
+                        span: Span::default(),
+                    }],
+                    span: Span::default(),
+                }),
+                right: Box::new(Expr::Number(0.05, Span::default())),
+                span: Span::default(),
+            }),
+            span: Span::default(),
+        };
+        let cin = make_cinematic(vec![
+            Stage { name: "circle".into(), args: vec![Arg { name: None, value, span: Span::default() }], span: Span::default(), expanded_from: vec![] },
+            Stage { name: "glow".into(), args: vec![], span: Span::default(), expanded_from: vec![] },
+        ]);
+        let names: Vec<String> = extract_uniforms(&cin).into_iter().map(|u| u.name).collect();
+        assert_eq!(names, vec!["base".to_string(), "speed".to_string()]);
+
+        let output = generate(&cin).unwrap();
+        assert!(output.wgsl_fragment.contains("sdf_circle(p, (base + (sin((time * speed)) * 0.05)))"));
+        assert!(output.glsl_fragment.contains("(base + (sin((time * speed)) * 0.05))"));
+    }
+
     #[test]
     fn validate_rejects_bad_pipeline() {
         let cin = make_cinematic(vec![
-            Stage { name: "glow".into(), args: vec![] },
+            Stage { name: "glow".into(), args: vec![], span: Span::default(), expanded_from: vec![] },
         ]);
         assert!(generate(&cin).is_err());
     }
@@ -285,19 +403,23 @@ mod tests {
                 opts: vec![],
                 memory: None,
                 cast: None,
+                blend: None,
                 body: LayerBody::Params(vec![
                     Param {
                         name: "intensity".into(),
-                        value: Expr::Number(0.5),
+                        value: Expr::Number(0.5, Span::default()),
                         modulation: None,
                         temporal_ops: vec![],
+                        span: Span::default(),
                     },
                 ]),
+                span: Span::default(),
             }],
             arcs: vec![],
             resonates: vec![],
             listen: None, voice: None, score: None, gravity: None,
             lenses: vec![], react: None, defines: vec![],
+            span: Span::default(),
         };
         let uniforms = extract_uniforms(&cin);
         assert_eq!(uniforms.len(), 1);
@@ -314,14 +436,17 @@ mod tests {
                 opts: vec![],
                 memory: None,
                 cast: Some("sdf".into()),
+                blend: None,
                 body: LayerBody::Pipeline(vec![
-                    Stage { name: "circle".into(), args: vec![] },
+                    Stage { name: "circle".into(), args: vec![], span: Span::default(), expanded_from: vec![] },
                 ]),
+                span: Span::default(),
             }],
             arcs: vec![],
             resonates: vec![],
             listen: None, voice: None, score: None, gravity: None,
             lenses: vec![], react: None, defines: vec![],
+            span: Span::default(),
         };
         assert!(generate(&cin).is_ok());
     }
@@ -335,15 +460,18 @@ mod tests {
                 opts: vec![],
                 memory: None,
                 cast: Some("sdf".into()),
+                blend: None,
                 body: LayerBody::Pipeline(vec![
-                    Stage { name: "circle".into(), args: vec![] },
-                    Stage { name: "glow".into(), args: vec![] },
+                    Stage { name: "circle".into(), args: vec![], span: Span::default(), expanded_from: vec![] },
+                    Stage { name: "glow".into(), args: vec![], span: Span::default(), expanded_from: vec![] },
                 ]),
+                span: Span::default(),
             }],
             arcs: vec![],
             resonates: vec![],
             listen: None, voice: None, score: None, gravity: None,
             lenses: vec![], react: None, defines: vec![],
+            span: Span::default(),
         };
         let err = generate(&cin).unwrap_err();
         assert!(err.to_string().contains("cast as 'sdf'"));
@@ -358,10 +486,12 @@ mod tests {
                 opts: vec![],
                 memory: None,
                 cast: None,
+                blend: None,
                 body: LayerBody::Pipeline(vec![
-                    Stage { name: "circle".into(), args: vec![] },
-                    Stage { name: "glow".into(), args: vec![] },
+                    Stage { name: "circle".into(), args: vec![], span: Span::default(), expanded_from: vec![] },
+                    Stage { name: "glow".into(), args: vec![], span: Span::default(), expanded_from: vec![] },
                 ]),
+                span: Span::default(),
             }],
             arcs: vec![],
             resonates: vec![],
@@ -370,12 +500,15 @@ mod tests {
                     name: "onset".into(),
                     algorithm: "attack".into(),
                     params: vec![],
+                    span: Span::default(),
                 }],
+                span: Span::default(),
             }),
             voice: None,
             score: None,
             gravity: None,
             lenses: vec![], react: None, defines: vec![],
+            span: Span::default(),
         };
         let output = generate(&cin).unwrap();
         assert_eq!(output.js_modules.len(), 1);
@@ -392,10 +525,12 @@ mod tests {
                 opts: vec![],
                 memory: None,
                 cast: None,
+                blend: None,
                 body: LayerBody::Pipeline(vec![
-                    Stage { name: "circle".into(), args: vec![] },
-                    Stage { name: "glow".into(), args: vec![] },
+                    Stage { name: "circle".into(), args: vec![], span: Span::default(), expanded_from: vec![] },
+                    Stage { name: "glow".into(), args: vec![], span: Span::default(), expanded_from: vec![] },
                 ]),
+                span: Span::default(),
             }],
             arcs: vec![],
             resonates: vec![],
@@ -403,11 +538,13 @@ mod tests {
             voice: None,
             score: None,
             gravity: Some(crate::ast::GravityBlock {
-                force_law: Expr::Number(1.0),
+                force_law: Expr::Number(1.0, Span::default()),
                 damping: 0.99,
                 bounds: crate::ast::BoundsMode::Reflect,
+                span: Span::default(),
             }),
             lenses: vec![], react: None, defines: vec![],
+            span: Span::default(),
         };
         let output = generate(&cin).unwrap();
         assert!(output.compute_wgsl.is_some());
@@ -418,8 +555,8 @@ mod tests {
     #[test]
     fn generate_default_has_empty_js_modules() {
         let cin = make_cinematic(vec![
-            Stage { name: "circle".into(), args: vec![] },
-            Stage { name: "glow".into(), args: vec![] },
+            Stage { name: "circle".into(), args: vec![], span: Span::default(), expanded_from: vec![] },
+            Stage { name: "glow".into(), args: vec![], span: Span::default(), expanded_from: vec![] },
         ]);
         let output = generate(&cin).unwrap();
         assert!(output.js_modules.is_empty());
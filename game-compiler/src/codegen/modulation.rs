@@ -0,0 +1,209 @@
+//! Modulation codegen — compiles `param: base ~ signal` bindings into a
+//! per-frame JS evaluator.
+//!
+//! Generates a `modulationUpdate(time, renderer)` function that the renderers
+//! call before uploading uniforms. Each modulated param is evaluated as
+//! `base + signal`, run through its temporal operators (`>>`, `<>`, `!!`, `..`)
+//! and written back into `renderer.userParams`.
+
+use crate::ast::{Expr, Param};
+use crate::codegen::expr;
+use crate::codegen::temporal;
+use crate::codegen::UniformInfo;
+
+/// Whether a param needs per-frame evaluation (`~` binding or temporal ops).
+fn is_dynamic(param: &Param) -> bool {
+    param.modulation.is_some() || !param.temporal_ops.is_empty()
+}
+
+/// Check whether any params need the modulation runtime.
+pub fn any_param_modulated(params: &[Param]) -> bool {
+    params.iter().any(is_dynamic)
+}
+
+/// Compile modulated params into a JS `modulationUpdate` function.
+///
+/// The generated function signature: `function modulationUpdate(time, renderer)`
+/// - `time` — seconds on the renderer's playback clock (`renderer.clock`)
+/// - `renderer` — a `GameRenderer` / `GameRendererGL` instance; reads
+///   `audioData`, `mouse` and `userParams`, writes `userParams`
+///
+/// Temporal processor instances live on the renderer, created on its first
+/// frame, so their state persists across frames and stays per element.
+pub fn generate_modulation_js(params: &[Param], uniforms: &[UniformInfo]) -> String {
+    let dynamic: Vec<Param> = params.iter().filter(|p| is_dynamic(p)).cloned().collect();
+    if dynamic.is_empty() {
+        return String::new();
+    }
+
+    let (temporal_init, temporal_update) = temporal::generate_temporal_js(&dynamic);
+
+    let mut s = String::with_capacity(1024);
+    s.push_str("// GAME modulation — per-frame `~` bindings\n");
+    s.push_str(&temporal_init);
+    s.push_str("function modulationUpdate(time, renderer) {\n");
+    s.push_str("  const params = renderer.userParams;\n");
+
+    // Declare the signals the expressions read, in first-use order
+    let mut signals = Vec::new();
+    for param in &dynamic {
+        if let Some(m) = &param.modulation {
+            collect_signals(m, uniforms, &mut signals);
+        }
+    }
+    for signal in &signals {
+        s.push_str(&format!("  {signal}\n"));
+    }
+
+    for param in &dynamic {
+        let base = expr::compile_js(&param.value);
+        let value = match &param.modulation {
+            Some(m) => format!("{base} + {}", expr::compile_js(m)),
+            None => base,
+        };
+        s.push_str(&format!("  let _val_{} = {value};\n", param.name));
+    }
+
+    for line in temporal_update.lines() {
+        s.push_str(&format!("  {line}\n"));
+    }
+
+    for param in &dynamic {
+        s.push_str(&format!("  params.{0} = _val_{0};\n", param.name));
+    }
+
+    s.push_str("}\n");
+    s
+}
+
+/// Collect JS declarations for the signals an expression references.
+fn collect_signals(e: &Expr, uniforms: &[UniformInfo], out: &mut Vec<String>) {
+    let mut push = |decl: String| {
+        if !out.contains(&decl) {
+            out.push(decl);
+        }
+    };
+    match e {
+        Expr::DottedIdent { object, field, .. } => match object.as_str() {
+            "audio" => push(format!(
+                "const audio{} = renderer.audioData.{field} || 0;",
+                expr::capitalize(field)
+            )),
+            "mouse" => push(format!(
+                "const mouse{} = renderer.mouse.{field} || 0;",
+                expr::capitalize(field)
+            )),
+            "data" => push(format!("const data_{field} = params.data_{field} ?? 0;")),
+            _ => {}
+        },
+        // Bare references to other params read their current value
+        Expr::Ident(name, _) if uniforms.iter().any(|u| &u.name == name) => {
+            push(format!("const {name} = params.{name};"));
+        }
+        Expr::Paren(inner, _) | Expr::Neg(inner, _) | Expr::Not(inner, _) => {
+            collect_signals(inner, uniforms, out)
+        }
+        Expr::BinOp { left, right, .. } => {
+            collect_signals(left, uniforms, out);
+            collect_signals(right, uniforms, out);
+        }
+        Expr::Call { args, .. } => {
+            for arg in args {
+                collect_signals(&arg.value, uniforms, out);
+            }
+        }
+        Expr::Array(elems, _) => {
+            for elem in elems {
+                collect_signals(elem, uniforms, out);
+            }
+        }
+        Expr::Ternary { condition, if_true, if_false, .. } => {
+            collect_signals(condition, uniforms, out);
+            collect_signals(if_true, uniforms, out);
+            collect_signals(if_false, uniforms, out);
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ast::*;
+
+    fn param(name: &str, base: f64, modulation: Option<Expr>, ops: Vec<TemporalOp>) -> Param {
+        Param {
+            name: name.into(),
+            value: Expr::Number(base, Span::default()),
+            modulation,
+            temporal_ops: ops,
+            span: Span::default(),
+        }
+    }
+
+    fn audio(field: &str) -> Expr {
+        Expr::DottedIdent { object: "audio".into(), field: field.into(), span: Span::default() }
+    }
+
+    #[test]
+    fn static_params_emit_nothing() {
+        let params = vec![param("radius", 0.3, None, vec![])];
+        assert!(!any_param_modulated(&params));
+        assert!(generate_modulation_js(&params, &[]).is_empty());
+    }
+
+    #[test]
+    fn audio_binding_adds_to_base() {
+        let m = Expr::BinOp {
+            op: BinOp::Mul,
+            left: Box::new(audio("bass")),
+            right: Box::new(Expr::Number(0.2, Span::default())),
+            span: Span::default(),
+        };
+        let params = vec![param("radius", 0.3, Some(m), vec![])];
+        let js = generate_modulation_js(&params, &[]);
+        assert!(js.contains("function modulationUpdate(time, renderer)"));
+        assert!(js.contains("const audioBass = renderer.audioData.bass || 0;"));
+        assert!(js.contains("let _val_radius = 0.3 + (audioBass * 0.2);"));
+        assert!(js.contains("params.radius = _val_radius;"));
+    }
+
+    #[test]
+    fn temporal_ops_run_between_eval_and_write() {
+        let params = vec![param(
+            "bass",
+            0.5,
+            Some(audio("bass")),
+            vec![TemporalOp::Smooth(Duration::Millis(50.0))],
+        )];
+        let js = generate_modulation_js(&params, &[]);
+        // Each renderer makes its processors on its first frame
+        let init = js.find("_smooth_bass: new GameEMAFilter(0.05),").unwrap();
+        let func = js.find("function modulationUpdate").unwrap();
+        assert!(init < func);
+        assert!(js.contains("  const temporal = renderer._temporal ??= temporalProcessors();"));
+
+        let eval = js.find("let _val_bass").unwrap();
+        let smooth = js.find("_val_bass = temporal._smooth_bass.update(_val_bass, time);").unwrap();
+        let write = js.find("params.bass = _val_bass;").unwrap();
+        assert!(eval < smooth && smooth < write);
+    }
+
+    #[test]
+    fn param_references_read_user_params() {
+        let uniforms = vec![
+            UniformInfo { name: "speed".into(), default: 1.0 },
+            UniformInfo { name: "phase".into(), default: 0.0 },
+        ];
+        let m = Expr::BinOp {
+            op: BinOp::Mul,
+            left: Box::new(Expr::Ident("time".into(), Span::default())),
+            right: Box::new(Expr::Ident("speed".into(), Span::default())),
+            span: Span::default(),
+        };
+        let params = vec![param("phase", 0.0, Some(m), vec![])];
+        let js = generate_modulation_js(&params, &uniforms);
+        assert!(js.contains("const speed = params.speed;"));
+        assert!(!js.contains("const time"));
+    }
+}
@@ -2,9 +2,11 @@
 //!
 //! Categorizes signals by pattern matching on Expr variants:
 //! - `mouse.click` → click listener
-//! - `mouse.x`/`mouse.y` → mousemove with normalized coordinates
+//! - `mouse.x`/`mouse.y` → a subscription to the renderer's pointer tracker,
+//!   in the same aspect-corrected space as `p`
 //! - `key("x")` → keydown listener
-//! - `audio.field > threshold` → audio threshold check in animation loop
+//! - `audio.field > threshold` (any comparison, or several joined with
+//!   `&&`, `||` and `!`) → audio threshold check in animation loop
 
 use crate::ast::{BinOp, Expr, ReactBlock};
 use crate::codegen::expr;
@@ -14,12 +16,13 @@ use crate::codegen::UniformInfo;
 enum SignalKind {
     /// `mouse.click` — generates a click addEventListener
     MouseClick,
-    /// `mouse.x` or `mouse.y` — generates a mousemove listener with normalized coords
+    /// `mouse.x` or `mouse.y` — subscribes to `renderer.pointer` moves
     MouseMove(String),
     /// `key("x")` — generates a keydown listener for a specific key
     Key(String),
-    /// `audio.field > threshold` — generates an audio threshold check
-    AudioThreshold { field: String, threshold: f64 },
+    /// `audio.field > threshold` and friends — generates an audio threshold
+    /// check; `fields` name the check, `condition` is its JS test
+    AudioThreshold { fields: Vec<String>, condition: String },
     /// Unrecognized signal — emit as a comment
     Unknown(String),
 }
@@ -27,42 +30,59 @@ enum SignalKind {
 /// Classify a signal expression into a SignalKind.
 fn classify_signal(signal: &Expr) -> SignalKind {
     match signal {
-        Expr::DottedIdent { object, field } if object == "mouse" && field == "click" => {
+        Expr::DottedIdent { object, field, .. } if object == "mouse" && field == "click" => {
             SignalKind::MouseClick
         }
-        Expr::DottedIdent { object, field }
+        Expr::DottedIdent { object, field, .. }
             if object == "mouse" && (field == "x" || field == "y") =>
         {
             SignalKind::MouseMove(field.clone())
         }
-        Expr::Call { name, args } if name == "key" => {
+        Expr::Call { name, args, .. } if name == "key" => {
             let key_name = args.first().and_then(|a| match &a.value {
-                Expr::String(s) => Some(s.clone()),
-                Expr::Ident(s) => Some(s.clone()),
+                Expr::String(s, _) => Some(s.clone()),
+                Expr::Ident(s, _) => Some(s.clone()),
                 _ => None,
             });
             SignalKind::Key(key_name.unwrap_or_else(|| "unknown".into()))
         }
-        Expr::BinOp {
-            op: BinOp::Gt,
-            left,
-            right,
-        } => {
-            if let Expr::DottedIdent { object, field } = left.as_ref() {
-                if object == "audio" {
-                    let threshold = match right.as_ref() {
-                        Expr::Number(v) => *v,
-                        _ => 0.5,
-                    };
-                    return SignalKind::AudioThreshold {
-                        field: field.clone(),
-                        threshold,
-                    };
-                }
+        _ => {
+            let mut fields = Vec::new();
+            match audio_condition(signal, &mut fields) {
+                Some(condition) => SignalKind::AudioThreshold { fields, condition },
+                None => SignalKind::Unknown(expr::compile_js(signal)),
+            }
+        }
+    }
+}
+
+/// The JS test for an audio threshold signal: comparisons of an `audio.*`
+/// field against a number, combined with `&&`, `||` and `!`. Collects the
+/// fields read into `fields`.
+fn audio_condition(signal: &Expr, fields: &mut Vec<String>) -> Option<String> {
+    match signal {
+        Expr::BinOp { op: op @ (BinOp::And | BinOp::Or), left, right, .. } => {
+            let l = audio_condition(left, fields)?;
+            let r = audio_condition(right, fields)?;
+            Some(format!("({l} {} {r})", expr::op_symbol(op)))
+        }
+        Expr::BinOp { op, left, right, .. } if op.is_boolean() => {
+            let Expr::DottedIdent { object, field, .. } = left.as_ref() else { return None };
+            if object != "audio" {
+                return None;
             }
-            SignalKind::Unknown(expr::compile_js(signal))
+            let threshold = match right.as_ref() {
+                Expr::Number(v, _) => *v,
+                _ => 0.5,
+            };
+            if !fields.contains(field) {
+                fields.push(field.clone());
+            }
+            Some(format!("audioData.{field} {} {threshold}", expr::op_symbol(op)))
         }
-        _ => SignalKind::Unknown(expr::compile_js(signal)),
+        Expr::Not(inner, _) => Some(format!("!({})", audio_condition(inner, fields)?)),
+        Expr::Paren(inner, _) => audio_condition(inner, fields),
+        _ => None,
     }
 }
 
@@ -75,7 +95,7 @@ fn classify_signal(signal: &Expr) -> SignalKind {
 /// - Other expressions → compile to JS
 fn compile_action(action: &Expr, uniforms: &[UniformInfo]) -> String {
     match action {
-        Expr::Ident(name) => {
+        Expr::Ident(name, _) => {
             // Setting a uniform directly via the renderer
             if uniforms.iter().any(|u| u.name == *name) {
                 format!("renderer.setParam('{name}', v);")
@@ -83,14 +103,14 @@ fn compile_action(action: &Expr, uniforms: &[UniformInfo]) -> String {
                 format!("// set {name} = v;")
             }
         }
-        Expr::Call { name, args } if name == "pulse" => {
+        Expr::Call { name, args, .. } if name == "pulse" => {
             let magnitude = args
                 .first()
                 .map(|a| expr::compile_js(&a.value))
                 .unwrap_or_else(|| "1.0".into());
             format!("pulse({magnitude});")
         }
-        Expr::Call { name, args } if name == "toggle" => {
+        Expr::Call { name, args, .. } if name == "toggle" => {
             let target = args
                 .first()
                 .map(|a| expr::compile_js(&a.value))
@@ -114,15 +134,8 @@ pub fn generate_react_js(block: &ReactBlock, uniforms: &[UniformInfo]) -> String
         return String::new();
     }
 
-    // Collect audio threshold fields to wire into the render loop
-    let audio_fields: Vec<String> = block
-        .reactions
-        .iter()
-        .filter_map(|r| match classify_signal(&r.signal) {
-            SignalKind::AudioThreshold { field, .. } => Some(field),
-            _ => None,
-        })
-        .collect();
+    // Audio threshold checks to wire into the render loop, one per reaction
+    let mut audio_checks: Vec<String> = Vec::new();
 
     let mut s = String::with_capacity(1024);
     s.push_str("// GAME react — event listeners + audio threshold checks\n");
@@ -159,17 +172,8 @@ pub fn generate_react_js(block: &ReactBlock, uniforms: &[UniformInfo]) -> String
                 s.push_str("  });\n");
             }
             SignalKind::MouseMove(axis) => {
-                s.push_str("  canvas.addEventListener('mousemove', function(e) {\n");
-                s.push_str("    const rect = canvas.getBoundingClientRect();\n");
-                if axis == "x" {
-                    s.push_str(
-                        "    const v = (e.clientX - rect.left) / rect.width;\n",
-                    );
-                } else {
-                    s.push_str(
-                        "    const v = 1.0 - (e.clientY - rect.top) / rect.height;\n",
-                    );
-                }
+                s.push_str("  renderer.pointer.subscribe(function(mouse) {\n");
+                s.push_str(&format!("    const v = mouse.{axis};\n"));
                 s.push_str(&format!("    {action_js}\n"));
                 s.push_str("  });\n");
             }
@@ -183,23 +187,27 @@ pub fn generate_react_js(block: &ReactBlock, uniforms: &[UniformInfo]) -> String
                 s.push_str("    }\n");
                 s.push_str("  });\n");
             }
-            SignalKind::AudioThreshold { field, threshold } => {
-                s.push_str(&format!(
-                    "  // audio threshold: {field} > {threshold}\n"
-                ));
-                s.push_str(&format!(
-                    "  function _checkAudio_{field}() {{\n"
-                ));
-                s.push_str(&format!(
-                    "    const audioData = renderer.audioData;\n"
-                ));
+            SignalKind::AudioThreshold { fields, condition } => {
+                // Two reactions on the same fields need distinct checks
+                let base = format!("_checkAudio_{}", fields.join("_"));
+                let mut check = base.clone();
+                let mut n = 1;
+                while audio_checks.contains(&check) {
+                    n += 1;
+                    check = format!("{base}_{n}");
+                }
                 s.push_str(&format!(
-                    "    if (audioData && audioData.{field} > {threshold}) {{\n"
+                    "  // audio threshold: {}\n",
+                    condition.replace("audioData.", "")
                 ));
+                s.push_str(&format!("  function {check}() {{\n"));
+                s.push_str("    const audioData = renderer.audioData;\n");
+                s.push_str(&format!("    if (audioData && {condition}) {{\n"));
                 s.push_str("      const v = 1.0;\n");
                 s.push_str(&format!("      {action_js}\n"));
                 s.push_str("    }\n");
                 s.push_str("  }\n");
+                audio_checks.push(check);
             }
             SignalKind::Unknown(sig_js) => {
                 s.push_str(&format!("  // unknown signal: {sig_js}\n"));
@@ -208,10 +216,10 @@ pub fn generate_react_js(block: &ReactBlock, uniforms: &[UniformInfo]) -> String
     }
 
     // Wire audio threshold checks into the render loop via _onRender callback
-    if !audio_fields.is_empty() {
+    if !audio_checks.is_empty() {
         s.push_str("  renderer._onRender = function() {\n");
-        for field in &audio_fields {
-            s.push_str(&format!("    _checkAudio_{field}();\n"));
+        for check in &audio_checks {
+            s.push_str(&format!("    {check}();\n"));
         }
         s.push_str("  };\n");
     }
@@ -229,6 +237,7 @@ mod tests {
     fn empty_react_block_returns_empty() {
         let block = ReactBlock {
             reactions: vec![],
+            span: Span::default(),
         };
         let js = generate_react_js(&block, &[]);
         assert!(js.is_empty());
@@ -241,15 +250,20 @@ mod tests {
                 signal: Expr::DottedIdent {
                     object: "mouse".into(),
                     field: "click".into(),
+                    span: Span::default(),
                 },
                 action: Expr::Call {
                     name: "pulse".into(),
                     args: vec![Arg {
                         name: None,
-                        value: Expr::Number(1.0),
+                        value: Expr::Number(1.0, Span::default()),
+                        span: Span::default(),
                     }],
+                    span: Span::default(),
                 },
+                span: Span::default(),
             }],
+            span: Span::default(),
         };
         let js = generate_react_js(&block, &[]);
         assert!(js.contains("addEventListener('click'"));
@@ -257,7 +271,7 @@ mod tests {
     }
 
     #[test]
-    fn mouse_x_generates_mousemove_listener() {
+    fn mouse_x_subscribes_to_shared_pointer() {
         let uniforms = vec![UniformInfo {
             name: "intensity".into(),
             default: 0.5,
@@ -267,13 +281,17 @@ mod tests {
                 signal: Expr::DottedIdent {
                     object: "mouse".into(),
                     field: "x".into(),
+                    span: Span::default(),
                 },
-                action: Expr::Ident("intensity".into()),
+                action: Expr::Ident("intensity".into(), Span::default()),
+                span: Span::default(),
             }],
+            span: Span::default(),
         };
         let js = generate_react_js(&block, &uniforms);
-        assert!(js.contains("addEventListener('mousemove'"));
-        assert!(js.contains("rect.width"));
+        assert!(js.contains("renderer.pointer.subscribe(function(mouse) {"));
+        assert!(js.contains("const v = mouse.x;"));
+        assert!(!js.contains("addEventListener('mousemove'"));
         assert!(js.contains("renderer.setParam('intensity', v);"));
     }
 
@@ -285,17 +303,23 @@ mod tests {
                     name: "key".into(),
                     args: vec![Arg {
                         name: None,
-                        value: Expr::String("x".into()),
+                        value: Expr::String("x".into(), Span::default()),
+                        span: Span::default(),
                     }],
+                    span: Span::default(),
                 },
                 action: Expr::Call {
                     name: "pulse".into(),
                     args: vec![Arg {
                         name: None,
-                        value: Expr::Number(2.0),
+                        value: Expr::Number(2.0, Span::default()),
+                        span: Span::default(),
                     }],
+                    span: Span::default(),
                 },
+                span: Span::default(),
             }],
+            span: Span::default(),
         };
         let js = generate_react_js(&block, &[]);
         assert!(js.contains("addEventListener('keydown'"));
@@ -311,17 +335,23 @@ mod tests {
                     left: Box::new(Expr::DottedIdent {
                         object: "audio".into(),
                         field: "bass".into(),
+                        span: Span::default(),
                     }),
-                    right: Box::new(Expr::Number(0.8)),
+                    right: Box::new(Expr::Number(0.8, Span::default())),
+                    span: Span::default(),
                 },
                 action: Expr::Call {
                     name: "pulse".into(),
                     args: vec![Arg {
                         name: None,
-                        value: Expr::Number(1.0),
+                        value: Expr::Number(1.0, Span::default()),
+                        span: Span::default(),
                     }],
+                    span: Span::default(),
                 },
+                span: Span::default(),
             }],
+            span: Span::default(),
         };
         let js = generate_react_js(&block, &[]);
         assert!(js.contains("audioData.bass > 0.8"));
@@ -330,7 +360,35 @@ mod tests {
     }
 
     #[test]
-    fn mouse_y_generates_inverted_coord() {
+    fn compound_audio_conditions_get_their_own_checks() {
+        let reaction = |src: &str| {
+            let tokens = crate::lexer::lex(src).unwrap();
+            Reaction {
+                signal: crate::parser::Parser::new(tokens).parse_expr().unwrap(),
+                action: Expr::Ident("x".into(), Span::default()),
+                span: Span::default(),
+            }
+        };
+        let block = ReactBlock {
+            reactions: vec![
+                reaction("audio.bass >= 0.8 && !(audio.mid < 0.2)"),
+                reaction("audio.bass <= 0.1"),
+                reaction("audio.bass > 0.5 && mouse.x > 0.5"),
+                reaction("audio.bass > 0.9"),
+            ],
+            span: Span::default(),
+        };
+        let js = generate_react_js(&block, &[]);
+        assert!(js.contains("if (audioData && (audioData.bass >= 0.8 && !(audioData.mid < 0.2))) {"));
+        assert!(js.contains("if (audioData && audioData.bass <= 0.1) {"));
+        assert!(js.contains(
+            "    _checkAudio_bass_mid();\n    _checkAudio_bass();\n    _checkAudio_bass_2();\n"
+        ));
+        assert!(js.contains("// unknown signal: ((audioBass > 0.5) && (mouseX > 0.5))"));
+    }
+
+    #[test]
+    fn mouse_y_reads_pointer_y() {
         let uniforms = vec![UniformInfo {
             name: "height".into(),
             default: 0.0,
@@ -340,21 +398,27 @@ mod tests {
                 signal: Expr::DottedIdent {
                     object: "mouse".into(),
                     field: "y".into(),
+                    span: Span::default(),
                 },
-                action: Expr::Ident("height".into()),
+                action: Expr::Ident("height".into(), Span::default()),
+                span: Span::default(),
             }],
+            span: Span::default(),
         };
         let js = generate_react_js(&block, &uniforms);
-        assert!(js.contains("1.0 - (e.clientY"));
+        // Already y-up in `p` space, like the shader's mouse uniform
+        assert!(js.contains("const v = mouse.y;"));
     }
 
     #[test]
     fn unknown_signal_emits_comment() {
         let block = ReactBlock {
             reactions: vec![Reaction {
-                signal: Expr::Number(42.0),
-                action: Expr::Ident("x".into()),
+                signal: Expr::Number(42.0, Span::default()),
+                action: Expr::Ident("x".into(), Span::default()),
+                span: Span::default(),
             }],
+            span: Span::default(),
         };
         let js = generate_react_js(&block, &[]);
         assert!(js.contains("// unknown signal"));
@@ -71,7 +71,7 @@ mod tests {
 
     #[test]
     fn empty_block_returns_empty() {
-        let block = ResonateBlock { entries: vec![] };
+        let block = ResonateBlock { entries: vec![], span: Span::default() };
         let js = generate_resonance_js(&block, &[]);
         assert!(js.is_empty());
     }
@@ -83,8 +83,10 @@ mod tests {
                 source: "kick".into(),
                 target: "bg".into(),
                 field: "scale".into(),
-                weight: Expr::Number(0.3),
+                weight: Expr::Number(0.3, Span::default()),
+                span: Span::default(),
             }],
+            span: Span::default(),
         };
         let uniforms = vec![UniformInfo {
             name: "scale".into(),
@@ -104,8 +106,10 @@ mod tests {
                 source: "kick".into(),
                 target: "bg".into(),
                 field: "opacity".into(),
-                weight: Expr::Number(0.5),
+                weight: Expr::Number(0.5, Span::default()),
+                span: Span::default(),
             }],
+            span: Span::default(),
         };
         // No uniforms match
         let js = generate_resonance_js(&block, &[]);
@@ -121,15 +125,18 @@ mod tests {
                     source: "kick".into(),
                     target: "bg".into(),
                     field: "scale".into(),
-                    weight: Expr::Number(0.3),
+                    weight: Expr::Number(0.3, Span::default()),
+                    span: Span::default(),
                 },
                 ResonateEntry {
                     source: "snare".into(),
                     target: "fg".into(),
                     field: "intensity".into(),
-                    weight: Expr::Number(0.7),
+                    weight: Expr::Number(0.7, Span::default()),
+                    span: Span::default(),
                 },
             ],
+            span: Span::default(),
         };
         let uniforms = vec![
             UniformInfo {
@@ -156,15 +163,18 @@ mod tests {
                     source: "kick".into(),
                     target: "bg".into(),
                     field: "scale".into(),
-                    weight: Expr::Number(0.3),
+                    weight: Expr::Number(0.3, Span::default()),
+                    span: Span::default(),
                 },
                 ResonateEntry {
                     source: "snare".into(),
                     target: "bg".into(),
                     field: "scale".into(),
-                    weight: Expr::Number(0.5),
+                    weight: Expr::Number(0.5, Span::default()),
+                    span: Span::default(),
                 },
             ],
+            span: Span::default(),
         };
         let uniforms = vec![UniformInfo {
             name: "scale".into(),
@@ -181,8 +191,10 @@ mod tests {
                 source: "kick".into(),
                 target: "bg".into(),
                 field: "scale".into(),
-                weight: Expr::Number(0.3),
+                weight: Expr::Number(0.3, Span::default()),
+                span: Span::default(),
             }],
+            span: Span::default(),
         };
         // Uniform named "bg_scale" matches compound target name
         let uniforms = vec![UniformInfo {
@@ -4,6 +4,7 @@
 //! of parameter transitions, using BPM for bar→seconds conversion.
 
 use crate::ast::{Duration, Expr, Motif, Phrase, ScoreBlock, Section};
+use crate::codegen::UniformInfo;
 
 /// A resolved timeline entry with absolute start time in seconds.
 #[derive(Debug, Clone)]
@@ -29,11 +30,23 @@ fn duration_to_seconds(d: &Duration, bpm: f64) -> f64 {
 /// Extract a float from an Expr (best-effort).
 fn expr_to_f64(e: &Expr) -> f64 {
     match e {
-        Expr::Number(v) => *v,
+        Expr::Number(v, _) => *v,
         _ => 0.0,
     }
 }
 
+/// Arc/score target → uniform name: `bg.opacity` tries `bg_opacity`, then
+/// the bare `opacity`.
+pub(crate) fn resolve_target(target: &str, uniforms: &[UniformInfo]) -> Option<String> {
+    let flat = target.replace('.', "_");
+    let bare = target.rsplit('.').next().unwrap_or(target);
+    let found = [flat.as_str(), bare]
+        .into_iter()
+        .find(|name| uniforms.iter().any(|u| u.name == *name))
+        .map(str::to_string);
+    found
+}
+
 /// Flatten a score block into a timeline of arc entries.
 pub fn flatten_score(score: &ScoreBlock) -> Vec<TimelineEntry> {
     let bpm = score.tempo_bpm;
@@ -156,7 +169,10 @@ fn emit_motif(timeline: &mut Vec<TimelineEntry>, motif: &Motif, offset: f64, bpm
 }
 
 /// Generate JavaScript timeline playback engine from a score.
-pub fn generate_score_js(score: &ScoreBlock) -> String {
+///
+/// Targets are resolved against `uniforms` so `apply(time, params)` can write
+/// straight into a renderer's `userParams`.
+pub fn generate_score_js(score: &ScoreBlock, uniforms: &[UniformInfo]) -> String {
     let timeline = flatten_score(score);
     let mut s = String::with_capacity(2048);
 
@@ -165,9 +181,14 @@ pub fn generate_score_js(score: &ScoreBlock) -> String {
     s.push_str("    this._timeline = [\n");
 
     for entry in &timeline {
+        let param = match resolve_target(&entry.target, uniforms) {
+            Some(name) => format!("'{name}'"),
+            None => "null".into(),
+        };
         s.push_str(&format!(
-            "      {{target:'{}',from:{},to:{},start:{},dur:{},easing:'{}'}},\n",
+            "      {{target:'{}',param:{},from:{},to:{},start:{},dur:{},easing:'{}'}},\n",
             entry.target,
+            param,
             entry.from,
             entry.to,
             entry.start_seconds,
@@ -201,6 +222,13 @@ pub fn generate_score_js(score: &ScoreBlock) -> String {
     s.push_str("    return result;\n");
     s.push_str("  }\n\n");
 
+    s.push_str("  apply(time, params) {\n");
+    s.push_str("    const values = this.evaluate(time);\n");
+    s.push_str("    for (const e of this._timeline) {\n");
+    s.push_str("      if (e.param !== null && e.target in values) params[e.param] = values[e.target];\n");
+    s.push_str("    }\n");
+    s.push_str("  }\n\n");
+
     s.push_str("  _ease(t, name) {\n");
     s.push_str("    switch(name) {\n");
     s.push_str("      case 'ease_in': return t * t;\n");
@@ -227,20 +255,22 @@ mod tests {
                     name: "rise".into(),
                     entries: vec![ArcEntry {
                         target: "scale".into(),
-                        from: Expr::Number(0.5),
-                        to: Expr::Number(2.0),
+                        from: Expr::Number(0.5, Span::default()),
+                        to: Expr::Number(2.0, Span::default()),
                         duration: Duration::Bars(4),
                         easing: Some("ease_in".into()),
+                        span: Span::default(),
                     }],
                 },
                 Motif {
                     name: "fall".into(),
                     entries: vec![ArcEntry {
                         target: "scale".into(),
-                        from: Expr::Number(2.0),
-                        to: Expr::Number(0.5),
+                        from: Expr::Number(2.0, Span::default()),
+                        to: Expr::Number(0.5, Span::default()),
                         duration: Duration::Bars(2),
                         easing: None,
+                        span: Span::default(),
                     }],
                 },
             ],
@@ -253,6 +283,7 @@ mod tests {
                 phrases: vec!["build".into()],
             }],
             arrange: vec!["verse".into()],
+            span: Span::default(),
         }
     }
 
@@ -272,12 +303,23 @@ mod tests {
     #[test]
     fn generate_score_js_produces_class() {
         let score = make_score();
-        let js = generate_score_js(&score);
+        let js = generate_score_js(&score, &[]);
         assert!(js.contains("class GameScorePlayer"));
         assert!(js.contains("_timeline"));
         assert!(js.contains("ease_in"));
     }
 
+    #[test]
+    fn score_targets_resolve_to_params() {
+        let score = make_score();
+        let uniforms = vec![UniformInfo { name: "scale".into(), default: 1.0 }];
+        let js = generate_score_js(&score, &uniforms);
+        assert!(js.contains("target:'scale',param:'scale'"));
+        assert!(js.contains("apply(time, params)"));
+        let js = generate_score_js(&score, &[]);
+        assert!(js.contains("param:null"));
+    }
+
     #[test]
     fn duration_conversion_120bpm() {
         // 120 BPM = 0.5s per beat = 2s per bar
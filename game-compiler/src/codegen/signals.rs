@@ -0,0 +1,240 @@
+//! Signal resolution — maps `audio.*`, `mouse.*` and `data.*` references in
+//! shader expressions to the uniform slots each backend actually declares.
+//!
+//! `audio.*` and `mouse.x`/`mouse.y` live in the fixed uniform header; `data.*`
+//! fields are declared as extra user uniforms named `data_<field>` and exposed
+//! as component attributes/properties by the runtime. The other pointer fields
+//! (`mouse.down`, `mouse.vx`, ...) become `mouse_<field>` uniforms, declared only
+//! when a cinematic reads them and fed by the runtime's pointer tracker.
+
+use crate::ast::{walk_stages, Cinematic, Expr, LayerBody, Span, Stage};
+use crate::codegen::expr;
+use crate::error::{suggest_similar, CompileError, ErrorCode};
+
+/// Audio analysis fields present in the uniform header.
+pub const AUDIO_FIELDS: &[&str] = &["bass", "mid", "treble", "energy", "beat"];
+
+/// Pointer fields: position (in the header), button state, click pulse,
+/// drag velocity and the touch count plus second touch point.
+pub const MOUSE_FIELDS: &[&str] = &["x", "y", "down", "click", "vx", "vy", "touches", "x2", "y2"];
+
+/// Uniform backing a `mouse.<field>` outside the header's `x`/`y`.
+pub fn mouse_uniform_name(field: &str) -> Option<String> {
+    (field != "x" && field != "y").then(|| format!("mouse_{field}"))
+}
+
+/// Uniform name backing a `data.<field>` binding.
+pub fn data_uniform_name(field: &str) -> String {
+    format!("data_{field}")
+}
+
+/// Resolve `object.field` to a WGSL expression.
+pub fn resolve_wgsl(object: &str, field: &str) -> String {
+    match object {
+        "audio" => format!("u.audio_{field}"),
+        "mouse" => mouse_uniform_name(field).unwrap_or_else(|| format!("u.mouse.{field}")),
+        "data" => data_uniform_name(field),
+        _ => format!("{object}.{field}"),
+    }
+}
+
+/// Resolve `object.field` to a GLSL expression.
+pub fn resolve_glsl(object: &str, field: &str) -> String {
+    match object {
+        "audio" => format!("u_audio_{field}"),
+        "mouse" => mouse_uniform_name(field).unwrap_or_else(|| format!("u_mouse.{field}")),
+        "data" => data_uniform_name(field),
+        _ => format!("{object}.{field}"),
+    }
+}
+
+/// Collect every `data.*` field referenced by a cinematic's layers, in
+/// first-use order: stage args, inline params and their modulation.
+pub fn collect_cinematic_data_fields(cinematic: &Cinematic) -> Vec<String> {
+    collect_cinematic_fields(cinematic, "data")
+}
+
+/// The `mouse_<field>` uniforms a cinematic's layers read, in first-use order.
+pub fn collect_cinematic_mouse_uniforms(cinematic: &Cinematic) -> Vec<String> {
+    collect_cinematic_fields(cinematic, "mouse")
+        .iter()
+        .filter_map(|field| mouse_uniform_name(field))
+        .collect()
+}
+
+fn collect_cinematic_fields(cinematic: &Cinematic, object: &str) -> Vec<String> {
+    let mut fields = Vec::new();
+    for layer in &cinematic.layers {
+        let params = match &layer.body {
+            LayerBody::Params(params) => params.as_slice(),
+            LayerBody::Pipeline(stages) => {
+                for stage in stages {
+                    for arg in &stage.args {
+                        expr::collect_signal_fields(&arg.value, object, &mut fields);
+                    }
+                }
+                &[]
+            }
+        };
+        for param in layer.opts.iter().chain(params) {
+            expr::collect_signal_fields(&param.value, object, &mut fields);
+            if let Some(m) = &param.modulation {
+                expr::collect_signal_fields(m, object, &mut fields);
+            }
+        }
+    }
+    fields
+}
+
+/// Reject `audio.*` / `mouse.*` fields in stage args that have no uniform slot.
+pub fn validate_signals(cinematic: &Cinematic) -> Result<(), CompileError> {
+    for layer in &cinematic.layers {
+        if let LayerBody::Pipeline(stages) = &layer.body {
+            let mut all = Vec::new();
+            walk_stages(stages, &mut |stage| all.push(stage));
+            for stage in all {
+                for arg in &stage.args {
+                    check_expr(&arg.value, &layer.name, stage)?;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+fn check_expr(e: &Expr, layer: &str, stage: &Stage) -> Result<(), CompileError> {
+    match e {
+        Expr::DottedIdent { object, field, span } => {
+            let known = match object.as_str() {
+                "audio" => AUDIO_FIELDS,
+                "mouse" => MOUSE_FIELDS,
+                _ => return Ok(()),
+            };
+            if known.contains(&field.as_str()) {
+                return Ok(());
+            }
+            let mut err = stage
+                .locate(CompileError::validation(format!(
+                    "layer '{layer}': unknown signal '{object}.{field}' in '{}'",
+                    stage.name
+                )))
+                .with_span(*span)
+                .with_code(ErrorCode::E006);
+            if let Some(s) = suggest_similar(field, known) {
+                let field_span = if span.is_empty() {
+                    Span::default()
+                } else {
+                    Span::new(span.end - field.len(), span.end)
+                };
+                err = err
+                    .with_help(format!("did you mean '{object}.{s}'?"))
+                    .with_fix(field_span, s);
+            }
+            Err(err)
+        }
+        Expr::Paren(inner, _) | Expr::Neg(inner, _) | Expr::Not(inner, _) => {
+            check_expr(inner, layer, stage)
+        }
+        Expr::BinOp { left, right, .. } => {
+            check_expr(left, layer, stage)?;
+            check_expr(right, layer, stage)
+        }
+        Expr::Call { args, .. } => args.iter().try_for_each(|a| check_expr(&a.value, layer, stage)),
+        Expr::Array(elems, _) => elems.iter().try_for_each(|el| check_expr(el, layer, stage)),
+        Expr::Ternary { condition, if_true, if_false, .. } => {
+            check_expr(condition, layer, stage)?;
+            check_expr(if_true, layer, stage)?;
+            check_expr(if_false, layer, stage)
+        }
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ast::*;
+
+    fn dotted(object: &str, field: &str) -> Expr {
+        Expr::DottedIdent { object: object.into(), field: field.into(), span: Span::default() }
+    }
+
+    fn pipeline_cinematic(args: Vec<Expr>) -> Cinematic {
+        Cinematic {
+            name: "test".into(),
+            layers: vec![Layer {
+                name: "main".into(),
+                opts: vec![],
+                memory: None,
+                cast: None,
+                blend: None,
+                body: LayerBody::Pipeline(vec![Stage {
+                    name: "circle".into(),
+                    args: args.into_iter().map(|value| Arg { name: None, value, span: Span::default() }).collect(),
+                    span: Span::default(),
+                    expanded_from: vec![],
+                }]),
+                span: Span::default(),
+            }],
+            arcs: vec![],
+            resonates: vec![],
+            listen: None, voice: None, score: None, gravity: None,
+            lenses: vec![], react: None, defines: vec![],
+            span: Span::default(),
+        }
+    }
+
+    #[test]
+    fn audio_and_mouse_resolve_to_header_slots() {
+        assert_eq!(resolve_wgsl("audio", "bass"), "u.audio_bass");
+        assert_eq!(resolve_glsl("audio", "bass"), "u_audio_bass");
+        assert_eq!(resolve_wgsl("mouse", "y"), "u.mouse.y");
+        assert_eq!(resolve_glsl("mouse", "x"), "u_mouse.x");
+    }
+
+    #[test]
+    fn extra_mouse_fields_resolve_to_uniform_aliases() {
+        assert_eq!(resolve_wgsl("mouse", "down"), "mouse_down");
+        assert_eq!(resolve_glsl("mouse", "vx"), "mouse_vx");
+        let cin = pipeline_cinematic(vec![
+            dotted("mouse", "x"),
+            dotted("mouse", "click"),
+            dotted("mouse", "y2"),
+        ]);
+        assert_eq!(collect_cinematic_mouse_uniforms(&cin), vec!["mouse_click", "mouse_y2"]);
+        assert!(validate_signals(&cin).is_ok());
+    }
+
+    #[test]
+    fn data_resolves_to_uniform_alias() {
+        assert_eq!(resolve_wgsl("data", "progress"), "data_progress");
+        assert_eq!(resolve_glsl("data", "progress"), "data_progress");
+    }
+
+    #[test]
+    fn collects_data_fields_from_args_and_params() {
+        let mut cin = pipeline_cinematic(vec![dotted("data", "progress")]);
+        cin.layers[0].opts.push(Param {
+            name: "glow_amt".into(),
+            value: Expr::Number(1.0, Span::default()),
+            modulation: Some(dotted("data", "health")),
+            temporal_ops: vec![],
+            span: Span::default(),
+        });
+        assert_eq!(collect_cinematic_data_fields(&cin), vec!["progress", "health"]);
+    }
+
+    #[test]
+    fn unknown_audio_field_suggests_correction() {
+        let cin = pipeline_cinematic(vec![dotted("audio", "bas")]);
+        let err = validate_signals(&cin).unwrap_err();
+        assert!(err.to_string().contains("unknown signal 'audio.bas'"));
+        assert_eq!(err.help(), Some("did you mean 'audio.bass'?"));
+    }
+
+    #[test]
+    fn known_signals_validate() {
+        let cin = pipeline_cinematic(vec![dotted("audio", "energy"), dotted("mouse", "x")]);
+        assert!(validate_signals(&cin).is_ok());
+    }
+}
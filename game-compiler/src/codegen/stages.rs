@@ -1,39 +1,86 @@
 //! Stage pipeline state machine for shader codegen.
 
-use crate::ast::{Arg, Expr, Stage};
-use crate::builtins::{self, ShaderState};
+use crate::ast::{Arg, Expr, Span, Stage};
+use crate::builtins::{self, BuiltinFn, ShaderState};
+use crate::codegen::{color, expr, signals};
 use crate::error::{CompileError, ErrorCode};
 
-/// Resolve an argument value to a float string for shader emission.
-pub fn resolve_arg(arg: &Arg, idx: usize, builtin_name: &str) -> String {
-    match &arg.value {
-        Expr::Number(v) => format!("{v:.6}"),
-        Expr::Ident(name) => name.clone(),
-        Expr::DottedIdent { object, field } => format!("{object}_{field}"),
-        _ => {
-            // Fallback: use builtin default if available
-            builtins::lookup(builtin_name)
-                .and_then(|b| b.params.get(idx))
-                .and_then(|p| p.default)
-                .map(|d| format!("{d:.6}"))
-                .unwrap_or_else(|| "0.0".into())
-        }
-    }
+/// Resolve an argument value to a WGSL float expression for shader emission.
+pub fn resolve_arg(arg: &Arg) -> String {
+    lower_arg(&arg.value, expr::value_wgsl, signals::resolve_wgsl)
+}
+
+/// Resolve an argument value to a GLSL float expression for shader emission.
+pub fn resolve_arg_glsl(arg: &Arg) -> String {
+    lower_arg(&arg.value, expr::value_glsl, signals::resolve_glsl)
 }
 
-/// Get an arg value by name or position.
+/// Get an arg value by name or position, lowered to WGSL.
+///
+/// A string (`mirror("xy")`) has no float value, so it takes the default.
 pub fn get_arg(args: &[Arg], name: &str, pos: usize, stage_name: &str) -> String {
-    // Try named first
-    for arg in args {
-        if arg.name.as_deref() == Some(name) {
-            return resolve_arg(arg, pos, stage_name);
-        }
+    find_float_arg(args, name, pos)
+        .map(resolve_arg)
+        .unwrap_or_else(|| default_arg(pos, stage_name))
+}
+
+/// Get an arg value by name or position, lowered to GLSL.
+pub fn get_arg_glsl(args: &[Arg], name: &str, pos: usize, stage_name: &str) -> String {
+    find_float_arg(args, name, pos)
+        .map(resolve_arg_glsl)
+        .unwrap_or_else(|| default_arg(pos, stage_name))
+}
+
+/// The `r, g, b` slots of `tint` or `shade` as a WGSL `vec3`; a lone
+/// color argument (`tint(gold)`, `tint(#ff8800)`) supplies all three.
+pub fn get_rgb(args: &[Arg], stage_name: &str) -> String {
+    match lone_color(args) {
+        Some(arg) => resolve_arg(arg),
+        None => format!(
+            "vec3<f32>({}, {}, {})",
+            get_arg(args, "r", 0, stage_name),
+            get_arg(args, "g", 1, stage_name),
+            get_arg(args, "b", 2, stage_name)
+        ),
+    }
+}
+
+/// [`get_rgb`] lowered to GLSL.
+pub fn get_rgb_glsl(args: &[Arg], stage_name: &str) -> String {
+    match lone_color(args) {
+        Some(arg) => resolve_arg_glsl(arg),
+        None => format!(
+            "vec3({}, {}, {})",
+            get_arg_glsl(args, "r", 0, stage_name),
+            get_arg_glsl(args, "g", 1, stage_name),
+            get_arg_glsl(args, "b", 2, stage_name)
+        ),
     }
-    // Try positional
-    if let Some(arg) = args.get(pos) {
-        return resolve_arg(arg, pos, stage_name);
+}
+
+fn lone_color(args: &[Arg]) -> Option<&Arg> {
+    match args {
+        [arg] if arg.name.as_deref().is_none_or(|n| n == "r") && color::is_color(&arg.value) => {
+            Some(arg)
+        }
+        _ => None,
     }
-    // Fallback to default
+}
+
+/// Find an arg by name first, then by position among unnamed args.
+pub(crate) fn find_arg<'a>(args: &'a [Arg], name: &str, pos: usize) -> Option<&'a Arg> {
+    args.iter()
+        .find(|arg| arg.name.as_deref() == Some(name))
+        .or_else(|| args.get(pos).filter(|arg| arg.name.is_none()))
+}
+
+/// [`find_arg`], skipping string values.
+fn find_float_arg<'a>(args: &'a [Arg], name: &str, pos: usize) -> Option<&'a Arg> {
+    find_arg(args, name, pos).filter(|arg| !matches!(arg.value, Expr::String(_, _)))
+}
+
+/// The builtin's declared default for a parameter slot, or `0.0`.
+fn default_arg(pos: usize, stage_name: &str) -> String {
     builtins::lookup(stage_name)
         .and_then(|b| b.params.get(pos))
         .and_then(|p| p.default)
@@ -41,6 +88,105 @@ pub fn get_arg(args: &[Arg], name: &str, pos: usize, stage_name: &str) -> String
         .unwrap_or_else(|| "0.0".into())
 }
 
+/// Resolves `object.field` signal references for one backend.
+type SignalResolver = fn(&str, &str) -> String;
+
+/// Lower an argument expression with the given backend compiler.
+///
+/// Named colors become literals; other bare identifiers stay raw so palette
+/// names and uniform aliases pass through untouched. Signals resolve to
+/// their uniform slots at any depth.
+fn lower_arg(value: &Expr, compile: fn(&Expr) -> String, signal: SignalResolver) -> String {
+    match value {
+        Expr::Number(v, _) => format!("{v:.6}"),
+        Expr::Ident(name, _) if color::named(name).is_some() => compile(value),
+        Expr::Ident(name, _) => expr::resolve_constant(name)
+            .map(str::to_string)
+            .unwrap_or_else(|| name.clone()),
+        Expr::DottedIdent { object, field, .. } => signal(object, field),
+        other => compile(&resolve_signals(other, signal)),
+    }
+}
+
+/// Rewrite `object.field` references into their resolved uniform expressions.
+fn resolve_signals(value: &Expr, signal: SignalResolver) -> Expr {
+    let recurse = |e: &Expr| resolve_signals(e, signal);
+    match value {
+        Expr::DottedIdent { object, field, span } => Expr::Ident(signal(object, field), *span),
+        Expr::Paren(inner, span) => Expr::Paren(Box::new(recurse(inner)), *span),
+        Expr::Neg(inner, span) => Expr::Neg(Box::new(recurse(inner)), *span),
+        Expr::Not(inner, span) => Expr::Not(Box::new(recurse(inner)), *span),
+        Expr::BinOp { op, left, right, span } => Expr::BinOp {
+            op: op.clone(),
+            left: Box::new(recurse(left)),
+            right: Box::new(recurse(right)),
+            span: *span,
+        },
+        Expr::Call { name, args, span } => Expr::Call {
+            name: name.clone(),
+            args: args
+                .iter()
+                .map(|a| Arg { name: a.name.clone(), value: recurse(&a.value), span: a.span })
+                .collect(),
+            span: *span,
+        },
+        Expr::Array(elems, span) => Expr::Array(elems.iter().map(recurse).collect(), *span),
+        Expr::Ternary { condition, if_true, if_false, span } => Expr::Ternary {
+            condition: Box::new(recurse(condition)),
+            if_true: Box::new(recurse(if_true)),
+            if_false: Box::new(recurse(if_false)),
+            span: *span,
+        },
+        other => other.clone(),
+    }
+}
+
+/// Shader locals that stage arguments may reference directly.
+const SHADER_LOCALS: &[&str] = &["time", "p", "uv", "aspect"];
+
+/// Collect identifiers referenced inside a stage argument that name user
+/// parameters, i.e. anything that isn't a shader local, a constant or a color.
+pub fn collect_arg_idents(value: &Expr, out: &mut Vec<String>) {
+    match value {
+        Expr::Ident(name, _)
+            if !SHADER_LOCALS.contains(&name.as_str())
+                && expr::resolve_constant(name).is_none()
+                && color::named(name).is_none()
+                && !out.contains(name) =>
+        {
+            out.push(name.clone());
+        }
+        Expr::Paren(inner, _) | Expr::Neg(inner, _) | Expr::Not(inner, _) => {
+            collect_arg_idents(inner, out)
+        }
+        Expr::BinOp { left, right, .. } => {
+            collect_arg_idents(left, out);
+            collect_arg_idents(right, out);
+        }
+        Expr::Call { args, .. } => {
+            for arg in args {
+                collect_arg_idents(&arg.value, out);
+            }
+        }
+        Expr::Array(elems, _) => {
+            for elem in elems {
+                collect_arg_idents(elem, out);
+            }
+        }
+        Expr::Ternary { condition, if_true, if_false, .. } => {
+            collect_arg_idents(condition, out);
+            collect_arg_idents(if_true, out);
+            collect_arg_idents(if_false, out);
+        }
+        Expr::Pipeline(stages, _) => {
+            for arg in stages.iter().flat_map(|s| &s.args) {
+                collect_arg_idents(&arg.value, out);
+            }
+        }
+        _ => {}
+    }
+}
+
 /// Validate a pipeline of stages — returns error if state transitions are invalid.
 pub fn validate_pipeline(stages: &[Stage]) -> Result<ShaderState, CompileError> {
     let mut state = ShaderState::Position;
@@ -51,23 +197,27 @@ pub fn validate_pipeline(stages: &[Stage]) -> Result<ShaderState, CompileError>
             let msg = format!("unknown stage function: '{}'", stage.name);
             let mut err = CompileError::validation(msg).with_code(ErrorCode::E001);
             if let Some(suggestion) = builtins::suggest(&stage.name) {
-                err = err.with_help(format!("did you mean '{suggestion}'?"));
+                err = err
+                    .with_help(format!("did you mean '{suggestion}'?"))
+                    .with_fix(stage.name_span(), suggestion);
             }
-            err
+            stage.locate(err)
         })?;
 
         if builtin.input != state && builtin.input != ShaderState::Position {
             // Allow implicit state reset for Position-input stages (e.g., fbm | displace | circle).
             // Non-Position stages must match the current pipeline state exactly.
             let prev = prev_stage_name.unwrap_or("(start)");
-            return Err(CompileError::validation(format!(
+            return Err(stage.locate(CompileError::validation(format!(
                 "type mismatch: '{}' expects {} input, but pipeline is in {} state\n  \
                  help: the pipeline flows Position -> Sdf -> Color. \
                  '{}' produces {} output.",
                 stage.name, builtin.input, state, prev, state
-            )).with_code(ErrorCode::E002));
+            )).with_code(ErrorCode::E002)));
         }
 
+        validate_sub_pipelines(stage, builtin)?;
+        validate_string_args(stage)?;
         prev_stage_name = Some(&stage.name);
         state = builtin.output;
     }
@@ -75,13 +225,88 @@ pub fn validate_pipeline(stages: &[Stage]) -> Result<ShaderState, CompileError>
     Ok(state)
 }
 
+/// Check a stage's nested pipelines: combinators need a Position -> Sdf
+/// chain in both `a` and `b`, and no other argument may be a pipeline.
+fn validate_sub_pipelines(stage: &Stage, builtin: &BuiltinFn) -> Result<(), CompileError> {
+    let slots: &[&str] = if builtin.is_combinator() { &["a", "b"] } else { &[] };
+    for (pos, slot) in slots.iter().enumerate() {
+        let Some(Expr::Pipeline(sub, _)) = find_arg(&stage.args, slot, pos).map(|a| &a.value)
+        else {
+            return Err(stage.locate(CompileError::validation(format!(
+                "'{}' expects a shape pipeline for '{slot}', e.g. `{}(circle(0.2), box(0.1, 0.1))`",
+                stage.name, stage.name
+            )).with_code(ErrorCode::E002)));
+        };
+        let out = validate_pipeline(sub)?;
+        if out != ShaderState::Sdf {
+            return Err(stage.locate(CompileError::validation(format!(
+                "type mismatch: '{}' argument '{slot}' must end in an Sdf, \
+                 but its pipeline produces {out}",
+                stage.name
+            )).with_code(ErrorCode::E002)));
+        }
+    }
+    let stray = stage.args.iter().enumerate().find(|(i, arg)| {
+        let slot = match arg.name.as_deref() {
+            Some(n) => slots.contains(&n),
+            None => *i < slots.len(),
+        };
+        !slot && matches!(arg.value, Expr::Pipeline(_, _))
+    });
+    if stray.is_some() {
+        return Err(stage.locate(CompileError::validation(format!(
+            "'{}' does not take a pipeline argument; only combinators do ({})",
+            stage.name,
+            builtins::COMBINATORS.join(", ")
+        )).with_code(ErrorCode::E002)));
+    }
+    Ok(())
+}
+
+/// Reject strings used as numbers inside an argument expression, e.g.
+/// `glow("a" * 2.0)`. A whole-argument string or array element is fine.
+fn validate_string_args(stage: &Stage) -> Result<(), CompileError> {
+    fn nested_string(e: &Expr) -> Option<(&str, Span)> {
+        match e {
+            Expr::Paren(inner, _) | Expr::Neg(inner, _) | Expr::Not(inner, _) => {
+                operand_string(inner)
+            }
+            Expr::BinOp { left, right, .. } => {
+                operand_string(left).or_else(|| operand_string(right))
+            }
+            Expr::Ternary { condition, if_true, if_false, .. } => operand_string(condition)
+                .or_else(|| operand_string(if_true))
+                .or_else(|| operand_string(if_false)),
+            Expr::Call { args, .. } => args.iter().find_map(|a| operand_string(&a.value)),
+            Expr::Array(elems, _) => elems.iter().find_map(nested_string),
+            _ => None,
+        }
+    }
+    fn operand_string(e: &Expr) -> Option<(&str, Span)> {
+        match e {
+            Expr::String(s, span) => Some((s, *span)),
+            other => nested_string(other),
+        }
+    }
+
+    for arg in &stage.args {
+        if let Some((text, span)) = nested_string(&arg.value) {
+            return Err(stage.locate(CompileError::validation(format!(
+                "'{}': string \"{text}\" can't be used as a number",
+                stage.name
+            )).with_code(ErrorCode::E002)).with_span(arg.span).with_span(span));
+        }
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::ast::{Arg, Expr, Stage};
+    use crate::ast::{Arg, BinOp, Expr, Span, Stage};
 
     fn stage(name: &str) -> Stage {
-        Stage { name: name.into(), args: vec![] }
+        Stage { name: name.into(), args: vec![], span: Span::default(), expanded_from: vec![] }
     }
 
     #[test]
@@ -118,7 +343,8 @@ mod tests {
     fn get_arg_positional() {
         let args = vec![Arg {
             name: None,
-            value: Expr::Number(0.5),
+            value: Expr::Number(0.5, Span::default()),
+            span: Span::default(),
         }];
         let val = get_arg(&args, "radius", 0, "circle");
         assert_eq!(val, "0.500000");
@@ -128,12 +354,186 @@ mod tests {
     fn get_arg_named() {
         let args = vec![Arg {
             name: Some("radius".into()),
-            value: Expr::Number(0.75),
+            value: Expr::Number(0.75, Span::default()),
+            span: Span::default(),
         }];
         let val = get_arg(&args, "radius", 0, "circle");
         assert_eq!(val, "0.750000");
     }
 
+    #[test]
+    fn positional_lookup_skips_named_args() {
+        // ring(0.4, width: 0.05): the named width must not fill slot 1 of another name
+        let args = vec![
+            Arg { name: None, value: Expr::Number(0.4, Span::default()), span: Span::default() },
+            Arg {
+                name: Some("width".into()),
+                value: Expr::Number(0.05, Span::default()),
+                span: Span::default(),
+            },
+        ];
+        assert!(find_arg(&args, "radius", 1).is_none());
+        assert_eq!(get_arg(&args, "width", 1, "ring"), "0.050000");
+    }
+
+    #[test]
+    fn string_arg_takes_default() {
+        // mirror("xy") has no float axis, so it mirrors as if left out
+        let value = Expr::String("xy".into(), Span::default());
+        let args = vec![Arg { name: None, value, span: Span::default() }];
+        assert_eq!(get_arg(&args, "axis", 0, "mirror"), "0.000000");
+        assert_eq!(get_arg_glsl(&args, "axis", 0, "mirror"), "0.000000");
+    }
+
+    #[test]
+    fn string_inside_expression_is_rejected() {
+        let value = Expr::BinOp {
+            op: BinOp::Mul,
+            left: Box::new(Expr::String("a".into(), Span::new(10, 13))),
+            right: Box::new(Expr::Number(2.0, Span::new(16, 19))),
+            span: Span::new(10, 19),
+        };
+        let mut glow = stage("glow");
+        glow.args = vec![Arg { name: None, value, span: Span::new(10, 19) }];
+        let err = validate_pipeline(&[stage("circle"), glow]).unwrap_err();
+        assert_eq!(err.code(), Some(ErrorCode::E002));
+        assert_eq!(err.span(), Some(Span::new(10, 13)));
+        assert!(err.to_string().contains("string \"a\""), "{err}");
+    }
+
+    fn call(name: &str, args: Vec<Expr>) -> Expr {
+        Expr::Call {
+            name: name.into(),
+            args: args.into_iter().map(|value| Arg { name: None, value, span: Span::default() }).collect(),
+            span: Span::default(),
+        }
+    }
+
+    #[test]
+    fn get_arg_lowers_nested_expression() {
+        // circle(0.3 + sin(time) * 0.05)
+        let value = Expr::BinOp {
+            op: BinOp::Add,
+            left: Box::new(Expr::Number(0.3, Span::default())),
+            right: Box::new(Expr::BinOp {
+                op: BinOp::Mul,
+                left: Box::new(call("sin", vec![Expr::Ident("time".into(), Span::default())])),
+                right: Box::new(Expr::Number(0.05, Span::default())),
+                span: Span::default(),
+            }),
+            span: Span::default(),
+        };
+        let args = vec![Arg { name: None, value, span: Span::default() }];
+        assert_eq!(get_arg(&args, "radius", 0, "circle"), "(0.3 + (sin(time) * 0.05))");
+        assert_eq!(get_arg_glsl(&args, "radius", 0, "circle"), "(0.3 + (sin(time) * 0.05))");
+    }
+
+    #[test]
+    fn get_arg_backends_differ_on_ternary_and_mod() {
+        let value = Expr::Ternary {
+            condition: Box::new(Expr::BinOp {
+                op: BinOp::Gt,
+                left: Box::new(Expr::Ident("x".into(), Span::default())),
+                right: Box::new(Expr::Number(0.5, Span::default())),
+                span: Span::default(),
+            }),
+            if_true: Box::new(call(
+                "mod",
+                vec![
+                    Expr::Ident("time".into(), Span::default()),
+                    Expr::Number(2.0, Span::default()),
+                ],
+            )),
+            if_false: Box::new(Expr::Number(0.0, Span::default())),
+            span: Span::default(),
+        };
+        let args = vec![Arg { name: None, value, span: Span::default() }];
+        assert_eq!(
+            get_arg(&args, "radius", 0, "circle"),
+            "select(0.0, (time % 2.0), (x > 0.5))"
+        );
+        assert_eq!(
+            get_arg_glsl(&args, "radius", 0, "circle"),
+            "((x > 0.5) ? mod(time, 2.0) : 0.0)"
+        );
+    }
+
+    #[test]
+    fn get_arg_resolves_nested_signals() {
+        let value = Expr::BinOp {
+            op: BinOp::Mul,
+            left: Box::new(Expr::DottedIdent {
+                object: "audio".into(),
+                field: "bass".into(),
+                span: Span::default(),
+            }),
+            right: Box::new(Expr::Number(2.0, Span::default())),
+            span: Span::default(),
+        };
+        let args = vec![Arg { name: None, value, span: Span::default() }];
+        assert_eq!(get_arg(&args, "intensity", 0, "glow"), "(u.audio_bass * 2.0)");
+        assert_eq!(get_arg_glsl(&args, "intensity", 0, "glow"), "(u_audio_bass * 2.0)");
+    }
+
+    #[test]
+    fn get_arg_resolves_top_level_data_signal() {
+        let args = vec![Arg {
+            name: None,
+            value: Expr::DottedIdent {
+                object: "data".into(),
+                field: "progress".into(),
+                span: Span::default(),
+            },
+            span: Span::default(),
+        }];
+        assert_eq!(get_arg(&args, "angle", 0, "mask_arc"), "data_progress");
+    }
+
+    #[test]
+    fn get_arg_negative_literal_no_longer_defaults() {
+        let value = Expr::Neg(Box::new(Expr::Number(0.25, Span::default())), Span::default());
+        let args = vec![Arg { name: None, value, span: Span::default() }];
+        assert_eq!(get_arg(&args, "x", 0, "translate"), "(-0.25)");
+    }
+
+    #[test]
+    fn collect_arg_idents_skips_time_constants_and_colors() {
+        let value = Expr::BinOp {
+            op: BinOp::Add,
+            left: Box::new(call(
+                "sin",
+                vec![
+                    Expr::Ident("time".into(), Span::default()),
+                    Expr::Ident("speed".into(), Span::default()),
+                ],
+            )),
+            right: Box::new(Expr::BinOp {
+                op: BinOp::Mul,
+                left: Box::new(Expr::Ident("pi".into(), Span::default())),
+                right: Box::new(Expr::Ident("size".into(), Span::default())),
+                span: Span::default(),
+            }),
+            span: Span::default(),
+        };
+        let mut idents = Vec::new();
+        collect_arg_idents(&value, &mut idents);
+        assert_eq!(idents, vec!["speed".to_string(), "size".to_string()]);
+    }
+
+    #[test]
+    fn collect_arg_idents_skips_position() {
+        // fbm(p * freq)
+        let value = Expr::BinOp {
+            op: BinOp::Mul,
+            left: Box::new(Expr::Ident("p".into(), Span::default())),
+            right: Box::new(Expr::Ident("freq".into(), Span::default())),
+            span: Span::default(),
+        };
+        let mut idents = Vec::new();
+        collect_arg_idents(&value, &mut idents);
+        assert_eq!(idents, vec!["freq".to_string()]);
+    }
+
     #[test]
     fn unknown_stage_suggests_correction() {
         let stages = vec![stage("cicle")];
@@ -195,4 +595,56 @@ mod tests {
         assert!(err_msg.contains("type mismatch"), "should mention type mismatch: {err_msg}");
         assert!(err_msg.contains("'tint'"), "should mention previous stage 'tint': {err_msg}");
     }
+
+    fn pipe(stages: Vec<Stage>) -> Arg {
+        Arg { name: None, value: Expr::Pipeline(stages, Span::default()), span: Span::default() }
+    }
+
+    fn combinator(name: &str, a: Vec<Stage>, b: Vec<Stage>) -> Stage {
+        Stage { args: vec![pipe(a), pipe(b)], ..stage(name) }
+    }
+
+    #[test]
+    fn combinator_sub_pipelines_are_position_to_sdf() {
+        let union =
+            combinator("union", vec![stage("circle")], vec![stage("translate"), stage("box")]);
+        assert_eq!(validate_pipeline(&[union, stage("glow")]).unwrap(), ShaderState::Color);
+
+        let nested = combinator(
+            "morph",
+            vec![combinator("subtract", vec![stage("circle")], vec![stage("ring")])],
+            vec![stage("star")],
+        );
+        assert_eq!(validate_pipeline(&[nested]).unwrap(), ShaderState::Sdf);
+    }
+
+    #[test]
+    fn combinator_requires_both_pipelines() {
+        let missing = Stage { args: vec![pipe(vec![stage("circle")])], ..stage("union") };
+        let err = validate_pipeline(&[missing]).unwrap_err().to_string();
+        assert!(err.contains("expects a shape pipeline for 'b'"), "{err}");
+    }
+
+    #[test]
+    fn combinator_rejects_sub_pipeline_ending_in_color() {
+        let bad =
+            combinator("intersect", vec![stage("circle")], vec![stage("circle"), stage("glow")]);
+        let err = validate_pipeline(&[bad]).unwrap_err().to_string();
+        assert!(err.contains("argument 'b' must end in an Sdf"), "{err}");
+
+        let bad = combinator("union", vec![stage("glow")], vec![stage("circle")]);
+        let err = validate_pipeline(&[bad]).unwrap_err().to_string();
+        assert!(err.contains("type mismatch: 'glow'"), "{err}");
+    }
+
+    #[test]
+    fn only_combinators_take_pipelines() {
+        let circle = Stage { args: vec![pipe(vec![stage("circle")])], ..stage("circle") };
+        let err = validate_pipeline(&[circle]).unwrap_err().to_string();
+        assert!(err.contains("'circle' does not take a pipeline argument"), "{err}");
+
+        let mut k_pipe = combinator("smooth_union", vec![stage("circle")], vec![stage("box")]);
+        k_pipe.args.push(pipe(vec![stage("circle")]));
+        assert!(validate_pipeline(&[k_pipe]).is_err());
+    }
 }
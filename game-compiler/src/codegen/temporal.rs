@@ -1,5 +1,10 @@
 //! Temporal operator codegen — emits JavaScript runtime classes for
 //! delay (>>), smooth (<>), trigger (!!), and range (..) operators.
+//!
+//! The processors step on the renderer's playback clock rather than per
+//! frame: a paused clock holds them, a faster one runs them faster, and
+//! seeking backwards starts them over. Each renderer gets its own set, so
+//! elements with separate clocks don't feed one another's state.
 
 use crate::ast::{Duration, Expr, Param, TemporalOp};
 
@@ -20,9 +25,9 @@ fn duration_to_seconds(d: &Duration) -> f64 {
 /// Convert an Expr to a JS literal string.
 fn expr_to_js(e: &Expr) -> String {
     match e {
-        Expr::Number(v) => format!("{v}"),
-        Expr::Ident(name) => name.clone(),
-        Expr::DottedIdent { object, field } => format!("{object}.{field}"),
+        Expr::Number(v, _) => format!("{v}"),
+        Expr::Ident(name, _) => name.clone(),
+        Expr::DottedIdent { object, field, .. } => format!("{object}.{field}"),
         _ => "0".into(),
     }
 }
@@ -31,13 +36,24 @@ fn expr_to_js(e: &Expr) -> String {
 pub fn ring_buffer_class() -> &'static str {
     r#"class GameRingBuffer {
   constructor(durationSec, fps = 60) {
+    this._fps = fps;
     this._size = Math.max(1, Math.ceil(durationSec * fps));
     this._buffer = new Float32Array(this._size);
     this._head = 0;
+    this._time = null;
   }
-  push(value) {
-    this._buffer[this._head] = value;
-    this._head = (this._head + 1) % this._size;
+  // One slot per 1/fps of clock time
+  push(value, time) {
+    if (this._time === null || time < this._time) {
+      this._buffer.fill(0);
+      this._time = time;
+    }
+    const steps = Math.floor((time - this._time) * this._fps);
+    for (let i = 0; i < Math.min(steps, this._size); i++) {
+      this._buffer[this._head] = value;
+      this._head = (this._head + 1) % this._size;
+    }
+    this._time += steps / this._fps;
   }
   delayed() {
     return this._buffer[this._head];
@@ -52,11 +68,20 @@ pub fn ema_filter_class() -> &'static str {
   constructor(durationSec, fps = 60) {
     const samples = Math.max(1, durationSec * fps);
     this._alpha = 2.0 / (samples + 1.0);
+    this._fps = fps;
     this._value = null;
+    this._time = null;
   }
-  update(value) {
-    if (this._value === null) { this._value = value; return value; }
-    this._value += this._alpha * (value - this._value);
+  // The per-frame alpha compounds over the 1/fps frames the clock moved
+  update(value, time) {
+    if (this._value === null || time < this._time) {
+      this._value = value;
+      this._time = time;
+      return value;
+    }
+    const frames = (time - this._time) * this._fps;
+    this._time = time;
+    this._value += (1 - Math.pow(1 - this._alpha, frames)) * (value - this._value);
     return this._value;
   }
 }
@@ -68,14 +93,18 @@ pub fn edge_detector_class() -> &'static str {
     r#"class GameEdgeDetector {
   constructor(decaySec, fps = 60) {
     this._decayRate = 1.0 / Math.max(1, decaySec * fps);
+    this._fps = fps;
     this._prev = 0;
     this._envelope = 0;
+    this._time = null;
   }
-  update(value) {
+  update(value, time) {
+    const frames = this._time === null || time < this._time ? 0 : (time - this._time) * this._fps;
+    this._time = time;
     const delta = value - this._prev;
     this._prev = value;
     if (delta > 0.01) { this._envelope = 1.0; }
-    else { this._envelope = Math.max(0, this._envelope - this._decayRate); }
+    else { this._envelope = Math.max(0, this._envelope - this._decayRate * frames); }
     return this._envelope;
   }
 }
@@ -85,8 +114,9 @@ pub fn edge_detector_class() -> &'static str {
 /// Generate JS temporal processing initialization code for a set of params.
 ///
 /// Returns (init_code, update_code) where:
-/// - init_code: class definitions + per-param processor creation
-/// - update_code: per-frame processing calls
+/// - init_code: class definitions + a `temporalProcessors()` factory
+/// - update_code: per-frame processing calls, reading the clock's `time`
+///   and the processors created on first use for `renderer`
 pub fn generate_temporal_js(params: &[Param]) -> (String, String) {
     let mut needs_ring = false;
     let mut needs_ema = false;
@@ -118,6 +148,7 @@ pub fn generate_temporal_js(params: &[Param]) -> (String, String) {
     }
 
     // Per-param processor instances
+    let mut processors = String::new();
     let mut update = String::new();
 
     for param in params {
@@ -137,29 +168,29 @@ pub fn generate_temporal_js(params: &[Param]) -> (String, String) {
             match op {
                 TemporalOp::Delay(dur) => {
                     let secs = duration_to_seconds(dur);
-                    init.push_str(&format!(
-                        "const _delay_{pname}{suffix} = new GameRingBuffer({secs});\n"
+                    processors.push_str(&format!(
+                        "    _delay_{pname}{suffix}: new GameRingBuffer({secs}),\n"
                     ));
                     update.push_str(&format!(
-                        "_delay_{pname}{suffix}.push(_val_{pname}); _val_{pname} = _delay_{pname}{suffix}.delayed();\n"
+                        "temporal._delay_{pname}{suffix}.push(_val_{pname}, time); _val_{pname} = temporal._delay_{pname}{suffix}.delayed();\n"
                     ));
                 }
                 TemporalOp::Smooth(dur) => {
                     let secs = duration_to_seconds(dur);
-                    init.push_str(&format!(
-                        "const _smooth_{pname}{suffix} = new GameEMAFilter({secs});\n"
+                    processors.push_str(&format!(
+                        "    _smooth_{pname}{suffix}: new GameEMAFilter({secs}),\n"
                     ));
                     update.push_str(&format!(
-                        "_val_{pname} = _smooth_{pname}{suffix}.update(_val_{pname});\n"
+                        "_val_{pname} = temporal._smooth_{pname}{suffix}.update(_val_{pname}, time);\n"
                     ));
                 }
                 TemporalOp::Trigger(dur) => {
                     let secs = duration_to_seconds(dur);
-                    init.push_str(&format!(
-                        "const _trigger_{pname}{suffix} = new GameEdgeDetector({secs});\n"
+                    processors.push_str(&format!(
+                        "    _trigger_{pname}{suffix}: new GameEdgeDetector({secs}),\n"
                     ));
                     update.push_str(&format!(
-                        "_val_{pname} = _trigger_{pname}{suffix}.update(_val_{pname});\n"
+                        "_val_{pname} = temporal._trigger_{pname}{suffix}.update(_val_{pname}, time);\n"
                     ));
                 }
                 TemporalOp::Range(min_expr, max_expr) => {
@@ -173,6 +204,13 @@ pub fn generate_temporal_js(params: &[Param]) -> (String, String) {
         }
     }
 
+    if !processors.is_empty() {
+        init.push_str("function temporalProcessors() {\n  return {\n");
+        init.push_str(&processors);
+        init.push_str("  };\n}\n");
+        update.insert_str(0, "const temporal = renderer._temporal ??= temporalProcessors();\n");
+    }
+
     (init, update)
 }
 
@@ -184,9 +222,10 @@ mod tests {
     fn param_with_ops(name: &str, ops: Vec<TemporalOp>) -> Param {
         Param {
             name: name.into(),
-            value: Expr::Number(0.5),
+            value: Expr::Number(0.5, Span::default()),
             modulation: None,
             temporal_ops: ops,
+            span: Span::default(),
         }
     }
 
@@ -194,9 +233,10 @@ mod tests {
     fn no_temporal_ops_empty_output() {
         let params = vec![Param {
             name: "x".into(),
-            value: Expr::Number(1.0),
+            value: Expr::Number(1.0, Span::default()),
             modulation: None,
             temporal_ops: vec![],
+            span: Span::default(),
         }];
         let (init, update) = generate_temporal_js(&params);
         assert!(init.is_empty());
@@ -213,7 +253,22 @@ mod tests {
         assert!(init.contains("class GameRingBuffer"));
         assert!(init.contains("_delay_bass"));
         assert!(init.contains("0.2")); // 200ms = 0.2s
-        assert!(update.contains("_delay_bass"));
+        assert!(update.contains("temporal._delay_bass.push(_val_bass, time)"));
+    }
+
+    #[test]
+    fn processors_belong_to_the_renderer() {
+        // Elements share the module but each has its own clock
+        let params = vec![param_with_ops(
+            "energy",
+            vec![TemporalOp::Smooth(Duration::Millis(50.0))],
+        )];
+        let (init, update) = generate_temporal_js(&params);
+        assert!(init.contains("function temporalProcessors() {"));
+        assert!(init.contains("    _smooth_energy: new GameEMAFilter(0.05),"));
+        assert!(!init.contains("const _smooth_energy"));
+        let lazy = "const temporal = renderer._temporal ??= temporalProcessors();";
+        assert!(update.starts_with(lazy));
     }
 
     #[test]
@@ -225,7 +280,7 @@ mod tests {
         let (init, update) = generate_temporal_js(&params);
         assert!(init.contains("class GameEMAFilter"));
         assert!(init.contains("_smooth_energy"));
-        assert!(update.contains("_smooth_energy"));
+        assert!(update.contains("_smooth_energy.update(_val_energy, time)"));
     }
 
     #[test]
@@ -237,7 +292,7 @@ mod tests {
         let (init, update) = generate_temporal_js(&params);
         assert!(init.contains("class GameEdgeDetector"));
         assert!(init.contains("_trigger_beat"));
-        assert!(update.contains("_trigger_beat"));
+        assert!(update.contains("_trigger_beat.update(_val_beat, time)"));
     }
 
     #[test]
@@ -245,8 +300,8 @@ mod tests {
         let params = vec![param_with_ops(
             "vol",
             vec![TemporalOp::Range(
-                Expr::Number(0.0),
-                Expr::Number(1.0),
+                Expr::Number(0.0, Span::default()),
+                Expr::Number(1.0, Span::default()),
             )],
         )];
         let (init, update) = generate_temporal_js(&params);
@@ -262,7 +317,10 @@ mod tests {
             vec![
                 TemporalOp::Smooth(Duration::Millis(50.0)),
                 TemporalOp::Delay(Duration::Millis(200.0)),
-                TemporalOp::Range(Expr::Number(0.0), Expr::Number(1.0)),
+                TemporalOp::Range(
+                    Expr::Number(0.0, Span::default()),
+                    Expr::Number(1.0, Span::default()),
+                ),
             ],
         )];
         let (init, update) = generate_temporal_js(&params);
@@ -280,9 +338,10 @@ mod tests {
     fn any_param_detection() {
         let no_temporal = vec![Param {
             name: "x".into(),
-            value: Expr::Number(1.0),
+            value: Expr::Number(1.0, Span::default()),
             modulation: None,
             temporal_ops: vec![],
+            span: Span::default(),
         }];
         assert!(!any_param_uses_temporal(&no_temporal));
 
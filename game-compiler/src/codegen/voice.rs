@@ -30,9 +30,7 @@ pub fn generate_voice_js(voice: &VoiceBlock) -> String {
                 ));
             }
             "noise" => {
-                s.push_str(&format!(
-                    "    {{ const buf = ctx.createBuffer(1, ctx.sampleRate * 2, ctx.sampleRate);\n"
-                ));
+                s.push_str("    { const buf = ctx.createBuffer(1, ctx.sampleRate * 2, ctx.sampleRate);\n");
                 s.push_str("      const data = buf.getChannelData(0);\n");
                 s.push_str("      for (let i = 0; i < data.length; i++) data[i] = Math.random() * 2 - 1;\n");
                 s.push_str(&format!(
@@ -257,7 +255,7 @@ pub fn generate_voice_js(voice: &VoiceBlock) -> String {
 fn get_param_f64(params: &[Param], name: &str, default: f64) -> f64 {
     for p in params {
         if p.name == name {
-            if let Expr::Number(v) = &p.value {
+            if let Expr::Number(v, _) = &p.value {
                 return *v;
             }
         }
@@ -269,8 +267,8 @@ fn get_param_str<'a>(params: &'a [Param], name: &str, default: &'a str) -> &'a s
     for p in params {
         if p.name == name {
             match &p.value {
-                Expr::Ident(v) => return v.as_str(),
-                Expr::String(v) => return v.as_str(),
+                Expr::Ident(v, _) => return v.as_str(),
+                Expr::String(v, _) => return v.as_str(),
                 _ => {}
             }
         }
@@ -291,11 +289,14 @@ mod tests {
                 kind: "sine".into(),
                 params: vec![Param {
                     name: "freq".into(),
-                    value: Expr::Number(440.0),
+                    value: Expr::Number(440.0, Span::default()),
                     modulation: None,
                     temporal_ops: vec![],
+                    span: Span::default(),
                 }],
+                span: Span::default(),
             }],
+            span: Span::default(),
         };
         let js = generate_voice_js(&voice);
         assert!(js.contains("createOscillator"));
@@ -310,11 +311,14 @@ mod tests {
                 kind: "lowpass".into(),
                 params: vec![Param {
                     name: "cutoff".into(),
-                    value: Expr::Number(2000.0),
+                    value: Expr::Number(2000.0, Span::default()),
                     modulation: None,
                     temporal_ops: vec![],
+                    span: Span::default(),
                 }],
+                span: Span::default(),
             }],
+            span: Span::default(),
         };
         let js = generate_voice_js(&voice);
         assert!(js.contains("createBiquadFilter"));
@@ -325,10 +329,11 @@ mod tests {
     fn voice_chain_connects() {
         let voice = VoiceBlock {
             nodes: vec![
-                VoiceNode { name: "osc".into(), kind: "sine".into(), params: vec![] },
-                VoiceNode { name: "filt".into(), kind: "lowpass".into(), params: vec![] },
-                VoiceNode { name: "vol".into(), kind: "gain".into(), params: vec![] },
+                VoiceNode { name: "osc".into(), kind: "sine".into(), params: vec![], span: Span::default() },
+                VoiceNode { name: "filt".into(), kind: "lowpass".into(), params: vec![], span: Span::default() },
+                VoiceNode { name: "vol".into(), kind: "gain".into(), params: vec![], span: Span::default() },
             ],
+            span: Span::default(),
         };
         let js = generate_voice_js(&voice);
         assert!(js.contains("connect(destination)"));
@@ -343,11 +348,14 @@ mod tests {
                 kind: "reverb".into(),
                 params: vec![Param {
                     name: "room".into(),
-                    value: Expr::Number(0.4),
+                    value: Expr::Number(0.4, Span::default()),
                     modulation: None,
                     temporal_ops: vec![],
+                    span: Span::default(),
                 }],
+                span: Span::default(),
             }],
+            span: Span::default(),
         };
         let js = generate_voice_js(&voice);
         assert!(js.contains("createConvolver"));
@@ -361,7 +369,9 @@ mod tests {
                 name: "noise".into(),
                 kind: "noise".into(),
                 params: vec![],
+                span: Span::default(),
             }],
+            span: Span::default(),
         };
         let js = generate_voice_js(&voice);
         assert!(js.contains("createBufferSource"));
@@ -375,12 +385,38 @@ mod tests {
                 name: "env".into(),
                 kind: "envelope".into(),
                 params: vec![
-                    Param { name: "attack".into(), value: Expr::Number(0.02), modulation: None, temporal_ops: vec![] },
-                    Param { name: "decay".into(), value: Expr::Number(0.15), modulation: None, temporal_ops: vec![] },
-                    Param { name: "sustain".into(), value: Expr::Number(0.6), modulation: None, temporal_ops: vec![] },
-                    Param { name: "release".into(), value: Expr::Number(0.4), modulation: None, temporal_ops: vec![] },
+                    Param {
+                        name: "attack".into(),
+                        value: Expr::Number(0.02, Span::default()),
+                        modulation: None,
+                        temporal_ops: vec![],
+                        span: Span::default(),
+                    },
+                    Param {
+                        name: "decay".into(),
+                        value: Expr::Number(0.15, Span::default()),
+                        modulation: None,
+                        temporal_ops: vec![],
+                        span: Span::default(),
+                    },
+                    Param {
+                        name: "sustain".into(),
+                        value: Expr::Number(0.6, Span::default()),
+                        modulation: None,
+                        temporal_ops: vec![],
+                        span: Span::default(),
+                    },
+                    Param {
+                        name: "release".into(),
+                        value: Expr::Number(0.4, Span::default()),
+                        modulation: None,
+                        temporal_ops: vec![],
+                        span: Span::default(),
+                    },
                 ],
+                span: Span::default(),
             }],
+            span: Span::default(),
         };
         let js = generate_voice_js(&voice);
         assert!(js.contains("ADSR Envelope"), "should label ADSR");
@@ -402,7 +438,9 @@ mod tests {
                 name: "e".into(),
                 kind: "adsr".into(),
                 params: vec![],
+                span: Span::default(),
             }],
+            span: Span::default(),
         };
         let js = generate_voice_js(&voice);
         assert!(js.contains("ADSR Envelope: e"));
@@ -416,11 +454,31 @@ mod tests {
                 name: "vib".into(),
                 kind: "lfo".into(),
                 params: vec![
-                    Param { name: "rate".into(), value: Expr::Number(6.0), modulation: None, temporal_ops: vec![] },
-                    Param { name: "depth".into(), value: Expr::Number(30.0), modulation: None, temporal_ops: vec![] },
-                    Param { name: "wave".into(), value: Expr::Ident("triangle".into()), modulation: None, temporal_ops: vec![] },
+                    Param {
+                        name: "rate".into(),
+                        value: Expr::Number(6.0, Span::default()),
+                        modulation: None,
+                        temporal_ops: vec![],
+                        span: Span::default(),
+                    },
+                    Param {
+                        name: "depth".into(),
+                        value: Expr::Number(30.0, Span::default()),
+                        modulation: None,
+                        temporal_ops: vec![],
+                        span: Span::default(),
+                    },
+                    Param {
+                        name: "wave".into(),
+                        value: Expr::Ident("triangle".into(), Span::default()),
+                        modulation: None,
+                        temporal_ops: vec![],
+                        span: Span::default(),
+                    },
                 ],
+                span: Span::default(),
             }],
+            span: Span::default(),
         };
         let js = generate_voice_js(&voice);
         assert!(js.contains("LFO: vib"), "should label LFO");
@@ -439,10 +497,24 @@ mod tests {
                 name: "echo".into(),
                 kind: "delay".into(),
                 params: vec![
-                    Param { name: "time".into(), value: Expr::Number(0.3), modulation: None, temporal_ops: vec![] },
-                    Param { name: "feedback".into(), value: Expr::Number(0.5), modulation: None, temporal_ops: vec![] },
+                    Param {
+                        name: "time".into(),
+                        value: Expr::Number(0.3, Span::default()),
+                        modulation: None,
+                        temporal_ops: vec![],
+                        span: Span::default(),
+                    },
+                    Param {
+                        name: "feedback".into(),
+                        value: Expr::Number(0.5, Span::default()),
+                        modulation: None,
+                        temporal_ops: vec![],
+                        span: Span::default(),
+                    },
                 ],
+                span: Span::default(),
             }],
+            span: Span::default(),
         };
         let js = generate_voice_js(&voice);
         assert!(js.contains("Delay: echo"), "should label delay");
@@ -461,17 +533,36 @@ mod tests {
         let voice = VoiceBlock {
             nodes: vec![
                 VoiceNode { name: "osc".into(), kind: "sawtooth".into(), params: vec![
-                    Param { name: "freq".into(), value: Expr::Number(220.0), modulation: None, temporal_ops: vec![] },
-                ]},
-                VoiceNode { name: "env".into(), kind: "envelope".into(), params: vec![] },
+                    Param {
+                        name: "freq".into(),
+                        value: Expr::Number(220.0, Span::default()),
+                        modulation: None,
+                        temporal_ops: vec![],
+                        span: Span::default(),
+                    },
+                ], span: Span::default() },
+                VoiceNode { name: "env".into(), kind: "envelope".into(), params: vec![], span: Span::default() },
                 VoiceNode { name: "filt".into(), kind: "lowpass".into(), params: vec![
-                    Param { name: "cutoff".into(), value: Expr::Number(800.0), modulation: None, temporal_ops: vec![] },
-                ]},
+                    Param {
+                        name: "cutoff".into(),
+                        value: Expr::Number(800.0, Span::default()),
+                        modulation: None,
+                        temporal_ops: vec![],
+                        span: Span::default(),
+                    },
+                ], span: Span::default() },
                 VoiceNode { name: "echo".into(), kind: "delay".into(), params: vec![
-                    Param { name: "time".into(), value: Expr::Number(0.25), modulation: None, temporal_ops: vec![] },
-                ]},
-                VoiceNode { name: "vol".into(), kind: "gain".into(), params: vec![] },
+                    Param {
+                        name: "time".into(),
+                        value: Expr::Number(0.25, Span::default()),
+                        modulation: None,
+                        temporal_ops: vec![],
+                        span: Span::default(),
+                    },
+                ], span: Span::default() },
+                VoiceNode { name: "vol".into(), kind: "gain".into(), params: vec![], span: Span::default() },
             ],
+            span: Span::default(),
         };
         let js = generate_voice_js(&voice);
         assert!(js.contains("createOscillator"), "has oscillator");
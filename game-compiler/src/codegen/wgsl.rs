@@ -1,8 +1,8 @@
 //! WGSL shader generation from GAME AST.
 
 use crate::ast::*;
-use crate::codegen::memory;
-use crate::codegen::stages::get_arg;
+use crate::codegen::{blend, combine, gradient, memory};
+use crate::codegen::stages::{get_arg, get_rgb};
 use crate::codegen::UniformInfo;
 
 /// Generate a WGSL fragment shader for a cinematic.
@@ -12,25 +12,13 @@ pub fn generate_fragment(
 ) -> String {
     let mut s = String::with_capacity(8192);
 
-    // Uniform struct
-    s.push_str("struct Uniforms {\n");
-    s.push_str("    time: f32,\n");
-    s.push_str("    audio_bass: f32,\n");
-    s.push_str("    audio_mid: f32,\n");
-    s.push_str("    audio_treble: f32,\n");
-    s.push_str("    audio_energy: f32,\n");
-    s.push_str("    audio_beat: f32,\n");
-    s.push_str("    resolution: vec2<f32>,\n");
-    s.push_str("    mouse: vec2<f32>,\n");
-    for u in uniforms {
-        s.push_str(&format!("    p_{}: f32,\n", u.name));
-    }
-    s.push_str("};\n\n");
-    s.push_str("@group(0) @binding(0) var<uniform> u: Uniforms;\n\n");
+    emit_uniform_block(&mut s, uniforms);
 
-    // Memory bindings (Group 1) — only when any layer uses memory
-    if memory::any_layer_uses_memory(&cinematic.layers) {
-        memory::emit_wgsl_memory_bindings(&mut s);
+    // Memory bindings (Group 1) — one texture per memory layer
+    let memory_layers = memory::memory_layers(&cinematic.layers).count();
+    if memory_layers > 0 {
+        memory::emit_wgsl_memory_bindings(&mut s, memory_layers);
+        memory::emit_wgsl_output_struct(&mut s, memory_layers);
     }
 
     // Vertex output struct
@@ -41,40 +29,89 @@ pub fn generate_fragment(
 
     // Built-in helper functions
     emit_wgsl_builtins(&mut s, cinematic);
+    memory::emit_wgsl_memory_helpers(&mut s, cinematic);
 
     // Fragment entry
     s.push_str("@fragment\n");
-    s.push_str("fn fs_main(input: VertexOutput) -> @location(0) vec4<f32> {\n");
-    s.push_str("    let uv = input.uv * 2.0 - 1.0;\n");
-    s.push_str("    let aspect = u.resolution.x / u.resolution.y;\n");
-    s.push_str("    let time = fract(u.time / 120.0) * 120.0;\n\n");
-
-    // Uniform param aliases
-    for u in uniforms {
-        s.push_str(&format!("    let {} = u.p_{};\n", u.name, u.name));
+    if memory_layers > 0 {
+        s.push_str("fn fs_main(input: VertexOutput) -> FragmentOutput {\n");
+    } else {
+        s.push_str("fn fs_main(input: VertexOutput) -> @location(0) vec4<f32> {\n");
     }
-    if !uniforms.is_empty() {
-        s.push('\n');
+    emit_prelude(&mut s, uniforms);
+    for i in 0..memory_layers {
+        s.push_str(&format!("    var mem_out_{i} = vec4<f32>(0.0);\n"));
     }
 
-    let multi_layer = cinematic.layers.len() > 1;
+    let multi_layer = blend::composites(cinematic);
     if multi_layer {
         s.push_str("    var final_color = vec4<f32>(0.0, 0.0, 0.0, 1.0);\n\n");
     }
 
+    let mut memory_index = 0;
     for (i, layer) in cinematic.layers.iter().enumerate() {
-        emit_wgsl_layer(&mut s, layer, i, multi_layer);
+        emit_wgsl_layer(&mut s, layer, i, multi_layer, memory_index);
+        memory_index += usize::from(layer.memory.is_some());
     }
 
-    if multi_layer {
+    if memory_layers > 0 {
+        let outputs: String = (0..memory_layers).map(|i| format!(", mem_out_{i}")).collect();
+        blend::emit_alpha(&mut s, "final_color", "    ");
+        s.push_str(&format!("    return FragmentOutput(final_color{outputs});\n"));
+    } else if multi_layer {
+        blend::emit_alpha(&mut s, "final_color", "    ");
         s.push_str("    return final_color;\n");
+    } else if !has_pipeline_layer(cinematic) {
+        // Params-only cinematics (define libraries, modulation demos) draw nothing
+        s.push_str("    return vec4<f32>(0.0, 0.0, 0.0, 0.0);\n");
     }
     s.push_str("}\n");
     s
 }
 
+/// Emit the `Uniforms` struct and its group-0 binding.
+///
+/// Shared by every fragment shader that reads the renderer's uniform buffer,
+/// so the layout must stay in sync with `GameRenderer.render()`.
+pub(crate) fn emit_uniform_block(s: &mut String, uniforms: &[UniformInfo]) {
+    s.push_str("struct Uniforms {\n");
+    s.push_str("    time: f32,\n");
+    s.push_str("    audio_bass: f32,\n");
+    s.push_str("    audio_mid: f32,\n");
+    s.push_str("    audio_treble: f32,\n");
+    s.push_str("    audio_energy: f32,\n");
+    s.push_str("    audio_beat: f32,\n");
+    s.push_str("    resolution: vec2<f32>,\n");
+    s.push_str("    mouse: vec2<f32>,\n");
+    for u in uniforms {
+        s.push_str(&format!("    p_{}: f32,\n", u.name));
+    }
+    s.push_str("};\n\n");
+    s.push_str("@group(0) @binding(0) var<uniform> u: Uniforms;\n\n");
+}
+
+/// Emit the `fs_main` prelude: centered uv, aspect, wrapped time and
+/// `let` aliases for every user uniform.
+pub(crate) fn emit_prelude(s: &mut String, uniforms: &[UniformInfo]) {
+    s.push_str("    let uv = input.uv * 2.0 - 1.0;\n");
+    s.push_str("    let aspect = u.resolution.x / u.resolution.y;\n");
+    s.push_str("    let time = fract(u.time / 120.0) * 120.0;\n\n");
+
+    // Uniform param aliases
+    for u in uniforms {
+        s.push_str(&format!("    let {} = u.p_{};\n", u.name, u.name));
+    }
+    if !uniforms.is_empty() {
+        s.push('\n');
+    }
+}
+
 // ── Helper function detection ───────────────────────────────────
 
+pub(crate) fn has_pipeline_layer(cinematic: &Cinematic) -> bool {
+    cinematic.layers.iter().any(|l| matches!(l.body, LayerBody::Pipeline(_)))
+}
+
 fn needs_noise_helpers(cinematic: &Cinematic) -> bool {
     cinematic.layers.iter().any(|l| {
         has_stage(l, "fbm")
@@ -230,7 +267,7 @@ fn emit_wgsl_palette_helper(s: &mut String) {
 
 // ── Layer emission ──────────────────────────────────────────────
 
-fn emit_wgsl_layer(s: &mut String, layer: &Layer, idx: usize, multi: bool) {
+fn emit_wgsl_layer(s: &mut String, layer: &Layer, idx: usize, multi: bool, memory_index: usize) {
     let body = match &layer.body {
         LayerBody::Pipeline(stages) => stages,
         _ => return,
@@ -248,24 +285,23 @@ fn emit_wgsl_layer(s: &mut String, layer: &Layer, idx: usize, multi: bool) {
         emit_wgsl_stage(s, stage, indent);
     }
 
-    // Memory: mix with previous frame if this layer has memory
-    if let Some(decay) = layer.memory {
-        memory::emit_wgsl_memory_mix(s, decay, indent);
+    // Memory: mix with this layer's previous frame
+    if layer.memory.is_some() {
+        memory::emit_wgsl_memory_mix(s, layer, memory_index, indent);
     }
 
     if multi {
-        // Screen blend: prevents blowout from additive compositing
-        s.push_str(&format!("{indent}let lc = color_result.rgb;\n"));
-        s.push_str(&format!("{indent}final_color = vec4<f32>(final_color.rgb + lc - final_color.rgb * lc, 1.0);\n"));
+        blend::emit_wgsl_blend(s, layer, indent);
         s.push_str("    }\n\n");
     } else {
+        blend::emit_alpha(s, "color_result", indent);
         s.push_str(&format!("{indent}return color_result;\n"));
     }
 }
 
-// ── Stage emission: ALL 38 builtins ─────────────────────────────
+// ── Stage emission: ALL 43 builtins ─────────────────────────────
 
-fn emit_wgsl_stage(s: &mut String, stage: &Stage, indent: &str) {
+pub(crate) fn emit_wgsl_stage(s: &mut String, stage: &Stage, indent: &str) {
     let args = &stage.args;
     match stage.name.as_str() {
         // ── SDF Generators: Position -> Sdf ─────────────────
@@ -333,6 +369,12 @@ fn emit_wgsl_stage(s: &mut String, stage: &Stage, indent: &str) {
             s.push_str(&format!("{indent}var sdf_result = sin(cw_r - time * 2.0) * {amp} * exp(-length(p) * {width});\n"));
         }
 
+        // ── SDF combinators: nested pipelines -> Sdf ────────
+
+        "union" | "subtract" | "intersect" | "smooth_union" | "morph" => {
+            combine::emit_wgsl(s, stage, indent);
+        }
+
         // ── Sdf -> Color bridges ────────────────────────────
 
         "glow" => {
@@ -341,12 +383,10 @@ fn emit_wgsl_stage(s: &mut String, stage: &Stage, indent: &str) {
             s.push_str(&format!("{indent}var color_result = vec4<f32>(vec3<f32>(glow_result), 1.0);\n"));
         }
         "shade" => {
-            let r = get_arg(args, "r", 0, "shade");
-            let g = get_arg(args, "g", 1, "shade");
-            let b = get_arg(args, "b", 2, "shade");
+            let rgb = get_rgb(args, "shade");
             // Anti-aliased edge using smoothstep with screen-space awareness
             s.push_str(&format!("{indent}let aa = 0.005;\n"));
-            s.push_str(&format!("{indent}var color_result = vec4<f32>(vec3<f32>({r}, {g}, {b}) * smoothstep(aa, -aa, sdf_result), 1.0);\n"));
+            s.push_str(&format!("{indent}var color_result = vec4<f32>({rgb} * smoothstep(aa, -aa, sdf_result), 1.0);\n"));
         }
         "emissive" => {
             let intensity = get_arg(args, "intensity", 0, "emissive");
@@ -373,10 +413,8 @@ fn emit_wgsl_stage(s: &mut String, stage: &Stage, indent: &str) {
         // ── Color processors: Color -> Color ────────────────
 
         "tint" => {
-            let r = get_arg(args, "r", 0, "tint");
-            let g = get_arg(args, "g", 1, "tint");
-            let b = get_arg(args, "b", 2, "tint");
-            s.push_str(&format!("{indent}color_result = vec4<f32>(color_result.rgb * vec3<f32>({r}, {g}, {b}), 1.0);\n"));
+            let rgb = get_rgb(args, "tint");
+            s.push_str(&format!("{indent}color_result = vec4<f32>(color_result.rgb * {rgb}, 1.0);\n"));
         }
         "bloom" => {
             let thresh = get_arg(args, "threshold", 0, "bloom");
@@ -514,18 +552,18 @@ fn emit_wgsl_stage(s: &mut String, stage: &Stage, indent: &str) {
         // ── Position -> Color generators ────────────────────
 
         "gradient" => {
-            // Default gradient: vertical dark-to-light
-            s.push_str(&format!("{indent}var color_result = vec4<f32>(vec3<f32>(uv.y * 0.5 + 0.5), 1.0);\n"));
+            gradient::emit_wgsl(s, stage, indent);
         }
         "spectrum" => {
             let bass = get_arg(args, "bass", 0, "spectrum");
             let mid = get_arg(args, "mid", 1, "spectrum");
             let treble = get_arg(args, "treble", 2, "spectrum");
+            s.push_str(&format!("{indent}var color_result: vec4<f32>;\n"));
             s.push_str(&format!("{indent}{{ let sp_d = length(p);\n"));
             s.push_str(&format!("{indent}let sp_bass = smoothstep(0.35, 0.15, sp_d) * {bass};\n"));
             s.push_str(&format!("{indent}let sp_mid = smoothstep(0.55, 0.35, sp_d) * smoothstep(0.15, 0.35, sp_d) * {mid};\n"));
             s.push_str(&format!("{indent}let sp_treble = smoothstep(0.75, 0.55, sp_d) * smoothstep(0.35, 0.55, sp_d) * {treble};\n"));
-            s.push_str(&format!("{indent}var color_result = vec4<f32>(\n"));
+            s.push_str(&format!("{indent}color_result = vec4<f32>(\n"));
             s.push_str(&format!("{indent}    sp_bass * vec3<f32>(1.0, 0.2, 0.1) +\n"));
             s.push_str(&format!("{indent}    sp_mid * vec3<f32>(0.1, 1.0, 0.3) +\n"));
             s.push_str(&format!("{indent}    sp_treble * vec3<f32>(0.2, 0.3, 1.0),\n"));
@@ -541,7 +579,12 @@ fn emit_wgsl_stage(s: &mut String, stage: &Stage, indent: &str) {
 
 fn has_stage(layer: &Layer, name: &str) -> bool {
     match &layer.body {
-        LayerBody::Pipeline(stages) => stages.iter().any(|s| s.name == name),
+        LayerBody::Pipeline(stages) => {
+            // Combinator sub-pipelines need their helpers too
+            let mut found = false;
+            walk_stages(stages, &mut |s| found |= s.name == name);
+            found
+        }
         _ => false,
     }
 }
@@ -580,25 +623,58 @@ mod tests {
                 opts: vec![],
                 memory: None,
                 cast: None,
+                blend: None,
                 body: LayerBody::Pipeline(stages),
+                span: Span::default(),
             }],
             arcs: vec![],
             resonates: vec![],
             listen: None, voice: None, score: None, gravity: None,
             lenses: vec![], react: None, defines: vec![],
+            span: Span::default(),
         }
     }
 
     #[test]
     fn basic_wgsl_output() {
         let cin = make_cinematic(vec![
-            Stage { name: "circle".into(), args: vec![Arg { name: None, value: Expr::Number(0.2) }] },
-            Stage { name: "glow".into(), args: vec![Arg { name: None, value: Expr::Number(1.5) }] },
+            Stage {
+                name: "circle".into(),
+                args: vec![Arg {
+                    name: None,
+                    value: Expr::Number(0.2, Span::default()),
+                    span: Span::default(),
+                }],
+                span: Span::default(),
+                expanded_from: vec![],
+            },
+            Stage {
+                name: "glow".into(),
+                args: vec![Arg {
+                    name: None,
+                    value: Expr::Number(1.5, Span::default()),
+                    span: Span::default(),
+                }],
+                span: Span::default(),
+                expanded_from: vec![],
+            },
             Stage { name: "tint".into(), args: vec![
-                Arg { name: None, value: Expr::Number(0.831) },
-                Arg { name: None, value: Expr::Number(0.686) },
-                Arg { name: None, value: Expr::Number(0.216) },
-            ] },
+                Arg {
+                    name: None,
+                    value: Expr::Number(0.831, Span::default()),
+                    span: Span::default(),
+                },
+                Arg {
+                    name: None,
+                    value: Expr::Number(0.686, Span::default()),
+                    span: Span::default(),
+                },
+                Arg {
+                    name: None,
+                    value: Expr::Number(0.216, Span::default()),
+                    span: Span::default(),
+                },
+            ], span: Span::default(), expanded_from: vec![] },
         ]);
         let output = generate_fragment(&cin, &[]);
         assert!(output.contains("fn fs_main"));
@@ -618,8 +694,26 @@ mod tests {
     #[test]
     fn wgsl_palette_emits_iq_helper_and_lookup() {
         let cin = make_cinematic(vec![
-            Stage { name: "fbm".into(), args: vec![Arg { name: None, value: Expr::Number(2.0) }] },
-            Stage { name: "palette".into(), args: vec![Arg { name: None, value: Expr::Ident("fire".into()) }] },
+            Stage {
+                name: "fbm".into(),
+                args: vec![Arg {
+                    name: None,
+                    value: Expr::Number(2.0, Span::default()),
+                    span: Span::default(),
+                }],
+                span: Span::default(),
+                expanded_from: vec![],
+            },
+            Stage {
+                name: "palette".into(),
+                args: vec![Arg {
+                    name: None,
+                    value: Expr::Ident("fire".into(), Span::default()),
+                    span: Span::default(),
+                }],
+                span: Span::default(),
+                expanded_from: vec![],
+            },
         ]);
         let output = generate_fragment(&cin, &[]);
         // Helper function emitted
@@ -633,8 +727,26 @@ mod tests {
     #[test]
     fn wgsl_palette_unknown_defaults_to_rainbow() {
         let cin = make_cinematic(vec![
-            Stage { name: "simplex".into(), args: vec![Arg { name: None, value: Expr::Number(1.0) }] },
-            Stage { name: "palette".into(), args: vec![Arg { name: None, value: Expr::Ident("unknown_name".into()) }] },
+            Stage {
+                name: "simplex".into(),
+                args: vec![Arg {
+                    name: None,
+                    value: Expr::Number(1.0, Span::default()),
+                    span: Span::default(),
+                }],
+                span: Span::default(),
+                expanded_from: vec![],
+            },
+            Stage {
+                name: "palette".into(),
+                args: vec![Arg {
+                    name: None,
+                    value: Expr::Ident("unknown_name".into(), Span::default()),
+                    span: Span::default(),
+                }],
+                span: Span::default(),
+                expanded_from: vec![],
+            },
         ]);
         let output = generate_fragment(&cin, &[]);
         // Should use rainbow default coefficients (d = 0.00,0.33,0.67)
@@ -0,0 +1,332 @@
+//! Per-pixel evaluation of stage argument expressions.
+//!
+//! Mirrors `expr::compile_wgsl` + `stages::lower_arg`: constants and color
+//! names resolve to literals, `time`/`p`/`uv`/`aspect` read the pixel's
+//! locals, signals read their uniform slots and any other identifier is a
+//! user uniform.
+
+use crate::ast::{BinOp, Duration, Expr};
+use crate::codegen::{color, expr};
+
+use super::helpers::{fract, mix, smoothstep, step, wgsl_mod};
+use super::Uniforms;
+
+/// A WGSL value: a scalar or a `vec2`–`vec4`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct Value {
+    pub len: usize,
+    pub v: [f32; 4],
+}
+
+impl Value {
+    pub fn scalar(x: f32) -> Self {
+        Self { len: 1, v: [x, 0.0, 0.0, 0.0] }
+    }
+
+    fn vector(components: &[f32]) -> Self {
+        let mut v = [0.0; 4];
+        let len = components.len().min(4);
+        v[..len].copy_from_slice(&components[..len]);
+        Self { len, v }
+    }
+
+    /// The value as a float; vectors collapse to their first component.
+    pub fn x(self) -> f32 {
+        self.v[0]
+    }
+
+    /// Component `i`, broadcasting scalars.
+    fn at(self, i: usize) -> f32 {
+        if self.len == 1 { self.v[0] } else { self.v[i] }
+    }
+
+    fn map(self, f: impl Fn(f32) -> f32) -> Self {
+        let mut out = self;
+        for c in &mut out.v[..self.len] {
+            *c = f(*c);
+        }
+        out
+    }
+
+    fn zip(self, other: Self, f: impl Fn(f32, f32) -> f32) -> Self {
+        let len = self.len.max(other.len);
+        let mut v = [0.0; 4];
+        for (i, c) in v.iter_mut().enumerate().take(len) {
+            *c = f(self.at(i), other.at(i));
+        }
+        Self { len, v }
+    }
+
+    fn zip3(self, b: Self, c: Self, f: impl Fn(f32, f32, f32) -> f32) -> Self {
+        let len = self.len.max(b.len).max(c.len);
+        let mut v = [0.0; 4];
+        for (i, out) in v.iter_mut().enumerate().take(len) {
+            *out = f(self.at(i), b.at(i), c.at(i));
+        }
+        Self { len, v }
+    }
+
+    fn dot(self, other: Self) -> f32 {
+        (0..self.len.max(other.len)).map(|i| self.at(i) * other.at(i)).sum()
+    }
+
+    fn length(self) -> f32 {
+        self.dot(self).sqrt()
+    }
+}
+
+/// Pixel-local values visible to expressions.
+pub(crate) struct Env<'a> {
+    pub uniforms: &'a Uniforms,
+    pub time: f32,
+    pub uv: [f32; 2],
+    pub p: [f32; 2],
+    pub aspect: f32,
+}
+
+/// Evaluate an expression for one pixel.
+pub(crate) fn eval(e: &Expr, env: &Env) -> Value {
+    match e {
+        Expr::Number(v, _) => Value::scalar(*v as f32),
+        Expr::String(_, _) | Expr::Pipeline(_, _) => Value::scalar(0.0),
+        Expr::Ident(name, _) => ident(name, env),
+        Expr::DottedIdent { object, field, .. } => {
+            Value::scalar(env.uniforms.signal(object, field))
+        }
+        Expr::Array(elems, _) => {
+            let components: Vec<f32> = elems.iter().map(|e| eval(e, env).x()).collect();
+            Value::vector(&components)
+        }
+        Expr::Paren(inner, _) => eval(inner, env),
+        Expr::Neg(inner, _) => eval(inner, env).map(|x| -x),
+        Expr::Not(inner, _) => eval(inner, env).map(|x| bool_value(x == 0.0)),
+        Expr::BinOp { op, left, right, .. } => {
+            let l = eval(left, env);
+            let r = eval(right, env);
+            match op {
+                BinOp::Add => l.zip(r, |a, b| a + b),
+                BinOp::Sub => l.zip(r, |a, b| a - b),
+                BinOp::Mul => l.zip(r, |a, b| a * b),
+                BinOp::Div => l.zip(r, |a, b| a / b),
+                BinOp::Mod => l.zip(r, |a, b| a % b),
+                BinOp::Pow => l.zip(r, f32::powf),
+                BinOp::Gt => l.zip(r, |a, b| bool_value(a > b)),
+                BinOp::Lt => l.zip(r, |a, b| bool_value(a < b)),
+                BinOp::Ge => l.zip(r, |a, b| bool_value(a >= b)),
+                BinOp::Le => l.zip(r, |a, b| bool_value(a <= b)),
+                BinOp::Eq => l.zip(r, |a, b| bool_value(a == b)),
+                BinOp::Ne => l.zip(r, |a, b| bool_value(a != b)),
+                BinOp::And => l.zip(r, |a, b| bool_value(a != 0.0 && b != 0.0)),
+                BinOp::Or => l.zip(r, |a, b| bool_value(a != 0.0 || b != 0.0)),
+            }
+        }
+        Expr::Call { name, args, .. } => {
+            let values: Vec<Value> = args.iter().map(|a| eval(&a.value, env)).collect();
+            call(name, &values)
+        }
+        Expr::Duration(dur, _) => Value::scalar(match dur {
+            Duration::Seconds(s) => *s as f32,
+            Duration::Millis(ms) => *ms as f32 / 1000.0,
+            Duration::Bars(b) => *b as f32 * 2.0,
+        }),
+        Expr::Ternary { condition, if_true, if_false, .. } => {
+            if eval(condition, env).x() != 0.0 {
+                eval(if_true, env)
+            } else {
+                eval(if_false, env)
+            }
+        }
+    }
+}
+
+fn bool_value(b: bool) -> f32 {
+    if b { 1.0 } else { 0.0 }
+}
+
+fn ident(name: &str, env: &Env) -> Value {
+    if let Some(c) = expr::resolve_constant(name) {
+        return Value::scalar(c.parse().unwrap_or(0.0));
+    }
+    if let Some(rgb) = color(name) {
+        return Value::vector(&rgb);
+    }
+    match name {
+        "time" => Value::scalar(env.time),
+        "p" => Value::vector(&env.p),
+        "uv" => Value::vector(&env.uv),
+        "aspect" => Value::scalar(env.aspect),
+        _ => Value::scalar(env.uniforms.param(name)),
+    }
+}
+
+/// A named color's channels.
+pub(crate) fn color(name: &str) -> Option<[f32; 3]> {
+    color::named(name).map(|rgb| rgb.map(|c| c as f32))
+}
+
+/// A color constructor's channels, converted in `f64` like the folded
+/// literal the shader gets.
+fn construct(convert: fn(f64, f64, f64) -> [f64; 3], a: Value, b: Value, c: Value) -> Value {
+    let [a, b, c] = [a, b, c].map(|v| f64::from(v.x()));
+    Value::vector(&convert(a, b, c).map(|x| x as f32))
+}
+
+/// Builtin math functions. Unknown functions evaluate to zero (the shader
+/// would fail to compile; `game check` reports them).
+fn call(name: &str, args: &[Value]) -> Value {
+    let zero = Value::scalar(0.0);
+    let arg = |i: usize| args.get(i).copied().unwrap_or(zero);
+    let (a, b, c) = (arg(0), arg(1), arg(2));
+    match name {
+        "abs" => a.map(f32::abs),
+        "sin" => a.map(f32::sin),
+        "cos" => a.map(f32::cos),
+        "tan" => a.map(f32::tan),
+        "sqrt" => a.map(f32::sqrt),
+        "floor" => a.map(f32::floor),
+        "ceil" => a.map(f32::ceil),
+        "fract" => a.map(fract),
+        "exp" => a.map(f32::exp),
+        "log" => a.map(f32::ln),
+        "sign" => a.map(|x| if x == 0.0 { 0.0 } else { x.signum() }),
+        "round" => a.map(f32::round_ties_even),
+        "length" => Value::scalar(a.length()),
+        "normalize" => {
+            let len = a.length();
+            a.map(|x| x / len)
+        }
+        "mod" if args.len() == 2 => a.zip(b, wgsl_mod),
+        "mod" => a.map(|x| wgsl_mod(x, 1.0)),
+        "mix" => a.zip3(b, c, mix),
+        "clamp" => a.zip3(b, c, |x, lo, hi| x.max(lo).min(hi)),
+        "smoothstep" => a.zip3(b, c, smoothstep),
+        "step" => a.zip(b, step),
+        "min" => a.zip(b, f32::min),
+        "max" => a.zip(b, f32::max),
+        "pow" => a.zip(b, f32::powf),
+        "atan2" => a.zip(b, f32::atan2),
+        "distance" => Value::scalar(a.zip(b, |x, y| x - y).length()),
+        "dot" => Value::scalar(a.dot(b)),
+        "cross" => Value::vector(&[
+            a.v[1] * b.v[2] - a.v[2] * b.v[1],
+            a.v[2] * b.v[0] - a.v[0] * b.v[2],
+            a.v[0] * b.v[1] - a.v[1] * b.v[0],
+        ]),
+        "rgb" => Value::vector(&[a.x(), b.x(), c.x()]),
+        "hsl" => construct(color::hsl_to_rgb, a, b, c),
+        "oklch" => construct(color::oklch_to_rgb, a, b, c),
+        "reflect" => {
+            let d = 2.0 * b.dot(a);
+            a.zip(b, |i, n| i - d * n)
+        }
+        _ => zero,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ast::{Arg, Span};
+
+    fn env(uniforms: &Uniforms) -> Env<'_> {
+        Env { uniforms, time: 2.0, uv: [0.5, -0.5], p: [0.75, -0.5], aspect: 1.5 }
+    }
+
+    fn call_expr(name: &str, args: Vec<Expr>) -> Expr {
+        Expr::Call {
+            name: name.into(),
+            args: args
+                .into_iter()
+                .map(|value| Arg { name: None, value, span: Span::default() })
+                .collect(),
+            span: Span::default(),
+        }
+    }
+
+    #[test]
+    fn arithmetic_and_locals() {
+        let uniforms = Uniforms::default();
+        // sin(time * pi / 4) + aspect
+        let e = Expr::BinOp {
+            op: BinOp::Add,
+            left: Box::new(call_expr(
+                "sin",
+                vec![Expr::BinOp {
+                    op: BinOp::Div,
+                    left: Box::new(Expr::BinOp {
+                        op: BinOp::Mul,
+                        left: Box::new(Expr::Ident("time".into(), Span::default())),
+                        right: Box::new(Expr::Ident("pi".into(), Span::default())),
+                        span: Span::default(),
+                    }),
+                    right: Box::new(Expr::Number(4.0, Span::default())),
+                    span: Span::default(),
+                }],
+            )),
+            right: Box::new(Expr::Ident("aspect".into(), Span::default())),
+            span: Span::default(),
+        };
+        assert!((eval(&e, &env(&uniforms)).x() - 2.5).abs() < 1e-5);
+        let len = call_expr("length", vec![Expr::Ident("uv".into(), Span::default())]);
+        assert!((eval(&len, &env(&uniforms)).x() - 0.5f32.hypot(0.5)).abs() < 1e-6);
+    }
+
+    #[test]
+    fn signals_params_and_colors() {
+        let mut uniforms = Uniforms { audio_bass: 0.25, ..Default::default() };
+        uniforms.set_param("speed", 3.0);
+        uniforms.set_data("progress", 0.5);
+        let env = env(&uniforms);
+        let signal = |object: &str, field: &str| {
+            eval(&Expr::DottedIdent {
+                object: object.into(),
+                field: field.into(),
+                span: Span::default(),
+            }, &env).x()
+        };
+        assert_eq!(signal("audio", "bass"), 0.25);
+        assert_eq!(signal("data", "progress"), 0.5);
+        assert_eq!(eval(&Expr::Ident("speed".into(), Span::default()), &env).x(), 3.0);
+        assert_eq!(eval(&Expr::Ident("missing".into(), Span::default()), &env).x(), 0.0);
+        let gold = eval(&Expr::Ident("gold".into(), Span::default()), &env);
+        assert_eq!(gold.len, 3);
+        assert!((gold.v[1] - 0.686).abs() < 1e-6);
+    }
+
+    #[test]
+    fn mod_and_ternary_follow_wgsl() {
+        let uniforms = Uniforms::default();
+        let env = env(&uniforms);
+        let m = call_expr(
+            "mod",
+            vec![Expr::Number(-1.0, Span::default()), Expr::Number(3.0, Span::default())],
+        );
+        assert_eq!(eval(&m, &env).x(), -1.0);
+        let t = Expr::Ternary {
+            condition: Box::new(Expr::BinOp {
+                op: BinOp::Gt,
+                left: Box::new(Expr::Ident("time".into(), Span::default())),
+                right: Box::new(Expr::Number(1.0, Span::default())),
+                span: Span::default(),
+            }),
+            if_true: Box::new(Expr::Number(7.0, Span::default())),
+            if_false: Box::new(Expr::Number(9.0, Span::default())),
+            span: Span::default(),
+        };
+        assert_eq!(eval(&t, &env).x(), 7.0);
+    }
+
+    #[test]
+    fn comparison_logic_and_mod_operators() {
+        let uniforms = Uniforms::default();
+        let env = env(&uniforms);
+        let eval_src = |src: &str| {
+            let tokens = crate::lexer::lex(src).unwrap();
+            eval(&crate::parser::Parser::new(tokens).parse_expr().unwrap(), &env).x()
+        };
+        assert_eq!(eval_src("time >= 2.0 && time <= 2.0"), 1.0);
+        assert_eq!(eval_src("time == 1.0 || !aspect"), 0.0);
+        assert_eq!(eval_src("time != 1.0"), 1.0);
+        assert_eq!(eval_src("-time % 1.5"), -0.5);
+    }
+}
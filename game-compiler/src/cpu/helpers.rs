@@ -0,0 +1,198 @@
+//! Scalar ports of the WGSL helper functions and builtins.
+//!
+//! Everything here runs in `f32` and follows the WGSL definitions exactly
+//! (`fract(x) = x - floor(x)`, truncating `%`, Hermite `smoothstep`) so the
+//! hash-based noise matches the GPU output bit for bit where it can.
+//! Literals are copied verbatim from the shaders, hence the clippy allows.
+
+#![allow(clippy::approx_constant, clippy::excessive_precision)]
+
+pub(crate) type Vec2 = [f32; 2];
+
+pub(crate) fn fract(x: f32) -> f32 {
+    x - x.floor()
+}
+
+/// WGSL float `%`: truncated remainder, sign follows the dividend.
+pub(crate) fn wgsl_mod(x: f32, y: f32) -> f32 {
+    x - y * (x / y).trunc()
+}
+
+pub(crate) fn mix(a: f32, b: f32, t: f32) -> f32 {
+    a * (1.0 - t) + b * t
+}
+
+pub(crate) fn step(edge: f32, x: f32) -> f32 {
+    if x >= edge { 1.0 } else { 0.0 }
+}
+
+pub(crate) fn smoothstep(e0: f32, e1: f32, x: f32) -> f32 {
+    let t = ((x - e0) / (e1 - e0)).clamp(0.0, 1.0);
+    t * t * (3.0 - 2.0 * t)
+}
+
+pub(crate) fn length(p: Vec2) -> f32 {
+    (p[0] * p[0] + p[1] * p[1]).sqrt()
+}
+
+fn dot(a: Vec2, b: Vec2) -> f32 {
+    a[0] * b[0] + a[1] * b[1]
+}
+
+/// Rotate a color around the grey axis by `turns` (`hue_shift`).
+pub(crate) fn hue_shift(c: [f32; 3], turns: f32) -> [f32; 3] {
+    let k = 0.57735;
+    let (s, cs) = (turns * 6.28318).sin_cos();
+    let cross = [k * (c[2] - c[1]), k * (c[0] - c[2]), k * (c[1] - c[0])];
+    let d = k * (c[0] + c[1] + c[2]) * k * (1.0 - cs);
+    std::array::from_fn(|i| c[i] * cs + cross[i] * s + d)
+}
+
+/// `fract(sin(x) * 43758.5453)`, the one-line hash used by grain and glitch.
+pub(crate) fn sin_hash(x: f32) -> f32 {
+    fract(x.sin() * 43758.5453)
+}
+
+/// Inverse-square glow with a soft core (`apply_glow`).
+pub(crate) fn apply_glow(d: f32, intensity: f32) -> f32 {
+    let edge = 0.005;
+    let core = smoothstep(edge, -edge, d);
+    let m = d.max(0.0);
+    let halo = intensity / (1.0 + m * m * intensity * intensity * 16.0);
+    core + halo
+}
+
+pub(crate) fn hash2(p: Vec2) -> f32 {
+    let mut p3 = [fract(p[0] * 0.1031), fract(p[1] * 0.1031), fract(p[0] * 0.1031)];
+    let d = p3[0] * (p3[1] + 33.33) + p3[1] * (p3[2] + 33.33) + p3[2] * (p3[0] + 33.33);
+    for c in &mut p3 {
+        *c += d;
+    }
+    fract((p3[0] + p3[1]) * p3[2])
+}
+
+/// Value noise in `[-1, 1]`.
+pub(crate) fn noise2(p: Vec2) -> f32 {
+    let i = [p[0].floor(), p[1].floor()];
+    let f = [fract(p[0]), fract(p[1])];
+    let u = [f[0] * f[0] * (3.0 - 2.0 * f[0]), f[1] * f[1] * (3.0 - 2.0 * f[1])];
+    let a = hash2(i);
+    let b = hash2([i[0] + 1.0, i[1]]);
+    let c = hash2([i[0], i[1] + 1.0]);
+    let d = hash2([i[0] + 1.0, i[1] + 1.0]);
+    mix(mix(a, b, u[0]), mix(c, d, u[0]), u[1]) * 2.0 - 1.0
+}
+
+/// Fractal noise with per-octave domain rotation, normalized by amplitude.
+pub(crate) fn fbm2(p: Vec2, octaves: i32, persistence: f32, lacunarity: f32) -> f32 {
+    let mut value = 0.0;
+    let mut amplitude = 1.0;
+    let mut frequency = 1.0;
+    let mut max_val = 0.0;
+    let mut q = p;
+    for _ in 0..octaves {
+        value += noise2([q[0] * frequency, q[1] * frequency]) * amplitude;
+        max_val += amplitude;
+        amplitude *= persistence;
+        frequency *= lacunarity;
+        q = [q[0] * 0.8 - q[1] * 0.6, q[0] * 0.6 + q[1] * 0.8];
+    }
+    value / max_val
+}
+
+/// Distance to the nearest cell point.
+pub(crate) fn voronoi2(p: Vec2, scale: f32) -> f32 {
+    let sp = [p[0] * scale, p[1] * scale];
+    let i_part = [sp[0].floor(), sp[1].floor()];
+    let f_part = [fract(sp[0]), fract(sp[1])];
+    let mut min_dist: f32 = 1.0;
+    for y in -1..=1 {
+        for x in -1..=1 {
+            let neighbor = [x as f32, y as f32];
+            let point_val = hash2([i_part[0] + neighbor[0], i_part[1] + neighbor[1]]);
+            let point = [
+                neighbor[0] + point_val - f_part[0],
+                neighbor[1] + fract(point_val * 17.0) - f_part[1],
+            ];
+            min_dist = min_dist.min(dot(point, point));
+        }
+    }
+    min_dist.sqrt()
+}
+
+fn hash2v(p: Vec2) -> Vec2 {
+    let q = [dot(p, [127.1, 311.7]), dot(p, [269.5, 183.3])];
+    [-1.0 + 2.0 * sin_hash(q[0]), -1.0 + 2.0 * sin_hash(q[1])]
+}
+
+pub(crate) fn simplex2(p: Vec2) -> f32 {
+    const K1: f32 = 0.366025404;
+    const K2: f32 = 0.211324865;
+    let s = (p[0] + p[1]) * K1;
+    let i_part = [(p[0] + s).floor(), (p[1] + s).floor()];
+    let t = (i_part[0] + i_part[1]) * K2;
+    let a = [p[0] - i_part[0] + t, p[1] - i_part[1] + t];
+    let o = [step(a[1], a[0]), step(a[0], a[1])];
+    let b = [a[0] - o[0] + K2, a[1] - o[1] + K2];
+    let c = [a[0] - 1.0 + 2.0 * K2, a[1] - 1.0 + 2.0 * K2];
+    let corners = [
+        (a, i_part),
+        (b, [i_part[0] + o[0], i_part[1] + o[1]]),
+        (c, [i_part[0] + 1.0, i_part[1] + 1.0]),
+    ];
+    corners
+        .iter()
+        .map(|&(v, cell)| {
+            let h = (0.5 - dot(v, v)).max(0.0);
+            h * h * h * h * dot(v, hash2v(cell))
+        })
+        .sum::<f32>()
+        * 70.0
+}
+
+/// Inigo Quilez cosine palette.
+pub(crate) fn iq_palette(t: f32, coeffs: &[[f32; 3]; 4]) -> [f32; 3] {
+    let [a, b, c, d] = coeffs;
+    std::array::from_fn(|i| a[i] + b[i] * (6.28318 * (c[i] * t + d[i])).cos())
+}
+
+/// `palette(name)` coefficients; unknown names fall back to rainbow.
+pub(crate) fn palette_coeffs(name: &str) -> [[f32; 3]; 4] {
+    const HALF: [f32; 3] = [0.5, 0.5, 0.5];
+    const ONE: [f32; 3] = [1.0, 1.0, 1.0];
+    match name {
+        "fire" | "plasma" => [HALF, HALF, ONE, [0.00, 0.10, 0.20]],
+        "ice" => [HALF, HALF, ONE, [0.30, 0.20, 0.20]],
+        "ocean" => [HALF, HALF, [1.0, 0.7, 0.4], [0.00, 0.15, 0.20]],
+        "forest" => [HALF, HALF, [1.0, 1.0, 0.5], [0.80, 0.90, 0.30]],
+        "neon" => [HALF, HALF, [2.0, 1.0, 0.0], [0.50, 0.20, 0.25]],
+        "sunset" => [[0.8, 0.5, 0.4], [0.2, 0.4, 0.2], [2.0, 1.0, 1.0], [0.00, 0.25, 0.25]],
+        _ => [HALF, HALF, ONE, [0.00, 0.33, 0.67]],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wgsl_mod_truncates() {
+        assert_eq!(wgsl_mod(-1.0, 3.0), -1.0);
+        assert_eq!(wgsl_mod(4.0, 3.0), 1.0);
+    }
+
+    #[test]
+    fn glow_core_is_bright_inside() {
+        assert!(apply_glow(-0.1, 1.5) > 2.0);
+        assert!(apply_glow(1.0, 1.5) < 0.1);
+    }
+
+    #[test]
+    fn noise_stays_in_range() {
+        for i in 0..200 {
+            let p = [i as f32 * 0.37 - 20.0, i as f32 * 0.11 + 3.0];
+            assert!((-1.0..=1.0).contains(&noise2(p)));
+            assert!((0.0..=1.5).contains(&voronoi2(p, 3.0)));
+        }
+    }
+}
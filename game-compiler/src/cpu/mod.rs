@@ -0,0 +1,671 @@
+//! CPU reference renderer.
+//!
+//! Interprets a cinematic's layer pipelines per pixel, mirroring the WGSL
+//! the compiler emits: the `fs_main` prelude, every builtin stage, layer
+//! blend modes and opacity, `memory:` feedback and the lens post pass. No
+//! GPU is needed, so previews and visual tests run anywhere.
+//!
+//! Output is tightly packed, premultiplied RGBA8, top row first — the same
+//! layout as a canvas readback or [`crate::snapshot::save_png`] input.
+//! Alpha is the shader's (the brightest channel), which only a transparent
+//! canvas shows.
+
+mod eval;
+mod helpers;
+mod stages;
+mod timeline;
+
+use std::collections::HashMap;
+
+use crate::ast::{Cinematic, LayerBody};
+use crate::codegen::blend::{self, BlendMode};
+use crate::codegen::memory::{self, FEEDBACK_OPTIONS, MAX_MEMORY_LAYERS};
+use crate::codegen::{self, analysis, lens, signals, UniformInfo};
+use crate::error::CompileError;
+
+use eval::Env;
+use helpers::{fract, hue_shift, mix};
+use stages::{CompiledStage, Pixel};
+
+pub use timeline::{Keyframes, Timeline};
+
+/// Uniform values for one frame, mirroring the shader's `Uniforms` block.
+#[derive(Debug, Clone, Default)]
+pub struct Uniforms {
+    /// Seconds since start; wrapped to 120s like the shader prelude.
+    pub time: f32,
+    pub audio_bass: f32,
+    pub audio_mid: f32,
+    pub audio_treble: f32,
+    pub audio_energy: f32,
+    pub audio_beat: f32,
+    /// Pointer position in the same aspect-corrected space as `p`. The other
+    /// `mouse.*` fields are `mouse_<field>` params.
+    pub mouse: [f32; 2],
+    /// User uniforms by name (`p_<name>` in the shader).
+    pub params: HashMap<String, f32>,
+}
+
+impl Uniforms {
+    /// Set a user uniform.
+    pub fn set_param(&mut self, name: &str, value: f32) {
+        self.params.insert(name.to_string(), value);
+    }
+
+    /// Set the uniform backing a `data.<field>` reference.
+    pub fn set_data(&mut self, field: &str, value: f32) {
+        self.set_param(&signals::data_uniform_name(field), value);
+    }
+
+    /// A user uniform's value, `0.0` when unset.
+    pub fn param(&self, name: &str) -> f32 {
+        self.params.get(name).copied().unwrap_or(0.0)
+    }
+
+    /// Resolve `object.field` the way `signals::resolve_wgsl` does.
+    fn signal(&self, object: &str, field: &str) -> f32 {
+        match (object, field) {
+            ("audio", "bass") => self.audio_bass,
+            ("audio", "mid") => self.audio_mid,
+            ("audio", "treble") => self.audio_treble,
+            ("audio", "energy") => self.audio_energy,
+            ("audio", "beat") => self.audio_beat,
+            ("mouse", "x") => self.mouse[0],
+            ("mouse", "y") => self.mouse[1],
+            ("mouse", field) => self.param(&format!("mouse_{field}")),
+            ("data", field) => self.param(&signals::data_uniform_name(field)),
+            _ => 0.0,
+        }
+    }
+}
+
+struct CompiledLayer {
+    /// `None` for params-only layers, which draw nothing.
+    stages: Option<Vec<CompiledStage>>,
+    memory: Option<MemoryLayer>,
+    blend: BlendMode,
+    /// Uniform holding the layer's `opacity`, if set.
+    opacity: Option<String>,
+}
+
+/// A `memory:` layer's decay, state buffer and feedback options.
+struct MemoryLayer {
+    decay: f32,
+    /// Index into `CpuRenderer::memory`, as in `mem_<slot>` in the shader.
+    slot: usize,
+    /// Uniform behind each of [`FEEDBACK_OPTIONS`], if the layer sets it.
+    feedback: Vec<Option<String>>,
+}
+
+impl MemoryLayer {
+    /// The `feedback_*` option at `index` in [`FEEDBACK_OPTIONS`].
+    fn option(&self, env: &Env, index: usize) -> f32 {
+        match &self.feedback[index] {
+            Some(uniform) => env.uniforms.param(uniform),
+            None => FEEDBACK_OPTIONS[index].1 as f32,
+        }
+    }
+}
+
+/// Renders frames of one cinematic at a fixed resolution.
+///
+/// Keeps each `memory:` layer's previous output, so successive
+/// [`render`](Self::render) calls accumulate trails like the live runtime.
+pub struct CpuRenderer {
+    layers: Vec<CompiledLayer>,
+    /// Lens post stages; `Some` when the cinematic has a post pass.
+    post: Option<Vec<CompiledStage>>,
+    /// Layers go through `final_color`, as in `blend::composites`.
+    composites: bool,
+    uniforms: Vec<UniformInfo>,
+    width: u32,
+    height: u32,
+    /// One RGBA8 state buffer per memory layer, top row first.
+    memory: Vec<Vec<u8>>,
+}
+
+impl CpuRenderer {
+    /// Prepare a cinematic for rendering: expands defines and validates
+    /// the pipelines exactly as `codegen::generate` would.
+    pub fn new(cinematic: &Cinematic, width: u32, height: u32) -> Result<Self, CompileError> {
+        let mut cinematic = cinematic.clone();
+        analysis::expand_defines(&mut cinematic)?;
+        codegen::validate(&cinematic)?;
+
+        let mut slots = 0;
+        let layers = cinematic
+            .layers
+            .iter()
+            .map(|layer| CompiledLayer {
+                stages: match &layer.body {
+                    LayerBody::Pipeline(stages) => {
+                        Some(stages.iter().map(CompiledStage::new).collect())
+                    }
+                    LayerBody::Params(_) => None,
+                },
+                memory: layer.memory.map(|decay| {
+                    slots += 1;
+                    MemoryLayer {
+                        decay: decay as f32,
+                        slot: slots - 1,
+                        feedback: FEEDBACK_OPTIONS
+                            .iter()
+                            .map(|(option, _)| {
+                                let name = option.trim_start_matches("feedback_");
+                                memory::feedback_uniform(layer, name)
+                            })
+                            .collect(),
+                    }
+                }),
+                blend: blend::layer_blend(layer),
+                opacity: blend::opacity_uniform(layer),
+            })
+            .collect();
+        let post = lens::has_post_pass(&cinematic).then(|| {
+            lens::post_stages(&cinematic).into_iter().map(CompiledStage::new).collect()
+        });
+
+        Ok(Self {
+            layers,
+            post,
+            composites: blend::composites(&cinematic),
+            uniforms: codegen::extract_uniforms(&cinematic),
+            width,
+            height,
+            memory: vec![vec![0; width as usize * height as usize * 4]; slots],
+        })
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Uniforms at their declared defaults, time zero and silent audio.
+    pub fn default_uniforms(&self) -> Uniforms {
+        let mut uniforms = Uniforms::default();
+        for u in &self.uniforms {
+            uniforms.set_param(&u.name, u.default as f32);
+        }
+        uniforms
+    }
+
+    /// Forget the previous frames, as if the canvas was just created.
+    pub fn reset(&mut self) {
+        for state in &mut self.memory {
+            state.fill(0);
+        }
+    }
+
+    /// Render one frame and keep each memory layer's output for the next.
+    pub fn render(&mut self, uniforms: &Uniforms) -> Vec<u8> {
+        let (w, h) = (self.width as usize, self.height as usize);
+        let time = fract(uniforms.time / 120.0) * 120.0;
+        let aspect = w as f32 / h as f32;
+        let mut frame = vec![0u8; w * h * 4];
+        let mut next = vec![vec![0u8; w * h * 4]; self.memory.len()];
+
+        // The layers run once per pixel, into the scene or straight to the frame
+        let mut shade = |i: usize| {
+            let env = self.env(uniforms, i, time, aspect);
+            let mut mem_out = [[0.0; 4]; MAX_MEMORY_LAYERS];
+            let color = self.shade_layers(env, &mut mem_out);
+            for (state, out) in next.iter_mut().zip(mem_out) {
+                state[i * 4..i * 4 + 4].copy_from_slice(&to_rgba8(out));
+            }
+            to_rgba8(color)
+        };
+        match &self.post {
+            Some(post) => {
+                let mut scene = vec![0u8; w * h * 4];
+                for (i, px) in scene.chunks_exact_mut(4).enumerate() {
+                    px.copy_from_slice(&shade(i));
+                }
+                for (i, px) in frame.chunks_exact_mut(4).enumerate() {
+                    let env = self.env(uniforms, i, time, aspect);
+                    px.copy_from_slice(&to_rgba8(self.shade_post(post, &scene, env)));
+                }
+            }
+            None => {
+                for (i, px) in frame.chunks_exact_mut(4).enumerate() {
+                    px.copy_from_slice(&shade(i));
+                }
+            }
+        }
+
+        self.memory = next;
+        frame
+    }
+
+    /// The prelude for pixel `i`: centered uv (y up) and `p` in aspect units.
+    fn env<'a>(&self, uniforms: &'a Uniforms, i: usize, time: f32, aspect: f32) -> Env<'a> {
+        let w = self.width as usize;
+        let (x, y) = (i % w, i / w);
+        let uv = [
+            (x as f32 + 0.5) / self.width as f32 * 2.0 - 1.0,
+            (1.0 - (y as f32 + 0.5) / self.height as f32) * 2.0 - 1.0,
+        ];
+        Env { uniforms, time, uv, p: [uv[0] * aspect, uv[1]], aspect }
+    }
+
+    /// Run every layer for one pixel and composite, writing each memory
+    /// layer's new state to `mem_out`.
+    fn shade_layers(&self, mut env: Env, mem_out: &mut [[f32; 4]]) -> [f32; 4] {
+        let mut final_color = [0.0, 0.0, 0.0, 0.0];
+        for layer in &self.layers {
+            let Some(stages) = &layer.stages else { continue };
+            env.p = [env.uv[0] * env.aspect, env.uv[1]];
+            let mut px = Pixel { sdf: 0.0, color: [0.0, 0.0, 0.0, 1.0] };
+            for stage in stages {
+                stages::apply(stage, &mut env, &mut px);
+            }
+
+            if let Some(memory) = &layer.memory {
+                let prev = self.feedback(memory, &env);
+                for (c, p) in px.color.iter_mut().zip(prev) {
+                    *c = mix(*c, p, memory.decay);
+                }
+                mem_out[memory.slot] = px.color;
+            }
+
+            if !self.composites {
+                return with_alpha(px.color);
+            }
+            let opacity = layer.opacity.as_ref().map_or(1.0, |u| env.uniforms.param(u));
+            let weight = match layer.blend {
+                BlendMode::Normal => px.color[3] * opacity,
+                _ => opacity,
+            };
+            for (f, &lc) in final_color.iter_mut().zip(&px.color[..3]) {
+                *f = mix(*f, layer.blend.apply(*f, lc), weight).clamp(0.0, 1.0);
+            }
+        }
+        with_alpha(final_color)
+    }
+
+    /// A memory layer's previous output at this pixel, moved, blurred and
+    /// hue-shifted like `memory::emit_wgsl_memory_mix`.
+    fn feedback(&self, memory: &MemoryLayer, env: &Env) -> [f32; 4] {
+        let [zoom, angle, x, y, blur, hue] =
+            std::array::from_fn(|k| memory.option(env, k));
+        let q = [(env.uv[0] * env.aspect - x) / zoom, (env.uv[1] - y) / zoom];
+        let (s, c) = angle.sin_cos();
+        let r = [c * q[0] + s * q[1], c * q[1] - s * q[0]];
+        let (u, v) = (r[0] / env.aspect * 0.5 + 0.5, 1.0 - (r[1] * 0.5 + 0.5));
+
+        let state = &self.memory[memory.slot];
+        let mut prev = self.sample(state, u, v);
+        if memory.feedback[4].is_some() {
+            let (dx, dy) = (blur / self.width as f32, blur / self.height as f32);
+            let taps = [(dx, 0.0), (-dx, 0.0), (0.0, dy), (0.0, -dy)];
+            for (du, dv) in taps {
+                let tap = self.sample(state, u + du, v + dv);
+                for (p, t) in prev.iter_mut().zip(tap) {
+                    *p += t;
+                }
+            }
+            prev = prev.map(|p| p * 0.2);
+        }
+        if memory.feedback[5].is_some() {
+            let rgb = hue_shift([prev[0], prev[1], prev[2]], hue);
+            prev[..3].copy_from_slice(&rgb);
+        }
+        prev
+    }
+
+    /// Lens pass: sample the scene through zoom/offset, apply exposure and
+    /// the `post` stages.
+    fn shade_post(&self, post: &[CompiledStage], scene: &[u8], mut env: Env) -> [f32; 4] {
+        let zoom = env.uniforms.param("lens_zoom");
+        let lens_uv = [
+            (env.uv[0] - env.uniforms.param("lens_offset_x")) / zoom * 0.5 + 0.5,
+            (env.uv[1] - env.uniforms.param("lens_offset_y")) / zoom * 0.5 + 0.5,
+        ];
+        let mut color = self.sample(scene, lens_uv[0], 1.0 - lens_uv[1]);
+        let exposure = env.uniforms.param("lens_exposure");
+        for c in &mut color[..3] {
+            *c *= exposure;
+        }
+
+        let mut px = Pixel { sdf: 0.0, color };
+        for stage in post {
+            stages::apply(stage, &mut env, &mut px);
+        }
+        with_alpha(px.color)
+    }
+
+    /// Bilinear, clamp-to-edge texture sample; `v` runs top-down.
+    fn sample(&self, texels: &[u8], u: f32, v: f32) -> [f32; 4] {
+        let (w, h) = (self.width as i64, self.height as i64);
+        let x = u * w as f32 - 0.5;
+        let y = v * h as f32 - 0.5;
+        let (x0, y0) = (x.floor(), y.floor());
+        let (fx, fy) = (x - x0, y - y0);
+        let texel = |tx: i64, ty: i64, c: usize| {
+            let tx = tx.clamp(0, w - 1);
+            let ty = ty.clamp(0, h - 1);
+            texels[((ty * w + tx) * 4) as usize + c] as f32 / 255.0
+        };
+        let (x0, y0) = (x0 as i64, y0 as i64);
+        std::array::from_fn(|c| {
+            let top = mix(texel(x0, y0, c), texel(x0 + 1, y0, c), fx);
+            let bottom = mix(texel(x0, y0 + 1, c), texel(x0 + 1, y0 + 1, c), fx);
+            mix(top, bottom, fy)
+        })
+    }
+}
+
+/// Render a single frame at `time` with default uniforms.
+pub fn render_frame(
+    cinematic: &Cinematic,
+    width: u32,
+    height: u32,
+    time: f32,
+) -> Result<Vec<u8>, CompileError> {
+    let mut renderer = CpuRenderer::new(cinematic, width, height)?;
+    let mut uniforms = renderer.default_uniforms();
+    uniforms.time = time;
+    Ok(renderer.render(&uniforms))
+}
+
+/// Replace alpha with the brightest channel, like `blend::emit_alpha`.
+fn with_alpha(mut color: [f32; 4]) -> [f32; 4] {
+    color[3] = blend::coverage(&color[..3]);
+    color
+}
+
+/// Unorm8 conversion: clamp, scale, round to nearest.
+fn to_rgba8(color: [f32; 4]) -> [u8; 4] {
+    color.map(|c| (c.clamp(0.0, 1.0) * 255.0).round() as u8)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builtins::{ShaderState, BUILTINS};
+
+    fn cinematic(source: &str) -> Cinematic {
+        let mut program = crate::compile_to_ast(source).unwrap();
+        crate::resolver::resolve_stdlib_imports(&mut program).unwrap();
+        program.cinematics.remove(0)
+    }
+
+    fn renderer(source: &str, w: u32, h: u32) -> CpuRenderer {
+        CpuRenderer::new(&cinematic(source), w, h).unwrap()
+    }
+
+    fn pixel(frame: &[u8], w: u32, x: u32, y: u32) -> [u8; 4] {
+        let i = ((y * w + x) * 4) as usize;
+        [frame[i], frame[i + 1], frame[i + 2], frame[i + 3]]
+    }
+
+    #[test]
+    fn circle_glow_is_bright_at_center_dark_at_corner() {
+        let src = r#"cinematic "c" { layer main { circle(0.3) | glow(2.0) } }"#;
+        let frame = render_frame(&cinematic(src), 33, 33, 0.0).unwrap();
+        assert_eq!(frame.len(), 33 * 33 * 4);
+        assert_eq!(pixel(&frame, 33, 16, 16), [255, 255, 255, 255]);
+        assert!(pixel(&frame, 33, 0, 0)[0] < 40);
+    }
+
+    #[test]
+    fn uv_y_points_up() {
+        let src = r#"cinematic "c" { layer main { gradient() } }"#;
+        let frame = render_frame(&cinematic(src), 4, 4, 0.0).unwrap();
+        assert!(pixel(&frame, 4, 0, 0)[0] > 200);
+        assert!(pixel(&frame, 4, 0, 3)[0] < 50);
+    }
+
+    #[test]
+    fn gradient_stops_follow_the_mode_and_angle() {
+        // Horizontal red -> green -> blue: left edge red, center green, right edge blue
+        let src = r#"cinematic "c" { layer main { gradient([red, green], blue, "x") } }"#;
+        let frame = render_frame(&cinematic(src), 8, 8, 0.0).unwrap();
+        let left = pixel(&frame, 8, 0, 4);
+        assert!(left[0] > 200 && left[1] < 50 && left[2] == 0, "{left:?}");
+        assert!(pixel(&frame, 8, 4, 4)[1] > 200);
+        assert!(pixel(&frame, 8, 7, 4)[2] > 200);
+
+        // A quarter turn makes the linear ramp run left to right as well
+        let src = r#"cinematic "c" { layer main { gradient(angle: pi / 2.0) } }"#;
+        let frame = render_frame(&cinematic(src), 8, 8, 0.0).unwrap();
+        assert!(pixel(&frame, 8, 0, 4)[0] < 50);
+        assert!(pixel(&frame, 8, 7, 4)[0] > 200);
+
+        let src = r##"cinematic "c" { layer main { gradient("#ff8800", black, "radial") } }"##;
+        let frame = render_frame(&cinematic(src), 8, 8, 0.0).unwrap();
+        let center = pixel(&frame, 8, 4, 4);
+        assert!(center[0] > 200 && center[1] > 100 && center[2] == 0, "{center:?}");
+    }
+
+    #[test]
+    fn aspect_keeps_circles_round() {
+        let src = r#"cinematic "c" { layer main { circle(0.5) | shade(1.0, 0.0, 0.0) } }"#;
+        let frame = render_frame(&cinematic(src), 64, 32, 0.0).unwrap();
+        // 0.5 units of radius = 8px vertically and horizontally on a 64x32 canvas
+        assert_eq!(pixel(&frame, 64, 32 + 6, 16)[0], 255);
+        assert_eq!(pixel(&frame, 64, 32 + 10, 16)[0], 0);
+        assert_eq!(pixel(&frame, 64, 32, 16 + 6)[0], 255);
+        assert_eq!(pixel(&frame, 64, 32, 16 + 10)[0], 0);
+    }
+
+    #[test]
+    fn layers_screen_blend() {
+        let src = r#"cinematic "c" {
+            layer a { circle(0.5) | shade(1.0, 0.0, 0.0) }
+            layer b { circle(0.5) | shade(0.0, 0.0, 0.6) }
+        }"#;
+        let frame = render_frame(&cinematic(src), 9, 9, 0.0).unwrap();
+        assert_eq!(pixel(&frame, 9, 4, 4), [255, 0, 153, 255]);
+        // Alpha follows the brightest channel, so the black corner is clear
+        assert_eq!(pixel(&frame, 9, 0, 0), [0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn layer_blend_modes_and_opacity() {
+        let src = r#"cinematic "c" {
+            layer a { circle(0.5) | shade(0.5, 0.5, 0.5) }
+            layer b (blend: multiply) { circle(0.5) | shade(1.0, 0.5, 0.0) }
+            layer c (blend: add, opacity: 0.5) { circle(0.5) | shade(0.0, 0.0, 1.0) }
+        }"#;
+        let frame = render_frame(&cinematic(src), 9, 9, 0.0).unwrap();
+        assert_eq!(pixel(&frame, 9, 4, 4), [128, 64, 128, 128]);
+
+        let mut r = renderer(src, 9, 9);
+        let mut u = r.default_uniforms();
+        u.set_param("c_opacity", 0.0);
+        assert_eq!(pixel(&r.render(&u), 9, 4, 4), [128, 64, 0, 128]);
+    }
+
+    #[test]
+    fn composites_stay_in_range() {
+        // Additive layers overshoot white; screening a bright layer over them must not invert
+        let src = r#"cinematic "c" {
+            layer a (blend: add) { circle(0.5) | shade(1.0, 1.0, 1.0) }
+            layer b (blend: add) { circle(0.5) | shade(1.0, 1.0, 1.0) }
+            layer c { circle(0.5) | shade(2.0, 2.0, 2.0) }
+        }"#;
+        let frame = render_frame(&cinematic(src), 9, 9, 0.0).unwrap();
+        assert_eq!(pixel(&frame, 9, 4, 4), [255, 255, 255, 255]);
+    }
+
+    #[test]
+    fn color_names_tint_all_channels() {
+        let src = r#"cinematic "c" { layer main { circle(0.5) | shade(1.0, 1.0, 1.0) | tint(gold) } }"#;
+        let frame = render_frame(&cinematic(src), 9, 9, 0.0).unwrap();
+        assert_eq!(pixel(&frame, 9, 4, 4), [212, 175, 55, 212]);
+    }
+
+    #[test]
+    fn hex_literals_and_live_constructors_tint() {
+        let src = r#"cinematic "c" { layer main { circle(0.5) | shade(1.0, 1.0, 1.0) | tint(#ff8800) } }"#;
+        let frame = render_frame(&cinematic(src), 9, 9, 0.0).unwrap();
+        assert_eq!(pixel(&frame, 9, 4, 4), [255, 136, 0, 255]);
+
+        let src = r#"cinematic "c" {
+            layer main { circle(0.5) | shade(1.0, 1.0, 1.0) | tint(hsl(time * 60.0, 1.0, 0.5)) }
+        }"#;
+        let frame = render_frame(&cinematic(src), 9, 9, 2.0).unwrap();
+        assert_eq!(pixel(&frame, 9, 4, 4), [0, 255, 0, 255]);
+    }
+
+    #[test]
+    fn memory_accumulates_previous_frames() {
+        let src = r#"cinematic "c" { layer main memory: 0.25 { circle(0.5) | shade(1.0, 1.0, 1.0) } }"#;
+        let mut r = renderer(src, 9, 9);
+        let uniforms = r.default_uniforms();
+        assert_eq!(pixel(&r.render(&uniforms), 9, 4, 4)[0], 191);
+        assert_eq!(pixel(&r.render(&uniforms), 9, 4, 4)[0], 239);
+        r.reset();
+        assert_eq!(pixel(&r.render(&uniforms), 9, 4, 4)[0], 191);
+    }
+
+    #[test]
+    fn memory_layers_keep_their_own_state() {
+        let src = r#"cinematic "c" {
+            layer a memory: 0.5 { circle(0.5) | shade(1.0, 0.0, 0.0) }
+            layer b memory: 0.5 {
+                fn: circle(0.5) | shade(0.0, 0.0, data.on)
+                feedback_x: 0.444444
+            }
+        }"#;
+        let mut r = renderer(src, 9, 9);
+        let mut uniforms = r.default_uniforms();
+        uniforms.set_data("on", 1.0);
+        assert_eq!(pixel(&r.render(&uniforms), 9, 4, 4), [128, 0, 128, 128]);
+        // Without new input, each layer replays only its own history; b's
+        // shifted right by two pixels (2/9 of the width is 0.444 units)
+        uniforms.set_data("on", 0.0);
+        let frame = r.render(&uniforms);
+        assert_eq!(pixel(&frame, 9, 4, 4), [192, 0, 64, 192]);
+        assert_eq!(pixel(&frame, 9, 8, 4)[2], 64);
+        assert_eq!(pixel(&frame, 9, 0, 4)[2], 0);
+    }
+
+    #[test]
+    fn feedback_hue_rotates_the_previous_frame() {
+        let src = r#"cinematic "c" {
+            layer a memory: 1.0 { fn: circle(0.5) | shade(1.0, 0.0, 0.0)
+                feedback_hue: 0.33333 }
+        }"#;
+        let mut r = renderer(src, 9, 9);
+        let mut uniforms = r.default_uniforms();
+        uniforms.set_param("a_feedback_hue", 0.0);
+        r.memory[0].fill(255);
+        r.memory[0].iter_mut().skip(1).step_by(4).for_each(|g| *g = 0);
+        r.memory[0].iter_mut().skip(2).step_by(4).for_each(|b| *b = 0);
+        assert_eq!(pixel(&r.render(&uniforms), 9, 4, 4)[..3], [255, 0, 0]);
+        uniforms.set_param("a_feedback_hue", 1.0 / 3.0);
+        let [red, green, blue, _] = pixel(&r.render(&uniforms), 9, 4, 4);
+        assert!(red < 2 && green > 253 && blue < 2, "{red} {green} {blue}");
+    }
+
+    #[test]
+    fn uniforms_drive_args() {
+        let src = r#"cinematic "c" {
+            layer main { circle(data.size + audio.bass) | shade(1.0, 1.0, 1.0) }
+        }"#;
+        let mut r = renderer(src, 21, 21);
+        let mut uniforms = r.default_uniforms();
+        assert_eq!(pixel(&r.render(&uniforms), 21, 13, 10)[0], 0);
+        uniforms.set_data("size", 0.2);
+        uniforms.audio_bass = 0.1;
+        assert_eq!(pixel(&r.render(&uniforms), 21, 13, 10)[0], 255);
+    }
+
+    #[test]
+    fn pointer_fields_read_header_and_params() {
+        let src = r#"cinematic "c" {
+            layer main { translate(mouse.x, 0.0) | circle(0.1 + mouse.down * 0.6) | shade(1.0, 1.0, 1.0) }
+        }"#;
+        let mut r = renderer(src, 21, 21);
+        let mut uniforms = r.default_uniforms();
+        assert_eq!(pixel(&r.render(&uniforms), 21, 16, 10)[0], 0);
+        uniforms.set_param("mouse_down", 1.0);
+        assert_eq!(pixel(&r.render(&uniforms), 21, 16, 10)[0], 255);
+        uniforms.set_param("mouse_down", 0.0);
+        uniforms.mouse = [0.6, 0.0];
+        assert_eq!(pixel(&r.render(&uniforms), 21, 16, 10)[0], 255);
+    }
+
+    #[test]
+    fn param_layers_provide_defaults() {
+        let src = r#"cinematic "c" {
+            layer config { size: 0.4 }
+            layer main { circle(size) | shade(1.0, 1.0, 1.0) }
+        }"#;
+        let r = renderer(src, 8, 8);
+        assert_eq!(r.default_uniforms().param("size"), 0.4);
+    }
+
+    #[test]
+    fn lens_exposure_scales_scene() {
+        let src = r#"cinematic "c" {
+            layer main { circle(0.5) | shade(1.0, 1.0, 1.0) }
+            lens { exposure: 0.4 }
+        }"#;
+        let frame = render_frame(&cinematic(src), 9, 9, 0.0).unwrap();
+        assert_eq!(pixel(&frame, 9, 4, 4)[0], 102);
+    }
+
+    #[test]
+    fn invalid_pipeline_is_rejected() {
+        let cin = cinematic(r#"cinematic "c" { layer main { glow(1.0) } }"#);
+        assert!(CpuRenderer::new(&cin, 4, 4).is_err());
+    }
+
+    #[test]
+    fn combinators_run_each_shape_from_the_same_position() {
+        // Transforms inside one argument only move that shape
+        let src = r#"cinematic "c" { layer main {
+            union(translate(-0.5, 0.0) | circle(0.2), translate(0.5, 0.0) | circle(0.2))
+                | shade(1.0, 1.0, 1.0)
+        } }"#;
+        let frame = render_frame(&cinematic(src), 33, 33, 0.0).unwrap();
+        assert_eq!(pixel(&frame, 33, 8, 16)[0], 255);
+        assert_eq!(pixel(&frame, 33, 24, 16)[0], 255);
+        assert_eq!(pixel(&frame, 33, 16, 16)[0], 0);
+
+        let src = r#"cinematic "c" { layer main {
+            subtract(circle(0.4), circle(0.2)) | shade(1.0, 1.0, 1.0)
+        } }"#;
+        let frame = render_frame(&cinematic(src), 33, 33, 0.0).unwrap();
+        assert_eq!(pixel(&frame, 33, 16, 16)[0], 0);
+        assert_eq!(pixel(&frame, 33, 21, 16)[0], 255);
+    }
+
+    #[test]
+    fn every_builtin_renders() {
+        for builtin in BUILTINS {
+            let name = builtin.name;
+            let args = match name {
+                "palette" => "fire",
+                _ if builtin.is_combinator() => "circle(0.2), translate(0.1, 0.0) | box(0.1, 0.1)",
+                _ => "",
+            };
+            let pipeline = match (builtin.input, builtin.output) {
+                (ShaderState::Position, ShaderState::Position) => {
+                    format!("{name}({args}) | circle(0.3) | glow(1.0)")
+                }
+                (ShaderState::Position, ShaderState::Sdf) => format!("{name}({args}) | glow(1.0)"),
+                (ShaderState::Sdf, ShaderState::Sdf) => {
+                    format!("circle(0.3) | {name}({args}) | glow(1.0)")
+                }
+                (ShaderState::Sdf, ShaderState::Color) => format!("circle(0.3) | {name}({args})"),
+                (ShaderState::Color, ShaderState::Color) => {
+                    format!("circle(0.3) | glow(1.0) | {name}({args})")
+                }
+                _ => format!("{name}({args})"),
+            };
+            let src = format!(r#"cinematic "c" {{ layer main {{ {pipeline} }} }}"#);
+            let mut r = renderer(&src, 16, 12);
+            let mut uniforms = r.default_uniforms();
+            uniforms.time = 1.5;
+            let frame = r.render(&uniforms);
+            assert_eq!(frame.len(), 16 * 12 * 4, "{name}");
+        }
+    }
+}
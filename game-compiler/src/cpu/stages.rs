@@ -0,0 +1,348 @@
+//! Per-pixel stage interpretation, one arm per builtin.
+//!
+//! Each arm is a transcription of the matching arm of
+//! `wgsl::emit_wgsl_stage`; keep the two in sync when a builtin changes.
+//! Constants are the shader literals, not `std::f32::consts`.
+
+#![allow(clippy::approx_constant, clippy::excessive_precision)]
+
+use crate::ast::{Expr, Stage};
+use crate::builtins;
+use crate::codegen::combine;
+use crate::codegen::gradient::Gradient;
+use crate::codegen::stages::find_arg;
+
+use super::eval::{eval, Env, Value};
+use super::helpers::*;
+
+/// A stage argument slot, resolved once per renderer.
+enum Slot {
+    Const(Value),
+    Expr(Expr),
+}
+
+/// A pipeline stage with its argument slots bound to the builtin's params.
+pub(crate) struct CompiledStage {
+    name: String,
+    slots: Vec<Slot>,
+    /// `palette(name)` coefficients, looked up at bind time.
+    palette: Option<[[f32; 3]; 4]>,
+    /// A combinator's `a` and `b` pipelines.
+    subs: Vec<Vec<CompiledStage>>,
+    /// `gradient` mode and color stops.
+    gradient: Option<Gradient>,
+}
+
+impl CompiledStage {
+    pub fn new(stage: &Stage) -> Self {
+        let params = builtins::lookup(&stage.name).map_or(&[][..], |b| b.params);
+        let slots = params
+            .iter()
+            .enumerate()
+            .map(|(pos, param)| match find_arg(&stage.args, param.name, pos) {
+                Some(arg) => match &arg.value {
+                    Expr::Number(v, _) => Slot::Const(Value::scalar(*v as f32)),
+                    other => Slot::Expr(other.clone()),
+                },
+                None => Slot::Const(Value::scalar(param.default.unwrap_or(0.0) as f32)),
+            })
+            .collect();
+        let palette = (stage.name == "palette").then(|| {
+            let name = match find_arg(&stage.args, "name", 0).map(|a| &a.value) {
+                Some(Expr::Ident(name, _)) | Some(Expr::String(name, _)) => name.as_str(),
+                _ => "",
+            };
+            palette_coeffs(name)
+        });
+        let subs = if builtins::is_combinator(&stage.name) {
+            ["a", "b"]
+                .into_iter()
+                .enumerate()
+                .map(|(pos, slot)| {
+                    combine::sub_pipeline(stage, slot, pos).iter().map(Self::new).collect()
+                })
+                .collect()
+        } else {
+            Vec::new()
+        };
+        let gradient = (stage.name == "gradient").then(|| Gradient::of(stage));
+        Self { name: stage.name.clone(), slots, palette, subs, gradient }
+    }
+
+    fn value(&self, pos: usize, env: &Env) -> Value {
+        match self.slots.get(pos) {
+            Some(Slot::Const(v)) => *v,
+            Some(Slot::Expr(e)) => eval(e, env),
+            None => Value::scalar(0.0),
+        }
+    }
+
+    fn arg(&self, pos: usize, env: &Env) -> f32 {
+        self.value(pos, env).x()
+    }
+
+    /// `r, g, b` slots; a color value in the first slot supplies all three.
+    fn rgb(&self, env: &Env) -> [f32; 3] {
+        let r = self.value(0, env);
+        if r.len >= 3 {
+            [r.v[0], r.v[1], r.v[2]]
+        } else {
+            [r.x(), self.arg(1, env), self.arg(2, env)]
+        }
+    }
+}
+
+/// Pipeline registers for one pixel. `p` lives in the [`Env`].
+pub(crate) struct Pixel {
+    pub sdf: f32,
+    pub color: [f32; 4],
+}
+
+/// Run one stage for one pixel.
+pub(crate) fn apply(stage: &CompiledStage, env: &mut Env, px: &mut Pixel) {
+    let a = |i: usize, env: &Env| stage.arg(i, env);
+    let p = env.p;
+    let uv = env.uv;
+    let time = env.time;
+    match stage.name.as_str() {
+        // ── SDF Generators: Position -> Sdf ─────────────────
+        "circle" => px.sdf = length(p) - a(0, env),
+        "ring" => px.sdf = (length(p) - a(0, env)).abs() - a(1, env),
+        "star" => {
+            let (points, radius, inner) = (a(0, env), a(1, env), a(2, env));
+            let angle = p[1].atan2(p[0]);
+            let r = length(p);
+            let seg = 6.28318 / points;
+            let raw = wgsl_mod(wgsl_mod(angle, seg) + seg, seg);
+            let ha = (raw - seg * 0.5).abs();
+            px.sdf = r * ha.cos() - radius + (r * ha.sin() - inner) * 0.5;
+        }
+        "box" => {
+            let d = [p[0].abs() - a(0, env), p[1].abs() - a(1, env)];
+            px.sdf = length([d[0].max(0.0), d[1].max(0.0)]) + d[0].max(d[1]).min(0.0);
+        }
+        "polygon" => {
+            let (sides, radius) = (a(0, env), a(1, env));
+            let angle = p[1].atan2(p[0]);
+            let seg = 6.28318 / sides;
+            let raw = wgsl_mod(wgsl_mod(angle, seg) + seg, seg);
+            px.sdf = length(p) * (raw - seg * 0.5).abs().cos() - radius;
+        }
+        "fbm" => {
+            let sc = a(0, env);
+            px.sdf = fbm2([p[0] * sc, p[1] * sc], a(1, env) as i32, a(2, env), a(3, env));
+        }
+        "simplex" => {
+            let sc = a(0, env);
+            px.sdf = simplex2([p[0] * sc, p[1] * sc]);
+        }
+        "voronoi" => px.sdf = voronoi2(p, a(0, env)),
+        "concentric_waves" => {
+            let (amp, width, freq) = (a(0, env), a(1, env), a(2, env));
+            let r = length(p) * freq;
+            px.sdf = (r - time * 2.0).sin() * amp * (-length(p) * width).exp();
+        }
+
+        // ── SDF combinators: nested pipelines -> Sdf ────────
+        "union" | "subtract" | "intersect" | "smooth_union" | "morph" => {
+            // Each pipeline starts from the combinator's position
+            let mut run = |sub: &[CompiledStage]| {
+                let mut inner = Pixel { sdf: px.sdf, color: px.color };
+                for s in sub {
+                    apply(s, env, &mut inner);
+                }
+                env.p = p;
+                inner.sdf
+            };
+            let (da, db) = (run(&stage.subs[0]), run(&stage.subs[1]));
+            px.sdf = match stage.name.as_str() {
+                "union" => da.min(db),
+                "subtract" => da.max(-db),
+                "intersect" => da.max(db),
+                "smooth_union" => {
+                    let k = a(2, env).max(0.0001);
+                    let h = (0.5 + 0.5 * (db - da) / k).clamp(0.0, 1.0);
+                    mix(db, da, h) - k * h * (1.0 - h)
+                }
+                _ => mix(da, db, a(2, env)),
+            };
+        }
+
+        // ── Sdf -> Color bridges ────────────────────────────
+        "glow" => {
+            let g = apply_glow(px.sdf, a(0, env));
+            px.color = [g, g, g, 1.0];
+        }
+        "shade" => {
+            let [r, g, b] = stage.rgb(env);
+            let edge = smoothstep(0.005, -0.005, px.sdf);
+            px.color = [r * edge, g * edge, b * edge, 1.0];
+        }
+        "emissive" => {
+            let g = apply_glow(px.sdf, a(0, env));
+            px.color = [g, g, g, g];
+        }
+        "palette" => {
+            let t = (px.sdf * 0.5 + 0.5).clamp(0.0, 1.0);
+            let [r, g, b] = iq_palette(t, stage.palette.as_ref().expect("bound at compile"));
+            px.color = [r, g, b, 1.0];
+        }
+
+        // ── Color processors: Color -> Color ────────────────
+        "tint" => {
+            let [r, g, b] = stage.rgb(env);
+            let c = px.color;
+            px.color = [c[0] * r, c[1] * g, c[2] * b, 1.0];
+        }
+        "bloom" => {
+            let (thresh, strength) = (a(0, env), a(1, env));
+            let add = (luma(px.color) - thresh).max(0.0) * strength;
+            map_rgb(&mut px.color, |c| c + add);
+        }
+        "grain" => {
+            let noise = sin_hash(p[0] * 12.9898 + p[1] * 78.233 + time);
+            let amount = a(0, env);
+            map_rgb(&mut px.color, |c| c + (noise - 0.5) * amount);
+        }
+        "vignette" => {
+            let (strength, radius) = (a(0, env), a(1, env));
+            let vig = smoothstep(radius + 0.3, radius - 0.2, length(uv));
+            let k = mix(1.0, vig, strength);
+            map_rgb(&mut px.color, |c| c * k);
+        }
+        "chromatic" => {
+            let d = length(uv) * a(0, env);
+            px.color[0] += d * 0.5;
+            px.color[2] -= d * 0.5;
+        }
+        "tonemap" => {
+            let exposure = a(0, env);
+            map_rgb(&mut px.color, |c| {
+                let x = c * exposure;
+                ((x * (2.51 * x + 0.03)) / (x * (2.43 * x + 0.59) + 0.14)).clamp(0.0, 1.0)
+            });
+        }
+        "scanlines" => {
+            let (freq, intensity) = (a(0, env), a(1, env));
+            let scan = (uv[1] * freq * 3.14159).sin() * 0.5 + 0.5;
+            map_rgb(&mut px.color, |c| c * (1.0 - intensity * (1.0 - scan)));
+        }
+        "saturate_color" => {
+            let gray = luma(px.color);
+            let amount = a(0, env);
+            map_rgb(&mut px.color, |c| mix(gray, c, amount));
+        }
+        "glitch" => {
+            let intensity = a(0, env);
+            let t = (time * 8.0).floor();
+            let offset = (sin_hash(t * 12.9898) - 0.5) * intensity * 0.1;
+            let block = step(
+                0.9 - intensity * 0.3,
+                sin_hash(uv[1] * 20.0 * 12.9898 + t * 78.233),
+            );
+            let c = px.color;
+            px.color[0] = mix(c[0], c[0] + offset, block);
+            px.color[1] = mix(c[1], c[1] - offset * 0.5, block);
+        }
+        "blend" => {
+            let factor = a(0, env);
+            map_rgb(&mut px.color, |c| c * factor);
+        }
+
+        // ── Position transforms: Position -> Position ───────
+        "rotate" => {
+            let angle = a(0, env);
+            let (s, c) = angle.sin_cos();
+            env.p = [p[0] * c - p[1] * s, p[0] * s + p[1] * c];
+        }
+        "translate" => env.p = [p[0] - a(0, env), p[1] - a(1, env)],
+        "scale" => {
+            let s = a(0, env);
+            env.p = [p[0] / s, p[1] / s];
+        }
+        "twist" => {
+            let tw = a(0, env) * p[1];
+            let (s, c) = tw.sin_cos();
+            env.p = [p[0] * c - p[1] * s, p[0] * s + p[1] * c];
+        }
+        "mirror" => {
+            env.p = if a(0, env) > 0.5 { [p[0], p[1].abs()] } else { [p[0].abs(), p[1]] };
+        }
+        "repeat" => {
+            let size = 2.0 / a(0, env);
+            let wrap = |x: f32| wgsl_mod(wgsl_mod(x, size) + size, size) - size * 0.5;
+            env.p = [wrap(p[0]), wrap(p[1])];
+        }
+        "domain_warp" => {
+            let (amount, freq) = (a(0, env), a(1, env));
+            let q = [p[0] * freq, p[1] * freq];
+            let offset = [noise2(q), noise2([q[0] + 5.2, q[1] + 1.3])];
+            env.p = [p[0] + offset[0] * amount, p[1] + offset[1] * amount];
+        }
+        "curl_noise" => {
+            let (freq, amp) = (a(0, env), a(1, env));
+            let eps = 0.01;
+            let n = |x: f32, y: f32| noise2([x * freq, y * freq]);
+            let cn_x = n(p[0] + eps, p[1]) - n(p[0] - eps, p[1]);
+            let cn_y = n(p[0], p[1] + eps) - n(p[0], p[1] - eps);
+            let k = amp / (2.0 * eps);
+            env.p = [p[0] + cn_y * k, p[1] - cn_x * k];
+        }
+        "displace" => {
+            let strength = a(0, env);
+            let q = [p[0] * 3.0, p[1] * 3.0];
+            let offset = [noise2(q), noise2([q[0] + 5.0, q[1] + 3.0])];
+            env.p = [p[0] + offset[0] * strength, p[1] + offset[1] * strength];
+        }
+
+        // ── SDF modifiers: Sdf -> Sdf ───────────────────────
+        "mask_arc" => {
+            let theta = p[0].atan2(p[1]) + 3.14159265359;
+            if theta >= a(0, env) {
+                px.sdf = 999.0;
+            }
+        }
+        "threshold" => px.sdf = step(a(0, env), px.sdf),
+        "onion" => px.sdf = px.sdf.abs() - a(0, env),
+        "round" => px.sdf -= a(0, env),
+
+        // ── Position -> Color generators ────────────────────
+        "gradient" => {
+            let Some(gradient) = &stage.gradient else { return };
+            let t = gradient.mode.t(uv, p, a(3, env)).clamp(0.0, 1.0);
+            let segments = gradient.stops.len().saturating_sub(1) as f32;
+            let mut c = [0.0; 3];
+            for (i, stop) in gradient.stops.iter().enumerate() {
+                let v = eval(stop, env);
+                let rgb = if v.len >= 3 { [v.v[0], v.v[1], v.v[2]] } else { [v.x(); 3] };
+                let f = (t * segments - (i as f32 - 1.0)).clamp(0.0, 1.0);
+                c = if i == 0 { rgb } else { std::array::from_fn(|k| mix(c[k], rgb[k], f)) };
+            }
+            px.color = [c[0], c[1], c[2], 1.0];
+        }
+        "spectrum" => {
+            let d = length(p);
+            let bass = smoothstep(0.35, 0.15, d) * a(0, env);
+            let mid = smoothstep(0.55, 0.35, d) * smoothstep(0.15, 0.35, d) * a(1, env);
+            let treble = smoothstep(0.75, 0.55, d) * smoothstep(0.35, 0.55, d) * a(2, env);
+            px.color = [
+                bass + mid * 0.1 + treble * 0.2,
+                bass * 0.2 + mid + treble * 0.3,
+                bass * 0.1 + mid * 0.3 + treble,
+                1.0,
+            ];
+        }
+
+        _ => {}
+    }
+}
+
+fn luma(c: [f32; 4]) -> f32 {
+    c[0] * 0.299 + c[1] * 0.587 + c[2] * 0.114
+}
+
+fn map_rgb(color: &mut [f32; 4], f: impl Fn(f32) -> f32) {
+    for c in &mut color[..3] {
+        *c = f(*c);
+    }
+}
@@ -0,0 +1,232 @@
+//! Deterministic uniform animation for offline rendering.
+//!
+//! [`Timeline`] plays a cinematic's `arc` blocks and `score` the way the
+//! generated `arcUpdate` / `GameScorePlayer` JS does, and [`Keyframes`]
+//! scripts a single value (a `data.*` field, say) over time.
+
+use crate::ast::Cinematic;
+use crate::codegen::expr::extract_number;
+use crate::codegen::score::{self, resolve_target};
+use crate::codegen::UniformInfo;
+
+use super::Uniforms;
+
+/// One eased `from -> to` transition on a uniform.
+#[derive(Debug, Clone)]
+struct Transition {
+    uniform: String,
+    from: f32,
+    to: f32,
+    start: f32,
+    duration: f32,
+    easing: String,
+}
+
+/// Arc and score transitions resolved against a cinematic's uniforms.
+#[derive(Debug, Clone, Default)]
+pub struct Timeline {
+    transitions: Vec<Transition>,
+}
+
+impl Timeline {
+    /// Flatten arcs (played back to back from `t = 0`) and the score.
+    pub fn new(cinematic: &Cinematic, uniforms: &[UniformInfo]) -> Self {
+        let mut transitions = Vec::new();
+
+        let mut cursor = 0.0;
+        for entry in cinematic.arcs.iter().flat_map(|arc| &arc.entries) {
+            let duration = duration_secs(&entry.duration);
+            if let Some(uniform) = resolve_target(&entry.target, uniforms) {
+                transitions.push(Transition {
+                    uniform,
+                    from: extract_number(&entry.from).unwrap_or(0.0) as f32,
+                    to: extract_number(&entry.to).unwrap_or(0.0) as f32,
+                    start: cursor,
+                    duration,
+                    easing: entry.easing.clone().unwrap_or_else(|| "linear".into()),
+                });
+            }
+            cursor += duration;
+        }
+
+        if let Some(block) = &cinematic.score {
+            for entry in score::flatten_score(block) {
+                if let Some(uniform) = resolve_target(&entry.target, uniforms) {
+                    transitions.push(Transition {
+                        uniform,
+                        from: entry.from as f32,
+                        to: entry.to as f32,
+                        start: entry.start_seconds as f32,
+                        duration: entry.duration_seconds as f32,
+                        easing: entry.easing.unwrap_or_else(|| "linear".into()),
+                    });
+                }
+            }
+        }
+
+        Self { transitions }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.transitions.is_empty()
+    }
+
+    /// Set every animated uniform to its value at `time`. Transitions that
+    /// have not started leave the uniform alone; finished ones hold `to`.
+    pub fn apply(&self, time: f32, uniforms: &mut Uniforms) {
+        for t in &self.transitions {
+            if time < t.start {
+                continue;
+            }
+            let value = if time >= t.start + t.duration {
+                t.to
+            } else {
+                let progress = (time - t.start) / t.duration;
+                t.from + (t.to - t.from) * ease(&t.easing, progress)
+            };
+            uniforms.set_param(&t.uniform, value);
+        }
+    }
+}
+
+/// Seconds at the default 120 BPM, matching the arc runtime.
+fn duration_secs(d: &crate::ast::Duration) -> f32 {
+    use crate::ast::Duration;
+    match d {
+        Duration::Seconds(v) => *v as f32,
+        Duration::Millis(v) => *v as f32 / 1000.0,
+        Duration::Bars(v) => *v as f32 * 2.0,
+    }
+}
+
+/// Arc easing curves plus the score's `ease_*` names; unknown is linear.
+fn ease(name: &str, t: f32) -> f32 {
+    use std::f32::consts::PI;
+    match name {
+        "expo_in" if t == 0.0 => 0.0,
+        "expo_in" => 2f32.powf(10.0 * (t - 1.0)),
+        "expo_out" if t == 1.0 => 1.0,
+        "expo_out" => 1.0 - 2f32.powf(-10.0 * t),
+        "cubic_in_out" if t < 0.5 => 4.0 * t * t * t,
+        "cubic_in_out" => 1.0 - (-2.0 * t + 2.0).powi(3) / 2.0,
+        "smooth" => t * t * (3.0 - 2.0 * t),
+        "elastic" if t == 0.0 || t == 1.0 => t,
+        "elastic" => -(2f32.powf(10.0 * (t - 1.0))) * ((t - 1.1) * 5.0 * PI).sin(),
+        "bounce" => {
+            let (n, d) = (7.5625, 2.75);
+            if t < 1.0 / d {
+                n * t * t
+            } else if t < 2.0 / d {
+                let t = t - 1.5 / d;
+                n * t * t + 0.75
+            } else if t < 2.5 / d {
+                let t = t - 2.25 / d;
+                n * t * t + 0.9375
+            } else {
+                let t = t - 2.625 / d;
+                n * t * t + 0.984375
+            }
+        }
+        "ease_in" => t * t,
+        "ease_out" => t * (2.0 - t),
+        "ease_in_out" if t < 0.5 => 2.0 * t * t,
+        "ease_in_out" => -1.0 + (4.0 - 2.0 * t) * t,
+        _ => t,
+    }
+}
+
+/// A value scripted over time: linear between `(time, value)` keys,
+/// held flat before the first and after the last.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Keyframes(Vec<(f32, f32)>);
+
+impl Keyframes {
+    pub fn constant(value: f32) -> Self {
+        Self(vec![(0.0, value)])
+    }
+
+    /// Parse `0.5` or `time:value,time:value,...` (times in seconds).
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let number = |s: &str| {
+            s.trim().trim_end_matches('s').parse::<f32>().map_err(|_| format!("bad number '{s}'"))
+        };
+        if !spec.contains(':') {
+            return number(spec).map(Self::constant);
+        }
+        let mut keys = spec
+            .split(',')
+            .map(|key| {
+                let (t, v) = key
+                    .split_once(':')
+                    .ok_or_else(|| format!("expected time:value, got '{key}'"))?;
+                Ok((number(t)?, number(v)?))
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+        keys.sort_by(|a, b| a.0.total_cmp(&b.0));
+        Ok(Self(keys))
+    }
+
+    pub fn sample(&self, time: f32) -> f32 {
+        let keys = &self.0;
+        let next = keys.partition_point(|&(t, _)| t <= time);
+        match (next.checked_sub(1).map(|i| keys[i]), keys.get(next)) {
+            (Some((t0, v0)), Some(&(t1, v1))) => v0 + (v1 - v0) * (time - t0) / (t1 - t0),
+            (Some((_, v)), None) | (None, Some(&(_, v))) => v,
+            (None, None) => 0.0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cinematic(source: &str) -> Cinematic {
+        crate::compile_to_ast(source).unwrap().cinematics.remove(0)
+    }
+
+    #[test]
+    fn keyframes_interpolate_and_hold() {
+        let k = Keyframes::parse("0:0, 2s:1, 4:0").unwrap();
+        assert_eq!(k.sample(-1.0), 0.0);
+        assert_eq!(k.sample(1.0), 0.5);
+        assert_eq!(k.sample(3.0), 0.5);
+        assert_eq!(k.sample(9.0), 0.0);
+        assert_eq!(Keyframes::parse("0.25").unwrap().sample(7.0), 0.25);
+        assert!(Keyframes::parse("0:x").is_err());
+    }
+
+    #[test]
+    fn arcs_play_back_to_back() {
+        let cin = cinematic(
+            r#"cinematic "c" {
+                layer config { radius: 0.1 }
+                layer main { circle(radius) | glow(1.0) }
+                arc {
+                    radius: 0.0 -> 1.0 over 2s linear
+                    radius: 1.0 -> 0.5 over 1s smooth
+                }
+            }"#,
+        );
+        let timeline = Timeline::new(&cin, &crate::codegen::extract_uniforms(&cin));
+        let at = |time: f32| {
+            let mut u = Uniforms::default();
+            timeline.apply(time, &mut u);
+            u.param("radius")
+        };
+        assert_eq!(at(1.0), 0.5);
+        assert_eq!(at(2.5), 0.75);
+        assert_eq!(at(10.0), 0.5);
+    }
+
+    #[test]
+    fn unknown_targets_are_skipped() {
+        let cin = cinematic(
+            r#"cinematic "c" {
+                layer main { circle(0.2) | glow(1.0) }
+                arc { nothing: 0.0 -> 1.0 over 1s }
+            }"#,
+        );
+        assert!(Timeline::new(&cin, &[]).is_empty());
+    }
+}
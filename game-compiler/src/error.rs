@@ -1,30 +1,75 @@
+use serde::Serialize;
 use thiserror::Error;
 
 /// Byte-offset span within source text.
-#[derive(Debug, Clone, PartialEq, Eq)]
+///
+/// Nodes built outside the parser (tests, synthesized stages) carry the
+/// default `0..0` span.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
 pub struct Span {
     pub start: usize,
     pub end: usize,
 }
 
+impl Span {
+    pub fn new(start: usize, end: usize) -> Self {
+        Self { start, end }
+    }
+
+    /// Smallest span covering both `self` and `other`.
+    pub fn to(self, other: Span) -> Span {
+        Span {
+            start: self.start.min(other.start),
+            end: self.end.max(other.end),
+        }
+    }
+
+    /// Whether this is a zero-width placeholder rather than real source text.
+    pub fn is_empty(&self) -> bool {
+        self.end <= self.start
+    }
+
+    /// 1-based `(line, column)` of `offset` in `source`; columns count chars.
+    pub fn line_col(source: &str, offset: usize) -> (usize, usize) {
+        let offset = offset.min(source.len());
+        let before = source.get(..offset).unwrap_or(source);
+        let line = before.matches('\n').count() + 1;
+        let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+        (line, before[line_start..].chars().count() + 1)
+    }
+}
+
 // ── Rich diagnostics ────────────────────────────────────────
 
 /// Severity level for a diagnostic message.
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
 pub enum Severity {
     Error,
     Warning,
     Note,
 }
 
+/// A machine-applicable edit: replace the text at `span` with `replacement`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Fix {
+    pub span: Span,
+    pub replacement: String,
+}
+
 /// A structured diagnostic carrying rich error information.
 #[derive(Debug, Clone)]
 pub struct Diagnostic {
     pub severity: Severity,
+    pub code: Option<ErrorCode>,
     pub message: String,
     pub span: Option<Span>,
     pub suggestion: Option<String>,
+    /// Machine-applicable edit: replace `fix.span` with `fix.replacement`.
+    pub fix: Option<Fix>,
     pub help: Option<String>,
+    /// Secondary notes, e.g. "expanded from define 'x'" at the call site.
+    pub notes: Vec<Diagnostic>,
 }
 
 impl Diagnostic {
@@ -32,10 +77,13 @@ impl Diagnostic {
     pub fn error(message: impl Into<String>) -> Self {
         Self {
             severity: Severity::Error,
+            code: None,
             message: message.into(),
             span: None,
             suggestion: None,
+            fix: None,
             help: None,
+            notes: Vec::new(),
         }
     }
 
@@ -43,10 +91,13 @@ impl Diagnostic {
     pub fn warning(message: impl Into<String>) -> Self {
         Self {
             severity: Severity::Warning,
+            code: None,
             message: message.into(),
             span: None,
             suggestion: None,
+            fix: None,
             help: None,
+            notes: Vec::new(),
         }
     }
 
@@ -54,10 +105,13 @@ impl Diagnostic {
     pub fn note(message: impl Into<String>) -> Self {
         Self {
             severity: Severity::Note,
+            code: None,
             message: message.into(),
             span: None,
             suggestion: None,
+            fix: None,
             help: None,
+            notes: Vec::new(),
         }
     }
 
@@ -73,11 +127,181 @@ impl Diagnostic {
         self
     }
 
+    /// Attach a machine-applicable replacement for the text at `span`.
+    pub fn with_fix(mut self, span: Span, replacement: impl Into<String>) -> Self {
+        self.fix = Some(Fix { span, replacement: replacement.into() });
+        self
+    }
+
+    /// Attach an error code.
+    pub fn with_code(mut self, code: ErrorCode) -> Self {
+        self.code = Some(code);
+        self
+    }
+
+    pub fn is_error(&self) -> bool {
+        self.severity == Severity::Error
+    }
+
     /// Attach a help message.
     pub fn with_help(mut self, help: impl Into<String>) -> Self {
         self.help = Some(help.into());
         self
     }
+
+    /// Attach a secondary note.
+    pub fn with_note(mut self, note: Diagnostic) -> Self {
+        self.notes.push(note);
+        self
+    }
+}
+
+// ── Diagnostics collection ──────────────────────────────────
+
+/// An ordered collection of diagnostics from one check or compile run.
+#[derive(Debug, Clone, Default)]
+pub struct Diagnostics {
+    items: Vec<Diagnostic>,
+}
+
+impl Diagnostics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, diag: Diagnostic) {
+        self.items.push(diag);
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, Diagnostic> {
+        self.items.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// Whether any diagnostic has error severity.
+    pub fn has_errors(&self) -> bool {
+        self.items.iter().any(Diagnostic::is_error)
+    }
+
+    /// Render every diagnostic with source context, one after another.
+    pub fn render(&self, source: &str) -> String {
+        self.items
+            .iter()
+            .map(|d| render_diagnostic(d, source))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// JSON array of diagnostics; spans gain 1-based line/column positions
+    /// resolved against `source`.
+    pub fn to_json(&self, source: &str) -> serde_json::Value {
+        serde_json::Value::Array(self.items.iter().map(|d| d.to_json(source)).collect())
+    }
+}
+
+impl Diagnostic {
+    /// JSON form of this diagnostic; see [`Diagnostics::to_json`].
+    pub fn to_json(&self, source: &str) -> serde_json::Value {
+        serde_json::to_value(JsonDiagnostic::new(self, source))
+            .expect("diagnostics always serialize")
+    }
+}
+
+impl From<CompileError> for Diagnostics {
+    fn from(err: CompileError) -> Self {
+        Self { items: vec![err.to_diagnostic()] }
+    }
+}
+
+impl FromIterator<Diagnostic> for Diagnostics {
+    fn from_iter<I: IntoIterator<Item = Diagnostic>>(iter: I) -> Self {
+        Self { items: iter.into_iter().collect() }
+    }
+}
+
+impl Extend<Diagnostic> for Diagnostics {
+    fn extend<I: IntoIterator<Item = Diagnostic>>(&mut self, iter: I) {
+        self.items.extend(iter);
+    }
+}
+
+impl IntoIterator for Diagnostics {
+    type Item = Diagnostic;
+    type IntoIter = std::vec::IntoIter<Diagnostic>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.items.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a Diagnostics {
+    type Item = &'a Diagnostic;
+    type IntoIter = std::slice::Iter<'a, Diagnostic>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.items.iter()
+    }
+}
+
+#[derive(Serialize)]
+struct JsonDiagnostic<'a> {
+    severity: Severity,
+    code: Option<String>,
+    message: &'a str,
+    span: Option<JsonSpan>,
+    help: Option<&'a str>,
+    suggestion: Option<&'a str>,
+    fix: Option<JsonFix<'a>>,
+    notes: Vec<JsonDiagnostic<'a>>,
+}
+
+#[derive(Serialize)]
+struct JsonSpan {
+    start: usize,
+    end: usize,
+    line_start: usize,
+    column_start: usize,
+    line_end: usize,
+    column_end: usize,
+}
+
+#[derive(Serialize)]
+struct JsonFix<'a> {
+    span: JsonSpan,
+    replacement: &'a str,
+}
+
+impl<'a> JsonDiagnostic<'a> {
+    fn new(diag: &'a Diagnostic, source: &str) -> Self {
+        Self {
+            severity: diag.severity,
+            code: diag.code.map(|c| c.to_string()),
+            message: &diag.message,
+            span: diag.span.map(|s| JsonSpan::new(s, source)),
+            help: diag.help.as_deref(),
+            suggestion: diag.suggestion.as_deref(),
+            fix: diag.fix.as_ref().map(|f| JsonFix {
+                span: JsonSpan::new(f.span, source),
+                replacement: &f.replacement,
+            }),
+            notes: diag.notes.iter().map(|n| Self::new(n, source)).collect(),
+        }
+    }
+}
+
+impl JsonSpan {
+    fn new(span: Span, source: &str) -> Self {
+        let (line_start, column_start) = Span::line_col(source, span.start);
+        let (line_end, column_end) = Span::line_col(source, span.end);
+        Self { start: span.start, end: span.end, line_start, column_start, line_end, column_end }
+    }
 }
 
 // ── Error codes ────────────────────────────────────────────────
@@ -108,6 +332,8 @@ pub enum ErrorCode {
     E009,
     /// Duplicate layer name within a cinematic.
     E010,
+    /// Generated shader failed naga validation (`game check --strict`).
+    E011,
 }
 
 impl std::fmt::Display for ErrorCode {
@@ -123,6 +349,7 @@ impl std::fmt::Display for ErrorCode {
             Self::E008 => write!(f, "E008"),
             Self::E009 => write!(f, "E009"),
             Self::E010 => write!(f, "E010"),
+            Self::E011 => write!(f, "E011"),
         }
     }
 }
@@ -134,11 +361,11 @@ pub(crate) fn levenshtein(a: &str, b: &str) -> usize {
     let a: Vec<char> = a.chars().collect();
     let b: Vec<char> = b.chars().collect();
     let mut matrix = vec![vec![0usize; b.len() + 1]; a.len() + 1];
-    for i in 0..=a.len() {
-        matrix[i][0] = i;
+    for (i, row) in matrix.iter_mut().enumerate() {
+        row[0] = i;
     }
-    for j in 0..=b.len() {
-        matrix[0][j] = j;
+    for (j, cell) in matrix[0].iter_mut().enumerate() {
+        *cell = j;
     }
     for i in 1..=a.len() {
         for j in 1..=b.len() {
@@ -191,12 +418,18 @@ pub enum CompileError {
     ValidationError {
         message: String,
         code: Option<ErrorCode>,
+        span: Option<Span>,
+        notes: Vec<Diagnostic>,
+        fix: Option<Fix>,
     },
 
     #[error("codegen error: {message}")]
     CodegenError {
         message: String,
         code: Option<ErrorCode>,
+        span: Option<Span>,
+        notes: Vec<Diagnostic>,
+        fix: Option<Fix>,
     },
 
     #[error(transparent)]
@@ -232,6 +465,9 @@ impl CompileError {
         Self::ValidationError {
             message: msg.into(),
             code: None,
+            span: None,
+            notes: Vec::new(),
+            fix: None,
         }
     }
 
@@ -239,6 +475,9 @@ impl CompileError {
         Self::CodegenError {
             message: msg.into(),
             code: None,
+            span: None,
+            notes: Vec::new(),
+            fix: None,
         }
     }
 
@@ -254,6 +493,83 @@ impl CompileError {
         self
     }
 
+    /// Point a validation or codegen error at source. Placeholder (empty)
+    /// spans are ignored so synthesized nodes don't clobber a real location.
+    pub fn with_span(mut self, at: Span) -> Self {
+        if let Self::ValidationError { span, .. } | Self::CodegenError { span, .. } =
+            self.inner_mut()
+        {
+            if !at.is_empty() {
+                *span = Some(at);
+            }
+        }
+        self
+    }
+
+    /// Attach a secondary note to a validation or codegen error.
+    pub fn with_note(mut self, note: Diagnostic) -> Self {
+        if let Self::ValidationError { notes, .. } | Self::CodegenError { notes, .. } =
+            self.inner_mut()
+        {
+            notes.push(note);
+        }
+        self
+    }
+
+    /// Attach a machine-applicable replacement to a validation or codegen error.
+    pub fn with_fix(mut self, at: Span, replacement: impl Into<String>) -> Self {
+        if let Self::ValidationError { fix, .. } | Self::CodegenError { fix, .. } =
+            self.inner_mut()
+        {
+            if !at.is_empty() {
+                *fix = Some(Fix { span: at, replacement: replacement.into() });
+            }
+        }
+        self
+    }
+
+    /// Source span of the error, if known.
+    pub fn span(&self) -> Option<Span> {
+        match self.inner() {
+            Self::LexError { span, .. } => Some(*span),
+            // The parser records the offending token's byte range in line/col
+            Self::ParseError { line, col, .. } => Some(Span::new(*line, *col)),
+            Self::ValidationError { span, .. } | Self::CodegenError { span, .. } => *span,
+            _ => None,
+        }
+    }
+
+    /// Secondary notes attached with [`with_note`](Self::with_note).
+    pub fn notes(&self) -> &[Diagnostic] {
+        match self.inner() {
+            Self::ValidationError { notes, .. } | Self::CodegenError { notes, .. } => notes,
+            _ => &[],
+        }
+    }
+
+    /// Convert into a structured [`Diagnostic`] (severity error).
+    pub fn to_diagnostic(&self) -> Diagnostic {
+        let inner = self.inner();
+        let message = match inner {
+            Self::LexError { message, .. }
+            | Self::ParseError { message, .. }
+            | Self::ValidationError { message, .. }
+            | Self::CodegenError { message, .. } => message.clone(),
+            other => other.to_string(),
+        };
+        let mut diag = Diagnostic::error(message);
+        diag.code = self.code();
+        diag.span = self.span();
+        diag.help = self.help().map(str::to_string);
+        diag.notes = self.notes().to_vec();
+        if let Self::ValidationError { fix: Some(fix), .. }
+        | Self::CodegenError { fix: Some(fix), .. } = inner
+        {
+            diag.fix = Some(fix.clone());
+        }
+        diag
+    }
+
     /// Retrieve the error code, if any.
     pub fn code(&self) -> Option<ErrorCode> {
         match self {
@@ -289,50 +605,19 @@ impl CompileError {
             other => other,
         }
     }
-}
 
-/// Render an error with source context: source line and caret underline.
-/// Also renders help text and suggestions when attached via `with_help`.
-pub fn render_with_source(error: &CompileError, source: &str) -> String {
-    let mut out = if let Some(code) = error.code() {
-        format!("error[{code}]: {error}")
-    } else {
-        format!("error: {error}")
-    };
-
-    // Unwrap through WithHelp to get the underlying error for span rendering
-    let inner = error.inner();
-
-    // For LexError, use the span to find the source line
-    if let CompileError::LexError { span, .. } = inner {
-        if span.start <= source.len() {
-            let line_num = source[..span.start].chars().filter(|c| *c == '\n').count() + 1;
-            let line_start = source[..span.start].rfind('\n').map(|i| i + 1).unwrap_or(0);
-            let line_end = source[span.start..]
-                .find('\n')
-                .map(|i| span.start + i)
-                .unwrap_or(source.len());
-            let line = &source[line_start..line_end];
-            let col = span.start - line_start;
-            let underline_len = (span.end - span.start).max(1).min(line.len().saturating_sub(col));
-
-            out.push_str(&format!("\n --> line {line_num}:{col}"));
-            out.push_str(&format!("\n  {line_num} | {line}"));
-            out.push_str(&format!(
-                "\n  {} | {}{}",
-                " ".repeat(line_num.to_string().len()),
-                " ".repeat(col),
-                "^".repeat(underline_len),
-            ));
+    fn inner_mut(&mut self) -> &mut CompileError {
+        match self {
+            Self::WithHelp { inner, .. } => inner.inner_mut(),
+            other => other,
         }
     }
+}
 
-    // Append help text if present
-    if let Some(help) = error.help() {
-        out.push_str(&format!("\n  help: {help}"));
-    }
-
-    out
+/// Render an error with source context: source line and caret underline.
+/// Also renders help text, suggestions and notes when attached.
+pub fn render_with_source(error: &CompileError, source: &str) -> String {
+    render_diagnostic(&error.to_diagnostic(), source)
 }
 
 /// Render a `Diagnostic` with optional source context.
@@ -342,30 +627,19 @@ pub fn render_diagnostic(diag: &Diagnostic, source: &str) -> String {
         Severity::Warning => "warning",
         Severity::Note => "note",
     };
-    let mut out = format!("{prefix}: {}", diag.message);
+    let mut out = match diag.code {
+        Some(code) => format!("{prefix}[{code}]: {}", diag.message),
+        None => format!("{prefix}: {}", diag.message),
+    };
 
     // If we have a span, render the source context
-    if let Some(ref span) = diag.span {
-        if span.start <= source.len() {
-            let line_num = source[..span.start].chars().filter(|c| *c == '\n').count() + 1;
-            let line_start = source[..span.start].rfind('\n').map(|i| i + 1).unwrap_or(0);
-            let line_end = source[span.start..]
-                .find('\n')
-                .map(|i| span.start + i)
-                .unwrap_or(source.len());
-            let line = &source[line_start..line_end];
-            let col = span.start - line_start;
-            let underline_len = (span.end - span.start).max(1).min(line.len().saturating_sub(col));
-
-            out.push_str(&format!("\n --> line {line_num}:{col}"));
-            out.push_str(&format!("\n  {line_num} | {line}"));
-            out.push_str(&format!(
-                "\n  {} | {}{}",
-                " ".repeat(line_num.to_string().len()),
-                " ".repeat(col),
-                "^".repeat(underline_len),
-            ));
-        }
+    if let Some(span) = diag.span {
+        push_snippet(&mut out, span, source);
+    }
+
+    for note in &diag.notes {
+        out.push('\n');
+        out.push_str(&render_diagnostic(note, source));
     }
 
     // Append suggestion
@@ -381,6 +655,31 @@ pub fn render_diagnostic(diag: &Diagnostic, source: &str) -> String {
     out
 }
 
+/// Append the ` --> line:col`, source line and caret underline for `span`.
+fn push_snippet(out: &mut String, span: Span, source: &str) {
+    if span.start > source.len() {
+        return;
+    }
+    let line_num = source[..span.start].chars().filter(|c| *c == '\n').count() + 1;
+    let line_start = source[..span.start].rfind('\n').map(|i| i + 1).unwrap_or(0);
+    let line_end = source[span.start..]
+        .find('\n')
+        .map(|i| span.start + i)
+        .unwrap_or(source.len());
+    let line = &source[line_start..line_end];
+    let col = span.start - line_start;
+    let underline_len = (span.end - span.start).max(1).min(line.len().saturating_sub(col));
+
+    out.push_str(&format!("\n --> line {line_num}:{col}"));
+    out.push_str(&format!("\n  {line_num} | {line}"));
+    out.push_str(&format!(
+        "\n  {} | {}{}",
+        " ".repeat(line_num.to_string().len()),
+        " ".repeat(col),
+        "^".repeat(underline_len),
+    ));
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -516,4 +815,53 @@ mod tests {
         let rendered = render_with_source(&err, "");
         assert!(rendered.starts_with("error: "), "should not have brackets: {rendered}");
     }
+
+    #[test]
+    fn line_col_is_one_based() {
+        let src = "ab\ncd";
+        assert_eq!(Span::line_col(src, 0), (1, 1));
+        assert_eq!(Span::line_col(src, 4), (2, 2));
+    }
+
+    #[test]
+    fn to_diagnostic_keeps_code_span_and_fix() {
+        let err = CompileError::validation("unknown stage function: 'cicle'")
+            .with_code(ErrorCode::E001)
+            .with_fix(Span::new(0, 5), "circle")
+            .with_span(Span::new(0, 10))
+            .with_help("did you mean 'circle'?");
+        let diag = err.to_diagnostic();
+        assert_eq!(diag.message, "unknown stage function: 'cicle'");
+        assert_eq!(diag.code, Some(ErrorCode::E001));
+        assert_eq!(diag.span, Some(Span::new(0, 10)));
+        assert_eq!(diag.help.as_deref(), Some("did you mean 'circle'?"));
+        assert_eq!(diag.fix, Some(Fix { span: Span::new(0, 5), replacement: "circle".into() }));
+    }
+
+    #[test]
+    fn diagnostics_json_shape() {
+        let src = "layer {\n  cicle(0.3)\n}";
+        let diags: Diagnostics = [
+            Diagnostic::error("unknown stage function: 'cicle'")
+                .with_code(ErrorCode::E001)
+                .with_span(Span::new(10, 20))
+                .with_fix(Span::new(10, 15), "circle"),
+            Diagnostic::warning("unused").with_note(Diagnostic::note("here")),
+        ]
+        .into_iter()
+        .collect();
+        assert!(diags.has_errors());
+
+        let json = diags.to_json(src);
+        let first = &json[0];
+        assert_eq!(first["severity"], "error");
+        assert_eq!(first["code"], "E001");
+        assert_eq!(first["span"]["line_start"], 2);
+        assert_eq!(first["span"]["column_start"], 3);
+        assert_eq!(first["fix"]["replacement"], "circle");
+        assert_eq!(first["fix"]["span"]["end"], 15);
+        assert_eq!(json[1]["severity"], "warning");
+        assert!(json[1]["span"].is_null());
+        assert_eq!(json[1]["notes"][0]["message"], "here");
+    }
 }
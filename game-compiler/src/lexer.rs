@@ -11,7 +11,6 @@ use crate::token::Token;
 #[derive(Logos, Debug, Clone, PartialEq)]
 #[logos(skip r"[ \t\r\n]+")]
 #[logos(skip r"//[^\n]*")]
-#[logos(skip r"#[^\n]*")]
 enum LexToken {
     // ── Keywords ─────────────────────────────────────────
     #[token("cinematic")]
@@ -96,6 +95,10 @@ enum LexToken {
     Integer,
     #[regex(r#""([^"\\]|\\.)*""#)]
     StringLit,
+    /// `#` followed by 3, 4, 6 or 8 hex digits is a color; any other `#`
+    /// starts a comment.
+    #[token("#", hash)]
+    HexColor,
 
     // ── Identifiers ──────────────────────────────────────
     #[regex(r"[a-zA-Z_\x80-\xff][a-zA-Z0-9_\x80-\xff]*", priority = 1)]
@@ -112,6 +115,18 @@ enum LexToken {
     DotDot,
     #[token("->")]
     Arrow,
+    #[token(">=")]
+    GreaterEq,
+    #[token("<=")]
+    LessEq,
+    #[token("==")]
+    EqEq,
+    #[token("!=")]
+    NotEq,
+    #[token("&&")]
+    AndAnd,
+    #[token("||")]
+    OrOr,
 
     // ── Single-char operators ────────────────────────────
     #[token(">", priority = 1)]
@@ -120,6 +135,10 @@ enum LexToken {
     Less,
     #[token("?")]
     Question,
+    #[token("!")]
+    Bang,
+    #[token("%")]
+    Percent,
     #[token("|")]
     Pipe,
     #[token("~")]
@@ -158,6 +177,22 @@ enum LexToken {
     Dot,
 }
 
+/// Extend a `#` over a hex color, or skip the rest of the line as a comment.
+fn hash(lex: &mut logos::Lexer<LexToken>) -> logos::Filter<()> {
+    let rest = lex.remainder();
+    let word = rest
+        .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_' || !c.is_ascii()))
+        .unwrap_or(rest.len());
+    let is_hex = rest[..word].chars().all(|c| c.is_ascii_hexdigit());
+    if is_hex && matches!(word, 3 | 4 | 6 | 8) {
+        lex.bump(word);
+        logos::Filter::Emit(())
+    } else {
+        lex.bump(rest.find('\n').unwrap_or(rest.len()));
+        logos::Filter::Skip
+    }
+}
+
 /// Lex source text into a vector of `(Token, start, end)` triples.
 pub fn lex(source: &str) -> Result<Vec<(Token, usize, usize)>, CompileError> {
     let mut result = Vec::new();
@@ -283,6 +318,7 @@ fn convert(lt: LexToken, slice: &str) -> Result<Token, CompileError> {
 
         // Ident
         LexToken::Ident => Token::Ident(slice.to_string()),
+        LexToken::HexColor => Token::HexColor(slice.to_string()),
 
         // Operators
         LexToken::Greater => Token::Greater,
@@ -293,6 +329,14 @@ fn convert(lt: LexToken, slice: &str) -> Result<Token, CompileError> {
         LexToken::BangBang => Token::BangBang,
         LexToken::DotDot => Token::DotDot,
         LexToken::Arrow => Token::Arrow,
+        LexToken::GreaterEq => Token::GreaterEq,
+        LexToken::LessEq => Token::LessEq,
+        LexToken::EqEq => Token::EqEq,
+        LexToken::NotEq => Token::NotEq,
+        LexToken::AndAnd => Token::AndAnd,
+        LexToken::OrOr => Token::OrOr,
+        LexToken::Bang => Token::Bang,
+        LexToken::Percent => Token::Percent,
         LexToken::Pipe => Token::Pipe,
         LexToken::Tilde => Token::Tilde,
         LexToken::Plus => Token::Plus,
@@ -336,6 +380,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::approx_constant)]
     fn lex_numbers() {
         assert_eq!(tokens("42"), vec![Token::Integer(42)]);
         assert_eq!(tokens("3.14"), vec![Token::Float(3.14)]);
@@ -371,6 +416,15 @@ mod tests {
         ]);
     }
 
+    #[test]
+    fn lex_comparison_and_logic_operators() {
+        assert_eq!(tokens(">= <= == != && || ! % > < = !!"), vec![
+            Token::GreaterEq, Token::LessEq, Token::EqEq, Token::NotEq,
+            Token::AndAnd, Token::OrOr, Token::Bang, Token::Percent,
+            Token::Greater, Token::Less, Token::Eq, Token::BangBang,
+        ]);
+    }
+
     #[test]
     fn lex_full_layer() {
         let toks = tokens(r#"layer ring { circle(0.2) | glow(1.5) | tint(0.831, 0.686, 0.216) }"#);
@@ -388,6 +442,29 @@ mod tests {
         ]);
     }
 
+    #[test]
+    fn lex_hex_colors() {
+        assert_eq!(tokens("tint(#ff8800) #ABC #ff880080 #fa0c"), vec![
+            Token::Ident("tint".into()),
+            Token::LParen,
+            Token::HexColor("#ff8800".into()),
+            Token::RParen,
+            Token::HexColor("#ABC".into()),
+            Token::HexColor("#ff880080".into()),
+            Token::HexColor("#fa0c".into()),
+        ]);
+    }
+
+    #[test]
+    fn lex_hash_comments_skipped() {
+        // Anything but a lone hex word after '#' is a comment
+        let src = "foo # comment\n#faded out\n#ff88001 x\n#bad_name\n#deadbeef0\nbar";
+        assert_eq!(tokens(src), vec![
+            Token::Ident("foo".into()),
+            Token::Ident("bar".into()),
+        ]);
+    }
+
     #[test]
     fn lex_modulation() {
         let toks = tokens("radius: 0.3 ~ audio.bass * 0.1");
@@ -2,21 +2,31 @@ pub mod adapters;
 pub mod ast;
 pub mod builtins;
 pub mod codegen;
+pub mod cpu;
 pub mod error;
 pub mod lexer;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod lsp;
 pub mod optimize;
 pub mod parser;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod render;
 pub mod resolver;
 pub mod runtime;
 #[cfg(not(target_arch = "wasm32"))]
 pub mod server;
-#[cfg(feature = "snapshot")]
+#[cfg(not(target_arch = "wasm32"))]
+pub mod shader_check;
+#[cfg(not(target_arch = "wasm32"))]
 pub mod snapshot;
+pub mod source;
+pub mod stdlib;
 pub mod token;
 #[cfg(feature = "wasm")]
 pub mod wasm;
 
 use error::CompileError;
+pub use error::{Diagnostic, Diagnostics, Fix, Severity};
 
 // ── Configuration ────────────────────────────────────────
 
@@ -25,6 +35,9 @@ pub enum OutputFormat {
     Component,
     Html,
     Standalone,
+    /// A Web Component that renders on a worker via OffscreenCanvas, falling
+    /// back to the main thread where that's unavailable.
+    Worker,
 }
 
 #[derive(Debug, Clone)]
@@ -34,10 +47,34 @@ pub enum ShaderTarget {
     Both,
 }
 
+impl ShaderTarget {
+    /// Whether WGSL shaders and the WebGPU renderer are emitted.
+    pub fn includes_webgpu(&self) -> bool {
+        !matches!(self, Self::WebGl2)
+    }
+
+    /// Whether GLSL shaders and the WebGL2 renderer are emitted.
+    pub fn includes_webgl2(&self) -> bool {
+        !matches!(self, Self::WebGpu)
+    }
+}
+
+/// Optimization level (`-O0`, `-O1`, `-O2`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OptLevel {
+    /// No AST optimization passes.
+    O0,
+    /// Constant folding, no-op stage and dead define elimination.
+    O1,
+    /// O1 plus dead-uniform removal and shader minification.
+    O2,
+}
+
 #[derive(Debug, Clone)]
 pub struct CompileConfig {
     pub output_format: OutputFormat,
     pub target: ShaderTarget,
+    pub opt_level: OptLevel,
 }
 
 impl Default for CompileConfig {
@@ -45,6 +82,7 @@ impl Default for CompileConfig {
         Self {
             output_format: OutputFormat::Component,
             target: ShaderTarget::Both,
+            opt_level: OptLevel::O1,
         }
     }
 }
@@ -80,8 +118,9 @@ pub fn lex(source: &str) -> Result<Vec<(token::Token, usize, usize)>, CompileErr
 /// Run semantic analysis on a parsed program. Returns warnings (not errors).
 ///
 /// Checks define body validity and builtin arity without performing codegen.
-pub fn check(program: &ast::Program) -> Vec<String> {
-    let mut warnings = Vec::new();
+/// Warnings carry source spans; render them with [`error::render_diagnostic`].
+pub fn check(program: &ast::Program) -> Diagnostics {
+    let mut warnings = Diagnostics::new();
     for cin in &program.cinematics {
         warnings.extend(optimize::check_define_semantics(cin));
         warnings.extend(optimize::check_arity(cin));
@@ -89,6 +128,51 @@ pub fn check(program: &ast::Program) -> Vec<String> {
     warnings
 }
 
+/// Parse, resolve and validate `source` without codegen, collecting errors
+/// and warnings as [`Diagnostics`] rather than stopping at the first.
+///
+/// File imports resolve against the embedded stdlib only; see
+/// [`diagnose_with_provider`].
+pub fn diagnose(source: &str) -> Diagnostics {
+    diagnose_program(compile_to_ast(source).and_then(|mut program| {
+        resolver::resolve_stdlib_imports(&mut program)?;
+        Ok(program)
+    }))
+}
+
+/// Like [`diagnose`], but locates file imports through `provider`.
+pub fn diagnose_with_provider(
+    source: &str,
+    provider: &dyn source::SourceProvider,
+    base_dir: &std::path::Path,
+    lib_dirs: &[std::path::PathBuf],
+) -> Diagnostics {
+    diagnose_program(compile_to_ast(source).and_then(|mut program| {
+        resolver::resolve_imports_with(&mut program, provider, base_dir, lib_dirs)?;
+        Ok(program)
+    }))
+}
+
+/// Validation errors for each cinematic, then semantic warnings.
+fn diagnose_program(program: Result<ast::Program, CompileError>) -> Diagnostics {
+    let program = match program {
+        Ok(p) => p,
+        Err(e) => return e.into(),
+    };
+
+    let mut diags = Diagnostics::new();
+    for cinematic in &program.cinematics {
+        let mut cinematic = cinematic.clone();
+        let validated = codegen::analysis::expand_defines(&mut cinematic)
+            .and_then(|()| codegen::validate(&cinematic));
+        if let Err(e) = validated {
+            diags.push(e.to_diagnostic());
+        }
+    }
+    diags.extend(check(&program));
+    diags
+}
+
 /// Builtin function metadata (name, type signature, parameters).
 #[derive(Debug, Clone)]
 pub struct BuiltinInfo {
@@ -111,21 +195,63 @@ pub fn list_builtins() -> Vec<BuiltinInfo> {
         .collect()
 }
 
-/// Full compile pipeline: lex → parse → validate → codegen → runtime output.
+/// Full compile pipeline: lex → parse → resolve → validate → codegen → runtime output.
 ///
+/// File imports resolve against the embedded stdlib only; use
+/// [`compile_with_resolver`] to also search the filesystem.
 /// Returns one `CompileOutput` per cinematic in the program.
 pub fn compile(source: &str, config: &CompileConfig) -> Result<Vec<CompileOutput>, CompileError> {
     let mut program = compile_to_ast(source)?;
+    resolver::resolve_stdlib_imports(&mut program)?;
+    compile_program(program, config)
+}
 
-    // Expand defines before codegen
-    for cinematic in &mut program.cinematics {
-        let _ = codegen::analysis::expand_defines(cinematic);
+/// Like [`compile`], but resolves file imports relative to `base_dir`, then
+/// in each of `lib_dirs`, then in the embedded stdlib.
+pub fn compile_with_resolver(
+    source: &str,
+    config: &CompileConfig,
+    base_dir: &std::path::Path,
+    lib_dirs: &[std::path::PathBuf],
+) -> Result<Vec<CompileOutput>, CompileError> {
+    let mut program = compile_to_ast(source)?;
+    resolver::resolve_imports(&mut program, base_dir, lib_dirs)?;
+    compile_program(program, config)
+}
+
+/// Like [`compile`], but locates file imports through `provider` — an
+/// in-memory map, an overlay of unsaved buffers, or the filesystem.
+pub fn compile_with_provider(
+    source: &str,
+    config: &CompileConfig,
+    provider: &dyn source::SourceProvider,
+    base_dir: &std::path::Path,
+    lib_dirs: &[std::path::PathBuf],
+) -> Result<Vec<CompileOutput>, CompileError> {
+    let mut program = compile_to_ast(source)?;
+    resolver::resolve_imports_with(&mut program, provider, base_dir, lib_dirs)?;
+    compile_program(program, config)
+}
+
+/// Expand defines, then optimize (constant folding, strength reduction,
+/// no-op elimination, dead define elimination); O2 also drops dead uniforms.
+pub(crate) fn lower_cinematic(cinematic: &mut ast::Cinematic, opt_level: OptLevel) {
+    let _ = codegen::analysis::expand_defines(cinematic);
+    if opt_level != OptLevel::O0 {
+        optimize::optimize_cinematic(cinematic);
+        if opt_level == OptLevel::O2 {
+            optimize::eliminate_dead_uniforms(cinematic);
+        }
     }
+}
 
-    // Optimize each cinematic (constant folding, strength reduction, no-op elimination,
-    // dead define elimination, semantic analysis)
+/// Codegen for a program whose file imports have already been resolved.
+fn compile_program(
+    mut program: ast::Program,
+    config: &CompileConfig,
+) -> Result<Vec<CompileOutput>, CompileError> {
     for cinematic in &mut program.cinematics {
-        optimize::optimize_cinematic(cinematic);
+        lower_cinematic(cinematic, config.opt_level);
     }
 
     let mut outputs = Vec::new();
@@ -146,12 +272,15 @@ pub fn compile(source: &str, config: &CompileConfig) -> Result<Vec<CompileOutput
             adapters::ImportScheme::Camera { device_index } => {
                 import_modules.push(adapters::camera::generate_camera_adapter(device_index));
             }
-            adapters::ImportScheme::File => {} // standard file import — future work
+            adapters::ImportScheme::File => {} // merged by the resolver
         }
     }
 
     for cinematic in &program.cinematics {
         let mut shader = codegen::generate(cinematic)?;
+        if config.opt_level == OptLevel::O2 {
+            codegen::minify::minify_output(&mut shader);
+        }
 
         // Prepend import adapter modules so they're available to all cinematic JS
         let mut all_js = import_modules.clone();
@@ -159,25 +288,23 @@ pub fn compile(source: &str, config: &CompileConfig) -> Result<Vec<CompileOutput
         shader.js_modules = all_js;
 
         let js = match config.output_format {
-            OutputFormat::Component | OutputFormat::Standalone => {
-                runtime::component::generate_component(&shader)
-            }
-            OutputFormat::Html => {
-                runtime::component::generate_component(&shader)
+            OutputFormat::Worker => {
+                runtime::component::generate_worker_component(&shader, &config.target)
             }
+            _ => runtime::component::generate_component(&shader, &config.target),
         };
 
         let html = match config.output_format {
             OutputFormat::Html | OutputFormat::Standalone => {
-                Some(runtime::html::generate_html(&shader))
+                Some(runtime::html::generate_html(&shader, &config.target))
             }
-            OutputFormat::Component => None,
+            OutputFormat::Component | OutputFormat::Worker => None,
         };
 
         outputs.push(CompileOutput {
             name: shader.name.clone(),
-            wgsl: Some(shader.wgsl_fragment),
-            glsl: Some(shader.glsl_fragment),
+            wgsl: config.target.includes_webgpu().then_some(shader.wgsl_fragment),
+            glsl: config.target.includes_webgl2().then_some(shader.glsl_fragment),
             js,
             html,
         });
@@ -186,7 +313,7 @@ pub fn compile(source: &str, config: &CompileConfig) -> Result<Vec<CompileOutput
     // Apply project vertex overrides
     for proj in &program.projects {
         let custom_vert = codegen::project::generate_vertex_wgsl(&proj.mode);
-        if let Some(out) = outputs.iter_mut().find(|o| o.name == proj.source) {
+        if let Some(out) = outputs.iter_mut().find(|o| o.name == proj.source && o.wgsl.is_some()) {
             out.wgsl = Some(custom_vert);
         }
     }
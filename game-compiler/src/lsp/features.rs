@@ -0,0 +1,677 @@
+//! Editor features computed from document text and the parsed program.
+//!
+//! Completion and signature help scan the raw text around the cursor, since
+//! the document rarely parses while it is being typed. Hover, definitions
+//! and symbols use the last successful parse.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use lsp_types::{
+    CodeAction, CodeActionKind, CompletionItem, CompletionItemKind, Diagnostic as LspDiagnostic,
+    DiagnosticRelatedInformation, DiagnosticSeverity, DocumentSymbol, Documentation, Hover,
+    HoverContents, Location, MarkupContent, MarkupKind, NumberOrString, ParameterInformation,
+    ParameterLabel, Range, SignatureHelp, SignatureInformation, SymbolKind, TextEdit, Uri,
+    WorkspaceEdit,
+};
+
+use super::position::LineIndex;
+use crate::ast::{DefineBlock, Program, Span};
+use crate::builtins::{self, ShaderState};
+use crate::error::{Diagnostics, Severity};
+use crate::source::SourceProvider;
+
+/// Keywords valid at the top level of a file.
+const TOP_LEVEL_KEYWORDS: &[&str] = &["cinematic", "import", "breed", "project"];
+
+/// Keywords valid inside a `cinematic` block.
+const CINEMATIC_KEYWORDS: &[&str] = &[
+    "layer", "define", "lens", "arc", "resonate", "listen", "voice", "score", "gravity", "react",
+];
+
+// ── Diagnostics ─────────────────────────────────────────
+
+/// Convert compiler diagnostics to LSP diagnostics for `uri`.
+pub fn to_lsp_diagnostics(text: &str, diags: &Diagnostics, uri: &Uri) -> Vec<LspDiagnostic> {
+    let index = LineIndex::new(text);
+    diags
+        .iter()
+        .map(|d| {
+            let related: Vec<DiagnosticRelatedInformation> = d
+                .notes
+                .iter()
+                .filter_map(|n| {
+                    Some(DiagnosticRelatedInformation {
+                        location: Location::new(uri.clone(), index.range(n.span?)),
+                        message: n.message.clone(),
+                    })
+                })
+                .collect();
+            let message = match &d.help {
+                Some(help) => format!("{}\nhelp: {help}", d.message),
+                None => d.message.clone(),
+            };
+            LspDiagnostic {
+                range: d.span.map_or_else(Range::default, |s| index.range(s)),
+                severity: Some(severity(d.severity)),
+                code: d.code.map(|c| NumberOrString::String(c.to_string())),
+                source: Some("game".into()),
+                message,
+                related_information: (!related.is_empty()).then_some(related),
+                ..Default::default()
+            }
+        })
+        .collect()
+}
+
+fn severity(severity: Severity) -> DiagnosticSeverity {
+    match severity {
+        Severity::Error => DiagnosticSeverity::ERROR,
+        Severity::Warning => DiagnosticSeverity::WARNING,
+        Severity::Note => DiagnosticSeverity::INFORMATION,
+    }
+}
+
+/// Quick fixes for diagnostics with a machine-applicable fix overlapping `range`.
+pub fn code_actions(text: &str, diags: &Diagnostics, uri: &Uri, range: Range) -> Vec<CodeAction> {
+    let index = LineIndex::new(text);
+    let lsp_diags = to_lsp_diagnostics(text, diags, uri);
+    diags
+        .iter()
+        .zip(lsp_diags)
+        .filter_map(|(d, lsp_diag)| {
+            let fix = d.fix.as_ref()?;
+            let fix_range = index.range(fix.span);
+            if fix_range.end < range.start || range.end < fix_range.start {
+                return None;
+            }
+            let edit = TextEdit::new(fix_range, fix.replacement.clone());
+            Some(CodeAction {
+                title: format!("Replace with '{}'", fix.replacement),
+                kind: Some(CodeActionKind::QUICKFIX),
+                diagnostics: Some(vec![lsp_diag]),
+                edit: Some(WorkspaceEdit {
+                    changes: Some(HashMap::from([(uri.clone(), vec![edit])])),
+                    ..Default::default()
+                }),
+                is_preferred: Some(true),
+                ..Default::default()
+            })
+        })
+        .collect()
+}
+
+// ── Hover ───────────────────────────────────────────────
+
+/// Docs for the builtin or define under the cursor.
+pub fn hover(text: &str, offset: usize, defines: &[DefineBlock]) -> Option<Hover> {
+    let (span, word) = word_at(text, offset)?;
+    let value = if let Some(b) = builtins::lookup(word) {
+        format!(
+            "```game\n{}\n```\n{} → {} — {}",
+            b.signature(),
+            b.input,
+            b.output,
+            b.kind()
+        )
+    } else {
+        let define = defines.iter().find(|d| d.name == word)?;
+        let mut value = format!("```game\ndefine {}({})\n```", define.name, define.params.join(", "));
+        if let Some((input, output)) = stage_states(&define.name, defines) {
+            value.push_str(&format!("\n{input} → {output}"));
+        }
+        value
+    };
+    Some(Hover {
+        contents: HoverContents::Markup(MarkupContent { kind: MarkupKind::Markdown, value }),
+        range: Some(LineIndex::new(text).range(span)),
+    })
+}
+
+// ── Completion ──────────────────────────────────────────
+
+/// What the cursor is positioned to type.
+#[derive(Debug, PartialEq)]
+enum Context<'a> {
+    /// Next stage of a pipe chain currently in `state`.
+    Stage(ShaderState),
+    /// An entry of a lens `post: [...]` list (Color → Color only).
+    PostStage,
+    /// An argument of `callee`; `active` counts preceding commas.
+    Args { callee: &'a str, active: usize },
+    /// A block keyword.
+    Keyword(&'static [&'static str]),
+}
+
+/// Completion items at `offset`: stages that accept the pipeline state at
+/// the cursor (exact matches first), or block keywords.
+pub fn completion(text: &str, offset: usize, defines: &[DefineBlock]) -> Vec<CompletionItem> {
+    match context_at(text, offset, defines) {
+        Some(Context::Stage(state)) => {
+            let mut items: Vec<CompletionItem> = builtins::BUILTINS
+                .iter()
+                .filter(|b| b.accepts(state))
+                .map(|b| builtin_item(b, b.input == state))
+                .collect();
+            for d in defines {
+                let Some((input, output)) = stage_states(&d.name, defines) else { continue };
+                if input == state || input == ShaderState::Position {
+                    items.push(CompletionItem {
+                        label: d.name.clone(),
+                        kind: Some(CompletionItemKind::FUNCTION),
+                        detail: Some(format!(
+                            "define {}({}) — {input} → {output}",
+                            d.name,
+                            d.params.join(", ")
+                        )),
+                        sort_text: Some(sort_key(input == state, &d.name)),
+                        ..Default::default()
+                    });
+                }
+            }
+            items
+        }
+        Some(Context::PostStage) => builtins::BUILTINS
+            .iter()
+            .filter(|b| b.input == ShaderState::Color && b.output == ShaderState::Color)
+            .map(|b| builtin_item(b, true))
+            .collect(),
+        Some(Context::Keyword(keywords)) => keywords
+            .iter()
+            .map(|k| CompletionItem {
+                label: k.to_string(),
+                kind: Some(CompletionItemKind::KEYWORD),
+                ..Default::default()
+            })
+            .collect(),
+        Some(Context::Args { .. }) | None => Vec::new(),
+    }
+}
+
+fn builtin_item(b: &builtins::BuiltinFn, exact: bool) -> CompletionItem {
+    CompletionItem {
+        label: b.name.to_string(),
+        kind: Some(CompletionItemKind::FUNCTION),
+        detail: Some(format!("{} → {}", b.input, b.output)),
+        documentation: Some(Documentation::String(format!("{} — {}", b.signature(), b.kind()))),
+        sort_text: Some(sort_key(exact, b.name)),
+        ..Default::default()
+    }
+}
+
+/// Stages that continue the current state sort before state resets.
+fn sort_key(exact: bool, name: &str) -> String {
+    format!("{}{name}", if exact { 0 } else { 1 })
+}
+
+/// Signature of the builtin whose argument list contains the cursor.
+pub fn signature_help(text: &str, offset: usize, defines: &[DefineBlock]) -> Option<SignatureHelp> {
+    let Some(Context::Args { callee, active }) = context_at(text, offset, defines) else {
+        return None;
+    };
+    let (label, params): (String, Vec<String>) = if let Some(b) = builtins::lookup(callee) {
+        (b.signature(), b.params.iter().map(|p| p.name.to_string()).collect())
+    } else {
+        let d = defines.iter().find(|d| d.name == callee)?;
+        (format!("{}({})", d.name, d.params.join(", ")), d.params.clone())
+    };
+    Some(SignatureHelp {
+        signatures: vec![SignatureInformation {
+            label,
+            documentation: None,
+            parameters: Some(
+                params
+                    .into_iter()
+                    .map(|p| ParameterInformation {
+                        label: ParameterLabel::Simple(p),
+                        documentation: None,
+                    })
+                    .collect(),
+            ),
+            active_parameter: None,
+        }],
+        active_signature: Some(0),
+        active_parameter: Some(active as u32),
+    })
+}
+
+/// Classify the cursor position by scanning backwards to the start of the
+/// enclosing stage chain, argument list or block.
+fn context_at<'a>(text: &'a str, offset: usize, defines: &[DefineBlock]) -> Option<Context<'a>> {
+    let bytes = text.as_bytes();
+    let offset = offset.min(bytes.len());
+    let (mut parens, mut brackets) = (0usize, 0usize);
+    let mut commas = 0;
+    let mut last_comma = None;
+    let mut i = offset;
+    while i > 0 {
+        i -= 1;
+        let top = parens == 0 && brackets == 0;
+        match bytes[i] {
+            b')' => parens += 1,
+            b']' => brackets += 1,
+            b'(' if parens == 0 => {
+                let callee = ident_before(text, i)?;
+                return Some(Context::Args { callee, active: commas });
+            }
+            b'(' => parens -= 1,
+            b'[' if top => {
+                let start = last_comma.unwrap_or(i) + 1;
+                return is_prefix(&text[start..offset]).then_some(Context::PostStage);
+            }
+            b'[' => brackets = brackets.saturating_sub(1),
+            b',' if top => {
+                commas += 1;
+                last_comma.get_or_insert(i);
+            }
+            b':' if top => return chain_context(&text[i + 1..offset], defines),
+            b'{' | b'}' | b';' if top => {
+                return match block_header(text, i) {
+                    None => is_prefix(&text[i + 1..offset])
+                        .then_some(Context::Keyword(TOP_LEVEL_KEYWORDS)),
+                    Some("cinematic") => is_prefix(&text[i + 1..offset])
+                        .then_some(Context::Keyword(CINEMATIC_KEYWORDS)),
+                    Some("layer" | "define") => chain_context(&text[i + 1..offset], defines),
+                    Some(_) => None,
+                };
+            }
+            _ => {}
+        }
+    }
+    is_prefix(&text[..offset]).then_some(Context::Keyword(TOP_LEVEL_KEYWORDS))
+}
+
+/// State of a pipe chain whose text (from its start to the cursor) is `chain`.
+fn chain_context<'a>(chain: &str, defines: &[DefineBlock]) -> Option<Context<'a>> {
+    let segments = split_top_level(chain, b'|');
+    let (partial, done) = segments.split_last()?;
+    if !is_prefix(partial) {
+        return None;
+    }
+    let mut state = ShaderState::Position;
+    for seg in done {
+        let name = seg.trim_start();
+        let end = name.find(|c: char| !is_ident_char(c)).unwrap_or(name.len());
+        if let Some((_, output)) = stage_states(&name[..end], defines) {
+            state = output;
+        }
+    }
+    Some(Context::Stage(state))
+}
+
+/// Split on `sep` outside parentheses and brackets.
+fn split_top_level(text: &str, sep: u8) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut depth = 0i32;
+    let mut start = 0;
+    for (i, &b) in text.as_bytes().iter().enumerate() {
+        match b {
+            b'(' | b'[' => depth += 1,
+            b')' | b']' => depth -= 1,
+            _ if b == sep && depth == 0 => {
+                parts.push(&text[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    parts.push(&text[start..]);
+    parts
+}
+
+/// First word of the header of the block whose `{` encloses `at`, or
+/// `None` at the top level.
+fn block_header(text: &str, at: usize) -> Option<&str> {
+    let bytes = text.as_bytes();
+    let mut depth = 0usize;
+    let mut i = at + 1;
+    let open = loop {
+        if i == 0 {
+            return None;
+        }
+        i -= 1;
+        match bytes[i] {
+            b'}' => depth += 1,
+            b'{' if depth == 0 => break i,
+            b'{' => depth -= 1,
+            _ => {}
+        }
+    };
+    // The header runs from the previous block boundary or line start to `{`
+    let head_start = text[..open]
+        .rfind(['{', '}', ';', '\n'])
+        .map_or(0, |p| p + 1);
+    text[head_start..open].split_whitespace().next()
+}
+
+/// `(input, output)` states of a builtin or define; defines take the input
+/// of their first stage and the output of their last.
+fn stage_states(name: &str, defines: &[DefineBlock]) -> Option<(ShaderState, ShaderState)> {
+    fn go(name: &str, defines: &[DefineBlock], depth: usize) -> Option<(ShaderState, ShaderState)> {
+        if let Some(b) = builtins::lookup(name) {
+            return Some((b.input, b.output));
+        }
+        if depth == 0 {
+            return None;
+        }
+        let d = defines.iter().find(|d| d.name == name)?;
+        let (input, _) = go(&d.body.first()?.name, defines, depth - 1)?;
+        let (_, output) = go(&d.body.last()?.name, defines, depth - 1)?;
+        Some((input, output))
+    }
+    go(name, defines, 16)
+}
+
+fn is_ident_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_'
+}
+
+/// Whitespace followed by an (possibly empty) identifier.
+fn is_prefix(text: &str) -> bool {
+    let word = text.trim_start();
+    word.chars().all(is_ident_char) && !word.starts_with(|c: char| c.is_ascii_digit())
+}
+
+/// Byte offset where the identifier ending `text` starts.
+fn ident_start(text: &str) -> usize {
+    text.char_indices()
+        .rev()
+        .find(|&(_, c)| !is_ident_char(c))
+        .map_or(0, |(p, c)| p + c.len_utf8())
+}
+
+fn ident_before(text: &str, end: usize) -> Option<&str> {
+    let head = text[..end].trim_end();
+    let ident = &head[ident_start(head)..];
+    (!ident.is_empty()).then_some(ident)
+}
+
+/// The identifier touching `offset`, with its span.
+pub fn word_at(text: &str, offset: usize) -> Option<(Span, &str)> {
+    let offset = offset.min(text.len());
+    if !text.is_char_boundary(offset) {
+        return None;
+    }
+    let start = ident_start(&text[..offset]);
+    let end = text[offset..]
+        .find(|c: char| !is_ident_char(c))
+        .map_or(text.len(), |p| offset + p);
+    (start < end).then(|| (Span::new(start, end), &text[start..end]))
+}
+
+// ── Definitions ─────────────────────────────────────────
+
+/// Where a definition lives: `file` is `None` for the current document.
+#[derive(Debug, PartialEq)]
+pub struct Target {
+    pub file: Option<PathBuf>,
+    pub range: Range,
+}
+
+/// Resolve the define or import path under the cursor. `program` is the
+/// document parsed without resolving imports.
+pub fn definition(
+    text: &str,
+    offset: usize,
+    program: &Program,
+    provider: &dyn SourceProvider,
+    base_dir: &Path,
+    lib_dirs: &[PathBuf],
+) -> Option<Target> {
+    // `import "path"` → the imported file
+    for import in &program.imports {
+        if import.span.start <= offset && offset < import.span.end {
+            let file = locate(provider, base_dir, lib_dirs, &import.path)?;
+            return Some(Target { file: Some(file), range: Range::default() });
+        }
+    }
+
+    let (_, word) = word_at(text, offset)?;
+    let local = program.cinematics.iter().flat_map(|c| &c.defines).find(|d| d.name == word);
+    if let Some(d) = local {
+        return Some(Target { file: None, range: LineIndex::new(text).range(d.span) });
+    }
+    find_imported_define(word, program, provider, base_dir, lib_dirs, 8)
+}
+
+fn find_imported_define(
+    name: &str,
+    program: &Program,
+    provider: &dyn SourceProvider,
+    base_dir: &Path,
+    lib_dirs: &[PathBuf],
+    depth: usize,
+) -> Option<Target> {
+    if depth == 0 {
+        return None;
+    }
+    program.imports.iter().find_map(|import| {
+        let file = locate(provider, base_dir, lib_dirs, &import.path)?;
+        let source = provider.read(&file).ok()?;
+        let imported = crate::parse(&source).ok()?;
+        let found = imported.cinematics.iter().flat_map(|c| &c.defines).find(|d| d.name == name);
+        match found {
+            Some(d) => {
+                let range = LineIndex::new(&source).range(d.span);
+                Some(Target { file: Some(file), range })
+            }
+            None => {
+                let dir = file.parent().unwrap_or(Path::new(""));
+                find_imported_define(name, &imported, provider, dir, lib_dirs, depth - 1)
+            }
+        }
+    })
+}
+
+/// Same search order as the resolver, minus the embedded stdlib (which has
+/// no file to open).
+fn locate(
+    provider: &dyn SourceProvider,
+    base_dir: &Path,
+    lib_dirs: &[PathBuf],
+    path: &str,
+) -> Option<PathBuf> {
+    std::iter::once(base_dir)
+        .chain(lib_dirs.iter().map(PathBuf::as_path))
+        .find_map(|dir| provider.locate(dir, path))
+}
+
+// ── Symbols ─────────────────────────────────────────────
+
+/// Outline: cinematics containing their layers, defines and lenses.
+#[allow(deprecated)] // `DocumentSymbol::deprecated` must still be initialized
+pub fn document_symbols(text: &str, program: &Program) -> Vec<DocumentSymbol> {
+    let index = LineIndex::new(text);
+    let symbol = |name: String, kind: SymbolKind, span: Span, children| DocumentSymbol {
+        name,
+        detail: None,
+        kind,
+        tags: None,
+        deprecated: None,
+        range: index.range(span),
+        selection_range: index.range(span),
+        children,
+    };
+    program
+        .cinematics
+        .iter()
+        .filter(|c| !c.span.is_empty())
+        .map(|c| {
+            let mut children: Vec<DocumentSymbol> = Vec::new();
+            children.extend(
+                c.layers
+                    .iter()
+                    .map(|l| symbol(l.name.clone(), SymbolKind::OBJECT, l.span, None)),
+            );
+            children.extend(
+                c.defines
+                    .iter()
+                    .filter(|d| !d.span.is_empty() && d.span.start >= c.span.start)
+                    .map(|d| symbol(d.name.clone(), SymbolKind::FUNCTION, d.span, None)),
+            );
+            children.extend(c.lenses.iter().map(|l| {
+                let name = l.name.clone().unwrap_or_else(|| "lens".into());
+                symbol(name, SymbolKind::OBJECT, l.span, None)
+            }));
+            children.sort_by_key(|s| (s.range.start.line, s.range.start.character));
+            symbol(c.name.clone(), SymbolKind::MODULE, c.span, Some(children))
+        })
+        .collect()
+}
+
+/// Defines visible in `program` (local and imported), for completion and hover.
+pub fn all_defines(program: &Program) -> Vec<DefineBlock> {
+    program.cinematics.iter().flat_map(|c| c.defines.iter().cloned()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn defines(src: &str) -> Vec<DefineBlock> {
+        all_defines(&crate::parse(src).unwrap())
+    }
+
+    fn labels(items: &[CompletionItem]) -> Vec<&str> {
+        items.iter().map(|i| i.label.as_str()).collect()
+    }
+
+    /// Split `src` at the `$` cursor marker.
+    fn at(src: &str) -> (String, usize) {
+        let offset = src.find('$').unwrap();
+        (src.replacen('$', "", 1), offset)
+    }
+
+    #[test]
+    fn completion_follows_pipeline_state() {
+        let (text, offset) = at("cinematic \"t\" { layer a { circle(0.3) | $ } }");
+        let items = completion(&text, offset, &[]);
+        let names = labels(&items);
+        assert!(names.contains(&"glow"));
+        assert!(names.contains(&"onion"));
+        assert!(!names.contains(&"tint"), "Color stage offered in Sdf state");
+
+        let glow = items.iter().find(|i| i.label == "glow").unwrap();
+        let circle = items.iter().find(|i| i.label == "circle").unwrap();
+        assert!(glow.sort_text < circle.sort_text, "state-preserving stages sort first");
+    }
+
+    #[test]
+    fn completion_after_color_stage_and_in_post() {
+        let (text, offset) = at("cinematic \"t\" { layer a { fn: circle(0.3) | glow(2.0) | t$ } }");
+        assert!(labels(&completion(&text, offset, &[])).contains(&"tint"));
+
+        let (text, offset) = at("cinematic \"t\" { lens { post: [bloom(0.5), $] } }");
+        let names = labels(&completion(&text, offset, &[])).join(",");
+        assert!(names.contains("vignette") && !names.contains("circle"), "{names}");
+    }
+
+    #[test]
+    fn completion_uses_define_states() {
+        let src = "cinematic \"t\" { define halo(r) { circle(r) | glow(2.0) } layer a { halo(0.2) | $ } }";
+        let defs = defines("cinematic \"t\" { define halo(r) { circle(r) | glow(2.0) } }");
+        let (text, offset) = at(src);
+        let names = labels(&completion(&text, offset, &defs)).join(",");
+        assert!(names.contains("tint") && !names.contains("glow"), "{names}");
+        assert!(names.contains("halo"), "Position-input define offered: {names}");
+    }
+
+    #[test]
+    fn completion_offers_keywords_in_blocks() {
+        let (text, offset) = at("cinematic \"t\" {\n  la$\n}");
+        assert!(labels(&completion(&text, offset, &[])).contains(&"layer"));
+        let (text, offset) = at("imp$");
+        assert!(labels(&completion(&text, offset, &[])).contains(&"import"));
+    }
+
+    #[test]
+    fn no_completion_inside_arguments() {
+        let (text, offset) = at("cinematic \"t\" { layer a { circle($) } }");
+        assert!(completion(&text, offset, &[]).is_empty());
+    }
+
+    #[test]
+    fn signature_help_tracks_active_parameter() {
+        let (text, offset) = at("cinematic \"t\" { layer a { ring(0.3, $) } }");
+        let help = signature_help(&text, offset, &[]).unwrap();
+        assert_eq!(help.signatures[0].label, "ring(radius = 0.3, width = 0.02)");
+        assert_eq!(help.active_parameter, Some(1));
+    }
+
+    #[test]
+    fn hover_shows_builtin_signature() {
+        let text = "cinematic \"t\" { layer a { circle(0.3) | glow(2.0) } }";
+        let offset = text.find("glow").unwrap() + 2;
+        let Some(Hover { contents: HoverContents::Markup(m), .. }) = hover(text, offset, &[]) else {
+            panic!("no hover");
+        };
+        assert!(m.value.contains("glow(intensity = 1.5)"), "{}", m.value);
+        assert!(m.value.contains("Sdf → Color"), "{}", m.value);
+    }
+
+    #[test]
+    fn non_ascii_text_does_not_panic() {
+        let text = "// résumé\ncinematic \"t\" { layer a { circle(0.3) | glow(2.0) } }";
+        for offset in (0..=text.len()).filter(|&i| text.is_char_boundary(i)) {
+            hover(text, offset, &[]);
+            completion(text, offset, &[]);
+            signature_help(text, offset, &[]);
+        }
+        let (span, word) = word_at(text, text.find("sumé").unwrap()).unwrap();
+        assert_eq!((span, word), (Span::new(6, 9), "sum"));
+
+        let (text, offset) = at("cinematic \"t\" { layer a { ring(é(0.3, $) } }");
+        assert!(signature_help(&text, offset, &[]).is_none());
+    }
+
+    #[test]
+    fn definition_finds_local_define() {
+        let text = "cinematic \"t\" {\n  define halo(r) { circle(r) | glow(2.0) }\n  layer a { halo(0.3) }\n}";
+        let program = crate::parse(text).unwrap();
+        let offset = text.rfind("halo").unwrap();
+        let provider = crate::source::MemoryProvider::new();
+        let target = definition(text, offset, &program, &provider, Path::new(""), &[]).unwrap();
+        assert_eq!(target.file, None);
+        assert_eq!(target.range.start.line, 1);
+    }
+
+    #[test]
+    fn definition_follows_imports() {
+        let lib = "cinematic \"lib\" {\n\n  define dot(r) { circle(r) | glow(1.0) }\n}";
+        let provider = crate::source::MemoryProvider::new().with_file("/p/lib.game", lib);
+        let text = "import \"lib.game\" expose dot\ncinematic \"t\" { layer a { dot(0.1) } }";
+        let program = crate::parse(text).unwrap();
+
+        let offset = text.rfind("dot").unwrap();
+        let target = definition(text, offset, &program, &provider, Path::new("/p"), &[]).unwrap();
+        assert_eq!(target.file, Some(PathBuf::from("/p/lib.game")));
+        assert_eq!(target.range.start.line, 2);
+
+        let on_path = definition(text, 9, &program, &provider, Path::new("/p"), &[]).unwrap();
+        assert_eq!(on_path.range, Range::default());
+    }
+
+    #[test]
+    fn symbols_nest_layers_under_cinematics() {
+        let text = "cinematic \"t\" {\n  layer a { circle(0.3) | glow(2.0) }\n  layer b { ring(0.3, 0.01) | glow(1.0) }\n}";
+        let symbols = document_symbols(text, &crate::parse(text).unwrap());
+        assert_eq!(symbols.len(), 1);
+        let children: Vec<&str> =
+            symbols[0].children.as_ref().unwrap().iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(children, ["a", "b"]);
+    }
+
+    #[test]
+    fn diagnostics_carry_code_and_fix() {
+        let text = "cinematic \"t\" { layer a { cicle(0.3) | glow(2.0) } }";
+        let diags = crate::diagnose(text);
+        let uri: Uri = "file:///t.game".parse().unwrap();
+        let lsp = to_lsp_diagnostics(text, &diags, &uri);
+        assert_eq!(lsp[0].code, Some(NumberOrString::String("E001".into())));
+
+        let actions = code_actions(text, &diags, &uri, lsp[0].range);
+        assert_eq!(actions.len(), 1);
+        let edits = &actions[0].edit.as_ref().unwrap().changes.as_ref().unwrap()[&uri];
+        assert_eq!(edits[0].new_text, "circle");
+    }
+}
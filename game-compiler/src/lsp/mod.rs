@@ -0,0 +1,378 @@
+//! Language server for `.game` files (`game lsp`), speaking LSP over stdio.
+//!
+//! Documents use full-text sync. Every change re-runs
+//! [`crate::diagnose_with_provider`] for each open document with all open
+//! buffers layered over the filesystem, so a file's diagnostics reflect
+//! unsaved edits to the files it imports.
+
+mod features;
+mod position;
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::path::{Path, PathBuf};
+
+use lsp_server::{Connection, ErrorCode, Message, Notification, Request, Response};
+use lsp_types::notification::{
+    DidChangeTextDocument, DidCloseTextDocument, DidOpenTextDocument,
+    Notification as LspNotification, PublishDiagnostics,
+};
+use lsp_types::request::{
+    CodeActionRequest, Completion, DocumentSymbolRequest, GotoDefinition, HoverRequest,
+    Request as LspRequest, SignatureHelpRequest,
+};
+use lsp_types::{
+    CodeActionOrCommand, CodeActionParams, CodeActionProviderCapability, CompletionOptions,
+    CompletionParams, CompletionResponse, DocumentSymbolParams, DocumentSymbolResponse,
+    GotoDefinitionParams, GotoDefinitionResponse, Hover, HoverParams, HoverProviderCapability,
+    Location, OneOf, PublishDiagnosticsParams, ServerCapabilities, SignatureHelp,
+    SignatureHelpOptions, SignatureHelpParams, TextDocumentPositionParams,
+    TextDocumentSyncCapability, TextDocumentSyncKind, Uri,
+};
+
+use crate::ast::{DefineBlock, Program};
+use crate::source::{FsProvider, MemoryProvider, OverlayProvider};
+
+use self::position::LineIndex;
+
+/// Run the language server on stdin/stdout until the client exits.
+/// `lib_dirs` are extra import search directories, as for `game check`.
+pub fn run(lib_dirs: Vec<PathBuf>) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let (connection, io_threads) = Connection::stdio();
+    serve(&connection, lib_dirs)?;
+    // The writer thread exits once the last sender is gone
+    drop(connection);
+    io_threads.join()?;
+    Ok(())
+}
+
+/// Initialize and serve one client over `connection`.
+pub fn serve(
+    connection: &Connection,
+    lib_dirs: Vec<PathBuf>,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    connection.initialize(serde_json::to_value(capabilities())?)?;
+    let mut server = Server { docs: HashMap::new(), lib_dirs };
+
+    for msg in &connection.receiver {
+        match msg {
+            Message::Request(req) => {
+                if connection.handle_shutdown(&req)? {
+                    break;
+                }
+                connection.sender.send(server.handle_request(req).into())?;
+            }
+            Message::Notification(note) => {
+                for out in server.handle_notification(note) {
+                    connection.sender.send(out.into())?;
+                }
+            }
+            Message::Response(_) => {}
+        }
+    }
+    Ok(())
+}
+
+fn capabilities() -> ServerCapabilities {
+    ServerCapabilities {
+        text_document_sync: Some(TextDocumentSyncCapability::Kind(TextDocumentSyncKind::FULL)),
+        hover_provider: Some(HoverProviderCapability::Simple(true)),
+        completion_provider: Some(CompletionOptions {
+            trigger_characters: Some(vec!["|".into(), "[".into()]),
+            ..Default::default()
+        }),
+        signature_help_provider: Some(SignatureHelpOptions {
+            trigger_characters: Some(vec!["(".into(), ",".into()]),
+            ..Default::default()
+        }),
+        definition_provider: Some(OneOf::Left(true)),
+        document_symbol_provider: Some(OneOf::Left(true)),
+        code_action_provider: Some(CodeActionProviderCapability::Simple(true)),
+        ..Default::default()
+    }
+}
+
+struct Document {
+    text: String,
+    /// Last successful parse with imports resolved; kept while the text
+    /// has syntax errors so completion and hover still see its defines.
+    resolved: Option<Program>,
+}
+
+struct Server {
+    docs: HashMap<Uri, Document>,
+    lib_dirs: Vec<PathBuf>,
+}
+
+impl Server {
+    fn handle_request(&mut self, req: Request) -> Response {
+        let id = req.id.clone();
+        let result = match req.method.as_str() {
+            HoverRequest::METHOD => self.call::<HoverRequest>(req, Self::hover),
+            Completion::METHOD => self.call::<Completion>(req, Self::completion),
+            SignatureHelpRequest::METHOD => {
+                self.call::<SignatureHelpRequest>(req, Self::signature_help)
+            }
+            GotoDefinition::METHOD => self.call::<GotoDefinition>(req, Self::definition),
+            DocumentSymbolRequest::METHOD => self.call::<DocumentSymbolRequest>(req, Self::symbols),
+            CodeActionRequest::METHOD => self.call::<CodeActionRequest>(req, Self::code_actions),
+            method => {
+                return Response::new_err(
+                    id,
+                    ErrorCode::MethodNotFound as i32,
+                    format!("unhandled method: {method}"),
+                )
+            }
+        };
+        match result {
+            Ok(value) => Response::new_ok(id, value),
+            Err(e) => Response::new_err(id, ErrorCode::InvalidParams as i32, e),
+        }
+    }
+
+    fn call<R: LspRequest>(
+        &self,
+        req: Request,
+        handler: fn(&Self, R::Params) -> R::Result,
+    ) -> Result<serde_json::Value, String> {
+        let params = serde_json::from_value(req.params).map_err(|e| e.to_string())?;
+        serde_json::to_value(handler(self, params)).map_err(|e| e.to_string())
+    }
+
+    fn handle_notification(&mut self, note: Notification) -> Vec<Notification> {
+        match note.method.as_str() {
+            DidOpenTextDocument::METHOD => {
+                let Some(p) = params::<DidOpenTextDocument>(note) else { return Vec::new() };
+                let doc = Document { text: p.text_document.text, resolved: None };
+                self.docs.insert(p.text_document.uri, doc);
+            }
+            DidChangeTextDocument::METHOD => {
+                let Some(p) = params::<DidChangeTextDocument>(note) else { return Vec::new() };
+                let doc = self.docs.get_mut(&p.text_document.uri);
+                if let (Some(doc), Some(change)) = (doc, p.content_changes.into_iter().last()) {
+                    doc.text = change.text;
+                }
+            }
+            DidCloseTextDocument::METHOD => {
+                let Some(p) = params::<DidCloseTextDocument>(note) else { return Vec::new() };
+                self.docs.remove(&p.text_document.uri);
+                return vec![publish(p.text_document.uri, Vec::new())];
+            }
+            _ => return Vec::new(),
+        }
+        self.refresh()
+    }
+
+    /// Re-check every open document; imports may have changed under it.
+    fn refresh(&mut self) -> Vec<Notification> {
+        let provider = self.provider();
+        let mut out = Vec::new();
+        for (uri, doc) in &mut self.docs {
+            let base = base_dir(uri);
+            let diags = crate::diagnose_with_provider(&doc.text, &provider, &base, &self.lib_dirs);
+            let resolved = crate::parse(&doc.text).and_then(|mut program| {
+                let lib_dirs = &self.lib_dirs;
+                crate::resolver::resolve_imports_with(&mut program, &provider, &base, lib_dirs)?;
+                Ok(program)
+            });
+            if let Ok(program) = resolved {
+                doc.resolved = Some(program);
+            }
+            out.push(publish(uri.clone(), features::to_lsp_diagnostics(&doc.text, &diags, uri)));
+        }
+        out
+    }
+
+    /// Open buffers over the filesystem.
+    fn provider(&self) -> OverlayProvider {
+        let mut open = MemoryProvider::new();
+        for (uri, doc) in &self.docs {
+            if let Some(path) = uri_to_path(uri) {
+                open.insert(path, doc.text.as_str());
+            }
+        }
+        OverlayProvider::new().layer(open).layer(FsProvider)
+    }
+
+    /// Document text, byte offset of the position, and defines in scope.
+    fn locate(&self, pos: &TextDocumentPositionParams) -> Option<(&Document, usize, Vec<DefineBlock>)> {
+        let doc = self.docs.get(&pos.text_document.uri)?;
+        let offset = LineIndex::new(&doc.text).offset(pos.position);
+        let defines = doc.resolved.as_ref().map(features::all_defines).unwrap_or_default();
+        Some((doc, offset, defines))
+    }
+
+    fn hover(&self, p: HoverParams) -> Option<Hover> {
+        let (doc, offset, defines) = self.locate(&p.text_document_position_params)?;
+        features::hover(&doc.text, offset, &defines)
+    }
+
+    fn completion(&self, p: CompletionParams) -> Option<CompletionResponse> {
+        let (doc, offset, defines) = self.locate(&p.text_document_position)?;
+        Some(CompletionResponse::Array(features::completion(&doc.text, offset, &defines)))
+    }
+
+    fn signature_help(&self, p: SignatureHelpParams) -> Option<SignatureHelp> {
+        let (doc, offset, defines) = self.locate(&p.text_document_position_params)?;
+        features::signature_help(&doc.text, offset, &defines)
+    }
+
+    fn definition(&self, p: GotoDefinitionParams) -> Option<GotoDefinitionResponse> {
+        let pos = &p.text_document_position_params;
+        let (doc, offset, _) = self.locate(pos)?;
+        let program = crate::parse(&doc.text).ok()?;
+        let uri = &pos.text_document.uri;
+        let target = features::definition(
+            &doc.text,
+            offset,
+            &program,
+            &self.provider(),
+            &base_dir(uri),
+            &self.lib_dirs,
+        )?;
+        let target_uri = match target.file {
+            Some(file) => path_to_uri(&file)?,
+            None => uri.clone(),
+        };
+        Some(GotoDefinitionResponse::Scalar(Location::new(target_uri, target.range)))
+    }
+
+    fn symbols(&self, p: DocumentSymbolParams) -> Option<DocumentSymbolResponse> {
+        let doc = self.docs.get(&p.text_document.uri)?;
+        let program = crate::parse(&doc.text).ok()?;
+        Some(DocumentSymbolResponse::Nested(features::document_symbols(&doc.text, &program)))
+    }
+
+    fn code_actions(&self, p: CodeActionParams) -> Option<Vec<CodeActionOrCommand>> {
+        let uri = &p.text_document.uri;
+        let doc = self.docs.get(uri)?;
+        let provider = self.provider();
+        let diags = crate::diagnose_with_provider(&doc.text, &provider, &base_dir(uri), &self.lib_dirs);
+        let actions = features::code_actions(&doc.text, &diags, uri, p.range);
+        Some(actions.into_iter().map(CodeActionOrCommand::CodeAction).collect())
+    }
+}
+
+fn params<N: LspNotification>(note: Notification) -> Option<N::Params> {
+    note.extract(N::METHOD).ok()
+}
+
+fn publish(uri: Uri, diagnostics: Vec<lsp_types::Diagnostic>) -> Notification {
+    let params = PublishDiagnosticsParams { uri, diagnostics, version: None };
+    Notification::new(PublishDiagnostics::METHOD.to_string(), params)
+}
+
+/// Directory imports in `uri` resolve against; untitled buffers use the
+/// working directory.
+fn base_dir(uri: &Uri) -> PathBuf {
+    uri_to_path(uri)
+        .and_then(|p| p.parent().map(Path::to_path_buf))
+        .unwrap_or_default()
+}
+
+// ── file:// URIs ───────────────────────────────────────
+
+fn uri_to_path(uri: &Uri) -> Option<PathBuf> {
+    if !uri.scheme().is_some_and(|s| s.as_str().eq_ignore_ascii_case("file")) {
+        return None;
+    }
+    let path = percent_decode(uri.path().as_str())?;
+    // `/C:/dir` → `C:/dir` on Windows
+    let bytes = path.as_bytes();
+    if bytes.len() >= 3 && bytes[0] == b'/' && bytes[2] == b':' && bytes[1].is_ascii_alphabetic() {
+        return Some(PathBuf::from(&path[1..]));
+    }
+    Some(PathBuf::from(path))
+}
+
+fn path_to_uri(path: &Path) -> Option<Uri> {
+    let path = path.to_str()?.replace('\\', "/");
+    let mut uri = String::from("file://");
+    if !path.starts_with('/') {
+        uri.push('/');
+    }
+    for b in path.bytes() {
+        if b.is_ascii_alphanumeric() || b"/-_.~:".contains(&b) {
+            uri.push(b as char);
+        } else {
+            uri.push_str(&format!("%{b:02X}"));
+        }
+    }
+    uri.parse().ok()
+}
+
+fn percent_decode(s: &str) -> Option<String> {
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let hex = s.get(i + 1..i + 3)?;
+            out.push(u8::from_str_radix(hex, 16).ok()?);
+            i += 3;
+        } else {
+            out.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8(out).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lsp_types::{
+        DidOpenTextDocumentParams, InitializeParams, InitializedParams, TextDocumentItem,
+    };
+
+    #[test]
+    fn file_uris_round_trip() {
+        let path = Path::new("/tmp/my shows/fx.game");
+        let uri = path_to_uri(path).unwrap();
+        assert_eq!(uri.as_str(), "file:///tmp/my%20shows/fx.game");
+        assert_eq!(uri_to_path(&uri).unwrap(), path);
+
+        let untitled: Uri = "untitled:Untitled-1".parse().unwrap();
+        assert_eq!(uri_to_path(&untitled), None);
+    }
+
+    #[test]
+    fn publishes_diagnostics_on_open() {
+        let (server, client) = Connection::memory();
+        let handle = std::thread::spawn(move || serve(&server, Vec::new()).unwrap());
+
+        let init = Request::new(1.into(), "initialize".into(), InitializeParams::default());
+        client.sender.send(init.into()).unwrap();
+        let Message::Response(resp) = client.receiver.recv().unwrap() else {
+            panic!("expected response")
+        };
+        assert!(resp.result.unwrap()["capabilities"]["hoverProvider"].as_bool().unwrap());
+        let initialized = Notification::new("initialized".into(), InitializedParams {});
+        client.sender.send(initialized.into()).unwrap();
+
+        let open = DidOpenTextDocumentParams {
+            text_document: TextDocumentItem {
+                uri: "file:///virtual/t.game".parse().unwrap(),
+                language_id: "game".into(),
+                version: 1,
+                text: "cinematic \"t\" { layer a { cicle(0.3) | glow(2.0) } }".into(),
+            },
+        };
+        client
+            .sender
+            .send(Notification::new(DidOpenTextDocument::METHOD.into(), open).into())
+            .unwrap();
+        let Message::Notification(note) = client.receiver.recv().unwrap() else {
+            panic!("expected notification")
+        };
+        assert_eq!(note.method, PublishDiagnostics::METHOD);
+        let params: PublishDiagnosticsParams = serde_json::from_value(note.params).unwrap();
+        assert_eq!(params.diagnostics.len(), 1);
+        assert!(params.diagnostics[0].message.contains("cicle"));
+
+        client.sender.send(Request::new(2.into(), "shutdown".into(), ()).into()).unwrap();
+        client.receiver.recv().unwrap();
+        client.sender.send(Notification::new("exit".into(), ()).into()).unwrap();
+        handle.join().unwrap();
+    }
+}
@@ -0,0 +1,94 @@
+//! Byte offset ↔ LSP position conversion.
+//!
+//! Compiler spans are byte offsets into UTF-8 source; LSP positions are
+//! zero-based lines with UTF-16 code unit columns.
+
+use lsp_types::{Position, Range};
+
+use crate::error::Span;
+
+/// Line start offsets for one document.
+pub struct LineIndex<'a> {
+    text: &'a str,
+    line_starts: Vec<usize>,
+}
+
+impl<'a> LineIndex<'a> {
+    pub fn new(text: &'a str) -> Self {
+        let mut line_starts = vec![0];
+        line_starts.extend(text.match_indices('\n').map(|(i, _)| i + 1));
+        Self { text, line_starts }
+    }
+
+    /// LSP position of a byte offset (clamped to the document).
+    pub fn position(&self, offset: usize) -> Position {
+        let offset = offset.min(self.text.len());
+        let line = self.line_starts.partition_point(|&s| s <= offset) - 1;
+        let start = self.line_starts[line];
+        let character: usize = self.text[start..floor_char_boundary(self.text, offset)]
+            .chars()
+            .map(char::len_utf16)
+            .sum();
+        Position::new(line as u32, character as u32)
+    }
+
+    /// Byte offset of an LSP position (clamped to the line).
+    pub fn offset(&self, pos: Position) -> usize {
+        let Some(&start) = self.line_starts.get(pos.line as usize) else {
+            return self.text.len();
+        };
+        let end = self
+            .line_starts
+            .get(pos.line as usize + 1)
+            .map_or(self.text.len(), |&next| next - 1);
+        let mut units = 0;
+        for (i, c) in self.text[start..end].char_indices() {
+            if units >= pos.character as usize {
+                return start + i;
+            }
+            units += c.len_utf16();
+        }
+        end
+    }
+
+    pub fn range(&self, span: Span) -> Range {
+        Range::new(self.position(span.start), self.position(span.end))
+    }
+}
+
+fn floor_char_boundary(text: &str, mut offset: usize) -> usize {
+    while !text.is_char_boundary(offset) {
+        offset -= 1;
+    }
+    offset
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_ascii() {
+        let text = "ab\ncd\n";
+        let index = LineIndex::new(text);
+        assert_eq!(index.position(4), Position::new(1, 1));
+        assert_eq!(index.offset(Position::new(1, 1)), 4);
+        assert_eq!(index.position(text.len()), Position::new(2, 0));
+    }
+
+    #[test]
+    fn columns_count_utf16_units() {
+        // 'é' is 2 bytes / 1 unit, '𝄞' is 4 bytes / 2 units
+        let text = "é𝄞x";
+        let index = LineIndex::new(text);
+        assert_eq!(index.position(6), Position::new(0, 3));
+        assert_eq!(index.offset(Position::new(0, 3)), 6);
+    }
+
+    #[test]
+    fn offset_clamps_past_line_end() {
+        let index = LineIndex::new("ab\ncd");
+        assert_eq!(index.offset(Position::new(0, 10)), 2);
+        assert_eq!(index.offset(Position::new(9, 0)), 5);
+    }
+}
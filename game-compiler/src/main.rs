@@ -1,9 +1,9 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use anyhow::{Context, Result};
-use clap::{Parser, Subcommand, ValueEnum};
+use clap::{Args, Parser, Subcommand, ValueEnum};
 
-use game_compiler::{CompileConfig, OutputFormat, ShaderTarget};
+use game_compiler::{CompileConfig, OptLevel, OutputFormat, ShaderTarget};
 
 /// GAME compiler — compiles .game DSL to WebGPU shaders + Web Components.
 #[derive(Parser, Debug)]
@@ -13,8 +13,14 @@ struct Cli {
     command: Command,
 
     /// Optimization level: 0 = none, 1 = default, 2 = aggressive.
-    #[arg(global = true, long, short = 'O', default_value = "1")]
+    #[arg(global = true, long, short = 'O', default_value = "1",
+          value_parser = clap::value_parser!(u8).range(0..=2))]
     optimize: u8,
+
+    /// Extra directory to search for imports (repeatable). Searched after the
+    /// importing file's directory and before the embedded stdlib.
+    #[arg(global = true, long = "lib-dir", value_name = "DIR")]
+    lib_dirs: Vec<PathBuf>,
 }
 
 #[derive(Subcommand, Debug)]
@@ -67,6 +73,89 @@ enum Command {
         /// Input .game file(s).
         #[arg(required = true)]
         input: Vec<PathBuf>,
+
+        /// Report format: rendered text on stderr, or JSON on stdout.
+        #[arg(long, default_value = "text")]
+        format: CheckFormatArg,
+
+        /// Also generate every WGSL and GLSL shader and validate it with
+        /// naga, reporting failures at the layer and stage that emitted them.
+        #[arg(long)]
+        strict: bool,
+    },
+
+    /// Run the language server over stdio (for editor integration).
+    Lsp {
+        /// Accepted for compatibility with clients that pass it; stdio is
+        /// the only transport.
+        #[arg(long, hide = true)]
+        stdio: bool,
+    },
+
+    /// Render a cinematic offline to a PNG sequence, GIF, APNG or Y4M stream.
+    ///
+    /// Frames are rendered on the CPU at a fixed clock (`time` of frame `i`
+    /// is `start + i / fps`), so output is identical run to run.
+    Render {
+        /// Input .game file.
+        input: PathBuf,
+
+        /// Output path: a directory for PNG sequences, `.gif`, `.apng`,
+        /// `.y4m`, or `-` for Y4M on stdout (pipe into ffmpeg).
+        #[arg(short, long)]
+        output: PathBuf,
+
+        /// Output format. Inferred from the output path when omitted.
+        #[arg(short, long)]
+        format: Option<RenderFormatArg>,
+
+        /// Cinematic to render (default: the first one in the file).
+        #[arg(long)]
+        cinematic: Option<String>,
+
+        #[arg(long, default_value_t = 640, value_parser = clap::value_parser!(u32).range(1..))]
+        width: u32,
+
+        #[arg(long, default_value_t = 360, value_parser = clap::value_parser!(u32).range(1..))]
+        height: u32,
+
+        #[arg(long, default_value_t = 30,
+              value_parser = clap::value_parser!(u32).range(1..=240))]
+        fps: u32,
+
+        /// Length of the render: `5`, `2.5s` or `500ms`.
+        #[arg(long, default_value = "5s", value_parser = game_compiler::render::parse_duration)]
+        duration: f32,
+
+        /// `time` of the first frame, in seconds.
+        #[arg(long, default_value_t = 0.0)]
+        start: f32,
+
+        /// Script a `data.*` field: `field=0.5` or keyframes
+        /// `field=0:0,2:1` (seconds:value, linear). Repeatable.
+        #[arg(long = "data", value_name = "FIELD=SPEC",
+              value_parser = game_compiler::render::parse_assignment)]
+        data: Vec<(String, game_compiler::cpu::Keyframes)>,
+
+        /// Script a layer param the same way as `--data`. Repeatable.
+        #[arg(long = "param", value_name = "NAME=SPEC",
+              value_parser = game_compiler::render::parse_assignment)]
+        params: Vec<(String, game_compiler::cpu::Keyframes)>,
+
+        /// Play the cinematic's `arc` blocks and `score` over the render.
+        #[arg(long)]
+        timeline: bool,
+
+        /// Keep alpha in PNG/APNG output (black is transparent) instead of
+        /// compositing over black.
+        #[arg(long)]
+        transparent: bool,
+    },
+
+    /// Record or check reference images for visual regression testing.
+    Snapshot {
+        #[command(subcommand)]
+        action: SnapshotAction,
     },
 
     /// Launch the hot-reload dev server.
@@ -81,11 +170,64 @@ enum Command {
     },
 }
 
+#[derive(Subcommand, Debug)]
+enum SnapshotAction {
+    /// Render references, overwriting existing ones.
+    Record(SnapshotArgs),
+    /// Compare renders against the references; exits 1 on any failure.
+    Check(SnapshotArgs),
+}
+
+#[derive(Args, Debug)]
+struct SnapshotArgs {
+    /// Input .game files, or directories of them.
+    #[arg(required = true)]
+    input: Vec<PathBuf>,
+
+    /// Reference directory. Each input file gets a subdirectory named
+    /// after its stem.
+    #[arg(short, long, default_value = "snapshots")]
+    dir: PathBuf,
+
+    #[arg(long, default_value_t = 128)]
+    width: u32,
+
+    #[arg(long, default_value_t = 128)]
+    height: u32,
+
+    /// Times to capture, in seconds (comma-separated or repeated).
+    #[arg(long = "time", value_delimiter = ',', default_value = "1.0")]
+    times: Vec<f32>,
+
+    /// Largest percentage of differing pixels that still passes.
+    #[arg(long, default_value_t = 1.0)]
+    threshold: f64,
+
+    /// Per-channel difference (0-255) below which pixels count as equal.
+    #[arg(long, default_value_t = 2)]
+    tolerance: i32,
+}
+
 #[derive(Debug, Clone, ValueEnum)]
 enum FormatArg {
     Component,
     Html,
     Standalone,
+    Worker,
+}
+
+#[derive(Debug, Clone, ValueEnum)]
+enum CheckFormatArg {
+    Text,
+    Json,
+}
+
+#[derive(Debug, Clone, ValueEnum)]
+enum RenderFormatArg {
+    Png,
+    Gif,
+    Apng,
+    Y4m,
 }
 
 #[derive(Debug, Clone, ValueEnum)]
@@ -97,7 +239,11 @@ enum TargetArg {
 
 fn main() -> Result<()> {
     let cli = Cli::parse();
-    let opt_level = cli.optimize;
+    let opt_level = match cli.optimize {
+        0 => OptLevel::O0,
+        1 => OptLevel::O1,
+        _ => OptLevel::O2,
+    };
 
     match cli.command {
         Command::Build {
@@ -111,12 +257,14 @@ fn main() -> Result<()> {
                     FormatArg::Component => OutputFormat::Component,
                     FormatArg::Html => OutputFormat::Html,
                     FormatArg::Standalone => OutputFormat::Standalone,
+                    FormatArg::Worker => OutputFormat::Worker,
                 },
                 target: match target {
                     TargetArg::Webgpu => ShaderTarget::WebGpu,
                     TargetArg::Webgl2 => ShaderTarget::WebGl2,
                     TargetArg::Both => ShaderTarget::Both,
                 },
+                opt_level,
             };
 
             std::fs::create_dir_all(&output_dir)
@@ -127,8 +275,13 @@ fn main() -> Result<()> {
                 let source = std::fs::read_to_string(path)
                     .with_context(|| format!("read: {}", path.display()))?;
 
-                let results = game_compiler::compile(&source, &config)
-                    .map_err(|e| anyhow::anyhow!("{e}"))?;
+                let results = game_compiler::compile_with_resolver(
+                    &source,
+                    &config,
+                    base_dir(path),
+                    &cli.lib_dirs,
+                )
+                .map_err(|e| anyhow::anyhow!("{e}"))?;
 
                 for output in &results {
                     let stem = &output.name;
@@ -179,21 +332,22 @@ fn main() -> Result<()> {
                 return Ok(());
             }
 
+            // --component and the default both go through component output
             let format = if html {
                 OutputFormat::Html
-            } else if component {
-                OutputFormat::Component
             } else {
-                OutputFormat::Component // default: WGSL via component output
+                OutputFormat::Component
             };
 
             let config = CompileConfig {
                 output_format: format,
                 target: ShaderTarget::Both,
+                opt_level,
             };
 
-            let results = game_compiler::compile(&source, &config)
-                .map_err(|e| anyhow::anyhow!("{e}"))?;
+            let results =
+                game_compiler::compile_with_resolver(&source, &config, base_dir(&input), &cli.lib_dirs)
+                    .map_err(|e| anyhow::anyhow!("{e}"))?;
 
             for output in &results {
                 if html {
@@ -210,63 +364,216 @@ fn main() -> Result<()> {
                 }
             }
         }
-        Command::Check { input } => {
+        Command::Check { input, format, strict } => {
             let mut had_errors = false;
+            let mut reports = Vec::new();
 
             for path in &input {
                 let source = std::fs::read_to_string(path)
                     .with_context(|| format!("read: {}", path.display()))?;
-
-                // Phase 1: lex + parse
-                let program = match game_compiler::compile_to_ast(&source) {
-                    Ok(p) => p,
-                    Err(e) => {
-                        eprintln!(
-                            "{}",
-                            game_compiler::error::render_with_source(&e, &source)
-                        );
-                        had_errors = true;
-                        continue;
+                let mut diags = game_compiler::diagnose_with_provider(
+                    &source,
+                    &game_compiler::source::FsProvider,
+                    base_dir(path),
+                    &cli.lib_dirs,
+                );
+                if strict && !diags.has_errors() {
+                    let program = load_program(path, &cli.lib_dirs)?;
+                    diags.extend(game_compiler::shader_check::check_program(&program, opt_level));
+                }
+                had_errors |= diags.has_errors();
+
+                match format {
+                    CheckFormatArg::Json => reports.push(serde_json::json!({
+                        "file": path.display().to_string(),
+                        "diagnostics": diags.to_json(&source),
+                    })),
+                    CheckFormatArg::Text if diags.is_empty() => {
+                        eprintln!("[game] {}: ok", path.display());
                     }
-                };
-
-                // Phase 2: codegen validation (pipeline type-flow, cast types)
-                for cinematic in &program.cinematics {
-                    if let Err(e) = game_compiler::codegen::validate(cinematic) {
-                        eprintln!(
-                            "{}",
-                            game_compiler::error::render_with_source(&e, &source)
-                        );
-                        had_errors = true;
+                    CheckFormatArg::Text => {
+                        for d in &diags {
+                            eprintln!(
+                                "{}: {}",
+                                path.display(),
+                                game_compiler::error::render_diagnostic(d, &source)
+                            );
+                        }
                     }
                 }
+            }
 
-                // Phase 3: semantic checks (warnings)
-                let warnings = game_compiler::check(&program);
-                for w in &warnings {
-                    eprintln!("warning: {}: {w}", path.display());
-                }
+            if let CheckFormatArg::Json = format {
+                println!("{}", serde_json::to_string_pretty(&reports)?);
+            }
+            if had_errors {
+                std::process::exit(1);
+            }
+        }
+        Command::Lsp { .. } => {
+            game_compiler::lsp::run(cli.lib_dirs.clone()).map_err(|e| anyhow::anyhow!("{e}"))?;
+        }
+        Command::Render {
+            input,
+            output,
+            format,
+            cinematic,
+            width,
+            height,
+            fps,
+            duration,
+            start,
+            data,
+            params,
+            timeline,
+            transparent,
+        } => {
+            use game_compiler::render::{self, RenderFormat, RenderSettings};
+
+            let program = load_program(&input, &cli.lib_dirs)?;
+            let cin = match &cinematic {
+                Some(name) => program
+                    .cinematics
+                    .iter()
+                    .find(|c| &c.name == name)
+                    .with_context(|| format!("no cinematic named '{name}'"))?,
+                None => program.cinematics.first().context("no cinematic in file")?,
+            };
+
+            let mut settings = RenderSettings {
+                width,
+                height,
+                fps,
+                duration,
+                start,
+                timeline,
+                transparent,
+                params,
+            };
+            for (field, keys) in data {
+                settings.set_data(&field, keys);
+            }
+            let format = match format {
+                Some(RenderFormatArg::Png) => RenderFormat::PngSequence,
+                Some(RenderFormatArg::Gif) => RenderFormat::Gif,
+                Some(RenderFormatArg::Apng) => RenderFormat::Apng,
+                Some(RenderFormatArg::Y4m) => RenderFormat::Y4m,
+                None => RenderFormat::from_path(&output),
+            };
 
-                if !had_errors && warnings.is_empty() {
-                    eprintln!("[game] {}: ok", path.display());
+            let frames = render::render_to(cin, &settings, format, &output)
+                .map_err(|e| anyhow::anyhow!("{e}"))?;
+            eprintln!("[game] rendered {frames} frames to {}", output.display());
+        }
+        Command::Snapshot { action } => {
+            let (recording, args) = match action {
+                SnapshotAction::Record(args) => (true, args),
+                SnapshotAction::Check(args) => (false, args),
+            };
+            let config = game_compiler::snapshot::SnapshotConfig {
+                dir: args.dir,
+                width: args.width,
+                height: args.height,
+                times: args.times,
+                max_diff_percent: args.threshold,
+                channel_threshold: args.tolerance,
+            };
+
+            let mut failures = 0;
+            for path in game_files(&args.input)? {
+                let group = path.file_stem().unwrap_or_default().to_string_lossy();
+                let results = load_program(&path, &cli.lib_dirs)
+                    .and_then(|program| {
+                        let run = if recording {
+                            game_compiler::snapshot::record
+                        } else {
+                            game_compiler::snapshot::check
+                        };
+                        run(&program, &group, &config).map_err(|e| anyhow::anyhow!("{e}"))
+                    });
+                let results = match results {
+                    Ok(results) => results,
+                    Err(e) => {
+                        eprintln!("[game] {}: error: {e}", path.display());
+                        failures += 1;
+                        continue;
+                    }
+                };
+                for r in &results {
+                    print_snapshot_result(&path, r, config.max_diff_percent);
+                    failures += usize::from(!r.is_ok());
                 }
             }
 
-            if had_errors {
+            if failures > 0 {
+                eprintln!("[game] {failures} snapshot(s) failed");
                 std::process::exit(1);
             }
         }
         Command::Dev { input, port } => {
             let path = input.first().context("need at least one .game file")?;
             let rt = tokio::runtime::Runtime::new()?;
-            rt.block_on(game_compiler::server::run_dev_server(path.clone(), port))
+            rt.block_on(game_compiler::server::run_dev_server(path.clone(), port, cli.lib_dirs.clone()))
                 .map_err(|e| anyhow::anyhow!("{e}"))?;
         }
     }
 
-    // Note: opt_level is available for future optimization-level-dependent behavior.
-    // Currently the optimizer always runs at level 1 inside compile().
-    let _ = opt_level;
-
     Ok(())
 }
+
+/// Parse `path` and resolve its imports.
+fn load_program(path: &Path, lib_dirs: &[PathBuf]) -> Result<game_compiler::ast::Program> {
+    let source =
+        std::fs::read_to_string(path).with_context(|| format!("read: {}", path.display()))?;
+    let mut program =
+        game_compiler::compile_to_ast(&source).map_err(|e| anyhow::anyhow!("{e}"))?;
+    game_compiler::resolver::resolve_imports(&mut program, base_dir(path), lib_dirs)
+        .map_err(|e| anyhow::anyhow!("{e}"))?;
+    Ok(program)
+}
+
+/// Expand directories in `inputs` to the `.game` files they contain.
+fn game_files(inputs: &[PathBuf]) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    for input in inputs {
+        if !input.is_dir() {
+            files.push(input.clone());
+            continue;
+        }
+        let mut found: Vec<_> = std::fs::read_dir(input)
+            .with_context(|| format!("read dir: {}", input.display()))?
+            .filter_map(|e| e.ok().map(|e| e.path()))
+            .filter(|p| p.extension().is_some_and(|ext| ext == "game"))
+            .collect();
+        found.sort();
+        files.extend(found);
+    }
+    Ok(files)
+}
+
+fn print_snapshot_result(path: &Path, r: &game_compiler::snapshot::SnapshotResult, max: f64) {
+    use game_compiler::snapshot::SnapshotOutcome;
+
+    let label = format!("{} {}@{:.2}s", path.display(), r.cinematic, r.time);
+    match &r.outcome {
+        SnapshotOutcome::Recorded => eprintln!("[game] {label}: wrote {}", r.reference.display()),
+        SnapshotOutcome::Passed { diff_percent } => {
+            eprintln!("[game] {label}: ok ({diff_percent:.2}% differ)")
+        }
+        SnapshotOutcome::Failed { diff_percent, diff } => eprintln!(
+            "[game] {label}: FAIL {diff_percent:.2}% differ (max {max:.2}%), diff at {}",
+            diff.display()
+        ),
+        SnapshotOutcome::Missing => {
+            eprintln!("[game] {label}: FAIL no reference at {}", r.reference.display())
+        }
+        SnapshotOutcome::SizeMismatch { width, height } => eprintln!(
+            "[game] {label}: FAIL reference is {width}x{height}, re-record at the new size"
+        ),
+    }
+}
+
+/// Directory that relative imports in `path` resolve against.
+fn base_dir(path: &Path) -> &Path {
+    path.parent().unwrap_or(Path::new("."))
+}
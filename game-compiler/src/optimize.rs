@@ -3,13 +3,14 @@
 //! Six passes that operate directly on AST nodes (no IR dependency):
 //! 1. Constant folding on expressions (including strength reduction)
 //! 2. No-op stage elimination in pipelines
-//! 3. Dead uniform detection across a cinematic
+//! 3. Dead uniform detection across a cinematic (removal at `-O2`)
 //! 4. Dead define elimination (DCE)
 //! 5. Define body validation (semantic analysis)
 //! 6. Arity checking for builtin calls
 
 use crate::ast::*;
 use crate::builtins;
+use crate::error::{Diagnostic, Diagnostics, ErrorCode};
 
 /// Results from running all optimization passes on a cinematic.
 #[derive(Debug, Clone)]
@@ -18,7 +19,7 @@ pub struct OptimizeStats {
     pub noop_stages_removed: usize,
     pub dead_uniforms: Vec<String>,
     pub dead_defines_removed: usize,
-    pub semantic_warnings: Vec<String>,
+    pub semantic_warnings: Diagnostics,
 }
 
 /// Run all optimization passes on a single `Cinematic`.
@@ -66,7 +67,7 @@ pub fn optimize_cinematic(cinematic: &mut Cinematic) -> OptimizeStats {
     let dead_defines_removed = eliminate_dead_defines(cinematic);
 
     // Semantic analysis passes (non-destructive — produce warnings only)
-    let mut semantic_warnings = Vec::new();
+    let mut semantic_warnings = Diagnostics::new();
     semantic_warnings.extend(check_define_semantics(cinematic));
     semantic_warnings.extend(check_arity(cinematic));
 
@@ -92,8 +93,8 @@ fn is_one(v: f64) -> bool {
 /// Extract a numeric literal from an expression, seeing through `Paren`.
 pub fn as_number(expr: &Expr) -> Option<f64> {
     match expr {
-        Expr::Number(v) => Some(*v),
-        Expr::Paren(inner) => as_number(inner),
+        Expr::Number(v, _) => Some(*v),
+        Expr::Paren(inner, _) => as_number(inner),
         _ => None,
     }
 }
@@ -110,10 +111,10 @@ pub fn constant_fold(expr: &mut Expr) -> usize {
             count += constant_fold(left);
             count += constant_fold(right);
         }
-        Expr::Neg(inner) => {
+        Expr::Neg(inner, _) | Expr::Not(inner, _) => {
             count += constant_fold(inner);
         }
-        Expr::Paren(inner) => {
+        Expr::Paren(inner, _) => {
             count += constant_fold(inner);
         }
         Expr::Call { args, .. } => {
@@ -121,46 +122,61 @@ pub fn constant_fold(expr: &mut Expr) -> usize {
                 count += constant_fold(&mut arg.value);
             }
         }
-        Expr::Array(elems) => {
+        Expr::Array(elems, _) => {
             for elem in elems.iter_mut() {
                 count += constant_fold(elem);
             }
         }
-        Expr::Ternary { condition, if_true, if_false } => {
+        Expr::Ternary { condition, if_true, if_false, .. } => {
             count += constant_fold(condition);
             count += constant_fold(if_true);
             count += constant_fold(if_false);
         }
+        Expr::Pipeline(stages, _) => {
+            for arg in stages.iter_mut().flat_map(|s| &mut s.args) {
+                count += constant_fold(&mut arg.value);
+            }
+        }
         _ => {}
     }
 
     // Now try to fold this node
+    let span = expr.span();
     let replacement = match expr {
         // Neg(Number) → Number
-        Expr::Neg(inner) => {
-            if let Some(v) = as_number(inner) {
-                Some(Expr::Number(-v))
-            } else {
-                None
-            }
-        }
+        Expr::Neg(inner, _) => as_number(inner).map(|v| number(-v)),
+        Expr::Not(inner, _) => as_number(inner).map(|v| truth(is_zero(v))),
+
+        // Constant condition → the branch it picks
+        Expr::Ternary { condition, if_true, if_false, .. } => as_number(condition)
+            .map(|c| if is_zero(c) { take_expr(if_false) } else { take_expr(if_true) }),
 
         // BinOp with two numeric operands → direct computation
-        Expr::BinOp { op, left, right } => {
+        Expr::BinOp { op, left, right, .. } => {
             let lv = as_number(left);
             let rv = as_number(right);
 
             match (lv, rv) {
                 // Both sides are constants
                 (Some(a), Some(b)) => match op {
-                    BinOp::Add => Some(Expr::Number(a + b)),
-                    BinOp::Sub => Some(Expr::Number(a - b)),
-                    BinOp::Mul => Some(Expr::Number(a * b)),
+                    BinOp::Add => Some(number(a + b)),
+                    BinOp::Sub => Some(number(a - b)),
+                    BinOp::Mul => Some(number(a * b)),
                     BinOp::Div => {
-                        if is_zero(b) { None } else { Some(Expr::Number(a / b)) }
+                        if is_zero(b) { None } else { Some(number(a / b)) }
                     }
-                    BinOp::Pow => Some(Expr::Number(a.powf(b))),
-                    _ => None,
+                    BinOp::Mod => {
+                        if is_zero(b) { None } else { Some(number(a % b)) }
+                    }
+                    BinOp::Pow => Some(number(a.powf(b))),
+                    BinOp::Gt => Some(truth(a > b)),
+                    BinOp::Lt => Some(truth(a < b)),
+                    BinOp::Ge => Some(truth(a >= b)),
+                    BinOp::Le => Some(truth(a <= b)),
+                    BinOp::Eq => Some(truth(a == b)),
+                    BinOp::Ne => Some(truth(a != b)),
+                    BinOp::And => Some(truth(!is_zero(a) && !is_zero(b))),
+                    BinOp::Or => Some(truth(!is_zero(a) || !is_zero(b))),
                 },
 
                 // Right side is constant — identity simplifications + strength reduction
@@ -168,14 +184,15 @@ pub fn constant_fold(expr: &mut Expr) -> usize {
                     BinOp::Add if is_zero(b) => Some(take_expr(left)),  // x + 0 → x
                     BinOp::Sub if is_zero(b) => Some(take_expr(left)),  // x - 0 → x
                     BinOp::Mul if is_one(b)  => Some(take_expr(left)),  // x * 1 → x
-                    BinOp::Mul if is_zero(b) => Some(Expr::Number(0.0)), // x * 0 → 0
+                    BinOp::Mul if is_zero(b) => Some(number(0.0)), // x * 0 → 0
                     BinOp::Div if is_one(b)  => Some(take_expr(left)),  // x / 1 → x
                     // Strength reduction: x / C → x * (1/C) (division is slower than multiply)
                     BinOp::Div if !is_zero(b) => {
                         Some(Expr::BinOp {
                             op: BinOp::Mul,
                             left: Box::new(take_expr(left)),
-                            right: Box::new(Expr::Number(1.0 / b)),
+                            right: Box::new(number(1.0 / b)),
+                            span: Span::default(),
                         })
                     }
                     _ => None,
@@ -185,7 +202,7 @@ pub fn constant_fold(expr: &mut Expr) -> usize {
                 (Some(a), None) => match op {
                     BinOp::Add if is_zero(a) => Some(take_expr(right)), // 0 + x → x
                     BinOp::Mul if is_one(a)  => Some(take_expr(right)), // 1 * x → x
-                    BinOp::Mul if is_zero(a) => Some(Expr::Number(0.0)), // 0 * x → 0
+                    BinOp::Mul if is_zero(a) => Some(number(0.0)), // 0 * x → 0
                     _ => None,
                 },
 
@@ -194,12 +211,16 @@ pub fn constant_fold(expr: &mut Expr) -> usize {
         }
 
         // Known math function calls with constant args
-        Expr::Call { name, args } => fold_known_call(name, args),
+        Expr::Call { name, args, .. } => fold_known_call(name, args),
 
         _ => None,
     };
 
-    if let Some(r) = replacement {
+    if let Some(mut r) = replacement {
+        // Synthesized results stand where the folded expression was.
+        if r.span().is_empty() {
+            *r.span_mut() = span;
+        }
         *expr = r;
         count += 1;
     }
@@ -207,9 +228,19 @@ pub fn constant_fold(expr: &mut Expr) -> usize {
     count
 }
 
+/// A folded comparison or logic result: 1.0 for true, 0.0 for false.
+fn truth(b: bool) -> Expr {
+    number(if b { 1.0 } else { 0.0 })
+}
+
+/// A synthesized number; [`constant_fold`] gives it the folded node's span.
+fn number(v: f64) -> Expr {
+    Expr::Number(v, Span::default())
+}
+
 /// Take ownership of an `Expr` behind a `Box`, replacing it with a dummy.
 fn take_expr(boxed: &mut Box<Expr>) -> Expr {
-    std::mem::replace(boxed.as_mut(), Expr::Number(0.0))
+    std::mem::replace(boxed.as_mut(), number(0.0))
 }
 
 /// Try to fold a call to a known single-arg or two-arg math function.
@@ -220,17 +251,17 @@ fn fold_known_call(name: &str, args: &[Arg]) -> Option<Expr> {
         .collect();
 
     match (name, positional.as_slice()) {
-        ("sin", [v]) if is_zero(*v) => Some(Expr::Number(0.0)),
-        ("cos", [v]) if is_zero(*v) => Some(Expr::Number(1.0)),
-        ("sin", [v])   => Some(Expr::Number(v.sin())),
-        ("cos", [v])   => Some(Expr::Number(v.cos())),
-        ("abs", [v])   => Some(Expr::Number(v.abs())),
-        ("floor", [v]) => Some(Expr::Number(v.floor())),
-        ("ceil", [v])  => Some(Expr::Number(v.ceil())),
-        ("sqrt", [v]) if *v >= 0.0 => Some(Expr::Number(v.sqrt())),
-        ("min", [a, b]) => Some(Expr::Number(a.min(*b))),
-        ("max", [a, b]) => Some(Expr::Number(a.max(*b))),
-        ("pow", [a, b]) => Some(Expr::Number(a.powf(*b))),
+        ("sin", [v]) if is_zero(*v) => Some(number(0.0)),
+        ("cos", [v]) if is_zero(*v) => Some(number(1.0)),
+        ("sin", [v])   => Some(number(v.sin())),
+        ("cos", [v])   => Some(number(v.cos())),
+        ("abs", [v])   => Some(number(v.abs())),
+        ("floor", [v]) => Some(number(v.floor())),
+        ("ceil", [v])  => Some(number(v.ceil())),
+        ("sqrt", [v]) if *v >= 0.0 => Some(number(v.sqrt())),
+        ("min", [a, b]) => Some(number(a.min(*b))),
+        ("max", [a, b]) => Some(number(a.max(*b))),
+        ("pow", [a, b]) => Some(number(a.powf(*b))),
         _ => None,
     }
 }
@@ -242,7 +273,13 @@ fn fold_known_call(name: &str, args: &[Arg]) -> Option<Expr> {
 pub fn eliminate_noop_stages(stages: &mut Vec<Stage>) -> usize {
     let before = stages.len();
     stages.retain(|stage| !is_noop_stage(stage));
-    before - stages.len()
+    let mut removed = before - stages.len();
+    for arg in stages.iter_mut().flat_map(|s| &mut s.args) {
+        if let Expr::Pipeline(sub, _) = &mut arg.value {
+            removed += eliminate_noop_stages(sub);
+        }
+    }
+    removed
 }
 
 fn is_noop_stage(stage: &Stage) -> bool {
@@ -369,8 +406,8 @@ pub fn find_dead_uniforms(cinematic: &Cinematic) -> Vec<String> {
 /// Recursively collect all `Ident` and `DottedIdent` names from an expression.
 fn collect_idents(expr: &Expr, out: &mut std::collections::HashSet<String>) {
     match expr {
-        Expr::Ident(name) => { out.insert(name.clone()); }
-        Expr::DottedIdent { object, field } => {
+        Expr::Ident(name, _) => { out.insert(name.clone()); }
+        Expr::DottedIdent { object, field, .. } => {
             out.insert(object.clone());
             out.insert(format!("{}.{}", object, field));
         }
@@ -378,26 +415,49 @@ fn collect_idents(expr: &Expr, out: &mut std::collections::HashSet<String>) {
             collect_idents(left, out);
             collect_idents(right, out);
         }
-        Expr::Neg(inner) | Expr::Paren(inner) => collect_idents(inner, out),
+        Expr::Neg(inner, _) | Expr::Not(inner, _) | Expr::Paren(inner, _) => {
+            collect_idents(inner, out)
+        }
         Expr::Call { args, .. } => {
             for arg in args {
                 collect_idents(&arg.value, out);
             }
         }
-        Expr::Array(elems) => {
+        Expr::Array(elems, _) => {
             for elem in elems {
                 collect_idents(elem, out);
             }
         }
-        Expr::Ternary { condition, if_true, if_false } => {
+        Expr::Ternary { condition, if_true, if_false, .. } => {
             collect_idents(condition, out);
             collect_idents(if_true, out);
             collect_idents(if_false, out);
         }
+        Expr::Pipeline(stages, _) => {
+            for arg in stages.iter().flat_map(|s| &s.args) {
+                collect_idents(&arg.value, out);
+            }
+        }
         _ => {}
     }
 }
 
+/// Remove dead uniforms from `Params`-body layers. Only run at `-O2`, since
+/// it also drops the matching component attributes.
+/// Returns the names removed.
+pub fn eliminate_dead_uniforms(cinematic: &mut Cinematic) -> Vec<String> {
+    let dead = find_dead_uniforms(cinematic);
+    if dead.is_empty() {
+        return dead;
+    }
+    for layer in &mut cinematic.layers {
+        if let LayerBody::Params(params) = &mut layer.body {
+            params.retain(|p| !dead.contains(&p.name));
+        }
+    }
+    dead
+}
+
 // ── Pass 4: Dead Define Elimination ─────────────────────
 
 /// Remove defines that are never referenced by any layer pipeline.
@@ -408,9 +468,9 @@ pub fn eliminate_dead_defines(cinematic: &mut Cinematic) -> usize {
     // Collect all stage names used in layer pipelines
     for layer in &cinematic.layers {
         if let LayerBody::Pipeline(stages) = &layer.body {
-            for stage in stages {
+            walk_stages(stages, &mut |stage| {
                 used_defines.insert(stage.name.clone());
-            }
+            });
         }
     }
 
@@ -430,7 +490,7 @@ pub fn eliminate_dead_defines(cinematic: &mut Cinematic) -> usize {
 
 /// Check that define blocks have valid pipeline stages and that
 /// all declared parameters are actually used in the body.
-pub fn check_define_semantics(cinematic: &Cinematic) -> Vec<String> {
+pub fn check_define_semantics(cinematic: &Cinematic) -> Vec<Diagnostic> {
     let mut warnings = Vec::new();
     for define in &cinematic.defines {
         // Check that stages in the define body are valid builtins or other defines
@@ -440,14 +500,15 @@ pub fn check_define_semantics(cinematic: &Cinematic) -> Vec<String> {
             .map(|d| d.name.as_str())
             .collect();
 
-        for stage in &define.body {
+        walk_stages(&define.body, &mut |stage| {
             if builtins::lookup(&stage.name).is_none() && !define_names.contains(stage.name.as_str()) {
-                warnings.push(format!(
+                warnings.push(stage.locate_diagnostic(Diagnostic::warning(format!(
                     "define '{}': unknown function '{}'",
                     define.name, stage.name
-                ));
+                ))
+                .with_code(ErrorCode::E001)));
             }
-        }
+        });
 
         // Check that define parameters are actually used in the body
         for param in &define.params {
@@ -458,10 +519,14 @@ pub fn check_define_semantics(cinematic: &Cinematic) -> Vec<String> {
                     .any(|arg| arg_references_name(&arg.value, param))
             });
             if !used {
-                warnings.push(format!(
-                    "define '{}': parameter '{}' is never used",
-                    define.name, param
-                ));
+                warnings.push(
+                    Diagnostic::warning(format!(
+                        "define '{}': parameter '{}' is never used",
+                        define.name, param
+                    ))
+                    .with_code(ErrorCode::E009)
+                    .with_span(define.span),
+                );
             }
         }
     }
@@ -471,23 +536,30 @@ pub fn check_define_semantics(cinematic: &Cinematic) -> Vec<String> {
 /// Recursively check if an expression references a given identifier name.
 pub fn arg_references_name(expr: &Expr, name: &str) -> bool {
     match expr {
-        Expr::Ident(s) => s == name,
+        Expr::Ident(s, _) => s == name,
         Expr::DottedIdent { object, .. } => object == name,
         Expr::BinOp { left, right, .. } => {
             arg_references_name(left, name) || arg_references_name(right, name)
         }
-        Expr::Neg(inner) | Expr::Paren(inner) => arg_references_name(inner, name),
+        Expr::Neg(inner, _) | Expr::Not(inner, _) | Expr::Paren(inner, _) => {
+            arg_references_name(inner, name)
+        }
         Expr::Call { args, .. } => args.iter().any(|a| arg_references_name(&a.value, name)),
-        Expr::Array(elems) => elems.iter().any(|e| arg_references_name(e, name)),
+        Expr::Array(elems, _) => elems.iter().any(|e| arg_references_name(e, name)),
         Expr::Ternary {
             condition,
             if_true,
             if_false,
+            ..
         } => {
             arg_references_name(condition, name)
                 || arg_references_name(if_true, name)
                 || arg_references_name(if_false, name)
         }
+        Expr::Pipeline(stages, _) => stages
+            .iter()
+            .flat_map(|s| &s.args)
+            .any(|a| arg_references_name(&a.value, name)),
         _ => false,
     }
 }
@@ -496,23 +568,24 @@ pub fn arg_references_name(expr: &Expr, name: &str) -> bool {
 
 /// Check that builtin calls in layer pipelines don't exceed the
 /// declared parameter count.
-pub fn check_arity(cinematic: &Cinematic) -> Vec<String> {
+pub fn check_arity(cinematic: &Cinematic) -> Vec<Diagnostic> {
     let mut warnings = Vec::new();
     for layer in &cinematic.layers {
         if let LayerBody::Pipeline(stages) = &layer.body {
-            for stage in stages {
+            walk_stages(stages, &mut |stage| {
                 if let Some(builtin) = builtins::lookup(&stage.name) {
                     let max_params = builtin.params.len();
                     let positional_count =
                         stage.args.iter().filter(|a| a.name.is_none()).count();
                     if positional_count > max_params {
-                        warnings.push(format!(
+                        warnings.push(stage.locate_diagnostic(Diagnostic::warning(format!(
                             "layer '{}': '{}' accepts {} parameters, but {} were given",
                             layer.name, stage.name, max_params, positional_count
-                        ));
+                        ))
+                        .with_code(ErrorCode::E007)));
                     }
                 }
-            }
+            });
         }
     }
 
@@ -528,10 +601,11 @@ pub fn check_arity(cinematic: &Cinematic) -> Vec<String> {
                 let positional_count =
                     stage.args.iter().filter(|a| a.name.is_none()).count();
                 if positional_count > max_params {
-                    warnings.push(format!(
+                    warnings.push(stage.locate_diagnostic(Diagnostic::warning(format!(
                         "lens '{}': '{}' accepts {} parameters, but {} were given",
                         lens_name, stage.name, max_params, positional_count
-                    ));
+                    ))
+                    .with_code(ErrorCode::E007)));
                 }
             }
         }
@@ -546,21 +620,22 @@ pub fn check_arity(cinematic: &Cinematic) -> Vec<String> {
 mod tests {
     use super::*;
 
-    fn num(v: f64) -> Expr { Expr::Number(v) }
-    fn ident(s: &str) -> Expr { Expr::Ident(s.to_string()) }
+    fn num(v: f64) -> Expr { Expr::Number(v, Span::default()) }
+    fn ident(s: &str) -> Expr { Expr::Ident(s.to_string(), Span::default()) }
 
     fn binop(op: BinOp, l: Expr, r: Expr) -> Expr {
-        Expr::BinOp { op, left: Box::new(l), right: Box::new(r) }
+        Expr::BinOp { op, left: Box::new(l), right: Box::new(r), span: Span::default() }
     }
 
     fn call(name: &str, vals: Vec<Expr>) -> Expr {
         Expr::Call {
             name: name.to_string(),
-            args: vals.into_iter().map(|v| Arg { name: None, value: v }).collect(),
+            args: vals.into_iter().map(|v| Arg { name: None, value: v, span: Span::default() }).collect(),
+            span: Span::default(),
         }
     }
 
-    fn pos_arg(v: Expr) -> Arg { Arg { name: None, value: v } }
+    fn pos_arg(v: Expr) -> Arg { Arg { name: None, value: v, span: Span::default() } }
 
     #[test]
     fn fold_literal_add() {
@@ -607,31 +682,55 @@ mod tests {
     fn fold_multiply_by_one() {
         let mut e = binop(BinOp::Mul, ident("x"), num(1.0));
         constant_fold(&mut e);
-        assert!(matches!(e, Expr::Ident(ref s) if s == "x"));
+        assert!(matches!(e, Expr::Ident(ref s, _) if s == "x"));
     }
 
     #[test]
     fn fold_add_zero() {
         let mut e = binop(BinOp::Add, ident("y"), num(0.0));
         constant_fold(&mut e);
-        assert!(matches!(e, Expr::Ident(ref s) if s == "y"));
+        assert!(matches!(e, Expr::Ident(ref s, _) if s == "y"));
     }
 
     #[test]
     fn fold_identity_div() {
         let mut e = binop(BinOp::Div, ident("z"), num(1.0));
         constant_fold(&mut e);
-        assert!(matches!(e, Expr::Ident(ref s) if s == "z"));
+        assert!(matches!(e, Expr::Ident(ref s, _) if s == "z"));
     }
 
     #[test]
     fn fold_neg_literal() {
-        let mut e = Expr::Neg(Box::new(num(7.0)));
+        let mut e = Expr::Neg(Box::new(num(7.0)), Span::default());
         let n = constant_fold(&mut e);
         assert_eq!(n, 1);
         assert_eq!(as_number(&e), Some(-7.0));
     }
 
+    #[test]
+    fn fold_comparisons_logic_and_mod() {
+        let fold = |src: &str| {
+            let tokens = crate::lexer::lex(src).unwrap();
+            let mut e = crate::parser::Parser::new(tokens).parse_expr().unwrap();
+            constant_fold(&mut e);
+            e
+        };
+        assert_eq!(as_number(&fold("3 >= 3")), Some(1.0));
+        assert_eq!(as_number(&fold("2 == 3 || 1 != 1")), Some(0.0));
+        assert_eq!(as_number(&fold("!(1 < 2) && 1")), Some(0.0));
+        assert_eq!(as_number(&fold("-7 % 3")), Some(-1.0));
+        assert!(matches!(fold("x % 0"), Expr::BinOp { op: BinOp::Mod, .. }));
+        assert!(matches!(fold("x >= 1 && 2 > 1"), Expr::BinOp { op: BinOp::And, .. }));
+    }
+
+    #[test]
+    fn fold_ternary_with_constant_condition() {
+        let tokens = crate::lexer::lex("2 > 1 ? x : y").unwrap();
+        let mut e = crate::parser::Parser::new(tokens).parse_expr().unwrap();
+        constant_fold(&mut e);
+        assert!(matches!(&e, Expr::Ident(name, _) if name == "x"));
+    }
+
     #[test]
     fn fold_sin_zero() {
         let mut e = call("sin", vec![num(0.0)]);
@@ -660,8 +759,8 @@ mod tests {
     #[test]
     fn noop_translate_zero() {
         let mut stages = vec![
-            Stage { name: "translate".into(), args: vec![pos_arg(num(0.0)), pos_arg(num(0.0))] },
-            Stage { name: "blur".into(), args: vec![pos_arg(num(5.0))] },
+            Stage { name: "translate".into(), args: vec![pos_arg(num(0.0)), pos_arg(num(0.0))], span: Span::default(), expanded_from: vec![] },
+            Stage { name: "blur".into(), args: vec![pos_arg(num(5.0))], span: Span::default(), expanded_from: vec![] },
         ];
         let n = eliminate_noop_stages(&mut stages);
         assert_eq!(n, 1);
@@ -672,7 +771,7 @@ mod tests {
     #[test]
     fn noop_scale_one() {
         let mut stages = vec![
-            Stage { name: "scale".into(), args: vec![pos_arg(num(1.0))] },
+            Stage { name: "scale".into(), args: vec![pos_arg(num(1.0))], span: Span::default(), expanded_from: vec![] },
         ];
         let n = eliminate_noop_stages(&mut stages);
         assert_eq!(n, 1);
@@ -682,7 +781,7 @@ mod tests {
     #[test]
     fn noop_rotate_zero() {
         let mut stages = vec![
-            Stage { name: "rotate".into(), args: vec![pos_arg(num(0.0))] },
+            Stage { name: "rotate".into(), args: vec![pos_arg(num(0.0))], span: Span::default(), expanded_from: vec![] },
         ];
         let n = eliminate_noop_stages(&mut stages);
         assert_eq!(n, 1);
@@ -692,8 +791,8 @@ mod tests {
     #[test]
     fn not_noop_nonzero() {
         let mut stages = vec![
-            Stage { name: "translate".into(), args: vec![pos_arg(num(1.0)), pos_arg(num(0.0))] },
-            Stage { name: "scale".into(), args: vec![pos_arg(num(2.0))] },
+            Stage { name: "translate".into(), args: vec![pos_arg(num(1.0)), pos_arg(num(0.0))], span: Span::default(), expanded_from: vec![] },
+            Stage { name: "scale".into(), args: vec![pos_arg(num(2.0))], span: Span::default(), expanded_from: vec![] },
         ];
         let n = eliminate_noop_stages(&mut stages);
         assert_eq!(n, 0);
@@ -703,16 +802,15 @@ mod tests {
     #[test]
     fn not_noop_dynamic() {
         let mut stages = vec![
-            Stage { name: "rotate".into(), args: vec![pos_arg(ident("angle"))] },
+            Stage { name: "rotate".into(), args: vec![pos_arg(ident("angle"))], span: Span::default(), expanded_from: vec![] },
         ];
         let n = eliminate_noop_stages(&mut stages);
         assert_eq!(n, 0);
         assert_eq!(stages.len(), 1);
     }
 
-    #[test]
-    fn dead_uniform_detection() {
-        let cinematic = Cinematic {
+    fn dead_uniform_cinematic() -> Cinematic {
+        Cinematic {
             name: "test".into(),
             layers: vec![
                 // Uniform layer declaring "color" and "unused_param"
@@ -721,20 +819,24 @@ mod tests {
                     opts: vec![],
                     memory: None,
                     cast: None,
+                    blend: None,
                     body: LayerBody::Params(vec![
                         Param {
                             name: "color".into(),
                             value: num(1.0),
                             modulation: None,
                             temporal_ops: vec![],
+                            span: Span::default(),
                         },
                         Param {
                             name: "unused_param".into(),
                             value: num(0.5),
                             modulation: None,
                             temporal_ops: vec![],
+                            span: Span::default(),
                         },
                     ]),
+                    span: Span::default(),
                 },
                 // Pipeline layer that only references "color"
                 Layer {
@@ -742,12 +844,16 @@ mod tests {
                     opts: vec![],
                     memory: None,
                     cast: None,
+                    blend: None,
                     body: LayerBody::Pipeline(vec![
                         Stage {
                             name: "fill".into(),
                             args: vec![pos_arg(ident("color"))],
+                            span: Span::default(),
+                            expanded_from: vec![],
                         },
                     ]),
+                    span: Span::default(),
                 },
             ],
             arcs: vec![],
@@ -759,12 +865,32 @@ mod tests {
             lenses: vec![],
             react: None,
             defines: vec![],
-        };
+            span: Span::default(),
+        }
+    }
 
+    #[test]
+    fn dead_uniform_detection() {
+        let cinematic = dead_uniform_cinematic();
         let dead = find_dead_uniforms(&cinematic);
         assert_eq!(dead, vec!["unused_param".to_string()]);
     }
 
+    #[test]
+    fn dead_uniform_elimination() {
+        let mut cinematic = dead_uniform_cinematic();
+        let removed = eliminate_dead_uniforms(&mut cinematic);
+        assert_eq!(removed, vec!["unused_param".to_string()]);
+        match &cinematic.layers[0].body {
+            LayerBody::Params(params) => {
+                assert_eq!(params.len(), 1);
+                assert_eq!(params[0].name, "color");
+            }
+            _ => panic!("expected params layer"),
+        }
+        assert!(find_dead_uniforms(&cinematic).is_empty());
+    }
+
     // ── Strength Reduction Tests ─────────────────────────
 
     #[test]
@@ -802,7 +928,7 @@ mod tests {
         // x / 1.0 → x (identity, not strength reduction)
         let mut e = binop(BinOp::Div, ident("z"), num(1.0));
         constant_fold(&mut e);
-        assert!(matches!(e, Expr::Ident(ref s) if s == "z"));
+        assert!(matches!(e, Expr::Ident(ref s, _) if s == "z"));
     }
 
     // ── Dead Define Elimination Tests ────────────────────
@@ -823,6 +949,7 @@ mod tests {
             lenses: vec![],
             react: None,
             defines,
+            span: Span::default(),
         }
     }
 
@@ -834,20 +961,24 @@ mod tests {
                 opts: vec![],
                 memory: None,
                 cast: None,
+                blend: None,
                 body: LayerBody::Pipeline(vec![
-                    Stage { name: "circle".into(), args: vec![] },
+                    Stage { name: "circle".into(), args: vec![], span: Span::default(), expanded_from: vec![] },
                 ]),
+                span: Span::default(),
             }],
             vec![
                 DefineBlock {
                     name: "used_shape".into(),
                     params: vec![],
-                    body: vec![Stage { name: "circle".into(), args: vec![] }],
+                    body: vec![Stage { name: "circle".into(), args: vec![], span: Span::default(), expanded_from: vec![] }],
+                    span: Span::default(),
                 },
                 DefineBlock {
                     name: "dead_shape".into(),
                     params: vec![],
-                    body: vec![Stage { name: "star".into(), args: vec![] }],
+                    body: vec![Stage { name: "star".into(), args: vec![], span: Span::default(), expanded_from: vec![] }],
+                    span: Span::default(),
                 },
             ],
         );
@@ -866,21 +997,25 @@ mod tests {
                 opts: vec![],
                 memory: None,
                 cast: None,
+                blend: None,
                 body: LayerBody::Pipeline(vec![
-                    Stage { name: "my_shape".into(), args: vec![] },
-                    Stage { name: "glow".into(), args: vec![] },
+                    Stage { name: "my_shape".into(), args: vec![], span: Span::default(), expanded_from: vec![] },
+                    Stage { name: "glow".into(), args: vec![], span: Span::default(), expanded_from: vec![] },
                 ]),
+                span: Span::default(),
             }],
             vec![
                 DefineBlock {
                     name: "my_shape".into(),
                     params: vec![],
-                    body: vec![Stage { name: "circle".into(), args: vec![] }],
+                    body: vec![Stage { name: "circle".into(), args: vec![], span: Span::default(), expanded_from: vec![] }],
+                    span: Span::default(),
                 },
                 DefineBlock {
                     name: "unused_shape".into(),
                     params: vec![],
-                    body: vec![Stage { name: "star".into(), args: vec![] }],
+                    body: vec![Stage { name: "star".into(), args: vec![], span: Span::default(), expanded_from: vec![] }],
+                    span: Span::default(),
                 },
             ],
         );
@@ -899,9 +1034,11 @@ mod tests {
                 opts: vec![],
                 memory: None,
                 cast: None,
+                blend: None,
                 body: LayerBody::Pipeline(vec![
-                    Stage { name: "circle".into(), args: vec![] },
+                    Stage { name: "circle".into(), args: vec![], span: Span::default(), expanded_from: vec![] },
                 ]),
+                span: Span::default(),
             }],
             vec![],
         );
@@ -920,16 +1057,17 @@ mod tests {
                 name: "my_effect".into(),
                 params: vec![],
                 body: vec![
-                    Stage { name: "circle".into(), args: vec![] },
-                    Stage { name: "nonexistent_fn".into(), args: vec![] },
+                    Stage { name: "circle".into(), args: vec![], span: Span::default(), expanded_from: vec![] },
+                    Stage { name: "nonexistent_fn".into(), args: vec![], span: Span::default(), expanded_from: vec![] },
                 ],
+                span: Span::default(),
             }],
         );
 
         let warnings = check_define_semantics(&cin);
         assert_eq!(warnings.len(), 1);
-        assert!(warnings[0].contains("unknown function 'nonexistent_fn'"));
-        assert!(warnings[0].contains("define 'my_effect'"));
+        assert!(warnings[0].message.contains("unknown function 'nonexistent_fn'"));
+        assert!(warnings[0].message.contains("define 'my_effect'"));
     }
 
     #[test]
@@ -943,15 +1081,19 @@ mod tests {
                     name: "circle".into(),
                     args: vec![Arg {
                         name: None,
-                        value: Expr::Ident("r".into()),
+                        value: Expr::Ident("r".into(), Span::default()),
+                        span: Span::default(),
                     }],
+                    span: Span::default(),
+                    expanded_from: vec![],
                 }],
+                span: Span::default(),
             }],
         );
 
         let warnings = check_define_semantics(&cin);
         assert_eq!(warnings.len(), 1);
-        assert!(warnings[0].contains("parameter 'unused_color' is never used"));
+        assert!(warnings[0].message.contains("parameter 'unused_color' is never used"));
     }
 
     #[test]
@@ -965,9 +1107,13 @@ mod tests {
                     name: "circle".into(),
                     args: vec![Arg {
                         name: None,
-                        value: Expr::Ident("r".into()),
+                        value: Expr::Ident("r".into(), Span::default()),
+                        span: Span::default(),
                     }],
+                    span: Span::default(),
+                    expanded_from: vec![],
                 }],
+                span: Span::default(),
             }],
         );
 
@@ -984,15 +1130,17 @@ mod tests {
                 DefineBlock {
                     name: "base_shape".into(),
                     params: vec![],
-                    body: vec![Stage { name: "circle".into(), args: vec![] }],
+                    body: vec![Stage { name: "circle".into(), args: vec![], span: Span::default(), expanded_from: vec![] }],
+                    span: Span::default(),
                 },
                 DefineBlock {
                     name: "styled_shape".into(),
                     params: vec![],
                     body: vec![
-                        Stage { name: "base_shape".into(), args: vec![] },
-                        Stage { name: "glow".into(), args: vec![] },
+                        Stage { name: "base_shape".into(), args: vec![], span: Span::default(), expanded_from: vec![] },
+                        Stage { name: "glow".into(), args: vec![], span: Span::default(), expanded_from: vec![] },
                     ],
+                    span: Span::default(),
                 },
             ],
         );
@@ -1012,6 +1160,7 @@ mod tests {
                 opts: vec![],
                 memory: None,
                 cast: None,
+                blend: None,
                 body: LayerBody::Pipeline(vec![Stage {
                     name: "circle".into(),
                     args: vec![
@@ -1019,14 +1168,17 @@ mod tests {
                         pos_arg(num(0.3)),
                         pos_arg(num(0.1)),
                     ],
+                    span: Span::default(),
+                    expanded_from: vec![],
                 }]),
+                span: Span::default(),
             }],
             vec![],
         );
 
         let warnings = check_arity(&cin);
         assert_eq!(warnings.len(), 1);
-        assert!(warnings[0].contains("'circle' accepts 1 parameters, but 3 were given"));
+        assert!(warnings[0].message.contains("'circle' accepts 1 parameters, but 3 were given"));
     }
 
     #[test]
@@ -1038,6 +1190,7 @@ mod tests {
                 opts: vec![],
                 memory: None,
                 cast: None,
+                blend: None,
                 body: LayerBody::Pipeline(vec![Stage {
                     name: "tint".into(),
                     args: vec![
@@ -1045,7 +1198,10 @@ mod tests {
                         pos_arg(num(0.5)),
                         pos_arg(num(0.0)),
                     ],
+                    span: Span::default(),
+                    expanded_from: vec![],
                 }]),
+                span: Span::default(),
             }],
             vec![],
         );
@@ -1063,13 +1219,17 @@ mod tests {
                 opts: vec![],
                 memory: None,
                 cast: None,
+                blend: None,
                 body: LayerBody::Pipeline(vec![Stage {
                     name: "circle".into(),
                     args: vec![
-                        Arg { name: Some("radius".into()), value: num(0.5) },
-                        Arg { name: Some("extra".into()), value: num(0.3) },
+                        Arg { name: Some("radius".into()), value: num(0.5), span: Span::default() },
+                        Arg { name: Some("extra".into()), value: num(0.3), span: Span::default() },
                     ],
+                    span: Span::default(),
+                    expanded_from: vec![],
                 }]),
+                span: Span::default(),
             }],
             vec![],
         );
@@ -1087,6 +1247,7 @@ mod tests {
                 opts: vec![],
                 memory: None,
                 cast: None,
+                blend: None,
                 body: LayerBody::Pipeline(vec![Stage {
                     name: "my_custom_define".into(),
                     args: vec![
@@ -1095,7 +1256,10 @@ mod tests {
                         pos_arg(num(3.0)),
                         pos_arg(num(4.0)),
                     ],
+                    span: Span::default(),
+                    expanded_from: vec![],
                 }]),
+                span: Span::default(),
             }],
             vec![],
         );
@@ -1108,16 +1272,17 @@ mod tests {
 
     #[test]
     fn arg_references_simple_ident() {
-        assert!(arg_references_name(&Expr::Ident("x".into()), "x"));
-        assert!(!arg_references_name(&Expr::Ident("y".into()), "x"));
+        assert!(arg_references_name(&Expr::Ident("x".into(), Span::default()), "x"));
+        assert!(!arg_references_name(&Expr::Ident("y".into(), Span::default()), "x"));
     }
 
     #[test]
     fn arg_references_nested_in_binop() {
         let expr = Expr::BinOp {
             op: BinOp::Add,
-            left: Box::new(Expr::Number(1.0)),
-            right: Box::new(Expr::Ident("target".into())),
+            left: Box::new(Expr::Number(1.0, Span::default())),
+            right: Box::new(Expr::Ident("target".into(), Span::default())),
+            span: Span::default(),
         };
         assert!(arg_references_name(&expr, "target"));
         assert!(!arg_references_name(&expr, "other"));
@@ -1129,8 +1294,10 @@ mod tests {
             name: "sin".into(),
             args: vec![Arg {
                 name: None,
-                value: Expr::Ident("angle".into()),
+                value: Expr::Ident("angle".into(), Span::default()),
+                span: Span::default(),
             }],
+            span: Span::default(),
         };
         assert!(arg_references_name(&expr, "angle"));
         assert!(!arg_references_name(&expr, "radius"));
@@ -1141,6 +1308,7 @@ mod tests {
         let expr = Expr::DottedIdent {
             object: "audio".into(),
             field: "bass".into(),
+            span: Span::default(),
         };
         assert!(arg_references_name(&expr, "audio"));
         assert!(!arg_references_name(&expr, "bass"));
@@ -1149,9 +1317,10 @@ mod tests {
     #[test]
     fn arg_references_in_ternary() {
         let expr = Expr::Ternary {
-            condition: Box::new(Expr::Ident("flag".into())),
-            if_true: Box::new(Expr::Number(1.0)),
-            if_false: Box::new(Expr::Ident("fallback".into())),
+            condition: Box::new(Expr::Ident("flag".into(), Span::default())),
+            if_true: Box::new(Expr::Number(1.0, Span::default())),
+            if_false: Box::new(Expr::Ident("fallback".into(), Span::default())),
+            span: Span::default(),
         };
         assert!(arg_references_name(&expr, "flag"));
         assert!(arg_references_name(&expr, "fallback"));
@@ -1168,15 +1337,18 @@ mod tests {
                 opts: vec![],
                 memory: None,
                 cast: None,
+                blend: None,
                 body: LayerBody::Pipeline(vec![
-                    Stage { name: "circle".into(), args: vec![pos_arg(num(0.5))] },
-                    Stage { name: "glow".into(), args: vec![] },
+                    Stage { name: "circle".into(), args: vec![pos_arg(num(0.5))], span: Span::default(), expanded_from: vec![] },
+                    Stage { name: "glow".into(), args: vec![], span: Span::default(), expanded_from: vec![] },
                 ]),
+                span: Span::default(),
             }],
             vec![DefineBlock {
                 name: "dead_define".into(),
                 params: vec![],
-                body: vec![Stage { name: "star".into(), args: vec![] }],
+                body: vec![Stage { name: "star".into(), args: vec![], span: Span::default(), expanded_from: vec![] }],
+                span: Span::default(),
             }],
         );
 
@@ -4,6 +4,7 @@
 // messages and straightforward recovery.
 
 use crate::ast::*;
+use crate::codegen::{color, memory};
 use crate::error::{CompileError, ErrorCode};
 use crate::token::Token;
 
@@ -52,8 +53,23 @@ impl Parser {
         }
     }
 
+    /// Byte offset where the next token starts.
+    fn start(&self) -> usize {
+        self.current_pos().0
+    }
+
+    /// Span from `start` to the end of the last consumed token.
+    fn span_from(&self, start: usize) -> Span {
+        let end = self
+            .pos
+            .checked_sub(1)
+            .and_then(|i| self.tokens.get(i))
+            .map_or(start, |(_, _, end)| *end);
+        Span::new(start, end.max(start))
+    }
+
     fn check(&self, expected: &Token) -> bool {
-        self.peek().map_or(false, |t| std::mem::discriminant(t) == std::mem::discriminant(expected))
+        self.peek().is_some_and(|t| std::mem::discriminant(t) == std::mem::discriminant(expected))
     }
 
     /// Returns `true` if the next token is an identifier that is NOT a
@@ -295,6 +311,7 @@ impl Parser {
     // ======================================================================
 
     fn parse_import(&mut self) -> Result<Import, CompileError> {
+        let start = self.start();
         self.expect(&Token::Import)?;
         let path = self.expect_string()?;
 
@@ -306,11 +323,11 @@ impl Parser {
                 self.advance();
                 names.push(self.expect_ident_or_all()?);
             }
-            Ok(Import { path, alias: String::new(), exposed: names })
+            Ok(Import { path, alias: String::new(), exposed: names, span: self.span_from(start) })
         } else {
             self.expect(&Token::As)?;
             let alias = self.expect_ident()?;
-            Ok(Import { path, alias, exposed: Vec::new() })
+            Ok(Import { path, alias, exposed: Vec::new(), span: self.span_from(start) })
         }
     }
 
@@ -319,6 +336,7 @@ impl Parser {
     // ======================================================================
 
     fn parse_cinematic(&mut self) -> Result<Cinematic, CompileError> {
+        let start = self.start();
         self.expect(&Token::Cinematic)?;
         let name = self.expect_string()?;
         self.expect(&Token::LBrace)?;
@@ -438,7 +456,7 @@ impl Parser {
 
         Ok(Cinematic {
             name, layers, arcs, resonates, listen, voice, score, gravity,
-            lenses, react, defines,
+            lenses, react, defines, span: self.span_from(start),
         })
     }
 
@@ -447,6 +465,7 @@ impl Parser {
     // ======================================================================
 
     fn parse_layer(&mut self) -> Result<Layer, CompileError> {
+        let start = self.start();
         self.expect(&Token::Layer)?;
 
         // Optional layer name: `layer myname { ... }` or `layer { ... }`
@@ -485,17 +504,34 @@ impl Parser {
         opts.extend(inline_params);
         self.expect(&Token::RBrace)?;
 
-        Ok(Layer { name, opts, memory, cast, body })
+        // `blend:` is a mode name, not a uniform; `opacity` and the memory
+        // feedback options get per-layer uniforms so arcs can target them as
+        // `layer.opacity`, `layer.feedback_zoom`, ...
+        let blend = opts
+            .iter()
+            .position(|p| p.name == "blend" || p.name == "blend_mode")
+            .map(|i| opts.remove(i));
+        for param in &mut opts {
+            let per_layer = param.name == "opacity"
+                || memory::FEEDBACK_OPTIONS.iter().any(|(option, _)| *option == param.name);
+            if per_layer {
+                param.name = format!("{name}_{}", param.name);
+            }
+        }
+
+        Ok(Layer { name, opts, memory, cast, blend, body, span: self.span_from(start) })
     }
 
     fn parse_layer_opts(&mut self) -> Result<Vec<Param>, CompileError> {
         self.expect(&Token::LParen)?;
         let mut params = Vec::new();
         while !self.at_end() && !self.check(&Token::RParen) {
+            let start = self.start();
             let name = self.expect_ident()?;
             self.expect(&Token::Colon)?;
             let value = self.parse_expr()?;
-            params.push(Param { name, value, modulation: None, temporal_ops: vec![] });
+            let span = self.span_from(start);
+            params.push(Param { name, value, modulation: None, temporal_ops: vec![], span });
             if !self.check(&Token::RParen) {
                 self.expect(&Token::Comma)?;
             }
@@ -550,6 +586,7 @@ impl Parser {
         // Parse remaining entries as inline params
         let mut params = Vec::new();
         while !self.at_end() && !self.check(&Token::RBrace) {
+            let start = self.start();
             let name = self.expect_ident()?;
             self.expect(&Token::Colon)?;
             let value = self.parse_expr()?;
@@ -563,7 +600,8 @@ impl Parser {
 
             let temporal_ops = self.parse_temporal_ops()?;
 
-            params.push(Param { name, value, modulation, temporal_ops });
+            let span = self.span_from(start);
+            params.push(Param { name, value, modulation, temporal_ops, span });
         }
 
         Ok((body, params))
@@ -572,6 +610,7 @@ impl Parser {
     fn parse_param_list(&mut self) -> Result<LayerBody, CompileError> {
         let mut params = Vec::new();
         while !self.at_end() && !self.check(&Token::RBrace) {
+            let start = self.start();
             let name = self.expect_ident()?;
             self.expect(&Token::Colon)?;
             let value = self.parse_expr()?;
@@ -585,7 +624,8 @@ impl Parser {
 
             let temporal_ops = self.parse_temporal_ops()?;
 
-            params.push(Param { name, value, modulation, temporal_ops });
+            let span = self.span_from(start);
+            params.push(Param { name, value, modulation, temporal_ops, span });
         }
         Ok(LayerBody::Params(params))
     }
@@ -636,11 +676,12 @@ impl Parser {
     }
 
     pub fn parse_stage(&mut self) -> Result<Stage, CompileError> {
+        let start = self.start();
         let name = self.expect_ident()?;
         self.expect(&Token::LParen)?;
         let args = self.parse_arg_list()?;
         self.expect(&Token::RParen)?;
-        Ok(Stage { name, args })
+        Ok(new_stage(name, args, self.span_from(start)))
     }
 
     fn parse_arg_list(&mut self) -> Result<Vec<Arg>, CompileError> {
@@ -657,6 +698,7 @@ impl Parser {
     }
 
     fn parse_arg(&mut self) -> Result<Arg, CompileError> {
+        let start = self.start();
         // Named arg: IDENT COLON expr  or  positional: expr
         // Lookahead for IDENT ':'
         if let (Some((Token::Ident(_), _, _)), Some((Token::Colon, _, _))) =
@@ -664,12 +706,37 @@ impl Parser {
         {
             let name = self.expect_ident()?;
             self.expect(&Token::Colon)?;
-            let value = self.parse_expr()?;
-            Ok(Arg { name: Some(name), value })
+            let value = self.parse_arg_value()?;
+            Ok(Arg { name: Some(name), value, span: self.span_from(start) })
         } else {
-            let value = self.parse_expr()?;
-            Ok(Arg { name: None, value })
+            let value = self.parse_arg_value()?;
+            Ok(Arg { name: None, value, span: self.span_from(start) })
+        }
+    }
+
+    /// An argument value: an expression, or a nested `a() | b()` stage chain
+    /// when the expression is a call followed by a pipe.
+    fn parse_arg_value(&mut self) -> Result<Expr, CompileError> {
+        let start = self.start();
+        let (line, col) = self.current_pos();
+        let value = self.parse_expr()?;
+        if !matches!(self.peek(), Some(Token::Pipe)) {
+            return Ok(value);
+        }
+        if !matches!(value, Expr::Call { .. }) {
+            return Err(CompileError::ParseError {
+                message: "only stage calls can be piped inside an argument".into(),
+                line,
+                col,
+                code: Some(ErrorCode::E003),
+            });
+        }
+        let mut stages = vec![call_to_stage(value)];
+        while matches!(self.peek(), Some(Token::Pipe)) {
+            self.advance();
+            stages.push(self.parse_stage()?);
         }
+        Ok(Expr::Pipeline(stages, self.span_from(start)))
     }
 
     // ======================================================================
@@ -677,6 +744,7 @@ impl Parser {
     // ======================================================================
 
     fn parse_arc(&mut self) -> Result<ArcBlock, CompileError> {
+        let start = self.start();
         self.expect(&Token::Arc)?;
         self.expect(&Token::LBrace)?;
         let mut entries = Vec::new();
@@ -690,7 +758,7 @@ impl Parser {
             }
         }
         self.expect(&Token::RBrace)?;
-        Ok(ArcBlock { entries })
+        Ok(ArcBlock { entries, span: self.span_from(start) })
     }
 
     /// Check if the next tokens form a timestamp pattern: Number Colon Number
@@ -726,6 +794,7 @@ impl Parser {
         self.expect(&Token::LBrace)?;
         let mut entries = Vec::new();
         while !self.at_end() && !self.check(&Token::RBrace) {
+            let start = self.start();
             let target = self.expect_ident()?;
 
             if matches!(self.peek(), Some(Token::Colon)) {
@@ -738,6 +807,7 @@ impl Parser {
                     to: value,
                     duration: Duration::Seconds(0.0),
                     easing: None,
+                    span: self.span_from(start),
                 });
             } else if matches!(self.peek(), Some(Token::Arrow)) {
                 // Transition: `param -> value [ease(name)] over duration`
@@ -765,10 +835,11 @@ impl Parser {
 
                 entries.push(ArcEntry {
                     target,
-                    from: Expr::Number(0.0), // implicit from previous value
+                    from: Expr::Number(0.0, Span::default()), // implicit from previous value
                     to,
                     duration,
                     easing,
+                    span: self.span_from(start),
                 });
             }
         }
@@ -778,6 +849,7 @@ impl Parser {
 
     fn parse_arc_entry(&mut self) -> Result<ArcEntry, CompileError> {
         // dotted_ident : from_expr -> to_expr over duration [easing]
+        let start = self.start();
         let target = self.parse_dotted_ident()?;
         self.expect(&Token::Colon)?;
         let from = self.parse_expr()?;
@@ -790,7 +862,7 @@ impl Parser {
         } else {
             None
         };
-        Ok(ArcEntry { target, from, to, duration, easing })
+        Ok(ArcEntry { target, from, to, duration, easing, span: self.span_from(start) })
     }
 
     fn parse_dotted_ident(&mut self) -> Result<String, CompileError> {
@@ -846,6 +918,7 @@ impl Parser {
     // ======================================================================
 
     fn parse_resonate(&mut self) -> Result<ResonateBlock, CompileError> {
+        let start = self.start();
         self.expect(&Token::Resonate)?;
         self.expect(&Token::LBrace)?;
         let mut entries = Vec::new();
@@ -853,11 +926,12 @@ impl Parser {
             entries.push(self.parse_resonate_entry()?);
         }
         self.expect(&Token::RBrace)?;
-        Ok(ResonateBlock { entries })
+        Ok(ResonateBlock { entries, span: self.span_from(start) })
     }
 
     fn parse_resonate_entry(&mut self) -> Result<ResonateEntry, CompileError> {
         // source -> target.field * weight
+        let start = self.start();
         let source = self.expect_ident()?;
         self.expect(&Token::Arrow)?;
         let target = self.expect_ident()?;
@@ -865,7 +939,7 @@ impl Parser {
         let field = self.expect_ident()?;
         self.expect(&Token::Star)?;
         let weight = self.parse_expr()?;
-        Ok(ResonateEntry { source, target, field, weight })
+        Ok(ResonateEntry { source, target, field, weight, span: self.span_from(start) })
     }
 
     // ======================================================================
@@ -873,10 +947,12 @@ impl Parser {
     // ======================================================================
 
     fn parse_listen(&mut self) -> Result<ListenBlock, CompileError> {
+        let block_start = self.start();
         self.expect(&Token::Listen)?;
         self.expect(&Token::LBrace)?;
         let mut signals = Vec::new();
         while !self.at_end() && !self.check(&Token::RBrace) {
+            let start = self.start();
             let name = self.expect_ident()?;
             self.expect(&Token::Colon)?;
             let algorithm = self.expect_ident()?;
@@ -885,20 +961,22 @@ impl Parser {
             } else {
                 vec![]
             };
-            signals.push(ListenSignal { name, algorithm, params });
+            signals.push(ListenSignal { name, algorithm, params, span: self.span_from(start) });
         }
         self.expect(&Token::RBrace)?;
-        Ok(ListenBlock { signals })
+        Ok(ListenBlock { signals, span: self.span_from(block_start) })
     }
 
     fn parse_listen_params(&mut self) -> Result<Vec<Param>, CompileError> {
         self.expect(&Token::LParen)?;
         let mut params = Vec::new();
         while !self.at_end() && !self.check(&Token::RParen) {
+            let start = self.start();
             let name = self.expect_ident()?;
             self.expect(&Token::Colon)?;
             let value = self.parse_expr()?;
-            params.push(Param { name, value, modulation: None, temporal_ops: vec![] });
+            let span = self.span_from(start);
+            params.push(Param { name, value, modulation: None, temporal_ops: vec![], span });
             if !self.check(&Token::RParen) {
                 self.expect(&Token::Comma)?;
             }
@@ -912,10 +990,12 @@ impl Parser {
     // ======================================================================
 
     fn parse_voice(&mut self) -> Result<VoiceBlock, CompileError> {
+        let block_start = self.start();
         self.expect(&Token::Voice)?;
         self.expect(&Token::LBrace)?;
         let mut nodes = Vec::new();
         while !self.at_end() && !self.check(&Token::RBrace) {
+            let start = self.start();
             let name = self.expect_ident()?;
             self.expect(&Token::Colon)?;
             let kind = self.expect_ident()?;
@@ -924,10 +1004,10 @@ impl Parser {
             } else {
                 vec![]
             };
-            nodes.push(VoiceNode { name, kind, params });
+            nodes.push(VoiceNode { name, kind, params, span: self.span_from(start) });
         }
         self.expect(&Token::RBrace)?;
-        Ok(VoiceBlock { nodes })
+        Ok(VoiceBlock { nodes, span: self.span_from(block_start) })
     }
 
     // ======================================================================
@@ -935,6 +1015,7 @@ impl Parser {
     // ======================================================================
 
     fn parse_score(&mut self) -> Result<ScoreBlock, CompileError> {
+        let start = self.start();
         self.expect(&Token::Score)?;
 
         // Parse optional tempo: `tempo(120)`
@@ -1013,7 +1094,7 @@ impl Parser {
             }
         }
         self.expect(&Token::RBrace)?;
-        Ok(ScoreBlock { tempo_bpm, motifs, phrases, sections, arrange })
+        Ok(ScoreBlock { tempo_bpm, motifs, phrases, sections, arrange, span: self.span_from(start) })
     }
 
     // ======================================================================
@@ -1021,6 +1102,7 @@ impl Parser {
     // ======================================================================
 
     fn parse_breed(&mut self) -> Result<BreedBlock, CompileError> {
+        let start = self.start();
         self.expect(&Token::Breed)?;
         let name = self.expect_string()?;
         self.expect(&Token::From)?;
@@ -1072,7 +1154,7 @@ impl Parser {
             }
         }
         self.expect(&Token::RBrace)?;
-        Ok(BreedBlock { name, parents, inherit_rules, mutations })
+        Ok(BreedBlock { name, parents, inherit_rules, mutations, span: self.span_from(start) })
     }
 
     // ======================================================================
@@ -1080,10 +1162,11 @@ impl Parser {
     // ======================================================================
 
     fn parse_gravity(&mut self) -> Result<GravityBlock, CompileError> {
+        let start = self.start();
         self.expect(&Token::Gravity)?;
         self.expect(&Token::LBrace)?;
 
-        let mut force_law = Expr::Number(1.0); // default: constant attraction
+        let mut force_law = Expr::Number(1.0, Span::default()); // default: constant attraction
         let mut damping = 0.99;
         let mut bounds = BoundsMode::Reflect;
 
@@ -1130,7 +1213,7 @@ impl Parser {
             }
         }
         self.expect(&Token::RBrace)?;
-        Ok(GravityBlock { force_law, damping, bounds })
+        Ok(GravityBlock { force_law, damping, bounds, span: self.span_from(start) })
     }
 
     // ======================================================================
@@ -1138,6 +1221,7 @@ impl Parser {
     // ======================================================================
 
     fn parse_project(&mut self) -> Result<ProjectBlock, CompileError> {
+        let start = self.start();
         self.expect(&Token::Project)?;
 
         // Mode identifier: flat, dome, cube, led
@@ -1187,7 +1271,7 @@ impl Parser {
         }
         self.expect(&Token::RBrace)?;
 
-        Ok(ProjectBlock { mode, source, params })
+        Ok(ProjectBlock { mode, source, params, span: self.span_from(start) })
     }
 
     // ======================================================================
@@ -1195,6 +1279,7 @@ impl Parser {
     // ======================================================================
 
     fn parse_lens(&mut self) -> Result<Lens, CompileError> {
+        let lens_start = self.start();
         self.expect(&Token::Lens)?;
         let name = if matches!(self.peek(), Some(Token::Ident(_))) {
             Some(self.expect_ident()?)
@@ -1213,24 +1298,37 @@ impl Parser {
                     if let Some((Token::Colon, _, _)) = self.tokens.get(self.pos + 1) {
                         self.advance(); // consume "post"
                         self.advance(); // consume ":"
-                        // Parse pipeline stages
-                        post.push(self.parse_stage()?);
-                        while matches!(self.peek(), Some(Token::Pipe)) {
+                        // Parse pipeline stages: `a | b` or `[a, b]`
+                        if self.check(&Token::LBracket) {
                             self.advance();
+                            while !self.at_end() && !self.check(&Token::RBracket) {
+                                post.push(self.parse_stage()?);
+                                if self.check(&Token::Comma) {
+                                    self.advance();
+                                }
+                            }
+                            self.expect(&Token::RBracket)?;
+                        } else {
                             post.push(self.parse_stage()?);
+                            while matches!(self.peek(), Some(Token::Pipe)) {
+                                self.advance();
+                                post.push(self.parse_stage()?);
+                            }
                         }
                         continue;
                     }
                 }
             }
             // Otherwise parse as param
+            let start = self.start();
             let name = self.expect_ident()?;
             self.expect(&Token::Colon)?;
             let value = self.parse_expr()?;
-            properties.push(Param { name, value, modulation: None, temporal_ops: vec![] });
+            let span = self.span_from(start);
+            properties.push(Param { name, value, modulation: None, temporal_ops: vec![], span });
         }
         self.expect(&Token::RBrace)?;
-        Ok(Lens { name, properties, post })
+        Ok(Lens { name, properties, post, span: self.span_from(lens_start) })
     }
 
     // ======================================================================
@@ -1238,17 +1336,19 @@ impl Parser {
     // ======================================================================
 
     fn parse_react(&mut self) -> Result<ReactBlock, CompileError> {
+        let block_start = self.start();
         self.expect(&Token::React)?;
         self.expect(&Token::LBrace)?;
         let mut reactions = Vec::new();
         while !self.at_end() && !self.check(&Token::RBrace) {
+            let start = self.start();
             let signal = self.parse_expr()?;
             self.expect(&Token::Arrow)?;
             let action = self.parse_expr()?;
-            reactions.push(Reaction { signal, action });
+            reactions.push(Reaction { signal, action, span: self.span_from(start) });
         }
         self.expect(&Token::RBrace)?;
-        Ok(ReactBlock { reactions })
+        Ok(ReactBlock { reactions, span: self.span_from(block_start) })
     }
 
     // ======================================================================
@@ -1256,6 +1356,7 @@ impl Parser {
     // ======================================================================
 
     fn parse_define(&mut self) -> Result<DefineBlock, CompileError> {
+        let start = self.start();
         self.expect(&Token::Define)?;
         let name = self.expect_ident()?;
 
@@ -1280,7 +1381,7 @@ impl Parser {
         }
         self.expect(&Token::RBrace)?;
 
-        Ok(DefineBlock { name, params, body })
+        Ok(DefineBlock { name, params, body, span: self.span_from(start) })
     }
 
     // ======================================================================
@@ -1288,7 +1389,8 @@ impl Parser {
     // ======================================================================
 
     pub fn parse_expr(&mut self) -> Result<Expr, CompileError> {
-        let expr = self.parse_comparison()?;
+        let start = self.start();
+        let expr = self.parse_or()?;
         // Ternary: expr ? if_true : if_false
         if matches!(self.peek(), Some(Token::Question)) {
             self.advance();
@@ -1299,18 +1401,77 @@ impl Parser {
                 condition: Box::new(expr),
                 if_true: Box::new(if_true),
                 if_false: Box::new(if_false),
+                span: self.span_from(start),
             })
         } else {
             Ok(expr)
         }
     }
 
+    fn parse_or(&mut self) -> Result<Expr, CompileError> {
+        let start = self.start();
+        let mut left = self.parse_and()?;
+        while matches!(self.peek(), Some(Token::OrOr)) {
+            self.advance();
+            let right = self.parse_and()?;
+            left = Expr::BinOp {
+                op: BinOp::Or,
+                left: Box::new(left),
+                right: Box::new(right),
+                span: self.span_from(start),
+            };
+        }
+        Ok(left)
+    }
+
+    fn parse_and(&mut self) -> Result<Expr, CompileError> {
+        let start = self.start();
+        let mut left = self.parse_equality()?;
+        while matches!(self.peek(), Some(Token::AndAnd)) {
+            self.advance();
+            let right = self.parse_equality()?;
+            left = Expr::BinOp {
+                op: BinOp::And,
+                left: Box::new(left),
+                right: Box::new(right),
+                span: self.span_from(start),
+            };
+        }
+        Ok(left)
+    }
+
+    fn parse_equality(&mut self) -> Result<Expr, CompileError> {
+        let start = self.start();
+        let mut left = self.parse_comparison()?;
+        while matches!(self.peek(), Some(Token::EqEq) | Some(Token::NotEq)) {
+            let op = match self.advance() {
+                Some(Token::EqEq) => BinOp::Eq,
+                Some(Token::NotEq) => BinOp::Ne,
+                _ => unreachable!(),
+            };
+            let right = self.parse_comparison()?;
+            left = Expr::BinOp {
+                op,
+                left: Box::new(left),
+                right: Box::new(right),
+                span: self.span_from(start),
+            };
+        }
+        Ok(left)
+    }
+
     fn parse_comparison(&mut self) -> Result<Expr, CompileError> {
+        let start = self.start();
         let mut left = self.parse_additive()?;
-        while matches!(self.peek(), Some(Token::Greater) | Some(Token::Less)) {
+        while matches!(
+            self.peek(),
+            Some(Token::Greater) | Some(Token::Less) | Some(Token::GreaterEq) | Some(Token::LessEq)
+        ) {
             let op = match self.advance() {
                 Some(Token::Greater) => BinOp::Gt,
                 Some(Token::Less) => BinOp::Lt,
+                Some(Token::GreaterEq) => BinOp::Ge,
+                Some(Token::LessEq) => BinOp::Le,
                 _ => unreachable!(),
             };
             let right = self.parse_additive()?;
@@ -1318,12 +1479,14 @@ impl Parser {
                 op,
                 left: Box::new(left),
                 right: Box::new(right),
+                span: self.span_from(start),
             };
         }
         Ok(left)
     }
 
     fn parse_additive(&mut self) -> Result<Expr, CompileError> {
+        let start = self.start();
         let mut left = self.parse_term()?;
         while matches!(self.peek(), Some(Token::Plus) | Some(Token::Minus)) {
             let op = match self.advance() {
@@ -1336,17 +1499,20 @@ impl Parser {
                 op,
                 left: Box::new(left),
                 right: Box::new(right),
+                span: self.span_from(start),
             };
         }
         Ok(left)
     }
 
     fn parse_term(&mut self) -> Result<Expr, CompileError> {
+        let start = self.start();
         let mut left = self.parse_factor()?;
-        while matches!(self.peek(), Some(Token::Star) | Some(Token::Slash)) {
+        while matches!(self.peek(), Some(Token::Star) | Some(Token::Slash) | Some(Token::Percent)) {
             let op = match self.advance() {
                 Some(Token::Star) => BinOp::Mul,
                 Some(Token::Slash) => BinOp::Div,
+                Some(Token::Percent) => BinOp::Mod,
                 _ => unreachable!(),
             };
             let right = self.parse_factor()?;
@@ -1354,12 +1520,14 @@ impl Parser {
                 op,
                 left: Box::new(left),
                 right: Box::new(right),
+                span: self.span_from(start),
             };
         }
         Ok(left)
     }
 
     fn parse_factor(&mut self) -> Result<Expr, CompileError> {
+        let start = self.start();
         let base = self.parse_atom()?;
         if matches!(self.peek(), Some(Token::Caret)) {
             self.advance();
@@ -1368,6 +1536,7 @@ impl Parser {
                 op: BinOp::Pow,
                 left: Box::new(base),
                 right: Box::new(exp),
+                span: self.span_from(start),
             })
         } else {
             Ok(base)
@@ -1375,15 +1544,45 @@ impl Parser {
     }
 
     fn parse_atom(&mut self) -> Result<Expr, CompileError> {
+        let start = self.start();
         let (line, col) = self.current_pos();
         match self.peek().cloned() {
-            Some(Token::Float(v)) => { self.advance(); Ok(Expr::Number(v)) }
-            Some(Token::Integer(v)) => { self.advance(); Ok(Expr::Number(v as f64)) }
-            Some(Token::Seconds(v)) => { self.advance(); Ok(Expr::Duration(Duration::Seconds(v))) }
-            Some(Token::Millis(v)) => { self.advance(); Ok(Expr::Duration(Duration::Millis(v))) }
-            Some(Token::Bars(v)) => { self.advance(); Ok(Expr::Duration(Duration::Bars(v))) }
-            Some(Token::Degrees(v)) => { self.advance(); Ok(Expr::Number(v)) }
-            Some(Token::StringLit(s)) => { self.advance(); Ok(Expr::String(s)) }
+            Some(Token::Float(v)) => {
+                self.advance();
+                Ok(Expr::Number(v, self.span_from(start)))
+            }
+            Some(Token::Integer(v)) => {
+                self.advance();
+                Ok(Expr::Number(v as f64, self.span_from(start)))
+            }
+            Some(Token::Seconds(v)) => {
+                self.advance();
+                Ok(Expr::Duration(Duration::Seconds(v), self.span_from(start)))
+            }
+            Some(Token::Millis(v)) => {
+                self.advance();
+                Ok(Expr::Duration(Duration::Millis(v), self.span_from(start)))
+            }
+            Some(Token::Bars(v)) => {
+                self.advance();
+                Ok(Expr::Duration(Duration::Bars(v), self.span_from(start)))
+            }
+            Some(Token::Degrees(v)) => {
+                self.advance();
+                Ok(Expr::Number(v, self.span_from(start)))
+            }
+            Some(Token::StringLit(s)) => {
+                self.advance();
+                Ok(Expr::String(s, self.span_from(start)))
+            }
+            Some(Token::HexColor(hex)) => {
+                self.advance();
+                let mut rgb = color::to_expr(color::parse_hex(&hex).unwrap_or_default());
+                if let Expr::Array(_, span) = &mut rgb {
+                    *span = self.span_from(start);
+                }
+                Ok(rgb)
+            }
             Some(Token::Ident(name)) => {
                 self.advance();
                 // call: IDENT '(' args ')'
@@ -1391,22 +1590,22 @@ impl Parser {
                     self.advance();
                     let args = self.parse_arg_list()?;
                     self.expect(&Token::RParen)?;
-                    Ok(Expr::Call { name, args })
+                    Ok(Expr::Call { name, args, span: self.span_from(start) })
                 }
                 // dotted: IDENT '.' IDENT
                 else if matches!(self.peek(), Some(Token::Dot)) {
                     self.advance();
                     let field = self.expect_ident()?;
-                    Ok(Expr::DottedIdent { object: name, field })
+                    Ok(Expr::DottedIdent { object: name, field, span: self.span_from(start) })
                 } else {
-                    Ok(Expr::Ident(name))
+                    Ok(Expr::Ident(name, self.span_from(start)))
                 }
             }
             Some(Token::LParen) => {
                 self.advance();
                 let inner = self.parse_expr()?;
                 self.expect(&Token::RParen)?;
-                Ok(Expr::Paren(Box::new(inner)))
+                Ok(Expr::Paren(Box::new(inner), self.span_from(start)))
             }
             Some(Token::LBracket) => {
                 self.advance();
@@ -1419,12 +1618,17 @@ impl Parser {
                     }
                 }
                 self.expect(&Token::RBracket)?;
-                Ok(Expr::Array(elems))
+                Ok(Expr::Array(elems, self.span_from(start)))
             }
             Some(Token::Minus) => {
                 self.advance();
                 let inner = self.parse_factor()?;
-                Ok(Expr::Neg(Box::new(inner)))
+                Ok(Expr::Neg(Box::new(inner), self.span_from(start)))
+            }
+            Some(Token::Bang) => {
+                self.advance();
+                let inner = self.parse_factor()?;
+                Ok(Expr::Not(Box::new(inner), self.span_from(start)))
             }
             Some(tok) => Err(CompileError::ParseError {
                 message: format!("unexpected token `{tok}` in expression"),
@@ -1442,6 +1646,38 @@ impl Parser {
     }
 }
 
+fn new_stage(name: String, mut args: Vec<Arg>, span: Span) -> Stage {
+    lift_combinator_args(&name, &mut args);
+    Stage { name, args, span, expanded_from: Vec::new() }
+}
+
+/// Turn a lone stage call in a combinator's `a`/`b` slot into a one-stage
+/// pipeline, so `union(circle(), box())` needs no pipes.
+pub(crate) fn lift_combinator_args(name: &str, args: &mut [Arg]) {
+    if !crate::builtins::is_combinator(name) {
+        return;
+    }
+    for (i, arg) in args.iter_mut().enumerate() {
+        let slot = match arg.name.as_deref() {
+            Some(n) => n == "a" || n == "b",
+            None => i < 2,
+        };
+        if slot && matches!(arg.value, Expr::Call { .. }) {
+            let span = arg.value.span();
+            let call = std::mem::replace(&mut arg.value, Expr::Pipeline(Vec::new(), span));
+            arg.value = Expr::Pipeline(vec![call_to_stage(call)], span);
+        }
+    }
+}
+
+/// Reinterpret a parsed call expression as a pipeline stage.
+fn call_to_stage(call: Expr) -> Stage {
+    match call {
+        Expr::Call { name, args, span } => new_stage(name, args, span),
+        _ => unreachable!("call_to_stage on a non-call expression"),
+    }
+}
+
 #[cfg(test)]
 #[path = "parser_tests.rs"]
 mod tests;
\ No newline at end of file
@@ -220,8 +220,8 @@ fn parse_expr_precedence() {
     let mut p = Parser::new(tokens);
     let expr = p.parse_expr().expect("should parse");
     match &expr {
-        Expr::BinOp { op: BinOp::Add, left, right } => {
-            assert!(matches!(left.as_ref(), Expr::Number(n) if (*n - 1.0).abs() < f64::EPSILON));
+        Expr::BinOp { op: BinOp::Add, left, right, .. } => {
+            assert!(matches!(left.as_ref(), Expr::Number(n, _) if (*n - 1.0).abs() < f64::EPSILON));
             assert!(matches!(right.as_ref(), Expr::BinOp { op: BinOp::Mul, .. }));
         }
         other => panic!("unexpected expr: {other:?}"),
@@ -370,7 +370,7 @@ fn parse_array_expr() {
     let mut p = Parser::new(tokens);
     let expr = p.parse_expr().expect("should parse array");
     match expr {
-        Expr::Array(elems) => assert_eq!(elems.len(), 3),
+        Expr::Array(elems, _) => assert_eq!(elems.len(), 3),
         other => panic!("expected array, got {other:?}"),
     }
 }
@@ -380,11 +380,12 @@ fn parse_array_expr() {
 // ===================================================================
 
 #[test]
+#[allow(clippy::approx_constant)]
 fn parse_negative_number() {
     let tokens = vec![s(Token::Minus), s(Token::Float(3.14))];
     let mut p = Parser::new(tokens);
     let expr = p.parse_expr().expect("should parse negative");
-    assert!(matches!(expr, Expr::Neg(_)));
+    assert!(matches!(expr, Expr::Neg(_, _)));
 }
 
 // ===================================================================
@@ -402,7 +403,7 @@ fn parse_call_expr() {
     let mut p = Parser::new(tokens);
     let expr = p.parse_expr().expect("should parse call");
     match expr {
-        Expr::Call { name, args } => {
+        Expr::Call { name, args, .. } => {
             assert_eq!(name, "sin");
             assert_eq!(args.len(), 1);
         }
@@ -424,7 +425,7 @@ fn parse_dotted_ident_expr() {
     let mut p = Parser::new(tokens);
     let expr = p.parse_expr().expect("should parse dotted ident");
     match expr {
-        Expr::DottedIdent { object, field } => {
+        Expr::DottedIdent { object, field, .. } => {
             assert_eq!(object, "layer");
             assert_eq!(field, "opacity");
         }
@@ -846,6 +847,26 @@ fn parse_lens_block() {
     assert_eq!(lens.post[1].name, "vignette");
 }
 
+#[test]
+fn parse_lens_post_list() {
+    let tokens = vec![
+        s(Token::Cinematic), s(Token::StringLit("t".into())), s(Token::LBrace),
+        s(Token::Lens), s(Token::LBrace),
+        s(Token::Ident("post".into())), s(Token::Colon), s(Token::LBracket),
+        s(Token::Ident("bloom".into())), s(Token::LParen), s(Token::Float(0.8)), s(Token::RParen),
+        s(Token::Comma),
+        s(Token::Ident("grain".into())), s(Token::LParen), s(Token::Float(0.02)), s(Token::RParen),
+        s(Token::RBracket),
+        s(Token::RBrace),
+        s(Token::RBrace),
+    ];
+    let mut p = Parser::new(tokens);
+    let prog = p.parse().expect("should parse lens post list");
+    let lens = &prog.cinematics[0].lenses[0];
+    assert_eq!(lens.post.len(), 2);
+    assert_eq!(lens.post[1].name, "grain");
+}
+
 // ===================================================================
 // Phase 2: React block
 // ===================================================================
@@ -918,10 +939,12 @@ fn parse_ternary_expr() {
     let mut p = Parser::new(tokens);
     let expr = p.parse_expr().expect("should parse ternary");
     match &expr {
-        Expr::Ternary { condition, if_true, if_false } => {
+        Expr::Ternary { condition, if_true, if_false, .. } => {
             assert!(matches!(condition.as_ref(), Expr::BinOp { op: BinOp::Gt, .. }));
-            assert!(matches!(if_true.as_ref(), Expr::Number(v) if (*v - 1.0).abs() < f64::EPSILON));
-            assert!(matches!(if_false.as_ref(), Expr::Number(v) if v.abs() < f64::EPSILON));
+            assert!(
+                matches!(if_true.as_ref(), Expr::Number(v, _) if (*v - 1.0).abs() < f64::EPSILON)
+            );
+            assert!(matches!(if_false.as_ref(), Expr::Number(v, _) if v.abs() < f64::EPSILON));
         }
         other => panic!("expected ternary, got {other:?}"),
     }
@@ -954,6 +977,64 @@ fn parse_comparison_gt_lt() {
     assert!(matches!(&expr, Expr::BinOp { op: BinOp::Lt, .. }));
 }
 
+fn parse_src_expr(src: &str) -> Expr {
+    let mut p = Parser::new(crate::lexer::lex(src).unwrap());
+    p.parse_expr().expect("should parse")
+}
+
+#[test]
+fn parse_logic_binds_looser_than_comparison() {
+    // a >= 1 && b != 2 || !c  =>  Or(And(Ge, Ne), Not(c))
+    match parse_src_expr("a >= 1 && b != 2 || !c") {
+        Expr::BinOp { op: BinOp::Or, left, right, .. } => {
+            match left.as_ref() {
+                Expr::BinOp { op: BinOp::And, left, right, .. } => {
+                    assert!(matches!(left.as_ref(), Expr::BinOp { op: BinOp::Ge, .. }));
+                    assert!(matches!(right.as_ref(), Expr::BinOp { op: BinOp::Ne, .. }));
+                }
+                other => panic!("expected &&, got {other:?}"),
+            }
+            assert!(matches!(right.as_ref(), Expr::Not(_, _)));
+        }
+        other => panic!("expected ||, got {other:?}"),
+    }
+}
+
+#[test]
+fn parse_equality_binds_looser_than_comparison() {
+    // a < b == c <= d  =>  Eq(Lt, Le)
+    match parse_src_expr("a < b == c <= d") {
+        Expr::BinOp { op: BinOp::Eq, left, right, .. } => {
+            assert!(matches!(left.as_ref(), Expr::BinOp { op: BinOp::Lt, .. }));
+            assert!(matches!(right.as_ref(), Expr::BinOp { op: BinOp::Le, .. }));
+        }
+        other => panic!("expected ==, got {other:?}"),
+    }
+}
+
+#[test]
+fn parse_mod_binds_like_mul_and_not_like_neg() {
+    // a + b % c * d  =>  Add(a, Mul(Mod(b, c), d))
+    match parse_src_expr("a + b % c * d") {
+        Expr::BinOp { op: BinOp::Add, right, .. } => match right.as_ref() {
+            Expr::BinOp { op: BinOp::Mul, left, .. } => {
+                assert!(matches!(left.as_ref(), Expr::BinOp { op: BinOp::Mod, .. }));
+            }
+            other => panic!("expected *, got {other:?}"),
+        },
+        other => panic!("expected +, got {other:?}"),
+    }
+    // !a > b  =>  Gt(Not(a), b)
+    match parse_src_expr("!a > b") {
+        Expr::BinOp { op: BinOp::Gt, left, .. } => {
+            assert!(matches!(left.as_ref(), Expr::Not(_, _)))
+        }
+        other => panic!("expected >, got {other:?}"),
+    }
+    // The ternary condition takes the whole logic expression
+    assert!(matches!(parse_src_expr("a > 1 || b ? 1.0 : 0.0"), Expr::Ternary { .. }));
+}
+
 // ===================================================================
 // Unnamed layers
 // ===================================================================
@@ -1053,3 +1134,109 @@ fn parse_import_expose_all() {
     assert_eq!(prog.imports.len(), 1);
     assert_eq!(prog.imports[0].exposed, vec!["ALL".to_string()]);
 }
+
+// ===================================================================
+// Source spans
+// ===================================================================
+
+#[test]
+fn nodes_carry_source_spans() {
+    let src = r#"cinematic "t" {
+  layer main { fn: circle(0.3) | glow(intensity: pulse(2.0)) }
+}"#;
+    let mut p = Parser::new(crate::lexer::lex(src).unwrap());
+    let prog = p.parse().unwrap();
+    let text = |span: Span| &src[span.start..span.end];
+
+    let cin = &prog.cinematics[0];
+    assert_eq!(text(cin.span), src);
+    let layer = &cin.layers[0];
+    assert_eq!(text(layer.span), "layer main { fn: circle(0.3) | glow(intensity: pulse(2.0)) }");
+    let LayerBody::Pipeline(stages) = &layer.body else { panic!("expected pipeline") };
+    assert_eq!(text(stages[0].span), "circle(0.3)");
+    assert_eq!(text(stages[0].args[0].span), "0.3");
+    assert_eq!(text(stages[1].args[0].span), "intensity: pulse(2.0)");
+    let Expr::Call { span, .. } = &stages[1].args[0].value else { panic!("expected call") };
+    assert_eq!(text(*span), "pulse(2.0)");
+}
+
+#[test]
+fn expressions_carry_source_spans() {
+    let src = r#"cinematic "t" { layer { fn: circle(-(0.2 + audio.bass) * 2 > 1 ? 0.1 : 0.3) } }"#;
+    let stages = pipeline_of(src);
+    let text = |span: Span| &src[span.start..span.end];
+
+    let Expr::Ternary { condition, if_false, span, .. } = &stages[0].args[0].value else {
+        panic!("expected ternary")
+    };
+    assert_eq!(text(*span), "-(0.2 + audio.bass) * 2 > 1 ? 0.1 : 0.3");
+    assert_eq!(text(if_false.span()), "0.3");
+    let Expr::BinOp { left: product, .. } = condition.as_ref() else { panic!("expected compare") };
+    assert_eq!(text(product.span()), "-(0.2 + audio.bass) * 2");
+    let Expr::BinOp { left: neg, .. } = product.as_ref() else { panic!("expected product") };
+    let Expr::Neg(paren, _) = neg.as_ref() else { panic!("expected negation") };
+    assert_eq!(text(paren.span()), "(0.2 + audio.bass)");
+    let Expr::Paren(sum, _) = paren.as_ref() else { panic!("expected paren") };
+    let Expr::BinOp { right: signal, .. } = sum.as_ref() else { panic!("expected sum") };
+    assert_eq!(text(signal.span()), "audio.bass");
+}
+
+// ===================================================================
+// Combinator sub-pipelines
+// ===================================================================
+
+fn pipeline_of(src: &str) -> Vec<Stage> {
+    let mut p = Parser::new(crate::lexer::lex(src).unwrap());
+    let mut prog = p.parse().unwrap();
+    match prog.cinematics.remove(0).layers.remove(0).body {
+        LayerBody::Pipeline(stages) => stages,
+        _ => panic!("expected pipeline"),
+    }
+}
+
+#[test]
+fn parse_combinator_sub_pipelines() {
+    let stages = pipeline_of(
+        r#"cinematic "t" { layer { union(circle(0.2), translate(0.3, 0.0) | box(0.1, 0.1)) | glow(2.0) } }"#,
+    );
+    assert_eq!(stages.len(), 2);
+    let union = &stages[0];
+    assert_eq!(union.name, "union");
+    let subs: Vec<Vec<&str>> = union
+        .sub_pipelines()
+        .map(|sub| sub.iter().map(|s| s.name.as_str()).collect())
+        .collect();
+    // The lone `circle(0.2)` call is lifted into a one-stage pipeline
+    assert_eq!(subs, [vec!["circle"], vec!["translate", "box"]]);
+}
+
+#[test]
+fn parse_named_and_nested_combinators() {
+    let stages = pipeline_of(
+        r#"cinematic "t" { layer { morph(b: box(0.1, 0.1), a: union(circle(0.1), ring(0.2, 0.01)), t: 0.5) | glow(1.0) } }"#,
+    );
+    let morph = &stages[0];
+    let Expr::Pipeline(a, _) = &morph.args[1].value else { panic!("expected pipeline for a") };
+    assert_eq!(a[0].name, "union");
+    assert_eq!(a[0].sub_pipelines().count(), 2);
+    assert!(matches!(morph.args[2].value, Expr::Number(_, _)));
+
+    let mut names = Vec::new();
+    walk_stages(&stages, &mut |s| names.push(s.name.as_str()));
+    assert_eq!(names, ["morph", "box", "union", "circle", "ring", "glow"]);
+}
+
+#[test]
+fn calls_outside_combinator_slots_stay_expressions() {
+    let stages = pipeline_of(
+        r#"cinematic "t" { layer { circle(sin(time)) | glow(1.0) } }"#,
+    );
+    assert!(matches!(stages[0].args[0].value, Expr::Call { .. }));
+}
+
+#[test]
+fn piping_a_non_call_argument_is_an_error() {
+    let tokens = crate::lexer::lex(r#"cinematic "t" { layer { union(0.2 | circle(), box()) } }"#);
+    let err = Parser::new(tokens.unwrap()).parse().unwrap_err();
+    assert!(err.to_string().contains("only stage calls can be piped"), "{err}");
+}
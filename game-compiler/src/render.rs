@@ -0,0 +1,388 @@
+//! Offline frame export for `game render`.
+//!
+//! Drives the CPU reference renderer ([`crate::cpu`]) on a fixed clock —
+//! frame `i` is rendered at exactly `start + i / fps` — with scripted
+//! uniforms and optional arc/score playback, then encodes the frames as a
+//! PNG sequence, an animated GIF or APNG, or a Y4M stream for ffmpeg.
+
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use crate::ast::Cinematic;
+use crate::codegen::{self, signals};
+use crate::cpu::{CpuRenderer, Keyframes, Timeline};
+use crate::snapshot;
+
+/// Container for rendered frames.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RenderFormat {
+    /// One PNG per frame in a directory.
+    PngSequence,
+    Gif,
+    Apng,
+    /// Uncompressed YUV 4:4:4 (`ffmpeg -i out.y4m ...`).
+    Y4m,
+}
+
+impl RenderFormat {
+    /// Guess the format from an output path: `.gif`, `.apng`/`.png`,
+    /// `.y4m` or `-` (stdout); anything else is a PNG sequence directory.
+    pub fn from_path(path: &Path) -> Self {
+        if path == Path::new("-") {
+            return Self::Y4m;
+        }
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("gif") => Self::Gif,
+            Some("apng" | "png") => Self::Apng,
+            Some("y4m") => Self::Y4m,
+            _ => Self::PngSequence,
+        }
+    }
+}
+
+/// Clock, resolution and scripted inputs for one render.
+#[derive(Debug, Clone)]
+pub struct RenderSettings {
+    pub width: u32,
+    pub height: u32,
+    pub fps: u32,
+    /// Seconds of output; the frame count is `round(duration * fps)`.
+    pub duration: f32,
+    /// `time` of the first frame.
+    pub start: f32,
+    /// Play the cinematic's `arc` blocks and `score`.
+    pub timeline: bool,
+    /// Keep the shader's alpha in PNG and APNG output instead of
+    /// compositing over black, like a component's `transparent` mode.
+    pub transparent: bool,
+    /// User uniforms scripted over time, by uniform name.
+    pub params: Vec<(String, Keyframes)>,
+}
+
+impl Default for RenderSettings {
+    fn default() -> Self {
+        Self {
+            width: 512,
+            height: 512,
+            fps: 30,
+            duration: 5.0,
+            start: 0.0,
+            timeline: false,
+            transparent: false,
+            params: Vec::new(),
+        }
+    }
+}
+
+impl RenderSettings {
+    /// Frames in the render; any duration gets at least one.
+    pub fn frame_count(&self) -> usize {
+        (self.duration * self.fps as f32).round().max(1.0) as usize
+    }
+
+    /// Script a `data.<field>` value.
+    pub fn set_data(&mut self, field: &str, keys: Keyframes) {
+        self.params.push((signals::data_uniform_name(field), keys));
+    }
+}
+
+/// Parse a `name=spec` scripting argument, where `spec` is a number or
+/// `time:value,...` keyframes.
+pub fn parse_assignment(arg: &str) -> Result<(String, Keyframes), String> {
+    let (name, spec) = arg
+        .split_once('=')
+        .ok_or_else(|| format!("expected name=value, got '{arg}'"))?;
+    Ok((name.trim().to_string(), Keyframes::parse(spec)?))
+}
+
+/// Parse a positive duration in seconds: `5`, `2.5s` or `500ms`.
+pub fn parse_duration(arg: &str) -> Result<f32, String> {
+    let arg = arg.trim();
+    let (number, scale) = match arg.strip_suffix("ms") {
+        Some(ms) => (ms, 0.001),
+        None => (arg.strip_suffix('s').unwrap_or(arg), 1.0),
+    };
+    let secs = number
+        .parse::<f32>()
+        .map(|v| v * scale)
+        .map_err(|_| format!("invalid duration '{arg}'"))?;
+    if secs.is_finite() && secs > 0.0 {
+        Ok(secs)
+    } else {
+        Err(format!("duration must be positive, got '{arg}'"))
+    }
+}
+
+/// Render every frame of `cinematic`, handing each RGBA8 buffer to `sink`
+/// in order. Returns the number of frames rendered.
+pub fn render_frames(
+    cinematic: &Cinematic,
+    settings: &RenderSettings,
+    mut sink: impl FnMut(usize, &[u8]) -> Result<(), String>,
+) -> Result<usize, String> {
+    let mut renderer = CpuRenderer::new(cinematic, settings.width, settings.height)
+        .map_err(|e| e.to_string())?;
+    let timeline = settings
+        .timeline
+        .then(|| Timeline::new(cinematic, &codegen::extract_uniforms(cinematic)));
+
+    let count = settings.frame_count();
+    for i in 0..count {
+        let time = settings.start + i as f32 / settings.fps as f32;
+        let mut uniforms = renderer.default_uniforms();
+        uniforms.time = time;
+        if let Some(timeline) = &timeline {
+            timeline.apply(time, &mut uniforms);
+        }
+        for (name, keys) in &settings.params {
+            uniforms.set_param(name, keys.sample(time));
+        }
+        sink(i, &renderer.render(&uniforms))?;
+    }
+    Ok(count)
+}
+
+/// Render to `output` in `format`. For PNG sequences `output` is a
+/// directory that receives `<name>_00000.png`, ...; `-` streams Y4M to
+/// stdout.
+pub fn render_to(
+    cinematic: &Cinematic,
+    settings: &RenderSettings,
+    format: RenderFormat,
+    output: &Path,
+) -> Result<usize, String> {
+    let (w, h) = (settings.width, settings.height);
+    match format {
+        RenderFormat::PngSequence => {
+            std::fs::create_dir_all(output)
+                .map_err(|e| format!("create {}: {e}", output.display()))?;
+            render_frames(cinematic, settings, |i, frame| {
+                let frame = keep_alpha(frame, settings.transparent);
+                snapshot::save_png(&frame, w, h, &sequence_path(output, &cinematic.name, i))
+            })
+        }
+        RenderFormat::Apng => {
+            let mut encoder = png::Encoder::new(create(output)?, w, h);
+            encoder.set_color(png::ColorType::Rgba);
+            encoder.set_depth(png::BitDepth::Eight);
+            let count = settings.frame_count().max(1) as u32;
+            let fps = settings.fps.clamp(1, u16::MAX as u32) as u16;
+            let png_err = |e: png::EncodingError| format!("APNG: {e}");
+            encoder.set_animated(count, 0).map_err(png_err)?;
+            encoder.set_frame_delay(1, fps).map_err(png_err)?;
+            let mut writer = encoder.write_header().map_err(png_err)?;
+            let frames = render_frames(cinematic, settings, |_, frame| {
+                writer.write_image_data(&keep_alpha(frame, settings.transparent)).map_err(png_err)
+            })?;
+            writer.finish().map_err(png_err)?;
+            Ok(frames)
+        }
+        RenderFormat::Gif => {
+            let (gw, gh) = gif_size(w, h)?;
+            gif_fps(settings.fps)?;
+            let mut encoder = gif::Encoder::new(create(output)?, gw, gh, &[])
+                .map_err(|e| format!("GIF: {e}"))?;
+            encoder.set_repeat(gif::Repeat::Infinite).map_err(|e| format!("GIF: {e}"))?;
+            render_frames(cinematic, settings, |i, frame| {
+                let mut pixels = snapshot::opaque(frame);
+                let mut gif_frame = gif::Frame::from_rgba_speed(gw, gh, &mut pixels, 10);
+                gif_frame.delay = gif_delay(i, settings.fps);
+                encoder.write_frame(&gif_frame).map_err(|e| format!("GIF: {e}"))
+            })
+        }
+        RenderFormat::Y4m => {
+            let out: Box<dyn Write> = if output == Path::new("-") {
+                Box::new(BufWriter::new(std::io::stdout().lock()))
+            } else {
+                Box::new(create(output)?)
+            };
+            let mut y4m = Y4mWriter::new(out, w, h, settings.fps)?;
+            let frames = render_frames(cinematic, settings, |_, frame| y4m.write_frame(frame))?;
+            y4m.finish()?;
+            Ok(frames)
+        }
+    }
+}
+
+/// `dir/<name>_00042.png`
+pub fn sequence_path(dir: &Path, name: &str, index: usize) -> PathBuf {
+    dir.join(format!("{name}_{index:05}.png"))
+}
+
+fn create(path: &Path) -> Result<BufWriter<File>, String> {
+    File::create(path)
+        .map(BufWriter::new)
+        .map_err(|e| format!("create {}: {e}", path.display()))
+}
+
+fn gif_size(w: u32, h: u32) -> Result<(u16, u16), String> {
+    match (u16::try_from(w), u16::try_from(h)) {
+        (Ok(w), Ok(h)) => Ok((w, h)),
+        _ => Err(format!("GIF frames are limited to 65535px, got {w}x{h}")),
+    }
+}
+
+/// GIF delays are whole centiseconds, and browsers play anything under 2cs
+/// at 10cs, so above 50 fps some frames would slow to a crawl.
+const MAX_GIF_FPS: u32 = 50;
+
+fn gif_fps(fps: u32) -> Result<(), String> {
+    if fps > MAX_GIF_FPS {
+        return Err(format!(
+            "GIF output supports at most {MAX_GIF_FPS} fps, got {fps}; \
+             use a lower --fps or render to .apng"
+        ));
+    }
+    Ok(())
+}
+
+/// Frame `i`'s delay in centiseconds, rounded so the total never drifts.
+fn gif_delay(i: usize, fps: u32) -> u16 {
+    let at = |frame: usize| (frame as f64 * 100.0 / fps as f64).round() as u64;
+    (at(i + 1) - at(i)) as u16
+}
+
+/// PNG stores straight alpha: un-premultiply when keeping it.
+fn keep_alpha(frame: &[u8], transparent: bool) -> Vec<u8> {
+    if !transparent {
+        return snapshot::opaque(frame);
+    }
+    frame
+        .chunks_exact(4)
+        .flat_map(|px| {
+            let a = px[3];
+            let straight = |c: u8| match a {
+                0 => 0,
+                _ => ((c as u32 * 255 + a as u32 / 2) / a as u32).min(255) as u8,
+            };
+            [straight(px[0]), straight(px[1]), straight(px[2]), a]
+        })
+        .collect()
+}
+
+/// YUV4MPEG2 writer: 4:4:4, BT.601 limited range, composited over black.
+pub struct Y4mWriter<W: Write> {
+    out: W,
+    planes: Vec<u8>,
+}
+
+impl<W: Write> Y4mWriter<W> {
+    pub fn new(mut out: W, width: u32, height: u32, fps: u32) -> Result<Self, String> {
+        writeln!(out, "YUV4MPEG2 W{width} H{height} F{fps}:1 Ip A1:1 C444")
+            .map_err(|e| format!("Y4M: {e}"))?;
+        Ok(Self { out, planes: Vec::with_capacity(width as usize * height as usize * 3) })
+    }
+
+    pub fn write_frame(&mut self, rgba: &[u8]) -> Result<(), String> {
+        let n = rgba.len() / 4;
+        self.planes.clear();
+        self.planes.resize(n * 3, 0);
+        let (y, uv) = self.planes.split_at_mut(n);
+        let (u, v) = uv.split_at_mut(n);
+        for (i, px) in rgba.chunks_exact(4).enumerate() {
+            let [r, g, b] = [px[0], px[1], px[2]].map(|c| c as f32 / 255.0);
+            y[i] = (16.0 + 65.481 * r + 128.553 * g + 24.966 * b).round() as u8;
+            u[i] = (128.0 - 37.797 * r - 74.203 * g + 112.0 * b).round() as u8;
+            v[i] = (128.0 + 112.0 * r - 93.786 * g - 18.214 * b).round() as u8;
+        }
+        self.out
+            .write_all(b"FRAME\n")
+            .and_then(|_| self.out.write_all(&self.planes))
+            .map_err(|e| format!("Y4M: {e}"))
+    }
+
+    pub fn finish(mut self) -> Result<W, String> {
+        self.out.flush().map_err(|e| format!("Y4M: {e}"))?;
+        Ok(self.out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cinematic(source: &str) -> Cinematic {
+        crate::compile_to_ast(source).unwrap().cinematics.remove(0)
+    }
+
+    fn settings(width: u32, height: u32, fps: u32, duration: f32) -> RenderSettings {
+        RenderSettings { width, height, fps, duration, ..Default::default() }
+    }
+
+    #[test]
+    fn frames_use_a_fixed_clock() {
+        let cin = cinematic(r#"cinematic "c" { layer main { circle(0.3) | glow(1.0) } }"#);
+        let mut sizes = Vec::new();
+        let count = render_frames(&cin, &settings(8, 4, 10, 0.5), |i, frame| {
+            sizes.push((i, frame.len()));
+            Ok(())
+        })
+        .unwrap();
+        assert_eq!(count, 5);
+        assert_eq!(sizes, (0..5).map(|i| (i, 8 * 4 * 4)).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn scripted_data_animates_frames() {
+        let cin = cinematic(
+            r#"cinematic "c" { layer main { circle(data.r) | shade(1.0, 1.0, 1.0) } }"#,
+        );
+        let mut s = settings(9, 9, 1, 2.0);
+        s.set_data("r", Keyframes::parse("0:0,1:0.5").unwrap());
+        let mut right_of_center = Vec::new();
+        render_frames(&cin, &s, |_, frame| {
+            right_of_center.push(frame[(4 * 9 + 6) * 4]);
+            Ok(())
+        })
+        .unwrap();
+        assert_eq!(right_of_center, vec![0, 255]);
+    }
+
+    #[test]
+    fn y4m_stream_layout() {
+        let mut y4m = Y4mWriter::new(Vec::new(), 2, 1, 24).unwrap();
+        y4m.write_frame(&[255, 255, 255, 255, 0, 0, 0, 255]).unwrap();
+        let bytes = y4m.finish().unwrap();
+        let header = b"YUV4MPEG2 W2 H1 F24:1 Ip A1:1 C444\nFRAME\n";
+        assert_eq!(&bytes[..header.len()], header);
+        assert_eq!(&bytes[header.len()..], &[235, 16, 128, 128, 128, 128]);
+    }
+
+    #[test]
+    fn transparent_frames_keep_straight_alpha() {
+        let premultiplied = [64, 32, 0, 128, 0, 0, 0, 0];
+        assert_eq!(keep_alpha(&premultiplied, true), [128, 64, 0, 128, 0, 0, 0, 0]);
+        assert_eq!(keep_alpha(&premultiplied, false), [64, 32, 0, 255, 0, 0, 0, 255]);
+    }
+
+    #[test]
+    fn gif_delays_do_not_drift() {
+        let total: u32 = (0..30).map(|i| gif_delay(i, 30) as u32).sum();
+        assert_eq!(total, 100);
+    }
+
+    #[test]
+    fn gif_rejects_delays_browsers_would_stretch() {
+        assert!((0..50).all(|i| gif_delay(i, MAX_GIF_FPS) >= 2));
+        assert!(gif_fps(MAX_GIF_FPS).is_ok());
+        let err = gif_fps(60).unwrap_err();
+        assert!(err.contains("at most 50 fps, got 60"), "{err}");
+    }
+
+    #[test]
+    fn parses_cli_values() {
+        assert_eq!(parse_duration("500ms").unwrap(), 0.5);
+        assert_eq!(parse_duration("2s").unwrap(), 2.0);
+        assert_eq!(parse_duration("3").unwrap(), 3.0);
+        assert!(parse_duration("0").is_err());
+        assert!(parse_duration("-1s").is_err());
+        let (name, keys) = parse_assignment("progress=0:0,4:1").unwrap();
+        assert_eq!(name, "progress");
+        assert_eq!(keys.sample(2.0), 0.5);
+        assert!(parse_assignment("progress").is_err());
+        assert_eq!(RenderFormat::from_path(Path::new("out.gif")), RenderFormat::Gif);
+        assert_eq!(RenderFormat::from_path(Path::new("-")), RenderFormat::Y4m);
+        assert_eq!(RenderFormat::from_path(Path::new("frames")), RenderFormat::PngSequence);
+    }
+}
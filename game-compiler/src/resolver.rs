@@ -1,61 +1,120 @@
 //! Module import resolver for GAME programs.
 //!
-//! Resolves `import` declarations by locating files, parsing them, and merging
-//! their defines into the importing program.
+//! Resolves `import` declarations by locating files through a
+//! [`SourceProvider`] (with the embedded stdlib mounted last), parsing them,
+//! and merging their defines into the importing program.
 
-use std::collections::HashSet;
-use std::fs;
+use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 
-use crate::ast::{DefineBlock, Program};
+use crate::adapters::{self, ImportScheme};
+use crate::ast::{DefineBlock, Expr, Program, Span, Stage};
 use crate::error::CompileError;
 use crate::lexer;
 use crate::parser::Parser;
+use crate::source::{FsProvider, MemoryProvider, SourceProvider, StdlibProvider};
 
-/// Resolve all imports in `program`, merging imported defines into the first
-/// cinematic. Drains `program.imports` and recursively resolves transitive
-/// imports.
+/// Resolve all imports in `program` from the filesystem, merging imported
+/// defines into the first cinematic. See [`resolve_imports_with`].
 pub fn resolve_imports(
     program: &mut Program,
     base_dir: &Path,
     lib_dirs: &[PathBuf],
 ) -> Result<(), CompileError> {
-    let mut visited = HashSet::new();
-    if let Ok(canonical) = base_dir.join("__root__").canonicalize() {
-        visited.insert(canonical);
+    resolve_imports_with(program, &FsProvider, base_dir, lib_dirs)
+}
+
+/// Resolve imports against the embedded stdlib only — no filesystem access.
+pub fn resolve_stdlib_imports(program: &mut Program) -> Result<(), CompileError> {
+    resolve_imports_with(program, &MemoryProvider::new(), Path::new(""), &[])
+}
+
+/// Resolve all imports in `program` through `provider`, merging imported
+/// defines into the first cinematic. Drains the file imports from
+/// `program.imports` (URI-schemed adapter imports are left in place) and
+/// recursively resolves transitive imports.
+///
+/// Files are looked up relative to `base_dir`, then in each of `lib_dirs`,
+/// then in the embedded stdlib ([`StdlibProvider`]).
+pub fn resolve_imports_with(
+    program: &mut Program,
+    provider: &dyn SourceProvider,
+    base_dir: &Path,
+    lib_dirs: &[PathBuf],
+) -> Result<(), CompileError> {
+    let provider = WithStdlib(provider);
+    let mut lib_dirs = lib_dirs.to_vec();
+    lib_dirs.push(PathBuf::from(StdlibProvider::ROOT));
+    resolve_recursive(program, &provider, base_dir, &lib_dirs, &mut ImportState::default())
+}
+
+/// `provider` with [`StdlibProvider`] layered underneath.
+struct WithStdlib<'a>(&'a dyn SourceProvider);
+
+impl SourceProvider for WithStdlib<'_> {
+    fn locate(&self, dir: &Path, path: &str) -> Option<PathBuf> {
+        self.0.locate(dir, path).or_else(|| StdlibProvider.locate(dir, path))
+    }
+
+    fn read(&self, id: &Path) -> Result<String, CompileError> {
+        if id.starts_with(StdlibProvider::ROOT) {
+            StdlibProvider.read(id)
+        } else {
+            self.0.read(id)
+        }
     }
-    resolve_recursive(program, base_dir, lib_dirs, &mut visited)
+}
+
+#[derive(Default)]
+struct ImportState {
+    /// Files whose imports are being resolved, outermost first. Reaching one
+    /// of these again is a cycle.
+    stack: Vec<PathBuf>,
+    /// Files already resolved, reused when a second import path reaches
+    /// them (e.g. two modules that both import `ui`).
+    resolved: HashMap<PathBuf, Program>,
 }
 
 fn resolve_recursive(
     program: &mut Program,
+    provider: &dyn SourceProvider,
     base_dir: &Path,
     lib_dirs: &[PathBuf],
-    visited: &mut HashSet<PathBuf>,
+    state: &mut ImportState,
 ) -> Result<(), CompileError> {
-    let imports = std::mem::take(&mut program.imports);
+    let (imports, adapters): (Vec<_>, Vec<_>) = std::mem::take(&mut program.imports)
+        .into_iter()
+        .partition(|i| adapters::parse_uri(&i.path) == ImportScheme::File);
+    program.imports = adapters;
 
     for import in imports {
-        let file_path = find_file(&import.path, base_dir, lib_dirs)?;
-        let canonical = file_path.canonicalize().map_err(|e| {
-            CompileError::validation(format!("cannot canonicalize '{}': {e}", file_path.display()))
-        })?;
+        let id = find_file(provider, &import.path, base_dir, lib_dirs)
+            .map_err(|e| e.with_span(import.span))?;
 
-        if !visited.insert(canonical.clone()) {
+        if state.stack.contains(&id) {
             return Err(CompileError::validation(format!(
                 "circular import detected: '{}'",
                 import.path
-            )));
+            ))
+            .with_span(import.span));
         }
 
-        let source = fs::read_to_string(&canonical)?;
-        let tokens = lexer::lex(&source)?;
-        let mut imported = Parser::new(tokens).parse()?;
-
-        let import_dir = canonical.parent().unwrap_or(base_dir);
-        resolve_recursive(&mut imported, import_dir, lib_dirs, visited)?;
+        if !state.resolved.contains_key(&id) {
+            let source = provider.read(&id)?;
+            let tokens = lexer::lex(&source)?;
+            let mut imported = Parser::new(tokens).parse()?;
+
+            let import_dir = id.parent().unwrap_or(base_dir);
+            state.stack.push(id.clone());
+            // Spans from the imported file are meaningless here; report at the import
+            let result = resolve_recursive(&mut imported, provider, import_dir, lib_dirs, state)
+                .map_err(|e| e.with_span(import.span));
+            state.stack.pop();
+            result?;
+            state.resolved.insert(id.clone(), imported);
+        }
 
-        let defines = collect_defines(&imported, &import);
+        let defines = collect_defines(&state.resolved[&id], &import);
         merge_defines(program, defines, &import)?;
     }
 
@@ -65,21 +124,19 @@ fn resolve_recursive(
 /// Locate a file: try relative to `base_dir`, then each lib dir, with and
 /// without `.game` extension.
 fn find_file(
+    provider: &dyn SourceProvider,
     path: &str,
     base_dir: &Path,
     lib_dirs: &[PathBuf],
 ) -> Result<PathBuf, CompileError> {
-    let candidates = std::iter::once(base_dir.to_path_buf())
-        .chain(lib_dirs.iter().cloned());
+    let candidates = std::iter::once(base_dir).chain(lib_dirs.iter().map(PathBuf::as_path));
 
     for dir in candidates {
-        let direct = dir.join(path);
-        if direct.is_file() {
-            return Ok(direct);
+        if let Some(id) = provider.locate(dir, path) {
+            return Ok(id);
         }
-        let with_ext = dir.join(format!("{path}.game"));
-        if with_ext.is_file() {
-            return Ok(with_ext);
+        if let Some(id) = provider.locate(dir, &format!("{path}.game")) {
+            return Ok(id);
         }
     }
 
@@ -104,6 +161,7 @@ fn collect_defines(imported: &Program, import: &crate::ast::Import) -> Vec<Defin
                 name: format!("{}.{}", import.alias, d.name),
                 params: d.params.clone(),
                 body: d.body.clone(),
+                span: d.span,
             })
             .collect()
     } else if import.exposed.iter().any(|e| e == "ALL") {
@@ -129,7 +187,8 @@ fn merge_defines(
         return Err(CompileError::validation(format!(
             "import '{}': no matching defines found for exposed names {:?}",
             import.path, import.exposed
-        )));
+        ))
+        .with_span(import.span));
     }
 
     if defines.is_empty() {
@@ -142,10 +201,56 @@ fn merge_defines(
         ));
     }
 
-    program.cinematics[0].defines.extend(defines);
+    program.cinematics[0].defines.extend(defines.into_iter().map(|mut define| {
+        anchor_spans(&mut define, import.span);
+        define
+    }));
     Ok(())
 }
 
+/// Imported spans index into another file's source. Point the define at the
+/// `import` statement and clear the rest, so diagnostics fall back to the
+/// call site instead of an unrelated offset in the importing file.
+fn anchor_spans(define: &mut DefineBlock, import_span: Span) {
+    define.span = import_span;
+    define.body.iter_mut().for_each(clear_stage_spans);
+}
+
+fn clear_stage_spans(stage: &mut Stage) {
+    stage.span = Span::default();
+    for arg in &mut stage.args {
+        arg.span = Span::default();
+        clear_expr_spans(&mut arg.value);
+    }
+}
+
+fn clear_expr_spans(expr: &mut Expr) {
+    *expr.span_mut() = Span::default();
+    match expr {
+        Expr::Call { args, .. } => {
+            for arg in args {
+                arg.span = Span::default();
+                clear_expr_spans(&mut arg.value);
+            }
+        }
+        Expr::Paren(inner, _) | Expr::Neg(inner, _) | Expr::Not(inner, _) => {
+            clear_expr_spans(inner)
+        }
+        Expr::BinOp { left, right, .. } => {
+            clear_expr_spans(left);
+            clear_expr_spans(right);
+        }
+        Expr::Array(elems, _) => elems.iter_mut().for_each(clear_expr_spans),
+        Expr::Ternary { condition, if_true, if_false, .. } => {
+            clear_expr_spans(condition);
+            clear_expr_spans(if_true);
+            clear_expr_spans(if_false);
+        }
+        Expr::Pipeline(stages, _) => stages.iter_mut().for_each(clear_stage_spans),
+        _ => {}
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -164,7 +269,7 @@ mod tests {
         let dir = make_temp_dir("rel");
         fs::write(dir.join("utils.game"), "cinematic \"u\" {}").unwrap();
 
-        let result = find_file("utils.game", &dir, &[]);
+        let result = find_file(&FsProvider, "utils.game", &dir, &[]);
         assert!(result.is_ok());
         assert!(result.unwrap().ends_with("utils.game"));
 
@@ -177,7 +282,7 @@ mod tests {
         fs::write(dir.join("helpers.game"), "cinematic \"h\" {}").unwrap();
 
         // Should find "helpers" by appending .game
-        let result = find_file("helpers", &dir, &[]);
+        let result = find_file(&FsProvider, "helpers", &dir, &[]);
         assert!(result.is_ok());
         assert!(result.unwrap().ends_with("helpers.game"));
 
@@ -190,7 +295,7 @@ mod tests {
         let lib = make_temp_dir("lib_std");
         fs::write(lib.join("stdlib.game"), "cinematic \"s\" {}").unwrap();
 
-        let result = find_file("stdlib", &base, &[lib.clone()]);
+        let result = find_file(&FsProvider, "stdlib", &base, std::slice::from_ref(&lib));
         assert!(result.is_ok());
 
         let _ = fs::remove_dir_all(&base);
@@ -215,6 +320,40 @@ mod tests {
         let _ = fs::remove_dir_all(&dir);
     }
 
+    #[test]
+    fn diamond_import_is_not_circular() {
+        let files = MemoryProvider::new()
+            .with_file("a.game", r#"import "ui" as ui  cinematic "a" {}"#)
+            .with_file("b.game", r#"import "ui" as ui  cinematic "b" {}"#)
+            .with_file("ui.game", r#"cinematic "ui" { define dot(r) { circle(r) } }"#);
+        let source = r#"import "a" as a  import "b" as b  cinematic "main" {}"#;
+        let mut program = Parser::new(lexer::lex(source).unwrap()).parse().unwrap();
+
+        resolve_imports_with(&mut program, &files, Path::new(""), &[]).unwrap();
+        let names: Vec<_> = program.cinematics[0]
+            .defines
+            .iter()
+            .map(|d| d.name.as_str())
+            .collect();
+        assert_eq!(names, vec!["a.ui.dot", "b.ui.dot"]);
+    }
+
+    #[test]
+    fn stdlib_spellings_name_the_same_module() {
+        let source = r#"import "ui" expose pulse_dot
+            import "stdlib/ui.game" expose badge
+            cinematic "main" {}"#;
+        let mut program = Parser::new(lexer::lex(source).unwrap()).parse().unwrap();
+
+        resolve_stdlib_imports(&mut program).unwrap();
+        let names: Vec<_> = program.cinematics[0]
+            .defines
+            .iter()
+            .map(|d| d.name.as_str())
+            .collect();
+        assert_eq!(names, vec!["pulse_dot", "badge"]);
+    }
+
     #[test]
     fn expose_all_merges_defines() {
         use crate::ast::*;
@@ -231,6 +370,7 @@ mod tests {
                 path: "lib".into(),
                 alias: String::new(),
                 exposed: vec!["ALL".into()],
+                span: Span::default(),
             }],
             cinematics: vec![Cinematic {
                 name: "main".into(),
@@ -244,6 +384,7 @@ mod tests {
                 lenses: vec![],
                 react: None,
                 defines: vec![],
+                span: Span::default(),
             }],
             breeds: vec![],
             projects: vec![],
@@ -276,4 +417,103 @@ mod tests {
 
         let _ = fs::remove_dir_all(&dir);
     }
+
+    #[test]
+    fn stdlib_import_falls_back_to_embedded() {
+        let dir = make_temp_dir("stdlib_fallback");
+        let source = r#"import "ui" expose progress_ring  cinematic "main" {}"#;
+        let mut program = Parser::new(lexer::lex(source).unwrap()).parse().unwrap();
+
+        resolve_imports(&mut program, &dir, &[]).unwrap();
+        assert_eq!(program.cinematics[0].defines.len(), 1);
+        assert_eq!(program.cinematics[0].defines[0].name, "progress_ring");
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn local_file_shadows_stdlib() {
+        let dir = make_temp_dir("stdlib_shadow");
+        fs::write(
+            dir.join("ui.game"),
+            r#"cinematic "ui" { define progress_ring(r) { circle(r) | glow(1.0) } }"#,
+        ).unwrap();
+        let source = r#"import "ui" expose progress_ring  cinematic "main" {}"#;
+        let mut program = Parser::new(lexer::lex(source).unwrap()).parse().unwrap();
+
+        resolve_imports(&mut program, &dir, &[]).unwrap();
+        assert_eq!(program.cinematics[0].defines[0].params, vec!["r".to_string()]);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn stdlib_only_resolution_rejects_local_files() {
+        let source = r#"import "my_local_lib" as m  cinematic "main" {}"#;
+        let mut program = Parser::new(lexer::lex(source).unwrap()).parse().unwrap();
+        let err = resolve_stdlib_imports(&mut program).unwrap_err();
+        assert!(err.to_string().contains("import not found: 'my_local_lib'"));
+    }
+
+    #[test]
+    fn adapter_imports_are_left_in_place() {
+        let source = r#"import "midi://channel/1" as midi  import "noise" as n  cinematic "main" {}"#;
+        let mut program = Parser::new(lexer::lex(source).unwrap()).parse().unwrap();
+        resolve_stdlib_imports(&mut program).unwrap();
+        assert_eq!(program.imports.len(), 1);
+        assert_eq!(program.imports[0].path, "midi://channel/1");
+        assert!(program.cinematics[0].defines.iter().all(|d| d.name.starts_with("n.")));
+    }
+
+    #[test]
+    fn memory_provider_resolves_transitive_imports() {
+        let files = MemoryProvider::new()
+            .with_file(
+                "/play/shapes.game",
+                r#"import "lib/core" expose dot  cinematic "shapes" { define blob(r) { dot(r) } }"#,
+            )
+            .with_file(
+                "/play/lib/core.game",
+                r#"cinematic "core" { define dot(r) { circle(r) | glow(2.0) } }"#,
+            );
+
+        let source = r#"import "shapes" expose blob  cinematic "main" {}"#;
+        let mut program = Parser::new(lexer::lex(source).unwrap()).parse().unwrap();
+        resolve_imports_with(&mut program, &files, Path::new("/play"), &[]).unwrap();
+
+        // `dot` was imported by shapes, not exposed by it
+        let names: Vec<_> = program.cinematics[0]
+            .defines
+            .iter()
+            .map(|d| d.name.as_str())
+            .collect();
+        assert_eq!(names, vec!["blob"]);
+    }
+
+    #[test]
+    fn overlay_lets_unsaved_buffer_shadow_saved_file() {
+        use crate::source::OverlayProvider;
+
+        let dir = make_temp_dir("overlay");
+        fs::write(
+            dir.join("fx.game"),
+            r#"cinematic "fx" { define old_fx(r) { circle(r) } }"#,
+        )
+        .unwrap();
+        let canonical = dir.canonicalize().unwrap();
+
+        // Unsaved edit of fx.game adds a define that is not on disk yet
+        let unsaved = MemoryProvider::new().with_file(
+            canonical.join("fx.game"),
+            r#"cinematic "fx" { define new_fx(r) { ring(r, 0.02) } }"#,
+        );
+        let overlay = OverlayProvider::new().layer(unsaved).layer(FsProvider);
+
+        let source = r#"import "fx" expose new_fx  cinematic "main" {}"#;
+        let mut program = Parser::new(lexer::lex(source).unwrap()).parse().unwrap();
+        resolve_imports_with(&mut program, &overlay, &canonical, &[]).unwrap();
+        assert_eq!(program.cinematics[0].defines[0].name, "new_fx");
+
+        let _ = fs::remove_dir_all(&dir);
+    }
 }
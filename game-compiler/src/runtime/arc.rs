@@ -1,11 +1,14 @@
 //! Arc block codegen — compiles `arc { target: from -> to over duration [easing] }`
 //! into a JS timeline with easing functions.
 //!
-//! Emits a self-contained `arcUpdate(time)` function that interpolates uniform
-//! parameters over time using configurable easing curves.
+//! Emits a self-contained `arcUpdate(time, params)` function that interpolates
+//! uniform parameters over time using configurable easing curves. The renderers
+//! call it every frame with their playback clock's time and `userParams`.
+//! `ARC_END` marks when the last arc settles.
 
 use crate::ast::{ArcBlock, Duration};
 use crate::codegen::expr as expr_compile;
+use crate::codegen::score::resolve_target;
 use crate::codegen::UniformInfo;
 
 /// Convert Duration to seconds using default 120 BPM (2s per bar).
@@ -32,7 +35,7 @@ fn easing_library() -> &'static str {
 
 /// A resolved arc timeline entry for JS emission.
 struct ArcTimelineEntry {
-    uniform_idx: Option<usize>,
+    param: Option<String>,
     target_name: String,
     from_js: String,
     to_js: String,
@@ -46,7 +49,8 @@ struct ArcTimelineEntry {
 /// Emits:
 /// 1. Easing functions library
 /// 2. Timeline data array
-/// 3. `arcUpdate(time, params)` function that interpolates uniforms
+/// 3. `arcUpdate(time, params)` function that interpolates uniforms, keyed by
+///    name in `params`
 ///
 /// Multiple ArcBlocks are concatenated sequentially.
 pub fn generate_arc_js(arcs: &[ArcBlock], uniforms: &[UniformInfo]) -> String {
@@ -67,19 +71,8 @@ pub fn generate_arc_js(arcs: &[ArcBlock], uniforms: &[UniformInfo]) -> String {
         for entry in &block.entries {
             let dur_secs = duration_to_secs(&entry.duration);
 
-            // Look up uniform index: try "target" as dotted name e.g. "bg.opacity" -> "bg_opacity"
-            let uniform_name = entry.target.replace('.', "_");
-            let idx = uniforms
-                .iter()
-                .position(|u| u.name == uniform_name)
-                .or_else(|| {
-                    // Also try bare name (last segment after dot)
-                    let bare = entry.target.rsplit('.').next().unwrap_or(&entry.target);
-                    uniforms.iter().position(|u| u.name == bare)
-                });
-
             entries.push(ArcTimelineEntry {
-                uniform_idx: idx,
+                param: resolve_target(&entry.target, uniforms),
                 target_name: entry.target.clone(),
                 from_js: expr_compile::compile_js(&entry.from),
                 to_js: expr_compile::compile_js(&entry.to),
@@ -95,29 +88,31 @@ pub fn generate_arc_js(arcs: &[ArcBlock], uniforms: &[UniformInfo]) -> String {
     // Emit timeline data
     s.push_str("const _arcTimeline = [\n");
     for e in &entries {
-        let idx_str = match e.uniform_idx {
-            Some(i) => format!("{i}"),
-            None => "-1".into(),
+        let param = match &e.param {
+            Some(name) => format!("'{name}'"),
+            None => "null".into(),
         };
         s.push_str(&format!(
-            "  {{idx:{},name:'{}',from:{},to:{},start:{},dur:{},ease:'{}'}},\n",
-            idx_str, e.target_name, e.from_js, e.to_js, e.start_secs, e.duration_secs, e.easing,
+            "  {{param:{},name:'{}',from:{},to:{},start:{},dur:{},ease:'{}'}},\n",
+            param, e.target_name, e.from_js, e.to_js, e.start_secs, e.duration_secs, e.easing,
         ));
     }
-    s.push_str("];\n\n");
+    s.push_str("];\n");
+    // Every arc has settled here; reduced motion freezes on this frame
+    s.push_str(&format!("const ARC_END = {cursor};\n\n"));
 
     // Emit arcUpdate function
     s.push_str("function arcUpdate(time, params) {\n");
     s.push_str("  for (const a of _arcTimeline) {\n");
-    s.push_str("    if (a.idx < 0) continue;\n");
+    s.push_str("    if (a.param === null) continue;\n");
     s.push_str("    if (time < a.start) continue;\n");
     s.push_str("    if (time >= a.start + a.dur) {\n");
-    s.push_str("      params[a.idx] = a.to;\n");
+    s.push_str("      params[a.param] = a.to;\n");
     s.push_str("      continue;\n");
     s.push_str("    }\n");
     s.push_str("    const t = (time - a.start) / a.dur;\n");
     s.push_str("    const easeFn = _ease[a.ease] || _ease.linear;\n");
-    s.push_str("    params[a.idx] = a.from + (a.to - a.from) * easeFn(t);\n");
+    s.push_str("    params[a.param] = a.from + (a.to - a.from) * easeFn(t);\n");
     s.push_str("  }\n");
     s.push_str("}\n");
 
@@ -141,11 +136,13 @@ mod tests {
         let arcs = vec![ArcBlock {
             entries: vec![ArcEntry {
                 target: "opacity".into(),
-                from: Expr::Number(0.0),
-                to: Expr::Number(1.0),
+                from: Expr::Number(0.0, Span::default()),
+                to: Expr::Number(1.0, Span::default()),
                 duration: Duration::Seconds(2.0),
                 easing: Some("expo_out".into()),
+                span: Span::default(),
             }],
+            span: Span::default(),
         }];
         let uniforms = vec![UniformInfo {
             name: "opacity".into(),
@@ -153,7 +150,7 @@ mod tests {
         }];
         let js = generate_arc_js(&arcs, &uniforms);
         assert!(js.contains("_arcTimeline"));
-        assert!(js.contains("idx:0"));
+        assert!(js.contains("param:'opacity'"));
         assert!(js.contains("expo_out"));
         assert!(js.contains("from:0.0"));
         assert!(js.contains("to:1.0"));
@@ -184,20 +181,24 @@ mod tests {
             ArcBlock {
                 entries: vec![ArcEntry {
                     target: "scale".into(),
-                    from: Expr::Number(1.0),
-                    to: Expr::Number(2.0),
+                    from: Expr::Number(1.0, Span::default()),
+                    to: Expr::Number(2.0, Span::default()),
                     duration: Duration::Seconds(1.0),
                     easing: None,
+                    span: Span::default(),
                 }],
+                span: Span::default(),
             },
             ArcBlock {
                 entries: vec![ArcEntry {
                     target: "scale".into(),
-                    from: Expr::Number(2.0),
-                    to: Expr::Number(0.5),
+                    from: Expr::Number(2.0, Span::default()),
+                    to: Expr::Number(0.5, Span::default()),
                     duration: Duration::Seconds(1.0),
                     easing: Some("smooth".into()),
+                    span: Span::default(),
                 }],
+                span: Span::default(),
             },
         ];
         let uniforms = vec![UniformInfo {
@@ -208,21 +209,24 @@ mod tests {
         // Should have two entries in timeline
         assert!(js.contains("start:0"));
         assert!(js.contains("start:1"));
+        assert!(js.contains("const ARC_END = 2;"));
     }
 
     #[test]
-    fn unmapped_target_gets_negative_idx() {
+    fn unmapped_target_gets_null_param() {
         let arcs = vec![ArcBlock {
             entries: vec![ArcEntry {
                 target: "unknown_param".into(),
-                from: Expr::Number(0.0),
-                to: Expr::Number(1.0),
+                from: Expr::Number(0.0, Span::default()),
+                to: Expr::Number(1.0, Span::default()),
                 duration: Duration::Seconds(1.0),
                 easing: None,
+                span: Span::default(),
             }],
+            span: Span::default(),
         }];
         let js = generate_arc_js(&arcs, &[]);
-        assert!(js.contains("idx:-1"));
+        assert!(js.contains("param:null"));
     }
 
     #[test]
@@ -230,18 +234,20 @@ mod tests {
         let arcs = vec![ArcBlock {
             entries: vec![ArcEntry {
                 target: "bg.opacity".into(),
-                from: Expr::Number(0.0),
-                to: Expr::Number(1.0),
+                from: Expr::Number(0.0, Span::default()),
+                to: Expr::Number(1.0, Span::default()),
                 duration: Duration::Seconds(1.0),
                 easing: None,
+                span: Span::default(),
             }],
+            span: Span::default(),
         }];
         let uniforms = vec![UniformInfo {
             name: "bg_opacity".into(),
             default: 0.0,
         }];
         let js = generate_arc_js(&arcs, &uniforms);
-        assert!(js.contains("idx:0"));
+        assert!(js.contains("param:'bg_opacity'"));
     }
 
     #[test]
@@ -249,11 +255,13 @@ mod tests {
         let arcs = vec![ArcBlock {
             entries: vec![ArcEntry {
                 target: "x".into(),
-                from: Expr::Number(0.0),
-                to: Expr::Number(1.0),
+                from: Expr::Number(0.0, Span::default()),
+                to: Expr::Number(1.0, Span::default()),
                 duration: Duration::Seconds(1.0),
                 easing: None,
+                span: Span::default(),
             }],
+            span: Span::default(),
         }];
         let js = generate_arc_js(&arcs, &[]);
         assert!(js.contains("expo_in"));
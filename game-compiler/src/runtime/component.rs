@@ -4,10 +4,26 @@
 //! (`<game-xyz>`) with WebGPU primary and WebGL2 fallback.
 
 use crate::codegen::ShaderOutput;
-use crate::codegen::memory;
+use crate::ShaderTarget;
 
 /// Generate a zero-dependency Web Component JS file.
-pub fn generate_component(shader: &ShaderOutput) -> String {
+///
+/// Only the renderer classes and shader strings for `target` are embedded.
+pub fn generate_component(shader: &ShaderOutput, target: &ShaderTarget) -> String {
+    component(shader, target, false)
+}
+
+/// Generate a Web Component that renders on a worker thread.
+///
+/// The element hands its canvas to a worker with `transferControlToOffscreen`
+/// and forwards params, data, audio, pointer and clock changes to it. Where
+/// OffscreenCanvas or the worker is unavailable it renders on the main thread
+/// like [`generate_component`].
+pub fn generate_worker_component(shader: &ShaderOutput, target: &ShaderTarget) -> String {
+    component(shader, target, true)
+}
+
+fn component(shader: &ShaderOutput, target: &ShaderTarget, worker: bool) -> String {
     let tag = to_kebab(&shader.name);
     let class = to_pascal(&shader.name);
 
@@ -22,34 +38,60 @@ pub fn generate_component(shader: &ShaderOutput) -> String {
     let wgsl_f = escape_js(&shader.wgsl_fragment);
     let glsl_v = escape_js(&shader.glsl_vertex);
     let glsl_f = escape_js(&shader.glsl_fragment);
+    let post = |src: &Option<String>| match src {
+        Some(src) => format!("`{}`", escape_js(src)),
+        None => "null".to_string(),
+    };
+    let wgsl_post = post(&shader.post_wgsl);
+    let glsl_post = post(&shader.post_glsl);
 
-    let uses_memory = shader.uses_memory;
+    let data_fields_json = shader
+        .data_fields
+        .iter()
+        .map(|f| format!("'{f}'"))
+        .collect::<Vec<_>>()
+        .join(",");
 
     // Build incrementally to avoid stack overflow from giant format! macro
     let mut s = String::with_capacity(16384);
 
     s.push_str(&format!("// GAME Component: {tag} — auto-generated, do not edit.\n"));
-    s.push_str("(function(){\n");
-    s.push_str(&format!("const WGSL_V = `{wgsl_v}`;\n"));
-    s.push_str(&format!("const WGSL_F = `{wgsl_f}`;\n"));
-    s.push_str(&format!("const GLSL_V = `{glsl_v}`;\n"));
-    s.push_str(&format!("const GLSL_F = `{glsl_f}`;\n"));
+    // The worker re-runs this function by name, so it must stay self-contained
+    s.push_str(if worker { "(function gameComponent(){\n" } else { "(function(){\n" });
+    if target.includes_webgpu() {
+        s.push_str(&format!("const WGSL_V = `{wgsl_v}`;\n"));
+        s.push_str(&format!("const WGSL_F = `{wgsl_f}`;\n"));
+        s.push_str(&format!("const WGSL_POST = {wgsl_post};\n"));
+    }
+    if target.includes_webgl2() {
+        s.push_str(&format!("const GLSL_V = `{glsl_v}`;\n"));
+        s.push_str(&format!("const GLSL_F = `{glsl_f}`;\n"));
+        s.push_str(&format!("const GLSL_POST = {glsl_post};\n"));
+    }
     s.push_str(&format!("const UNIFORMS = [{uniform_defs_json}];\n"));
-    s.push_str(&format!("const USES_MEMORY = {};\n\n", if uses_memory { "true" } else { "false" }));
+    s.push_str(&format!("const DATA_FIELDS = [{data_fields_json}];\n"));
+    s.push_str(&format!("const MEMORY_LAYERS = {};\n", shader.memory_layers));
+    s.push_str(&format!("const ANIMATED = {};\n\n", shader.animated));
 
-    s.push_str(super::helpers::webgpu_renderer());
+    s.push_str(super::helpers::game_shared());
+    s.push_str("\n\n");
+    s.push_str(super::helpers::game_clock());
+    s.push_str("\n\n");
+    s.push_str(super::helpers::game_pointer());
     s.push_str("\n\n");
 
-    if uses_memory {
-        s.push_str(memory::webgpu_memory_runtime());
+    if target.includes_webgpu() {
+        s.push_str(super::helpers::webgpu_renderer());
         s.push_str("\n\n");
     }
 
-    s.push_str(super::helpers::webgl2_renderer());
-    s.push_str("\n\n");
+    if target.includes_webgl2() {
+        s.push_str(super::helpers::webgl2_renderer());
+        s.push_str("\n\n");
+    }
 
-    if uses_memory {
-        s.push_str(memory::webgl2_memory_runtime());
+    if worker {
+        s.push_str(super::helpers::game_worker());
         s.push_str("\n\n");
     }
 
@@ -59,6 +101,10 @@ pub fn generate_component(shader: &ShaderOutput) -> String {
         s.push_str("\n\n");
     }
 
+    if worker {
+        worker_entry(&mut s, target);
+    }
+
     // Custom element class
     s.push_str(&format!("class {class} extends HTMLElement {{\n"));
     s.push_str("  constructor() {\n");
@@ -66,15 +112,30 @@ pub fn generate_component(shader: &ShaderOutput) -> String {
     s.push_str("    this.attachShadow({ mode: 'open' });\n");
     s.push_str("    this._renderer = null;\n");
     s.push_str("    this._resizeObserver = null;\n");
+    s.push_str("    this._params = {};\n");
+    s.push_str("    this._clock = new GameClock();\n");
+    s.push_str("    this._inView = true;\n");
     s.push_str("  }\n\n");
 
     s.push_str("  connectedCallback() {\n");
     s.push_str("    const style = document.createElement('style');\n");
-    s.push_str("    style.textContent = ':host{display:block;width:100%;height:100%}canvas{width:100%;height:100%;display:block}';\n");
+    s.push_str("    style.textContent = ':host{display:block;width:100%;height:100%}canvas{width:100%;height:100%;display:block;touch-action:none}';\n");
     s.push_str("    const canvas = document.createElement('canvas');\n");
     s.push_str("    this.shadowRoot.appendChild(style);\n");
     s.push_str("    this.shadowRoot.appendChild(canvas);\n");
     s.push_str("    this._canvas = canvas;\n");
+    // Off-screen elements and background tabs stop drawing; their clocks hold
+    s.push_str("    this._intersectionObserver = new IntersectionObserver(entries => {\n");
+    s.push_str("      this._inView = entries[entries.length - 1].isIntersecting;\n");
+    s.push_str("      this._schedule();\n");
+    s.push_str("    });\n");
+    s.push_str("    this._intersectionObserver.observe(this);\n");
+    s.push_str("    this._onVisibility = () => this._schedule();\n");
+    s.push_str("    document.addEventListener('visibilitychange', this._onVisibility);\n");
+    s.push_str("    this._motionQuery = window.matchMedia('(prefers-reduced-motion: reduce)');\n");
+    s.push_str("    this._onMotion = () => this._applyPolicy();\n");
+    s.push_str("    this._motionQuery.addEventListener('change', this._onMotion);\n");
+    s.push_str("    this._applyPolicy();\n");
     s.push_str("    this._initRenderer();\n");
     s.push_str("    this._resizeObserver = new ResizeObserver(() => this._resize());\n");
     s.push_str("    this._resizeObserver.observe(this);\n");
@@ -84,49 +145,210 @@ pub fn generate_component(shader: &ShaderOutput) -> String {
     s.push_str("    this._renderer?.destroy();\n");
     s.push_str("    this._renderer = null;\n");
     s.push_str("    this._resizeObserver?.disconnect();\n");
+    s.push_str("    this._intersectionObserver?.disconnect();\n");
+    s.push_str("    document.removeEventListener('visibilitychange', this._onVisibility);\n");
+    s.push_str("    this._motionQuery?.removeEventListener('change', this._onMotion);\n");
     s.push_str("  }\n\n");
 
     s.push_str("  async _initRenderer() {\n");
-    s.push_str("    const gpu = new GameRenderer(this._canvas, WGSL_V, WGSL_F, UNIFORMS);\n");
-    s.push_str("    if (await gpu.init()) {\n");
-    s.push_str("      this._renderer = gpu;\n");
-    s.push_str("    } else {\n");
-    s.push_str("      const gl = new GameRendererGL(this._canvas, GLSL_V, GLSL_F, UNIFORMS);\n");
-    s.push_str("      if (gl.init()) {\n");
-    s.push_str("        this._renderer = gl;\n");
-    s.push_str("      } else {\n");
-    s.push_str(&format!("        console.warn('game-{tag}: no WebGPU or WebGL2 support');\n"));
-    s.push_str("        return;\n");
-    s.push_str("      }\n");
-    s.push_str("    }\n");
+    if worker {
+        s.push_str("    if (await this._initWorker()) { this._rendererReady(); return; }\n");
+    }
+    match target {
+        ShaderTarget::Both => {
+            s.push_str("    const gpu = new GameRenderer(this._canvas, WGSL_V, WGSL_F, UNIFORMS, WGSL_POST, MEMORY_LAYERS);\n");
+            s.push_str("    gpu.transparent = this.hasAttribute('transparent');\n");
+            s.push_str("    gpu.clock = this._clock;\n");
+            s.push_str("    if (await gpu.init()) {\n");
+            s.push_str("      this._renderer = gpu;\n");
+            s.push_str("    } else {\n");
+            s.push_str("      const gl = new GameRendererGL(this._canvas, GLSL_V, GLSL_F, UNIFORMS, GLSL_POST, MEMORY_LAYERS);\n");
+            s.push_str("      gl.transparent = this.hasAttribute('transparent');\n");
+            s.push_str("      gl.clock = this._clock;\n");
+            s.push_str("      if (gl.init()) {\n");
+            s.push_str("        this._renderer = gl;\n");
+            s.push_str("      } else {\n");
+            s.push_str(&format!("        console.warn('game-{tag}: no WebGPU or WebGL2 support');\n"));
+            s.push_str("        return;\n");
+            s.push_str("      }\n");
+            s.push_str("    }\n");
+        }
+        ShaderTarget::WebGpu => {
+            s.push_str("    const gpu = new GameRenderer(this._canvas, WGSL_V, WGSL_F, UNIFORMS, WGSL_POST, MEMORY_LAYERS);\n");
+            s.push_str("    gpu.transparent = this.hasAttribute('transparent');\n");
+            s.push_str("    gpu.clock = this._clock;\n");
+            s.push_str("    if (!(await gpu.init())) {\n");
+            s.push_str(&format!("      console.warn('game-{tag}: no WebGPU support');\n"));
+            s.push_str("      return;\n");
+            s.push_str("    }\n");
+            s.push_str("    this._renderer = gpu;\n");
+        }
+        ShaderTarget::WebGl2 => {
+            s.push_str("    const gl = new GameRendererGL(this._canvas, GLSL_V, GLSL_F, UNIFORMS, GLSL_POST, MEMORY_LAYERS);\n");
+            s.push_str("    gl.transparent = this.hasAttribute('transparent');\n");
+            s.push_str("    gl.clock = this._clock;\n");
+            s.push_str("    if (!gl.init()) {\n");
+            s.push_str(&format!("      console.warn('game-{tag}: no WebGL2 support');\n"));
+            s.push_str("      return;\n");
+            s.push_str("    }\n");
+            s.push_str("    this._renderer = gl;\n");
+        }
+    }
+    s.push_str("    this._rendererReady();\n");
+    s.push_str("  }\n\n");
+
+    if worker {
+        s.push_str("  // Render on a worker when the browser can hand it the canvas\n");
+        s.push_str("  async _initWorker() {\n");
+        s.push_str("    if (!GameWorkerRenderer.supported(this._canvas)) return false;\n");
+        s.push_str("    const worker = new GameWorkerRenderer(this._canvas, gameComponent);\n");
+        s.push_str("    worker.transparent = this.hasAttribute('transparent');\n");
+        s.push_str("    worker.clock = this._clock;\n");
+        s.push_str("    // Messages queue in the worker until its renderer is ready\n");
+        s.push_str("    this._renderer = worker;\n");
+        s.push_str("    if (await worker.init()) return true;\n");
+        s.push_str("    worker.destroy();\n");
+        s.push_str("    this._renderer = null;\n");
+        s.push_str("    // The worker may own the canvas by now; render on a fresh one\n");
+        s.push_str("    const canvas = document.createElement('canvas');\n");
+        s.push_str("    this._canvas.replaceWith(canvas);\n");
+        s.push_str("    this._canvas = canvas;\n");
+        s.push_str("    return false;\n");
+        s.push_str("  }\n\n");
+    }
+
+    s.push_str("  _rendererReady() {\n");
+    s.push_str("    for (const k in this._params) this._renderer.setParam(k, this._params[k]);\n");
     s.push_str("    this._resize();\n");
     s.push_str("    if (typeof _gameReactSetup === 'function') _gameReactSetup(this._canvas, this._renderer);\n");
-    s.push_str("    this._renderer.start();\n");
+    s.push_str("    this._applyPolicy();\n");
+    s.push_str("    this._schedule();\n");
+    s.push_str("  }\n\n");
+
+    s.push_str("  // Draw only while on screen in a visible tab\n");
+    s.push_str("  _schedule() {\n");
+    s.push_str("    if (!this._renderer) return;\n");
+    s.push_str("    if (this._inView && !document.hidden) this._renderer.start();\n");
+    s.push_str("    else this._renderer.stop();\n");
+    s.push_str("  }\n\n");
+
+    // Frame policy: `max-fps` caps the rate, static cinematics render on
+    // change, and `reduced-motion` picks what prefers-reduced-motion does
+    s.push_str("  _applyPolicy() {\n");
+    s.push_str("    const policy = this.getAttribute('reduced-motion');\n");
+    s.push_str("    const reduce = policy !== 'ignore' && !!this._motionQuery?.matches;\n");
+    s.push_str("    const freeze = reduce && policy !== 'slow';\n");
+    // Hold on the frame where every arc has settled
+    s.push_str("    const seek = freeze && this._clock.motion !== 0;\n");
+    s.push_str("    if (seek) this._clock.seek(typeof ARC_END === 'number' ? ARC_END : this._clock.time);\n");
+    s.push_str("    this._clock.motion = freeze ? 0 : reduce ? 0.25 : 1;\n");
+    s.push_str("    this._syncClock(seek);\n");
+    s.push_str("    if (!this._renderer) return;\n");
+    s.push_str("    const fps = parseFloat(this.getAttribute('max-fps'));\n");
+    s.push_str("    this._renderer.frameInterval = fps > 0 ? 1000 / fps : 0;\n");
+    s.push_str("    this._renderer.animated = ANIMATED && !freeze;\n");
+    s.push_str("    this._renderer.dirty = true;\n");
     s.push_str("  }\n\n");
 
     s.push_str("  _resize() {\n");
     s.push_str("    const rect = this.getBoundingClientRect();\n");
     s.push_str("    const dpr = window.devicePixelRatio || 1;\n");
-    s.push_str("    this._canvas.width = Math.round(rect.width * dpr);\n");
-    s.push_str("    this._canvas.height = Math.round(rect.height * dpr);\n");
+    s.push_str("    const width = Math.round(rect.width * dpr);\n");
+    s.push_str("    const height = Math.round(rect.height * dpr);\n");
+    s.push_str("    // A canvas handed to a worker is sized there\n");
+    s.push_str("    if (this._renderer?.offscreen) this._renderer.resize(width, height);\n");
+    s.push_str("    else { this._canvas.width = width; this._canvas.height = height; }\n");
+    s.push_str("    // Resizing clears the canvas; draw it again even if nothing else changed\n");
+    s.push_str("    this._redraw();\n");
     s.push_str("  }\n\n");
 
-    s.push_str("  setParam(name, value) { this._renderer?.setParam(name, value); }\n");
+    s.push_str("  setParam(name, value) { this._params[name] = value; this._renderer?.setParam(name, value); }\n");
     s.push_str("  setAudioData(data) { this._renderer?.setAudioData(data); }\n");
     s.push_str("  setAudioSource(bridge) { bridge?.subscribe(d => this._renderer?.setAudioData(d)); }\n\n");
 
-    s.push_str("  static get observedAttributes() { return UNIFORMS.map(u => u.name); }\n");
+    // Playback: the `paused` attribute is the source of truth, so play() and
+    // pause() reflect into it like a media element
+    s.push_str("  play() { this.removeAttribute('paused'); }\n");
+    s.push_str("  pause() { this.setAttribute('paused', ''); }\n");
+    s.push_str("  get paused() { return this._clock.paused; }\n");
+    s.push_str("  get currentTime() { return this._clock.time; }\n");
+    s.push_str("  set currentTime(t) { this.seek(t); }\n");
+    s.push_str("  seek(seconds) { this._clock.seek(seconds); this._syncClock(true); this._redraw(); }\n");
+    s.push_str("  step(seconds = 1 / 60) { this._clock.step(seconds); this._syncClock(true); this._redraw(); }\n");
+    s.push_str("  // Worker renderers keep a copy of the clock; the others share it\n");
+    s.push_str("  _syncClock(seeked = false) { this._renderer?.syncClock?.(seeked); }\n");
+    s.push_str("  _redraw() {\n");
+    s.push_str("    if (!this._renderer) return;\n");
+    s.push_str("    // The frame loop picks this up; a manual clock has none, and off-screen\n");
+    s.push_str("    // elements redraw when they return\n");
+    s.push_str("    if (this._renderer.running) this._renderer.dirty = true;\n");
+    s.push_str("    else if (this._clock.manual) this._renderer.render();\n");
+    s.push_str("  }\n");
+    s.push_str("  _setClock(mode) {\n");
+    s.push_str("    this._clock.manual = mode === 'manual';\n");
+    s.push_str("    this._syncClock();\n");
+    s.push_str("    if (!this._renderer) return;\n");
+    s.push_str("    this._renderer.stop();\n");
+    s.push_str("    this._schedule();\n");
+    s.push_str("  }\n\n");
+
+    s.push_str("  static get observedAttributes() {\n");
+    s.push_str("    return ['paused', 'time-scale', 'clock', 'max-fps', 'reduced-motion'].concat(UNIFORMS.map(u => u.name).concat(DATA_FIELDS));\n");
+    s.push_str("  }\n");
     s.push_str("  attributeChangedCallback(name, _, val) {\n");
-    s.push_str("    if (val !== null) this.setParam(name, parseFloat(val));\n");
+    s.push_str("    if (name === 'paused') { this._clock.paused = val !== null; this._syncClock(); return; }\n");
+    s.push_str("    if (name === 'time-scale') {\n");
+    s.push_str("      const scale = parseFloat(val);\n");
+    s.push_str("      this._clock.scale = Number.isFinite(scale) ? scale : 1;\n");
+    s.push_str("      this._syncClock();\n");
+    s.push_str("      return;\n");
+    s.push_str("    }\n");
+    s.push_str("    if (name === 'clock') { this._setClock(val); return; }\n");
+    s.push_str("    if (name === 'max-fps' || name === 'reduced-motion') { this._applyPolicy(); return; }\n");
+    s.push_str("    if (val === null) return;\n");
+    s.push_str("    if (DATA_FIELDS.includes(name)) this[name] = parseFloat(val);\n");
+    s.push_str("    else this.setParam(name, parseFloat(val));\n");
     s.push_str("  }\n");
     s.push_str("}\n\n");
 
+    // data.* fields become plain JS properties backed by `data_<field>` uniforms
+    s.push_str("for (const f of DATA_FIELDS) {\n");
+    s.push_str(&format!("  Object.defineProperty({class}.prototype, f, {{\n"));
+    s.push_str("    get() { return this._params['data_' + f] ?? 0; },\n");
+    s.push_str("    set(v) { this.setParam('data_' + f, Number(v)); },\n");
+    s.push_str("  });\n");
+    s.push_str("}\n\n");
+
     s.push_str(&format!("customElements.define('game-{tag}', {class});\n"));
     s.push_str("})();\n");
 
     s
 }
 
+/// Emit the worker side of a worker component: a renderer factory making the
+/// same WebGPU-then-WebGL2 choice as the element, and the check that hands
+/// the rest of the file over to `gameWorkerMain` inside the worker.
+fn worker_entry(s: &mut String, target: &ShaderTarget) {
+    s.push_str("async function gameCreateRenderer(canvas, transparent) {\n");
+    if target.includes_webgpu() {
+        s.push_str("  const gpu = new GameRenderer(canvas, WGSL_V, WGSL_F, UNIFORMS, WGSL_POST, MEMORY_LAYERS);\n");
+        s.push_str("  gpu.transparent = transparent;\n");
+        s.push_str("  if (await gpu.init()) return gpu;\n");
+    }
+    if target.includes_webgl2() {
+        s.push_str("  const gl = new GameRendererGL(canvas, GLSL_V, GLSL_F, UNIFORMS, GLSL_POST, MEMORY_LAYERS);\n");
+        s.push_str("  gl.transparent = transparent;\n");
+        s.push_str("  if (gl.init()) return gl;\n");
+    }
+    s.push_str("  return null;\n");
+    s.push_str("}\n\n");
+    // Inside the worker there is no element to define, only renderers to run
+    s.push_str("if (typeof document === 'undefined') {\n");
+    s.push_str("  gameWorkerMain(gameCreateRenderer);\n");
+    s.push_str("  return;\n");
+    s.push_str("}\n\n");
+}
+
 fn to_kebab(s: &str) -> String {
     s.chars()
         .map(|c| {
@@ -178,11 +400,15 @@ mod tests {
             glsl_fragment: "void main(){}".into(),
             glsl_vertex: "void main(){}".into(),
             uniforms: vec![],
-            uses_memory: false,
+            memory_layers: 0,
             js_modules: vec![],
             compute_wgsl: None,
+            data_fields: vec![],
+            post_wgsl: None,
+            post_glsl: None,
+            animated: true,
         };
-        let js = generate_component(&shader);
+        let js = generate_component(&shader, &ShaderTarget::Both);
         assert!(js.contains("customElements.define('game-test-viz'"));
         assert!(js.contains("class TestViz extends HTMLElement"));
     }
@@ -196,14 +422,20 @@ mod tests {
             glsl_fragment: "glsl".into(),
             glsl_vertex: "glsl_v".into(),
             uniforms: vec![UniformInfo { name: "speed".into(), default: 1.0 }],
-            uses_memory: false,
+            memory_layers: 0,
             js_modules: vec![],
             compute_wgsl: None,
+            data_fields: vec![],
+            post_wgsl: None,
+            post_glsl: None,
+            animated: true,
         };
-        let js = generate_component(&shader);
+        let js = generate_component(&shader, &ShaderTarget::Both);
         assert!(js.contains("class GameRenderer"));
         assert!(js.contains("class GameRendererGL"));
         assert!(js.contains("{name:'speed',default:1}"));
+        assert!(js.contains("gpu.transparent = this.hasAttribute('transparent');"));
+        assert!(js.contains("gl.transparent = this.hasAttribute('transparent');"));
     }
 
     #[test]
@@ -215,12 +447,17 @@ mod tests {
             glsl_fragment: "glsl".into(),
             glsl_vertex: "glsl_v".into(),
             uniforms: vec![],
-            uses_memory: true,
+            memory_layers: 2,
             js_modules: vec![],
             compute_wgsl: None,
+            data_fields: vec![],
+            post_wgsl: None,
+            post_glsl: None,
+            animated: true,
         };
-        let js = generate_component(&shader);
-        assert!(js.contains("USES_MEMORY = true"));
+        let js = generate_component(&shader, &ShaderTarget::Both);
+        assert!(js.contains("const MEMORY_LAYERS = 2;"));
+        assert!(js.contains("UNIFORMS, WGSL_POST, MEMORY_LAYERS)"));
         assert!(js.contains("_initMemory"));
         assert!(js.contains("_initMemoryGL"));
     }
@@ -234,14 +471,211 @@ mod tests {
             glsl_fragment: "glsl".into(),
             glsl_vertex: "glsl_v".into(),
             uniforms: vec![],
-            uses_memory: false,
+            memory_layers: 0,
             js_modules: vec!["class GameListenPipeline { /* listen */ }".into()],
             compute_wgsl: None,
+            data_fields: vec![],
+            post_wgsl: None,
+            post_glsl: None,
+            animated: true,
         };
-        let js = generate_component(&shader);
+        let js = generate_component(&shader, &ShaderTarget::Both);
         assert!(js.contains("GameListenPipeline"));
     }
 
+    #[test]
+    fn component_exposes_data_fields() {
+        let shader = ShaderOutput {
+            name: "ring".into(),
+            wgsl_fragment: "wgsl".into(),
+            wgsl_vertex: "wgsl_v".into(),
+            glsl_fragment: "glsl".into(),
+            glsl_vertex: "glsl_v".into(),
+            uniforms: vec![UniformInfo { name: "data_progress".into(), default: 0.0 }],
+            memory_layers: 0,
+            js_modules: vec![],
+            compute_wgsl: None,
+            data_fields: vec!["progress".into()],
+            post_wgsl: None,
+            post_glsl: None,
+            animated: true,
+        };
+        let js = generate_component(&shader, &ShaderTarget::Both);
+        assert!(js.contains("const DATA_FIELDS = ['progress'];"));
+        assert!(js.contains("UNIFORMS.map(u => u.name).concat(DATA_FIELDS)"));
+        assert!(js.contains("Object.defineProperty(Ring.prototype, f, {"));
+        assert!(js.contains("this.setParam('data_' + f, Number(v))"));
+    }
+
+    #[test]
+    fn component_exposes_playback_controls() {
+        let shader = ShaderOutput {
+            name: "intro".into(),
+            wgsl_fragment: "wgsl".into(),
+            wgsl_vertex: "wgsl_v".into(),
+            glsl_fragment: "glsl".into(),
+            glsl_vertex: "glsl_v".into(),
+            uniforms: vec![],
+            memory_layers: 0,
+            js_modules: vec![],
+            compute_wgsl: None,
+            data_fields: vec![],
+            post_wgsl: None,
+            post_glsl: None,
+            animated: true,
+        };
+        let js = generate_component(&shader, &ShaderTarget::Both);
+        assert!(js.contains("class GameClock"));
+        assert!(js.contains("this._clock = new GameClock();"));
+        assert!(js.contains("gpu.clock = this._clock;"));
+        assert!(js.contains("gl.clock = this._clock;"));
+        for method in ["play()", "pause()", "seek(seconds)", "step(seconds = 1 / 60)"] {
+            assert!(js.contains(&format!("  {method} {{")), "missing {method}");
+        }
+        assert!(js.contains("get currentTime()"));
+        assert!(js.contains("['paused', 'time-scale', 'clock', 'max-fps', 'reduced-motion']"));
+        assert!(js.contains("this._clock.manual = mode === 'manual';"));
+        // Every renderer reads time from its clock, never from construction
+        assert!(!js.contains("startTime"));
+        assert_eq!(js.matches("const t = this.clock.tick();").count(), 2);
+        assert_eq!(js.matches("this.score?.apply(t, this.userParams);").count(), 2);
+    }
+
+    #[test]
+    fn components_share_device_context_and_frame_loop() {
+        let shader = ShaderOutput {
+            name: "gauge".into(),
+            wgsl_fragment: "wgsl".into(),
+            wgsl_vertex: "wgsl_v".into(),
+            glsl_fragment: "glsl".into(),
+            glsl_vertex: "glsl_v".into(),
+            uniforms: vec![],
+            memory_layers: 0,
+            js_modules: vec![],
+            compute_wgsl: None,
+            data_fields: vec![],
+            post_wgsl: None,
+            post_glsl: None,
+            animated: true,
+        };
+        let js = generate_component(&shader, &ShaderTarget::Both);
+        assert!(js.contains("const GAME_SHARED = globalThis.__gameShared ??= {"));
+        assert!(js.contains("class GameScheduler"));
+        // Both renderers hand their frames to the one scheduler
+        assert_eq!(js.matches("GAME_SHARED.scheduler.add(this);").count(), 2);
+        assert!(!js.contains("requestAnimationFrame(() => this._loop())"));
+        // One adapter request and one WebGL2 context for the page
+        assert_eq!(js.matches("navigator.gpu.requestAdapter()").count(), 1);
+        assert_eq!(js.matches("getContext('webgl2'").count(), 1);
+        assert!(js.contains("this.canvas.getContext('2d', { alpha: this.transparent })"));
+        assert!(!js.contains("this.device.destroy()"));
+        assert!(js.contains("GAME_SHARED.pipelines.get(key)"));
+        assert!(js.contains("this.shared.programs"));
+    }
+
+    #[test]
+    fn component_applies_visibility_and_motion_policies() {
+        let shader = ShaderOutput {
+            name: "gauge".into(),
+            wgsl_fragment: "wgsl".into(),
+            wgsl_vertex: "wgsl_v".into(),
+            glsl_fragment: "glsl".into(),
+            glsl_vertex: "glsl_v".into(),
+            uniforms: vec![],
+            memory_layers: 0,
+            js_modules: vec![],
+            compute_wgsl: None,
+            data_fields: vec![],
+            post_wgsl: None,
+            post_glsl: None,
+            animated: false,
+        };
+        let js = generate_component(&shader, &ShaderTarget::Both);
+        assert!(js.contains("const ANIMATED = false;"));
+        assert!(js.contains("new IntersectionObserver("));
+        assert!(js.contains("document.addEventListener('visibilitychange', this._onVisibility);"));
+        assert!(js.contains("matchMedia('(prefers-reduced-motion: reduce)')"));
+        assert!(js.contains("this._renderer.animated = ANIMATED && !freeze;"));
+        assert!(js.contains("this._renderer.frameInterval = fps > 0 ? 1000 / fps : 0;"));
+        // Visibility, not the init path, decides whether the loop runs
+        assert!(!js.contains("    this._renderer.start();\n  }"));
+        assert!(js.contains("if (!r.animated && !r.dirty) continue;"));
+        assert_eq!(js.matches("this.userParams[name] = value; this.dirty = true;").count(), 2);
+    }
+
+    #[test]
+    fn worker_component_renders_offscreen_with_fallback() {
+        let shader = ShaderOutput {
+            name: "nebula".into(),
+            wgsl_fragment: "wgsl".into(),
+            wgsl_vertex: "wgsl_v".into(),
+            glsl_fragment: "glsl".into(),
+            glsl_vertex: "glsl_v".into(),
+            uniforms: vec![],
+            memory_layers: 0,
+            js_modules: vec![],
+            compute_wgsl: None,
+            data_fields: vec![],
+            post_wgsl: None,
+            post_glsl: None,
+            animated: true,
+        };
+        let js = generate_worker_component(&shader, &ShaderTarget::Both);
+        assert!(js.starts_with("// GAME Component: nebula"));
+        assert!(js.contains("(function gameComponent(){"));
+        assert!(js.contains("class GameWorkerRenderer"));
+        assert!(js.contains("this.canvas.transferControlToOffscreen()"));
+        // Inside the worker the same file only runs renderers
+        let entry = js.find("gameWorkerMain(gameCreateRenderer);").unwrap();
+        assert!(entry < js.find("class Nebula extends HTMLElement").unwrap());
+        assert!(js.contains("if (await gpu.init()) return gpu;"));
+        assert!(js.contains("if (gl.init()) return gl;"));
+        // Main-thread rendering stays as the fallback
+        assert!(js.contains("if (await this._initWorker()) { this._rendererReady(); return; }"));
+        assert!(js.contains("this._canvas.replaceWith(canvas);"));
+        assert!(js.contains("new GameRenderer(this._canvas, WGSL_V"));
+
+        let plain = generate_component(&shader, &ShaderTarget::Both);
+        assert!(plain.contains("(function(){"));
+        assert!(!plain.contains("GameWorkerRenderer"));
+        assert!(!plain.contains("_initWorker"));
+
+        let js = generate_worker_component(&shader, &ShaderTarget::WebGl2);
+        assert!(!js.contains("new GameRenderer("));
+        assert!(js.contains("if (gl.init()) return gl;"));
+    }
+
+    #[test]
+    fn webgpu_target_strips_webgl2_renderer() {
+        let shader = ShaderOutput {
+            name: "demo".into(),
+            wgsl_fragment: "wgsl".into(),
+            wgsl_vertex: "wgsl_v".into(),
+            glsl_fragment: "glsl".into(),
+            glsl_vertex: "glsl_v".into(),
+            uniforms: vec![],
+            memory_layers: 2,
+            js_modules: vec![],
+            compute_wgsl: None,
+            data_fields: vec![],
+            post_wgsl: None,
+            post_glsl: None,
+            animated: true,
+        };
+        let js = generate_component(&shader, &ShaderTarget::WebGpu);
+        assert!(js.contains("class GameRenderer {"));
+        assert!(js.contains("const WGSL_F = `wgsl`;"));
+        assert!(!js.contains("GameRendererGL"));
+        assert!(!js.contains("GLSL_"));
+        assert!(!js.contains("_initMemoryGL"));
+
+        let js = generate_component(&shader, &ShaderTarget::WebGl2);
+        assert!(js.contains("class GameRendererGL"));
+        assert!(js.contains("const GLSL_F = `glsl`;"));
+        assert!(!js.contains("class GameRenderer {"));
+        assert!(!js.contains("WGSL_"));
+    }
+
     #[test]
     fn kebab_and_pascal() {
         assert_eq!(to_kebab("celebration-burst"), "celebration-burst");
@@ -1,30 +1,266 @@
 //! JavaScript helper code shared by all output formats.
 
-/// WebGPU renderer class — initializes device, creates pipeline, runs render loop.
+/// Playback clock shared by the renderers and the component — scaled wall
+/// time that can be paused, sought, or stepped by hand.
+pub fn game_clock() -> &'static str {
+    r#"class GameClock {
+  constructor() {
+    this.time = 0;
+    this.scale = 1;
+    // Reduced-motion rate, kept apart from the user's scale
+    this.motion = 1;
+    this.paused = false;
+    // Manual clocks only move on seek()/step(), for frame-exact capture
+    this.manual = false;
+    this._last = null;
+  }
+
+  // Advance by the scaled wall time since the previous tick
+  tick(now = performance.now() / 1000) {
+    if (this._last !== null && !this.paused && !this.manual) {
+      this.time = Math.max(0, this.time + (now - this._last) * this.scale * this.motion);
+    }
+    this._last = now;
+    return this.time;
+  }
+
+  // Forget the last tick so time spent stopped isn't counted
+  resync() { this._last = null; }
+  seek(t) { this.time = Math.max(0, Number(t) || 0); }
+  step(dt) { this.seek(this.time + dt); }
+}"#
+}
+
+/// Pointer tracker behind the `mouse` signals — mouse, pen and touch input in
+/// the same aspect-corrected space as `p`, shared by the renderer and `react`.
+pub fn game_pointer() -> &'static str {
+    r#"class GamePointer {
+  constructor(canvas) {
+    this.canvas = canvas;
+    // down: 1 while pressed; click: 1 on press, fading out; vx/vy: drag
+    // velocity in p units per second; x2/y2: the second touch point
+    this.state = { x: 0, y: 0, down: 0, click: 0, vx: 0, vy: 0, touches: 0, x2: 0, y2: 0 };
+    this._points = new Map();
+    this._hover = [0, 0];
+    this._move = null;
+    this._frame = null;
+    this._subscribers = [];
+    this._listener = null;
+    // Also receives each event once mapped into p space, e.g. to post it to
+    // a render worker
+    this.forward = null;
+  }
+
+  attach() {
+    if (this._listener) return;
+    this._listener = (e) => {
+      if (e.type === 'pointerdown') this.canvas.setPointerCapture?.(e.pointerId);
+      const event = {
+        type: e.type,
+        id: e.pointerId,
+        mouse: e.pointerType === 'mouse',
+        pos: this._toP(e),
+        t: e.timeStamp / 1000,
+      };
+      this.handle(event);
+      this.forward?.(event);
+    };
+    for (const type of GamePointer.EVENTS) this.canvas.addEventListener(type, this._listener);
+  }
+
+  detach() {
+    if (!this._listener) return;
+    for (const type of GamePointer.EVENTS) this.canvas.removeEventListener(type, this._listener);
+    this._listener = null;
+  }
+
+  // Apply one event, already mapped into p space
+  handle(event) {
+    const { type, id, pos } = event;
+    if (type === 'pointerdown') {
+      this._points.set(id, pos);
+      this.state.click = 1;
+    } else if (type === 'pointermove') {
+      if (this._points.has(id)) {
+        if (id === this._points.keys().next().value) this._drag(pos, event.t);
+        this._points.set(id, pos);
+      } else if (event.mouse) {
+        this._hover = pos;
+      }
+    } else {
+      if (this._points.delete(id) && event.mouse) this._hover = pos;
+      // The primary pointer may have changed; measure velocity afresh
+      this._move = null;
+    }
+    this._sync();
+  }
+
+  // Called with the new state after every pointer move
+  subscribe(fn) { this._subscribers.push(fn); }
+
+  // Fade the click pulse and let velocity settle once the pointer stops
+  frame(params) {
+    const now = performance.now() / 1000;
+    const dt = this._frame === null ? 0 : Math.min(0.1, now - this._frame);
+    this._frame = now;
+    const st = this.state;
+    st.click = Math.max(0, st.click - dt * 4);
+    const settle = Math.exp(-dt * 8);
+    st.vx *= settle; st.vy *= settle;
+    for (const k in st) if (('mouse_' + k) in params) params['mouse_' + k] = st[k];
+  }
+
+  _toP(e) {
+    const r = this.canvas.getBoundingClientRect();
+    const aspect = r.width / Math.max(1, r.height);
+    return [
+      ((e.clientX - r.left) / Math.max(1, r.width) * 2 - 1) * aspect,
+      1 - (e.clientY - r.top) / Math.max(1, r.height) * 2,
+    ];
+  }
+
+  _drag(pos, t) {
+    const prev = this._move;
+    this._move = [pos[0], pos[1], t];
+    if (!prev || t <= prev[2]) return;
+    const dt = t - prev[2];
+    this.state.vx = (pos[0] - prev[0]) / dt;
+    this.state.vy = (pos[1] - prev[1]) / dt;
+  }
+
+  _sync() {
+    const pts = [...this._points.values()];
+    const first = pts[0] ?? this._hover;
+    const second = pts[1] ?? first;
+    const st = this.state;
+    st.x = first[0]; st.y = first[1];
+    st.x2 = second[0]; st.y2 = second[1];
+    st.touches = pts.length;
+    st.down = pts.length > 0 ? 1 : 0;
+    for (const fn of this._subscribers) fn(st);
+  }
+}
+GamePointer.EVENTS = ['pointerdown', 'pointermove', 'pointerup', 'pointercancel'];"#
+}
+
+/// Page-wide runtime state: one frame loop for every element, plus the shared
+/// GPU device, WebGL2 context and compiled pipelines the renderers reuse. The
+/// loop skips renderers with nothing new to draw or a frame-rate cap to keep.
+///
+/// Stored on `globalThis` so elements from different component files share it.
+pub fn game_shared() -> &'static str {
+    r#"class GameScheduler {
+  constructor() {
+    this.renderers = new Set();
+    this._rafId = null;
+  }
+
+  add(renderer) {
+    this.renderers.add(renderer);
+    if (this._rafId === null) this._rafId = requestAnimationFrame(now => this._tick(now));
+  }
+
+  remove(renderer) {
+    this.renderers.delete(renderer);
+    if (this.renderers.size === 0 && this._rafId !== null) {
+      cancelAnimationFrame(this._rafId);
+      this._rafId = null;
+    }
+  }
+
+  _tick(now) {
+    this._rafId = null;
+    for (const r of this.renderers) {
+      // A zero-size canvas (display: none, not laid out yet) has nothing to draw
+      if (r.canvas.width === 0 || r.canvas.height === 0) continue;
+      // Static cinematics draw only when something changed
+      if (!r.animated && !r.dirty) continue;
+      // 1ms of slack so a 30fps cap on a 60Hz display keeps every other frame
+      if (now - r.lastFrame < r.frameInterval - 1) continue;
+      r.lastFrame = now;
+      r.dirty = false;
+      try {
+        r.render();
+      } catch (e) {
+        console.error('GAME: render failed, stopping this element:', e);
+        r.stop();
+      }
+    }
+    if (this.renderers.size > 0) this._rafId = requestAnimationFrame(now => this._tick(now));
+  }
+}
+
+const GAME_SHARED = globalThis.__gameShared ??= {
+  scheduler: new GameScheduler(),
+  device: null,         // Promise<GPUDevice | null>, requested once
+  pipelines: new Map(), // WebGPU pipelines by shader source
+  gl: null,             // { canvas, gl, programs } for the hidden WebGL2 canvas
+};"#
+}
+
+/// WebGPU renderer class — borrows the shared device and cached pipelines and
+/// renders when the shared scheduler ticks it.
 pub fn webgpu_renderer() -> &'static str {
     r#"class GameRenderer {
-  constructor(canvas, wgslVertex, wgslFragment, uniformDefs) {
+  constructor(canvas, wgslVertex, wgslFragment, uniformDefs, wgslPost = null, memoryLayers = 0) {
     this.canvas = canvas;
     this.wgslVertex = wgslVertex;
     this.wgslFragment = wgslFragment;
+    this.wgslPost = wgslPost;
     this.uniformDefs = uniformDefs;
+    this.memoryLayers = memoryLayers;
     this.device = null;
     this.pipeline = null;
+    this.postPipeline = null;
+    this.sceneTexture = null;
+    this.memTextures = null;
     this.uniformBuffer = null;
     this.bindGroup = null;
     this.running = false;
-    this._rafId = null;
-    this.startTime = performance.now() / 1000;
+    // Frame policy, read by the scheduler: a renderer that isn't animated
+    // draws only when dirty, and frameInterval (ms) caps its frame rate
+    this.animated = true;
+    this.dirty = true;
+    this.frameInterval = 0;
+    this.lastFrame = -Infinity;
+    this.clock = new GameClock();
+    // Score playback runs on the same clock as arcs and modulation
+    this.score = typeof GameScorePlayer === 'function' ? new GameScorePlayer() : null;
+    this.score?.start(0);
     this.audioData = { bass: 0, mid: 0, treble: 0, energy: 0, beat: 0 };
+    this.pointer = new GamePointer(canvas);
+    this.mouse = this.pointer.state;
     this.userParams = {};
     for (const u of uniformDefs) this.userParams[u.name] = u.default;
+    // Composite over the page (black is transparent); set before init()
+    this.transparent = false;
+  }
+
+  // One device for the whole page: browsers cap how many a page may hold
+  static sharedDevice() {
+    if (!GAME_SHARED.device) {
+      GAME_SHARED.device = (async () => {
+        if (!navigator.gpu) return null;
+        const adapter = await navigator.gpu.requestAdapter();
+        if (!adapter) return null;
+        const device = await adapter.requestDevice();
+        // Pipelines die with their device; the next init() requests a new one
+        device.lost.then(() => {
+          GAME_SHARED.device = null;
+          GAME_SHARED.pipelines.clear();
+        });
+        return device;
+      })().catch(() => null);
+    }
+    return GAME_SHARED.device;
   }
 
   async init() {
-    if (!navigator.gpu) return false;
-    const adapter = await navigator.gpu.requestAdapter();
-    if (!adapter) return false;
-    this.device = await adapter.requestDevice();
+    this.device = await GameRenderer.sharedDevice();
+    if (!this.device) return false;
+    const format = navigator.gpu.getPreferredCanvasFormat();
+    const pipes = await this._pipelines(format);
+    if (!pipes) return false;
     this.device.lost.then((info) => {
       console.warn('GAME: WebGPU device lost:', info.message);
       if (this.running) {
@@ -32,65 +268,164 @@ pub fn webgpu_renderer() -> &'static str {
         setTimeout(() => this.init().then(() => this.start()), 500);
       }
     });
+    Object.assign(this, pipes);
+
+    // Configure the canvas last, so a failed init leaves it free for WebGL2
     const ctx = this.canvas.getContext('webgpu');
-    const format = navigator.gpu.getPreferredCanvasFormat();
-    ctx.configure({ device: this.device, format, alphaMode: 'premultiplied' });
+    ctx.configure({ device: this.device, format, alphaMode: this.transparent ? 'premultiplied' : 'opaque' });
     this.ctx = ctx;
     this.format = format;
 
-    const vMod = this.device.createShaderModule({ code: this.wgslVertex });
-    const fMod = this.device.createShaderModule({ code: this.wgslFragment });
-    const fragInfo = await fMod.getCompilationInfo();
-    if (fragInfo.messages.some(m => m.type === 'error')) {
-      console.error('GAME: Shader compilation errors:', fragInfo.messages);
-      return false;
-    }
-
     // 8 base floats + user params, padded to 16-byte alignment
     const floatCount = 8 + 2 + 2 + this.uniformDefs.length; // time,bass,mid,treble,energy,beat + res(2) + mouse(2) + user
     const bufSize = Math.ceil(floatCount * 4 / 16) * 16;
+    this.uniformBuffer?.destroy();
     this.uniformBuffer = this.device.createBuffer({
       size: bufSize, usage: GPUBufferUsage.UNIFORM | GPUBufferUsage.COPY_DST
     });
     this.floatCount = floatCount;
-
-    const bindGroupLayout = this.device.createBindGroupLayout({
-      entries: [{ binding: 0, visibility: GPUShaderStage.FRAGMENT, buffer: { type: 'uniform' } }]
-    });
     this.bindGroup = this.device.createBindGroup({
-      layout: bindGroupLayout,
+      layout: this.bindGroupLayout,
       entries: [{ binding: 0, resource: { buffer: this.uniformBuffer } }]
     });
+    // A re-init after device loss starts from black
+    this.memTextures = null;
+    this.sceneTexture = null;
+    return true;
+  }
+
+  // Pipelines are cached by shader source, so identical elements compile once
+  _pipelines(format) {
+    const key = [format, this.memoryLayers, this.wgslVertex, this.wgslFragment, this.wgslPost ?? ''].join('\u0000');
+    let pipes = GAME_SHARED.pipelines.get(key);
+    if (!pipes) {
+      pipes = this._createPipelines(format);
+      GAME_SHARED.pipelines.set(key, pipes);
+    }
+    return pipes;
+  }
+
+  async _createPipelines(format) {
+    const device = this.device;
+    const vMod = device.createShaderModule({ code: this.wgslVertex });
+    const fMod = device.createShaderModule({ code: this.wgslFragment });
+    const fragInfo = await fMod.getCompilationInfo();
+    if (fragInfo.messages.some(m => m.type === 'error')) {
+      console.error('GAME: Shader compilation errors:', fragInfo.messages);
+      return null;
+    }
+    const pipes = { postPipeline: null };
 
-    const pipelineLayout = this.device.createPipelineLayout({ bindGroupLayouts: [bindGroupLayout] });
-    this.pipeline = this.device.createRenderPipeline({
-      layout: pipelineLayout,
+    pipes.bindGroupLayout = device.createBindGroupLayout({
+      entries: [{ binding: 0, visibility: GPUShaderStage.FRAGMENT, buffer: { type: 'uniform' } }]
+    });
+
+    // Memory layers: group 1 holds each layer's previous frame, and the
+    // fragment shader writes the new one to an extra color target
+    const layouts = [pipes.bindGroupLayout];
+    const targets = [{ format }];
+    if (this.memoryLayers > 0) {
+      const entries = [{ binding: 0, visibility: GPUShaderStage.FRAGMENT, sampler: { type: 'filtering' } }];
+      for (let k = 0; k < this.memoryLayers; k++) {
+        entries.push({ binding: 1 + k, visibility: GPUShaderStage.FRAGMENT, texture: { sampleType: 'float' } });
+        targets.push({ format: 'rgba8unorm' });
+      }
+      pipes.memLayout = device.createBindGroupLayout({ entries });
+      pipes.memSampler = device.createSampler({ magFilter: 'linear', minFilter: 'linear' });
+      layouts.push(pipes.memLayout);
+    }
+    pipes.pipeline = device.createRenderPipeline({
+      layout: device.createPipelineLayout({ bindGroupLayouts: layouts }),
       vertex: { module: vMod, entryPoint: 'vs_main' },
-      fragment: { module: fMod, entryPoint: 'fs_main', targets: [{ format }] },
+      fragment: { module: fMod, entryPoint: 'fs_main', targets },
       primitive: { topology: 'triangle-list' }
     });
-    return true;
+
+    // Lens post pass: layers render offscreen, the post shader samples the scene
+    if (this.wgslPost) {
+      const pMod = device.createShaderModule({ code: this.wgslPost });
+      const postInfo = await pMod.getCompilationInfo();
+      if (postInfo.messages.some(m => m.type === 'error')) {
+        console.error('GAME: Post shader compilation errors:', postInfo.messages);
+        return null;
+      }
+      pipes.sceneLayout = device.createBindGroupLayout({
+        entries: [
+          { binding: 0, visibility: GPUShaderStage.FRAGMENT, texture: { sampleType: 'float' } },
+          { binding: 1, visibility: GPUShaderStage.FRAGMENT, sampler: { type: 'filtering' } }
+        ]
+      });
+      pipes.sceneSampler = device.createSampler({ magFilter: 'linear', minFilter: 'linear' });
+      pipes.postPipeline = device.createRenderPipeline({
+        layout: device.createPipelineLayout({ bindGroupLayouts: [pipes.bindGroupLayout, pipes.sceneLayout] }),
+        vertex: { module: vMod, entryPoint: 'vs_main' },
+        fragment: { module: pMod, entryPoint: 'fs_main', targets: [{ format }] },
+        primitive: { topology: 'triangle-list' }
+      });
+    }
+    return pipes;
+  }
+
+  _ensureScene(w, h) {
+    w = Math.max(1, w); h = Math.max(1, h);
+    if (this.sceneTexture && this.sceneTexture.width === w && this.sceneTexture.height === h) return;
+    this.sceneTexture?.destroy();
+    this.sceneTexture = this.device.createTexture({
+      size: [w, h], format: this.format,
+      usage: GPUTextureUsage.RENDER_ATTACHMENT | GPUTextureUsage.TEXTURE_BINDING
+    });
+    this.sceneView = this.sceneTexture.createView();
+    this.sceneBindGroup = this.device.createBindGroup({
+      layout: this.sceneLayout,
+      entries: [
+        { binding: 0, resource: this.sceneView },
+        { binding: 1, resource: this.sceneSampler }
+      ]
+    });
+  }
+
+  // Two sets of memory targets: frames read one set and write the other
+  _initMemory(w, h) {
+    w = Math.max(1, w); h = Math.max(1, h);
+    if (this.memTextures && this.memSize[0] === w && this.memSize[1] === h) return;
+    this.memTextures?.flat().forEach(t => t.destroy());
+    this.memTextures = [0, 1].map(() => Array.from({ length: this.memoryLayers }, () =>
+      this.device.createTexture({
+        size: [w, h], format: 'rgba8unorm',
+        usage: GPUTextureUsage.RENDER_ATTACHMENT | GPUTextureUsage.TEXTURE_BINDING
+      })));
+    this.memViews = this.memTextures.map(set => set.map(t => t.createView()));
+    this.memBindGroups = this.memViews.map(views => this.device.createBindGroup({
+      layout: this.memLayout,
+      entries: [{ binding: 0, resource: this.memSampler }]
+        .concat(views.map((view, k) => ({ binding: 1 + k, resource: view })))
+    }));
+    this.memIdx = 0;
+    this.memSize = [w, h];
   }
 
   start() {
     if (this.running) return;
+    this.pointer.attach();
+    // A manual clock draws only when sought or stepped
+    if (this.clock.manual) { this.render(); return; }
+    this.clock.resync();
     this.running = true;
-    this._loop();
-  }
-
-  _loop() {
-    if (!this.running) return;
-    this.render();
-    this._rafId = requestAnimationFrame(() => this._loop());
+    this.dirty = true;
+    GAME_SHARED.scheduler.add(this);
   }
 
   stop() {
     this.running = false;
-    if (this._rafId) { cancelAnimationFrame(this._rafId); this._rafId = null; }
+    GAME_SHARED.scheduler.remove(this);
   }
 
   render() {
-    const t = performance.now() / 1000 - this.startTime;
+    const t = this.clock.tick();
+    if (typeof arcUpdate === 'function') arcUpdate(t, this.userParams);
+    this.score?.apply(t, this.userParams);
+    this.pointer.frame(this.userParams);
+    if (typeof modulationUpdate === 'function') modulationUpdate(t, this);
     const w = this.canvas.width;
     const h = this.canvas.height;
     const data = new Float32Array(this.floatCount);
@@ -101,85 +436,270 @@ pub fn webgpu_renderer() -> &'static str {
     data[4] = this.audioData.energy;
     data[5] = this.audioData.beat;
     data[6] = w; data[7] = h;
-    data[8] = 0; data[9] = 0; // mouse
+    data[8] = this.mouse.x; data[9] = this.mouse.y;
     let i = 10;
     for (const u of this.uniformDefs) data[i++] = this.userParams[u.name] ?? u.default;
     this.device.queue.writeBuffer(this.uniformBuffer, 0, data);
 
     const encoder = this.device.createCommandEncoder();
-    const pass = encoder.beginRenderPass({
-      colorAttachments: [{
-        view: this.ctx.getCurrentTexture().createView(),
-        loadOp: 'clear', storeOp: 'store', clearValue: { r: 0, g: 0, b: 0, a: 1 }
-      }]
-    });
+    const canvasView = this.ctx.getCurrentTexture().createView();
+    if (this.postPipeline) this._ensureScene(w, h);
+    const colorAttachments = [{
+      view: this.postPipeline ? this.sceneView : canvasView,
+      loadOp: 'clear', storeOp: 'store', clearValue: { r: 0, g: 0, b: 0, a: 1 }
+    }];
+    if (this.memoryLayers > 0) {
+      this._initMemory(w, h);
+      for (const view of this.memViews[1 - this.memIdx]) {
+        colorAttachments.push({ view, loadOp: 'clear', storeOp: 'store', clearValue: { r: 0, g: 0, b: 0, a: 0 } });
+      }
+    }
+    const pass = encoder.beginRenderPass({ colorAttachments });
     pass.setPipeline(this.pipeline);
     pass.setBindGroup(0, this.bindGroup);
+    if (this.memoryLayers > 0) pass.setBindGroup(1, this.memBindGroups[this.memIdx]);
     pass.draw(3);
     pass.end();
+    if (this.postPipeline) {
+      const post = encoder.beginRenderPass({
+        colorAttachments: [{
+          view: canvasView,
+          loadOp: 'clear', storeOp: 'store', clearValue: { r: 0, g: 0, b: 0, a: 1 }
+        }]
+      });
+      post.setPipeline(this.postPipeline);
+      post.setBindGroup(0, this.bindGroup);
+      post.setBindGroup(1, this.sceneBindGroup);
+      post.draw(3);
+      post.end();
+    }
     this.device.queue.submit([encoder.finish()]);
+    if (this.memoryLayers > 0) this.memIdx = 1 - this.memIdx;
     if (this._onRender) this._onRender();
   }
 
-  setParam(name, value) { this.userParams[name] = value; }
-  setAudioData(d) { Object.assign(this.audioData, d); }
-  destroy() { this.stop(); if (this.device) this.device.destroy(); }
+  setParam(name, value) { this.userParams[name] = value; this.dirty = true; }
+  setAudioData(d) { Object.assign(this.audioData, d); this.dirty = true; }
+  destroy() {
+    this.stop();
+    this.pointer.detach();
+    // The device and pipelines are shared; only this element's resources go
+    this.ctx?.unconfigure();
+    this.uniformBuffer?.destroy();
+    this.sceneTexture?.destroy();
+    this.memTextures?.flat().forEach(t => t.destroy());
+  }
 }"#
 }
 
-/// WebGL2 fallback renderer class.
+/// WebGL2 fallback renderer class — renders on the page's shared WebGL2 context
+/// and copies each frame into the element's own 2D canvas.
 pub fn webgl2_renderer() -> &'static str {
     r#"class GameRendererGL {
-  constructor(canvas, glslVertex, glslFragment, uniformDefs) {
+  constructor(canvas, glslVertex, glslFragment, uniformDefs, glslPost = null, memoryLayers = 0) {
     this.canvas = canvas;
     this.glslVertex = glslVertex;
     this.glslFragment = glslFragment;
+    this.glslPost = glslPost;
     this.uniformDefs = uniformDefs;
+    this.memoryLayers = memoryLayers;
     this.gl = null;
     this.program = null;
+    this.postProgram = null;
+    this.fbo = null;
+    this.memFbo = null;
     this.running = false;
-    this._rafId = null;
-    this.startTime = performance.now() / 1000;
+    // Frame policy, read by the scheduler: a renderer that isn't animated
+    // draws only when dirty, and frameInterval (ms) caps its frame rate
+    this.animated = true;
+    this.dirty = true;
+    this.frameInterval = 0;
+    this.lastFrame = -Infinity;
+    this.clock = new GameClock();
+    // Score playback runs on the same clock as arcs and modulation
+    this.score = typeof GameScorePlayer === 'function' ? new GameScorePlayer() : null;
+    this.score?.start(0);
     this.audioData = { bass: 0, mid: 0, treble: 0, energy: 0, beat: 0 };
+    this.pointer = new GamePointer(canvas);
+    this.mouse = this.pointer.state;
     this.userParams = {};
     for (const u of uniformDefs) this.userParams[u.name] = u.default;
+    // Composite over the page (black is transparent); set before init()
+    this.transparent = false;
+  }
+
+  // One WebGL2 context for the whole page, on a hidden canvas: browsers cap
+  // live contexts, so each element renders there and copies the frame out
+  static sharedContext() {
+    if (!GAME_SHARED.gl) {
+      // Render workers have no DOM to make the canvas in
+      const canvas = typeof document === 'undefined'
+        ? new OffscreenCanvas(1, 1)
+        : document.createElement('canvas');
+      canvas.width = canvas.height = 1;
+      const gl = canvas.getContext('webgl2', { alpha: true, premultipliedAlpha: true });
+      if (!gl) return null;
+      GAME_SHARED.gl = { canvas, gl, programs: new Map() };
+    }
+    return GAME_SHARED.gl;
   }
 
   init() {
-    const gl = this.canvas.getContext('webgl2');
-    if (!gl) return false;
-    this.gl = gl;
-
-    const vs = this._compile(gl.VERTEX_SHADER, this.glslVertex);
-    const fs = this._compile(gl.FRAGMENT_SHADER, this.glslFragment);
-    if (!vs || !fs) return false;
-
-    this.program = gl.createProgram();
-    gl.attachShader(this.program, vs);
-    gl.attachShader(this.program, fs);
-    gl.linkProgram(this.program);
-    if (!gl.getProgramParameter(this.program, gl.LINK_STATUS)) {
-      console.error('GAME link error:', gl.getProgramInfoLog(this.program));
-      return false;
+    this.shared = GameRendererGL.sharedContext();
+    if (!this.shared) return false;
+    const gl = this.gl = this.shared.gl;
+
+    this.program = this._program(this.glslVertex, this.glslFragment);
+    if (!this.program) return false;
+    this.locs = this._locations(this.program);
+
+    // Lens post pass: layers render into an FBO, the post program samples it
+    if (this.glslPost) {
+      this.postProgram = this._program(this.glslVertex, this.glslPost);
+      if (!this.postProgram) return false;
+      this.postLocs = this._locations(this.postProgram);
+      this.sceneLoc = gl.getUniformLocation(this.postProgram, 'u_scene');
+      this.fbo = gl.createFramebuffer();
+      this.sceneTex = gl.createTexture();
+      gl.bindTexture(gl.TEXTURE_2D, this.sceneTex);
+      gl.texParameteri(gl.TEXTURE_2D, gl.TEXTURE_MIN_FILTER, gl.LINEAR);
+      gl.texParameteri(gl.TEXTURE_2D, gl.TEXTURE_MAG_FILTER, gl.LINEAR);
+      gl.texParameteri(gl.TEXTURE_2D, gl.TEXTURE_WRAP_S, gl.CLAMP_TO_EDGE);
+      gl.texParameteri(gl.TEXTURE_2D, gl.TEXTURE_WRAP_T, gl.CLAMP_TO_EDGE);
+      this.sceneSize = [0, 0];
     }
-    gl.useProgram(this.program);
 
-    // Cache uniform locations
-    this.locs = {
-      time: gl.getUniformLocation(this.program, 'u_time'),
-      bass: gl.getUniformLocation(this.program, 'u_audio_bass'),
-      mid: gl.getUniformLocation(this.program, 'u_audio_mid'),
-      treble: gl.getUniformLocation(this.program, 'u_audio_treble'),
-      energy: gl.getUniformLocation(this.program, 'u_audio_energy'),
-      beat: gl.getUniformLocation(this.program, 'u_audio_beat'),
-      resolution: gl.getUniformLocation(this.program, 'u_resolution'),
-      mouse: gl.getUniformLocation(this.program, 'u_mouse'),
+    // Memory layers: the scene renders into an FBO whose extra draw buffers
+    // hold each layer's new state; the other FBO's textures are the inputs
+    if (this.memoryLayers > 0) {
+      this.memLocs = [];
+      for (let k = 0; k < this.memoryLayers; k++) {
+        this.memLocs.push(gl.getUniformLocation(this.program, 'u_mem_' + k));
+      }
+      this.memFbo = [gl.createFramebuffer(), gl.createFramebuffer()];
+      this.memTex = [0, 1].map(() => Array.from({ length: this.memoryLayers }, () => this._texture()));
+      this.memColorTex = this.postProgram ? this.sceneTex : this._texture();
+      this.memIdx = 0;
+      this.memSize = [0, 0];
+    }
+
+    // Opaque elements drop the alpha the shader writes, as an opaque context would
+    this.ctx = this.canvas.getContext('2d', { alpha: this.transparent });
+    if (!this.ctx) return false;
+    this.ctx.globalCompositeOperation = 'copy';
+    return true;
+  }
+
+  // Programs are cached by source, so identical elements compile once
+  _program(vsSrc, fsSrc) {
+    const key = vsSrc + '\u0000' + fsSrc;
+    const programs = this.shared.programs;
+    if (!programs.has(key)) {
+      const vs = this._compile(this.gl.VERTEX_SHADER, vsSrc);
+      const fs = vs && this._compile(this.gl.FRAGMENT_SHADER, fsSrc);
+      programs.set(key, fs ? this._link(vs, fs) : null);
+    }
+    return programs.get(key);
+  }
+
+  // Grow the shared canvas to fit; frames draw into its bottom-left corner
+  _fitShared(w, h) {
+    const canvas = this.shared.canvas;
+    if (canvas.width < w) canvas.width = w;
+    if (canvas.height < h) canvas.height = h;
+  }
+
+  _texture() {
+    const gl = this.gl;
+    const tex = gl.createTexture();
+    gl.bindTexture(gl.TEXTURE_2D, tex);
+    gl.texParameteri(gl.TEXTURE_2D, gl.TEXTURE_MIN_FILTER, gl.LINEAR);
+    gl.texParameteri(gl.TEXTURE_2D, gl.TEXTURE_MAG_FILTER, gl.LINEAR);
+    gl.texParameteri(gl.TEXTURE_2D, gl.TEXTURE_WRAP_S, gl.CLAMP_TO_EDGE);
+    gl.texParameteri(gl.TEXTURE_2D, gl.TEXTURE_WRAP_T, gl.CLAMP_TO_EDGE);
+    return tex;
+  }
+
+  // (Re)allocate both sets of memory targets, cleared to zero
+  _initMemoryGL(w, h) {
+    const gl = this.gl;
+    if (this.memSize[0] === w && this.memSize[1] === h) return;
+    const textures = [this.memColorTex].concat(this.memTex.flat());
+    for (const tex of textures) {
+      gl.bindTexture(gl.TEXTURE_2D, tex);
+      gl.texImage2D(gl.TEXTURE_2D, 0, gl.RGBA8, w, h, 0, gl.RGBA, gl.UNSIGNED_BYTE, null);
+    }
+    if (this.postProgram) this.sceneSize = [w, h];
+    const buffers = [gl.COLOR_ATTACHMENT0];
+    for (let set = 0; set < 2; set++) {
+      gl.bindFramebuffer(gl.FRAMEBUFFER, this.memFbo[set]);
+      gl.framebufferTexture2D(gl.FRAMEBUFFER, gl.COLOR_ATTACHMENT0, gl.TEXTURE_2D, this.memColorTex, 0);
+      this.memTex[set].forEach((tex, k) => {
+        gl.framebufferTexture2D(gl.FRAMEBUFFER, gl.COLOR_ATTACHMENT1 + k, gl.TEXTURE_2D, tex, 0);
+        if (set === 0) buffers.push(gl.COLOR_ATTACHMENT1 + k);
+      });
+      gl.drawBuffers(buffers);
+    }
+    this.memIdx = 0;
+    this.memSize = [w, h];
+  }
+
+  _link(vs, fs) {
+    const gl = this.gl;
+    const program = gl.createProgram();
+    gl.attachShader(program, vs);
+    gl.attachShader(program, fs);
+    gl.linkProgram(program);
+    if (!gl.getProgramParameter(program, gl.LINK_STATUS)) {
+      console.error('GAME link error:', gl.getProgramInfoLog(program));
+      return null;
+    }
+    return program;
+  }
+
+  // Cache uniform locations for a program
+  _locations(program) {
+    const gl = this.gl;
+    const locs = {
+      time: gl.getUniformLocation(program, 'u_time'),
+      bass: gl.getUniformLocation(program, 'u_audio_bass'),
+      mid: gl.getUniformLocation(program, 'u_audio_mid'),
+      treble: gl.getUniformLocation(program, 'u_audio_treble'),
+      energy: gl.getUniformLocation(program, 'u_audio_energy'),
+      beat: gl.getUniformLocation(program, 'u_audio_beat'),
+      resolution: gl.getUniformLocation(program, 'u_resolution'),
+      mouse: gl.getUniformLocation(program, 'u_mouse'),
+      params: {},
     };
-    this.paramLocs = {};
     for (const u of this.uniformDefs) {
-      this.paramLocs[u.name] = gl.getUniformLocation(this.program, 'u_p_' + u.name);
+      locs.params[u.name] = gl.getUniformLocation(program, 'u_p_' + u.name);
     }
-    return true;
+    return locs;
+  }
+
+  _upload(locs, t) {
+    const gl = this.gl;
+    gl.uniform1f(locs.time, t);
+    gl.uniform1f(locs.bass, this.audioData.bass);
+    gl.uniform1f(locs.mid, this.audioData.mid);
+    gl.uniform1f(locs.treble, this.audioData.treble);
+    gl.uniform1f(locs.energy, this.audioData.energy);
+    gl.uniform1f(locs.beat, this.audioData.beat);
+    gl.uniform2f(locs.resolution, this.canvas.width, this.canvas.height);
+    gl.uniform2f(locs.mouse, this.mouse.x, this.mouse.y);
+    for (const u of this.uniformDefs) {
+      gl.uniform1f(locs.params[u.name], this.userParams[u.name] ?? u.default);
+    }
+  }
+
+  _ensureScene(w, h) {
+    const gl = this.gl;
+    if (this.sceneSize[0] === w && this.sceneSize[1] === h) return;
+    gl.bindTexture(gl.TEXTURE_2D, this.sceneTex);
+    gl.texImage2D(gl.TEXTURE_2D, 0, gl.RGBA8, w, h, 0, gl.RGBA, gl.UNSIGNED_BYTE, null);
+    gl.bindFramebuffer(gl.FRAMEBUFFER, this.fbo);
+    gl.framebufferTexture2D(gl.FRAMEBUFFER, gl.COLOR_ATTACHMENT0, gl.TEXTURE_2D, this.sceneTex, 0);
+    this.sceneSize = [w, h];
   }
 
   _compile(type, src) {
@@ -196,46 +716,274 @@ pub fn webgl2_renderer() -> &'static str {
 
   start() {
     if (this.running) return;
+    this.pointer.attach();
+    // A manual clock draws only when sought or stepped
+    if (this.clock.manual) { this.render(); return; }
+    this.clock.resync();
     this.running = true;
-    this._loop();
-  }
-
-  _loop() {
-    if (!this.running) return;
-    this.render();
-    this._rafId = requestAnimationFrame(() => this._loop());
+    this.dirty = true;
+    GAME_SHARED.scheduler.add(this);
   }
 
   stop() {
     this.running = false;
-    if (this._rafId) { cancelAnimationFrame(this._rafId); this._rafId = null; }
+    GAME_SHARED.scheduler.remove(this);
   }
 
   render() {
     const gl = this.gl;
-    const t = performance.now() / 1000 - this.startTime;
-    gl.viewport(0, 0, this.canvas.width, this.canvas.height);
+    const t = this.clock.tick();
+    if (typeof arcUpdate === 'function') arcUpdate(t, this.userParams);
+    this.score?.apply(t, this.userParams);
+    this.pointer.frame(this.userParams);
+    if (typeof modulationUpdate === 'function') modulationUpdate(t, this);
+    const w = this.canvas.width;
+    const h = this.canvas.height;
+    this._fitShared(w, h);
+    if (this.memoryLayers > 0) {
+      this._initMemoryGL(Math.max(1, w), Math.max(1, h));
+      gl.bindFramebuffer(gl.FRAMEBUFFER, this.memFbo[1 - this.memIdx]);
+    } else if (this.postProgram) {
+      this._ensureScene(Math.max(1, w), Math.max(1, h));
+      gl.bindFramebuffer(gl.FRAMEBUFFER, this.fbo);
+    } else {
+      gl.bindFramebuffer(gl.FRAMEBUFFER, null);
+    }
+    gl.viewport(0, 0, w, h);
     gl.clearColor(0, 0, 0, 1);
     gl.clear(gl.COLOR_BUFFER_BIT);
     gl.useProgram(this.program);
-
-    gl.uniform1f(this.locs.time, t);
-    gl.uniform1f(this.locs.bass, this.audioData.bass);
-    gl.uniform1f(this.locs.mid, this.audioData.mid);
-    gl.uniform1f(this.locs.treble, this.audioData.treble);
-    gl.uniform1f(this.locs.energy, this.audioData.energy);
-    gl.uniform1f(this.locs.beat, this.audioData.beat);
-    gl.uniform2f(this.locs.resolution, this.canvas.width, this.canvas.height);
-    gl.uniform2f(this.locs.mouse, 0, 0);
-    for (const u of this.uniformDefs) {
-      gl.uniform1f(this.paramLocs[u.name], this.userParams[u.name] ?? u.default);
+    this._upload(this.locs, t);
+    if (this.memoryLayers > 0) {
+      this.memTex[this.memIdx].forEach((tex, k) => {
+        gl.activeTexture(gl.TEXTURE1 + k);
+        gl.bindTexture(gl.TEXTURE_2D, tex);
+        gl.uniform1i(this.memLocs[k], 1 + k);
+      });
     }
     gl.drawArrays(gl.TRIANGLES, 0, 3);
+
+    if (this.memoryLayers > 0) {
+      if (!this.postProgram) {
+        gl.bindFramebuffer(gl.READ_FRAMEBUFFER, this.memFbo[1 - this.memIdx]);
+        gl.readBuffer(gl.COLOR_ATTACHMENT0);
+        gl.bindFramebuffer(gl.DRAW_FRAMEBUFFER, null);
+        gl.blitFramebuffer(0, 0, w, h, 0, 0, w, h, gl.COLOR_BUFFER_BIT, gl.NEAREST);
+        gl.bindFramebuffer(gl.FRAMEBUFFER, null);
+      }
+      this.memIdx = 1 - this.memIdx;
+    }
+
+    if (this.postProgram) {
+      gl.bindFramebuffer(gl.FRAMEBUFFER, null);
+      gl.clear(gl.COLOR_BUFFER_BIT);
+      gl.useProgram(this.postProgram);
+      this._upload(this.postLocs, t);
+      gl.activeTexture(gl.TEXTURE0);
+      gl.bindTexture(gl.TEXTURE_2D, this.sceneTex);
+      gl.uniform1i(this.sceneLoc, 0);
+      gl.drawArrays(gl.TRIANGLES, 0, 3);
+    }
+
+    // Copy out of the shared canvas; GL rows start at the bottom
+    if (w > 0 && h > 0) {
+      const src = this.shared.canvas;
+      this.ctx.drawImage(src, 0, src.height - h, w, h, 0, 0, w, h);
+    }
     if (this._onRender) this._onRender();
   }
 
-  setParam(name, value) { this.userParams[name] = value; }
-  setAudioData(d) { Object.assign(this.audioData, d); }
-  destroy() { this.stop(); }
+  setParam(name, value) { this.userParams[name] = value; this.dirty = true; }
+  setAudioData(d) { Object.assign(this.audioData, d); this.dirty = true; }
+  destroy() {
+    this.stop();
+    this.pointer.detach();
+    // The context and programs are shared; only this element's targets go
+    const gl = this.gl;
+    if (this.memFbo) {
+      this.memFbo.forEach(fb => gl.deleteFramebuffer(fb));
+      this.memTex.flat().forEach(tex => gl.deleteTexture(tex));
+      if (!this.postProgram) gl.deleteTexture(this.memColorTex);
+      this.memFbo = null;
+    }
+    if (this.fbo) {
+      gl.deleteFramebuffer(this.fbo);
+      gl.deleteTexture(this.sceneTex);
+      this.fbo = null;
+    }
+  }
+}"#
+}
+
+/// Render-worker mode — `GameWorkerRenderer` stands in for a renderer on the
+/// main thread and forwards to `gameWorkerMain`, which runs the real one on a
+/// worker that owns the element's transferred canvas.
+pub fn game_worker() -> &'static str {
+    r#"class GameWorkerRenderer {
+  constructor(canvas, main) {
+    this.canvas = canvas;
+    // The component's own function: the worker runs it again to get the
+    // same shaders, renderers and modules
+    this.main = main;
+    this.offscreen = true;
+    this.running = false;
+    this.transparent = false;
+    this.clock = null;
+    // Kept here too for `react`, which runs on the main thread
+    this.audioData = { bass: 0, mid: 0, treble: 0, energy: 0, beat: 0 };
+    this.pointer = new GamePointer(canvas);
+    this.pointer.forward = (event) => this._post({ type: 'pointer', event });
+    this.mouse = this.pointer.state;
+    this.thread = null;
+    this._ready = null;
+  }
+
+  static supported(canvas) {
+    return typeof canvas.transferControlToOffscreen === 'function' && typeof Worker === 'function';
+  }
+
+  // One worker per component, shared by all of its elements
+  static thread(main) {
+    let thread = GameWorkerRenderer._thread;
+    if (!thread) {
+      const url = URL.createObjectURL(new Blob(['(' + main + ')();'], { type: 'text/javascript' }));
+      const worker = new Worker(url);
+      thread = GameWorkerRenderer._thread = { worker, url, renderers: new Map(), nextId: 1 };
+      worker.onmessage = ({ data }) => thread.renderers.get(data.id)?._receive(data);
+      // A worker that fails to load (e.g. blocked by CSP) renders nothing
+      worker.onerror = (e) => {
+        console.warn('GAME: render worker failed, rendering on the main thread:', e.message);
+        for (const r of thread.renderers.values()) r._receive({ type: 'ready', ok: false });
+      };
+    }
+    return thread;
+  }
+
+  init() {
+    try {
+      this.thread = GameWorkerRenderer.thread(this.main);
+    } catch (e) {
+      return Promise.resolve(false);
+    }
+    this.id = this.thread.nextId++;
+    this.thread.renderers.set(this.id, this);
+    const canvas = this.canvas.transferControlToOffscreen();
+    this.thread.worker.postMessage({
+      type: 'init', id: this.id, canvas, transparent: this.transparent, clock: this._clockState(true),
+    }, [canvas]);
+    return new Promise(resolve => { this._ready = resolve; });
+  }
+
+  _receive(msg) {
+    if (msg.type === 'ready') {
+      this._ready?.(msg.ok);
+      this._ready = null;
+    } else if (msg.type === 'frame') {
+      // The worker's clock runs the show; mirror it for currentTime
+      this.clock.time = msg.time;
+      if (this._onRender) this._onRender();
+    }
+  }
+
+  _post(msg) { this.thread?.worker.postMessage({ ...msg, id: this.id }); }
+
+  _clockState(withTime) {
+    const { scale, motion, paused, manual, time } = this.clock;
+    return withTime ? { scale, motion, paused, manual, time } : { scale, motion, paused, manual };
+  }
+
+  start() {
+    if (this.running) return;
+    this.pointer.attach();
+    this._post({ type: 'start' });
+    // As in the worker: a manual clock draws once instead of running
+    this.running = !this.clock.manual;
+  }
+
+  stop() {
+    this.running = false;
+    this._post({ type: 'stop' });
+  }
+
+  render() { this._post({ type: 'render' }); }
+  resize(width, height) { this._post({ type: 'size', width, height }); }
+  setParam(name, value) { this._post({ type: 'param', name, value }); }
+  setAudioData(d) {
+    Object.assign(this.audioData, d);
+    this._post({ type: 'audio', data: d });
+  }
+  // The worker's clock is a copy; send what changed, and the time only
+  // when it was set rather than merely ticked
+  syncClock(seeked = false) { this._post({ type: 'clock', clock: this._clockState(seeked) }); }
+
+  set animated(v) { this._post({ type: 'policy', policy: { animated: v } }); }
+  set frameInterval(v) { this._post({ type: 'policy', policy: { frameInterval: v } }); }
+  set dirty(v) { this._post({ type: 'policy', policy: { dirty: v } }); }
+
+  destroy() {
+    const thread = this.thread;
+    if (!thread) return;
+    this.stop();
+    this.pointer.detach();
+    this._post({ type: 'destroy' });
+    this._ready?.(false);
+    this._ready = null;
+    thread.renderers.delete(this.id);
+    this.thread = null;
+    if (thread.renderers.size === 0) {
+      thread.worker.terminate();
+      URL.revokeObjectURL(thread.url);
+      GameWorkerRenderer._thread = null;
+    }
+  }
+}
+
+// Worker side: builds a renderer for each transferred canvas and applies
+// what its element forwards. An element's messages wait for its renderer,
+// so they apply in the order they were sent.
+function gameWorkerMain(create) {
+  // Not every browser gives dedicated workers requestAnimationFrame
+  if (typeof requestAnimationFrame !== 'function') {
+    globalThis.requestAnimationFrame = (f) => setTimeout(() => f(performance.now()), 1000 / 60);
+    globalThis.cancelAnimationFrame = clearTimeout;
+  }
+  const renderers = new Map();
+  self.onmessage = async ({ data: msg }) => {
+    const id = msg.id;
+    if (msg.type === 'init') {
+      const ready = create(msg.canvas, msg.transparent).catch(() => null);
+      renderers.set(id, ready);
+      const r = await ready;
+      if (r) {
+        Object.assign(r.clock, msg.clock);
+        r._onRender = () => self.postMessage({ type: 'frame', id, time: r.clock.time });
+      } else {
+        renderers.delete(id);
+      }
+      self.postMessage({ type: 'ready', id, ok: !!r });
+      return;
+    }
+    const r = await renderers.get(id);
+    if (!r) return;
+    switch (msg.type) {
+      case 'size':
+        r.canvas.width = msg.width;
+        r.canvas.height = msg.height;
+        r.dirty = true;
+        break;
+      case 'param': r.setParam(msg.name, msg.value); break;
+      case 'audio': r.setAudioData(msg.data); break;
+      case 'pointer': r.pointer.handle(msg.event); break;
+      case 'clock': Object.assign(r.clock, msg.clock); break;
+      case 'policy': Object.assign(r, msg.policy); break;
+      case 'start': r.start(); break;
+      case 'stop': r.stop(); break;
+      case 'render': r.render(); break;
+      case 'destroy':
+        r.destroy();
+        renderers.delete(id);
+        break;
+    }
+  };
 }"#
 }
@@ -3,13 +3,22 @@
 //! Generates a single `.html` file with embedded shaders and renderer.
 
 use crate::codegen::ShaderOutput;
+use crate::ShaderTarget;
 
 /// Generate a self-contained HTML page.
-pub fn generate_html(shader: &ShaderOutput) -> String {
+///
+/// Only the renderer classes and shader strings for `target` are embedded.
+pub fn generate_html(shader: &ShaderOutput, target: &ShaderTarget) -> String {
     let wgsl_v = escape_html_js(&shader.wgsl_vertex);
     let wgsl_f = escape_html_js(&shader.wgsl_fragment);
     let glsl_v = escape_html_js(&shader.glsl_vertex);
     let glsl_f = escape_html_js(&shader.glsl_fragment);
+    let post = |src: &Option<String>| match src {
+        Some(src) => format!("`{}`", escape_html_js(src)),
+        None => "null".to_string(),
+    };
+    let wgsl_post = post(&shader.post_wgsl);
+    let glsl_post = post(&shader.post_glsl);
 
     let uniform_defs_json = shader
         .uniforms
@@ -25,20 +34,39 @@ pub fn generate_html(shader: &ShaderOutput) -> String {
     s.push_str("<meta charset=\"utf-8\">\n");
     s.push_str("<meta name=\"viewport\" content=\"width=device-width,initial-scale=1\">\n");
     s.push_str(&format!("<title>{} — GAME</title>\n", shader.name));
-    s.push_str("<style>*{margin:0;padding:0}html,body{width:100%;height:100%;overflow:hidden;background:#000}canvas{width:100%;height:100%;display:block}</style>\n");
+    s.push_str("<style>*{margin:0;padding:0}html,body{width:100%;height:100%;overflow:hidden;background:#000}canvas{width:100%;height:100%;display:block;touch-action:none}</style>\n");
     s.push_str("</head>\n<body>\n<canvas id=\"c\"></canvas>\n<script>\n");
 
-    s.push_str(&format!("const WGSL_V = `{wgsl_v}`;\n"));
-    s.push_str(&format!("const WGSL_F = `{wgsl_f}`;\n"));
-    s.push_str(&format!("const GLSL_V = `{glsl_v}`;\n"));
-    s.push_str(&format!("const GLSL_F = `{glsl_f}`;\n"));
-    s.push_str(&format!("const UNIFORMS = [{uniform_defs_json}];\n\n"));
+    if target.includes_webgpu() {
+        s.push_str(&format!("const WGSL_V = `{wgsl_v}`;\n"));
+        s.push_str(&format!("const WGSL_F = `{wgsl_f}`;\n"));
+        s.push_str(&format!("const WGSL_POST = {wgsl_post};\n"));
+    }
+    if target.includes_webgl2() {
+        s.push_str(&format!("const GLSL_V = `{glsl_v}`;\n"));
+        s.push_str(&format!("const GLSL_F = `{glsl_f}`;\n"));
+        s.push_str(&format!("const GLSL_POST = {glsl_post};\n"));
+    }
+    s.push_str(&format!("const UNIFORMS = [{uniform_defs_json}];\n"));
+    s.push_str(&format!("const MEMORY_LAYERS = {};\n", shader.memory_layers));
+    s.push_str(&format!("const ANIMATED = {};\n\n", shader.animated));
 
-    s.push_str(super::helpers::webgpu_renderer());
+    s.push_str(super::helpers::game_shared());
     s.push_str("\n\n");
-    s.push_str(super::helpers::webgl2_renderer());
+    s.push_str(super::helpers::game_clock());
+    s.push_str("\n\n");
+    s.push_str(super::helpers::game_pointer());
     s.push_str("\n\n");
 
+    if target.includes_webgpu() {
+        s.push_str(super::helpers::webgpu_renderer());
+        s.push_str("\n\n");
+    }
+    if target.includes_webgl2() {
+        s.push_str(super::helpers::webgl2_renderer());
+        s.push_str("\n\n");
+    }
+
     // Inject feature JS modules (listen, voice, score, temporal, gravity, breed)
     for module_js in &shader.js_modules {
         s.push_str(module_js);
@@ -47,21 +75,32 @@ pub fn generate_html(shader: &ShaderOutput) -> String {
 
     s.push_str("(async function() {\n");
     s.push_str("  const canvas = document.getElementById('c');\n");
+    s.push_str("  let renderer = null;\n");
     s.push_str("  function resize() {\n");
     s.push_str("    canvas.width = window.innerWidth * devicePixelRatio;\n");
     s.push_str("    canvas.height = window.innerHeight * devicePixelRatio;\n");
+    s.push_str("    if (renderer) renderer.dirty = true;\n");
     s.push_str("  }\n");
     s.push_str("  window.addEventListener('resize', resize);\n");
     s.push_str("  resize();\n\n");
-    s.push_str("  let renderer = null;\n");
-    s.push_str("  const gpu = new GameRenderer(canvas, WGSL_V, WGSL_F, UNIFORMS);\n");
-    s.push_str("  if (await gpu.init()) { renderer = gpu; }\n");
-    s.push_str("  if (!renderer) {\n");
-    s.push_str("    const gl = new GameRendererGL(canvas, GLSL_V, GLSL_F, UNIFORMS);\n");
-    s.push_str("    if (gl.init()) { renderer = gl; }\n");
-    s.push_str("  }\n");
-    s.push_str("  if (!renderer) { document.body.textContent = 'No WebGPU or WebGL2 support.'; return; }\n");
+    if target.includes_webgpu() {
+        s.push_str("  const gpu = new GameRenderer(canvas, WGSL_V, WGSL_F, UNIFORMS, WGSL_POST, MEMORY_LAYERS);\n");
+        s.push_str("  if (await gpu.init()) { renderer = gpu; }\n");
+    }
+    if target.includes_webgl2() {
+        s.push_str("  if (!renderer) {\n");
+        s.push_str("    const gl = new GameRendererGL(canvas, GLSL_V, GLSL_F, UNIFORMS, GLSL_POST, MEMORY_LAYERS);\n");
+        s.push_str("    if (gl.init()) { renderer = gl; }\n");
+        s.push_str("  }\n");
+    }
+    let unsupported = match target {
+        ShaderTarget::Both => "No WebGPU or WebGL2 support.",
+        ShaderTarget::WebGpu => "No WebGPU support.",
+        ShaderTarget::WebGl2 => "No WebGL2 support.",
+    };
+    s.push_str(&format!("  if (!renderer) {{ document.body.textContent = '{unsupported}'; return; }}\n"));
     s.push_str("  if (typeof _gameReactSetup === 'function') _gameReactSetup(canvas, renderer);\n");
+    s.push_str("  renderer.animated = ANIMATED;\n");
     s.push_str("  renderer.start();\n");
     s.push_str("})();\n");
 
@@ -91,11 +130,15 @@ mod tests {
             glsl_fragment: "void main(){}".into(),
             glsl_vertex: "void main(){}".into(),
             uniforms: vec![],
-            uses_memory: false,
+            memory_layers: 0,
             js_modules: vec![],
             compute_wgsl: None,
+            data_fields: vec![],
+            post_wgsl: None,
+            post_glsl: None,
+            animated: true,
         };
-        let html = generate_html(&shader);
+        let html = generate_html(&shader, &ShaderTarget::Both);
         assert!(html.contains("<!DOCTYPE html>"));
         assert!(html.contains("<title>demo"));
         assert!(html.contains("class GameRenderer"));
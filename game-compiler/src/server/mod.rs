@@ -8,6 +8,7 @@ pub mod export;
 pub mod page;
 pub mod util;
 
+use std::collections::HashMap;
 use std::net::SocketAddr;
 use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
@@ -21,13 +22,17 @@ use notify::{Event, RecursiveMode, Watcher};
 use serde::{Deserialize, Serialize};
 use tower_livereload::LiveReloadLayer;
 
-use crate::{CompileConfig, CompileOutput, OutputFormat, ShaderTarget};
+use crate::error::render_diagnostic;
+use crate::source::{FsProvider, MemoryProvider, OverlayProvider, SourceProvider};
+use crate::{CompileConfig, CompileOutput, OptLevel, OutputFormat, ShaderTarget};
 
 // ── State ───────────────────────────────────────────
 
 struct DevState {
     source_path: PathBuf,
     tag_name: String,
+    /// Extra import search paths (`--lib-dir`).
+    lib_dirs: Vec<PathBuf>,
 }
 
 // ── Helpers ─────────────────────────────────────────
@@ -71,28 +76,71 @@ fn derive_tag_name(path: &Path) -> String {
     format!("game-{collapsed}")
 }
 
+/// Directory that imports in the watched file resolve against.
+fn base_dir(state: &DevState) -> PathBuf {
+    let dir = state.source_path.parent().unwrap_or(Path::new("."));
+    dir.canonicalize().unwrap_or_else(|_| dir.to_path_buf())
+}
+
+/// Compile source text as if it were the watched file, resolving its imports
+/// through `provider`.
+fn compile_text(
+    state: &DevState,
+    source: &str,
+    provider: &dyn SourceProvider,
+) -> Result<Vec<CompileOutput>, String> {
+    let config = CompileConfig {
+        output_format: OutputFormat::Html,
+        target: ShaderTarget::Both,
+        opt_level: OptLevel::O1,
+    };
+    crate::compile_with_provider(source, &config, provider, &base_dir(state), &state.lib_dirs)
+        .map_err(|e| render_diagnostic(&e.to_diagnostic(), source))
+}
+
+/// Unsaved editor buffers (paths relative to the watched file's directory)
+/// layered over the filesystem.
+fn unsaved_overlay(state: &DevState, files: &HashMap<String, String>) -> OverlayProvider {
+    let base = base_dir(state);
+    let mut unsaved = MemoryProvider::new();
+    for (path, source) in files {
+        unsaved.insert(base.join(path), source.as_str());
+    }
+    OverlayProvider::new().layer(unsaved).layer(FsProvider)
+}
+
 /// Read the source file and compile it, returning the source text and compilation result.
 fn compile_source(state: &DevState) -> (String, Result<Vec<CompileOutput>, String>) {
     let source = match std::fs::read_to_string(&state.source_path) {
         Ok(s) => s,
         Err(e) => return (String::new(), Err(format!("read error: {e}"))),
     };
-    let config = CompileConfig {
-        output_format: OutputFormat::Html,
-        target: ShaderTarget::Both,
-    };
-    let result = crate::compile(&source, &config).map_err(|e| e.to_string());
+    let result = compile_text(state, &source, &FsProvider);
     (source, result)
 }
 
 /// Extract uniform info by compiling to AST and running codegen.
-fn extract_uniforms_from_source(source: &str) -> Vec<UniformParam> {
-    let program = match crate::compile_to_ast(source) {
+fn extract_uniforms_from_source(
+    state: &DevState,
+    source: &str,
+    provider: &dyn SourceProvider,
+) -> Vec<UniformParam> {
+    let mut program = match crate::compile_to_ast(source) {
         Ok(p) => p,
         Err(_) => return Vec::new(),
     };
+    let resolved = crate::resolver::resolve_imports_with(
+        &mut program,
+        provider,
+        &base_dir(state),
+        &state.lib_dirs,
+    );
+    if resolved.is_err() {
+        return Vec::new();
+    }
     let mut params = Vec::new();
-    for cinematic in &program.cinematics {
+    for cinematic in &mut program.cinematics {
+        let _ = crate::codegen::analysis::expand_defines(cinematic);
         if let Ok(shader) = crate::codegen::generate(cinematic) {
             for u in &shader.uniforms {
                 params.push(UniformParam {
@@ -108,12 +156,17 @@ fn extract_uniforms_from_source(source: &str) -> Vec<UniformParam> {
 // ── Public entry point ──────────────────────────────
 
 /// Start the hot-reload dev server for a single `.game` file.
-pub async fn run_dev_server(path: PathBuf, port: u16) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+pub async fn run_dev_server(
+    path: PathBuf,
+    port: u16,
+    lib_dirs: Vec<PathBuf>,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
     let tag_name = derive_tag_name(&path);
 
     let state = Arc::new(Mutex::new(DevState {
         source_path: path.clone(),
         tag_name: tag_name.clone(),
+        lib_dirs,
     }));
 
     // LiveReload layer — injected into HTML responses
@@ -211,6 +264,10 @@ async fn serve_fullscreen(State(state): State<Arc<Mutex<DevState>>>) -> Html<Str
 #[derive(Deserialize)]
 struct CompileRequest {
     source: String,
+    /// Unsaved buffers of other files, keyed by path relative to the watched
+    /// file. They shadow the saved copies when resolving imports.
+    #[serde(default)]
+    files: HashMap<String, String>,
 }
 
 #[derive(Serialize)]
@@ -218,6 +275,8 @@ struct CompileResponse {
     wgsl: Option<String>,
     js: Option<String>,
     error: Option<String>,
+    /// Errors and warnings as structured JSON (see `Diagnostics::to_json`).
+    diagnostics: serde_json::Value,
     params: Vec<UniformParam>,
 }
 
@@ -228,29 +287,32 @@ struct UniformParam {
 }
 
 async fn serve_compile(
-    State(_state): State<Arc<Mutex<DevState>>>,
+    State(state): State<Arc<Mutex<DevState>>>,
     Json(req): Json<CompileRequest>,
 ) -> Json<CompileResponse> {
-    let config = CompileConfig {
-        output_format: OutputFormat::Html,
-        target: ShaderTarget::Both,
-    };
-    match crate::compile(&req.source, &config) {
+    let st = state.lock().unwrap();
+    let provider = unsaved_overlay(&st, &req.files);
+    let diagnostics =
+        crate::diagnose_with_provider(&req.source, &provider, &base_dir(&st), &st.lib_dirs)
+            .to_json(&req.source);
+    match compile_text(&st, &req.source, &provider) {
         Ok(outputs) => {
             let wgsl = outputs.first().and_then(|o| o.wgsl.clone());
             let js = outputs.first().map(|o| o.js.clone());
-            let params = extract_uniforms_from_source(&req.source);
+            let params = extract_uniforms_from_source(&st, &req.source, &provider);
             Json(CompileResponse {
                 wgsl,
                 js,
                 error: None,
+                diagnostics,
                 params,
             })
         }
         Err(e) => Json(CompileResponse {
             wgsl: None,
             js: None,
-            error: Some(e.to_string()),
+            error: Some(e),
+            diagnostics,
             params: Vec::new(),
         }),
     }
@@ -318,4 +380,39 @@ mod tests {
             "game-particle-storm"
         );
     }
+
+    #[test]
+    fn unsaved_buffers_shadow_saved_imports() {
+        let dir = std::env::temp_dir().join("game_dev_server_overlay");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(
+            dir.join("fx.game"),
+            r#"cinematic "fx" { define dot(r) { circle(r) | glow(1.0) } }"#,
+        )
+        .unwrap();
+        let state = DevState {
+            source_path: dir.join("main.game"),
+            tag_name: "game-main".into(),
+            lib_dirs: vec![],
+        };
+        let src = r#"import "fx" expose dot  cinematic "main" { layer { fn: dot(0.25) } }"#;
+
+        let saved = compile_text(&state, src, &FsProvider).unwrap();
+        assert!(saved[0]
+            .wgsl
+            .as_ref()
+            .unwrap()
+            .contains("sdf_circle(p, 0.250000)"));
+
+        let mut files = HashMap::new();
+        files.insert(
+            "fx.game".to_string(),
+            r#"cinematic "fx" { define dot(r) { ring(r, 0.02) | glow(1.0) } }"#.to_string(),
+        );
+        let unsaved = compile_text(&state, src, &unsaved_overlay(&state, &files)).unwrap();
+        assert!(!unsaved[0].wgsl.as_ref().unwrap().contains("sdf_circle"));
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
 }
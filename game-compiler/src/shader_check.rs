@@ -0,0 +1,439 @@
+//! Strict shader validation for `game check --strict`.
+//!
+//! Every shader the compiler emits for a cinematic — the WGSL fragment,
+//! vertex, compute and lens post modules and the GLSL ES 3.00 fragment,
+//! vertex and post shaders — is parsed and validated with naga. Failures
+//! become E011 diagnostics at the layer and stage whose generated code
+//! contains the offending line.
+
+use std::ops::Range;
+
+use naga::valid::{Capabilities, ValidationFlags, Validator};
+use naga::ShaderStage;
+
+use crate::ast::{Cinematic, LayerBody, Program, Stage};
+use crate::codegen::{self, blend, glsl, lens, project, wgsl};
+use crate::error::{Diagnostic, Diagnostics, ErrorCode, Span};
+use crate::OptLevel;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Language {
+    Wgsl,
+    Glsl,
+}
+
+/// One emitted shader module, with the layer/stage each part came from.
+#[derive(Debug, Clone)]
+pub struct GeneratedShader {
+    /// `fragment.wgsl`, `post.frag`, ...
+    pub label: &'static str,
+    pub language: Language,
+    pub stage: ShaderStage,
+    pub source: String,
+    origins: Vec<Origin>,
+}
+
+/// Where a byte range of generated code came from.
+#[derive(Debug, Clone)]
+struct Origin {
+    range: Range<usize>,
+    layer: String,
+    stage: Option<(String, Span)>,
+}
+
+/// A naga parse or validation failure.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ShaderError {
+    pub message: String,
+    /// Byte offset of the offending code in the shader source.
+    pub offset: Option<usize>,
+}
+
+/// Validate every shader emitted for `program` at `opt_level`.
+///
+/// `program` must have its imports resolved. Cinematics that fail ordinary
+/// validation are skipped; [`crate::diagnose`] already reports them.
+pub fn check_program(program: &Program, opt_level: OptLevel) -> Diagnostics {
+    let mut diags = Diagnostics::new();
+    for cinematic in &program.cinematics {
+        let mut cinematic = cinematic.clone();
+        crate::lower_cinematic(&mut cinematic, opt_level);
+        let Ok(mut output) = codegen::generate(&cinematic) else { continue };
+        if opt_level == OptLevel::O2 {
+            codegen::minify::minify_output(&mut output);
+        }
+
+        let mut shaders = generated_shaders(&cinematic, &output);
+        for proj in program.projects.iter().filter(|p| p.source == cinematic.name) {
+            shaders.push(GeneratedShader::new(
+                "project.wgsl",
+                Language::Wgsl,
+                ShaderStage::Vertex,
+                project::generate_vertex_wgsl(&proj.mode),
+            ));
+        }
+
+        for shader in &shaders {
+            if let Err(e) = validate(shader) {
+                diags.push(shader.diagnostic(&cinematic.name, &e));
+            }
+        }
+    }
+    diags
+}
+
+/// The shaders in `output`, generated from the lowered `cinematic`.
+pub fn generated_shaders(cinematic: &Cinematic, output: &codegen::ShaderOutput) -> Vec<GeneratedShader> {
+    use Language::{Glsl, Wgsl};
+    use ShaderStage::{Compute, Fragment, Vertex};
+
+    let mut shaders = vec![
+        GeneratedShader::new("fragment.wgsl", Wgsl, Fragment, output.wgsl_fragment.clone())
+            .with_layer_origins(cinematic, wgsl::emit_wgsl_stage),
+        GeneratedShader::new("vertex.wgsl", Wgsl, Vertex, output.wgsl_vertex.clone()),
+        GeneratedShader::new("fragment.frag", Glsl, Fragment, output.glsl_fragment.clone())
+            .with_layer_origins(cinematic, glsl::emit_glsl_stage),
+        GeneratedShader::new("vertex.vert", Glsl, Vertex, output.glsl_vertex.clone()),
+    ];
+    if let Some(post) = &output.post_wgsl {
+        shaders.push(
+            GeneratedShader::new("post.wgsl", Wgsl, Fragment, post.clone())
+                .with_post_origins(cinematic, wgsl::emit_wgsl_stage),
+        );
+    }
+    if let Some(post) = &output.post_glsl {
+        shaders.push(
+            GeneratedShader::new("post.frag", Glsl, Fragment, post.clone())
+                .with_post_origins(cinematic, glsl::emit_glsl_stage),
+        );
+    }
+    if let Some(compute) = &output.compute_wgsl {
+        shaders.push(GeneratedShader::new("gravity.wgsl", Wgsl, Compute, compute.clone()));
+    }
+    shaders
+}
+
+/// Parse and validate one shader with naga.
+pub fn validate(shader: &GeneratedShader) -> Result<(), ShaderError> {
+    let module = match shader.language {
+        Language::Wgsl => naga::front::wgsl::parse_str(&shader.source).map_err(|e| ShaderError {
+            message: e.message().to_string(),
+            offset: e.labels().find_map(|(span, _)| span.to_range()).map(|r| r.start),
+        })?,
+        Language::Glsl => {
+            let desktop = DesktopGlsl::new(&shader.source);
+            let module = naga::front::glsl::Frontend::default()
+                .parse(&shader.stage.into(), &desktop.source)
+                .map_err(|e| {
+                    let first = e.errors.first();
+                    ShaderError {
+                        message: first.map_or_else(|| "GLSL parse error".into(), |e| e.kind.to_string()),
+                        offset: first
+                            .and_then(|e| e.meta.to_range())
+                            .map(|r| desktop.original_offset(r.start)),
+                    }
+                })?;
+            return validate_module(&module, |offset| desktop.original_offset(offset));
+        }
+    };
+    validate_module(&module, |offset| offset)
+}
+
+fn validate_module(
+    module: &naga::Module,
+    original_offset: impl Fn(usize) -> usize,
+) -> Result<(), ShaderError> {
+    Validator::new(ValidationFlags::all(), Capabilities::default())
+        .validate(module)
+        .map(|_| ())
+        .map_err(|e| {
+            // The top-level error only names the function; the cause chain
+            // says what is wrong with it.
+            let mut message = e.as_inner().to_string();
+            let mut source: Option<&dyn std::error::Error> =
+                std::error::Error::source(e.as_inner());
+            while let Some(cause) = source {
+                message.push_str(": ");
+                message.push_str(&cause.to_string());
+                source = cause.source();
+            }
+            ShaderError {
+                message,
+                offset: e
+                    .spans()
+                    .find_map(|(span, _)| span.to_range())
+                    .map(|r| original_offset(r.start)),
+            }
+        })
+}
+
+/// GLSL ES 3.00 rewritten into the Vulkan-flavoured GLSL 4.50 that naga's
+/// front-end reads, with enough bookkeeping to map offsets back.
+struct DesktopGlsl {
+    source: String,
+    /// `(offset in the rewrite, bytes added so far)`, ascending.
+    shifts: Vec<(usize, usize)>,
+}
+
+impl DesktopGlsl {
+    /// Bump the version, give uniforms a binding and stage in/outs a
+    /// location (unless they declare one, like memory outputs), split each
+    /// `sampler2D` into a texture and a sampler, and spell `gl_VertexID`
+    /// the Vulkan way. Original text is only ever added to, never
+    /// reordered, so offsets map back exactly. Declarations are found by
+    /// statement rather than by line, since `-O2` puts them all on one.
+    fn new(es: &str) -> Self {
+        let mut out = Self { source: String::with_capacity(es.len() + 256), shifts: Vec::new() };
+        let mut renames = vec![("gl_VertexID".to_string(), "gl_VertexIndex".to_string())];
+        let (mut binding, mut input, mut output) = (0, 0, 0);
+        for line in es.split_inclusive(['\n', ';', '{', '}']) {
+            if let Some(rest) = line.strip_prefix("#version 300 es") {
+                // Same length, so nothing after it moves.
+                out.source.push_str("#version 450   ");
+                out.source.push_str(rest);
+            } else if let Some(name) = line.strip_prefix("uniform sampler2D ") {
+                let name = name.trim_end().trim_end_matches(';');
+                out.push_added(&format!("layout(binding = {binding}) "));
+                out.source.push_str("uniform texture2D ");
+                out.push_rewritten(&line["uniform sampler2D ".len()..], &[]);
+                out.push_added(&format!(
+                    "layout(binding = {}) uniform sampler {name}_sampler;\n",
+                    binding + 1
+                ));
+                binding += 2;
+                renames.push((format!("({name},"), format!("(sampler2D({name}, {name}_sampler),")));
+            } else {
+                let qualifier = if line.starts_with("uniform ") {
+                    binding += 1;
+                    format!("layout(binding = {}) ", binding - 1)
+                } else if line.starts_with("in ") {
+                    input += 1;
+                    format!("layout(location = {}) ", input - 1)
+                } else if line.starts_with("out ") {
+                    output += 1;
+                    format!("layout(location = {}) ", output - 1)
+                } else {
+                    String::new()
+                };
+                out.push_added(&qualifier);
+                out.push_rewritten(line, &renames);
+            }
+        }
+        out
+    }
+
+    /// Push text that has no counterpart in the original.
+    fn push_added(&mut self, text: &str) {
+        if !text.is_empty() {
+            self.source.push_str(text);
+            let added = self.added() + text.len();
+            self.shifts.push((self.source.len(), added));
+        }
+    }
+
+    /// Push original text, replacing each `from` with its longer `to`.
+    fn push_rewritten(&mut self, mut text: &str, renames: &[(String, String)]) {
+        while let Some((at, from, to)) = renames
+            .iter()
+            .filter_map(|(from, to)| text.find(from.as_str()).map(|at| (at, from, to)))
+            .min_by_key(|&(at, _, _)| at)
+        {
+            self.source.push_str(&text[..at]);
+            self.source.push_str(to);
+            let added = self.added() + to.len() - from.len();
+            self.shifts.push((self.source.len(), added));
+            text = &text[at + from.len()..];
+        }
+        self.source.push_str(text);
+    }
+
+    fn added(&self) -> usize {
+        self.shifts.last().map_or(0, |&(_, added)| added)
+    }
+
+    /// Map an offset in the rewrite back to the original source.
+    fn original_offset(&self, offset: usize) -> usize {
+        let i = self.shifts.partition_point(|&(at, _)| at <= offset);
+        offset - i.checked_sub(1).map_or(0, |i| self.shifts[i].1)
+    }
+}
+
+type StageEmitter = fn(&mut String, &Stage, &str);
+
+impl GeneratedShader {
+    fn new(label: &'static str, language: Language, stage: ShaderStage, source: String) -> Self {
+        Self { label, language, stage, source, origins: Vec::new() }
+    }
+
+    /// Locate each layer by its `// ── Layer N: name ──` marker, then each
+    /// stage by re-emitting it and finding the text inside the layer.
+    fn with_layer_origins(mut self, cinematic: &Cinematic, emit: StageEmitter) -> Self {
+        let indent = if blend::composites(cinematic) { "        " } else { "    " };
+        let mut cursor = 0;
+        for (idx, layer) in cinematic.layers.iter().enumerate() {
+            let LayerBody::Pipeline(stages) = &layer.body else { continue };
+            let marker = format!("// ── Layer {idx}: {} ──", layer.name);
+            let Some(start) = self.source[cursor..].find(&marker).map(|i| cursor + i) else {
+                break;
+            };
+            cursor = start + marker.len();
+            let end = self.source[cursor..]
+                .find("// ── Layer ")
+                .map_or(self.source.len(), |i| cursor + i);
+            self.origins.push(Origin { range: start..end, layer: layer.name.clone(), stage: None });
+            self.locate_stages(stages.iter(), &layer.name, cursor..end, indent, emit);
+        }
+        self
+    }
+
+    /// Post stages run inside the lens pass's entry point.
+    fn with_post_origins(mut self, cinematic: &Cinematic, emit: StageEmitter) -> Self {
+        let entry = match self.language {
+            Language::Wgsl => "fn fs_main",
+            Language::Glsl => "void main",
+        };
+        if let Some(start) = self.source.find(entry) {
+            let range = start..self.source.len();
+            self.origins.push(Origin { range: range.clone(), layer: "lens".into(), stage: None });
+            let stages = lens::post_stages(cinematic);
+            self.locate_stages(stages.into_iter(), "lens", range, "    ", emit);
+        }
+        self
+    }
+
+    fn locate_stages<'a>(
+        &mut self,
+        stages: impl Iterator<Item = &'a Stage>,
+        layer: &str,
+        range: Range<usize>,
+        indent: &str,
+        emit: StageEmitter,
+    ) {
+        let mut at = range.start;
+        for stage in stages {
+            let mut code = String::new();
+            emit(&mut code, stage, indent);
+            if code.is_empty() {
+                continue;
+            }
+            if let Some(i) = self.source[at..range.end].find(&code) {
+                let start = at + i;
+                at = start + code.len();
+                self.origins.push(Origin {
+                    range: start..at,
+                    layer: layer.to_string(),
+                    stage: Some((stage.name.clone(), stage.span)),
+                });
+                // Combinators emit their sub-pipelines two blocks deeper
+                let nested = format!("{indent}        ");
+                let subs = stage.sub_pipelines().flatten();
+                self.locate_stages(subs, layer, start..at, &nested, emit);
+            }
+        }
+    }
+
+    /// The innermost origin covering `offset`.
+    fn origin_at(&self, offset: usize) -> Option<&Origin> {
+        self.origins.iter().rev().find(|o| o.range.contains(&offset))
+    }
+
+    fn diagnostic(&self, cinematic: &str, error: &ShaderError) -> Diagnostic {
+        let mut diag = Diagnostic::error(format!(
+            "cinematic '{cinematic}': generated {} is invalid: {}",
+            self.label, error.message
+        ))
+        .with_code(ErrorCode::E011);
+
+        let origin = error.offset.and_then(|offset| self.origin_at(offset));
+        match origin {
+            Some(Origin { layer, stage: Some((stage, span)), .. }) => {
+                if !span.is_empty() {
+                    diag = diag.with_span(*span);
+                }
+                diag = diag.with_note(Diagnostic::note(format!(
+                    "emitted for stage '{stage}' in layer '{layer}'"
+                )));
+            }
+            Some(Origin { layer, .. }) => {
+                diag = diag.with_note(Diagnostic::note(format!("emitted for layer '{layer}'")));
+            }
+            None => {}
+        }
+
+        if let Some(offset) = error.offset {
+            let line_no = self.source[..offset].matches('\n').count() + 1;
+            let line_start = self.source[..offset].rfind('\n').map_or(0, |i| i + 1);
+            let line = self.source[line_start..].lines().next().unwrap_or("");
+            diag = diag.with_note(Diagnostic::note(format!(
+                "{}:{line_no}: {}",
+                self.label,
+                line.trim()
+            )));
+        }
+
+        diag.with_help(
+            "an argument may have the wrong type for its stage (for example a vec2 where a \
+             number is expected); otherwise this is a compiler bug",
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check(source: &str) -> Diagnostics {
+        check_program(&crate::compile_to_ast(source).unwrap(), OptLevel::O1)
+    }
+
+    #[test]
+    fn valid_pipelines_pass() {
+        let diags = check(
+            r#"cinematic "ok" {
+                layer a { fbm(3.0, octaves: 4) | glow(1.0) | tint(0.5, 0.8, 1.0) }
+                layer b { ring(0.4, 0.02) | glow(2.0) | bloom(0.3, 1.2) }
+                lens { post: [vignette(0.5)] }
+            }"#,
+        );
+        assert!(diags.is_empty(), "{diags:?}");
+    }
+
+    #[test]
+    fn failures_point_at_the_stage() {
+        let source = r#"cinematic "bad" {
+            layer main { circle(0.3) | glow(p * 2.0) }
+        }"#;
+        let diags = check(source);
+        assert!(diags.has_errors());
+        let d = diags.iter().next().unwrap();
+        assert_eq!(d.code, Some(ErrorCode::E011));
+        let span = d.span.expect("span of the glow stage");
+        assert!(source[span.start..span.end].starts_with("glow"));
+        assert!(d.notes.iter().any(|n| n.message.contains("stage 'glow' in layer 'main'")));
+    }
+
+    #[test]
+    fn minified_glsl_passes() {
+        let program = crate::compile_to_ast(
+            r#"cinematic "ok" {
+                layer a { circle(0.3) | glow(2.0) | tint(gold) }
+                layer b memory: 0.9 { ring(0.4, 0.02) | glow(2.0) }
+            }"#,
+        )
+        .unwrap();
+        let diags = check_program(&program, OptLevel::O2);
+        assert!(diags.is_empty(), "{diags:?}");
+    }
+
+    #[test]
+    fn glsl_errors_are_reported() {
+        let shader = GeneratedShader::new(
+            "fragment.frag",
+            Language::Glsl,
+            ShaderStage::Fragment,
+            "#version 300 es\nprecision highp float;\nout vec4 c;\nvoid main(){ c = 1; }\n".into(),
+        );
+        let err = validate(&shader).unwrap_err();
+        assert!(err.offset.is_some(), "{err:?}");
+    }
+}
@@ -1,24 +1,37 @@
-//! Headless GPU snapshot testing for GAME shaders.
+//! Snapshot testing for GAME shaders.
 //!
-//! Renders compiled shaders to a texture via wgpu, captures pixel data,
-//! and compares against reference images for visual regression testing.
+//! PNG I/O and pixel comparison for visual regression testing, fed either
+//! by the CPU reference renderer ([`crate::cpu`]) or by the headless GPU
+//! renderer below.
 //!
-//! Enable with: `cargo build --features snapshot`
+//! [`record`] and [`check`] drive `game snapshot`: every cinematic in a
+//! program is rendered on the CPU at each configured time and compared
+//! against `<dir>/<group>/<cinematic>@<time>s.png`.
+//!
+//! The GPU renderer renders compiled shaders to a texture via wgpu and
+//! captures pixel data. Enable with: `cargo build --features snapshot`
 //!
 //! Note: Requires a GPU-capable environment. CI should use `swiftshader`
 //! or `lavapipe` as a software Vulkan driver.
 
-use std::path::Path;
+use std::fs::File;
+use std::io::{BufReader, BufWriter};
+use std::path::{Path, PathBuf};
+
+use crate::ast::Program;
+use crate::cpu::CpuRenderer;
 
-use image::{ImageBuffer, Rgba};
+#[cfg(feature = "snapshot")]
 use wgpu::util::DeviceExt;
 
 /// Headless GPU renderer for visual snapshot testing.
+#[cfg(feature = "snapshot")]
 pub struct SnapshotRenderer {
     device: wgpu::Device,
     queue: wgpu::Queue,
 }
 
+#[cfg(feature = "snapshot")]
 impl SnapshotRenderer {
     /// Create a new renderer using the best available GPU.
     pub fn new() -> Result<Self, String> {
@@ -223,20 +236,51 @@ impl SnapshotRenderer {
     }
 }
 
+/// Composite a premultiplied frame over black by dropping alpha, which is
+/// what an opaque canvas shows.
+pub fn opaque(frame: &[u8]) -> Vec<u8> {
+    frame.chunks_exact(4).flat_map(|px| [px[0], px[1], px[2], 255]).collect()
+}
+
 /// Save RGBA pixel data as a PNG file.
 pub fn save_png(pixels: &[u8], width: u32, height: u32, path: &Path) -> Result<(), String> {
-    let img: ImageBuffer<Rgba<u8>, _> =
-        ImageBuffer::from_raw(width, height, pixels.to_vec())
-            .ok_or("invalid pixel dimensions")?;
-    img.save(path).map_err(|e| format!("PNG save error: {e}"))
+    if pixels.len() != width as usize * height as usize * 4 {
+        return Err("invalid pixel dimensions".into());
+    }
+    let file = File::create(path).map_err(|e| format!("PNG save error: {e}"))?;
+    let mut encoder = png::Encoder::new(BufWriter::new(file), width, height);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder
+        .write_header()
+        .and_then(|mut writer| {
+            writer.write_image_data(pixels)?;
+            writer.finish()
+        })
+        .map_err(|e| format!("PNG save error: {e}"))
 }
 
 /// Load a PNG file and return RGBA pixel data.
 pub fn load_png(path: &Path) -> Result<(Vec<u8>, u32, u32), String> {
-    let img = image::open(path).map_err(|e| format!("PNG load error: {e}"))?;
-    let rgba = img.to_rgba8();
-    let (w, h) = rgba.dimensions();
-    Ok((rgba.into_raw(), w, h))
+    let err = |e: &dyn std::fmt::Display| format!("PNG load error: {e}");
+    let file = File::open(path).map_err(|e| err(&e))?;
+    let mut decoder = png::Decoder::new(BufReader::new(file));
+    decoder.set_transformations(png::Transformations::normalize_to_color8());
+    let mut reader = decoder.read_info().map_err(|e| err(&e))?;
+    let mut buf = vec![0; reader.output_buffer_size().ok_or("PNG load error: image too large")?];
+    let info = reader.next_frame(&mut buf).map_err(|e| err(&e))?;
+    buf.truncate(info.buffer_size());
+
+    let rgba = match info.color_type {
+        png::ColorType::Rgba => buf,
+        png::ColorType::Rgb => buf.chunks_exact(3).flat_map(|c| [c[0], c[1], c[2], 255]).collect(),
+        png::ColorType::GrayscaleAlpha => {
+            buf.chunks_exact(2).flat_map(|c| [c[0], c[0], c[0], c[1]]).collect()
+        }
+        png::ColorType::Grayscale => buf.iter().flat_map(|&g| [g, g, g, 255]).collect(),
+        png::ColorType::Indexed => return Err("PNG load error: unexpanded palette".into()),
+    };
+    Ok((rgba, info.width, info.height))
 }
 
 /// Compare two RGBA pixel buffers. Returns similarity percentage (0-100).
@@ -282,6 +326,146 @@ pub fn generate_diff(actual: &[u8], reference: &[u8]) -> Vec<u8> {
     diff
 }
 
+/// Settings shared by `game snapshot record` and `game snapshot check`.
+#[derive(Debug, Clone)]
+pub struct SnapshotConfig {
+    /// Root of the reference images; each group gets a subdirectory.
+    pub dir: PathBuf,
+    pub width: u32,
+    pub height: u32,
+    /// `time` values to capture. Each is rendered as a single frame, so
+    /// `memory:` layers start from a blank previous frame.
+    pub times: Vec<f32>,
+    /// Largest share of differing pixels (percent) a check accepts.
+    pub max_diff_percent: f64,
+    /// Per-channel difference (0-255) below which pixels count as equal.
+    pub channel_threshold: i32,
+}
+
+impl Default for SnapshotConfig {
+    fn default() -> Self {
+        Self {
+            dir: PathBuf::from("snapshots"),
+            width: 128,
+            height: 128,
+            times: vec![1.0],
+            max_diff_percent: 1.0,
+            channel_threshold: 2,
+        }
+    }
+}
+
+/// What happened to one reference image.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SnapshotOutcome {
+    Recorded,
+    Passed { diff_percent: f64 },
+    /// Over the threshold; the diff and the actual render were written
+    /// next to the reference.
+    Failed { diff_percent: f64, diff: PathBuf },
+    /// No reference yet — run `record`.
+    Missing,
+    /// The reference has a different resolution than the config.
+    SizeMismatch { width: u32, height: u32 },
+}
+
+#[derive(Debug, Clone)]
+pub struct SnapshotResult {
+    pub cinematic: String,
+    pub time: f32,
+    pub reference: PathBuf,
+    pub outcome: SnapshotOutcome,
+}
+
+impl SnapshotResult {
+    pub fn is_ok(&self) -> bool {
+        matches!(self.outcome, SnapshotOutcome::Recorded | SnapshotOutcome::Passed { .. })
+    }
+}
+
+/// Render every cinematic in `program` at each configured time and store
+/// the frames as references under `<dir>/<group>/`.
+pub fn record(
+    program: &Program,
+    group: &str,
+    config: &SnapshotConfig,
+) -> Result<Vec<SnapshotResult>, String> {
+    let dir = config.dir.join(group);
+    std::fs::create_dir_all(&dir).map_err(|e| format!("create {}: {e}", dir.display()))?;
+    render_all(program, config, &dir, |reference, pixels| {
+        save_png(pixels, config.width, config.height, reference)?;
+        // A fresh reference makes any earlier failure artifacts stale.
+        std::fs::remove_file(reference.with_extension("diff.png")).ok();
+        std::fs::remove_file(reference.with_extension("actual.png")).ok();
+        Ok(SnapshotOutcome::Recorded)
+    })
+}
+
+/// Render like [`record`] and compare against the stored references. On
+/// failure `<name>.diff.png` and `<name>.actual.png` are written beside
+/// the reference.
+pub fn check(
+    program: &Program,
+    group: &str,
+    config: &SnapshotConfig,
+) -> Result<Vec<SnapshotResult>, String> {
+    let dir = config.dir.join(group);
+    render_all(program, config, &dir, |reference, pixels| {
+        if !reference.exists() {
+            return Ok(SnapshotOutcome::Missing);
+        }
+        let (expected, width, height) = load_png(reference)?;
+        if (width, height) != (config.width, config.height) {
+            return Ok(SnapshotOutcome::SizeMismatch { width, height });
+        }
+        let diff_percent = 100.0 - compare_pixels(pixels, &expected, config.channel_threshold);
+        if diff_percent <= config.max_diff_percent {
+            return Ok(SnapshotOutcome::Passed { diff_percent });
+        }
+        let diff = reference.with_extension("diff.png");
+        save_png(&generate_diff(pixels, &expected), width, height, &diff)?;
+        save_png(pixels, width, height, &reference.with_extension("actual.png"))?;
+        Ok(SnapshotOutcome::Failed { diff_percent, diff })
+    })
+}
+
+/// `<cinematic>@<time>s.png`, with path-hostile characters replaced.
+pub fn reference_name(cinematic: &str, time: f32) -> String {
+    let name: String = cinematic
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+        .collect();
+    format!("{name}@{time:.2}s.png")
+}
+
+fn render_all(
+    program: &Program,
+    config: &SnapshotConfig,
+    dir: &Path,
+    mut visit: impl FnMut(&Path, &[u8]) -> Result<SnapshotOutcome, String>,
+) -> Result<Vec<SnapshotResult>, String> {
+    let mut results = Vec::new();
+    for cinematic in &program.cinematics {
+        let mut renderer = CpuRenderer::new(cinematic, config.width, config.height)
+            .map_err(|e| format!("{}: {e}", cinematic.name))?;
+        for &time in &config.times {
+            renderer.reset();
+            let mut uniforms = renderer.default_uniforms();
+            uniforms.time = time;
+            let reference = dir.join(reference_name(&cinematic.name, time));
+            let outcome = visit(&reference, &opaque(&renderer.render(&uniforms)))?;
+            results.push(SnapshotResult {
+                cinematic: cinematic.name.clone(),
+                time,
+                reference,
+                outcome,
+            });
+        }
+    }
+    Ok(results)
+}
+
+#[cfg(feature = "snapshot")]
 fn floats_to_bytes(floats: &[f32]) -> Vec<u8> {
     let mut bytes = Vec::with_capacity(floats.len() * 4);
     for f in floats {
@@ -292,3 +476,58 @@ fn floats_to_bytes(floats: &[f32]) -> Vec<u8> {
     }
     bytes
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn png_round_trip() {
+        let pixels: Vec<u8> = (0..2 * 3 * 4).map(|i| (i * 10) as u8).collect();
+        let path = std::env::temp_dir().join(format!("game-snapshot-{}.png", std::process::id()));
+        save_png(&pixels, 2, 3, &path).unwrap();
+        let loaded = load_png(&path);
+        std::fs::remove_file(&path).ok();
+        assert_eq!(loaded.unwrap(), (pixels, 2, 3));
+    }
+
+    #[test]
+    fn save_png_rejects_wrong_size() {
+        assert!(save_png(&[0; 7], 1, 2, Path::new("unused.png")).is_err());
+    }
+
+    #[test]
+    fn record_then_check() {
+        let program = crate::compile_to_ast(
+            r#"cinematic "ring test" { layer main { ring(0.4, 0.05) | glow(2.0) } }"#,
+        )
+        .unwrap();
+        let dir = std::env::temp_dir().join(format!("game-snapshots-{}", std::process::id()));
+        let config = SnapshotConfig {
+            dir: dir.clone(),
+            width: 16,
+            height: 16,
+            times: vec![0.0, 2.5],
+            ..Default::default()
+        };
+
+        assert_eq!(check(&program, "g", &config).unwrap()[0].outcome, SnapshotOutcome::Missing);
+        let recorded = record(&program, "g", &config).unwrap();
+        assert_eq!(recorded.len(), 2);
+        assert!(recorded[1].reference.ends_with("g/ring_test@2.50s.png"));
+
+        let checked = check(&program, "g", &config).unwrap();
+        assert!(checked.iter().all(|r| r.outcome == SnapshotOutcome::Passed { diff_percent: 0.0 }));
+
+        // Corrupt one reference: a black frame against the ring.
+        save_png(&[0; 16 * 16 * 4], 16, 16, &recorded[0].reference).unwrap();
+        let checked = check(&program, "g", &config).unwrap();
+        let failed = matches!(
+            &checked[0].outcome,
+            SnapshotOutcome::Failed { diff_percent, diff } if *diff_percent > 1.0 && diff.exists()
+        );
+        std::fs::remove_dir_all(&dir).ok();
+        assert!(failed, "{:?}", checked[0].outcome);
+        assert!(checked[1].is_ok());
+    }
+}
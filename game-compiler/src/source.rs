@@ -0,0 +1,234 @@
+//! Source providers for the import resolver.
+//!
+//! The resolver never touches `std::fs` directly; it asks a [`SourceProvider`]
+//! to locate and read imported files. This lets the same resolution rules run
+//! against the real filesystem, an in-memory map (WASM playground, unit
+//! tests), or a layered overlay (an editor's unsaved buffers over saved files).
+//! The embedded stdlib is a provider too, mounted at [`StdlibProvider::ROOT`].
+
+use std::collections::HashMap;
+use std::path::{Component, Path, PathBuf};
+
+use crate::error::CompileError;
+use crate::stdlib;
+
+/// Locates and reads `.game` sources for the resolver.
+pub trait SourceProvider {
+    /// Locate `path` relative to `dir`. Returns a stable id for the file,
+    /// used for cycle detection and as the base for its own imports.
+    fn locate(&self, dir: &Path, path: &str) -> Option<PathBuf>;
+
+    /// Read the source of a file previously returned by `locate`.
+    fn read(&self, id: &Path) -> Result<String, CompileError>;
+}
+
+// ── Filesystem ──────────────────────────────────────────
+
+/// Reads from the real filesystem. Ids are canonicalized paths.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FsProvider;
+
+impl SourceProvider for FsProvider {
+    fn locate(&self, dir: &Path, path: &str) -> Option<PathBuf> {
+        let candidate = dir.join(path);
+        if candidate.is_file() {
+            candidate.canonicalize().ok()
+        } else {
+            None
+        }
+    }
+
+    fn read(&self, id: &Path) -> Result<String, CompileError> {
+        Ok(std::fs::read_to_string(id)?)
+    }
+}
+
+// ── In-memory ───────────────────────────────────────────
+
+/// A virtual filesystem backed by a map of path → source.
+///
+/// Paths are normalized lexically (`.` and `..` are folded), so
+/// `lib/../ui.game` and `ui.game` name the same file.
+#[derive(Debug, Clone, Default)]
+pub struct MemoryProvider {
+    files: HashMap<PathBuf, String>,
+}
+
+impl MemoryProvider {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add or replace a file.
+    pub fn insert(&mut self, path: impl AsRef<Path>, source: impl Into<String>) {
+        self.files.insert(normalize(path.as_ref()), source.into());
+    }
+
+    /// Builder-style [`insert`](Self::insert).
+    pub fn with_file(mut self, path: impl AsRef<Path>, source: impl Into<String>) -> Self {
+        self.insert(path, source);
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.files.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+}
+
+impl SourceProvider for MemoryProvider {
+    fn locate(&self, dir: &Path, path: &str) -> Option<PathBuf> {
+        let key = normalize(&dir.join(path));
+        self.files.contains_key(&key).then_some(key)
+    }
+
+    fn read(&self, id: &Path) -> Result<String, CompileError> {
+        self.files
+            .get(&normalize(id))
+            .cloned()
+            .ok_or_else(|| CompileError::validation(format!("no such file: '{}'", id.display())))
+    }
+}
+
+/// Fold `.` and `..` components without touching the filesystem.
+fn normalize(path: &Path) -> PathBuf {
+    let mut out = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if !out.pop() {
+                    out.push("..");
+                }
+            }
+            other => out.push(other.as_os_str()),
+        }
+    }
+    out
+}
+
+// ── Embedded stdlib ────────────────────────────────────
+
+/// Serves the compiled-in stdlib from the virtual directory [`Self::ROOT`].
+///
+/// It only answers lookups in that directory, so the resolver can search it
+/// last, after the importing file's directory and any lib dirs.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct StdlibProvider;
+
+impl StdlibProvider {
+    /// Directory the stdlib is mounted at. Ids look like `<stdlib>/ui.game`.
+    pub const ROOT: &'static str = "<stdlib>";
+}
+
+impl SourceProvider for StdlibProvider {
+    fn locate(&self, dir: &Path, path: &str) -> Option<PathBuf> {
+        if dir != Path::new(Self::ROOT) {
+            return None;
+        }
+        stdlib::lookup(path)?;
+        Some(dir.join(format!("{}.game", stdlib::module_name(path))))
+    }
+
+    fn read(&self, id: &Path) -> Result<String, CompileError> {
+        id.strip_prefix(Self::ROOT)
+            .ok()
+            .and_then(|p| p.to_str())
+            .and_then(stdlib::lookup)
+            .map(str::to_string)
+            .ok_or_else(|| CompileError::validation(format!("no such file: '{}'", id.display())))
+    }
+}
+
+// ── Overlay ─────────────────────────────────────────────
+
+/// Layers several providers; earlier layers shadow later ones.
+///
+/// `OverlayProvider::new().layer(unsaved_buffers).layer(FsProvider)` lets an
+/// editor compile unsaved buffers that import saved files (and vice versa).
+#[derive(Default)]
+pub struct OverlayProvider {
+    layers: Vec<Box<dyn SourceProvider>>,
+}
+
+impl OverlayProvider {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a layer below the existing ones.
+    pub fn layer(mut self, provider: impl SourceProvider + 'static) -> Self {
+        self.layers.push(Box::new(provider));
+        self
+    }
+}
+
+impl SourceProvider for OverlayProvider {
+    fn locate(&self, dir: &Path, path: &str) -> Option<PathBuf> {
+        self.layers.iter().find_map(|l| l.locate(dir, path))
+    }
+
+    fn read(&self, id: &Path) -> Result<String, CompileError> {
+        let mut last_err = None;
+        for layer in &self.layers {
+            match layer.read(id) {
+                Ok(src) => return Ok(src),
+                Err(e) => last_err = Some(e),
+            }
+        }
+        Err(last_err.unwrap_or_else(|| {
+            CompileError::validation(format!("no such file: '{}'", id.display()))
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn memory_locates_relative_paths() {
+        let mem = MemoryProvider::new().with_file("/proj/lib/fx.game", "cinematic \"fx\" {}");
+        let id = mem
+            .locate(Path::new("/proj/src"), "../lib/fx.game")
+            .unwrap();
+        assert_eq!(id, PathBuf::from("/proj/lib/fx.game"));
+        assert!(mem.read(&id).unwrap().contains("fx"));
+        assert!(mem.locate(Path::new("/proj"), "fx.game").is_none());
+    }
+
+    #[test]
+    fn memory_read_missing_is_error() {
+        let mem = MemoryProvider::new();
+        assert!(mem.read(Path::new("nope.game")).is_err());
+    }
+
+    #[test]
+    fn stdlib_is_only_visible_at_its_root() {
+        let root = Path::new(StdlibProvider::ROOT);
+        let id = StdlibProvider.locate(root, "stdlib/ui.game").unwrap();
+        assert_eq!(id, PathBuf::from("<stdlib>/ui.game"));
+        assert_eq!(StdlibProvider.locate(root, "ui"), Some(id.clone()));
+        assert!(StdlibProvider.read(&id).unwrap().contains("define"));
+        assert!(StdlibProvider.locate(Path::new("/proj"), "ui").is_none());
+        assert!(StdlibProvider.locate(root, "nope").is_none());
+    }
+
+    #[test]
+    fn overlay_prefers_earlier_layers() {
+        let top = MemoryProvider::new().with_file("a.game", "top");
+        let bottom = MemoryProvider::new()
+            .with_file("a.game", "bottom")
+            .with_file("b.game", "only bottom");
+        let overlay = OverlayProvider::new().layer(top).layer(bottom);
+
+        let a = overlay.locate(Path::new(""), "a.game").unwrap();
+        assert_eq!(overlay.read(&a).unwrap(), "top");
+        let b = overlay.locate(Path::new(""), "b.game").unwrap();
+        assert_eq!(overlay.read(&b).unwrap(), "only bottom");
+        assert!(overlay.locate(Path::new(""), "c.game").is_none());
+    }
+}
@@ -0,0 +1,64 @@
+//! Embedded copy of the GAME standard library (`game-compiler/stdlib/*.game`).
+//!
+//! Compiled into the binary so `import "ui"` and `import "stdlib/noise.game"`
+//! resolve without a filesystem — in the WASM playground, the dev server's
+//! `/compile` endpoint, and `lib::compile`.
+
+/// `(module name, source)` for every stdlib file.
+const MODULES: &[(&str, &str)] = &[
+    ("audio", include_str!("../stdlib/audio.game")),
+    ("backgrounds", include_str!("../stdlib/backgrounds.game")),
+    ("color", include_str!("../stdlib/color.game")),
+    ("effects", include_str!("../stdlib/effects.game")),
+    ("motion", include_str!("../stdlib/motion.game")),
+    ("noise", include_str!("../stdlib/noise.game")),
+    ("patterns", include_str!("../stdlib/patterns.game")),
+    ("post", include_str!("../stdlib/post.game")),
+    ("primitives", include_str!("../stdlib/primitives.game")),
+    ("transitions", include_str!("../stdlib/transitions.game")),
+    ("ui", include_str!("../stdlib/ui.game")),
+];
+
+/// Normalize an import path to a stdlib module name.
+///
+/// Accepts `ui`, `ui.game`, `stdlib/ui` and `stdlib/ui.game`.
+pub fn module_name(path: &str) -> &str {
+    let path = path.strip_prefix("stdlib/").unwrap_or(path);
+    path.strip_suffix(".game").unwrap_or(path)
+}
+
+/// Look up the source of a stdlib module by import path.
+pub fn lookup(path: &str) -> Option<&'static str> {
+    let name = module_name(path);
+    MODULES.iter().find(|(n, _)| *n == name).map(|(_, src)| *src)
+}
+
+/// Names of all embedded stdlib modules.
+pub fn module_names() -> impl Iterator<Item = &'static str> {
+    MODULES.iter().map(|(n, _)| *n)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lookup_accepts_import_spellings() {
+        for path in ["ui", "ui.game", "stdlib/ui", "stdlib/ui.game"] {
+            assert!(lookup(path).is_some(), "{path}");
+        }
+        assert!(lookup("nope").is_none());
+        assert!(lookup("stdlib/").is_none());
+    }
+
+    #[test]
+    fn embedded_modules_parse() {
+        for name in module_names() {
+            let src = lookup(name).unwrap();
+            let tokens = crate::lexer::lex(src).unwrap_or_else(|e| panic!("{name}: {e}"));
+            crate::parser::Parser::new(tokens)
+                .parse()
+                .unwrap_or_else(|e| panic!("{name}: {e}"));
+        }
+    }
+}
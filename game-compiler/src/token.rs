@@ -58,6 +58,14 @@ pub enum Token {
     Diamond,   // <>
     BangBang,  // !!
     DotDot,    // ..
+    GreaterEq, // >=
+    LessEq,    // <=
+    EqEq,      // ==
+    NotEq,     // !=
+    AndAnd,    // &&
+    OrOr,      // ||
+    Bang,      // !
+    Percent,   // %
     Greater,   // >
     Less,      // <
     Question,  // ?
@@ -67,6 +75,7 @@ pub enum Token {
     Integer(i64),
     StringLit(String),
     Ident(String),
+    HexColor(String), // #ff8800, '#' included
 
     // --- units (number already embedded) ---
     Seconds(f64),
@@ -135,10 +144,19 @@ impl std::fmt::Display for Token {
             Token::Greater => write!(f, ">"),
             Token::Less => write!(f, "<"),
             Token::Question => write!(f, "?"),
+            Token::GreaterEq => write!(f, ">="),
+            Token::LessEq => write!(f, "<="),
+            Token::EqEq => write!(f, "=="),
+            Token::NotEq => write!(f, "!="),
+            Token::AndAnd => write!(f, "&&"),
+            Token::OrOr => write!(f, "||"),
+            Token::Bang => write!(f, "!"),
+            Token::Percent => write!(f, "%"),
             Token::Float(v) => write!(f, "{v}"),
             Token::Integer(v) => write!(f, "{v}"),
             Token::StringLit(s) => write!(f, "\"{s}\""),
             Token::Ident(s) => write!(f, "{s}"),
+            Token::HexColor(s) => write!(f, "{s}"),
             Token::Seconds(v) => write!(f, "{v}s"),
             Token::Millis(v) => write!(f, "{v}ms"),
             Token::Bars(v) => write!(f, "{v}bars"),
@@ -3,9 +3,13 @@
 //! Exposes the compiler's core functions to JavaScript via wasm-bindgen.
 //! Build with: `wasm-pack build --target web --features wasm`
 
+use std::collections::HashMap;
+use std::path::Path;
+
 use wasm_bindgen::prelude::*;
 
-use crate::{CompileConfig, OutputFormat, ShaderTarget};
+use crate::source::MemoryProvider;
+use crate::{CompileConfig, OptLevel, OutputFormat, ShaderTarget};
 
 /// Compile `.game` source to WGSL shader code.
 ///
@@ -15,6 +19,7 @@ pub fn compile_to_wgsl(source: &str) -> Result<String, JsError> {
     let config = CompileConfig {
         output_format: OutputFormat::Component,
         target: ShaderTarget::WebGpu,
+        opt_level: OptLevel::O1,
     };
     let outputs = crate::compile(source, &config).map_err(|e| JsError::new(&e.to_string()))?;
     let first = outputs
@@ -34,6 +39,7 @@ pub fn compile_to_html(source: &str) -> Result<String, JsError> {
     let config = CompileConfig {
         output_format: OutputFormat::Html,
         target: ShaderTarget::Both,
+        opt_level: OptLevel::O1,
     };
     let outputs = crate::compile(source, &config).map_err(|e| JsError::new(&e.to_string()))?;
     let first = outputs
@@ -53,6 +59,7 @@ pub fn compile_to_component(source: &str) -> Result<String, JsError> {
     let config = CompileConfig {
         output_format: OutputFormat::Component,
         target: ShaderTarget::Both,
+        opt_level: OptLevel::O1,
     };
     let outputs = crate::compile(source, &config).map_err(|e| JsError::new(&e.to_string()))?;
     let first = outputs
@@ -61,33 +68,59 @@ pub fn compile_to_component(source: &str) -> Result<String, JsError> {
     Ok(first.js.clone())
 }
 
+/// Compile `.game` source to a Web Component ES module, resolving imports
+/// against a virtual filesystem.
+///
+/// `files` is a plain object mapping paths to `.game` source text, e.g.
+/// `{ "lib/brand.game": "..." }`. Paths are relative to the compiled source;
+/// imports not found there fall back to the embedded stdlib.
+#[wasm_bindgen]
+pub fn compile_to_component_with_files(source: &str, files: JsValue) -> Result<String, JsError> {
+    let files: HashMap<String, String> =
+        serde_wasm_bindgen::from_value(files).map_err(|e| JsError::new(&e.to_string()))?;
+    let mut provider = MemoryProvider::new();
+    for (path, text) in files {
+        provider.insert(path, text);
+    }
+    let config = CompileConfig {
+        output_format: OutputFormat::Component,
+        target: ShaderTarget::Both,
+        opt_level: OptLevel::O1,
+    };
+    let outputs = crate::compile_with_provider(source, &config, &provider, Path::new(""), &[])
+        .map_err(|e| JsError::new(&e.to_string()))?;
+    let first = outputs
+        .first()
+        .ok_or_else(|| JsError::new("no cinematic found in source"))?;
+    Ok(first.js.clone())
+}
+
 /// Validate `.game` source without full compilation.
 ///
 /// Returns a JSON string with validation result:
-/// `{ "valid": true, "cinematics": 2, "layers": 5 }` or
-/// `{ "valid": false, "error": "..." }`
+/// `{ "valid": true, "cinematics": 2, "layers": 5, "warnings": [...], "diagnostics": [...] }`
+/// or `{ "valid": false, "error": "...", "warnings": [...], "diagnostics": [...] }`.
+/// `error` and `warnings` hold plain messages; `diagnostics` holds every
+/// error and warning with code, span, help and fix (see `Diagnostics::to_json`).
 #[wasm_bindgen]
 pub fn validate(source: &str) -> String {
-    match crate::compile_to_ast(source) {
-        Ok(program) => {
-            let total_layers: usize = program
-                .cinematics
-                .iter()
-                .map(|c| c.layers.len())
-                .sum();
-            format!(
-                r#"{{"valid":true,"cinematics":{},"layers":{}}}"#,
-                program.cinematics.len(),
-                total_layers
-            )
-        }
-        Err(e) => {
-            let escaped = e
-                .to_string()
-                .replace('\\', "\\\\")
-                .replace('"', "\\\"")
-                .replace('\n', "\\n");
-            format!(r#"{{"valid":false,"error":"{}"}}"#, escaped)
-        }
+    let diags = crate::diagnose(source);
+    let warnings: Vec<&str> = diags
+        .iter()
+        .filter(|d| !d.is_error())
+        .map(|d| d.message.as_str())
+        .collect();
+    let mut result = serde_json::json!({
+        "valid": !diags.has_errors(),
+        "warnings": warnings,
+        "diagnostics": diags.to_json(source),
+    });
+    if let Some(first) = diags.iter().find(|d| d.is_error()) {
+        result["error"] = first.message.clone().into();
+    } else if let Ok(program) = crate::compile_to_ast(source) {
+        let total_layers: usize = program.cinematics.iter().map(|c| c.layers.len()).sum();
+        result["cinematics"] = program.cinematics.len().into();
+        result["layers"] = total_layers.into();
     }
+    result.to_string()
 }
@@ -1,12 +1,13 @@
 use std::fs;
 use std::path::Path;
 
-use game_compiler::{CompileConfig, OutputFormat, ShaderTarget};
+use game_compiler::{CompileConfig, OptLevel, OutputFormat, ShaderTarget};
 
 fn default_config() -> CompileConfig {
     CompileConfig {
         output_format: OutputFormat::Component,
         target: ShaderTarget::Both,
+        opt_level: OptLevel::O1,
     }
 }
 
@@ -29,6 +30,7 @@ fn compile_all_in_dir(dir: &Path) -> (usize, usize, Vec<String>) {
     let config = CompileConfig {
         output_format: OutputFormat::Html,
         target: ShaderTarget::Both,
+        opt_level: OptLevel::O1,
     };
 
     let mut passed = 0;
@@ -113,14 +115,7 @@ fn runyourempire_examples_compile() {
 
 #[test]
 fn all_stdlib_compile() {
-    let dir = Path::new(env!("CARGO_MANIFEST_DIR"))
-        .parent()
-        .expect("game-compiler should have a parent dir")
-        .join("stdlib");
-    if !dir.exists() {
-        eprintln!("  skipping: {} not found", dir.display());
-        return;
-    }
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("stdlib");
     let (passed, failed, errors) = compile_all_in_dir(&dir);
 
     if failed > 0 {
@@ -354,7 +349,7 @@ fn check_detects_unknown_function_in_define() {
     let program = game_compiler::parse(src).unwrap();
     let warnings = game_compiler::check(&program);
     assert!(
-        warnings.iter().any(|w| w.contains("unknown function")),
+        warnings.iter().any(|w| w.message.contains("unknown function")),
         "Should warn about unknown function: {warnings:?}"
     );
 }
@@ -368,7 +363,7 @@ fn check_detects_unused_define_parameter() {
     let program = game_compiler::parse(src).unwrap();
     let warnings = game_compiler::check(&program);
     assert!(
-        warnings.iter().any(|w| w.contains("unused")),
+        warnings.iter().any(|w| w.message.contains("unused")),
         "Should warn about unused parameter: {warnings:?}"
     );
 }
@@ -398,9 +393,573 @@ fn html_output_format_produces_html() {
     let config = CompileConfig {
         output_format: OutputFormat::Html,
         target: ShaderTarget::Both,
+        opt_level: OptLevel::O1,
     };
     let results = game_compiler::compile(src, &config).unwrap();
     assert!(results[0].html.is_some(), "HTML output should be present");
     let html = results[0].html.as_ref().unwrap();
     assert!(html.contains("<"), "HTML should contain markup");
 }
+
+// ── Modulation runtime ──────────────────────────────────
+
+fn compile_example_js(rel: &str) -> String {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join(rel);
+    let source = fs::read_to_string(&path)
+        .unwrap_or_else(|e| panic!("cannot read {}: {e}", path.display()));
+    let results = game_compiler::compile(&source, &default_config())
+        .unwrap_or_else(|e| panic!("{}: {e}", path.display()));
+    results[0].js.clone()
+}
+
+#[test]
+fn modulation_mouse_follow_binds_mouse() {
+    let js = compile_example_js("../examples/008-mouse-follow.game");
+    assert!(js.contains("function modulationUpdate(time, renderer)"));
+    assert!(js.contains("const mouseX = renderer.mouse.x || 0;"));
+    assert!(js.contains("let _val_mx = 0.0 + mouseX;"));
+    assert!(js.contains("let _val_my = 0.0 + mouseY;"));
+    assert!(js.contains("params.mx = _val_mx;"));
+    assert!(js.contains("params.my = _val_my;"));
+}
+
+#[test]
+fn pointer_fields_become_uniforms_fed_by_the_tracker() {
+    let src = r#"cinematic "press" {
+        layer {
+            fn: circle(0.2 + mouse.down * 0.1) | glow(1.0 + mouse.click + mouse.vx)
+        }
+    }"#;
+    let out = &game_compiler::compile(src, &default_config()).unwrap()[0];
+    let wgsl = out.wgsl.as_deref().unwrap();
+    let glsl = out.glsl.as_deref().unwrap();
+    for field in ["down", "click", "vx"] {
+        assert!(wgsl.contains(&format!("let mouse_{field} = u.p_mouse_{field};")), "{field}");
+        assert!(glsl.contains(&format!("uniform float u_p_mouse_{field};")), "{field}");
+        assert!(out.js.contains(&format!("{{name:'mouse_{field}',default:0}}")), "{field}");
+    }
+    assert!(!wgsl.contains("p_mouse_x"), "x and y stay in the header");
+    assert!(out.js.contains("class GamePointer"));
+    assert_eq!(out.js.matches("this.pointer.frame(this.userParams);").count(), 2);
+    // Otherwise touch drags scroll the page and the pointer is cancelled
+    assert!(out.js.contains("display:block;touch-action:none}"));
+}
+
+#[test]
+fn modulation_tutorial_binds_time_and_audio() {
+    let js = compile_example_js("../examples/tutorial-07-modulation.game");
+    assert!(js.contains("let _val_radius = 0.2 + (Math.sin((time * 2.0)) * 0.08);"));
+    assert!(js.contains("let _val_intensity = 2.0 + (Math.sin((time * 3.0)) * 0.8);"));
+    assert!(js.contains("const audioBass = renderer.audioData.bass || 0;"));
+    assert!(js.contains("let _val_glow_str = 1.5 + (audioBass * 3.0);"));
+}
+
+#[test]
+fn modulation_data_binding_reads_data_params() {
+    let js = compile_example_js("../examples/tutorial-11-data-binding.game");
+    assert!(js.contains("const data_progress = params.data_progress ?? 0;"));
+    assert!(js.contains("let _val_angle = 0.0 + (data_progress * 6.28318530717959);"));
+    assert!(js.contains("params.brightness = _val_brightness;"));
+}
+
+#[test]
+fn modulation_composes_with_temporal_ops() {
+    let js = compile_example_js("../examples/005-temporal-ops.game");
+    let func = js.find("function modulationUpdate").expect("evaluator emitted");
+    // Processors are made per renderer, since each element has its own clock
+    assert!(js.find("    _smooth_bass_0: new GameEMAFilter(0.05),").unwrap() < func);
+    assert!(js.find("    _trigger_beat: new GameEdgeDetector(0.3),").unwrap() < func);
+    assert!(!js.contains("const _smooth_bass_0"));
+
+    let body = &js[func..];
+    let eval = body.find("let _val_bass = 0.5 + audioBass;").unwrap();
+    let smooth = body.find("_val_bass = temporal._smooth_bass_0.update(_val_bass, time);").unwrap();
+    let delay = body.find("temporal._delay_bass_1.push(_val_bass, time);").unwrap();
+    let clamp = body.find("_val_bass = Math.min(Math.max(_val_bass, 0), 1);").unwrap();
+    let write = body.find("params.bass = _val_bass;").unwrap();
+    assert!(eval < smooth && smooth < delay && delay < clamp && clamp < write);
+}
+
+#[test]
+fn modulation_renderers_call_evaluator_each_frame() {
+    let js = compile_example_js("../examples/008-mouse-follow.game");
+    let calls = js.matches("if (typeof modulationUpdate === 'function') modulationUpdate(t, this);").count();
+    assert_eq!(calls, 2, "both WebGPU and WebGL2 renderers should run modulation");
+}
+
+#[test]
+fn modulation_every_bound_param_is_written() {
+    for dir in ["examples", "../examples", "../presets"] {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join(dir);
+        for entry in fs::read_dir(&dir).unwrap().filter_map(|e| e.ok()) {
+            let path = entry.path();
+            if path.extension().is_none_or(|ext| ext != "game") {
+                continue;
+            }
+            let source = fs::read_to_string(&path).unwrap();
+            let Ok(program) = game_compiler::parse(&source) else { continue };
+            let Ok(results) = game_compiler::compile(&source, &default_config()) else { continue };
+            for (cinematic, output) in program.cinematics.iter().zip(&results) {
+                for layer in &cinematic.layers {
+                    let body = match &layer.body {
+                        game_compiler::ast::LayerBody::Params(params) => params.as_slice(),
+                        game_compiler::ast::LayerBody::Pipeline(_) => &[],
+                    };
+                    for param in layer.opts.iter().chain(body) {
+                        if param.modulation.is_none() {
+                            continue;
+                        }
+                        let write = format!("params.{0} = _val_{0};", param.name);
+                        assert!(
+                            output.js.contains(&write),
+                            "{}: modulated param '{}' is never written",
+                            path.display(),
+                            param.name
+                        );
+                    }
+                }
+            }
+        }
+    }
+}
+
+// ── Shader signals ──────────────────────────────────────
+
+#[test]
+fn shader_signals_resolve_to_uniform_slots() {
+    let src = r#"cinematic "Signals" {
+        layer { fn: ring(0.3, 0.02) | mask_arc(data.progress * tau) | glow(1.0 + audio.bass) }
+    }"#;
+    let results = game_compiler::compile(src, &default_config()).unwrap();
+    let wgsl = results[0].wgsl.as_ref().unwrap();
+    let glsl = results[0].glsl.as_ref().unwrap();
+
+    assert!(wgsl.contains("p_data_progress: f32,"));
+    assert!(wgsl.contains("let data_progress = u.p_data_progress;"));
+    assert!(wgsl.contains("(data_progress * 6.28318530717959)"));
+    assert!(wgsl.contains("(1.0 + u.audio_bass)"));
+
+    assert!(glsl.contains("uniform float u_p_data_progress;"));
+    assert!(glsl.contains("float data_progress = u_p_data_progress;"));
+    assert!(glsl.contains("(1.0 + u_audio_bass)"));
+
+    assert!(results[0].js.contains("const DATA_FIELDS = ['progress'];"));
+}
+
+#[test]
+fn data_binding_example_exposes_progress_property() {
+    let js = compile_example_js("../examples/tutorial-11-data-binding.game");
+    assert!(js.contains("const DATA_FIELDS = ['progress'];"));
+    assert!(js.contains("{name:'data_progress',default:0}"));
+}
+
+#[test]
+fn unknown_shader_signal_is_rejected() {
+    let src = r#"cinematic "Bad" { layer { fn: circle(0.2 + audio.kick) | glow(1.0) } }"#;
+    let err = game_compiler::compile(src, &default_config()).unwrap_err();
+    assert!(err.to_string().contains("unknown signal 'audio.kick'"), "{err}");
+}
+
+// ── Lens post pass ──────────────────────────────────────
+
+#[test]
+fn lens_post_compiles_to_second_pass() {
+    let src = r#"cinematic "Lensed" {
+        layer { fn: circle(0.3) | glow(2.0) }
+        lens { exposure: 1.2 post: bloom(0.3, 2.0) | vignette(0.5) }
+    }"#;
+    let results = game_compiler::compile(src, &default_config()).unwrap();
+    let js = &results[0].js;
+
+    assert!(js.contains("{name:'lens_exposure',default:1.2}"));
+    assert!(js.contains("{name:'lens_zoom',default:1}"));
+    assert!(js.contains("{name:'lens_offset_x',default:0}"));
+
+    let wgsl_post = js.split("const WGSL_POST = `").nth(1).expect("WGSL post shader");
+    assert!(wgsl_post.contains("textureSample(scene_tex, scene_sampler"));
+    assert!(wgsl_post.contains("pp_lum"));
+    assert!(wgsl_post.contains("vig_d"));
+
+    let glsl_post = js.split("const GLSL_POST = `").nth(1).expect("GLSL post shader");
+    assert!(glsl_post.contains("texture(u_scene, lens_uv)"));
+
+    assert!(js.contains("new GameRenderer(this._canvas, WGSL_V, WGSL_F, UNIFORMS, WGSL_POST, MEMORY_LAYERS)"));
+    assert!(js.contains("_ensureScene"));
+}
+
+#[test]
+fn no_lens_means_no_post_pass() {
+    let src = r#"cinematic "Plain" { layer { fn: circle(0.3) | glow(2.0) } }"#;
+    let results = game_compiler::compile(src, &default_config()).unwrap();
+    assert!(results[0].js.contains("const WGSL_POST = null;"));
+    assert!(results[0].js.contains("const GLSL_POST = null;"));
+}
+
+// ── Memory feedback ─────────────────────────────────────
+
+#[test]
+fn memory_layers_get_their_own_targets() {
+    let src = r#"cinematic "Trails" {
+        layer a memory: 0.9 { fn: circle(0.1) | glow(2.0)
+            feedback_zoom: 1.02 }
+        layer b memory: 0.8 { ring(0.3, 0.02) | glow(1.0) }
+        lens { exposure: 1.1 }
+    }"#;
+    let results = game_compiler::compile(src, &default_config()).unwrap();
+    let js = &results[0].js;
+
+    assert!(js.contains("const MEMORY_LAYERS = 2;"));
+    assert!(js.contains("{name:'a_feedback_zoom',default:1.02}"));
+    assert!(js.contains("_initMemory(w, h)"));
+    assert!(js.contains("_initMemoryGL("));
+    let wgsl = js.split("const WGSL_F = `").nth(1).expect("WGSL fragment shader");
+    assert!(wgsl.contains("@group(1) @binding(2) var mem_1: texture_2d<f32>;"));
+    let glsl = js.split("const GLSL_F = `").nth(1).expect("GLSL fragment shader");
+    assert!(glsl.contains("layout(location = 2) out vec4 mem_out_1;"));
+}
+
+#[test]
+fn too_many_memory_layers_is_an_error() {
+    let layers: String = (0..4)
+        .map(|i| format!("layer l{i} memory: 0.9 {{ circle(0.1) | glow(1.0) }}\n"))
+        .collect();
+    let src = format!(r#"cinematic "Crowded" {{ {layers} }}"#);
+    let err = game_compiler::compile(&src, &default_config()).unwrap_err();
+    assert!(err.to_string().contains("at most 3 are supported"), "{err}");
+}
+
+#[test]
+fn combinators_compile_nested_pipelines() {
+    let src = r#"cinematic "Shapes" {
+        layer main {
+            smooth_union(fbm(3.0), translate(0.2, 0.0) | circle(0.1 + audio.bass * 0.1), k: 0.05)
+                | glow(2.0)
+        }
+    }"#;
+    let results = game_compiler::compile(src, &default_config()).unwrap();
+    let js = &results[0].js;
+    let wgsl = js.split("const WGSL_F = `").nth(1).expect("WGSL fragment shader");
+    // Helpers are emitted for stages that only appear inside a combinator
+    assert!(wgsl.contains("fn fbm2("));
+    assert!(wgsl.contains("fn sdf_circle("));
+    assert!(wgsl.contains("{ // smooth_union"));
+    let glsl = js.split("const GLSL_F = `").nth(1).expect("GLSL fragment shader");
+    assert!(glsl.contains("float fbm2("));
+    assert!(glsl.contains("u_audio_bass"), "nested signal args resolve");
+}
+
+#[test]
+fn combinator_errors_point_at_the_stage() {
+    let src = r#"cinematic "Bad" {
+        layer { union(circle(0.2), circle(0.1) | glow(1.0)) | glow(2.0) }
+    }"#;
+    let err = game_compiler::compile(src, &default_config()).unwrap_err();
+    assert!(err.to_string().contains("argument 'b' must end in an Sdf"), "{err}");
+
+    let src = r#"cinematic "Bad" { layer { circle(box(0.1, 0.1) | round(0.02)) | glow(2.0) } }"#;
+    let err = game_compiler::compile(src, &default_config()).unwrap_err();
+    assert!(err.to_string().contains("does not take a pipeline argument"), "{err}");
+}
+
+#[test]
+fn gradient_colors_reach_both_backends() {
+    let src = r##"cinematic "Sky" {
+        define sky(top) { gradient(midnight, top, "y") }
+        layer main { sky(["#ff8800", [0.2, 0.4, audio.bass]]) }
+    }"##;
+    let results = game_compiler::compile(src, &default_config()).unwrap();
+    let js = &results[0].js;
+    let wgsl = js.split("const WGSL_F = `").nth(1).expect("WGSL fragment shader");
+    assert!(wgsl.contains("var gr_c = vec3f(0.039, 0.039, 0.118);"));
+    assert!(wgsl.contains("mix(gr_c, vec3f(1.0, 0.533, 0.0), clamp(gr_t * 2.0 - 0.0, 0.0, 1.0))"));
+    let glsl = js.split("const GLSL_F = `").nth(1).expect("GLSL fragment shader");
+    assert!(glsl.contains("vec3(0.2, 0.4, u_audio_bass)"), "nested signal args resolve");
+
+    let src = r#"cinematic "Bad" { layer { gradient(black, white, "conical") } }"#;
+    let err = game_compiler::compile(src, &default_config()).unwrap_err();
+    assert!(err.to_string().contains("unknown mode 'conical'"), "{err}");
+}
+
+#[test]
+fn color_literals_and_css_names_compile() {
+    let src = r#"cinematic "Colors" {
+        layer a { circle(0.2) | glow(2.0) | tint(crimson) }
+        layer b { ring(0.3, 0.02) | glow(1.0) | tint(#ff880080) }
+        layer c { fn: circle(0.1) | glow(1.0) | tint(hsl(hue, 0.8, 0.5))
+                  hue: 200.0 }
+    }"#;
+    let results = game_compiler::compile(src, &default_config()).unwrap();
+    let js = &results[0].js;
+    let wgsl = js.split("const WGSL_F = `").nth(1).expect("WGSL fragment shader");
+    assert!(wgsl.contains("vec3f(0.863, 0.078, 0.235)"), "crimson is inlined");
+    assert!(wgsl.contains("vec3f(0.502, 0.268, 0.0)"), "hex alpha is premultiplied");
+    assert!(wgsl.contains("vec3f(0.0, 8.0, 4.0) + hue / 30.0"), "hsl of a param stays live");
+    let glsl = js.split("const GLSL_F = `").nth(1).expect("GLSL fragment shader");
+    assert!(glsl.contains("vec3(0.863, 0.078, 0.235)"));
+
+    let src = r#"cinematic "Bad" { layer { circle(0.2) | glow(1.0) | tint(crimsn) } }"#;
+    let err = game_compiler::compile(src, &default_config()).unwrap_err();
+    assert!(err.to_string().contains("unknown color 'crimsn'"), "{err}");
+}
+
+#[test]
+fn lens_post_rejects_sdf_stage() {
+    let src = r#"cinematic "Bad" {
+        layer { fn: circle(0.3) | glow(2.0) }
+        lens { post: circle(0.2) }
+    }"#;
+    let err = game_compiler::compile(src, &default_config()).unwrap_err();
+    assert!(err.to_string().contains("post stages must be Color -> Color"), "{err}");
+}
+
+// ── Targets and optimization levels ─────────────────────
+
+fn compile_with(src: &str, target: ShaderTarget, opt_level: OptLevel) -> game_compiler::CompileOutput {
+    let config = CompileConfig { output_format: OutputFormat::Component, target, opt_level };
+    game_compiler::compile(src, &config).unwrap().remove(0)
+}
+
+const SIZE_SRC: &str = r#"cinematic "Sized" {
+    layer config { speed: 1.0 unused_knob: 0.5 }
+    layer { fn: rotate(time * speed) | star(5, 0.2 + 0.1, 0.08) | glow(1.0 * 2.0) | tint(gold) }
+    lens { post: bloom(0.3, 2.0) }
+}"#;
+
+#[test]
+fn single_target_strips_other_renderer() {
+    let both = compile_with(SIZE_SRC, ShaderTarget::Both, OptLevel::O1);
+    let webgpu = compile_with(SIZE_SRC, ShaderTarget::WebGpu, OptLevel::O1);
+    let webgl2 = compile_with(SIZE_SRC, ShaderTarget::WebGl2, OptLevel::O1);
+
+    assert!(webgpu.js.len() < both.js.len());
+    assert!(webgl2.js.len() < both.js.len());
+
+    assert!(!webgpu.js.contains("GameRendererGL"));
+    assert!(!webgpu.js.contains("GLSL_F"));
+    assert!(webgpu.wgsl.is_some() && webgpu.glsl.is_none());
+
+    assert!(!webgl2.js.contains("class GameRenderer {"));
+    assert!(!webgl2.js.contains("WGSL_F"));
+    assert!(webgl2.glsl.is_some() && webgl2.wgsl.is_none());
+}
+
+#[test]
+fn opt_levels_shrink_output() {
+    let o0 = compile_with(SIZE_SRC, ShaderTarget::Both, OptLevel::O0);
+    let o1 = compile_with(SIZE_SRC, ShaderTarget::Both, OptLevel::O1);
+    let o2 = compile_with(SIZE_SRC, ShaderTarget::Both, OptLevel::O2);
+
+    // O0 leaves constant expressions unfolded
+    let (o0_wgsl, o1_wgsl) = (o0.wgsl.unwrap(), o1.wgsl.unwrap());
+    assert!(o0_wgsl.contains("(0.2 + 0.1)"));
+    assert!(!o1_wgsl.contains("(0.2 + 0.1)"));
+    assert!(o1_wgsl.len() < o0_wgsl.len());
+
+    // O2 drops the unreferenced uniform and minifies every shader
+    let o2_wgsl = o2.wgsl.unwrap();
+    assert!(o1.js.contains("{name:'unused_knob',default:0.5}"));
+    assert!(!o2.js.contains("unused_knob"));
+    assert!(o2.js.contains("{name:'speed',default:1}"));
+    assert!(!o2_wgsl.contains("\n    "));
+    assert!(o2_wgsl.len() < o1_wgsl.len());
+    assert!(o2.glsl.unwrap().len() < o1.glsl.unwrap().len());
+    assert!(o2.js.len() < o1.js.len());
+}
+
+// ── Imports ─────────────────────────────────────────────
+
+#[test]
+fn compile_resolves_embedded_stdlib_imports() {
+    let src = r#"import "ui" expose progress_ring
+    cinematic "Progress" {
+        layer { fn: progress_ring(0.3, 0.04, data.progress * tau) }
+    }"#;
+    let results = game_compiler::compile(src, &default_config()).unwrap();
+    let wgsl = results[0].wgsl.as_ref().unwrap();
+    assert!(wgsl.contains("abs(length(p) - 0.300000) - 0.040000"), "progress_ring should expand to a ring");
+    assert!(wgsl.contains("arc_theta < (data_progress * 6.28318530717959)"));
+}
+
+#[test]
+fn compile_with_resolver_searches_lib_dirs() {
+    let root = std::env::temp_dir().join("game_batch_lib_dirs");
+    let _ = fs::remove_dir_all(&root);
+    let (base, lib) = (root.join("project"), root.join("shared"));
+    fs::create_dir_all(&base).unwrap();
+    fs::create_dir_all(&lib).unwrap();
+    fs::write(
+        lib.join("brand.game"),
+        r#"cinematic "brand" { define brand_dot(r) { circle(r) | glow(2.0) | tint(cyan) } }"#,
+    )
+    .unwrap();
+
+    let src = r#"import "brand" expose brand_dot
+    cinematic "Logo" { layer { fn: brand_dot(0.2) } }"#;
+
+    // Not visible without the lib dir
+    let err = game_compiler::compile_with_resolver(src, &default_config(), &base, &[]).unwrap_err();
+    assert!(err.to_string().contains("import not found: 'brand'"), "{err}");
+
+    let results =
+        game_compiler::compile_with_resolver(src, &default_config(), &base, std::slice::from_ref(&lib)).unwrap();
+    assert_eq!(results[0].name, "Logo");
+    assert!(results[0].wgsl.as_ref().unwrap().contains("sdf_circle(p, 0.200000)"));
+
+    let _ = fs::remove_dir_all(&root);
+}
+
+#[test]
+fn compile_with_provider_uses_virtual_files() {
+    use game_compiler::source::MemoryProvider;
+
+    let files = MemoryProvider::new().with_file(
+        "lib/brand.game",
+        r#"cinematic "brand" { define brand_dot(r) { circle(r) | glow(2.0) | tint(cyan) } }"#,
+    );
+    let src = r#"import "lib/brand" expose brand_dot
+    cinematic "Logo" { layer { fn: brand_dot(0.2) } }"#;
+
+    let results =
+        game_compiler::compile_with_provider(src, &default_config(), &files, Path::new(""), &[])
+            .unwrap();
+    assert!(results[0]
+        .wgsl
+        .as_ref()
+        .unwrap()
+        .contains("sdf_circle(p, 0.200000)"));
+}
+
+// ── Source spans ────────────────────────────────────────
+
+#[test]
+fn define_errors_point_at_body_and_call_site() {
+    let src = r#"cinematic "t" {
+  define halo(r) { circle(r) | tint(gold) }
+  layer { fn: halo(0.3) }
+}"#;
+    let err = game_compiler::compile(src, &default_config()).unwrap_err();
+    let span = err.span().expect("error should carry a span");
+    assert_eq!(&src[span.start..span.end], "tint(gold)");
+
+    let note = &err.notes()[0];
+    assert_eq!(note.message, "expanded from define 'halo'");
+    let call = note.span.unwrap();
+    assert_eq!(&src[call.start..call.end], "halo(0.3)");
+
+    let rendered = game_compiler::error::render_with_source(&err, src);
+    assert!(rendered.contains("note: expanded from define 'halo'"), "{rendered}");
+    assert!(rendered.contains(" --> line 3:14"), "{rendered}");
+}
+
+#[test]
+fn check_warnings_point_at_source() {
+    let src = r#"cinematic "t" {
+  define wasteful(r, unused) { circle(r) }
+  layer { fn: circle(0.3, 0.1, 0.2, 0.4) }
+}"#;
+    let program = game_compiler::parse(src).unwrap();
+    let warnings = game_compiler::check(&program);
+    assert!(!warnings.is_empty());
+    for w in &warnings {
+        let span = w.span.unwrap_or_else(|| panic!("warning without span: {w:?}"));
+        let text = &src[span.start..span.end];
+        assert!(text.starts_with("define wasteful") || text.starts_with("circle("), "{text}");
+    }
+}
+
+#[test]
+fn diagnose_reports_machine_applicable_fixes() {
+    let src = r#"cinematic "t" {
+  layer { fn: cicle(0.3) | glow(2.0) }
+}"#;
+    let diags = game_compiler::diagnose(src);
+    assert!(diags.has_errors());
+    let err = diags.iter().find(|d| d.is_error()).unwrap();
+    let fix = err.fix.as_ref().expect("did-you-mean should carry a fix");
+    assert_eq!(&src[fix.span.start..fix.span.end], "cicle");
+    assert_eq!(fix.replacement, "circle");
+
+    let json = diags.to_json(src);
+    assert_eq!(json[0]["code"], "E001");
+    assert_eq!(json[0]["span"]["line_start"], 2);
+    assert_eq!(json[0]["fix"]["replacement"], "circle");
+}
+
+#[test]
+fn diagnose_clean_source_is_empty() {
+    let src = r#"cinematic "t" { layer { fn: circle(0.3) | glow(2.0) } }"#;
+    assert!(game_compiler::diagnose(src).is_empty());
+}
+
+#[test]
+fn diagnose_fixes_misspelled_signal_field() {
+    let src = r#"cinematic "t" {
+  layer { fn: circle(0.3) | glow(audio.bas) }
+}"#;
+    let diags = game_compiler::diagnose(src);
+    let fix = diags.iter().find_map(|d| d.fix.as_ref()).expect("signal fix");
+    assert_eq!(&src[fix.span.start..fix.span.end], "bas");
+    assert_eq!(fix.replacement, "bass");
+}
+
+#[test]
+fn nested_expression_errors_point_at_the_operand() {
+    let src = r#"cinematic "t" {
+  layer { fn: circle(0.2 + audio.bas * 0.1) | glow(2.0) }
+}"#;
+    let err = game_compiler::compile(src, &default_config()).unwrap_err();
+    let span = err.span().expect("error should carry a span");
+    assert_eq!(&src[span.start..span.end], "audio.bas");
+
+    let diags = game_compiler::diagnose(src);
+    let fix = diags.iter().find_map(|d| d.fix.as_ref()).expect("signal fix");
+    assert_eq!(&src[fix.span.start..fix.span.end], "bas");
+}
+
+#[test]
+fn arcs_and_score_follow_the_playback_clock() {
+    let js = compile_example_js("../examples/tutorial-10-arc-timeline.game");
+    assert!(js.contains("{param:'radius',name:'radius'"), "arc targets resolve to params");
+    assert!(js.contains("params[a.param] = a.from"));
+    assert_eq!(js.matches("const t = this.clock.tick();").count(), 2);
+    assert_eq!(js.matches("if (typeof arcUpdate === 'function') arcUpdate(t, this.userParams);").count(), 2);
+
+    let js = compile_example_js("../examples/008-score-timeline.game");
+    assert!(js.contains("class GameScorePlayer"));
+    assert!(js.contains("apply(time, params)"));
+    assert!(js.contains("this.score?.start(0);"));
+}
+
+#[test]
+fn static_cinematics_render_on_change() {
+    // Data-bound gauges only move when their data does
+    for path in ["../examples/tutorial-11-data-binding.game", "../presets/loading-ring.game"] {
+        let js = compile_example_js(path);
+        assert!(js.contains("const ANIMATED = false;"), "{path}");
+    }
+    for path in [
+        "../examples/tutorial-07-modulation.game",
+        "../examples/tutorial-10-arc-timeline.game",
+        "../examples/003-memory-trails.game",
+    ] {
+        let js = compile_example_js(path);
+        assert!(js.contains("const ANIMATED = true;"), "{path}");
+    }
+    let js = compile_example_js("../examples/tutorial-10-arc-timeline.game");
+    assert!(js.contains("const ARC_END = "), "reduced motion freezes once arcs settle");
+}
+
+#[test]
+fn worker_format_compiles_examples() {
+    let config = CompileConfig { output_format: OutputFormat::Worker, ..default_config() };
+    for rel in ["../examples/001-hello.game", "../examples/008-mouse-follow.game"] {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join(rel);
+        let source = fs::read_to_string(&path).unwrap();
+        let results = game_compiler::compile(&source, &config)
+            .unwrap_or_else(|e| panic!("{rel}: {e}"));
+        let js = &results[0].js;
+        assert!(js.contains("gameWorkerMain(gameCreateRenderer);"), "{rel}");
+        assert!(js.contains("customElements.define("), "{rel}");
+        assert!(results[0].html.is_none(), "{rel}");
+    }
+}
@@ -0,0 +1,142 @@
+//! Every shader generated from the repo's `examples/` and `presets/`, and
+//! from this crate's `examples/` and `stdlib/`, must pass naga validation
+//! (`game check --strict`) at `-O1` and at `-O2`.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use game_compiler::OptLevel;
+
+/// Files whose generated shaders are known to be invalid. A file that
+/// starts passing must be removed from this list.
+const KNOWN_INVALID: &[&str] = &[
+    // `cast sdf` in a multi-layer cinematic reads an undeclared color_result
+    "004-cast-types.game",
+    // Two SDF generators in one layer both declare sdf_result
+    "void-heartbeat.game",
+];
+
+fn game_files(dir: &Path) -> Vec<PathBuf> {
+    let mut files: Vec<_> = fs::read_dir(dir)
+        .unwrap_or_else(|e| panic!("cannot read {}: {e}", dir.display()))
+        .filter_map(|e| e.ok().map(|e| e.path()))
+        .filter(|p| p.extension().is_some_and(|ext| ext == "game"))
+        .collect();
+    files.sort();
+    files
+}
+
+fn repo_dir(name: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .parent()
+        .expect("game-compiler should have a parent dir")
+        .join(name)
+}
+
+fn validate_dir(dir: &Path, opt_level: OptLevel) {
+    if !dir.exists() {
+        eprintln!("  skipping: {} not found", dir.display());
+        return;
+    }
+
+    let mut checked = 0;
+    let mut failures = Vec::new();
+    for path in game_files(dir) {
+        let file_name = path.file_name().unwrap().to_string_lossy().into_owned();
+        let source = fs::read_to_string(&path).unwrap();
+        // Files that don't compile are batch_compile's concern.
+        let Ok(mut program) = game_compiler::compile_to_ast(&source) else {
+            continue;
+        };
+        if game_compiler::resolver::resolve_imports(&mut program, dir, &[]).is_err() {
+            continue;
+        }
+        checked += 1;
+
+        let diags = game_compiler::shader_check::check_program(&program, opt_level);
+        let known = KNOWN_INVALID.contains(&file_name.as_str());
+        match (diags.has_errors(), known) {
+            (true, false) => {
+                failures.push(format!("{}:\n{}", path.display(), diags.render(&source)))
+            }
+            (false, true) => failures.push(format!(
+                "{} now validates; remove it from KNOWN_INVALID",
+                path.display()
+            )),
+            _ => {}
+        }
+    }
+
+    eprintln!(
+        "  {} ({opt_level:?}): {checked} files validated, {} failed",
+        dir.display(),
+        failures.len()
+    );
+    assert!(failures.is_empty(), "shader validation failures:\n  {}", failures.join("\n  "));
+    assert!(checked > 0, "no files validated in {}", dir.display());
+}
+
+#[test]
+fn example_shaders_validate() {
+    validate_dir(&repo_dir("examples"), OptLevel::O1);
+    validate_dir(&repo_dir("examples"), OptLevel::O2);
+}
+
+#[test]
+fn crate_example_shaders_validate() {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("examples");
+    validate_dir(&dir, OptLevel::O1);
+    validate_dir(&dir, OptLevel::O2);
+}
+
+#[test]
+fn preset_shaders_validate() {
+    validate_dir(&repo_dir("presets"), OptLevel::O1);
+    validate_dir(&repo_dir("presets"), OptLevel::O2);
+}
+
+#[test]
+fn stdlib_shaders_validate() {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("stdlib");
+    validate_dir(&dir, OptLevel::O1);
+    validate_dir(&dir, OptLevel::O2);
+}
+
+#[test]
+fn comparison_and_logic_operators_validate() {
+    let source = r#"cinematic "Ops" {
+        layer main {
+            fn: circle(time % 2.0 >= 1.0 ? 0.3 : 0.2)
+              | glow(mouse.x > 0.5 && mouse.y <= 0.5 || !data.on ? 2.0 : 1.0)
+              | tint(data.level != 0.0 ? 1.0 : 0.5, 0.5 % 0.3, 1.0)
+        }
+    }"#;
+    let program = game_compiler::compile_to_ast(source).unwrap();
+    let diags = game_compiler::shader_check::check_program(&program, OptLevel::O1);
+    assert!(!diags.has_errors(), "{}", diags.render(source));
+}
+
+#[test]
+fn string_stage_args_validate() {
+    // `mirror("xy")` takes the builtin's default axis
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("examples/kaleidoscope.game");
+    let source = fs::read_to_string(&path).unwrap();
+    let program = game_compiler::compile_to_ast(&source).unwrap();
+    let diags = game_compiler::shader_check::check_program(&program, OptLevel::O1);
+    assert!(!diags.has_errors(), "{}", diags.render(&source));
+}
+
+#[test]
+fn booleans_used_as_numbers_validate() {
+    let source = r#"cinematic "Bools" {
+        layer main {
+            fn: circle(0.2 + (time >= 1.0) * 0.1) | glow(time ? 1.0 : 0.5)
+              | tint(-(mouse.x > 0.5), max(time < 2.0, 0.5), (data.on == 1.0) + 0.5)
+        }
+    }"#;
+    let program = game_compiler::compile_to_ast(source).unwrap();
+    for opt_level in [OptLevel::O1, OptLevel::O2] {
+        let diags = game_compiler::shader_check::check_program(&program, opt_level);
+        assert!(!diags.has_errors(), "{}", diags.render(source));
+    }
+}
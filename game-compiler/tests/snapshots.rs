@@ -0,0 +1,96 @@
+//! Visual regression over the repo's `examples/` and `presets/`.
+//!
+//! Every cinematic is rendered on the CPU and compared against the
+//! references in `tests/snapshots/<dir>/`. After an intended visual change,
+//! re-record with:
+//!
+//!     GAME_UPDATE_SNAPSHOTS=1 cargo test --test snapshots
+//!
+//! which is the same as `game snapshot record ../<dir> --width 64 --height 64
+//! -d tests/snapshots/<dir>`.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use game_compiler::snapshot::{self, SnapshotConfig};
+
+fn game_files(dir: &Path) -> Vec<PathBuf> {
+    let mut files: Vec<_> = fs::read_dir(dir)
+        .unwrap_or_else(|e| panic!("cannot read {}: {e}", dir.display()))
+        .filter_map(|e| e.ok().map(|e| e.path()))
+        .filter(|p| p.extension().is_some_and(|ext| ext == "game"))
+        .collect();
+    files.sort();
+    files
+}
+
+fn snapshot_dir(name: &str) {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let dir = root.parent().expect("game-compiler should have a parent dir").join(name);
+    if !dir.exists() {
+        eprintln!("  skipping: {} not found", dir.display());
+        return;
+    }
+    let config = SnapshotConfig {
+        dir: root.join("tests/snapshots").join(name),
+        width: 64,
+        height: 64,
+        ..Default::default()
+    };
+    let update = std::env::var_os("GAME_UPDATE_SNAPSHOTS").is_some();
+
+    let mut checked = 0;
+    let mut failures = Vec::new();
+    for path in game_files(&dir) {
+        let group = path.file_stem().unwrap().to_string_lossy().into_owned();
+        let source = fs::read_to_string(&path).unwrap();
+        let program = game_compiler::compile_to_ast(&source).and_then(|mut program| {
+            game_compiler::resolver::resolve_imports(&mut program, &dir, &[])?;
+            Ok(program)
+        });
+        let results = match program {
+            Ok(program) if update => snapshot::record(&program, &group, &config),
+            Ok(program) => snapshot::check(&program, &group, &config),
+            // Compile coverage is batch_compile's job; only flag files that
+            // used to have references.
+            Err(e) if config.dir.join(&group).exists() => {
+                failures.push(format!("{}: {e}", path.display()));
+                continue;
+            }
+            Err(_) => continue,
+        };
+        match results {
+            Ok(results) => {
+                checked += results.len();
+                for r in results.iter().filter(|r| !r.is_ok()) {
+                    failures.push(format!(
+                        "{} {}@{:.2}s: {:?}",
+                        path.display(),
+                        r.cinematic,
+                        r.time,
+                        r.outcome
+                    ));
+                }
+            }
+            Err(e) => failures.push(format!("{}: {e}", path.display())),
+        }
+    }
+
+    eprintln!("  {name}: {checked} snapshots, {} failed", failures.len());
+    assert!(
+        failures.is_empty(),
+        "snapshot failures (re-record with GAME_UPDATE_SNAPSHOTS=1 if intended):\n  {}",
+        failures.join("\n  ")
+    );
+    assert!(checked > 0, "no snapshots rendered from {}", dir.display());
+}
+
+#[test]
+fn examples_match_snapshots() {
+    snapshot_dir("examples");
+}
+
+#[test]
+fn presets_match_snapshots() {
+    snapshot_dir("presets");
+}
@@ -623,50 +623,30 @@ async function updateDiagnostics(document) {
     const diagnostics = [];
 
     try {
-        const result = wasm.validate(source);
+        const result = JSON.parse(wasm.validate(source));
 
-        if (!result.valid && result.error) {
-            const parsed = parseError(result.error);
+        for (const diag of result.diagnostics || []) {
             let range;
 
-            if (parsed.startByte !== null && parsed.endByte !== null) {
-                const startPos = byteOffsetToPosition(source, parsed.startByte);
-                const endPos = byteOffsetToPosition(source, parsed.endByte);
+            if (diag.span) {
+                const startPos = byteOffsetToPosition(source, diag.span.start);
+                const endPos = byteOffsetToPosition(source, diag.span.end);
                 range = new vscode.Range(startPos, endPos);
             } else {
                 range = new vscode.Range(0, 0, 0, document.lineAt(0).text.length);
             }
 
-            diagnostics.push(new vscode.Diagnostic(
+            const message = diag.help ? `${diag.message}\n${diag.help}` : diag.message;
+            const d = new vscode.Diagnostic(
                 range,
-                parsed.message,
-                vscode.DiagnosticSeverity.Error
-            ));
-        }
-
-        if (result.valid && result.warnings) {
-            const warnings = Array.isArray(result.warnings)
-                ? result.warnings
-                : Array.from(result.warnings || []);
-
-            for (const warning of warnings) {
-                const parsed = parseError(warning);
-                let range;
-
-                if (parsed.startByte !== null && parsed.endByte !== null) {
-                    const startPos = byteOffsetToPosition(source, parsed.startByte);
-                    const endPos = byteOffsetToPosition(source, parsed.endByte);
-                    range = new vscode.Range(startPos, endPos);
-                } else {
-                    range = new vscode.Range(0, 0, 0, document.lineAt(0).text.length);
-                }
-
-                diagnostics.push(new vscode.Diagnostic(
-                    range,
-                    parsed.message,
-                    vscode.DiagnosticSeverity.Warning
-                ));
-            }
+                message,
+                diag.severity === 'error'
+                    ? vscode.DiagnosticSeverity.Error
+                    : vscode.DiagnosticSeverity.Warning
+            );
+            if (diag.code) d.code = diag.code;
+            d.source = 'game';
+            diagnostics.push(d);
         }
     } catch (err) {
         const parsed = parseError(err.message || String(err));